@@ -0,0 +1,113 @@
+// -------------------------------------------------------------------------------------------------------------
+// These benchmarks time each stage of the compiler on large generated programs (see generator/mod.rs), so that a
+// change that makes the compiler slower on big programs (like a lookup that goes quadratic) shows up.
+// Run them with "cargo bench", or "cargo bench -- scanner" to only run some of them
+// -------------------------------------------------------------------------------------------------------------
+
+// The benchmarks are written in the same explicit style as the compiler
+#![allow(clippy::needless_return)]
+
+mod generator;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use soup::code_gen::code_gen_target::TARGETS;
+use soup::compile_str;
+use soup::parser::parser_driver::parse_program;
+use soup::scanner::scanner_driver::scan_str;
+use soup::semantic::semantic_data::Warnings;
+use soup::semantic::semantic_driver::semantic_checker;
+
+use crate::generator::generate_program;
+
+// The sizes of the programs each stage is timed on, as (number of functions, statements per function)
+const PROGRAM_SIZES: &[(usize, usize)] = &[(10, 20), (100, 20), (1000, 20)];
+
+// Generate a program of each size, named after how many functions it has
+fn get_programs() -> Vec<(usize, String)> {
+    return PROGRAM_SIZES
+        .iter()
+        .map(|&(num_funcs, num_statements)| {
+            (num_funcs, generate_program(num_funcs, num_statements))
+        })
+        .collect();
+}
+
+fn bench_scanner(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scanner");
+
+    for (num_funcs, program) in get_programs() {
+        group.throughput(Throughput::Bytes(program.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_funcs),
+            &program,
+            |b, program| {
+                b.iter(|| scan_str(program).unwrap());
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parser");
+
+    for (num_funcs, program) in get_programs() {
+        let tokens = scan_str(&program).unwrap();
+        group.throughput(Throughput::Elements(tokens.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_funcs),
+            &tokens,
+            |b, tokens| {
+                b.iter(|| parse_program(tokens).unwrap());
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_semantic_checker(c: &mut Criterion) {
+    let mut group = c.benchmark_group("semantic_checker");
+
+    for (num_funcs, program) in get_programs() {
+        let tokens = scan_str(&program).unwrap();
+
+        // The checker changes the tree it checks, so every run gets a freshly parsed one
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_funcs),
+            &tokens,
+            |b, tokens| {
+                b.iter_batched(
+                    || parse_program(tokens).unwrap(),
+                    |mut ast| semantic_checker(&mut ast, &mut Warnings::default()).unwrap(),
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipeline");
+    group.sample_size(10);
+
+    for (num_funcs, program) in get_programs() {
+        for opt_level in [0, 2] {
+            let id = BenchmarkId::new(format!("O{}", opt_level), num_funcs);
+            group.bench_with_input(id, &program, |b, program| {
+                b.iter(|| compile_str(program, &TARGETS[0], opt_level).unwrap());
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_scanner,
+    bench_parser,
+    bench_semantic_checker,
+    bench_pipeline
+);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file generates large synthetic soup programs for the benchmarks to compile. Every program is valid soup
+// (so it makes it all the way through the compiler), and the same sizes always give back the same program.
+// They're only meant to be compiled: running one could divide by zero, or take a very long time
+// -------------------------------------------------------------------------------------------------------------
+
+// The number of ints in the global array every function reads and writes
+const TABLE_SIZE: usize = 16;
+
+// A small, fast pseudo-random number generator (xorshift), so that every generated program is the same
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        return (self.0 % bound.max(1) as u64) as usize;
+    }
+}
+
+// Generate a program with the given number of functions, each with (roughly) the given number of statements.
+// Every function calls a few of the functions before it, and main calls the last one (so there has to be at least one)
+pub fn generate_program(num_funcs: usize, statements_per_func: usize) -> String {
+    let mut rng = Rng(0x5eed_50c0);
+    let mut program = format!("int calls;\nint table[{}];\n", TABLE_SIZE);
+
+    for func_num in 0..num_funcs {
+        program.push_str(&generate_func(&mut rng, func_num, statements_per_func));
+    }
+
+    program.push_str(&format!(
+        "\nfunc main() returns void {{\n    printf(\"{{}} {{}}\\n\", func_{}(1, 2), calls);\n}}\n",
+        num_funcs - 1
+    ));
+    return program;
+}
+
+// Generate a function that takes two ints and returns an int, made up of declarations, assignments, loops,
+// branches and calls to the functions before it
+fn generate_func(rng: &mut Rng, func_num: usize, num_statements: usize) -> String {
+    let mut body = String::from("    calls += 1;\n    int v0 = a + b;\n");
+    let mut num_vars = 1;
+
+    for _ in 0..num_statements {
+        let var = format!("v{}", rng.next(num_vars));
+        let expr = generate_expr(rng, num_vars, func_num);
+
+        match rng.next(6) {
+            0 => {
+                body.push_str(&format!("    int v{} = {};\n", num_vars, expr));
+                num_vars += 1;
+            }
+            1 => body.push_str(&format!(
+                "    if {} < {} {{\n        {} = {};\n    }} else {{\n        {} -= 1;\n    }}\n",
+                var,
+                rng.next(100),
+                var,
+                expr,
+                var
+            )),
+            2 => {
+                // The loop's counter is a new variable, which is left declared after the loop
+                let counter = format!("v{}", num_vars);
+                body.push_str(&format!(
+                    "    int {} = 0;\n    while {} < {} {{\n        {} = {} + {} * 2;\n        {} += 1;\n    }}\n",
+                    counter,
+                    counter,
+                    1 + rng.next(8),
+                    var,
+                    var,
+                    counter,
+                    counter
+                ));
+                num_vars += 1;
+            }
+            3 => body.push_str(&format!(
+                "    table[{}] = table[{}] + {};\n",
+                rng.next(TABLE_SIZE),
+                rng.next(TABLE_SIZE),
+                var
+            )),
+            _ => body.push_str(&format!("    {} = {};\n", var, expr)),
+        }
+    }
+
+    return format!(
+        "\nfunc func_{}(int a, int b) returns int {{\n{}    return v{};\n}}\n",
+        func_num,
+        body,
+        rng.next(num_vars)
+    );
+}
+
+// Generate an expression using the variables declared so far, which might call one of the functions before this one
+fn generate_expr(rng: &mut Rng, num_vars: usize, func_num: usize) -> String {
+    let lhs = format!("v{}", rng.next(num_vars));
+    let rhs = match rng.next(4) {
+        0 => format!("{}", rng.next(1000)),
+        1 => String::from("a"),
+        2 if func_num > 0 => format!("func_{}(b, {})", rng.next(func_num), rng.next(10)),
+        _ => format!("v{}", rng.next(num_vars)),
+    };
+    let op = ["+", "-", "*", "%"][rng.next(4)];
+    return format!("{} {} ({} + 1)", lhs, op, rhs);
+}
@@ -0,0 +1,19 @@
+use std::process::Command;
+
+// Record the git commit the compiler is being built from, so that "soup --version" can report exactly
+// which compiler produced an assembly file
+fn main() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+
+    println!("cargo:rustc-env=SOUP_GIT_HASH={}", git_hash);
+
+    // Rebuild whenever the current commit changes
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
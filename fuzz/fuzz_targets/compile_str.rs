@@ -0,0 +1,17 @@
+// Compile whatever source code the fuzzer comes up with, for every target, at the lowest and highest optimization levels.
+// compile_str gives back anything wrong with the source as an error, so any panic (or anything else that ends the process)
+// is a bug in the compiler. Run with "cargo +nightly fuzz run compile_str"
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use soup::code_gen::code_gen_target::TARGETS;
+use soup::compile_str;
+
+fuzz_target!(|source: &str| {
+    for target in &TARGETS {
+        for opt_level in [0, 2] {
+            let _ = compile_str(source, target, opt_level);
+        }
+    }
+});
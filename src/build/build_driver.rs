@@ -0,0 +1,204 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::cli_data::get_default_warnings;
+use crate::cli::cli_runner::{archive, assemble};
+use crate::code_gen::code_gen_driver::code_gen;
+use crate::code_gen::code_gen_target::{get_target_info, ObjectFormat, TargetInfo, TARGETS};
+use crate::ir::ir_driver::{lower_program, set_source_files};
+use crate::optimizer::optimizer_driver::optimize;
+use crate::parser::parser_driver::{get_function_files, merge_programs, parse_program};
+use crate::scanner::scanner_driver::scan_str;
+use crate::semantic::semantic_data::Warnings;
+use crate::semantic::semantic_driver::semantic_checker;
+use crate::throw_error;
+
+// -----------------------------------------------------------------
+// BUILD SCRIPTS
+// -----------------------------------------------------------------
+
+// Compiles soup files into a static library from a Cargo build script (build.rs), so a Rust program can link against
+// them and call their functions (see "Calling soup from C" in the README), like:
+//
+// SoupBuild::new().file("src/math.soup").compile("math");
+//
+// The files are compiled together into a single program, which still needs a main function, just like any other
+pub struct SoupBuild {
+    files: Vec<PathBuf>,
+    opt_level: Option<u32>,
+    target: Option<String>,
+    out_dir: Option<PathBuf>,
+    overflow_checks: bool,
+}
+
+impl Default for SoupBuild {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl SoupBuild {
+    pub fn new() -> SoupBuild {
+        return SoupBuild {
+            files: vec![],
+            opt_level: None,
+            target: None,
+            out_dir: None,
+            overflow_checks: false,
+        };
+    }
+
+    // Add a soup file to the library
+    pub fn file(&mut self, file: impl AsRef<Path>) -> &mut SoupBuild {
+        self.files.push(file.as_ref().to_path_buf());
+        return self;
+    }
+
+    // Optimize the library at the given level, rather than the one Cargo is building at
+    pub fn opt_level(&mut self, opt_level: u32) -> &mut SoupBuild {
+        self.opt_level = Some(opt_level);
+        return self;
+    }
+
+    // Compile the library for the given target (as a soup or Rust target triple), rather than the one Cargo is building for
+    pub fn target(&mut self, target: &str) -> &mut SoupBuild {
+        self.target = Some(String::from(target));
+        return self;
+    }
+
+    // Put the library in the given directory, rather than the build script's output directory
+    pub fn out_dir(&mut self, out_dir: impl AsRef<Path>) -> &mut SoupBuild {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        return self;
+    }
+
+    // Make arithmetic that overflows a runtime error (like --overflow-checks), rather than wrapping around
+    pub fn overflow_checks(&mut self, overflow_checks: bool) -> &mut SoupBuild {
+        self.overflow_checks = overflow_checks;
+        return self;
+    }
+
+    // Compile the files into a static library with the given name (lib<name>.a), and tell Cargo to link against it,
+    // reporting anything that goes wrong as the compiler would, which fails the build
+    pub fn compile(&self, name: &str) {
+        if let Err(msg) = self.try_compile(name) {
+            throw_error(&msg);
+        }
+    }
+
+    // Compile the files into a static library with the given name, and tell Cargo to link against it,
+    // giving back the path of the library, or the error that stopped it from being built
+    pub fn try_compile(&self, name: &str) -> Result<PathBuf, String> {
+        if self.files.is_empty() {
+            return Err(format!("No soup files were given to build '{}' from", name));
+        }
+
+        let target = self.get_target()?;
+        let opt_level = self.opt_level.unwrap_or_else(get_cargo_opt_level);
+        let out_dir = match &self.out_dir {
+            Some(out_dir) => out_dir.clone(),
+            None => match env::var_os("OUT_DIR") {
+                Some(out_dir) => PathBuf::from(out_dir),
+                None => {
+                    return Err(String::from(
+                        "OUT_DIR isn't set (is this being run from a build script?)",
+                    ))
+                }
+            },
+        };
+
+        // Each file is scanned and parsed on its own, and the build is rerun whenever any of them change
+        let mut programs = vec![];
+        for file in &self.files {
+            let file = file.to_string_lossy().to_string();
+            println!("cargo:rerun-if-changed={}", file);
+
+            let source = match fs::read_to_string(&file) {
+                Ok(source) => source,
+                Err(_) => return Err(format!("Could not read file '{}'", file)),
+            };
+            let tokens = scan_str(&source).map_err(|error| format!("{} in '{}'", error, file))?;
+            let ast = parse_program(&tokens).map_err(|error| format!("{} in '{}'", error, file))?;
+            programs.push((file, ast));
+        }
+
+        // Then the program is compiled just as the compiler would compile it, except that any error is given back
+        let function_files = get_function_files(&programs);
+        let mut ast = merge_programs(programs)?;
+        let mut warnings = Warnings::new(&get_default_warnings(), false);
+        let result = semantic_checker(&mut ast, &mut warnings);
+        for warning in warnings.found {
+            println!("cargo:warning={}", warning);
+        }
+        result?;
+
+        let mut program = lower_program(&ast, self.overflow_checks, false);
+        set_source_files(&mut program, &function_files);
+        optimize(&mut program, opt_level);
+        let asm = code_gen(&program, opt_level, target, false, false);
+
+        let asm_file = out_dir.join(format!("{}.s", name));
+        let obj_file = out_dir.join(format!("{}.o", name));
+        let lib_file = out_dir.join(format!("lib{}.a", name));
+        if fs::write(&asm_file, asm).is_err() {
+            return Err(format!("Could not write to file '{}'", asm_file.display()));
+        }
+        assemble(&asm_file, &obj_file, target)?;
+        archive(&lib_file, &[&obj_file])?;
+
+        println!("cargo:rustc-link-search=native={}", out_dir.display());
+        println!("cargo:rustc-link-lib=static={}", name);
+
+        return Ok(lib_file);
+    }
+
+    // Get the target to compile for: the one asked for, or else the one Cargo is building for
+    // (or the machine we're running on, if we're not being run by Cargo)
+    fn get_target(&self) -> Result<&'static TargetInfo, String> {
+        let triple = match &self.target {
+            Some(triple) => triple.clone(),
+            None => env::var("TARGET").unwrap_or_else(|_| get_host_triple()),
+        };
+
+        return match get_rust_target_info(&triple) {
+            Some(target) if target.object_format != ObjectFormat::Wasm => Ok(target),
+            _ => Err(format!(
+                "Can't build a soup library for target '{}'",
+                triple
+            )),
+        };
+    }
+}
+
+// Get the soup target matching a target triple, which can also be Rust's name for it (as Cargo gives it to build scripts)
+pub fn get_rust_target_info(triple: &str) -> Option<&'static TargetInfo> {
+    let triple = match triple {
+        "aarch64-apple-darwin" => "arm64-apple-darwin",
+        "riscv64gc-unknown-linux-gnu" => "riscv64-unknown-linux-gnu",
+        _ => triple,
+    };
+    return get_target_info(triple);
+}
+
+// Get the triple of the machine we're running on, as Rust names it
+fn get_host_triple() -> String {
+    return match (env::consts::ARCH, env::consts::OS) {
+        ("aarch64", "macos") => String::from("aarch64-apple-darwin"),
+        (arch, "linux") => format!("{}-unknown-linux-gnu", arch),
+        _ => String::from(TARGETS[0].triple),
+    };
+}
+
+// Get the optimization level Cargo is building at, where optimizing for size is treated like -O2
+fn get_cargo_opt_level() -> u32 {
+    return parse_cargo_opt_level(&env::var("OPT_LEVEL").unwrap_or_default());
+}
+
+// Turn one of Cargo's optimization levels ("0" to "3", "s" or "z") into one of soup's
+pub fn parse_cargo_opt_level(opt_level: &str) -> u32 {
+    return match opt_level {
+        "s" | "z" => 2,
+        _ => opt_level.parse().unwrap_or(0),
+    };
+}
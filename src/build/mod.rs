@@ -0,0 +1,5 @@
+pub mod build_driver;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
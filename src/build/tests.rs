@@ -0,0 +1,49 @@
+mod tests {
+    use crate::build::build_driver::*;
+
+    #[test]
+    fn test_rust_target_info() {
+        // Rust's names for targets are understood alongside soup's own
+        let target = get_rust_target_info("aarch64-apple-darwin").unwrap();
+        assert_eq!("arm64-apple-darwin", target.triple);
+        let target = get_rust_target_info("riscv64gc-unknown-linux-gnu").unwrap();
+        assert_eq!("riscv64-unknown-linux-gnu", target.triple);
+        let target = get_rust_target_info("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!("x86_64-unknown-linux-gnu", target.triple);
+
+        assert!(get_rust_target_info("x86_64-pc-windows-msvc").is_none());
+    }
+
+    #[test]
+    fn test_cargo_opt_level() {
+        assert_eq!(0, parse_cargo_opt_level("0"));
+        assert_eq!(3, parse_cargo_opt_level("3"));
+        assert_eq!(2, parse_cargo_opt_level("s"));
+        assert_eq!(2, parse_cargo_opt_level("z"));
+        assert_eq!(0, parse_cargo_opt_level(""));
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let dir = std::env::temp_dir();
+        assert!(SoupBuild::new().out_dir(&dir).try_compile("empty").is_err());
+
+        let result = SoupBuild::new()
+            .file("test_files/missing.soup")
+            .out_dir(&dir)
+            .try_compile("missing");
+        assert_eq!(
+            Err(String::from(
+                "Could not read file 'test_files/missing.soup'"
+            )),
+            result
+        );
+
+        let result = SoupBuild::new()
+            .file("test_files/exit.soup")
+            .target("wasm32-unknown-unknown")
+            .out_dir(&dir)
+            .try_compile("exit");
+        assert!(result.is_err());
+    }
+}
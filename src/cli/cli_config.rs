@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::Path;
+
+use crate::cli::cli_data::Config;
+
+// The name of the manifest file that configures a soup project
+pub const CONFIG_FILE: &str = "soup.toml";
+
+// -----------------------------------------------------------------
+// PROJECT CONFIGURATION
+// -----------------------------------------------------------------
+
+// Load the project configuration from the soup.toml in the current directory, if there is one
+pub fn load_config() -> Result<Option<Config>, String> {
+    if !Path::new(CONFIG_FILE).is_file() {
+        return Ok(None);
+    }
+
+    match fs::read_to_string(CONFIG_FILE) {
+        Ok(contents) => match parse_config(&contents) {
+            Ok(config) => return Ok(Some(config)),
+            Err(msg) => return Err(format!("{}: {}", CONFIG_FILE, msg)),
+        },
+        Err(_) => return Err(format!("Could not read {}", CONFIG_FILE)),
+    }
+}
+
+// Parse the contents of a soup.toml, which supports the small subset of TOML needed to describe a project:
+//
+//     [build]
+//     entry = "main.soup"                  # or a list of files, e.g. ["main.soup", "math.soup"]
+//     output = "build/main.asm"
+//     opt-level = 1
+//     target = "arm64-apple-darwin"
+//     warnings-as-errors = true
+//
+// where every key is optional, and the [build] header may be left out
+pub fn parse_config(contents: &str) -> Result<Config, String> {
+    let mut config = Config::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line_num = i + 1;
+        let line = strip_comment(line).trim();
+
+        // Skip blank lines and the (optional) [build] table header
+        if line.is_empty() || line == "[build]" {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            return Err(format!(
+                "Line {}: Unknown table {}, only [build] is supported",
+                line_num, line
+            ));
+        }
+
+        // Every other line must be of the form key = value
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                return Err(format!(
+                    "Line {}: Expected a line of the form key = value",
+                    line_num
+                ))
+            }
+        };
+
+        match key {
+            "entry" => {
+                // The entry can either be a single file or a list of files
+                config.entry = if value.starts_with('[') {
+                    parse_string_list(value, line_num)?
+                } else {
+                    vec![parse_string(value, line_num)?]
+                };
+            }
+            "output" => config.output = Some(parse_string(value, line_num)?),
+            "opt-level" => match value.parse::<u32>() {
+                Ok(opt_level) => config.opt_level = Some(opt_level),
+                Err(_) => {
+                    return Err(format!(
+                        "Line {}: opt-level must be a non-negative integer",
+                        line_num
+                    ))
+                }
+            },
+            "target" => config.target = Some(parse_string(value, line_num)?),
+            "warnings-as-errors" => match value {
+                "true" => config.warnings_as_errors = Some(true),
+                "false" => config.warnings_as_errors = Some(false),
+                _ => {
+                    return Err(format!(
+                        "Line {}: warnings-as-errors must be true or false",
+                        line_num
+                    ))
+                }
+            },
+            _ => return Err(format!("Line {}: Unknown key '{}'", line_num, key)),
+        }
+    }
+
+    return Ok(config);
+}
+
+// Remove a comment from the end of a line, as long as the # isn't inside of a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+
+    for (i, char) in line.char_indices() {
+        if char == '"' {
+            in_string = !in_string;
+        } else if char == '#' && !in_string {
+            return &line[..i];
+        }
+    }
+
+    return line;
+}
+
+// Parse a value of the form "string"
+fn parse_string(value: &str, line_num: usize) -> Result<String, String> {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(string) if !string.contains('"') => return Ok(String::from(string)),
+        _ => {
+            return Err(format!(
+                "Line {}: Expected a string in double quotes, got {}",
+                line_num, value
+            ))
+        }
+    }
+}
+
+// Parse a value of the form ["string", "string", ...]
+fn parse_string_list(value: &str, line_num: usize) -> Result<Vec<String>, String> {
+    let items = match value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+    {
+        Some(items) => items,
+        None => {
+            return Err(format!(
+                "Line {}: Expected a list of strings in square brackets",
+                line_num
+            ))
+        }
+    };
+
+    let mut strings = vec![];
+    for item in items.split(',') {
+        // Allow a trailing comma after the last item
+        if item.trim().is_empty() {
+            continue;
+        }
+        strings.push(parse_string(item.trim(), line_num)?);
+    }
+
+    return Ok(strings);
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::ir::ir_coverage::COVERAGE_MAGIC;
+use crate::{throw_error, write_output};
+
+// The counts a program compiled with --coverage wrote out when it exited (see ir_coverage)
+#[derive(Debug, PartialEq)]
+pub struct CoverageCounts {
+    // The soup files the program was built from
+    pub files: Vec<String>,
+    // The number of times each counted line was run, as the index of its file, the line and its count
+    pub lines: Vec<(usize, i32, u32)>,
+}
+
+// -----------------------------------------------------------------
+// COVERAGE REPORTS
+// -----------------------------------------------------------------
+
+// Write out every soup file the counts in the given file were counted from, with the number of times each line ran
+pub fn report_coverage(coverage_file: &str, output_file: &str) {
+    let data = match fs::read(coverage_file) {
+        Ok(data) => data,
+        Err(_) => {
+            throw_error(&format!("Could not read coverage file '{}'", coverage_file));
+            return;
+        }
+    };
+
+    let counts = match parse_coverage(&data) {
+        Ok(counts) => counts,
+        Err(msg) => {
+            throw_error(&format!("{} in coverage file '{}'", msg, coverage_file));
+            return;
+        }
+    };
+
+    let mut sources = vec![];
+    for file in &counts.files {
+        match fs::read_to_string(file) {
+            Ok(source) => sources.push(source),
+            Err(_) => throw_error(&format!(
+                "Could not read file '{}', which the counts in '{}' were counted from",
+                file, coverage_file
+            )),
+        }
+    }
+
+    write_output(output_file, &format_coverage_report(&counts, &sources));
+}
+
+// Parse the counts a program wrote out: a header saying which line of which file each counter counts,
+// followed by the counters themselves, as 4 byte little-endian unsigned ints
+pub fn parse_coverage(data: &[u8]) -> Result<CoverageCounts, String> {
+    let mut rest = data;
+    let mut next_line = || -> Result<String, String> {
+        let end = match rest.iter().position(|byte| *byte == b'\n') {
+            Some(end) => end,
+            None => return Err(String::from("Unexpected end of header")),
+        };
+        let line = String::from_utf8_lossy(&rest[..end]).to_string();
+        rest = &rest[end + 1..];
+        return Ok(line);
+    };
+
+    if next_line()? != COVERAGE_MAGIC {
+        return Err(String::from("Missing coverage header"));
+    }
+
+    let num_files = parse_count(&next_line()?, "files")?;
+    let mut files = vec![];
+    for _ in 0..num_files {
+        files.push(next_line()?);
+    }
+
+    let num_counters = parse_count(&next_line()?, "counters")?;
+    let mut lines = vec![];
+    for _ in 0..num_counters {
+        let counter = next_line()?;
+        let (file, line) = match counter.split_once(' ') {
+            Some((file, line)) => (file.parse::<usize>(), line.parse::<i32>()),
+            None => return Err(format!("Malformed counter '{}'", counter)),
+        };
+        match (file, line) {
+            (Ok(file), Ok(line)) if file < files.len() => lines.push((file, line, 0)),
+            _ => return Err(format!("Malformed counter '{}'", counter)),
+        }
+    }
+
+    // The counters themselves follow straight on from the header
+    if rest.len() != lines.len() * 4 {
+        return Err(format!(
+            "Expected {} counters, found {} bytes of them",
+            lines.len(),
+            rest.len()
+        ));
+    }
+    for (line, count) in lines.iter_mut().zip(rest.chunks_exact(4)) {
+        line.2 = u32::from_le_bytes([count[0], count[1], count[2], count[3]]);
+    }
+
+    return Ok(CoverageCounts {
+        files: files,
+        lines: lines,
+    });
+}
+
+// Parse one of the lines of the header giving how many of something follow it (like "files 2")
+fn parse_count(line: &str, name: &str) -> Result<usize, String> {
+    return match line
+        .strip_prefix(name)
+        .and_then(|count| count.strip_prefix(' '))
+    {
+        Some(count) => count
+            .parse::<usize>()
+            .map_err(|_| format!("Malformed line '{}'", line)),
+        None => Err(format!("Expected the number of {}, found '{}'", name, line)),
+    };
+}
+
+// Format each file's source (given in the same order as its counts) with the number of times each line ran alongside it,
+// in the same style as gcov: "-" for a line that doesn't run anything, "#####" for one that never ran,
+// and the number of times it ran otherwise. Each file ends with the percentage of its lines that ran
+pub fn format_coverage_report(counts: &CoverageCounts, sources: &[String]) -> String {
+    let mut report = String::new();
+
+    for (file_index, (file, source)) in counts.files.iter().zip(sources).enumerate() {
+        let line_counts: HashMap<i32, u32> = counts
+            .lines
+            .iter()
+            .filter(|(file, _, _)| *file == file_index)
+            .map(|(_, line, count)| (*line, *count))
+            .collect();
+
+        // Each file is separated from the one before it by a blank line
+        if file_index > 0 {
+            report.push('\n');
+        }
+        report.push_str(&format!("{:>9}:{:>5}:Source:{}\n", "-", 0, file));
+        for (i, text) in source.lines().enumerate() {
+            let line_num = i as i32 + 1;
+            let count = match line_counts.get(&line_num) {
+                None => String::from("-"),
+                Some(0) => String::from("#####"),
+                Some(count) => count.to_string(),
+            };
+            report.push_str(&format!("{:>9}:{:>5}:{}\n", count, line_num, text));
+        }
+
+        let num_run = line_counts.values().filter(|count| **count > 0).count();
+        let percent_run = if line_counts.is_empty() {
+            0.0
+        } else {
+            num_run as f64 * 100.0 / line_counts.len() as f64
+        };
+        report.push_str(&format!(
+            "Lines run: {:.2}% of {}\n",
+            percent_run,
+            line_counts.len()
+        ));
+    }
+
+    return report;
+}
@@ -0,0 +1,232 @@
+use crate::code_gen::code_gen_target::{get_target_triples, TARGETS};
+
+// The usage message printed by "soup --help", and alongside any command line errors
+pub const USAGE: &str = "\
+Usage: soup <command> [options]
+
+If no files are given, they are taken from the soup.toml in the current directory (if there is one),
+along with any other settings it contains, which are overridden by any options that are given.
+
+Commands:
+    build <file>...     Compile one or more soup files into a single assembly file
+    run <file>...       Compile one or more soup files, then assemble, link and run the program,
+                        exiting with its exit code
+    fmt <file>...       Format one or more soup files in place, in the canonical style
+    doc <file>...       Write out a summary of the functions and global variables of a program, along with
+                        the \"///\" documentation comments just above them, as Markdown (or HTML)
+    lsp                 Run a language server over stdin and stdout, which an editor can use to show errors
+                        and warnings as you type, the types of identifiers, and where they were declared
+    repl                Start an interactive session, which runs functions, statements and expressions
+                        as they're entered (with an interpreter), printing the value of each expression
+    test-suite <dir>... Run every soup file in the given directories that has the output it's expected to print
+                        alongside it (like hello.stdout for hello.soup), and report the ones that don't print it
+                        (for testing the compiler itself, with whichever target and options are given)
+    cov report [<file>] Write out each soup file a program compiled with --coverage was built from, with the number
+                        of times each line was run alongside it, from the counts it wrote out (soup.cov by default)
+    help                Print this message
+
+Options:
+    -O<level>           Set the optimization level, from 0 (the default) to 3
+    --target <triple>   Generate assembly for the given target, one of arm64-apple-darwin (the default),
+                        aarch64-unknown-linux-gnu, x86_64-unknown-linux-gnu, riscv64-unknown-linux-gnu
+                        (Linux targets are run under qemu on any other kind of machine)
+                        or wasm32-unknown-unknown (a WebAssembly text module, which is run under node)
+    -Werror             Treat every warning as an error
+    -W<warning>         Turn on the given warning, one of:
+                            constant-condition - an if or while condition that's always true or always false
+                                                 (on by default)
+                            shadow             - a parameter or local variable with the same name as something
+                                                 declared outside of its function, which it hides
+    -Wno-<warning>      Turn off the given warning
+    --overflow-checks   Exit the program with an error (like dividing by zero does) when an int overflows,
+                        rather than letting it wrap around
+    --zero-init         Start every local variable and array declared without a value off at zero (just like the
+                        interpreter does), rather than with whatever was left in its register or on the stack
+    --interpret         With run (or test-suite), run the program with an interpreter rather than compiling it,
+                        so it can be run anywhere (without an assembler, a linker or a machine for the target)
+    -g                  Generate debug info, mapping the assembly back to each line of soup so a debugger
+                        (like gdb or lldb) can step through the program
+    --asm-comments      Write each line of soup into the assembly as a comment, above the code generated for it
+    --coverage[=<file>] With build or run, count the number of times each line of soup is run, writing the counts
+                        to <file> (soup.cov by default) when the program exits, for \"soup cov report\" to show
+    --trace-exec        With build or run, make the program print the file, line and text of every statement it
+                        runs, just before running it
+    -o <file>           Write the output to <file>, or to stdout if <file> is \"-\" (defaults to the first
+                        <file> with an .asm extension for assembly, and to stdout for everything else)
+    --emit=<stage>      Stop after the given stage and write out its result, one of:
+                            tokens   - the tokens found by the scanner
+                            ast      - the abstract syntax tree built by the parser
+                            ast-json - the same, once it has been semantically checked, as JSON
+                                       (with the type and symbol table entry of each node)
+                            ast-dot  - the same, as a Graphviz graph, for the dot tool to draw
+                            ir       - the (optimized) intermediate representation of each function
+                            cfg      - the same, split up into each function's control flow graph
+                            llvm-ir  - the program as textual LLVM IR, for LLVM's own optimizers and targets
+                            c        - the program as portable C, for any platform with a C compiler
+                            asm      - the generated assembly (default)
+    --emit-ir           The same as --emit=ir
+    --print-ast         Print the abstract syntax tree once it has been semantically checked
+    --dump-symbols      Print the symbols in each scope once the program has been semantically checked,
+                        with their types and the labels they're given in the assembly
+    --dump-frame        Print the stack frame of each function once the program has been optimized, with its size
+                        and where each parameter and local variable lives (a register or an offset from sp)
+    --format=<format>   With doc, the format to write the documentation in, either markdown (the default) or html
+    --check             With fmt, list the files that aren't formatted (exiting with an error if there are any)
+                        rather than formatting them
+    -v, --verbose       Report the progress of each stage of the compiler to stderr
+    --stats             With build or run, print how many tokens, AST nodes, symbols, string literals, IR
+                        instructions and assembly instructions the program was made into, and its largest stack frame
+    --save-temps        With build or run, also write the result of every stage of the compiler next to the first
+                        <file>: its tokens, AST, checked AST, IR and assembly (like prog.tokens, prog.ast,
+                        prog.ast.json, prog.ir and prog.s for prog.soup)
+    --time-passes       Report the time spent in each stage of the compiler (and the most memory it allocated at once)
+                        to stderr once it's done
+    -w, --watch         Keep watching the given files, and compile (or run) them again whenever they change
+    -V, --version       Print the version of the compiler, and the targets it supports
+    -h, --help          Print this message";
+
+// The warnings that can be turned on (with -W<warning>) or off (with -Wno-<warning>), and whether each one is on by default
+pub const WARNINGS: &[(&str, bool)] = &[("constant-condition", true), ("shadow", false)];
+
+// Get the names of the warnings that are turned on unless they're turned off on the command line
+pub fn get_default_warnings() -> Vec<String> {
+    return WARNINGS
+        .iter()
+        .filter(|(_, default)| *default)
+        .map(|(name, _)| String::from(*name))
+        .collect();
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Build,
+    Run,
+    Fmt,
+    Doc,
+    Lsp,
+    Repl,
+    TestSuite,
+    CovReport,
+    Help,
+    Version,
+}
+
+// The stage of the compiler whose result should be written out
+#[derive(Debug, PartialEq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    AstJson,
+    AstDot,
+    Ir,
+    Cfg,
+    LlvmIr,
+    C,
+    Asm,
+}
+
+// The format "soup doc" writes documentation in
+#[derive(Debug, PartialEq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub input_files: Vec<String>,
+    pub output_file: String,
+    pub emit: Emit,
+    pub print_ast: bool,
+    pub dump_symbols: bool,
+    pub dump_frame: bool,
+    pub verbose: bool,
+    pub time_passes: bool,
+    pub save_temps: bool,
+    pub stats: bool,
+    pub watch: bool,
+    pub opt_level: u32,
+    pub target: String,
+    pub warnings_as_errors: bool,
+    pub warnings: Vec<String>,
+    pub debug_info: bool,
+    pub asm_comments: bool,
+    pub overflow_checks: bool,
+    pub zero_init: bool,
+    pub interpret: bool,
+    pub coverage: Option<String>,
+    pub trace_exec: bool,
+    pub check: bool,
+    pub doc_format: DocFormat,
+}
+
+impl Options {
+    pub fn new(command: Command) -> Options {
+        return Options {
+            command: command,
+            input_files: vec![],
+            output_file: String::from(""),
+            emit: Emit::Asm,
+            print_ast: false,
+            dump_symbols: false,
+            dump_frame: false,
+            verbose: false,
+            time_passes: false,
+            save_temps: false,
+            stats: false,
+            watch: false,
+            opt_level: 0,
+            target: String::from(TARGETS[0].triple),
+            warnings_as_errors: false,
+            warnings: get_default_warnings(),
+            debug_info: false,
+            asm_comments: false,
+            overflow_checks: false,
+            zero_init: false,
+            interpret: false,
+            coverage: None,
+            trace_exec: false,
+            check: false,
+            doc_format: DocFormat::Markdown,
+        };
+    }
+}
+
+// The settings given by a project's soup.toml, each of which can be overridden on the command line
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub entry: Vec<String>,
+    pub output: Option<String>,
+    pub opt_level: Option<u32>,
+    pub target: Option<String>,
+    pub warnings_as_errors: Option<bool>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Config {
+    pub fn new() -> Config {
+        return Config {
+            entry: vec![],
+            output: None,
+            opt_level: None,
+            target: None,
+            warnings_as_errors: None,
+        };
+    }
+}
+
+// Get the version information printed by "soup --version"
+pub fn get_version_string() -> String {
+    return format!(
+        "soup {} ({})\ntargets: {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("SOUP_GIT_HASH"),
+        get_target_triples().join(", ")
+    );
+}
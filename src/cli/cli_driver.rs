@@ -0,0 +1,523 @@
+use std::path::Path;
+
+use crate::cli::cli_data::*;
+use crate::code_gen::code_gen_target::{get_target_info, get_target_triples, Arch};
+use crate::ir::ir_coverage::DEFAULT_COVERAGE_FILE;
+
+// -----------------------------------------------------------------
+// ARGUMENT PARSING
+// -----------------------------------------------------------------
+
+// Parse the command line arguments (not including the name of the executable) into a set of options,
+// starting from the settings in the project's soup.toml (if there is one),
+// and returning an error message describing the problem if the arguments don't make sense
+pub fn parse_args(args: &Vec<String>, config: Option<&Config>) -> Result<Options, String> {
+    // Asking for help anywhere overrides everything else
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        return Ok(Options::new(Command::Help));
+    }
+
+    // Asking for the version is the same, as long as we haven't also asked for help
+    if args.iter().any(|arg| arg == "-V" || arg == "--version") {
+        return Ok(Options::new(Command::Version));
+    }
+
+    // The first argument is the command we want to run
+    let mut options = match args[0].as_str() {
+        "build" => Options::new(Command::Build),
+        "run" => Options::new(Command::Run),
+        "fmt" => Options::new(Command::Fmt),
+        "doc" => Options::new(Command::Doc),
+        "lsp" => Options::new(Command::Lsp),
+        "repl" => Options::new(Command::Repl),
+        "test-suite" => Options::new(Command::TestSuite),
+        "cov" => match args.get(1).map(|subcommand| subcommand.as_str()) {
+            Some("report") => Options::new(Command::CovReport),
+            Some(subcommand) => return Err(format!("Unknown cov command '{}'", subcommand)),
+            None => {
+                return Err(String::from(
+                    "The cov command must be followed by \"report\"",
+                ))
+            }
+        },
+        "help" => return Ok(Options::new(Command::Help)),
+        command => return Err(format!("Unknown command '{}'", command)),
+    };
+
+    // Anything in the project configuration is used unless it's overridden by a command line argument
+    // (a coverage report is only ever made from the counts a program wrote out, not the project's entry files)
+    if let Some(config) = config {
+        if options.command != Command::CovReport {
+            apply_config(&mut options, config);
+        }
+    }
+    let mut files = vec![];
+
+    // Loop through the rest of the arguments, which are either flags or the files to compile
+    // (skipping over the "report" in "cov report")
+    let mut i = if options.command == Command::CovReport {
+        2
+    } else {
+        1
+    };
+    while i < args.len() {
+        let arg = &args[i];
+
+        if arg == "-o" {
+            // The output file is given by the next argument
+            i += 1;
+            match args.get(i) {
+                None => return Err(String::from("Option -o must be followed by a file name")),
+                Some(output_file) => options.output_file = output_file.clone(),
+            }
+        } else if arg == "--emit" || arg.starts_with("--emit=") {
+            // The stage can either be attached to the flag ("--emit=ast") or given by the next argument ("--emit ast")
+            let stage = match arg.strip_prefix("--emit=") {
+                Some(stage) => stage,
+                None => {
+                    i += 1;
+                    match args.get(i) {
+                        None => return Err(String::from("Option --emit must be given a stage")),
+                        Some(stage) => stage,
+                    }
+                }
+            };
+
+            options.emit = match stage {
+                "tokens" => Emit::Tokens,
+                "ast" => Emit::Ast,
+                "ast-json" => Emit::AstJson,
+                "ast-dot" => Emit::AstDot,
+                "ir" => Emit::Ir,
+                "cfg" => Emit::Cfg,
+                "llvm-ir" => Emit::LlvmIr,
+                "c" => Emit::C,
+                "asm" => Emit::Asm,
+                _ => {
+                    return Err(format!(
+                        "Unknown stage '{}' given to --emit, must be one of \"tokens\", \"ast\", \"ast-json\", \"ast-dot\", \"ir\", \"cfg\", \"llvm-ir\", \"c\", \"asm\"",
+                        stage
+                    ))
+                }
+            };
+        } else if arg == "--emit-ir" {
+            options.emit = Emit::Ir;
+        } else if arg == "--print-ast" {
+            options.print_ast = true;
+        } else if arg == "--dump-symbols" {
+            options.dump_symbols = true;
+        } else if arg == "--dump-frame" {
+            options.dump_frame = true;
+        } else if arg == "-v" || arg == "--verbose" {
+            options.verbose = true;
+        } else if arg == "--time-passes" {
+            options.time_passes = true;
+        } else if arg == "--save-temps" {
+            options.save_temps = true;
+        } else if arg == "--stats" {
+            options.stats = true;
+        } else if arg == "-w" || arg == "--watch" {
+            options.watch = true;
+        } else if let Some(level) = arg.strip_prefix("-O") {
+            options.opt_level = match level.parse::<u32>() {
+                Ok(level) if level <= 3 => level,
+                _ => {
+                    return Err(format!(
+                        "Unknown optimization level '{}', must be 0 to 3",
+                        arg
+                    ))
+                }
+            };
+        } else if arg == "--target" || arg.starts_with("--target=") {
+            let target = match arg.strip_prefix("--target=") {
+                Some(target) => target,
+                None => {
+                    i += 1;
+                    match args.get(i) {
+                        None => return Err(String::from("Option --target must be given a target")),
+                        Some(target) => target,
+                    }
+                }
+            };
+            options.target = String::from(target);
+        } else if arg == "-Werror" {
+            options.warnings_as_errors = true;
+        } else if let Some(warning) = arg.strip_prefix("-W") {
+            // Each warning can be turned on or off, with -W<warning> or -Wno-<warning>
+            let (name, enabled) = match warning.strip_prefix("no-") {
+                Some(name) => (name, false),
+                None => (warning, true),
+            };
+            if !WARNINGS.iter().any(|(known, _)| *known == name) {
+                return Err(format!("Unknown warning '{}'", arg));
+            }
+
+            options.warnings.retain(|other| other != name);
+            if enabled {
+                options.warnings.push(String::from(name));
+            }
+        } else if arg == "-g" {
+            options.debug_info = true;
+        } else if arg == "--asm-comments" {
+            options.asm_comments = true;
+        } else if arg == "--overflow-checks" {
+            options.overflow_checks = true;
+        } else if arg == "--zero-init" {
+            options.zero_init = true;
+        } else if arg == "--interpret" {
+            options.interpret = true;
+        } else if arg == "--coverage" {
+            options.coverage = Some(String::from(DEFAULT_COVERAGE_FILE));
+        } else if let Some(coverage_file) = arg.strip_prefix("--coverage=") {
+            if coverage_file.is_empty() {
+                return Err(String::from("Option --coverage= must be given a file name"));
+            }
+            options.coverage = Some(String::from(coverage_file));
+        } else if arg == "--trace-exec" {
+            options.trace_exec = true;
+        } else if arg == "--format" || arg.starts_with("--format=") {
+            let format = match arg.strip_prefix("--format=") {
+                Some(format) => format,
+                None => {
+                    i += 1;
+                    match args.get(i) {
+                        None => return Err(String::from("Option --format must be given a format")),
+                        Some(format) => format,
+                    }
+                }
+            };
+
+            options.doc_format = match format {
+                "markdown" => DocFormat::Markdown,
+                "html" => DocFormat::Html,
+                _ => {
+                    return Err(format!(
+                    "Unknown format '{}' given to --format, must be one of \"markdown\", \"html\"",
+                    format
+                ))
+                }
+            };
+        } else if arg == "--check" {
+            options.check = true;
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option '{}'", arg));
+        } else if files.contains(arg) {
+            return Err(format!("File '{}' was given more than once", arg));
+        } else {
+            files.push(arg.clone());
+        }
+
+        i += 1;
+    }
+
+    // Only a program that's built (or run) can be instrumented for coverage
+    if options.coverage.is_some()
+        && options.command != Command::Build
+        && options.command != Command::Run
+    {
+        return Err(String::from(
+            "Option --coverage can only be used with the build and run commands",
+        ));
+    }
+
+    // Likewise for tracing its execution
+    if options.trace_exec && options.command != Command::Build && options.command != Command::Run {
+        return Err(String::from(
+            "Option --trace-exec can only be used with the build and run commands",
+        ));
+    }
+
+    // Only a program that's built (or run) goes through every stage, and statistics are kept on each of them
+    if options.stats && options.command != Command::Build && options.command != Command::Run {
+        return Err(String::from(
+            "Option --stats can only be used with the build and run commands",
+        ));
+    }
+
+    // Likewise for saving the result of every stage of the compiler
+    if options.save_temps && options.command != Command::Build && options.command != Command::Run {
+        return Err(String::from(
+            "Option --save-temps can only be used with the build and run commands",
+        ));
+    }
+
+    // A coverage report is made from a single file of counts, and written to stdout unless it's given a file with -o
+    if options.command == Command::CovReport {
+        if files.len() > 1 {
+            return Err(String::from(
+                "The cov report command takes a single file of counts",
+            ));
+        } else if options.emit != Emit::Asm
+            || options.watch
+            || options.check
+            || options.doc_format != DocFormat::Markdown
+        {
+            return Err(String::from(
+                "Options --emit, --watch, --check and --format can't be used with the cov report command",
+            ));
+        }
+
+        options.input_files = match files.pop() {
+            Some(file) => vec![file],
+            None => vec![String::from(DEFAULT_COVERAGE_FILE)],
+        };
+        return Ok(options);
+    }
+
+    // The language server is told which files to check by the editor, and only ever talks to the editor
+    // (but it does check them with the warnings that were asked for)
+    if options.command == Command::Lsp {
+        if !files.is_empty() {
+            return Err(String::from(
+                "The lsp command doesn't take any files, they're opened by the editor",
+            ));
+        } else if args.contains(&String::from("-o"))
+            || options.emit != Emit::Asm
+            || options.watch
+            || options.check
+        {
+            return Err(String::from(
+                "Options -o, --emit, --watch and --check can't be used with the lsp command",
+            ));
+        }
+        return Ok(options);
+    }
+
+    // The REPL only ever runs what's typed into it, printing everything (including its errors) to stdout
+    // (but it does run it with the warnings and overflow checks that were asked for)
+    if options.command == Command::Repl {
+        if !files.is_empty() {
+            return Err(String::from(
+                "The repl command doesn't take any files, everything it runs is typed in",
+            ));
+        } else if args.contains(&String::from("-o"))
+            || options.emit != Emit::Asm
+            || options.watch
+            || options.check
+            || options.doc_format != DocFormat::Markdown
+        {
+            return Err(String::from(
+                "Options -o, --emit, --watch, --check and --format can't be used with the repl command",
+            ));
+        }
+        return Ok(options);
+    }
+
+    // The test suite only ever runs the programs in the directories it's given (not the project's entry files),
+    // printing whether each one passed, so there's no output to pick
+    if options.command == Command::TestSuite {
+        if files.is_empty() {
+            return Err(String::from(
+                "The test-suite command must be given at least one directory of programs",
+            ));
+        } else if args.contains(&String::from("-o"))
+            || options.emit != Emit::Asm
+            || options.watch
+            || options.check
+            || options.doc_format != DocFormat::Markdown
+        {
+            return Err(String::from(
+                "Options -o, --emit, --watch, --check and --format can't be used with the test-suite command",
+            ));
+        }
+        options.output_file = String::from("");
+    }
+
+    // Files given on the command line replace the entry files from the project configuration
+    if !files.is_empty() {
+        options.input_files = files;
+    }
+
+    if options.input_files.is_empty() {
+        return Err(String::from("No file given to compile"));
+    }
+
+    if get_target_info(&options.target).is_none() {
+        return Err(format!(
+            "Unsupported target '{}', must be one of: {}",
+            options.target,
+            get_target_triples().join(", ")
+        ));
+    }
+
+    if options.command == Command::TestSuite {
+        return Ok(options);
+    }
+
+    // Formatting only ever writes soup files, either in place or to a file (or stdout) given with -o
+    if options.command == Command::Fmt {
+        if options.emit != Emit::Asm || options.watch {
+            return Err(String::from(
+                "Options --emit and --watch can't be used with the fmt command",
+            ));
+        } else if args.contains(&String::from("-o")) && options.input_files.len() > 1 {
+            return Err(String::from(
+                "Option -o can only be used to format a single file",
+            ));
+        }
+
+        // The output file in the project configuration is meant for building
+        if !args.contains(&String::from("-o")) {
+            options.output_file = String::from("");
+        }
+        return Ok(options);
+    } else if options.check {
+        return Err(String::from(
+            "Option --check can only be used with the fmt command",
+        ));
+    }
+
+    // Documentation is written to stdout, unless it's given a file with -o
+    if options.command == Command::Doc {
+        if options.emit != Emit::Asm || options.watch {
+            return Err(String::from(
+                "Options --emit and --watch can't be used with the doc command",
+            ));
+        }
+
+        // The output file in the project configuration is meant for building
+        if !args.contains(&String::from("-o")) {
+            options.output_file = String::from("");
+        }
+        return Ok(options);
+    } else if options.doc_format != DocFormat::Markdown {
+        return Err(String::from(
+            "Option --format can only be used with the doc command",
+        ));
+    }
+
+    // The counts are written out by the generated assembly, which the C and LLVM IR backends never get to
+    // (and a WebAssembly module has no file of its own to write them to)
+    if options.coverage.is_some() {
+        if options.emit == Emit::C || options.emit == Emit::LlvmIr {
+            return Err(String::from(
+                "Option --coverage can't be used with --emit=c or --emit=llvm-ir",
+            ));
+        } else if options.interpret {
+            return Err(String::from(
+                "Option --coverage can't be used with --interpret",
+            ));
+        } else if get_target_info(&options.target).is_some_and(|target| target.arch == Arch::Wasm32)
+        {
+            return Err(format!(
+                "Option --coverage isn't supported on target '{}'",
+                options.target
+            ));
+        }
+    }
+
+    // The interpreter and the C backend both work straight from the AST, which is never instrumented
+    if options.trace_exec {
+        if options.emit == Emit::C {
+            return Err(String::from(
+                "Option --trace-exec can't be used with --emit=c",
+            ));
+        } else if options.interpret {
+            return Err(String::from(
+                "Option --trace-exec can't be used with --interpret",
+            ));
+        }
+    }
+
+    // Statistics include the assembly the program is made into, which it never gets as far as otherwise
+    if options.stats {
+        if options.emit != Emit::Asm {
+            return Err(String::from("Option --stats can't be used with --emit"));
+        } else if options.interpret {
+            return Err(String::from(
+                "Option --stats can't be used with --interpret",
+            ));
+        }
+    }
+
+    // Stack frames are only laid out by soup's own code generators, and a WebAssembly function doesn't have one
+    if options.dump_frame {
+        if options.emit == Emit::C || options.emit == Emit::LlvmIr {
+            return Err(String::from(
+                "Option --dump-frame can't be used with --emit=c or --emit=llvm-ir",
+            ));
+        } else if options.interpret {
+            return Err(String::from(
+                "Option --dump-frame can't be used with --interpret",
+            ));
+        } else if get_target_info(&options.target).is_some_and(|target| target.arch == Arch::Wasm32)
+        {
+            return Err(format!(
+                "Option --dump-frame isn't supported on target '{}'",
+                options.target
+            ));
+        }
+    }
+
+    // Running a program always goes all the way through to a temporary executable, so there's no output to pick
+    // (although there may be one in the project configuration, which is meant for building)
+    if options.command == Command::Run {
+        if args.contains(&String::from("-o")) {
+            return Err(String::from("Option -o can't be used with the run command"));
+        } else if options.emit != Emit::Asm {
+            return Err(String::from(
+                "Option --emit can't be used with the run command",
+            ));
+        }
+
+        options.output_file = String::from("");
+        return Ok(options);
+    } else if options.interpret {
+        return Err(String::from(
+            "Option --interpret can only be used with the run and test-suite commands",
+        ));
+    }
+
+    // If we weren't told where to put the assembly, put it next to the file we're compiling
+    // (tokens and ASTs are written to stdout instead, which is signified by an empty output file)
+    if options.output_file.is_empty() && options.emit == Emit::Asm {
+        options.output_file = get_default_output_file(&options.input_files[0]);
+    }
+
+    return Ok(options);
+}
+
+// Fill in the given options with the settings from a project configuration
+fn apply_config(options: &mut Options, config: &Config) {
+    options.input_files = config.entry.clone();
+
+    if let Some(output) = &config.output {
+        options.output_file = output.clone();
+    }
+    if let Some(opt_level) = config.opt_level {
+        options.opt_level = opt_level;
+    }
+    if let Some(target) = &config.target {
+        options.target = target.clone();
+    }
+    if let Some(warnings_as_errors) = config.warnings_as_errors {
+        options.warnings_as_errors = warnings_as_errors;
+    }
+}
+
+// Get the name of the assembly file to generate when none is given (e.g. "hello.asm" for "hello.soup")
+pub fn get_default_output_file(input_file: &str) -> String {
+    return Path::new(input_file)
+        .with_extension("asm")
+        .to_string_lossy()
+        .to_string();
+}
+
+// Get the name of the file the result of a stage of the compiler is saved to with --save-temps,
+// which is named after the first file being compiled (e.g. "hello.ir" for "hello.soup")
+pub fn get_save_temps_file(input_file: &str, extension: &str) -> String {
+    return Path::new(input_file)
+        .with_extension(extension)
+        .to_string_lossy()
+        .to_string();
+}
+
+// Get the arguments to recompile with each time a watched file changes, which are just the original arguments
+// without the watch flag (so that each compilation only happens once)
+pub fn get_watch_args(args: &Vec<String>) -> Vec<String> {
+    return args
+        .iter()
+        .filter(|arg| *arg != "-w" && *arg != "--watch")
+        .cloned()
+        .collect();
+}
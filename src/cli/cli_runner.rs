@@ -0,0 +1,168 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use crate::code_gen::code_gen_target::{ObjectFormat, TargetInfo};
+
+// -----------------------------------------------------------------
+// ASSEMBLING, LINKING AND RUNNING
+// -----------------------------------------------------------------
+
+// The script node runs a WebAssembly module with, which provides everything the module imports
+const WASM_HOST: &str = include_str!("cli_wasm_host.js");
+
+// Assemble and link an assembly file generated by the compiler for the given target into an executable
+pub fn assemble_and_link(
+    asm_file: &Path,
+    exe_file: &Path,
+    target: &TargetInfo,
+) -> Result<(), String> {
+    if target.object_format == ObjectFormat::Wasm {
+        // The "assembly" is a WebAssembly text module, which only needs converting into a binary module
+        run_tool(
+            process::Command::new("wat2wasm")
+                .arg(asm_file)
+                .arg("-o")
+                .arg(exe_file),
+        )?;
+        return Ok(());
+    }
+
+    if target.object_format == ObjectFormat::Elf {
+        // The C compiler knows where to find the C library, and how to link against it
+        // (the assembly file has to be marked as assembly, since it doesn't have a .s extension).
+        // A cross compiler links statically, so the executable can be run under qemu without a copy of the target's C library
+        let mut command = get_c_compiler(target);
+        if target.arch.get_name() != env::consts::ARCH {
+            command.arg("-static");
+        }
+        run_tool(
+            command
+                .args(["-x", "assembler"])
+                .arg(asm_file)
+                .args(["-x", "none", "-o"])
+                .arg(exe_file),
+        )?;
+        return Ok(());
+    }
+
+    // Otherwise, build for macOS the same way the soup script does
+    let obj_file = asm_file.with_extension("o");
+    assemble(asm_file, &obj_file, target)?;
+
+    // Find the macOS SDK, which the system library is linked from
+    let sdk_path =
+        run_tool(process::Command::new("xcrun").args(["-sdk", "macosx", "--show-sdk-path"]))?;
+
+    // Link the object file into an executable
+    run_tool(
+        process::Command::new("ld")
+            .arg("-o")
+            .arg(exe_file)
+            .arg(&obj_file)
+            .args(["-lSystem", "-syslibroot", sdk_path.trim()])
+            .args(["-e", target.entry_symbol, "-arch", "arm64"]),
+    )?;
+
+    return Ok(());
+}
+
+// Assemble an assembly file generated by the compiler for the given target into an object file, without linking it
+pub fn assemble(asm_file: &Path, obj_file: &Path, target: &TargetInfo) -> Result<(), String> {
+    if target.object_format == ObjectFormat::Wasm {
+        return Err(format!(
+            "Target '{}' can't be assembled into an object file",
+            target.triple
+        ));
+    }
+
+    let mut command = if target.object_format == ObjectFormat::Elf {
+        let mut command = get_c_compiler(target);
+        command.args(["-c", "-x", "assembler"]);
+        command
+    } else {
+        let mut command = process::Command::new("as");
+        command.args(["-arch", "arm64"]);
+        command
+    };
+    run_tool(command.arg("-o").arg(obj_file).arg(asm_file))?;
+
+    return Ok(());
+}
+
+// Bundle the given object files into a static library, replacing the library if it already exists
+pub fn archive(lib_file: &Path, obj_files: &[&Path]) -> Result<(), String> {
+    // ar adds to an existing archive rather than replacing it, which could leave stale objects behind
+    let _ = fs::remove_file(lib_file);
+    run_tool(
+        process::Command::new("ar")
+            .arg("crs")
+            .arg(lib_file)
+            .args(obj_files),
+    )?;
+
+    return Ok(());
+}
+
+// Get the C compiler for a Linux target, which is the machine's own unless we're on a machine of a different
+// architecture, in which case it's a cross compiler
+fn get_c_compiler(target: &TargetInfo) -> process::Command {
+    if target.arch.get_name() == env::consts::ARCH {
+        return process::Command::new("cc");
+    }
+    return process::Command::new(format!("{}-linux-gnu-gcc", target.arch.get_name()));
+}
+
+// Run an executable built for the given target, letting it share our stdin, stdout and stderr, and return its exit code
+pub fn run_executable(exe_file: &Path, target: &TargetInfo) -> Result<i32, String> {
+    // A Linux executable for another architecture is run under qemu,
+    // and a WebAssembly module is run under node, by the host written alongside it
+    let mut command = if target.object_format == ObjectFormat::Wasm {
+        let host_file = exe_file.with_file_name("soup.js");
+        if fs::write(&host_file, WASM_HOST).is_err() {
+            return Err(format!("Could not write '{}'", host_file.display()));
+        }
+
+        let mut command = process::Command::new("node");
+        command.arg(host_file).arg(exe_file);
+        command
+    } else if target.object_format == ObjectFormat::Elf
+        && target.arch.get_name() != env::consts::ARCH
+    {
+        let mut command = process::Command::new(format!("qemu-{}", target.arch.get_name()));
+        command.arg(exe_file);
+        command
+    } else {
+        process::Command::new(exe_file)
+    };
+
+    let status = match command.status() {
+        Ok(status) => status,
+        Err(err) => return Err(format!("Could not run '{}': {}", exe_file.display(), err)),
+    };
+
+    // A program killed by a signal has no exit code, so treat it like any other failure
+    return Ok(status.code().unwrap_or(1));
+}
+
+// Run one of the tools used to build an executable, returning its stdout if it succeeds,
+// or an error message including its stderr if it fails
+fn run_tool(command: &mut process::Command) -> Result<String, String> {
+    let tool = command.get_program().to_string_lossy().to_string();
+
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(err) => return Err(format!("Could not run '{}': {}", tool, err)),
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "'{}' failed:\n{}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
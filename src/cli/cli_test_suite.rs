@@ -0,0 +1,180 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::cli::cli_data::{Options, WARNINGS};
+use crate::throw_error;
+
+// The extension of the file holding the output a program in the test suite is expected to print
+// (like "hello.stdout" for "hello.soup")
+const EXPECTED_EXTENSION: &str = "stdout";
+
+// -----------------------------------------------------------------
+// RUNNING THE TEST SUITE
+// -----------------------------------------------------------------
+
+// Run every program in the given directories (and the directories inside them) that has the output it's expected to print
+// alongside it, reporting each one that prints anything else, and return true if they all passed.
+// Every program is built and run by a fresh copy of the compiler, so it goes through exactly what "soup run" does
+// (and an error in one program doesn't stop the rest from being run)
+pub fn run_test_suite(options: &Options) -> bool {
+    let compiler = match env::current_exe() {
+        Ok(compiler) => compiler,
+        Err(_) => {
+            throw_error("Could not find the soup executable to run the test suite with");
+            return false;
+        }
+    };
+
+    let mut programs = vec![];
+    for dir in &options.input_files {
+        if !Path::new(dir).is_dir() {
+            throw_error(&format!("Directory '{}' does not exist", dir));
+        }
+        find_programs(Path::new(dir), &mut programs);
+    }
+    programs.sort();
+
+    let run_args = get_run_args(options);
+    let mut num_failed = 0;
+
+    for program in &programs {
+        // The expected output was found alongside the program, so it can only fail to be read if it's just been removed
+        let expected = fs::read(program.with_extension(EXPECTED_EXTENSION)).unwrap_or_default();
+
+        let output = match process::Command::new(&compiler)
+            .arg("run")
+            .arg(program)
+            .args(&run_args)
+            .stdin(process::Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => {
+                throw_error("Could not run the soup executable to run the test suite with");
+                return false;
+            }
+        };
+
+        let mismatch = describe_mismatch(
+            &String::from_utf8_lossy(&expected),
+            &String::from_utf8_lossy(&output.stdout),
+        );
+        match mismatch {
+            None => {
+                if options.verbose {
+                    println!("PASS {}", program.display());
+                }
+            }
+            Some(mismatch) => {
+                num_failed += 1;
+                println!("FAIL {}: {}", program.display(), mismatch);
+
+                // Anything printed to stderr is from the compiler (like an error in the program), not the program itself
+                for line in String::from_utf8_lossy(&output.stderr).lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} passed, {} failed",
+        programs.len() - num_failed,
+        num_failed
+    );
+    return num_failed == 0;
+}
+
+// Add every soup file in a directory (and the directories inside it) that has an expected output file to a list
+fn find_programs(dir: &Path, programs: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            throw_error(&format!("Could not read directory '{}'", dir.display()));
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            find_programs(&path, programs);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "soup")
+            && path.with_extension(EXPECTED_EXTENSION).is_file()
+        {
+            programs.push(path);
+        }
+    }
+}
+
+// Get the arguments to run each program with (following "run" and the program),
+// which build it just the way the test suite was asked to
+fn get_run_args(options: &Options) -> Vec<String> {
+    let mut args = vec![
+        String::from("--target"),
+        options.target.clone(),
+        format!("-O{}", options.opt_level),
+    ];
+
+    for (warning, _) in WARNINGS {
+        if options.warnings.iter().any(|enabled| enabled == warning) {
+            args.push(format!("-W{}", warning));
+        } else {
+            args.push(format!("-Wno-{}", warning));
+        }
+    }
+    if options.warnings_as_errors {
+        args.push(String::from("-Werror"));
+    }
+    if options.overflow_checks {
+        args.push(String::from("--overflow-checks"));
+    }
+    if options.zero_init {
+        args.push(String::from("--zero-init"));
+    }
+    if options.interpret {
+        args.push(String::from("--interpret"));
+    }
+
+    return args;
+}
+
+// Describe the first line where a program's output differs from what it was expected to print
+// (like "line 2: expected \"3\", got \"4\""), or give back None if it printed exactly what was expected
+pub fn describe_mismatch(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    let mut expected_lines = expected.split('\n');
+    let mut actual_lines = actual.split('\n');
+    let mut line_num = 1;
+
+    loop {
+        let mismatch = match (expected_lines.next(), actual_lines.next()) {
+            (Some(expected), Some(actual)) if expected == actual => None,
+            (Some(expected), Some(actual)) => {
+                Some(format!("expected {:?}, got {:?}", expected, actual))
+            }
+            (Some(expected), None) => Some(format!(
+                "expected {:?}, got the end of the output",
+                expected
+            )),
+            (None, Some(actual)) => {
+                Some(format!("expected the end of the output, got {:?}", actual))
+            }
+            // Should never happen, since the outputs aren't the same
+            (None, None) => return None,
+        };
+
+        if let Some(mismatch) = mismatch {
+            return Some(format!("line {}: {}", line_num, mismatch));
+        }
+        line_num += 1;
+    }
+}
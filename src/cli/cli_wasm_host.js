@@ -0,0 +1,115 @@
+// Runs a soup program compiled to WebAssembly under node: node soup.js <program.wasm>
+//
+// A browser can run the same module by passing the same imports (env.printf, env.exit, env.sleep and the file
+// functions) to WebAssembly.instantiate, and calling the _start function it exports
+const fs = require("fs");
+
+// Thrown by exit to stop the program, since there's no other way out of the middle of a WebAssembly function
+class Exit {
+    constructor(code) {
+        this.code = code;
+    }
+}
+
+let memory = null;
+
+// Read the C string (ending in a 0 byte) at the given address in the program's memory
+function readString(address) {
+    const bytes = new Uint8Array(memory.buffer);
+    let end = address;
+    while (bytes[end] !== 0) {
+        end++;
+    }
+    return new TextDecoder().decode(bytes.subarray(address, end));
+}
+
+// Fill in a format string, replacing each %d with the next int in the array at args
+function formatString(format, args) {
+    const view = new DataView(memory.buffer);
+    let i = 0;
+    return readString(format).replace(/%[d%]/g, (formatter) => {
+        if (formatter === "%%") {
+            return "%";
+        }
+        return String(view.getInt32(args + 4 * i++, true));
+    });
+}
+
+const imports = {
+    env: {
+        printf(format, args) {
+            fs.writeSync(1, formatString(format, args));
+        },
+        exit(code) {
+            throw new Exit(code);
+        },
+        // Block until the time is up, by waiting on memory that nothing will ever change
+        sleep(ms) {
+            if (ms > 0) {
+                Atomics.wait(new Int32Array(new SharedArrayBuffer(4)), 0, 0, ms);
+            }
+        },
+        // A file is numbered by its file descriptor, just like it is in a compiled program,
+        // and anything done with a file that isn't open does nothing
+        fopen(path, mode) {
+            try {
+                return fs.openSync(readString(path), readString(mode));
+            } catch {
+                return -1;
+            }
+        },
+        dprintf(file, format, args) {
+            try {
+                fs.writeSync(file, formatString(format, args));
+            } catch {}
+        },
+        // Read the next line of a file a byte at a time, storing every byte that fits in the array at line,
+        // up to the newline, followed by a 0 if there's room for one. Gives back the number of bytes stored,
+        // or -1 if the end of the file was reached before anything was read
+        fread_line(file, line, len) {
+            const view = new DataView(memory.buffer);
+            const byte = Buffer.alloc(1);
+            let stored = 0;
+            let readAnything = false;
+            try {
+                while (fs.readSync(file, byte, 0, 1, null) === 1) {
+                    readAnything = true;
+                    if (byte[0] === 10) {
+                        break;
+                    }
+                    if (stored < len) {
+                        view.setInt32(line + 4 * stored++, byte[0], true);
+                    }
+                }
+            } catch {}
+
+            if (!readAnything) {
+                return -1;
+            }
+            if (stored < len) {
+                view.setInt32(line + 4 * stored, 0, true);
+            }
+            return stored;
+        },
+        fclose(file) {
+            try {
+                fs.closeSync(file);
+            } catch {}
+        },
+    },
+};
+
+WebAssembly.instantiate(fs.readFileSync(process.argv[2]), imports)
+    .then(({ instance }) => {
+        memory = instance.exports.memory;
+        instance.exports._start();
+    })
+    .catch((err) => {
+        if (err instanceof Exit) {
+            process.exit(err.code);
+        }
+
+        // Anything else is a trap, like running out of stack
+        console.error(`Error: ${err.message}`);
+        process.exit(1);
+    });
@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::throw_error;
+
+// How long to wait between checks for changes to the files being watched
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// -----------------------------------------------------------------
+// WATCHING
+// -----------------------------------------------------------------
+
+// Recompile whenever any of the given files change, until the user stops us (e.g. with Ctrl-C).
+// Every compilation happens in a fresh copy of the compiler, run with the given arguments,
+// so that an error in the program being compiled is reported without ending the watch
+pub fn watch(files: &Vec<String>, compiler_args: &Vec<String>) {
+    let compiler = match env::current_exe() {
+        Ok(compiler) => compiler,
+        Err(_) => {
+            throw_error("Could not find the soup executable to recompile with");
+            return;
+        }
+    };
+
+    // No file has been compiled yet, so make sure the first check sees every file as changed
+    let mut last_modified = vec![None; files.len()];
+
+    loop {
+        let modified = get_modified_times(files);
+
+        if modified != last_modified {
+            last_modified = modified;
+
+            eprintln!("[watch] Compiling {}", files.join(", "));
+            match process::Command::new(&compiler)
+                .args(compiler_args)
+                .status()
+            {
+                Ok(status) if status.success() => {
+                    eprintln!("[watch] Finished, waiting for changes")
+                }
+                Ok(status) => eprintln!(
+                    "[watch] Failed with exit code {}, waiting for changes",
+                    status.code().unwrap_or(1)
+                ),
+                Err(_) => throw_error("Could not run the soup executable to recompile with"),
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Get the time each of the given files was last modified (or None if a file can't be read right now,
+// which happens briefly while some editors save a file)
+fn get_modified_times(files: &Vec<String>) -> Vec<Option<SystemTime>> {
+    return files
+        .iter()
+        .map(|file| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect();
+}
@@ -0,0 +1,11 @@
+pub mod cli_config;
+pub mod cli_coverage;
+pub mod cli_data;
+pub mod cli_driver;
+pub mod cli_runner;
+pub mod cli_test_suite;
+pub mod cli_watch;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
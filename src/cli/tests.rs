@@ -0,0 +1,558 @@
+mod tests {
+    use crate::cli::cli_config::parse_config;
+    use crate::cli::cli_coverage::*;
+    use crate::cli::cli_data::*;
+    use crate::cli::cli_driver::*;
+    use crate::cli::cli_test_suite::describe_mismatch;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        return args.iter().map(|arg| String::from(*arg)).collect();
+    }
+
+    #[test]
+    fn test_parse_args_build() {
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "-o", "out.asm"]), None).unwrap();
+
+        assert_eq!(Command::Build, options.command);
+        assert_eq!(vec!["hello.soup"], options.input_files);
+        assert_eq!("out.asm", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_multiple_files() {
+        let options = parse_args(
+            &to_args(&["build", "a.soup", "b.soup", "-o", "prog.s"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(vec!["a.soup", "b.soup"], options.input_files);
+        assert_eq!("prog.s", options.output_file);
+
+        let options = parse_args(&to_args(&["build", "a.soup", "b.soup"]), None).unwrap();
+        assert_eq!("a.asm", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_stdout() {
+        let options = parse_args(&to_args(&["build", "hello.soup", "-o", "-"]), None).unwrap();
+
+        assert_eq!("-", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_default_output_file() {
+        let options = parse_args(&to_args(&["build", "dir/hello.soup"]), None).unwrap();
+
+        assert_eq!("dir/hello.asm", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_emit() {
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--emit=tokens"]), None).unwrap();
+        assert_eq!(Emit::Tokens, options.emit);
+        assert_eq!("", options.output_file);
+
+        let options =
+            parse_args(&to_args(&["build", "--emit", "ast", "hello.soup"]), None).unwrap();
+        assert_eq!(Emit::Ast, options.emit);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--emit=ast-json"]), None).unwrap();
+        assert_eq!(Emit::AstJson, options.emit);
+        assert_eq!("", options.output_file);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--emit=ast-dot"]), None).unwrap();
+        assert_eq!(Emit::AstDot, options.emit);
+        assert_eq!("", options.output_file);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--emit=asm"]), None).unwrap();
+        assert_eq!(Emit::Asm, options.emit);
+        assert_eq!("hello.asm", options.output_file);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--emit=ir"]), None).unwrap();
+        assert_eq!(Emit::Ir, options.emit);
+        assert_eq!("", options.output_file);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--emit-ir"]), None).unwrap();
+        assert_eq!(Emit::Ir, options.emit);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--emit=cfg"]), None).unwrap();
+        assert_eq!(Emit::Cfg, options.emit);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--emit=llvm-ir"]), None).unwrap();
+        assert_eq!(Emit::LlvmIr, options.emit);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--emit=c"]), None).unwrap();
+        assert_eq!(Emit::C, options.emit);
+        assert_eq!("", options.output_file);
+
+        assert!(parse_args(&to_args(&["build", "hello.soup", "--emit=llvm"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "hello.soup", "--emit"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_flags() {
+        let options = parse_args(&to_args(&["build", "hello.soup"]), None).unwrap();
+        assert!(!options.print_ast);
+        assert!(!options.verbose);
+
+        let options = parse_args(
+            &to_args(&["build", "-v", "hello.soup", "--print-ast"]),
+            None,
+        )
+        .unwrap();
+        assert!(options.print_ast);
+        assert!(options.verbose);
+        assert!(!options.dump_symbols);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--dump-symbols"]), None).unwrap();
+        assert!(options.dump_symbols);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "--verbose"]), None).unwrap();
+        assert!(options.verbose);
+        assert!(!options.debug_info);
+        assert!(!options.time_passes);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--time-passes"]), None).unwrap();
+        assert!(options.time_passes);
+
+        let options = parse_args(&to_args(&["build", "-g", "hello.soup"]), None).unwrap();
+        assert!(options.debug_info);
+        assert!(!options.asm_comments);
+
+        let options =
+            parse_args(&to_args(&["build", "hello.soup", "--asm-comments"]), None).unwrap();
+        assert!(options.asm_comments);
+        assert!(!options.overflow_checks);
+
+        let options = parse_args(
+            &to_args(&["build", "hello.soup", "--overflow-checks"]),
+            None,
+        )
+        .unwrap();
+        assert!(options.overflow_checks);
+    }
+
+    #[test]
+    fn test_parse_args_run() {
+        let options = parse_args(&to_args(&["run", "hello.soup", "-v"]), None).unwrap();
+        assert_eq!(Command::Run, options.command);
+        assert_eq!(vec!["hello.soup"], options.input_files);
+        assert!(options.verbose);
+
+        assert!(parse_args(&to_args(&["run", "hello.soup", "-o", "out.asm"]), None).is_err());
+        assert!(parse_args(&to_args(&["run", "hello.soup", "--emit=ast"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_fmt() {
+        // Files are formatted in place unless an output file is given
+        let options = parse_args(&to_args(&["fmt", "a.soup", "b.soup"]), None).unwrap();
+        assert_eq!(Command::Fmt, options.command);
+        assert_eq!(vec!["a.soup", "b.soup"], options.input_files);
+        assert_eq!("", options.output_file);
+        assert!(!options.check);
+
+        let options = parse_args(&to_args(&["fmt", "a.soup", "-o", "-"]), None).unwrap();
+        assert_eq!("-", options.output_file);
+
+        let options = parse_args(&to_args(&["fmt", "--check", "a.soup"]), None).unwrap();
+        assert!(options.check);
+
+        assert!(parse_args(&to_args(&["fmt", "a.soup", "b.soup", "-o", "-"]), None).is_err());
+        assert!(parse_args(&to_args(&["fmt", "a.soup", "--emit=ast"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "a.soup", "--check"]), None).is_err());
+
+        // The output file in the project configuration is only for building
+        let config = parse_config("entry = \"main.soup\"\noutput = \"main.s\"").unwrap();
+        let options = parse_args(&to_args(&["fmt"]), Some(&config)).unwrap();
+        assert_eq!(vec!["main.soup"], options.input_files);
+        assert_eq!("", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_doc() {
+        // Documentation is written to stdout as Markdown, unless we're told otherwise
+        let options = parse_args(&to_args(&["doc", "a.soup"]), None).unwrap();
+        assert_eq!(Command::Doc, options.command);
+        assert_eq!(DocFormat::Markdown, options.doc_format);
+        assert_eq!("", options.output_file);
+
+        let options = parse_args(
+            &to_args(&["doc", "a.soup", "--format", "html", "-o", "a.html"]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(DocFormat::Html, options.doc_format);
+        assert_eq!("a.html", options.output_file);
+
+        assert!(parse_args(&to_args(&["doc", "a.soup", "--format=pdf"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "a.soup", "--format=html"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_lsp() {
+        // The language server takes warnings like any other command, but no files (which come from the editor)
+        let options = parse_args(&to_args(&["lsp", "-Wshadow"]), None).unwrap();
+        assert_eq!(Command::Lsp, options.command);
+        assert!(options.warnings.contains(&String::from("shadow")));
+
+        assert!(parse_args(&to_args(&["lsp", "a.soup"]), None).is_err());
+        assert!(parse_args(&to_args(&["lsp", "--emit=ast"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_repl() {
+        let options = parse_args(&to_args(&["repl", "--overflow-checks"]), None).unwrap();
+        assert_eq!(Command::Repl, options.command);
+        assert!(options.overflow_checks);
+
+        assert!(parse_args(&to_args(&["repl", "a.soup"]), None).is_err());
+        assert!(parse_args(&to_args(&["repl", "-o", "a.asm"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_interpret() {
+        let options = parse_args(&to_args(&["run", "hello.soup", "--interpret"]), None).unwrap();
+        assert!(options.interpret);
+
+        assert!(parse_args(&to_args(&["build", "hello.soup", "--interpret"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_zero_init() {
+        let options = parse_args(&to_args(&["build", "hello.soup", "--zero-init"]), None).unwrap();
+        assert!(options.zero_init);
+
+        let options = parse_args(&to_args(&["build", "hello.soup"]), None).unwrap();
+        assert!(!options.zero_init);
+    }
+
+    #[test]
+    fn test_parse_args_trace_exec() {
+        let options = parse_args(&to_args(&["run", "hello.soup", "--trace-exec"]), None).unwrap();
+        assert!(options.trace_exec);
+
+        assert!(parse_args(&to_args(&["doc", "hello.soup", "--trace-exec"]), None).is_err());
+        let args = to_args(&["run", "hello.soup", "--trace-exec", "--interpret"]);
+        assert!(parse_args(&args, None).is_err());
+        let args = to_args(&["build", "hello.soup", "--trace-exec", "--emit=c"]);
+        assert!(parse_args(&args, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_dump_frame() {
+        let options = parse_args(&to_args(&["build", "hello.soup", "--dump-frame"]), None).unwrap();
+        assert!(options.dump_frame);
+
+        let args = to_args(&["build", "hello.soup", "--dump-frame", "--emit=llvm-ir"]);
+        assert!(parse_args(&args, None).is_err());
+        let args = to_args(&[
+            "build",
+            "hello.soup",
+            "--dump-frame",
+            "--target",
+            "wasm32-unknown-unknown",
+        ]);
+        assert!(parse_args(&args, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_stats() {
+        let options = parse_args(&to_args(&["build", "hello.soup", "--stats"]), None).unwrap();
+        assert!(options.stats);
+
+        assert!(parse_args(&to_args(&["doc", "hello.soup", "--stats"]), None).is_err());
+        let args = to_args(&["build", "hello.soup", "--stats", "--emit=ir"]);
+        assert!(parse_args(&args, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_save_temps() {
+        let options = parse_args(&to_args(&["run", "hello.soup", "--save-temps"]), None).unwrap();
+        assert!(options.save_temps);
+
+        assert!(parse_args(&to_args(&["fmt", "hello.soup", "--save-temps"]), None).is_err());
+        assert_eq!("dir/hello.ir", get_save_temps_file("dir/hello.soup", "ir"));
+        assert_eq!(
+            "hello.ast.json",
+            get_save_temps_file("hello.soup", "ast.json")
+        );
+    }
+
+    #[test]
+    fn test_parse_args_test_suite() {
+        let args = to_args(&["test-suite", "test_files", "--interpret", "-O2"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(Command::TestSuite, options.command);
+        assert_eq!(vec!["test_files"], options.input_files);
+        assert!(options.interpret);
+
+        assert!(parse_args(&to_args(&["test-suite"]), None).is_err());
+        assert!(parse_args(&to_args(&["test-suite", "test_files", "--emit=ir"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_coverage() {
+        let options = parse_args(&to_args(&["run", "hello.soup", "--coverage"]), None).unwrap();
+        assert_eq!(Some(String::from("soup.cov")), options.coverage);
+
+        let args = to_args(&["build", "hello.soup", "--coverage=hello.cov"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(Some(String::from("hello.cov")), options.coverage);
+
+        // The counts can only be written out by a native executable
+        assert!(parse_args(
+            &to_args(&["run", "hello.soup", "--coverage", "--interpret"]),
+            None
+        )
+        .is_err());
+        assert!(parse_args(
+            &to_args(&["build", "hello.soup", "--coverage", "--emit=c"]),
+            None
+        )
+        .is_err());
+        let args = to_args(&[
+            "build",
+            "hello.soup",
+            "--coverage",
+            "--target",
+            "wasm32-unknown-unknown",
+        ]);
+        assert!(parse_args(&args, None).is_err());
+        assert!(parse_args(&to_args(&["fmt", "hello.soup", "--coverage"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_cov_report() {
+        let options = parse_args(&to_args(&["cov", "report"]), None).unwrap();
+        assert_eq!(Command::CovReport, options.command);
+        assert_eq!(vec!["soup.cov"], options.input_files);
+        assert_eq!("", options.output_file);
+
+        let args = to_args(&["cov", "report", "hello.cov", "-o", "report.txt"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(vec!["hello.cov"], options.input_files);
+        assert_eq!("report.txt", options.output_file);
+
+        assert!(parse_args(&to_args(&["cov"]), None).is_err());
+        assert!(parse_args(&to_args(&["cov", "merge"]), None).is_err());
+        assert!(parse_args(&to_args(&["cov", "report", "a.cov", "b.cov"]), None).is_err());
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let mut data = b"soup coverage\nfiles 1\nmain.soup\ncounters 3\n0 2\n0 3\n0 4\n".to_vec();
+        for count in [1u32, 0, 12] {
+            data.extend(count.to_le_bytes());
+        }
+
+        let counts = parse_coverage(&data).unwrap();
+        assert_eq!(vec!["main.soup"], counts.files);
+        assert_eq!(vec![(0, 2, 1), (0, 3, 0), (0, 4, 12)], counts.lines);
+
+        let source =
+            String::from("func main() returns void {\nint x = 1;\nif false {\nx = 2;\n}\n}\n");
+        let expected = "        -:    0:Source:main.soup
+        -:    1:func main() returns void {
+        1:    2:int x = 1;
+    #####:    3:if false {
+       12:    4:x = 2;
+        -:    5:}
+        -:    6:}
+Lines run: 66.67% of 3
+";
+        assert_eq!(expected, format_coverage_report(&counts, &[source]));
+
+        // The counters have to match up with the header
+        assert!(parse_coverage(&data[..data.len() - 1]).is_err());
+        assert!(parse_coverage(b"soup coverage\nfiles 1\n").is_err());
+        assert!(parse_coverage(b"not coverage\n").is_err());
+        assert!(parse_coverage(b"soup coverage\nfiles 0\ncounters 1\n0 2\n\0\0\0\0").is_err());
+    }
+
+    #[test]
+    fn test_describe_mismatch() {
+        assert_eq!(None, describe_mismatch("1\n2\n", "1\n2\n"));
+        assert_eq!(
+            Some(String::from("line 2: expected \"2\", got \"3\"")),
+            describe_mismatch("1\n2\n", "1\n3\n")
+        );
+        assert_eq!(
+            Some(String::from(
+                "line 2: expected \"2\", got the end of the output"
+            )),
+            describe_mismatch("1\n2", "1")
+        );
+    }
+
+    #[test]
+    fn test_parse_args_watch() {
+        let args = to_args(&["run", "--watch", "hello.soup", "-v"]);
+        let options = parse_args(&args, None).unwrap();
+        assert!(options.watch);
+
+        // Each recompilation is given the same arguments, minus the watch flag
+        assert_eq!(to_args(&["run", "hello.soup", "-v"]), get_watch_args(&args));
+    }
+
+    #[test]
+    fn test_parse_args_options() {
+        let options = parse_args(&to_args(&["build", "hello.soup"]), None).unwrap();
+        assert_eq!(0, options.opt_level);
+        assert_eq!("arm64-apple-darwin", options.target);
+        assert!(!options.warnings_as_errors);
+
+        let args = to_args(&[
+            "build",
+            "hello.soup",
+            "-O2",
+            "--target",
+            "arm64-apple-darwin",
+            "-Werror",
+        ]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(2, options.opt_level);
+        assert!(options.warnings_as_errors);
+
+        assert!(parse_args(&to_args(&["build", "hello.soup", "-O4"]), None).is_err());
+        let args = to_args(&["build", "hello.soup", "--target=x86_64-unknown-linux-gnu"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!("x86_64-unknown-linux-gnu", options.target);
+        let args = to_args(&["build", "hello.soup", "--target=aarch64-unknown-linux-gnu"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!("aarch64-unknown-linux-gnu", options.target);
+        let args = to_args(&["build", "hello.soup", "--target=riscv64-unknown-linux-gnu"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!("riscv64-unknown-linux-gnu", options.target);
+        let args = to_args(&["build", "hello.soup", "--target=wasm32-unknown-unknown"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!("wasm32-unknown-unknown", options.target);
+
+        assert!(parse_args(&to_args(&["build", "hello.soup", "--target=z80"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_warnings() {
+        let options = parse_args(&to_args(&["build", "hello.soup"]), None).unwrap();
+        assert_eq!(vec!["constant-condition"], options.warnings);
+
+        let args = to_args(&["build", "hello.soup", "-Wno-constant-condition"]);
+        let options = parse_args(&args, None).unwrap();
+        assert!(options.warnings.is_empty());
+
+        // The last flag given for a warning wins
+        let args = to_args(&[
+            "build",
+            "hello.soup",
+            "-Wno-constant-condition",
+            "-Wconstant-condition",
+        ]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(vec!["constant-condition"], options.warnings);
+
+        let args = to_args(&["build", "hello.soup", "-Wshadow"]);
+        let options = parse_args(&args, None).unwrap();
+        assert_eq!(vec!["constant-condition", "shadow"], options.warnings);
+
+        assert!(parse_args(&to_args(&["build", "hello.soup", "-Wfast"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "hello.soup", "-Wno-"]), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = parse_config(
+            "# A soup project
+[build]
+entry = [\"main.soup\", \"math.soup\"]  # compiled together
+output = \"build/main.asm\"
+opt-level = 1
+warnings-as-errors = true
+",
+        )
+        .unwrap();
+
+        assert_eq!(vec!["main.soup", "math.soup"], config.entry);
+        assert_eq!(Some(String::from("build/main.asm")), config.output);
+        assert_eq!(Some(1), config.opt_level);
+        assert_eq!(None, config.target);
+        assert_eq!(Some(true), config.warnings_as_errors);
+
+        assert_eq!(
+            vec!["main.soup"],
+            parse_config("entry = \"main.soup\"").unwrap().entry
+        );
+
+        assert!(parse_config("entry = main.soup").is_err());
+        assert!(parse_config("[package]").is_err());
+        assert!(parse_config("optimize = 1").is_err());
+        assert!(parse_config("opt-level = fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_args_with_config() {
+        let config =
+            parse_config("entry = \"main.soup\"\noutput = \"main.s\"\nopt-level = 1").unwrap();
+
+        // With a project configuration, no files or options need to be given
+        let options = parse_args(&to_args(&["build"]), Some(&config)).unwrap();
+        assert_eq!(vec!["main.soup"], options.input_files);
+        assert_eq!("main.s", options.output_file);
+        assert_eq!(1, options.opt_level);
+
+        // but anything given on the command line overrides it
+        let args = to_args(&["build", "other.soup", "-o", "other.s", "-O0"]);
+        let options = parse_args(&args, Some(&config)).unwrap();
+        assert_eq!(vec!["other.soup"], options.input_files);
+        assert_eq!("other.s", options.output_file);
+        assert_eq!(0, options.opt_level);
+
+        // The output file is only for building, so it's ignored when running
+        let options = parse_args(&to_args(&["run"]), Some(&config)).unwrap();
+        assert_eq!("", options.output_file);
+    }
+
+    #[test]
+    fn test_parse_args_help() {
+        assert_eq!(
+            Command::Help,
+            parse_args(&to_args(&[]), None).unwrap().command
+        );
+        assert_eq!(
+            Command::Help,
+            parse_args(&to_args(&["help"]), None).unwrap().command
+        );
+        assert_eq!(
+            Command::Help,
+            parse_args(&to_args(&["build", "hello.soup", "--help"]), None)
+                .unwrap()
+                .command
+        );
+    }
+
+    #[test]
+    fn test_parse_args_version() {
+        let options = parse_args(&to_args(&["--version"]), None).unwrap();
+        assert_eq!(Command::Version, options.command);
+
+        let options = parse_args(&to_args(&["build", "hello.soup", "-V"]), None).unwrap();
+        assert_eq!(Command::Version, options.command);
+
+        assert!(get_version_string().starts_with(&format!("soup {}", env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(&to_args(&["compile", "hello.soup"]), None).is_err());
+        assert!(parse_args(&to_args(&["build"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "hello.soup", "-o"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "hello.soup", "--fast"]), None).is_err());
+        assert!(parse_args(&to_args(&["build", "a.soup", "a.soup"]), None).is_err());
+    }
+}
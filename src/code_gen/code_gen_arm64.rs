@@ -0,0 +1,45 @@
+use crate::ir::ir_data::{Coverage, IrFunction, IrProgram, IrType};
+
+use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_generators::*;
+use crate::code_gen::code_gen_peephole;
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::code_gen::code_gen_utils::{gen_data, get_reg, SAVED_REGS};
+
+// -----------------------------------------------------------------------------------------
+// ARM64 (macOS AND LINUX)
+// -----------------------------------------------------------------------------------------
+
+pub struct Arm64Generator {
+    pub target: &'static TargetInfo,
+}
+
+impl CodeGenerator for Arm64Generator {
+    fn gen_globals(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        gen_data(writer, self.target, program);
+    }
+
+    fn gen_asm_main(&self, writer: &mut ASMWriter, main_returns_int: bool) {
+        gen_asm_main(writer, self.target, main_returns_int);
+    }
+
+    fn gen_function(&self, writer: &mut ASMWriter, func: &IrFunction) {
+        gen_function(writer, self.target, func);
+    }
+
+    fn get_saved_reg_name(&self, reg: usize, temp_type: IrType) -> String {
+        return get_reg(SAVED_REGS[reg], temp_type);
+    }
+
+    fn gen_coverage_routine(&self, writer: &mut ASMWriter, coverage: &Coverage) {
+        gen_coverage_routine(writer, self.target, coverage);
+    }
+
+    fn gen_runtime_lib(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        gen_runtime_lib(writer, self.target, program);
+    }
+
+    fn optimize_asm(&self, lines: Vec<AsmLine>) -> Vec<AsmLine> {
+        return code_gen_peephole::optimize_asm(lines);
+    }
+}
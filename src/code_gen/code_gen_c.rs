@@ -0,0 +1,1183 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::ir::ir_constants::get_global_value;
+use crate::ir::ir_utils::*;
+use crate::parser::parser_data::{NodeRef, AST};
+use crate::semantic::semantic_data::{Symbol, Type};
+use crate::semantic::semantic_utils::{is_array_type, is_binary, is_unary};
+
+// The runtime support every C program starts with: arrays (and strings) are passed around as the address of their
+// first element alongside their length, arithmetic wraps around on overflow (unless it's checked, by doing it with
+// 64 bit ints), and dividing by zero, indexing out of bounds or failing to convert a number to or from its characters
+// (with parse_int or to_string) prints the same error message (and exits with the same exit code) as the native
+// backends. Sleeping and files are done just like the native backends do them too, with functions that are POSIX
+// rather than standard C, so they have to be asked for
+const C_PRELUDE: &str = "\
+#ifndef _POSIX_C_SOURCE
+#define _POSIX_C_SOURCE 200809L
+#endif
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <time.h>
+#include <unistd.h>
+
+#if __STDC_VERSION__ >= 201112L
+#define SOUP_NORETURN _Noreturn
+#else
+#define SOUP_NORETURN
+#endif
+
+typedef struct {
+    void *ptr;
+    int32_t len;
+} soup_array;
+
+static inline SOUP_NORETURN void soup_panic(const char *message) {
+    fputs(message, stdout);
+    exit(1);
+}
+
+static inline int32_t soup_add(int32_t a, int32_t b) { return (int32_t)((uint32_t)a + (uint32_t)b); }
+static inline int32_t soup_sub(int32_t a, int32_t b) { return (int32_t)((uint32_t)a - (uint32_t)b); }
+static inline int32_t soup_mul(int32_t a, int32_t b) { return (int32_t)((uint32_t)a * (uint32_t)b); }
+static inline int32_t soup_neg(int32_t a) { return (int32_t)(0u - (uint32_t)a); }
+
+static inline int32_t soup_checked(int64_t result, int32_t line) {
+    if (result < INT32_MIN || result > INT32_MAX) {
+        printf(\"Error: Line %d: Integer overflow\\n\", (int)line);
+        exit(1);
+    }
+    return (int32_t)result;
+}
+
+static inline int32_t soup_div(int32_t a, int32_t b, int32_t line) {
+    if (b == 0) {
+        printf(\"Error: Line %d: Division by zero\\n\", (int)line);
+        exit(1);
+    }
+    return b == -1 ? soup_neg(a) : a / b;
+}
+
+static inline int32_t soup_checked_div(int32_t a, int32_t b, int32_t line) {
+    if (b == -1) {
+        return soup_checked(-(int64_t)a, line);
+    }
+    return soup_div(a, b, line);
+}
+
+static inline int32_t soup_rem(int32_t a, int32_t b, int32_t line) {
+    if (b == 0) {
+        printf(\"Error: Line %d: Division by zero\\n\", (int)line);
+        exit(1);
+    }
+    return b == -1 ? 0 : a % b;
+}
+
+static inline char *soup_index(soup_array array, int32_t index, int32_t size, int32_t line, const char *indexed) {
+    if ((uint32_t)index >= (uint32_t)array.len) {
+        printf(\"Error: Line %d: %s index out of bounds\\n\", (int)line, indexed);
+        exit(1);
+    }
+    return (char *)array.ptr + (size_t)index * (size_t)size;
+}
+
+static inline int32_t *soup_int(soup_array array, int32_t index, int32_t line) {
+    return (int32_t *)soup_index(array, index, 4, line, \"Array\");
+}
+
+static inline uint8_t *soup_char(soup_array array, int32_t index, int32_t line) {
+    return (uint8_t *)soup_index(array, index, 1, line, \"String\");
+}
+
+static inline soup_array soup_row(soup_array array, int32_t index, int32_t row_size, int32_t row_len, int32_t line) {
+    soup_array row;
+    row.ptr = soup_index(array, index, row_size * 4, line, \"Array\");
+    row.len = row_len;
+    return row;
+}
+
+static inline SOUP_NORETURN void soup_parse_int_error(int32_t line, const char *message) {
+    printf(\"Error: Line %d: %s\\n\", (int)line, message);
+    exit(1);
+}
+
+static inline int32_t soup_parse_int(soup_array text, int32_t size, int32_t line) {
+    int64_t value = 0;
+    int32_t start = 0;
+    int32_t i;
+    for (i = 0; i < text.len; i++) {
+        int32_t c = size == 1 ? ((uint8_t *)text.ptr)[i] : ((int32_t *)text.ptr)[i];
+        if (c == 0) {
+            break;
+        }
+        if (i == 0 && c == '-') {
+            start = 1;
+            continue;
+        }
+        if (c < '0' || c > '9') {
+            soup_parse_int_error(line, \"parse_int was given something that isn't a number\");
+        }
+        value = value * 10 + (c - '0');
+        if (value > -(int64_t)INT32_MIN) {
+            soup_parse_int_error(line, \"Number given to parse_int is too large to fit in an int\");
+        }
+    }
+    if (i == start) {
+        soup_parse_int_error(line, \"parse_int was given something that isn't a number\");
+    }
+    if (!start && value > INT32_MAX) {
+        soup_parse_int_error(line, \"Number given to parse_int is too large to fit in an int\");
+    }
+    return (int32_t)(start ? -value : value);
+}
+
+static inline int32_t soup_to_string(int32_t value, soup_array chars, int32_t line) {
+    char text[12];
+    int32_t *elements = (int32_t *)chars.ptr;
+    int32_t num_chars = (int32_t)snprintf(text, sizeof(text), \"%ld\", (long)value);
+    int32_t i;
+    if (num_chars > chars.len) {
+        printf(\"Error: Line %d: Array given to to_string is too short to hold the number\\n\", (int)line);
+        exit(1);
+    }
+    for (i = 0; i < num_chars; i++) {
+        elements[i] = (unsigned char)text[i];
+    }
+    if (num_chars < chars.len) {
+        elements[num_chars] = 0;
+    }
+    return num_chars;
+}
+
+static inline void soup_sleep(int32_t ms) {
+    struct timespec time;
+    if (ms < 0) {
+        ms = 0;
+    }
+    time.tv_sec = ms / 1000;
+    time.tv_nsec = (long)(ms % 1000) * 1000000;
+    fflush(stdout);
+    nanosleep(&time, NULL);
+}
+
+static inline int32_t soup_fopen(soup_array path, soup_array mode) {
+    FILE *stream = fopen((const char *)path.ptr, (const char *)mode.ptr);
+    int32_t file;
+    if (stream == NULL) {
+        return -1;
+    }
+    file = dup(fileno(stream));
+    fclose(stream);
+    return file;
+}
+
+static inline int32_t soup_fread_line(int32_t file, soup_array line) {
+    int32_t *elements = (int32_t *)line.ptr;
+    int32_t stored = 0;
+    int read_anything = 0;
+    unsigned char byte;
+    while (read(file, &byte, 1) == 1) {
+        read_anything = 1;
+        if (byte == '\\n') {
+            break;
+        }
+        if (stored < line.len) {
+            elements[stored++] = byte;
+        }
+    }
+    if (!read_anything) {
+        return -1;
+    }
+    if (stored < line.len) {
+        elements[stored] = 0;
+    }
+    return stored;
+}
+";
+
+// Names that can't be given to a variable or function in C, either because they're keywords, or because they're
+// declared by the headers included above (or are used by the generated code itself)
+const C_RESERVED: [&str; 84] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "main",
+    "printf",
+    "exit",
+    "abs",
+    "abort",
+    "atoi",
+    "free",
+    "malloc",
+    "calloc",
+    "realloc",
+    "rand",
+    "srand",
+    "puts",
+    "putchar",
+    "getchar",
+    "fputs",
+    "stdin",
+    "stdout",
+    "stderr",
+    "size_t",
+    "int32_t",
+    "uint32_t",
+    "uint8_t",
+    "INT32_MIN",
+    "NULL",
+    "EOF",
+    "FILE",
+    "div",
+    "time",
+    "clock",
+    "difftime",
+    "mktime",
+    "gmtime",
+    "localtime",
+    "nanosleep",
+    "fopen",
+    "fclose",
+    "fileno",
+    "dprintf",
+    "read",
+    "write",
+    "close",
+    "dup",
+    "sleep",
+    "pipe",
+    "fork",
+    "link",
+    "unlink",
+    "access",
+    "getpid",
+];
+
+// The most operators of a chain (see get_operator_chain) that are nested inside each other in a single C expression,
+// since C compilers parse nested expressions recursively too. The rest of a longer chain is hoisted into temporaries
+const MAX_C_CHAIN: usize = 100;
+
+// The side effects of evaluating an expression, which decide whether C (which, unlike soup, doesn't evaluate operands
+// and arguments from left to right) could get a different result by evaluating it out of order
+struct Effects {
+    calls: bool,
+    fallible: bool,
+    reads_memory: bool,
+}
+
+impl Effects {
+    // Returns true if evaluating an expression with these effects after one with the given effects (rather than before)
+    // could change the result, or what's printed before the program exits
+    fn conflicts_with(&self, later: &Effects) -> bool {
+        return (self.calls && (later.calls || later.fallible || later.reads_memory))
+            || (later.calls && (self.fallible || self.reads_memory))
+            || (self.fallible && later.fallible);
+    }
+}
+
+// A loop whose body is being generated, and whether anything in it needs a label to get out of it or continue it
+// (since C's own break and continue only work for the innermost loop)
+struct CLoop {
+    name: Option<Arc<str>>,
+    id: usize,
+    // A continue in a repeat loop has to skip ahead to its condition, rather than going back to the start
+    is_repeat: bool,
+    needs_break_label: bool,
+    needs_continue_label: bool,
+}
+
+struct CWriter {
+    text: String,
+    indent: usize,
+    // Statements hoisted out of the expression being generated, to be written out before the statement it's in
+    pending: Vec<String>,
+    num_temps: usize,
+    // The loops we're currently inside of, innermost last, and the number of loops seen so far (to number their labels)
+    loops: Vec<CLoop>,
+    num_loops: usize,
+    globals: HashSet<*const RefCell<Symbol>>,
+    func_names: HashSet<String>,
+    // Whether arithmetic that overflows is a runtime error (--overflow-checks)
+    overflow_checks: bool,
+}
+
+// -----------------------------------------------------------------------------------------
+// C SOURCE
+// -----------------------------------------------------------------------------------------
+
+// Get the (semantically checked) program as portable C source, which any C compiler can build for platforms that none
+// of our native backends support, or to check the output of a native backend against.
+//
+// Each function is translated more or less statement by statement, so the result is readable alongside the soup it
+// came from. Wherever C could evaluate the operands of an expression in a different order than soup does
+// (like "f() + g()"), the earlier operands are hoisted into temporaries first
+pub fn format_c(ast: &AST, overflow_checks: bool) -> String {
+    let mut writer = CWriter {
+        text: String::from(C_PRELUDE),
+        indent: 0,
+        pending: vec![],
+        num_temps: 0,
+        loops: vec![],
+        num_loops: 0,
+        globals: HashSet::new(),
+        func_names: HashSet::new(),
+        overflow_checks: overflow_checks,
+    };
+
+    for child in ast.get_root().children() {
+        if child.node_type == "globVarDecl" {
+            writer.globals.insert(Rc::as_ptr(&child.get_sym()));
+        } else {
+            writer.func_names.insert(child.get_func_name());
+        }
+    }
+
+    // Global variables
+    writer.text.push('\n');
+    for child in ast.get_root().children() {
+        if child.node_type == "globVarDecl" {
+            let sym = child.get_sym();
+            let name = writer.get_var_name(&sym.borrow().name);
+
+            if sym.borrow().is_array_storage() {
+                let num_elements = sym.borrow().get_num_elements();
+                writer.write(&format!("static int32_t {}[{}];", name, num_elements));
+            } else if child.has_assignment() {
+                let value = get_int_literal(get_global_value(child));
+                writer.write(&format!("static int32_t {} = {};", name, value));
+            } else {
+                writer.write(&format!("static int32_t {};", name));
+            }
+        }
+    }
+
+    // Every function is declared up front, since soup functions can be called before they're defined
+    writer.text.push('\n');
+    for child in ast.get_root().children() {
+        if child.node_type != "globVarDecl" {
+            let signature = writer.get_signature(child);
+            writer.write(&format!("{};", signature));
+        }
+    }
+
+    for child in ast.get_root().children() {
+        if child.node_type != "globVarDecl" {
+            writer.text.push('\n');
+            writer.gen_function(child);
+        }
+    }
+
+    // Calls the compilee's main function, then prints the last newline and exits the program,
+    // with main's exit code if it returns one
+    let main_returns_int = ast.get_root().children().any(|child| {
+        child.node_type != "globVarDecl"
+            && child.get_func_name() == "main"
+            && child.get_sym().borrow().get_returns() != Type::Void
+    });
+    writer.text.push('\n');
+    writer.write("int main(void) {");
+    writer.indent += 1;
+    if main_returns_int {
+        writer.write("int32_t code = soup_main();");
+        writer.write("printf(\"\\n\");");
+        writer.write("return code;");
+    } else {
+        writer.write("soup_main();");
+        writer.write("printf(\"\\n\");");
+        writer.write("return 0;");
+    }
+    writer.indent -= 1;
+    writer.write("}");
+
+    return writer.text;
+}
+
+impl CWriter {
+    // Write out a line of C at the current indentation, after any statements hoisted out of it
+    fn write(&mut self, line: &str) {
+        for pending in std::mem::take(&mut self.pending) {
+            self.text
+                .push_str(&format!("{}{}\n", "    ".repeat(self.indent), pending));
+        }
+        self.text
+            .push_str(&format!("{}{}\n", "    ".repeat(self.indent), line));
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Names
+    // ---------------------------------------------------------------------------------------
+
+    // Get the C name of a variable, which can't clash with a function (since C functions and variables share a scope)
+    fn get_var_name(&self, name: &str) -> String {
+        if C_RESERVED.contains(&name) || name.starts_with("soup_") || self.func_names.contains(name)
+        {
+            return format!("{}_", name);
+        }
+        return String::from(name);
+    }
+
+    // Get the C name of a function, where soup's main function is renamed to make way for C's
+    fn get_func_name(&self, name: &str) -> String {
+        if name == "main" {
+            return String::from("soup_main");
+        } else if C_RESERVED.contains(&name) || name.starts_with("soup_") {
+            return format!("{}_", name);
+        }
+        return String::from(name);
+    }
+
+    // Get the C declaration of a function, such as "static int32_t sum(soup_array values)"
+    fn get_signature(&self, node: NodeRef) -> String {
+        let mut params = vec![];
+        for param in node.child(1).children() {
+            let sym = param.get_sym();
+            params.push(format!(
+                "{} {}",
+                get_c_type(&sym.borrow().type_sig),
+                self.get_var_name(&sym.borrow().name)
+            ));
+        }
+        if params.is_empty() {
+            params.push(String::from("void"));
+        }
+
+        return format!(
+            "static {} {}({})",
+            get_c_type(&node.get_sym().borrow().get_returns()),
+            self.get_func_name(&node.get_func_name()),
+            params.join(", ")
+        );
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Functions and statements
+    // ---------------------------------------------------------------------------------------
+
+    fn gen_function(&mut self, node: NodeRef) {
+        let signature = self.get_signature(node);
+        self.write(&format!("{} {{", signature));
+        self.indent += 1;
+
+        for statement in node.child(3).children() {
+            self.gen_statement(statement);
+        }
+
+        // If we make it to the end of a non-void function, there's no value to return
+        let ends_in_return = match node.child(3).children().last() {
+            None => false,
+            Some(statement) => statement.node_type == "return",
+        };
+        if node.get_sym().borrow().get_returns() != Type::Void && !ends_in_return {
+            self.write(&format!("soup_panic(\"Error: Line {}: A control path reaches the end of a non-void function without returning a value\\n\");", node.get_line_num()));
+        }
+
+        self.indent -= 1;
+        self.write("}");
+    }
+
+    // Generate the statements of a block, inside of the braces that have already been opened
+    fn gen_block(&mut self, node: NodeRef) {
+        self.indent += 1;
+        if node.node_type == "block" {
+            for statement in node.children() {
+                self.gen_statement(statement);
+            }
+        } else {
+            self.gen_statement(node);
+        }
+        self.indent -= 1;
+    }
+
+    fn gen_statement(&mut self, node: NodeRef) {
+        if node.node_type == "block" {
+            self.write("{");
+            self.gen_block(node);
+            self.write("}");
+        } else if node.node_type == "varDecl" {
+            let sym = node.get_sym();
+            let name = self.get_var_name(&sym.borrow().name);
+
+            if sym.borrow().is_array_storage() {
+                let num_elements = sym.borrow().get_num_elements();
+                self.write(&format!("int32_t {}[{}] = {{0}};", name, num_elements));
+            } else if node.has_assignment() {
+                let value = self.gen_expr(node.child(2));
+                self.write(&format!("int32_t {} = {};", name, value));
+            } else {
+                self.write(&format!("int32_t {} = 0;", name));
+            }
+        } else if node.node_type == "="
+            || node.node_type == "+="
+            || node.node_type == "-="
+            || node.node_type == "*="
+            || node.node_type == "/="
+            || node.node_type == "%="
+        {
+            self.gen_assignment(node);
+        } else if node.node_type == "funcCall" {
+            let call = self.gen_func_call(node);
+            self.write(&format!("{};", call));
+        } else if node.node_type == "return" {
+            if node.children.is_empty() {
+                self.write("return;");
+            } else {
+                let value = self.gen_value(node.child(0));
+                self.write(&format!("return {};", value));
+            }
+        } else if node.node_type == "if" || node.node_type == "ifElse" {
+            let cond = self.gen_expr(node.child(0));
+            self.write(&format!("if ({}) {{", strip_parens(&cond)));
+            self.gen_block(node.child(1));
+
+            if node.node_type == "ifElse" {
+                self.write("} else {");
+                self.gen_block(node.child(2));
+            }
+            self.write("}");
+        } else if node.node_type == "while" {
+            let cond = self.gen_expr(node.child(0));
+
+            if self.pending.is_empty() {
+                self.write(&format!("while ({}) {{", strip_parens(&cond)));
+            } else {
+                // Anything hoisted out of the condition has to be evaluated again before every iteration
+                let pending = std::mem::take(&mut self.pending);
+                self.write("while (1) {");
+                self.indent += 1;
+                self.pending = pending;
+                self.write(&format!("if (!{}) {{", wrap_parens(&cond)));
+                self.write("    break;");
+                self.write("}");
+                self.indent -= 1;
+            }
+            self.gen_loop_body(node, node.child(1));
+            self.gen_loop_end();
+        } else if node.node_type == "loop" {
+            self.write("while (1) {");
+            self.gen_loop_body(node, node.child(0));
+            self.gen_loop_end();
+        } else if node.node_type == "repeat" {
+            // Anything hoisted out of the condition is evaluated at the end of the body, just before it's tested
+            self.write("while (1) {");
+            self.gen_loop_body(node, node.child(0));
+            self.indent += 1;
+            let cond = self.gen_expr(node.child(1));
+            self.write(&format!("if ({}) {{", strip_parens(&cond)));
+            self.write("    break;");
+            self.write("}");
+            self.indent -= 1;
+            self.gen_loop_end();
+        } else if node.node_type == "break" || node.node_type == "continue" {
+            self.gen_break_or_continue(node);
+        }
+    }
+
+    // Generate the body of a loop (whose opening brace has already been written),
+    // followed by the label a continue meant for it jumps to, if anything in it needs one
+    fn gen_loop_body(&mut self, node: NodeRef, body: NodeRef) {
+        self.loops.push(CLoop {
+            name: node.attr.clone(),
+            id: self.num_loops,
+            is_repeat: node.node_type == "repeat",
+            needs_break_label: false,
+            needs_continue_label: false,
+        });
+        self.num_loops += 1;
+
+        self.gen_block(body);
+
+        if let Some(current) = self.loops.last() {
+            if current.needs_continue_label {
+                let label = format!("soup_continue_{}:;", current.id);
+                self.indent += 1;
+                self.write(&label);
+                self.indent -= 1;
+            }
+        }
+    }
+
+    // Close the innermost loop, followed by the label a break meant for it jumps to, if anything in it needs one
+    fn gen_loop_end(&mut self) {
+        self.write("}");
+        if let Some(current) = self.loops.pop() {
+            if current.needs_break_label {
+                self.write(&format!("soup_break_{}:;", current.id));
+            }
+        }
+    }
+
+    // Generate a break or continue, which jumps to a label unless it's meant for the innermost loop
+    // (and isn't a continue in a repeat loop)
+    fn gen_break_or_continue(&mut self, node: NodeRef) {
+        let num_loops = self.loops.len();
+        let index = self
+            .loops
+            .iter()
+            .rposition(|target| node.attr.is_none() || target.name == node.attr)
+            .unwrap_or(num_loops - 1);
+        let target = &mut self.loops[index];
+        let innermost = index + 1 == num_loops;
+
+        if node.node_type == "break" {
+            if innermost {
+                self.write("break;");
+            } else {
+                target.needs_break_label = true;
+                let label = format!("goto soup_break_{};", target.id);
+                self.write(&label);
+            }
+        } else if innermost && !target.is_repeat {
+            self.write("continue;");
+        } else {
+            target.needs_continue_label = true;
+            let label = format!("goto soup_continue_{};", target.id);
+            self.write(&label);
+        }
+    }
+
+    fn gen_assignment(&mut self, node: NodeRef) {
+        let lhs = node.child(0);
+        let rhs = node.child(1);
+
+        if lhs.node_type != "index" {
+            let name = self.get_var_name(&lhs.get_sym().borrow().name);
+
+            if node.node_type == "=" {
+                let value = self.gen_expr(rhs);
+                self.write(&format!("{} = {};", name, value));
+            } else {
+                // The current value is read before the right hand side is evaluated
+                let operands = self.gen_operands(&[lhs, rhs]);
+                let value = self.gen_binop(node, &operands[0], &operands[1]);
+                self.write(&format!("{} = {};", name, value));
+            }
+            return;
+        }
+
+        // An array element's address is worked out (and bounds checked) before the right hand side is evaluated
+        let rhs_effects = self.get_effects(rhs);
+        let mut addr = self.gen_index_addr(lhs);
+        if rhs_effects.calls || rhs_effects.fallible || node.node_type != "=" {
+            let temp = self.new_temp();
+            self.pending.push(format!("int32_t *{} = {};", temp, addr));
+            addr = temp;
+        }
+
+        if node.node_type == "=" {
+            let value = self.gen_expr(rhs);
+            self.write(&format!("*{} = {};", addr, value));
+        } else {
+            // The current value is read before the right hand side is evaluated, which could change it
+            let mut current = format!("*{}", addr);
+            if rhs_effects.calls {
+                let temp = self.new_temp();
+                self.pending
+                    .push(format!("int32_t {} = {};", temp, current));
+                current = temp;
+            }
+
+            let value = self.gen_expr(rhs);
+            let value = self.gen_binop(node, &current, &value);
+            self.write(&format!("*{} = {};", addr, value));
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------
+    // Expressions
+    // ---------------------------------------------------------------------------------------
+
+    // Generate an expression of any type, which is a soup_array for an array or string
+    fn gen_value(&mut self, node: NodeRef) -> String {
+        let type_sig = node.get_type();
+        if is_array_type(&type_sig) || type_sig == Type::String {
+            return self.gen_array(node);
+        }
+        return self.gen_expr(node);
+    }
+
+    // Generate an int or bool expression
+    fn gen_expr(&mut self, node: NodeRef) -> String {
+        let chain = node.get_operator_chain();
+        if !chain.is_empty() {
+            return self.gen_chain(&chain);
+        } else if is_binary(node) {
+            let operands = self.gen_operands(&[node.child(0), node.child(1)]);
+            return self.gen_binop(node, &operands[0], &operands[1]);
+        } else if node.node_type == "u-" {
+            // Negative numbers are written as they are, rather than as a negation
+            let child = node.child(0);
+            if child.node_type == "number" {
+                return get_int_literal(get_literal_value(child).wrapping_neg());
+            }
+            if self.overflow_checks {
+                return format!(
+                    "soup_checked(-(int64_t){}, {})",
+                    self.gen_expr(child),
+                    node.get_line_num()
+                );
+            }
+            return format!("soup_neg({})", self.gen_expr(child));
+        } else if node.node_type == "cast" {
+            // A bool is already 0 or 1, but an int has to be compared against zero to get a bool
+            let value = self.gen_expr(node.child(0));
+            if node.get_type() == Type::Bool && node.child(0).get_type() != Type::Bool {
+                return format!("({} != 0)", wrap_parens(&value));
+            }
+            return value;
+        } else if is_unary(node) {
+            return format!("!{}", wrap_parens(&self.gen_expr(node.child(0))));
+        } else if node.node_type == "id" {
+            return self.get_var_name(&node.get_sym().borrow().name);
+        } else if node.node_type == "index" {
+            return format!("*{}", self.gen_index_addr(node));
+        } else if node.node_type == "funcCall" {
+            return self.gen_func_call(node);
+        }
+
+        // Otherwise, we have a literal (a number, true or false)
+        return get_int_literal(get_literal_value(node));
+    }
+
+    // Generate a chain of binary operators (see get_operator_chain) in a loop, from its leftmost operand up, where
+    // the left hand side of each operator is hoisted into a temporary if its right hand side could change it
+    // (just like the operands from gen_operands), or if the chain has gotten too long for a single C expression
+    fn gen_chain(&mut self, chain: &[NodeRef]) -> String {
+        let last = chain[chain.len() - 1];
+        let mut lhs_effects = self.get_effects(last.child(0));
+        let mut value = self.gen_value(last.child(0));
+
+        for (i, operator) in chain.iter().rev().enumerate() {
+            if i > 0 && i % MAX_C_CHAIN == 0 {
+                value = self.hoist_operand(operator.child(0), &value);
+            }
+
+            let rhs = operator.child(1);
+            let rhs_effects = self.get_effects(rhs);
+
+            if operator.node_type == "&&" || operator.node_type == "||" {
+                value = self.gen_short_circuit(*operator, value);
+            } else {
+                if lhs_effects.conflicts_with(&rhs_effects) {
+                    value = self.hoist_operand(operator.child(0), &value);
+                }
+                let rhs_value = self.gen_value(rhs);
+                value = self.gen_binop(*operator, &value, &rhs_value);
+            }
+
+            // Everything generated so far is the left hand side of the next operator
+            lhs_effects = merge_effects(
+                merge_effects(lhs_effects, rhs_effects),
+                self.get_own_effects(*operator),
+            );
+        }
+
+        return value;
+    }
+
+    // Generate a logical and/or (given its left hand side),
+    // which only evaluates its right hand side if the left hand side doesn't decide the result
+    fn gen_short_circuit(&mut self, node: NodeRef, lhs: String) -> String {
+        let outer = std::mem::take(&mut self.pending);
+        let rhs = self.gen_expr(node.child(1));
+        let inner = std::mem::replace(&mut self.pending, outer);
+
+        if inner.is_empty() {
+            return format!("({} {} {})", lhs, node.node_type, rhs);
+        }
+
+        // Anything hoisted out of the right hand side can only be evaluated if the left hand side doesn't decide it
+        let temp = self.new_temp();
+        self.pending.push(format!("int32_t {} = {};", temp, lhs));
+        if node.node_type == "&&" {
+            self.pending.push(format!("if ({}) {{", temp));
+        } else {
+            self.pending.push(format!("if (!{}) {{", temp));
+        }
+        for statement in inner {
+            self.pending.push(format!("    {}", statement));
+        }
+        self.pending
+            .push(format!("    {} = {};", temp, strip_parens(&rhs)));
+        self.pending.push(String::from("}"));
+        return temp;
+    }
+
+    // Generate an expression of array (or string) type, as a soup_array
+    fn gen_array(&mut self, node: NodeRef) -> String {
+        if node.node_type == "funcCall" {
+            return self.gen_func_call(node);
+        } else if node.node_type == "index" {
+            // A row of a multi-dimensional array starts at the address of the element,
+            // and its length is the next dimension of the array
+            let operands = self.gen_operands(&[node.child(0), node.child(1)]);
+            let row_size = get_array_element_size(node.child(0)) / 4;
+            return format!(
+                "soup_row({}, {}, {}, {}, {})",
+                operands[0],
+                operands[1],
+                row_size,
+                get_expr_array_dims(node)[0],
+                node.get_line_num()
+            );
+        } else if node.node_type == "string" {
+            let bytes = convert_string_literal(node).unwrap_or_default();
+            return format!(
+                "(soup_array){{\"{}\", {}}}",
+                escape_c_string(&bytes),
+                bytes.len()
+            );
+        }
+
+        // Otherwise, we have an identifier, which is either an array parameter (which is already a soup_array),
+        // or an array stored in a local or global variable
+        let sym = node.get_sym();
+        let name = self.get_var_name(&sym.borrow().name);
+        if sym.borrow().is_array_storage() {
+            return format!("(soup_array){{{}, {}}}", name, sym.borrow().array_dims[0]);
+        }
+        return name;
+    }
+
+    // Generate the address of an array element (from an index node), after checking the index is in bounds
+    fn gen_index_addr(&mut self, node: NodeRef) -> String {
+        let operands = self.gen_operands(&[node.child(0), node.child(1)]);
+        let func = if node.child(0).get_type() == Type::String {
+            "soup_char"
+        } else {
+            "soup_int"
+        };
+        return format!(
+            "{}({}, {}, {})",
+            func,
+            operands[0],
+            operands[1],
+            node.get_line_num()
+        );
+    }
+
+    fn gen_func_call(&mut self, node: NodeRef) -> String {
+        let func = node.get_func_name();
+        let args: Vec<NodeRef> = node.child(1).children().map(|arg| arg.child(0)).collect();
+
+        if func == "printf" {
+            let format = escape_c_string(&convert_format_string(node).unwrap_or_default());
+            let mut values = vec![format!("\"{}\"", format)];
+            values.extend(self.gen_operands(&args[1..]));
+            return format!("printf({})", values.join(", "));
+        } else if func == "len" {
+            // The length of an array stored in a variable (or of a string literal) is known up front
+            let array = args[0];
+            if array.node_type == "string" {
+                return convert_string_literal(array)
+                    .unwrap_or_default()
+                    .len()
+                    .to_string();
+            } else if array.node_type == "id" && array.get_sym().borrow().is_array_storage() {
+                return array.get_sym().borrow().array_dims[0].to_string();
+            }
+            return format!("{}.len", self.gen_array(array));
+        } else if func == "parse_int" {
+            // The characters of a string are bytes, and those of an int array are ints
+            let size = get_array_element_size(args[0]);
+            return format!(
+                "soup_parse_int({}, {}, {})",
+                self.gen_operands(&args)[0],
+                size,
+                node.get_line_num()
+            );
+        } else if func == "to_string" {
+            let values = self.gen_operands(&args);
+            return format!(
+                "soup_to_string({}, {}, {})",
+                values[0],
+                values[1],
+                node.get_line_num()
+            );
+        } else if func == "fwrite_line" {
+            // The file is evaluated first, but the format string goes after it
+            let format = escape_c_string(&convert_format_string(node).unwrap_or_default());
+            let mut operands = vec![args[0]];
+            operands.extend_from_slice(&args[2..]);
+            let mut values = self.gen_operands(&operands);
+            values.insert(1, format!("\"{}\"", format));
+            return format!("dprintf({})", values.join(", "));
+        } else if func == "exit" {
+            return format!("exit({})", self.gen_operands(&args)[0]);
+        } else if func == "fclose" {
+            return format!("close({})", self.gen_operands(&args)[0]);
+        } else if matches!(&*func, "sleep" | "fopen" | "fread_line") {
+            // Everything else in the runtime library is defined along with the rest of the runtime support
+            let values = self.gen_operands(&args);
+            return format!("soup_{}({})", func, values.join(", "));
+        }
+
+        let values = self.gen_operands(&args);
+        return format!("{}({})", self.get_func_name(&func), values.join(", "));
+    }
+
+    // Generate a list of operands (or arguments) that soup evaluates from left to right, hoisting any operand that
+    // could give a different result if it were evaluated after a later one into a temporary
+    fn gen_operands(&mut self, nodes: &[NodeRef]) -> Vec<String> {
+        let effects: Vec<Effects> = nodes.iter().map(|node| self.get_effects(*node)).collect();
+        let mut operands = vec![];
+
+        for i in 0..nodes.len() {
+            let mut operand = self.gen_value(nodes[i]);
+
+            if effects[i + 1..]
+                .iter()
+                .any(|later| effects[i].conflicts_with(later))
+            {
+                operand = self.hoist_operand(nodes[i], &operand);
+            }
+            operands.push(operand);
+        }
+
+        return operands;
+    }
+
+    // Evaluate an operand (generated from the given node) into a temporary ahead of the statement it's part of,
+    // giving back the temporary
+    fn hoist_operand(&mut self, node: NodeRef, operand: &str) -> String {
+        let type_sig = node.get_type();
+        let c_type = if is_array_type(&type_sig) || type_sig == Type::String {
+            "soup_array"
+        } else {
+            "int32_t"
+        };
+        let temp = self.new_temp();
+        self.pending
+            .push(format!("{} {} = {};", c_type, temp, strip_parens(operand)));
+        return temp;
+    }
+
+    // Get the side effects of evaluating an expression, which are those of every part of it
+    // (walked without recursing, since a long chain of binary operators makes a tree far deeper than the stack allows)
+    fn get_effects(&self, node: NodeRef) -> Effects {
+        let mut effects = Effects {
+            calls: false,
+            fallible: false,
+            reads_memory: false,
+        };
+
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            effects = merge_effects(effects, self.get_own_effects(node));
+
+            // The name of a function isn't evaluated, only its arguments are
+            if node.node_type == "funcCall" {
+                stack.extend(node.child(1).children().map(|arg| arg.child(0)));
+            } else {
+                stack.extend(node.children());
+            }
+        }
+        return effects;
+    }
+
+    // Get the side effects of evaluating a single part of an expression, leaving out those of its operands
+    fn get_own_effects(&self, node: NodeRef) -> Effects {
+        let mut effects = Effects {
+            calls: false,
+            fallible: false,
+            reads_memory: false,
+        };
+
+        if node.node_type == "funcCall" {
+            effects.calls = node.get_func_name() != "len";
+        } else if node.node_type == "/" || node.node_type == "%" {
+            // Dividing by a (non-zero) number can't fail
+            let divisor = node.child(1);
+            effects.fallible = divisor.node_type != "number" || get_literal_value(divisor) == 0;
+        } else if node.node_type == "+" || node.node_type == "-" || node.node_type == "*" {
+            effects.fallible = self.overflow_checks;
+        } else if node.node_type == "u-" {
+            // Negating a number gives another number
+            effects.fallible = self.overflow_checks && node.child(0).node_type != "number";
+        } else if node.node_type == "index" {
+            // Neither can indexing into an array variable with a number that's in bounds (though any function call
+            // could change the element)
+            let dims = get_expr_array_dims(node.child(0));
+            let index = node.child(1);
+            effects.fallible = node.child(0).node_type != "id"
+                || dims.is_empty()
+                || index.node_type != "number"
+                || get_literal_value(index) < 0
+                || get_literal_value(index) >= dims[0];
+            effects.reads_memory = true;
+        } else if node.node_type == "id" {
+            // The address of a global array never changes, so only reading a global int or bool counts
+            let sym = node.get_sym();
+            effects.reads_memory =
+                self.globals.contains(&Rc::as_ptr(&sym)) && !sym.borrow().is_array_storage();
+        }
+        return effects;
+    }
+
+    // Combine two operands with the binary (or compound assignment) operator of the given node
+    fn gen_binop(&self, node: NodeRef, lhs: &str, rhs: &str) -> String {
+        // Checked arithmetic is done with 64 bit ints, where the result always fits
+        if self.overflow_checks {
+            let op = node.node_type.trim_end_matches('=');
+            if op == "+" || op == "-" || op == "*" {
+                return format!(
+                    "soup_checked((int64_t){} {} {}, {})",
+                    lhs,
+                    op,
+                    rhs,
+                    node.get_line_num()
+                );
+            }
+            if op == "/" {
+                return format!(
+                    "soup_checked_div({}, {}, {})",
+                    lhs,
+                    strip_parens(rhs),
+                    node.get_line_num()
+                );
+            }
+        }
+
+        return match node.node_type.as_str() {
+            "+" | "+=" => format!("soup_add({}, {})", lhs, strip_parens(rhs)),
+            "-" | "-=" => format!("soup_sub({}, {})", lhs, strip_parens(rhs)),
+            "*" | "*=" => format!("soup_mul({}, {})", lhs, strip_parens(rhs)),
+            "/" | "/=" => format!(
+                "soup_div({}, {}, {})",
+                lhs,
+                strip_parens(rhs),
+                node.get_line_num()
+            ),
+            "%" | "%=" => format!(
+                "soup_rem({}, {}, {})",
+                lhs,
+                strip_parens(rhs),
+                node.get_line_num()
+            ),
+            // Comparisons (which are the only binary operators left) give 1 or 0 in C too
+            op => format!("({} {} {})", lhs, op, rhs),
+        };
+    }
+
+    fn new_temp(&mut self) -> String {
+        self.num_temps += 1;
+        return format!("soup_t{}", self.num_temps);
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// HELPERS
+// -----------------------------------------------------------------------------------------
+
+fn merge_effects(a: Effects, b: Effects) -> Effects {
+    return Effects {
+        calls: a.calls || b.calls,
+        fallible: a.fallible || b.fallible,
+        reads_memory: a.reads_memory || b.reads_memory,
+    };
+}
+
+// Get the C type of a soup type, where ints and bools are both int32_t
+fn get_c_type(type_sig: &Type) -> &'static str {
+    if *type_sig == Type::Void {
+        return "void";
+    } else if is_array_type(type_sig) || *type_sig == Type::String {
+        return "soup_array";
+    }
+    return "int32_t";
+}
+
+// Get an int as a C literal, where the most negative int has to be spelled out
+// (since "-2147483648" is the negation of a number too large for an int)
+fn get_int_literal(value: i32) -> String {
+    if value == i32::MIN {
+        return String::from("INT32_MIN");
+    }
+    return value.to_string();
+}
+
+// Remove the parentheses around an expression, if the whole expression is wrapped in them
+fn strip_parens(expr: &str) -> &str {
+    if expr.starts_with('(') && expr.ends_with(')') {
+        let mut depth = 0;
+        for (i, char) in expr.char_indices() {
+            if char == '(' {
+                depth += 1;
+            } else if char == ')' {
+                depth -= 1;
+                if depth == 0 {
+                    if i == expr.len() - 1 {
+                        return &expr[1..expr.len() - 1];
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    return expr;
+}
+
+// Wrap an expression in parentheses, unless it's already a single operand
+fn wrap_parens(expr: &str) -> String {
+    if strip_parens(expr) != expr
+        || expr
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+    {
+        return String::from(expr);
+    }
+    return format!("({})", expr);
+}
+
+// Escape the bytes of a string so they can be written out inside of a C string literal
+fn escape_c_string(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for byte in bytes {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            // A question mark is escaped so it can't form a trigraph
+            b'?' => escaped.push_str("\\?"),
+            b' '..=b'~' => escaped.push(*byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    return escaped;
+}
@@ -1,148 +1,248 @@
-use std::fs::File;
-use std::io::prelude::*;
-
-use crate::parser::parser_data::ASTNode;
-use crate::throw_error;
+use crate::ir::ir_data::{Coverage, IrFunction, IrProgram, IrType};
 
+// Generated assembly is held onto (as structured lines) until it's all been generated,
+// so that it can be cleaned up (by the target's peephole optimizer) before it's turned into text
 pub struct ASMWriter {
-    pub file: File,
-    pub label: String,
-    pub regs: Vec<i32>,
-    pub current_func: Option<ASTNode>,
-    pub while_labels: Vec<String>,
+    pub lines: Vec<AsmLine>,
+    // The source file (as an index into the program's files) of the function being generated
+    pub source_file: usize,
+    // Whether each line of soup is marked with a .loc directive, for debug info (-g)
+    pub debug_info: bool,
+    // The lines of each source file, if each line of soup is written out as a comment above the assembly
+    // generated for it (--asm-comments), and the characters that start a comment on the target
+    pub source_lines: Option<Vec<Vec<String>>>,
+    pub comment_prefix: &'static str,
+    // Whether temporaries are given registers by coloring the interference graph (at -O2 and above),
+    // rather than by linear scan
+    pub graph_coloring: bool,
+    // Where the program keeps count of the lines it runs, if it's been instrumented for coverage
+    // (in which case it writes them all out, by calling the coverage routine, whenever it exits)
+    pub coverage: Option<Coverage>,
 }
 
-impl ASMWriter {
-    pub fn new(filename: &str) -> ASMWriter {
-        // Open up the file with the given filename
-        let asm_file = match File::create(filename) {
-            Ok(asm_file) => asm_file,
-            Err(_) => panic!("Uh Oh, I can't make an assembly file. Oh well, goodbye!"),
-        };
-
-        // Initialize label
-        let label = String::from("L0");
-
-        // Initialize the initial state of the registers
-        //          <---- r9 - r15 --->              <-------- r19 - r28 ------->
-        let regs = vec![
-            0, 0, 0, 0, 0, 0, 0, -1, -1, -1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ];
-        // 0 = unallocated, 1 = allocated, -1 = not allocatable
+impl Default for ASMWriter {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
 
+impl ASMWriter {
+    pub fn new() -> ASMWriter {
         return ASMWriter {
-            file: asm_file,
-            label: label,
-            regs: regs,
-            current_func: None,
-            while_labels: vec![],
+            lines: vec![],
+            source_file: 0,
+            debug_info: false,
+            source_lines: None,
+            comment_prefix: "//",
+            graph_coloring: false,
+            coverage: None,
         };
     }
 
-    // Write a line to the assembly file
+    // Add a line (or several, separated by newlines) to the assembly
     pub fn write(&mut self, line: &str) {
-        // Attempt to write the line (with a bonus newline at the end), and panic if unable to
-        match write!(self.file, "{}\n", line) {
-            Ok(()) => {}
-            Err(_) => panic!("Unable to write to ASM file! Quitting now, sorry!"),
-        };
+        for line in line.split('\n') {
+            self.lines.push(AsmLine::parse(line));
+        }
     }
 
-    // Update the current label
-    pub fn new_label(&mut self) -> String {
-        // Get number of current label
-        let mut label_num = self.label[1..].to_string().parse::<u64>().unwrap();
+    // Add a line (or several) that the peephole optimizer shouldn't try to understand (like WebAssembly text),
+    // which is written out exactly as it was given
+    pub fn write_raw(&mut self, line: &str) {
+        for line in line.split('\n') {
+            self.lines.push(AsmLine::Other(String::from(line)));
+        }
+    }
 
-        // Increment label number by one
-        label_num += 1;
+    // Get the text of all of the assembly, with every line (including the last) ending in a newline
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            text.push_str(&line.to_text());
+            text.push('\n');
+        }
+        return text;
+    }
+}
 
-        // Update label
-        self.label = format!("L{}", label_num);
+// -----------------------------------------------------------------
+// CODE GENERATOR
+// -----------------------------------------------------------------
 
-        // Return the label, just for fun
-        return self.label.clone();
-    }
+// Everything needed to generate assembly for a target, called on by the code generator in the order
+// the parts of the assembly file are written in
+pub trait CodeGenerator {
+    // Write out the globals and strings used by the program, which come before any code
+    fn gen_globals(&self, writer: &mut ASMWriter, program: &IrProgram);
 
-    pub fn alloc_reg(&mut self) -> i32 {
-        // Usable registers are 9 - 15 (not saved), 19 - 28 (saved)
-        for (i, reg) in self.regs.iter_mut().enumerate() {
-            // If we find an unallocated register,
-            if *reg == 0 {
-                // Mark it as allocated
-                *reg = 1;
-
-                // Since the register at self.regs[0] is r9,
-                // the actual register number is i + 9
-                return (i + 9) as i32;
-            }
-        }
+    // Write out the assembly main routine (not to be confused with the compilee's main function),
+    // which calls the compilee's main function and exits the program, with its exit code if it returns one
+    fn gen_asm_main(&self, writer: &mut ASMWriter, main_returns_int: bool);
 
-        // If we've made it through the whole list and run out of unallocated registers completely, throw an error
-        throw_error("Calculation too compilated, ran out of registers");
-        return 0;
-    }
+    fn gen_function(&self, writer: &mut ASMWriter, func: &IrFunction);
+
+    // Get the name of the given callee-saved register (numbered as they are in a frame's temp_homes), as it would be
+    // written for a temporary of the given type, for dumps of the stack frame (--dump-frame)
+    fn get_saved_reg_name(&self, reg: usize, temp_type: IrType) -> String;
+
+    // Write out the routine a program instrumented for coverage calls right before it exits, which writes its counts
+    // out to a file (and leaves the exit code in the first argument passing register alone)
+    fn gen_coverage_routine(&self, writer: &mut ASMWriter, coverage: &Coverage);
 
-    pub fn free_reg(&mut self, to_free: i32) {
-        // Usable registers are 9 - 15 (not saved), 19 - 28 (saved)
+    // Write out the functions in the runtime library (leaving out any the program never calls)
+    fn gen_runtime_lib(&self, writer: &mut ASMWriter, program: &IrProgram);
 
-        // Since the register at self.regs[0] is r9,
-        // the index of the register we want to free is to_free - 9
-        self.regs[(to_free - 9) as usize] = 0;
+    // Clean up wasteful instructions in the generated assembly, which is only done at -O1 and above
+    fn optimize_asm(&self, lines: Vec<AsmLine>) -> Vec<AsmLine> {
+        return lines;
     }
+}
 
-    pub fn get_allocated_caller_saved_registers(&self) -> Vec<usize> {
-        let mut allocated_caller_saved_registers = Vec::new();
+// -----------------------------------------------------------------
+// ASSEMBLY LINES
+// -----------------------------------------------------------------
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AsmLine {
+    // A label on a line of its own, like "_soup_main:"
+    Label(String),
+    Instr(AsmInstr),
+    // A comment on a line of its own, which the peephole optimizer looks straight past
+    Comment(String),
+    // Anything else (directives, data, comments and blank lines), which is written out exactly as it was given
+    Other(String),
+}
 
-        // Loop through caller saved registers (self.regs[0] - self.regs[6])
-        for i in 0..7 {
-            if self.regs[i] == 1 {
-                allocated_caller_saved_registers.push(i + 9);
-            }
-        }
+// A single instruction, like "ldr w9, [sp, 8]  // comment"
+#[derive(Clone, PartialEq, Debug)]
+pub struct AsmInstr {
+    pub op: String,
+    pub operands: Vec<String>,
+    pub comment: Option<String>,
+}
 
-        return allocated_caller_saved_registers;
+impl AsmInstr {
+    pub fn new(op: &str, operands: Vec<&str>) -> AsmInstr {
+        return AsmInstr {
+            op: String::from(op),
+            operands: operands
+                .iter()
+                .map(|operand| String::from(*operand))
+                .collect(),
+            comment: None,
+        };
     }
+}
 
-    pub fn get_allocated_callee_saved_registers(&self) -> Vec<usize> {
-        let mut allocated_callee_saved_registers = Vec::new();
-
-        // Loop through callee saved registers (self.regs[10] - self.regs[19])
-        for i in 10..self.regs.len() {
-            if self.regs[i] == 1 {
-                allocated_callee_saved_registers.push(i + 9);
+impl AsmLine {
+    // Work out what kind of line a line of generated assembly is
+    pub fn parse(line: &str) -> AsmLine {
+        // Labels (and data, like "LG0: .word 1") start at the beginning of the line
+        if !line.starts_with(' ') {
+            if line.ends_with(':') && !line.contains(' ') {
+                return AsmLine::Label(String::from(&line[..line.len() - 1]));
             }
+            return AsmLine::Other(String::from(line));
         }
 
-        return allocated_callee_saved_registers;
-    }
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('.') || trimmed.starts_with("//") {
+            return AsmLine::Other(String::from(line));
+        }
 
-    pub fn enter_func(&mut self, func: &mut ASTNode) {
-        self.current_func = Some(func.clone());
-    }
+        // Split off any comment at the end of the instruction
+        let (code, comment) = match trimmed.split_once("//") {
+            None => (trimmed, None),
+            Some((code, comment)) => (code.trim(), Some(String::from(comment.trim()))),
+        };
+
+        let (op, operands) = match code.split_once(' ') {
+            None => (code, vec![]),
+            Some((op, operands)) => (op, split_operands(operands.trim())),
+        };
 
-    pub fn exit_func(&mut self) {
-        self.current_func = None;
+        return AsmLine::Instr(AsmInstr {
+            op: String::from(op),
+            operands: operands,
+            comment: comment,
+        });
     }
 
-    pub fn get_current_func(&self) -> ASTNode {
-        match &self.current_func {
-            None => {
-                return ASTNode::new("", None, None);
-            } // Will never happen, indicates an error on my part
-            Some(func) => {
-                return func.clone();
+    // Get the line as it should appear in the assembly file
+    pub fn to_text(&self) -> String {
+        return match self {
+            AsmLine::Label(label) => format!("{}:", label),
+            AsmLine::Instr(instr) => {
+                let mut text = if instr.operands.is_empty() {
+                    format!("        {}", instr.op)
+                } else {
+                    format!("        {:<8}{}", instr.op, instr.operands.join(", "))
+                };
+                if let Some(comment) = &instr.comment {
+                    text.push_str(&format!("  // {}", comment));
+                }
+                text
             }
-        }
+            AsmLine::Comment(text) => text.clone(),
+            AsmLine::Other(text) => text.clone(),
+        };
     }
+}
 
-    pub fn get_current_func_name(&self) -> String {
-        match &self.current_func {
-            None => {
-                return String::from("FUNC");
-            } // Will never happen, indicates an error on my part
-            Some(func) => {
-                return func.get_sym().borrow().name.clone();
-            }
+// Split the operands of an instruction on their commas, other than the ones inside of an address like "[sp, 8]"
+fn split_operands(operands: &str) -> Vec<String> {
+    let mut split = vec![];
+    let mut current = String::from("");
+    let mut depth = 0;
+
+    for c in operands.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+
+        if c == ',' && depth == 0 {
+            split.push(String::from(current.trim()));
+            current.clear();
+        } else {
+            current.push(c);
         }
     }
+    split.push(String::from(current.trim()));
+
+    return split;
+}
+
+// -----------------------------------------------------------------
+// STACK FRAME
+// -----------------------------------------------------------------
+
+// Where everything a function keeps on the stack lives, as offsets from the stack pointer
+// (once the function has allocated its stack frame):
+//
+// sp + size + 16 ->  any parameters that didn't fit in the argument passing registers
+// sp + size      ->  the caller's frame pointer and our return address
+//                    the caller's values of any callee-saved registers we keep temporaries in
+//                    stack slots (local arrays)
+//                    temporaries that didn't get a register (or a constant)
+// sp             ->  space for passing arguments on the stack to any functions we call
+pub struct Frame {
+    pub temp_homes: Vec<TempHome>,
+    pub slot_offsets: Vec<i32>,
+    // The offset each callee-saved register the function uses is saved at (numbered as they are in temp_homes)
+    pub saved_regs: Vec<(usize, i32)>,
+    pub size: i32,
+}
+
+// Where a temporary is kept in between the instructions that use it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TempHome {
+    // One of the target's callee-saved registers (as an index into the code generator's list of them)
+    Reg(usize),
+    // The given offset from the stack pointer
+    Stack(i32),
+    // Nowhere, since the temporary only ever holds the given (small) constant, which is given straight to
+    // any instruction that can take it as an immediate operand, or put in a scratch register for any that can't
+    Imm(i32),
 }
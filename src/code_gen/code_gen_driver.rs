@@ -1,29 +1,158 @@
-use crate::parser::parser_data::ASTNode;
+use std::fs;
+use std::mem;
 
+use crate::ir::ir_data::IrProgram;
+use crate::passes::passes_trace::{log_event, log_span, LogLevel};
+
+use crate::code_gen::code_gen_arm64::Arm64Generator;
 use crate::code_gen::code_gen_data::*;
-use crate::code_gen::code_gen_generators::*;
-use crate::code_gen::code_gen_traversals::*;
+use crate::code_gen::code_gen_riscv64::Riscv64Generator;
+use crate::code_gen::code_gen_target::{Arch, ObjectFormat, TargetInfo};
+use crate::code_gen::code_gen_utils::{escape_string, format_frame, get_frame};
+use crate::code_gen::code_gen_wasm::WasmGenerator;
+use crate::code_gen::code_gen_x86_64::X86Generator;
 
 // -----------------------------------------------------------------
 // CODE GENERATOR
 // -----------------------------------------------------------------
 
-pub fn code_gen(asm_filename: &str, ast: &mut ASTNode) {
+// Generate the assembly for a program, giving back its text
+pub fn code_gen(
+    program: &IrProgram,
+    opt_level: u32,
+    target: &'static TargetInfo,
+    debug_info: bool,
+    asm_comments: bool,
+) -> String {
+    let generator = get_code_generator(target);
+
     // Initialize the ASMWriter
-    let mut writer = ASMWriter::new(asm_filename);
+    let mut writer = ASMWriter::new();
+    writer.comment_prefix = target.comment_prefix;
+
+    // Coloring the interference graph takes longer than a linear scan, but gets rid of copies between temporaries,
+    // so it's left for -O2 and above
+    writer.graph_coloring = opt_level >= 2;
+    writer.coverage = program.coverage.clone();
+
+    // Annotating the assembly with each line of soup means reading the source files back in
+    // (any that can't be read any more just go without)
+    if asm_comments {
+        let source_lines = program
+            .files
+            .iter()
+            .map(|file| match fs::read_to_string(file) {
+                Ok(source) => source.lines().map(String::from).collect(),
+                Err(_) => vec![],
+            })
+            .collect();
+        writer.source_lines = Some(source_lines);
+    }
 
-    writer.write(".data");
-    // First, before we write any code, find all the strings and add them to the top of the file
-    gen_strings(&mut writer, ast);
-    writer.write(".align 2");
-    writer.write(".text");
+    // Debug info maps each instruction back to the line of soup it came from (with a .loc directive, numbering
+    // each source file with a .file directive), which the assembler turns into a DWARF line table.
+    // WebAssembly text has no such directives, so it never gets any
+    writer.debug_info = debug_info && target.object_format != ObjectFormat::Wasm;
+    if writer.debug_info {
+        for (i, file) in program.files.iter().enumerate() {
+            writer.write(&format!(
+                "        .file {} \"{}\"",
+                i + 1,
+                escape_string(file.as_bytes())
+            ));
+        }
+    }
 
-    // Generate the assembly file main routine (not to be confused with the compilee's main function)
-    gen_asm_main(&mut writer);
+    // First, before we write any code, write out all the globals and strings at the top of the file
+    generator.gen_globals(&mut writer, program);
 
-    // Begin traversing the AST and generating code
-    traverse_prune(&mut writer, ast);
+    // Generate the assembly file main routine (not to be confused with the compilee's main function),
+    // which needs to know whether the compilee's main function returns an exit code
+    let main_returns_int = match program.get_function("main") {
+        None => false,
+        Some(main_func) => !main_func.returns.is_empty(),
+    };
+    generator.gen_asm_main(&mut writer, main_returns_int);
+
+    // Generate each function
+    for func in &program.functions {
+        writer.source_file = func.file;
+        log_span(
+            || format!("function '{}'", func.name),
+            || {
+                let num_lines = writer.lines.len();
+                generator.gen_function(&mut writer, func);
+                log_event(LogLevel::Debug, || {
+                    format!(
+                        "{} instructions became {} lines of assembly",
+                        func.body.len(),
+                        writer.lines.len() - num_lines
+                    )
+                });
+            },
+        );
+    }
+
+    // Nothing in the runtime library came from a line of soup
+    writer.debug_info = false;
+    writer.source_lines = None;
+
+    if let Some(coverage) = &program.coverage {
+        generator.gen_coverage_routine(&mut writer, coverage);
+    }
 
     // Finally, generate the runtime library
-    gen_runtime_lib(&mut writer);
+    generator.gen_runtime_lib(&mut writer, program);
+
+    // Clean up the generated assembly with the peephole optimizer at -O1 and above, before writing it all out
+    if opt_level > 0 {
+        writer.lines = generator.optimize_asm(mem::take(&mut writer.lines));
+    }
+    return writer.to_text();
+}
+
+// Describe the stack frame of every function in a program (--dump-frame), laid out just as the code generator
+// lays them out at the given optimization level
+pub fn format_frames(program: &IrProgram, opt_level: u32, target: &'static TargetInfo) -> String {
+    let generator = get_code_generator(target);
+
+    let mut text = String::new();
+    for func in &program.functions {
+        let frame = get_frame(func, target, opt_level >= 2);
+        text.push_str(&format_frame(func, &frame, target, generator.as_ref()));
+    }
+    return text;
+}
+
+// Find the function with the largest stack frame (as the code generator lays them out at the given optimization level)
+// and the size of its frame, if the target has stack frames at all
+pub fn get_largest_frame(
+    program: &IrProgram,
+    opt_level: u32,
+    target: &'static TargetInfo,
+) -> Option<(String, i32)> {
+    if target.arch == Arch::Wasm32 {
+        return None;
+    }
+
+    return program
+        .functions
+        .iter()
+        .map(|func| {
+            (
+                func.name.clone(),
+                get_frame(func, target, opt_level >= 2).size,
+            )
+        })
+        .max_by_key(|(_, size)| *size);
+}
+
+// Get the code generator for the given target's architecture, which is told everything else it needs to know about the target
+pub fn get_code_generator(target: &'static TargetInfo) -> Box<dyn CodeGenerator> {
+    return match target.arch {
+        Arch::Arm64 => Box::new(Arm64Generator { target: target }),
+        Arch::X86_64 => Box::new(X86Generator { target: target }),
+        Arch::Riscv64 => Box::new(Riscv64Generator { target: target }),
+        Arch::Wasm32 => Box::new(WasmGenerator),
+    };
 }
@@ -1,468 +1,988 @@
-use crate::parser::parser_data::ASTNode;
-use crate::semantic::semantic_utils::{is_binary, is_unary};
+use std::collections::HashMap;
+
+use crate::ir::ir_data::*;
 
 use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_target::*;
 use crate::code_gen::code_gen_utils::*;
 
-pub fn gen_asm_main(writer: &mut ASMWriter) {
+pub fn gen_asm_main(writer: &mut ASMWriter, target: &TargetInfo, main_returns_int: bool) {
     // Write ASM main routine (not to be confused with the compilee's main function)
-    writer.write("\n        .global _start");
+    gen_entry_label(writer, target);
     writer.write("        .balign 4");
-    writer.write("_start: stp     x29, x30, [sp, -16]!");
+    writer.write(&format!("{}:", target.entry_symbol));
+    writer.write("        stp     x29, x30, [sp, -16]!");
     writer.write("        mov     x29, sp");
 
     // Branch and link to the compilee's main function
-    writer.write("        bl      main1");
-
-    writer.write("end:    ldp     x29, x30, [sp], 16");
-
-    // Exit the program
-    writer.write(".data");
-    writer.write("last_newline: .string \"\\n\"");
-    writer.write(".align 4");
-    writer.write(".text");
-    writer.write("        adrp    x0, last_newline@PAGE");
-    writer.write("        add     x0, x0, last_newline@PAGEOFF");
-    writer.write("        bl      _printf");
-    writer.write("        mov     x0, 0  // Return code 0");
-    writer.write("        mov     x16, 1  // Sys call code to terminate program");
-    writer.write("        svc     0x80  // Make system call");
+    writer.write(&format!(
+        "        bl      {}",
+        get_func_label(target, "main")
+    ));
+
+    // If main returns an int, hold onto it in a callee-saved register so printing the last newline can't trample it
+    if main_returns_int {
+        writer.write("        mov     w19, w0");
+    }
+
+    writer.write("end:");
+    writer.write("        ldp     x29, x30, [sp], 16");
+
+    // Print the last newline and exit the program
+    gen_global_addr(writer, target, "x0", "last_newline");
+    writer.write(&format!("        bl      {}printf", target.symbol_prefix));
+    if main_returns_int {
+        writer.write("        mov     w0, w19  // Return code returned by main");
+    } else {
+        writer.write("        mov     x0, 0  // Return code 0");
+    }
+    gen_exit(writer, target);
 }
 
-pub fn gen_runtime_lib(writer: &mut ASMWriter) {
-    writer.write("\nexit1:");
+pub fn gen_runtime_lib(writer: &mut ASMWriter, target: &TargetInfo, program: &IrProgram) {
+    writer.write(&format!("\n{}:", get_func_label(target, "exit")));
     writer.write("// Return code is passed into exit and is already in x0");
-    writer.write("        mov     x16, 1  // Sys call code to terminate program");
-    writer.write("        svc     0x80  // Make system call");
-}
+    gen_exit(writer, target);
+
+    if program.calls("sleep") {
+        // The number of milliseconds to sleep for is passed in w0, and is split into the seconds and nanoseconds
+        // of a timespec on the stack for nanosleep (after showing everything printed so far),
+        // where a negative number of milliseconds doesn't sleep at all
+        writer.write(&format!("\n{}:", get_func_label(target, "sleep")));
+        writer.write("        stp     x29, x30, [sp, -48]!");
+        writer.write("        mov     x29, sp");
+        writer.write("        str     x19, [sp, 16]");
+        writer.write("        cmp     w0, 0");
+        writer.write("        csel    w19, w0, wzr, gt");
+        writer.write("        mov     x0, 0");
+        writer.write(&format!("        bl      {}fflush", target.symbol_prefix));
+        writer.write("        mov     w9, 1000");
+        writer.write("        udiv    w10, w19, w9");
+        writer.write("        msub    w11, w10, w9, w19");
+        writer.write("        movz    w9, 0x4240");
+        writer.write("        movk    w9, 0xf, lsl 16  // 1000000");
+        writer.write("        mul     w11, w11, w9");
+        writer.write("        stp     x10, x11, [sp, 32]");
+        writer.write("        add     x0, sp, 32");
+        writer.write("        mov     x1, 0");
+        writer.write(&format!(
+            "        bl      {}nanosleep",
+            target.symbol_prefix
+        ));
+        writer.write("        ldr     x19, [sp, 16]");
+        writer.write("        ldp     x29, x30, [sp], 48");
+        writer.write("        ret");
+    }
 
-pub fn gen_expr(writer: &mut ASMWriter, node: &ASTNode) -> i32 {
-    if is_binary(node) {
-        // Generate the expressions on either side of the operator, each returned in a register
-        let lhs = gen_expr(writer, &node.children[0]);
-        let rhs = gen_expr(writer, &node.children[1]);
-        let mut dest = writer.alloc_reg();
-
-        if node.node_type == "=" {
-            writer.free_reg(lhs);
-            writer.free_reg(dest);
-            return rhs;
-        } else if node.node_type == "+" || node.node_type == "+=" {
-            writer.write(&format!("        add     w{}, w{}, w{}", dest, lhs, rhs));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-            return dest;
-        } else if node.node_type == "-" || node.node_type == "-=" {
-            writer.write(&format!("        sub     w{}, w{}, w{}", dest, lhs, rhs));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-            return dest;
-        } else if node.node_type == "*" || node.node_type == "*=" {
-            writer.write(&format!("        mul     w{}, w{}, w{}", dest, lhs, rhs));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-            return dest;
-        } else if node.node_type == "/" || node.node_type == "/=" {
-            gen_division(writer, node, dest, lhs, rhs);
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-            return dest;
-        } else if node.node_type == "%" || node.node_type == "%=" {
-            gen_division(writer, node, dest, lhs, rhs);
-            writer.write(&format!(
-                "        msub    w{}, w{}, w{}, w{}",
-                lhs, rhs, dest, lhs
-            ));
-            writer.free_reg(dest);
-            writer.free_reg(rhs);
-            return lhs;
-        } else if node.node_type == "&&" {
-            // Since these expressions must be short-circuiting, if the left-hand side is false,
-            // then no matter what the right hand side is, the expression will be false, so we don't even need to evaluate it
-            let after_label = writer.new_label();
-            writer.write(&format!("        cmp     w{}, wzr", lhs));
-            writer.write(&format!("        b.eq    {}", after_label));
-            writer.free_reg(dest);
-            dest = lhs;
-
-            // Otherwise, the left hand side is true, so we can evaluate the right hand side
-            writer.write(&format!("        and     w{}, w{}, w{}", dest, lhs, rhs));
-
-            // Write the after label
-            writer.write(&format!("        {}:", after_label));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == "||" {
-            // Since these expressions must be short-circuiting, if the left-hand side is true,
-            // then no matter what the right hand side is, the expression will be true, so we don't even need to evaluate it
-            let after_label = writer.new_label();
-            writer.write(&format!("        cmp     w{}, 1", lhs));
-            writer.write(&format!("        b.eq    {}", after_label));
-            writer.free_reg(dest);
-            dest = lhs;
-
-            // otherwise, the left hand side is false, so we can evaluate the right hand side
-            writer.write(&format!("        orr     w{}, w{}, w{}", dest, lhs, rhs));
-
-            // Write the after label
-            writer.write(&format!("        {}:", after_label));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == "==" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, EQ", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == "!=" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, NE", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == "<" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, LT", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == ">" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, GT", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == "<=" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, LE", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        } else if node.node_type == ">=" {
-            // dest is 1 if lhs = rhs and 0 otherwise
-            writer.write(&format!("        cmp     w{}, w{}", lhs, rhs));
-            writer.write(&format!("        cset    w{}, GE", dest));
-            writer.free_reg(lhs);
-            writer.free_reg(rhs);
-
-            return dest;
-        }
-    } else if is_unary(node) {
-        // Generate the expression on the right side of the operator, returned in a register
-        let rhs = gen_expr(writer, &node.children[0]);
-
-        if node.node_type == "u-" {
-            writer.write(&format!("        neg     w{}, w{}", rhs, rhs));
-            return rhs;
-        } else if node.node_type == "!" {
-            writer.write(&format!("        not     w{}, w{}", rhs, rhs));
-            return rhs;
-        }
-    } else if node.node_type == "number" {
-        // Allocate a register, move the number into it, and return it
-        let reg = writer.alloc_reg();
-        writer.write(&format!("        mov     w{}, {}", reg, node.get_attr()));
-        return reg;
-    } else if node.node_type == "true" {
-        let reg = writer.alloc_reg();
-        writer.write(&format!("        mov     w{}, 1", reg));
-        return reg;
-    } else if node.node_type == "false" {
-        let reg = writer.alloc_reg();
-        writer.write(&format!("        mov     w{}, 0", reg));
-        return reg;
-    } else if node.node_type == "id" {
-        // We have either a local variable or a global variable
-
-        // Allocate a register to store the value of the variable in
-        let reg = writer.alloc_reg();
-
-        // To check which one, we can simply find out if the variable's symbol table entry has an addr or a label
-        match node.get_sym().borrow().addr {
-            Some(addr) => {
-                // We have a local variable, so we can load the value at its address
-                writer.write(&format!("        ldr     w{}, [sp, {}]", reg, addr));
-                return reg;
-            }
-            None => {
-                // We have a global variable, so get the value stored at its label
-                writer.write(&format!(
-                    "        adrp    x8, {}@PAGE",
-                    node.get_sym().borrow().get_label()
-                ));
-                writer.write(&format!(
-                    "        add     x8, x8, {}@PAGEOFF",
-                    node.get_sym().borrow().get_label()
-                ));
-                writer.write(&format!("        ldr     w{}, [x8]", reg));
-                return reg;
-            }
-        }
-    } else if node.node_type == "funcCall" {
-        gen_func_call(writer, &mut node.clone());
-        let reg = writer.alloc_reg();
-        writer.write(&format!("        mov     w{}, w0", reg));
-        return reg;
+    if program.calls("fopen") {
+        // The path and mode are passed in x0 and x1, which is where fopen wants them. A file is given back as its
+        // file descriptor, which is duplicated so the FILE fopen made can be closed (or as -1, if it couldn't be opened)
+        writer.write(&format!("\n{}:", get_func_label(target, "fopen")));
+        writer.write("        stp     x29, x30, [sp, -32]!");
+        writer.write("        mov     x29, sp");
+        writer.write("        stp     x19, x20, [sp, 16]");
+        writer.write(&format!("        bl      {}fopen", target.symbol_prefix));
+        writer.write("        mov     x19, x0");
+        writer.write("        mov     w20, -1");
+        writer.write("        cbz     x19, 1f");
+        writer.write(&format!("        bl      {}fileno", target.symbol_prefix));
+        writer.write(&format!("        bl      {}dup", target.symbol_prefix));
+        writer.write("        mov     w20, w0");
+        writer.write("        mov     x0, x19");
+        writer.write(&format!("        bl      {}fclose", target.symbol_prefix));
+        writer.write("1:");
+        writer.write("        mov     w0, w20");
+        writer.write("        ldp     x19, x20, [sp, 16]");
+        writer.write("        ldp     x29, x30, [sp], 32");
+        writer.write("        ret");
     }
 
-    return 0;
-}
+    if program.calls("fread_line") {
+        // The file is passed in w0, and the array the line is read into in x1 (its address) and w2 (its length).
+        // The line is read a byte at a time (into the last 8 bytes of the stack frame), where every byte that fits
+        // is stored in the array, up to the newline, followed by a 0 if there's room for one. The number of bytes
+        // stored is given back, or -1 if the end of the file was reached before anything was read
+        writer.write(&format!("\n{}:", get_func_label(target, "fread_line")));
+        writer.write("        stp     x29, x30, [sp, -64]!");
+        writer.write("        mov     x29, sp");
+        writer.write("        stp     x19, x20, [sp, 16]");
+        writer.write("        stp     x21, x22, [sp, 32]");
+        writer.write("        str     x23, [sp, 48]");
+        writer.write("        mov     w19, w0");
+        writer.write("        mov     x20, x1");
+        writer.write("        mov     w21, w2");
+        writer.write("        mov     w22, 0  // Number of bytes stored");
+        writer.write("        mov     w23, 0  // Whether anything was read");
+        writer.write("1:");
+        writer.write("        mov     w0, w19");
+        writer.write("        add     x1, sp, 56");
+        writer.write("        mov     x2, 1");
+        writer.write(&format!("        bl      {}read", target.symbol_prefix));
+        writer.write("        cmp     x0, 0");
+        writer.write("        b.le    2f");
+        writer.write("        mov     w23, 1");
+        writer.write("        ldrb    w9, [sp, 56]");
+        writer.write("        cmp     w9, 10");
+        writer.write("        b.eq    3f");
+        writer.write("        cmp     w22, w21");
+        writer.write("        b.hs    1b");
+        writer.write("        str     w9, [x20, w22, uxtw 2]");
+        writer.write("        add     w22, w22, 1");
+        writer.write("        b       1b");
+        writer.write("2:");
+        writer.write("        mov     w0, -1");
+        writer.write("        cbz     w23, 5f");
+        writer.write("3:");
+        writer.write("        cmp     w22, w21");
+        writer.write("        b.hs    4f");
+        writer.write("        str     wzr, [x20, w22, uxtw 2]");
+        writer.write("4:");
+        writer.write("        mov     w0, w22");
+        writer.write("5:");
+        writer.write("        ldr     x23, [sp, 48]");
+        writer.write("        ldp     x21, x22, [sp, 32]");
+        writer.write("        ldp     x19, x20, [sp, 16]");
+        writer.write("        ldp     x29, x30, [sp], 64");
+        writer.write("        ret");
+    }
 
-pub fn gen_division(writer: &mut ASMWriter, node: &ASTNode, dest: i32, lhs: i32, rhs: i32) {
-    // Generate labels
-    let div_label = writer.new_label();
-    let after_label = writer.new_label();
+    if program.calls("fclose") {
+        // The file is passed in w0, which is where close wants it
+        writer.write(&format!("\n{}:", get_func_label(target, "fclose")));
+        writer.write(&format!("        b       {}close", target.symbol_prefix));
+    }
 
-    // If denominator is zero, jump over division to error call
-    writer.write(&format!("        cmp     w{}, wzr", rhs));
-    writer.write(&format!("        b.eq    {}", div_label));
+    if program.calls("parse_int") {
+        // The string or int array is passed in x0 (its address) and w1 (its length), the size of each of its
+        // characters in w2 and the line parse_int was called on in w3. The characters (up to the first 0) are
+        // an optional minus sign followed by at least one digit, built up into a positive number in x9
+        // (which is big enough to tell when it no longer fits in an int). Anything else exits the program
+        // with an error message, through the runtime error routine
+        writer.write(&format!("\n{}:", get_func_label(target, "parse_int")));
+        writer.write("        mov     x9, 0");
+        writer.write("        mov     w10, 0  // Index of the next character");
+        writer.write("        mov     w11, 0  // Whether there's a minus sign");
+        writer.write("        mov     x13, 2147483648");
+        writer.write("        mov     x14, 10");
+        writer.write("1:");
+        writer.write("        cmp     w10, w1");
+        writer.write("        b.ge    4f");
+        writer.write("        cmp     w2, 1");
+        writer.write("        b.ne    2f");
+        writer.write("        ldrb    w12, [x0, w10, uxtw]");
+        writer.write("        b       3f");
+        writer.write("2:");
+        writer.write("        ldr     w12, [x0, w10, uxtw 2]");
+        writer.write("3:");
+        writer.write("        cbz     w12, 4f");
+        writer.write("        add     w10, w10, 1");
+        writer.write("        cmp     w10, 1");
+        writer.write("        b.ne    5f");
+        writer.write("        cmp     w12, 45");
+        writer.write("        b.ne    5f");
+        writer.write("        mov     w11, 1");
+        writer.write("        b       1b");
+        writer.write("5:");
+        writer.write("        sub     w12, w12, 48");
+        writer.write("        cmp     w12, 9");
+        writer.write("        b.hi    6f");
+        writer.write("        madd    x9, x9, x14, x12");
+        writer.write("        cmp     x9, x13");
+        writer.write("        b.hi    7f");
+        writer.write("        b       1b");
+        writer.write("4:");
+        writer.write("        cmp     w10, w11");
+        writer.write("        b.eq    6f");
+        writer.write("        cbz     w11, 8f");
+        writer.write("        neg     w0, w9");
+        writer.write("        ret");
+        writer.write("8:");
+        writer.write("        cmp     x9, x13");
+        writer.write("        b.hs    7f");
+        writer.write("        mov     w0, w9");
+        writer.write("        ret");
+        writer.write("6:");
+        gen_global_addr(writer, target, "x0", "parse_int_invalid");
+        writer.write("        mov     w1, w3");
+        writer.write(&format!("        b       {}", RUNTIME_ERROR_LABEL));
+        writer.write("7:");
+        gen_global_addr(writer, target, "x0", "parse_int_too_large");
+        writer.write("        mov     w1, w3");
+        writer.write(&format!("        b       {}", RUNTIME_ERROR_LABEL));
+    }
 
-    // Otherwise, perform division and jump over error
-    writer.write(&format!("        sdiv    w{}, w{}, w{}", dest, lhs, rhs));
-    writer.write(&format!("        b       {}", after_label));
+    if program.calls("to_string") {
+        // The number is passed in w0, the int array its characters are written into in x1 (its address)
+        // and w2 (its length), and the line to_string was called on in w3. The digits of the number are
+        // counted first (so an array that's too short can exit the program before anything is written to it),
+        // then written from last to first, followed by a 0 if there's room for one. The number of characters
+        // written is given back
+        writer.write(&format!("\n{}:", get_func_label(target, "to_string")));
+        writer.write("        cmp     w0, 0");
+        writer.write("        cset    w9, lt  // Whether there's a minus sign");
+        writer.write("        cneg    w10, w0, lt");
+        writer.write("        mov     w11, w10");
+        writer.write("        mov     w12, w9  // Number of characters");
+        writer.write("        mov     w13, 10");
+        writer.write("1:");
+        writer.write("        add     w12, w12, 1");
+        writer.write("        udiv    w11, w11, w13");
+        writer.write("        cbnz    w11, 1b");
+        writer.write("        cmp     w12, w2");
+        writer.write("        b.gt    4f");
+        writer.write("        cbz     w9, 2f");
+        writer.write("        mov     w14, 45");
+        writer.write("        str     w14, [x1]");
+        writer.write("2:");
+        writer.write("        mov     w11, w12");
+        writer.write("3:");
+        writer.write("        sub     w11, w11, 1");
+        writer.write("        udiv    w14, w10, w13");
+        writer.write("        msub    w15, w14, w13, w10");
+        writer.write("        add     w15, w15, 48");
+        writer.write("        str     w15, [x1, w11, uxtw 2]");
+        writer.write("        mov     w10, w14");
+        writer.write("        cbnz    w10, 3b");
+        writer.write("        cmp     w12, w2");
+        writer.write("        b.ge    5f");
+        writer.write("        str     wzr, [x1, w12, uxtw 2]");
+        writer.write("5:");
+        writer.write("        mov     w0, w12");
+        writer.write("        ret");
+        writer.write("4:");
+        gen_global_addr(writer, target, "x0", "to_string_too_short");
+        writer.write("        mov     w1, w3");
+        writer.write(&format!("        b       {}", RUNTIME_ERROR_LABEL));
+    }
 
-    // Define error string
-    writer.write(&format!("{}:", div_label));
-    writer.write(".data");
-    let div_zero_label = writer.new_label();
-    writer.write(&format!(
-        "{}: .string \"Error: Line {}: Division by zero\\n\"",
-        div_zero_label,
-        node.get_line_num()
-    ));
-    writer.write(".align 4");
-    writer.write(".text");
-    // Call printf
-    writer.write(&format!("        adrp    x0, {}@PAGE", div_zero_label));
-    writer.write(&format!(
-        "        add     x0, x0, {}@PAGEOFF",
-        div_zero_label
-    ));
-    writer.write("        bl      _printf");
-    // Exit the program
+    // The error message is passed in x0 and the line it happened on in w1, which is where printf wants them
+    // (unless its format arguments go on the stack), then the program exits with an exit code of 1
+    writer.write(&format!("\n{}:", RUNTIME_ERROR_LABEL));
+    if target.num_vararg_regs == 0 {
+        writer.write("        sub     sp, sp, 16");
+        writer.write("        str     w1, [sp]");
+    }
+    writer.write(&format!("        bl      {}printf", target.symbol_prefix));
     writer.write("        mov     x0, 1  // Return code 1");
-    writer.write("        mov     x16, 1  // Sys call code to terminate program");
-    writer.write("        svc     0x80  // Make system call");
-    // Move on and free registers
-    writer.write(&format!("{}:", after_label));
+    gen_exit(writer, target);
+
+    // Let the linker know we don't need an executable stack
+    if target.object_format == ObjectFormat::Elf {
+        writer.write("\n        .section .note.GNU-stack,\"\",@progbits");
+    }
+}
+
+// Write out the counts of a program instrumented for coverage with the C library (fopen, fwrite and fclose),
+// keeping the exit code in x0 (and w19, which holds the exit code returned by main) safe
+pub fn gen_coverage_routine(writer: &mut ASMWriter, target: &TargetInfo, coverage: &Coverage) {
+    writer.write(&format!("\n        .balign 4\n{}:", COVERAGE_LABEL));
+    writer.write("        stp     x29, x30, [sp, -32]!");
+    writer.write("        mov     x29, sp");
+    writer.write("        stp     x0, x19, [sp, 16]");
+
+    // If the file can't be opened, there's nothing we can do about it
+    gen_global_addr(writer, target, "x0", &get_global_label(coverage.file));
+    gen_global_addr(writer, target, "x1", &get_global_label(coverage.mode));
+    writer.write(&format!("        bl      {}fopen", target.symbol_prefix));
+    writer.write(&format!("        cbz     x0, {}_done", COVERAGE_LABEL));
+    writer.write("        mov     x19, x0");
+
+    // Write the header, followed by the counters
+    gen_global_addr(writer, target, "x0", &get_global_label(coverage.header));
+    writer.write("        mov     x1, 1");
+    gen_mov_imm(writer, "w2", coverage.header_len as i32);
+    writer.write("        mov     x3, x19");
+    writer.write(&format!("        bl      {}fwrite", target.symbol_prefix));
+    gen_global_addr(writer, target, "x0", &get_global_label(coverage.counters));
+    writer.write("        mov     x1, 4");
+    gen_mov_imm(writer, "w2", coverage.num_counters as i32);
+    writer.write("        mov     x3, x19");
+    writer.write(&format!("        bl      {}fwrite", target.symbol_prefix));
+    writer.write("        mov     x0, x19");
+    writer.write(&format!("        bl      {}fclose", target.symbol_prefix));
+
+    writer.write(&format!("{}_done:", COVERAGE_LABEL));
+    writer.write("        ldp     x0, x19, [sp, 16]");
+    writer.write("        ldp     x29, x30, [sp], 32");
+    writer.write("        ret");
 }
 
-pub fn gen_func_call(writer: &mut ASMWriter, node: &mut ASTNode) {
-    if node.get_func_name() == "printf" {
-        // Get label of string
-        let string_label = node.children[1].children[0].children[0]
-            .get_sym()
-            .borrow()
-            .get_label();
+// Exit the program, with the exit code already in x0
+// (writing out the program's counts first, if it's been instrumented for coverage)
+fn gen_exit(writer: &mut ASMWriter, target: &TargetInfo) {
+    if writer.coverage.is_some() {
+        writer.write(&format!("        bl      {}", COVERAGE_LABEL));
+    }
+
+    match target.exit_abi {
+        ExitAbi::Syscall { reg, number, instr } => {
+            writer.write(&format!(
+                "        mov     {}, {}  // Sys call code to terminate program",
+                reg, number
+            ));
+            writer.write(&format!("        {}  // Make system call", instr));
+        }
+        // Through the C library, so anything printf is still holding onto gets written out
+        ExitAbi::Libc => writer.write(&format!("        bl      {}exit", target.symbol_prefix)),
+    }
+}
 
-        // Generate the printf function call
-        func_call_printf(writer, node, &string_label);
+// Put the address of a label in the data section into an x register, which takes the page the label is on
+// plus its offset into that page (where Mach-O and ELF spell out those two halves differently)
+fn gen_global_addr(writer: &mut ASMWriter, target: &TargetInfo, reg: &str, label: &str) {
+    if target.object_format == ObjectFormat::MachO {
+        writer.write(&format!("        adrp    {}, {}@PAGE", reg, label));
+        writer.write(&format!(
+            "        add     {}, {}, {}@PAGEOFF",
+            reg, reg, label
+        ));
     } else {
-        // Check how many arguments we want to pass
-        let num_args = node.children[1].children.len();
+        writer.write(&format!("        adrp    {}, {}", reg, label));
+        writer.write(&format!(
+            "        add     {}, {}, :lo12:{}",
+            reg, reg, label
+        ));
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// FUNCTIONS
+// -----------------------------------------------------------------------------------------
+
+pub fn gen_function(writer: &mut ASMWriter, target: &TargetInfo, func: &IrFunction) {
+    let frame = get_frame(func, target, writer.graph_coloring);
+
+    // Write function entry label, save the frame pointer and return address, and allocate the stack frame
+    gen_func_label(writer, target, func);
+    writer.write("        stp     x29, x30, [sp, -16]!");
+    writer.write("        mov     x29, sp");
+    if frame.size > 4095 {
+        gen_mov_imm(writer, "w16", frame.size);
+        writer.write("        sub     sp, sp, x16");
+    } else if frame.size != 0 {
+        writer.write(&format!("        sub     sp, sp, {}", frame.size));
+    }
+    gen_saved_regs(writer, &frame, false);
+
+    // Store any parameters in their temporaries, where the first 8 are passed in the argument passing registers
+    // and the rest are passed on the stack, just above our saved frame pointer and return address
+    let param_types: Vec<IrType> = func.params.iter().map(|param| func.temps[*param]).collect();
+    let stack_offsets = get_stack_arg_offsets(target, &param_types);
+    for (slot, param) in func.params.iter().enumerate() {
+        if slot < target.num_arg_regs {
+            gen_store_temp(writer, func, &frame, slot, *param);
+        } else {
+            let reg = get_def_reg(func, &frame, 9, *param);
+            writer.write(&format!(
+                "        ldr     {}, [x29, {}]",
+                reg,
+                16 + stack_offsets[slot - target.num_arg_regs]
+            ));
+            gen_store_reg(writer, func, &frame, &reg, *param);
+        }
+    }
 
-        // There are 8 argument passing registers, r0 - r7, so if there are more arguments than that,
-        // we need to allocate extra space on the stack for them
-        if num_args > 8 {
-            let extra_space = ((num_args - 8) * 4) as i32;
-            // Allocate enough space on the stack, and adjust the addresses of the local variables accordingly
-            allocate_stack(writer, extra_space);
+    let fused_pairs = get_fused_pairs(func);
+    for (i, instr) in func.body.iter().enumerate() {
+        // An instruction that's fused into the instruction after it is generated along with it
+        if fused_pairs.values().any(|first| *first == i) {
+            continue;
+        }
+        if let Some(first) = fused_pairs.get(&i) {
+            gen_fused_pair(
+                writer,
+                target,
+                func,
+                &frame,
+                &func.body[*first],
+                instr,
+                get_next_instr(&func.body, i),
+            );
+            continue;
         }
 
-        // Loop through any arguments and pass them using the correct method
-        for (i, arg) in node.children[1].children.iter().enumerate() {
-            let expr_reg = gen_expr(writer, &arg.children[0]);
+        gen_instr(
+            writer,
+            target,
+            func,
+            &frame,
+            instr,
+            get_next_instr(&func.body, i),
+        );
+    }
+
+    // Write function exit label, deallocate the stack frame and return
+    writer.write(&format!("{}:", get_return_label(target, func)));
+    gen_saved_regs(writer, &frame, true);
+    writer.write("        mov     sp, x29");
+    writer.write("        ldp     x29, x30, [sp], 16");
+    writer.write("        ret");
+}
 
-            // If the argument number is less than 8, just put it in the corresponding argument passing register
-            if i < 8 {
-                writer.write(&format!("        mov     w{}, w{}", i, expr_reg));
+// -----------------------------------------------------------------------------------------
+// INSTRUCTIONS
+// -----------------------------------------------------------------------------------------
+
+// Generate a single instruction, which works on the callee-saved registers temporaries are kept in directly,
+// while any temporary kept on the stack is loaded into a scratch register (w9 - w11, or x9 - x11) before it's used
+// and stored back afterwards, and any constant that can be used as an immediate operand is used as one
+pub fn gen_instr(
+    writer: &mut ASMWriter,
+    target: &TargetInfo,
+    func: &IrFunction,
+    frame: &Frame,
+    instr: &Instr,
+    next: Option<&Instr>,
+) {
+    match instr {
+        // A constant that's never kept anywhere is put straight into whatever uses it
+        Instr::Const { dest, .. } if matches!(frame.temp_homes[*dest], TempHome::Imm(_)) => {}
+        Instr::Const { dest, value } => {
+            let reg = get_def_reg(func, frame, 9, *dest);
+            gen_mov_imm(writer, &reg, *value);
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        // A copy between two temporaries that were merged into the same register does nothing
+        Instr::Copy { dest, src } if frame.temp_homes[*dest] == frame.temp_homes[*src] => {}
+        Instr::Copy { dest, src } => {
+            let reg = if matches!(frame.temp_homes[*src], TempHome::Reg(_)) {
+                gen_use_temp(writer, func, frame, 9, *src)
             } else {
-                // Otherwise, place it on the stack at offset (i - 8) * 4
-                // (for example, argument 8 will be stored at sp + 0, argument 9 at sp + 4, etc...)
+                // Anything that isn't in a register can be loaded straight into the destination's register
+                let reg = get_def_reg(func, frame, 9, *dest);
+                gen_load_reg(writer, func, frame, &reg, *src);
+                reg
+            };
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::BinOp { op, dest, lhs, rhs } => {
+            let lhs = gen_use_temp(writer, func, frame, 9, *lhs);
+            let rhs = get_rhs_operand(writer, func, frame, *op, *rhs);
+            let reg = get_def_reg(func, frame, 9, *dest);
+            gen_binop(writer, *op, &reg, &lhs, &rhs);
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::UnOp { op, dest, src } => {
+            let src = gen_use_temp(writer, func, frame, 9, *src);
+            let reg = get_def_reg(func, frame, 9, *dest);
+            match op {
+                UnOp::Neg => writer.write(&format!("        neg     {}, {}", reg, src)),
+                // A bool is always 0 or 1, so flipping the lowest bit flips the bool
+                UnOp::Not => writer.write(&format!("        eor     {}, {}, 1", reg, src)),
+            }
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => {
+            let cond = gen_use_temp(writer, func, frame, 9, *cond);
+            writer.write(&format!("        cmp     {}, 0", cond));
+            gen_csel(writer, func, frame, *dest, *if_true, *if_false, "ne");
+        }
+        Instr::SlotAddr { dest, slot } => {
+            let reg = get_def_reg(func, frame, 9, *dest);
+            gen_sp_offset(writer, &reg, frame.slot_offsets[*slot]);
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::GlobalAddr { dest, global } => {
+            let reg = get_def_reg(func, frame, 9, *dest);
+            gen_global_addr(writer, target, &reg, &get_global_label(*global));
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::ElementAddr {
+            dest,
+            base,
+            index,
+            scale,
+        } => {
+            let base = gen_use_temp(writer, func, frame, 9, *base);
+            let index = gen_use_temp(writer, func, frame, 10, *index);
+            let reg = get_def_reg(func, frame, 9, *dest);
+
+            if *scale == 1 {
+                writer.write(&format!(
+                    "        add     {}, {}, {}, sxtw",
+                    reg, base, index
+                ));
+            } else if *scale == 4 {
                 writer.write(&format!(
-                    "        str     w{}, [sp, {}]",
-                    expr_reg,
-                    (i - 8) * 4
+                    "        add     {}, {}, {}, sxtw 2",
+                    reg, base, index
+                ));
+            } else {
+                // Each element is a row of a multi-dimensional array, so the row is at address + (index * row size)
+                gen_mov_imm(writer, "w11", *scale);
+                writer.write(&format!(
+                    "        smaddl  {}, {}, w11, {}",
+                    reg, index, base
                 ));
             }
 
-            writer.free_reg(expr_reg);
+            gen_store_reg(writer, func, frame, &reg, *dest);
         }
-
-        // If there are any actively allocated caller-stored registers,
-        // they could get trampled by the function we are calling, so we have to store them
-        let active_caller = writer.get_allocated_caller_saved_registers();
-        // Allocate space on the stack and temporarily store the registers on that allocated space
-        allocate_stack(writer, (active_caller.len() * 4) as i32);
-        for (i, reg) in active_caller.iter().enumerate() {
-            writer.write(&format!("        str     w{}, [sp, {}]", reg, i * 4));
+        Instr::Load { dest, addr, width } => {
+            let addr = gen_use_temp(writer, func, frame, 9, *addr);
+            let reg = get_def_reg(func, frame, 9, *dest);
+            match width {
+                Width::Word => writer.write(&format!("        ldr     {}, [{}]", reg, addr)),
+                Width::Byte => writer.write(&format!("        ldrb    {}, [{}]", reg, addr)),
+            }
+            gen_store_reg(writer, func, frame, &reg, *dest);
         }
-        if active_caller.len() > 0 {
-            // If we are storing any of these registers, the callee will need to know about it since we've moved the
-            // stack pointer away from any possible arguments stored on the stack
-            node.get_sym().borrow_mut().stored_bytes = (active_caller.len() * 4) as i32;
+        Instr::Store { src, addr, width } => {
+            let addr = gen_use_temp(writer, func, frame, 9, *addr);
+            let src = gen_use_temp(writer, func, frame, 10, *src);
+            match width {
+                Width::Word => writer.write(&format!("        str     {}, [{}]", src, addr)),
+                Width::Byte => writer.write(&format!("        strb    {}, [{}]", src, addr)),
+            }
         }
+        Instr::Call {
+            dests,
+            func: callee,
+            args,
+        } => {
+            // The first 8 arguments go in the argument passing registers, and the rest go on the stack,
+            // at the bottom of our stack frame (for example, argument 8 is stored at sp + 0, argument 9 at sp + 8,
+            // or at sp + 4 if argument 8 is an int and the target packs them together, etc...)
+            let arg_types: Vec<IrType> = args.iter().map(|arg| func.temps[*arg]).collect();
+            let stack_offsets = get_stack_arg_offsets(target, &arg_types);
+            for (slot, arg) in args.iter().enumerate() {
+                if slot < target.num_arg_regs {
+                    gen_load_temp(writer, func, frame, slot, *arg);
+                } else {
+                    let arg = gen_use_temp(writer, func, frame, 9, *arg);
+                    writer.write(&format!(
+                        "        str     {}, [sp, {}]",
+                        arg,
+                        stack_offsets[slot - target.num_arg_regs]
+                    ));
+                }
+            }
 
-        writer.write(&format!(
-            "        bl      {}1",
-            node.get_sym().borrow().name
-        ));
+            writer.write(&format!(
+                "        bl      {}",
+                get_func_label(target, callee)
+            ));
+
+            // Anything returned is passed back in the first few argument passing registers
+            for (reg, dest) in dests.iter().enumerate() {
+                gen_store_temp(writer, func, frame, reg, *dest);
+            }
+        }
+        Instr::Print { file, format, args } => {
+            // printf's format arguments go in w1 - w7 on targets that pass variadic arguments in registers,
+            // and the rest (every one of them on macOS) go on the stack, 8 bytes each. dprintf, which prints
+            // to a file, takes the file in w0 and its format string in x1, pushing its format arguments along by one
+            let first_reg = if file.is_some() { 2 } else { 1 };
+            let num_regs = get_num_print_regs(target, file.is_some());
+            for (i, arg) in args.iter().enumerate() {
+                if i < num_regs {
+                    gen_load_temp(writer, func, frame, i + first_reg, *arg);
+                } else {
+                    let arg = gen_use_temp(writer, func, frame, 9, *arg);
+                    writer.write(&format!(
+                        "        str     {}, [sp, {}]",
+                        arg,
+                        (i - num_regs) * 8
+                    ));
+                }
+            }
 
-        for (i, reg) in active_caller.iter().enumerate() {
-            writer.write(&format!("        ldr     w{}, [sp, {}]", reg, i * 4));
+            if let Some(file) = file {
+                gen_load_temp(writer, func, frame, 0, *file);
+                gen_global_addr(writer, target, "x1", &get_global_label(*format));
+                writer.write(&format!("        bl      {}dprintf", target.symbol_prefix));
+            } else {
+                gen_global_addr(writer, target, "x0", &get_global_label(*format));
+                writer.write(&format!("        bl      {}printf", target.symbol_prefix));
+            }
+        }
+        Instr::Label(label) => {
+            writer.write(&format!("{}:", get_label_name(target, func, *label)));
+        }
+        Instr::Jump(label) => {
+            // There's no need to jump to the very next instruction
+            if next != Some(&Instr::Label(*label)) {
+                writer.write(&format!(
+                    "        b       {}",
+                    get_label_name(target, func, *label)
+                ));
+            }
         }
-        if active_caller.len() > 0 {
-            // If we stored any caller-saved registers, deallocate the space on the stack
-            allocate_stack(writer, -((active_caller.len() * 4) as i32));
+        Instr::Branch {
+            cond,
+            if_true,
+            if_false,
+        } => {
+            let cond = gen_use_temp(writer, func, frame, 9, *cond);
+
+            // Fall through to whichever label comes next, if either of them does
+            if next == Some(&Instr::Label(*if_true)) {
+                writer.write(&format!(
+                    "        cbz     {}, {}",
+                    cond,
+                    get_label_name(target, func, *if_false)
+                ));
+            } else {
+                writer.write(&format!(
+                    "        cbnz    {}, {}",
+                    cond,
+                    get_label_name(target, func, *if_true)
+                ));
+                if next != Some(&Instr::Label(*if_false)) {
+                    writer.write(&format!(
+                        "        b       {}",
+                        get_label_name(target, func, *if_false)
+                    ));
+                }
+            }
         }
+        Instr::Return(values) => {
+            // Values are returned in the first few argument passing registers
+            for (reg, value) in values.iter().enumerate() {
+                gen_load_temp(writer, func, frame, reg, *value);
+            }
 
-        // If we cleared extra space, we have to deallocate it after the function call
-        if num_args > 8 {
-            let extra_space = ((num_args - 8) * 4) as i32;
-            // Deallocate space on the stack, and adjust the addresses of the local variables accordingly
-            allocate_stack(writer, -extra_space);
+            // Jump to the function exit (unless it's the very next thing)
+            if next.is_some() {
+                writer.write(&format!(
+                    "        b       {}",
+                    get_return_label(target, func)
+                ));
+            }
+        }
+        Instr::Panic { message, line } => {
+            // Exit the program through the runtime library, which prints the error message
+            gen_global_addr(writer, target, "x0", &get_global_label(*message));
+            gen_mov_imm(writer, "w1", *line);
+            writer.write(&format!("        bl      {}", RUNTIME_ERROR_LABEL));
         }
+        Instr::Line(line) => gen_line(writer, *line),
     }
 }
 
-pub fn gen_func_enter(writer: &mut ASMWriter, node: &mut ASTNode) {
-    // Get number of bytes to allocate on the stack
-    let num_bytes = get_func_stack_alloc(node);
-
-    // Calculate and store memory addresses for all local variables defined in this function
-    declare_variables(node, &mut 0);
-
-    // Write function entry label
-    writer.write(&format!("\n{}1:", node.get_func_name()));
-    writer.write("        stp     x29, x30, [sp, -16]!");
-    writer.write("        mov     x29, sp");
-    if num_bytes != 0 {
-        writer.write(&format!("        sub     sp, sp, {}", num_bytes));
-    }
-
-    // Store any parameters in their assigned memory locations
-    for (i, param) in node.children[1].children.iter().enumerate() {
-        // If the parameter number is less than 8, it is stored in an argument passing register
-        if i < 8 {
-            writer.write(&format!(
-                "        str     w{}, [sp, {}]",
-                i,
-                param.get_sym().borrow().get_addr()
-            ));
-        } else {
-            // Otherwise, it is stored on the stack
-            let temp_reg = writer.alloc_reg();
-            // Get the amount of space we need to consider that is used to store saved caller-saved registers
-            let caller_bytes = node.get_sym().borrow().stored_bytes;
-            writer.write(&format!(
-                "        ldr     w{}, [sp, {}]",
-                temp_reg,
-                ((i - 8) * 4) + 16 + ((num_bytes + caller_bytes) as usize)
-            ));
-            writer.write(&format!(
-                "        str     w{}, [sp, {}]",
-                temp_reg,
-                param.get_sym().borrow().get_addr()
-            ));
-            writer.free_reg(temp_reg);
+// Combine the left and right hand sides (a register, and a register or an immediate) with the given operator,
+// putting the result in the given register
+fn gen_binop(writer: &mut ASMWriter, op: BinOp, dest: &str, lhs: &str, rhs: &str) {
+    let cond = match op {
+        BinOp::Add | BinOp::Sub => {
+            let (instr, rhs) = if op == BinOp::Add {
+                get_add_sub("add", "sub", rhs)
+            } else {
+                get_add_sub("sub", "add", rhs)
+            };
+            return writer.write(&format!("        {}     {}, {}, {}", instr, dest, lhs, rhs));
         }
-    }
+        BinOp::Mul => return writer.write(&format!("        mul     {}, {}, {}", dest, lhs, rhs)),
+        BinOp::Div => return writer.write(&format!("        sdiv    {}, {}, {}", dest, lhs, rhs)),
+        BinOp::Rem => {
+            // The remainder is lhs - (lhs / rhs) * rhs, where sdiv rounds towards zero,
+            // so it has the same sign as lhs (see evaluate_binop)
+            writer.write(&format!("        sdiv    w11, {}, {}", lhs, rhs));
+            writer.write(&format!("        msub    {}, w11, {}, {}", dest, rhs, lhs));
+            return;
+        }
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::ULt => {
+            get_cond(op).unwrap()
+        }
+        // Adding or subtracting while setting the flags overflows if the signed result doesn't fit,
+        // and multiplying does if the full 64 bit product isn't the same as its lower 32 bits sign extended
+        BinOp::AddOverflows => {
+            let (instr, rhs) = get_add_sub("adds", "subs", rhs);
+            writer.write(&format!("        {}    wzr, {}, {}", instr, lhs, rhs));
+            return writer.write(&format!("        cset    {}, vs", dest));
+        }
+        BinOp::SubOverflows => {
+            let (instr, rhs) = get_add_sub("subs", "adds", rhs);
+            writer.write(&format!("        {}    wzr, {}, {}", instr, lhs, rhs));
+            return writer.write(&format!("        cset    {}, vs", dest));
+        }
+        BinOp::MulOverflows => {
+            writer.write(&format!("        smull   x9, {}, {}", lhs, rhs));
+            writer.write("        cmp     x9, w9, sxtw");
+            return writer.write(&format!("        cset    {}, ne", dest));
+        }
+        // Dividing overflows if lhs is the smallest int and rhs is -1
+        // (where rhs is only compared against -1 if lhs is the smallest int, and otherwise the flags say they differ)
+        BinOp::DivOverflows => {
+            writer.write("        mov     w11, -2147483648");
+            writer.write(&format!("        cmp     {}, w11", lhs));
+            writer.write(&format!("        ccmn    {}, 1, 0, eq", rhs));
+            return writer.write(&format!("        cset    {}, eq", dest));
+        }
+    };
 
-    // We no longer need to keep track of the amount of space allocated for caller-saved registers
-    node.get_sym().borrow_mut().stored_bytes = 0;
+    // The result is 1 if the comparison holds and 0 otherwise
+    let (instr, rhs) = get_add_sub("cmp", "cmn", rhs);
+    writer.write(&format!("        {}     {}, {}", instr, lhs, rhs));
+    writer.write(&format!("        cset    {}, {}", dest, cond));
+}
 
-    // Now, if there are any callee-saved registers currently allocated, it is the job of the callee to save them
-    let mut active_callee = writer.get_allocated_callee_saved_registers();
-    // Keep track of this for when we're exiting the function
-    node.get_sym()
-        .borrow_mut()
-        .active_callee_saved
-        .append(&mut active_callee);
+// Pick one of two temporaries for the destination, depending on whether the given condition holds
+// (where loading either of them into a scratch register leaves the flags alone)
+fn gen_csel(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    dest: Temp,
+    if_true: Temp,
+    if_false: Temp,
+    cond: &str,
+) {
+    let if_true = gen_use_temp(writer, func, frame, 10, if_true);
+    let if_false = gen_use_temp(writer, func, frame, 11, if_false);
+    let reg = get_def_reg(func, frame, 9, dest);
+    writer.write(&format!(
+        "        csel    {}, {}, {}, {}",
+        reg, if_true, if_false, cond
+    ));
+    gen_store_reg(writer, func, frame, &reg, dest);
+}
 
-    allocate_stack(writer, (active_callee.len() * 4) as i32);
+// Get the condition code a comparison holds under, if the operator is a comparison
+fn get_cond(op: BinOp) -> Option<&'static str> {
+    return match op {
+        BinOp::Eq => Some("eq"),
+        BinOp::Ne => Some("ne"),
+        BinOp::Lt => Some("lt"),
+        BinOp::Gt => Some("gt"),
+        BinOp::Le => Some("le"),
+        BinOp::Ge => Some("ge"),
+        BinOp::ULt => Some("lo"),
+        _ => None,
+    };
+}
 
-    for (i, reg) in active_callee.iter().enumerate() {
-        writer.write(&format!("        str     w{}, [sp, {}]", reg, i * 4));
-    }
+// Get the condition code that holds exactly when the given one doesn't
+fn get_inverse_cond(cond: &str) -> &'static str {
+    return match cond {
+        "eq" => "ne",
+        "ne" => "eq",
+        "lt" => "ge",
+        "ge" => "lt",
+        "gt" => "le",
+        "le" => "gt",
+        "lo" => "hs",
+        _ => "lo",
+    };
 }
 
-pub fn gen_func_exit(writer: &mut ASMWriter, node: &mut ASTNode) {
-    // Generate an error message if function is non-void
-    if node.get_sym().borrow().returns != "void" {
-        // Define error string
-        writer.write(".data");
-        let no_ret_label = writer.new_label();
-        writer.write(&format!("{}: .string \"Error: Line {}: A control path reaches the end of a non-void function without returning a value\\n\"", no_ret_label, node.get_line_num()));
-        writer.write(".align 4");
-        writer.write(".text");
-        // Call printf
-        writer.write(&format!("        adrp    x0, {}@PAGE", no_ret_label));
-        writer.write(&format!("        add     x0, x0, {}@PAGEOFF", no_ret_label));
-        writer.write("        bl      _printf");
-        // Exit the program
-        writer.write("        mov     x0, 1  // Return code 1");
-        writer.write("        mov     x16, 1  // Sys call code to terminate program");
-        writer.write("        svc     0x80  // Make system call");
+// Get the right hand side of an operator, as an immediate operand if it's a constant the operator can take as one,
+// and otherwise as the register it's in (loading it into w10 if it isn't kept in one)
+fn get_rhs_operand(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    op: BinOp,
+    rhs: Temp,
+) -> String {
+    return match get_imm_operand(frame, op, rhs) {
+        Some(value) => value.to_string(),
+        None => gen_use_temp(writer, func, frame, 10, rhs),
+    };
+}
+
+// Get the constant the right hand side of an operator holds, if it can be given to the operator as an immediate operand
+// rather than being put in a register first, which it can for adding, subtracting and comparing
+// (if it fits in 12 bits, once a negative one is flipped by get_add_sub)
+fn get_imm_operand(frame: &Frame, op: BinOp, rhs: Temp) -> Option<i32> {
+    let TempHome::Imm(value) = frame.temp_homes[rhs] else {
+        return None;
+    };
+    if matches!(
+        op,
+        BinOp::Mul | BinOp::Div | BinOp::Rem | BinOp::MulOverflows | BinOp::DivOverflows
+    ) || !(-4095..4096).contains(&value)
+    {
+        return None;
     }
 
-    // Get number of bytes to allocate on the stack
-    let num_bytes = get_func_stack_alloc(node);
+    return Some(value);
+}
 
-    // Write function exit label
-    writer.write(&format!("{}2:", node.get_func_name()));
+// Get the instruction that adds, subtracts or compares with the given right hand side, and the operand to give it,
+// where an immediate can only be 0 to 4095, so a negative one is given to the opposite instruction as its negation
+// (which sets the same flags, since the immediate is never 0)
+fn get_add_sub<'a>(
+    instr: &'static str,
+    opposite: &'static str,
+    rhs: &'a str,
+) -> (&'static str, &'a str) {
+    return match rhs.strip_prefix('-') {
+        Some(value) => (opposite, value),
+        None => (instr, rhs),
+    };
+}
 
-    // If there are any callee-saved registers currently saved, we have to restore them
-    let active_callee = node.get_sym().borrow().get_active_callees();
-    for (i, reg) in active_callee.iter().enumerate() {
-        writer.write(&format!("        ldr     w{}, [sp, {}]", reg, i * 4));
+// -----------------------------------------------------------------------------------------
+// FUSED INSTRUCTIONS
+// -----------------------------------------------------------------------------------------
+
+// Find every instruction (as the index of it in the body of a function) that uses the result of the instruction
+// right before it, where nothing else uses that result and the two can be done together without keeping it anywhere,
+// mapping each one to the index of the instruction before it
+fn get_fused_pairs(func: &IrFunction) -> HashMap<usize, usize> {
+    let mut num_uses = vec![0; func.temps.len()];
+    for temp in func.body.iter().flat_map(|instr| instr.get_uses()) {
+        num_uses[temp] += 1;
     }
-    allocate_stack(writer, -((active_callee.len() * 4) as i32));
 
-    if num_bytes != 0 {
-        writer.write(&format!("        add     sp, sp, {}", num_bytes));
+    let mut pairs = HashMap::new();
+    for (i, instr) in func.body.iter().enumerate() {
+        let Instr::BinOp { dest: result, .. } = instr else {
+            continue;
+        };
+        if num_uses[*result] != 1 {
+            continue;
+        }
+
+        // The user has to be the very next instruction, so nothing can change the operands in between
+        let Some(next) =
+            (i + 1..func.body.len()).find(|j| !matches!(func.body[*j], Instr::Line(_)))
+        else {
+            continue;
+        };
+        if can_fuse(instr, &func.body[next]) {
+            pairs.insert(next, i);
+        }
     }
-    writer.write("        ldp     x29, x30, [sp], 16");
-    writer.write("        ret");
+
+    return pairs;
 }
 
-pub fn func_call_printf(writer: &mut ASMWriter, node: &ASTNode, string_label: &String) {
-    let mut formatting = false;
-    writer.write(&format!("        adrp    x0, {}@PAGE", string_label));
-    writer.write(&format!("        add     x0, x0, {}@PAGEOFF", string_label));
-    for (i, param) in node.children[1].children.iter().enumerate() {
-        if i > 0 {
-            formatting = true;
-            let expr_reg = gen_expr(writer, &param.children[0]);
-            if i == 1 {
-                writer.write(&format!("        str     w{}, [sp, -32]!", expr_reg));
-                increment_addrs(&writer.get_current_func(), 32, &mut vec![]);
+// Return true if an operator and the instruction after it that uses its result can be done together, which they can
+// for a multiply and an add or subtract (a * b + c or c - a * b) with madd or msub, and for a comparison
+// and a branch or select on it, by using the comparison itself rather than a bool set by it
+fn can_fuse(first: &Instr, second: &Instr) -> bool {
+    let Instr::BinOp {
+        op, dest: result, ..
+    } = first
+    else {
+        return false;
+    };
+
+    return match (op, second) {
+        (
+            BinOp::Mul,
+            Instr::BinOp {
+                op: BinOp::Add,
+                lhs,
+                rhs,
+                ..
+            },
+        ) => lhs == result || rhs == result,
+        // Only the product being subtracted can be fused (c - a * b, not a * b - c)
+        (
+            BinOp::Mul,
+            Instr::BinOp {
+                op: BinOp::Sub,
+                rhs,
+                ..
+            },
+        ) => rhs == result,
+        (_, Instr::Branch { cond, .. }) => cond == result && get_cond(*op).is_some(),
+        (_, Instr::Select { cond, .. }) => cond == result && get_cond(*op).is_some(),
+        _ => false,
+    };
+}
+
+// Generate an instruction along with the instruction before it whose result it uses, as found by get_fused_pairs
+fn gen_fused_pair(
+    writer: &mut ASMWriter,
+    target: &TargetInfo,
+    func: &IrFunction,
+    frame: &Frame,
+    first: &Instr,
+    second: &Instr,
+    next: Option<&Instr>,
+) {
+    let Instr::BinOp {
+        op: first_op,
+        dest: result,
+        lhs: a,
+        rhs: b,
+    } = first
+    else {
+        return;
+    };
+
+    match second {
+        // madd gives c + a * b and msub gives c - a * b
+        Instr::BinOp { op, dest, lhs, rhs } => {
+            let c = if lhs == result { rhs } else { lhs };
+            let a = gen_use_temp(writer, func, frame, 9, *a);
+            let b = gen_use_temp(writer, func, frame, 10, *b);
+            let c = gen_use_temp(writer, func, frame, 11, *c);
+            let reg = get_def_reg(func, frame, 9, *dest);
+            let op = if *op == BinOp::Add { "madd" } else { "msub" };
+            writer.write(&format!("        {}    {}, {}, {}, {}", op, reg, a, b, c));
+            gen_store_reg(writer, func, frame, &reg, *dest);
+        }
+        Instr::Branch {
+            if_true, if_false, ..
+        } => {
+            // Fall through to whichever label comes next, if either of them does, by branching to the other one
+            // when the comparison doesn't hold (or does)
+            let cond = get_cond(*first_op).unwrap();
+            let (cond, label) = if next == Some(&Instr::Label(*if_true)) {
+                (get_inverse_cond(cond), *if_false)
             } else {
+                (cond, *if_true)
+            };
+            let label = get_label_name(target, func, label);
+
+            let lhs = gen_use_temp(writer, func, frame, 9, *a);
+            match (cond, frame.temp_homes[*b]) {
+                // Comparing with zero needs no comparison at all
+                ("eq", TempHome::Imm(0)) => {
+                    writer.write(&format!("        cbz     {}, {}", lhs, label))
+                }
+                ("ne", TempHome::Imm(0)) => {
+                    writer.write(&format!("        cbnz    {}, {}", lhs, label))
+                }
+                _ => {
+                    gen_compare(writer, func, frame, *first_op, &lhs, *b);
+                    writer.write(&format!("        b.{}    {}", cond, label));
+                }
+            }
+
+            if next != Some(&Instr::Label(*if_true)) && next != Some(&Instr::Label(*if_false)) {
                 writer.write(&format!(
-                    "        str     w{}, [sp, {}]",
-                    expr_reg,
-                    (i - 1) * 8
+                    "        b       {}",
+                    get_label_name(target, func, *if_false)
                 ));
             }
-            writer.free_reg(expr_reg);
         }
+        Instr::Select {
+            dest,
+            if_true,
+            if_false,
+            ..
+        } => {
+            let lhs = gen_use_temp(writer, func, frame, 9, *a);
+            gen_compare(writer, func, frame, *first_op, &lhs, *b);
+            let cond = get_cond(*first_op).unwrap();
+            gen_csel(writer, func, frame, *dest, *if_true, *if_false, cond);
+        }
+        _ => {}
     }
-    writer.write("        bl      _printf");
-    if formatting {
-        // Deallocate space on the stack for the printf arguments
-        allocate_stack(writer, -32);
-    }
+}
+
+// Compare the left hand side of a comparison (in the given register) with its right hand side,
+// setting the flags for a conditional instruction (where loading anything afterwards leaves the flags alone)
+fn gen_compare(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    op: BinOp,
+    lhs: &str,
+    rhs: Temp,
+) {
+    let rhs = get_rhs_operand(writer, func, frame, op, rhs);
+    let (instr, rhs) = get_add_sub("cmp", "cmn", &rhs);
+    writer.write(&format!("        {}     {}, {}", instr, lhs, rhs));
 }
@@ -0,0 +1,807 @@
+use crate::ir::ir_data::*;
+
+use crate::code_gen::code_gen_utils::{
+    get_export_name, get_global_label, get_runtime_messages, RUNTIME_ERROR_LABEL,
+};
+
+// -----------------------------------------------------------------------------------------
+// LLVM IR
+// -----------------------------------------------------------------------------------------
+
+// Get the program as a module of textual LLVM IR, which can be handed to LLVM's own optimizers and code generators
+// (like "opt -O2" and "llc") instead of using one of our native backends. Addresses use opaque pointers ("ptr"),
+// which LLVM 15 and newer expect (LLVM 14 needs to be given -opaque-pointers).
+//
+// Every temporary is given its own stack slot (an alloca) which is loaded from and stored to around each instruction,
+// just like in the native backends, since temporaries can be assigned more than once and LLVM IR must be in SSA form.
+// LLVM's mem2reg pass turns all of that back into registers
+pub fn format_llvm_ir(program: &IrProgram) -> String {
+    let mut text = String::from("; ModuleID = 'soup'\n\n");
+
+    for (i, global) in program.globals.iter().enumerate() {
+        let label = get_global_label(i);
+        let definition = match &global.kind {
+            GlobalKind::Word(value) => {
+                format!("@{} = private global i32 {}, align 4", label, value)
+            }
+            GlobalKind::Zeroed(num_bytes) => format!(
+                "@{} = private global [{} x i8] zeroinitializer, align 8",
+                label, num_bytes
+            ),
+            GlobalKind::String(bytes) => format!(
+                "@{} = private constant [{} x i8] c\"{}\\00\"",
+                label,
+                bytes.len() + 1,
+                escape_llvm_string(bytes)
+            ),
+        };
+        text.push_str(&format!("{}\n", definition));
+    }
+    text.push_str("@last_newline = private constant [2 x i8] c\"\\0A\\00\"\n");
+    for (label, format) in get_runtime_messages(program) {
+        text.push_str(&format!(
+            "@{} = private constant [{} x i8] c\"{}\\00\"\n",
+            label,
+            format.len() + 1,
+            escape_llvm_string(format.as_bytes())
+        ));
+    }
+    text.push('\n');
+
+    // The C library provides printf and exit (along with dprintf, for printing to files), and everything else
+    // the parts of the runtime library the program calls are built on
+    text.push_str("declare i32 @printf(ptr, ...)\n");
+    text.push_str("declare void @exit(i32) noreturn\n");
+    text.push_str("declare i32 @dprintf(i32, ptr, ...)\n");
+    if program.calls("sleep") {
+        text.push_str("declare i32 @fflush(ptr)\n");
+        text.push_str("declare i32 @nanosleep(ptr, ptr)\n");
+    }
+    if program.calls("fopen") {
+        text.push_str("declare ptr @fopen(ptr, ptr)\n");
+        text.push_str("declare i32 @fileno(ptr)\n");
+        text.push_str("declare i32 @dup(i32)\n");
+        text.push_str("declare i32 @fclose(ptr)\n");
+    }
+    if program.calls("fread_line") {
+        text.push_str("declare i64 @read(i32, ptr, i64)\n");
+    }
+    if program.calls("fclose") {
+        text.push_str("declare i32 @close(i32)\n");
+    }
+
+    // and LLVM itself provides arithmetic that also says whether it overflowed, for any overflow checks
+    let checks_overflow = program.functions.iter().any(|func| {
+        func.body.iter().any(|instr| match instr {
+            Instr::BinOp { op, .. } => matches!(
+                op,
+                BinOp::AddOverflows | BinOp::SubOverflows | BinOp::MulOverflows
+            ),
+            _ => false,
+        })
+    });
+    if checks_overflow {
+        for op in ["sadd", "ssub", "smul"] {
+            text.push_str(&format!(
+                "declare {{i32, i1}} @llvm.{}.with.overflow.i32(i32, i32)\n",
+                op
+            ));
+        }
+    }
+    text.push('\n');
+
+    // Calls the compilee's main function (not to be confused with the main function defined here),
+    // then prints the last newline and exits the program, with main's exit code if it returns one
+    // (where it's weak, so a C program linked against the module can replace it with its own main)
+    let main_returns_int = match program.get_function("main") {
+        None => false,
+        Some(main_func) => !main_func.returns.is_empty(),
+    };
+    text.push_str("define weak i32 @main() {\n");
+    let main_label = get_export_name("main");
+    if main_returns_int {
+        text.push_str(&format!("  %code = call i32 @{}()\n", main_label));
+    } else {
+        text.push_str(&format!("  call void @{}()\n", main_label));
+    }
+    text.push_str("  call i32 (ptr, ...) @printf(ptr @last_newline)\n");
+    if main_returns_int {
+        text.push_str("  call void @exit(i32 %code)\n");
+    } else {
+        text.push_str("  call void @exit(i32 0)\n");
+    }
+    text.push_str("  unreachable\n}\n");
+
+    for func in &program.functions {
+        text.push('\n');
+        text.push_str(&format_llvm_function(func));
+    }
+
+    // The runtime library's exit just exits through the C library
+    text.push_str(&format!(
+        "\ndefine internal void @{}(i32 %code) {{\n",
+        get_export_name("exit")
+    ));
+    text.push_str("  call void @exit(i32 %code)\n");
+    text.push_str("  unreachable\n}\n");
+
+    if program.calls("sleep") {
+        // The runtime library's sleep shows everything printed so far, then sleeps with nanosleep
+        // (where a negative number of milliseconds doesn't sleep at all)
+        text.push_str(&format!(
+            "\ndefine internal void @{}(i32 %ms) {{\n",
+            get_export_name("sleep")
+        ));
+        text.push_str("  %positive = icmp sgt i32 %ms, 0\n");
+        text.push_str("  %clamped = select i1 %positive, i32 %ms, i32 0\n");
+        text.push_str("  %secs = udiv i32 %clamped, 1000\n");
+        text.push_str("  %millis = urem i32 %clamped, 1000\n");
+        text.push_str("  %nanos = mul i32 %millis, 1000000\n");
+        text.push_str("  %time = alloca [2 x i64], align 8\n");
+        text.push_str("  %secs64 = zext i32 %secs to i64\n");
+        text.push_str("  store i64 %secs64, ptr %time, align 8\n");
+        text.push_str("  %nanos64 = zext i32 %nanos to i64\n");
+        text.push_str("  %nanos_ptr = getelementptr [2 x i64], ptr %time, i64 0, i64 1\n");
+        text.push_str("  store i64 %nanos64, ptr %nanos_ptr, align 8\n");
+        text.push_str("  call i32 @fflush(ptr null)\n");
+        text.push_str("  call i32 @nanosleep(ptr %time, ptr null)\n");
+        text.push_str("  ret void\n}\n");
+    }
+
+    if program.calls("fopen") {
+        // A file is given back as its file descriptor, which is duplicated so the FILE fopen made can be closed
+        // (or as -1, if it couldn't be opened)
+        text.push_str(&format!(
+            "\ndefine internal i32 @{}(ptr %path, ptr %mode) {{\n",
+            get_export_name("fopen")
+        ));
+        text.push_str("  %stream = call ptr @fopen(ptr %path, ptr %mode)\n");
+        text.push_str("  %failed = icmp eq ptr %stream, null\n");
+        text.push_str("  br i1 %failed, label %fail, label %opened\n");
+        text.push_str("fail:\n  ret i32 -1\n");
+        text.push_str("opened:\n");
+        text.push_str("  %fd = call i32 @fileno(ptr %stream)\n");
+        text.push_str("  %file = call i32 @dup(i32 %fd)\n");
+        text.push_str("  call i32 @fclose(ptr %stream)\n");
+        text.push_str("  ret i32 %file\n}\n");
+    }
+
+    if program.calls("fread_line") {
+        // A line is read a byte at a time, where every byte that fits is stored in the array, up to the newline,
+        // followed by a 0 if there's room for one. The number of bytes stored is given back,
+        // or -1 if the end of the file was reached before anything was read
+        text.push_str(&format!(
+            "\ndefine internal i32 @{}(i32 %file, ptr %line, i32 %len) {{\n",
+            get_export_name("fread_line")
+        ));
+        text.push_str("entry:\n");
+        text.push_str("  %byte = alloca i8\n");
+        text.push_str("  br label %next\n");
+        text.push_str("next:\n");
+        text.push_str(
+            "  %stored = phi i32 [0, %entry], [%stored, %fits], [%stored_next, %store]\n",
+        );
+        text.push_str("  %read_any = phi i1 [false, %entry], [true, %fits], [true, %store]\n");
+        text.push_str("  %num_read = call i64 @read(i32 %file, ptr %byte, i64 1)\n");
+        text.push_str("  %got_byte = icmp sgt i64 %num_read, 0\n");
+        text.push_str("  br i1 %got_byte, label %check, label %end\n");
+        text.push_str("check:\n");
+        text.push_str("  %char8 = load i8, ptr %byte\n");
+        text.push_str("  %char = zext i8 %char8 to i32\n");
+        text.push_str("  %newline = icmp eq i32 %char, 10\n");
+        text.push_str("  br i1 %newline, label %done, label %fits\n");
+        text.push_str("fits:\n");
+        text.push_str("  %room = icmp ult i32 %stored, %len\n");
+        text.push_str("  br i1 %room, label %store, label %next\n");
+        text.push_str("store:\n");
+        text.push_str("  %addr = getelementptr i32, ptr %line, i32 %stored\n");
+        text.push_str("  store i32 %char, ptr %addr\n");
+        text.push_str("  %stored_next = add i32 %stored, 1\n");
+        text.push_str("  br label %next\n");
+        text.push_str("end:\n");
+        text.push_str("  br i1 %read_any, label %done, label %eof\n");
+        text.push_str("eof:\n  ret i32 -1\n");
+        text.push_str("done:\n");
+        text.push_str("  %room_left = icmp ult i32 %stored, %len\n");
+        text.push_str("  br i1 %room_left, label %terminate, label %finish\n");
+        text.push_str("terminate:\n");
+        text.push_str("  %end_addr = getelementptr i32, ptr %line, i32 %stored\n");
+        text.push_str("  store i32 0, ptr %end_addr\n");
+        text.push_str("  br label %finish\n");
+        text.push_str("finish:\n  ret i32 %stored\n}\n");
+    }
+
+    if program.calls("fclose") {
+        text.push_str(&format!(
+            "\ndefine internal void @{}(i32 %file) {{\n",
+            get_export_name("fclose")
+        ));
+        text.push_str("  call i32 @close(i32 %file)\n");
+        text.push_str("  ret void\n}\n");
+    }
+
+    if program.calls("parse_int") {
+        // The characters of the string or int array (up to the first 0) are an optional minus sign followed by
+        // at least one digit, built up into a positive 64 bit number (which is big enough to tell when it no longer
+        // fits in an int). Anything else exits the program with an error message
+        text.push_str(&format!(
+            "\ndefine internal i32 @{}(ptr %text, i32 %len, i32 %size, i32 %line) {{\n",
+            get_export_name("parse_int")
+        ));
+        text.push_str("entry:\n");
+        text.push_str("  %bytes = icmp eq i32 %size, 1\n");
+        text.push_str("  br label %next\n");
+        text.push_str("next:\n");
+        text.push_str("  %index = phi i32 [0, %entry], [1, %minus], [%index_next, %digit]\n");
+        text.push_str("  %value = phi i64 [0, %entry], [0, %minus], [%value_next, %digit]\n");
+        text.push_str(
+            "  %negative = phi i1 [false, %entry], [true, %minus], [%negative, %digit]\n",
+        );
+        text.push_str("  %in_bounds = icmp slt i32 %index, %len\n");
+        text.push_str("  br i1 %in_bounds, label %load, label %end\n");
+        text.push_str("load:\n");
+        text.push_str("  br i1 %bytes, label %load_byte, label %load_word\n");
+        text.push_str("load_byte:\n");
+        text.push_str("  %byte_addr = getelementptr i8, ptr %text, i32 %index\n");
+        text.push_str("  %byte = load i8, ptr %byte_addr\n");
+        text.push_str("  %byte_char = zext i8 %byte to i32\n");
+        text.push_str("  br label %check\n");
+        text.push_str("load_word:\n");
+        text.push_str("  %word_addr = getelementptr i32, ptr %text, i32 %index\n");
+        text.push_str("  %word_char = load i32, ptr %word_addr\n");
+        text.push_str("  br label %check\n");
+        text.push_str("check:\n");
+        text.push_str("  %char = phi i32 [%byte_char, %load_byte], [%word_char, %load_word]\n");
+        text.push_str("  %is_end = icmp eq i32 %char, 0\n");
+        text.push_str("  br i1 %is_end, label %end, label %sign\n");
+        text.push_str("sign:\n");
+        text.push_str("  %first = icmp eq i32 %index, 0\n");
+        text.push_str("  %is_minus = icmp eq i32 %char, 45\n");
+        text.push_str("  %leading_minus = and i1 %first, %is_minus\n");
+        text.push_str("  br i1 %leading_minus, label %minus, label %check_digit\n");
+        text.push_str("minus:\n  br label %next\n");
+        text.push_str("check_digit:\n");
+        text.push_str("  %digit32 = sub i32 %char, 48\n");
+        text.push_str("  %is_digit = icmp ult i32 %digit32, 10\n");
+        text.push_str("  br i1 %is_digit, label %digit, label %invalid\n");
+        text.push_str("digit:\n");
+        text.push_str("  %digit64 = zext i32 %digit32 to i64\n");
+        text.push_str("  %shifted = mul i64 %value, 10\n");
+        text.push_str("  %value_next = add i64 %shifted, %digit64\n");
+        text.push_str("  %index_next = add i32 %index, 1\n");
+        text.push_str("  %fits = icmp ule i64 %value_next, 2147483648\n");
+        text.push_str("  br i1 %fits, label %next, label %too_large\n");
+        text.push_str("end:\n");
+        text.push_str("  %num_signs = zext i1 %negative to i32\n");
+        text.push_str("  %no_digits = icmp eq i32 %index, %num_signs\n");
+        text.push_str("  br i1 %no_digits, label %invalid, label %sign_result\n");
+        text.push_str("sign_result:\n");
+        text.push_str("  %result = trunc i64 %value to i32\n");
+        text.push_str("  br i1 %negative, label %negate, label %positive\n");
+        text.push_str("negate:\n");
+        text.push_str("  %negated = sub i32 0, %result\n");
+        text.push_str("  ret i32 %negated\n");
+        text.push_str("positive:\n");
+        text.push_str("  %positive_fits = icmp ult i64 %value, 2147483648\n");
+        text.push_str("  br i1 %positive_fits, label %done, label %too_large\n");
+        text.push_str("done:\n  ret i32 %result\n");
+        text.push_str("invalid:\n");
+        text.push_str(&format!(
+            "  call void @{}(ptr @parse_int_invalid, i32 %line)\n",
+            RUNTIME_ERROR_LABEL
+        ));
+        text.push_str("  unreachable\n");
+        text.push_str("too_large:\n");
+        text.push_str(&format!(
+            "  call void @{}(ptr @parse_int_too_large, i32 %line)\n",
+            RUNTIME_ERROR_LABEL
+        ));
+        text.push_str("  unreachable\n}\n");
+    }
+
+    if program.calls("to_string") {
+        // The digits of the number are counted first (so an array that's too short can exit the program before
+        // anything is written to it), then written from last to first, followed by a 0 if there's room for one
+        text.push_str(&format!(
+            "\ndefine internal i32 @{}(i32 %value, ptr %chars, i32 %len, i32 %line) {{\n",
+            get_export_name("to_string")
+        ));
+        text.push_str("entry:\n");
+        text.push_str("  %negative = icmp slt i32 %value, 0\n");
+        text.push_str("  %negated = sub i32 0, %value\n");
+        text.push_str("  %magnitude = select i1 %negative, i32 %negated, i32 %value\n");
+        text.push_str("  %sign = zext i1 %negative to i32\n");
+        text.push_str("  br label %count\n");
+        text.push_str("count:\n");
+        text.push_str("  %count_rest = phi i32 [%magnitude, %entry], [%count_rest_next, %count]\n");
+        text.push_str("  %counted = phi i32 [%sign, %entry], [%num_chars, %count]\n");
+        text.push_str("  %num_chars = add i32 %counted, 1\n");
+        text.push_str("  %count_rest_next = udiv i32 %count_rest, 10\n");
+        text.push_str("  %more_to_count = icmp ne i32 %count_rest_next, 0\n");
+        text.push_str("  br i1 %more_to_count, label %count, label %check\n");
+        text.push_str("check:\n");
+        text.push_str("  %too_short = icmp sgt i32 %num_chars, %len\n");
+        text.push_str("  br i1 %too_short, label %error, label %check_sign\n");
+        text.push_str("check_sign:\n");
+        text.push_str("  br i1 %negative, label %write_sign, label %write\n");
+        text.push_str("write_sign:\n");
+        text.push_str("  store i32 45, ptr %chars\n");
+        text.push_str("  br label %write\n");
+        text.push_str("write:\n");
+        text.push_str(
+            "  %index = phi i32 [%num_chars, %check_sign], [%num_chars, %write_sign], [%index_next, %write]\n",
+        );
+        text.push_str(
+            "  %rest = phi i32 [%magnitude, %check_sign], [%magnitude, %write_sign], [%rest_next, %write]\n",
+        );
+        text.push_str("  %index_next = sub i32 %index, 1\n");
+        text.push_str("  %digit = urem i32 %rest, 10\n");
+        text.push_str("  %char = add i32 %digit, 48\n");
+        text.push_str("  %addr = getelementptr i32, ptr %chars, i32 %index_next\n");
+        text.push_str("  store i32 %char, ptr %addr\n");
+        text.push_str("  %rest_next = udiv i32 %rest, 10\n");
+        text.push_str("  %more_to_write = icmp ne i32 %rest_next, 0\n");
+        text.push_str("  br i1 %more_to_write, label %write, label %written\n");
+        text.push_str("written:\n");
+        text.push_str("  %room = icmp slt i32 %num_chars, %len\n");
+        text.push_str("  br i1 %room, label %terminate, label %done\n");
+        text.push_str("terminate:\n");
+        text.push_str("  %end_addr = getelementptr i32, ptr %chars, i32 %num_chars\n");
+        text.push_str("  store i32 0, ptr %end_addr\n");
+        text.push_str("  br label %done\n");
+        text.push_str("done:\n  ret i32 %num_chars\n");
+        text.push_str("error:\n");
+        text.push_str(&format!(
+            "  call void @{}(ptr @to_string_too_short, i32 %line)\n",
+            RUNTIME_ERROR_LABEL
+        ));
+        text.push_str("  unreachable\n}\n");
+    }
+
+    // Every runtime error prints its message, along with the line it happened on, and exits with an exit code of 1
+    text.push_str(&format!(
+        "\ndefine internal void @{}(ptr %message, i32 %line) noreturn {{\n",
+        RUNTIME_ERROR_LABEL
+    ));
+    text.push_str("  call i32 (ptr, ...) @printf(ptr %message, i32 %line)\n");
+    text.push_str("  call void @exit(i32 1)\n");
+    text.push_str("  unreachable\n}\n");
+
+    return text;
+}
+
+// Get a single function as LLVM IR
+fn format_llvm_function(func: &IrFunction) -> String {
+    let mut gen = LlvmFunctionGen {
+        func: func,
+        text: String::from(""),
+        num_values: 0,
+        terminated: false,
+    };
+
+    let params: Vec<String> = func
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| format!("{} %arg{}", get_llvm_type(func.temps[*param]), i))
+        .collect();
+    gen.text.push_str(&format!(
+        "define {} @{}({}) {{\n",
+        get_llvm_return_type(func.returns.len()),
+        get_export_name(&func.name),
+        params.join(", ")
+    ));
+
+    // Give every temporary and stack slot its place on the stack, and store each parameter in its temporary
+    for (temp, temp_type) in func.temps.iter().enumerate() {
+        gen.line(&format!(
+            "{} = alloca {}",
+            get_temp_name(func, temp),
+            get_llvm_type(*temp_type)
+        ));
+    }
+    for (slot, size) in func.slots.iter().enumerate() {
+        gen.line(&format!("%slot{} = alloca [{} x i8], align 8", slot, size));
+    }
+    for (i, param) in func.params.iter().enumerate() {
+        let param_type = get_llvm_type(func.temps[*param]);
+        gen.line(&format!(
+            "store {} %arg{}, ptr {}",
+            param_type,
+            i,
+            get_temp_name(func, *param)
+        ));
+    }
+
+    for instr in &func.body {
+        gen.gen_instr(instr);
+    }
+
+    // Every body ends by returning or panicking, but LLVM needs to be told that the end can't be reached
+    if !gen.terminated {
+        gen.line("unreachable");
+    }
+    gen.text.push_str("}\n");
+
+    return gen.text;
+}
+
+// Everything needed to generate the body of one function
+struct LlvmFunctionGen<'a> {
+    func: &'a IrFunction,
+    text: String,
+    // How many SSA values (and unreachable blocks) have been created, which is used to give each one a unique name
+    num_values: usize,
+    // Whether the current basic block has ended (with a jump, branch or return)
+    terminated: bool,
+}
+
+impl LlvmFunctionGen<'_> {
+    fn gen_instr(&mut self, instr: &Instr) {
+        // A label starts a new basic block, which needs to be explicitly jumped to if we'd otherwise fall through to it
+        if let Instr::Label(label) = instr {
+            if !self.terminated {
+                self.line(&format!("br label %L{}", label));
+            }
+            self.text.push_str(&format!("L{}:\n", label));
+            self.terminated = false;
+            return;
+        }
+
+        // Anything after the end of a basic block (but before the next label) can't be reached,
+        // but still needs to be in a basic block of its own
+        if self.terminated && !matches!(instr, Instr::Line(_)) {
+            self.num_values += 1;
+            self.text.push_str(&format!("dead{}:\n", self.num_values));
+            self.terminated = false;
+        }
+
+        match instr {
+            Instr::Const { dest, value } => {
+                self.line(&format!(
+                    "store i32 {}, ptr {}",
+                    value,
+                    get_temp_name(self.func, *dest)
+                ));
+            }
+            Instr::Copy { dest, src } => {
+                let value = self.gen_load_temp(*src);
+                self.gen_store_temp(&value, *dest);
+            }
+            Instr::BinOp { op, dest, lhs, rhs } => {
+                let lhs = self.gen_load_temp(*lhs);
+                let rhs = self.gen_load_temp(*rhs);
+                let result = self.gen_binop(*op, &lhs, &rhs);
+                self.gen_store_temp(&result, *dest);
+            }
+            Instr::UnOp { op, dest, src } => {
+                let src = self.gen_load_temp(*src);
+                let result = match op {
+                    UnOp::Neg => self.value(&format!("sub i32 0, {}", src)),
+                    // A bool is always 0 or 1, so flipping the lowest bit flips the bool
+                    UnOp::Not => self.value(&format!("xor i32 {}, 1", src)),
+                };
+                self.gen_store_temp(&result, *dest);
+            }
+            Instr::Select {
+                dest,
+                cond,
+                if_true,
+                if_false,
+            } => {
+                let cond = self.gen_load_temp(*cond);
+                let cond = self.value(&format!("icmp ne i32 {}, 0", cond));
+                let if_true = self.gen_load_temp(*if_true);
+                let if_false = self.gen_load_temp(*if_false);
+                let value_type = get_llvm_type(self.func.temps[*dest]);
+                let result = self.value(&format!(
+                    "select i1 {}, {} {}, {} {}",
+                    cond, value_type, if_true, value_type, if_false
+                ));
+                self.gen_store_temp(&result, *dest);
+            }
+            Instr::SlotAddr { dest, slot } => {
+                self.gen_store_temp(&format!("%slot{}", slot), *dest);
+            }
+            Instr::GlobalAddr { dest, global } => {
+                self.gen_store_temp(&format!("@{}", get_global_label(*global)), *dest);
+            }
+            Instr::ElementAddr {
+                dest,
+                base,
+                index,
+                scale,
+            } => {
+                let base = self.gen_load_temp(*base);
+                let index = self.gen_load_temp(*index);
+                let index = self.value(&format!("sext i32 {} to i64", index));
+                let offset = self.value(&format!("mul i64 {}, {}", index, scale));
+                let addr = self.value(&format!("getelementptr i8, ptr {}, i64 {}", base, offset));
+                self.gen_store_temp(&addr, *dest);
+            }
+            Instr::Load { dest, addr, width } => {
+                let addr = self.gen_load_temp(*addr);
+                let value = match width {
+                    Width::Word => self.value(&format!("load i32, ptr {}", addr)),
+                    Width::Byte => {
+                        let byte = self.value(&format!("load i8, ptr {}", addr));
+                        self.value(&format!("zext i8 {} to i32", byte))
+                    }
+                };
+                self.gen_store_temp(&value, *dest);
+            }
+            Instr::Store { src, addr, width } => {
+                let addr = self.gen_load_temp(*addr);
+                let value = self.gen_load_temp(*src);
+                match width {
+                    Width::Word => self.line(&format!("store i32 {}, ptr {}", value, addr)),
+                    Width::Byte => {
+                        let byte = self.value(&format!("trunc i32 {} to i8", value));
+                        self.line(&format!("store i8 {}, ptr {}", byte, addr));
+                    }
+                }
+            }
+            Instr::Call {
+                dests,
+                func: callee,
+                args,
+            } => {
+                let args = self.gen_args(args);
+                let call = format!(
+                    "call {} @{}({})",
+                    get_llvm_return_type(dests.len()),
+                    get_export_name(callee),
+                    args
+                );
+
+                match dests.len() {
+                    0 => self.line(&call),
+                    1 => {
+                        let result = self.value(&call);
+                        self.gen_store_temp(&result, dests[0]);
+                    }
+                    // An array is returned as a struct holding its address and its length
+                    _ => {
+                        let result = self.value(&call);
+                        for (i, dest) in dests.iter().enumerate() {
+                            let value = self.value(&format!(
+                                "extractvalue {} {}, {}",
+                                get_llvm_return_type(dests.len()),
+                                result,
+                                i
+                            ));
+                            self.gen_store_temp(&value, *dest);
+                        }
+                    }
+                }
+            }
+            Instr::Print { file, format, args } => {
+                let mut args = self.gen_args(args);
+                if !args.is_empty() {
+                    args = format!(", {}", args);
+                }
+                match file {
+                    Some(file) => {
+                        let file = self.gen_load_temp(*file);
+                        self.line(&format!(
+                            "call i32 (i32, ptr, ...) @dprintf(i32 {}, ptr @{}{})",
+                            file,
+                            get_global_label(*format),
+                            args
+                        ));
+                    }
+                    None => self.line(&format!(
+                        "call i32 (ptr, ...) @printf(ptr @{}{})",
+                        get_global_label(*format),
+                        args
+                    )),
+                }
+            }
+            Instr::Jump(label) => {
+                self.line(&format!("br label %L{}", label));
+                self.terminated = true;
+            }
+            Instr::Branch {
+                cond,
+                if_true,
+                if_false,
+            } => {
+                let cond = self.gen_load_temp(*cond);
+                let cond = self.value(&format!("icmp ne i32 {}, 0", cond));
+                self.line(&format!(
+                    "br i1 {}, label %L{}, label %L{}",
+                    cond, if_true, if_false
+                ));
+                self.terminated = true;
+            }
+            Instr::Return(values) => {
+                match values.len() {
+                    0 => self.line("ret void"),
+                    1 => {
+                        let value = self.gen_load_temp(values[0]);
+                        self.line(&format!("ret i32 {}", value));
+                    }
+                    _ => {
+                        // Build up the struct holding the array's address and length, one field at a time
+                        let struct_type = get_llvm_return_type(values.len());
+                        let mut result = String::from("undef");
+                        for (i, value) in values.iter().enumerate() {
+                            let value_type = get_llvm_type(self.func.temps[*value]);
+                            let value = self.gen_load_temp(*value);
+                            result = self.value(&format!(
+                                "insertvalue {} {}, {} {}, {}",
+                                struct_type, result, value_type, value, i
+                            ));
+                        }
+                        self.line(&format!("ret {} {}", struct_type, result));
+                    }
+                }
+                self.terminated = true;
+            }
+            Instr::Panic { message, line } => {
+                // Exit the program through the runtime library, which prints the error message
+                self.line(&format!(
+                    "call void @{}(ptr @{}, i32 {})",
+                    RUNTIME_ERROR_LABEL,
+                    get_global_label(*message),
+                    line
+                ));
+                self.line("unreachable");
+                self.terminated = true;
+            }
+            // Keep track of where everything came from, to make the output easier to follow
+            Instr::Line(line) => self.line(&format!("; line {}", line)),
+            Instr::Label(_) => {}
+        }
+    }
+
+    // Combine two values with the given operator, returning the result
+    fn gen_binop(&mut self, op: BinOp, lhs: &str, rhs: &str) -> String {
+        let cond = match op {
+            BinOp::Add => return self.value(&format!("add i32 {}, {}", lhs, rhs)),
+            BinOp::Sub => return self.value(&format!("sub i32 {}, {}", lhs, rhs)),
+            BinOp::Mul => return self.value(&format!("mul i32 {}, {}", lhs, rhs)),
+            BinOp::Div | BinOp::Rem => return self.gen_division(op == BinOp::Rem, lhs, rhs),
+            BinOp::Eq => "eq",
+            BinOp::Ne => "ne",
+            BinOp::Lt => "slt",
+            BinOp::Gt => "sgt",
+            BinOp::Le => "sle",
+            BinOp::Ge => "sge",
+            BinOp::ULt => "ult",
+            BinOp::AddOverflows => return self.gen_overflows("sadd", lhs, rhs),
+            BinOp::SubOverflows => return self.gen_overflows("ssub", lhs, rhs),
+            BinOp::MulOverflows => return self.gen_overflows("smul", lhs, rhs),
+            // There's no intrinsic for dividing, which only overflows for the smallest int divided by -1
+            BinOp::DivOverflows => {
+                let is_smallest = self.value(&format!("icmp eq i32 {}, -2147483648", lhs));
+                let is_neg_one = self.value(&format!("icmp eq i32 {}, -1", rhs));
+                let overflowed = self.value(&format!("and i1 {}, {}", is_smallest, is_neg_one));
+                return self.value(&format!("zext i1 {} to i32", overflowed));
+            }
+        };
+
+        // The comparison gives an i1, which is widened to 0 or 1
+        let result = self.value(&format!("icmp {} i32 {}, {}", cond, lhs, rhs));
+        return self.value(&format!("zext i1 {} to i32", result));
+    }
+
+    // Do some arithmetic with the given overflow intrinsic, returning 1 if it overflowed and 0 otherwise
+    fn gen_overflows(&mut self, intrinsic: &str, lhs: &str, rhs: &str) -> String {
+        let result = self.value(&format!(
+            "call {{i32, i1}} @llvm.{}.with.overflow.i32(i32 {}, i32 {})",
+            intrinsic, lhs, rhs
+        ));
+        let overflowed = self.value(&format!("extractvalue {{i32, i1}} {}, 1", result));
+        return self.value(&format!("zext i1 {} to i32", overflowed));
+    }
+
+    // Divide one value by another, returning either the quotient or the remainder
+    fn gen_division(&mut self, remainder: bool, lhs: &str, rhs: &str) -> String {
+        // sdiv and srem are undefined when the quotient doesn't fit (i.e. INT_MIN / -1), so dividing by -1 is done
+        // by hand instead, wrapping around just like it does on ARM64 (division by 0 is already checked for)
+        let is_neg_one = self.value(&format!("icmp eq i32 {}, -1", rhs));
+        let divisor = self.value(&format!("select i1 {}, i32 1, i32 {}", is_neg_one, rhs));
+
+        let (op, by_neg_one) = if remainder {
+            ("srem", String::from("0"))
+        } else {
+            ("sdiv", self.value(&format!("sub i32 0, {}", lhs)))
+        };
+        let result = self.value(&format!("{} i32 {}, {}", op, lhs, divisor));
+        return self.value(&format!(
+            "select i1 {}, i32 {}, i32 {}",
+            is_neg_one, by_neg_one, result
+        ));
+    }
+
+    // Load each argument of a call, returning them as a list of typed arguments
+    fn gen_args(&mut self, args: &Vec<Temp>) -> String {
+        let mut typed_args = vec![];
+        for arg in args {
+            let value = self.gen_load_temp(*arg);
+            typed_args.push(format!(
+                "{} {}",
+                get_llvm_type(self.func.temps[*arg]),
+                value
+            ));
+        }
+        return typed_args.join(", ");
+    }
+
+    // Load the value of a temporary, returning the SSA value it was loaded into
+    fn gen_load_temp(&mut self, temp: Temp) -> String {
+        return self.value(&format!(
+            "load {}, ptr {}",
+            get_llvm_type(self.func.temps[temp]),
+            get_temp_name(self.func, temp)
+        ));
+    }
+
+    // Store a value into a temporary
+    fn gen_store_temp(&mut self, value: &str, temp: Temp) {
+        self.line(&format!(
+            "store {} {}, ptr {}",
+            get_llvm_type(self.func.temps[temp]),
+            value,
+            get_temp_name(self.func, temp)
+        ));
+    }
+
+    // Write an instruction whose result is put in a new SSA value, returning the value's name
+    fn value(&mut self, instr: &str) -> String {
+        self.num_values += 1;
+        let value = format!("%v{}", self.num_values);
+        self.line(&format!("{} = {}", value, instr));
+        return value;
+    }
+
+    // Write a line of the function's body
+    fn line(&mut self, line: &str) {
+        self.text.push_str(&format!("  {}\n", line));
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the name of the alloca holding a temporary, which includes the name of the variable it holds (if it holds one)
+fn get_temp_name(func: &IrFunction, temp: Temp) -> String {
+    return match func.temp_names.get(&temp) {
+        None => format!("%t{}", temp),
+        Some(name) => format!("%{}.{}", name, temp),
+    };
+}
+
+fn get_llvm_type(temp_type: IrType) -> &'static str {
+    return match temp_type {
+        IrType::Int => "i32",
+        IrType::Ptr => "ptr",
+    };
+}
+
+// Get the type returned by a function with the given number of return values,
+// where an array (the only thing with two return values) is returned as a struct of its address and length
+fn get_llvm_return_type(num_returns: usize) -> &'static str {
+    return match num_returns {
+        0 => "void",
+        1 => "i32",
+        _ => "{ ptr, i32 }",
+    };
+}
+
+// Convert bytes into the form expected by an LLVM string constant, where anything other than
+// printable ASCII (and quotes and backslashes) is written as a two digit hex escape
+fn escape_llvm_string(bytes: &[u8]) -> String {
+    let mut escaped = String::from("");
+
+    for byte in bytes {
+        match byte {
+            b' '..=b'~' if *byte != b'"' && *byte != b'\\' => escaped.push(*byte as char),
+            _ => escaped.push_str(&format!("\\{:02X}", byte)),
+        }
+    }
+
+    return escaped;
+}
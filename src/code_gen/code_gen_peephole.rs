@@ -0,0 +1,119 @@
+use crate::code_gen::code_gen_data::*;
+
+// -----------------------------------------------------------------------------------------
+// PEEPHOLE OPTIMIZER
+// -----------------------------------------------------------------------------------------
+
+// Clean up wasteful pairs of adjacent instructions in the generated assembly,
+// repeating until there's nothing left to clean up (since each change can make another one possible)
+pub fn optimize_asm(mut lines: Vec<AsmLine>) -> Vec<AsmLine> {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut optimized: Vec<AsmLine> = vec![];
+
+        for line in lines {
+            let AsmLine::Instr(instr) = line else {
+                optimized.push(line);
+                continue;
+            };
+
+            // Only instructions directly after another instruction (not counting comments) can be combined with it
+            // (anything could jump to a label in between them)
+            let prev = match optimized
+                .iter()
+                .rev()
+                .find(|prev| !matches!(prev, AsmLine::Comment(_)))
+            {
+                Some(AsmLine::Instr(prev)) => Some(prev),
+                _ => None,
+            };
+
+            match optimize_pair(prev, &instr) {
+                Some(replacement) => {
+                    changed = true;
+                    if let Some(replacement) = replacement {
+                        optimized.push(AsmLine::Instr(replacement));
+                    }
+                }
+                None => optimized.push(AsmLine::Instr(instr)),
+            }
+        }
+
+        lines = optimized;
+    }
+
+    return lines;
+}
+
+// Work out what an instruction can be replaced by, given the instruction before it, returning:
+// - None if the instruction can't be improved
+// - Some(None) if the instruction can be removed entirely
+// - Some(Some(replacement)) if the instruction can be replaced by a cheaper one
+fn optimize_pair(prev: Option<&AsmInstr>, instr: &AsmInstr) -> Option<Option<AsmInstr>> {
+    // mov wX, wX does nothing
+    if is_reg_mov(instr) && instr.operands[0] == instr.operands[1] {
+        return Some(None);
+    }
+
+    let prev = prev?;
+
+    // mov wX, wY followed by mov wY, wX does nothing the second time, since wX and wY already hold the same value
+    if is_reg_mov(prev)
+        && is_reg_mov(instr)
+        && prev.operands[0] == instr.operands[1]
+        && prev.operands[1] == instr.operands[0]
+    {
+        return Some(None);
+    }
+
+    // str wX, [addr] followed by ldr wY, [addr] loads the value that's still sitting in wX,
+    // so there's no need to go back to memory for it
+    if prev.op == "str"
+        && instr.op == "ldr"
+        && is_simple_access(prev)
+        && is_simple_access(instr)
+        && prev.operands[1] == instr.operands[1]
+        && get_reg_size(&prev.operands[0]) == get_reg_size(&instr.operands[0])
+    {
+        return Some(Some(AsmInstr::new(
+            "mov",
+            vec![&instr.operands[0], &prev.operands[0]],
+        )));
+    }
+
+    return None;
+}
+
+// Return true if the instruction moves one register into another
+fn is_reg_mov(instr: &AsmInstr) -> bool {
+    return instr.op == "mov"
+        && instr.operands.len() == 2
+        && instr
+            .operands
+            .iter()
+            .all(|operand| get_reg_size(operand).is_some());
+}
+
+// Return true if the instruction is a load or store to an address that doesn't change the base register
+// (like "ldr w9, [sp, 8]", but not "ldr w9, [sp], 16" or "ldr w9, [sp, 16]!")
+fn is_simple_access(instr: &AsmInstr) -> bool {
+    return instr.operands.len() == 2
+        && get_reg_size(&instr.operands[0]).is_some()
+        && instr.operands[1].starts_with('[')
+        && instr.operands[1].ends_with(']');
+}
+
+// Get the size in bytes of a general purpose register (like w9 or x29), or None if the operand isn't one
+fn get_reg_size(operand: &str) -> Option<i32> {
+    let size = match operand.chars().next() {
+        Some('w') => 4,
+        Some('x') => 8,
+        _ => return None,
+    };
+
+    return match operand[1..].parse::<u32>() {
+        Ok(reg) if reg <= 30 => Some(size),
+        _ => None,
+    };
+}
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use crate::ir::ir_data::*;
+use crate::ir::ir_liveness::{get_interference_graph, get_live_ranges};
+
+// -----------------------------------------------------------------------------------------
+// REGISTER ALLOCATION
+// -----------------------------------------------------------------------------------------
+
+// Decide which temporaries of a function are kept in a register rather than on the stack, given the number of
+// registers there are to keep them in (numbered from 0) and the temporaries that don't need one, by linear scan: going through the temporaries in the order
+// they become live, each one takes a register that isn't held by anything still live. When there isn't one, whichever
+// of it and the temporaries holding a register stays live the longest is left on the stack instead.
+// The registers are callee-saved, so a temporary can stay in its register across a call
+pub fn allocate_registers(
+    func: &IrFunction,
+    num_regs: usize,
+    fixed: &[bool],
+) -> Vec<Option<usize>> {
+    let ranges = get_live_ranges(func);
+    let mut regs: Vec<Option<usize>> = vec![None; func.temps.len()];
+
+    let mut order: Vec<Temp> = (0..func.temps.len())
+        .filter(|temp| ranges[*temp].is_some() && !fixed[*temp])
+        .collect();
+    order.sort_by_key(|temp| get_start(&ranges, *temp));
+
+    // The temporaries currently holding a register, and the registers nothing is holding
+    // (with the lowest numbered register at the end, so it's taken first)
+    let mut active: Vec<Temp> = vec![];
+    let mut free: Vec<usize> = (0..num_regs).rev().collect();
+
+    for temp in order {
+        // Give back the registers of anything that's no longer live
+        // (only once it's completely finished with, since it can be used by the instruction this one is set by)
+        let start = get_start(&ranges, temp);
+        active.retain(|other| {
+            if get_end(&ranges, *other) < start {
+                free.push(regs[*other].unwrap());
+                return false;
+            }
+            return true;
+        });
+        free.sort_by(|a, b| b.cmp(a));
+
+        if let Some(reg) = free.pop() {
+            regs[temp] = Some(reg);
+            active.push(temp);
+            continue;
+        }
+
+        let furthest = active
+            .iter()
+            .copied()
+            .max_by_key(|other| get_end(&ranges, *other));
+        if let Some(other) = furthest {
+            if get_end(&ranges, other) > get_end(&ranges, temp) {
+                regs[temp] = regs[other].take();
+                active.retain(|active_temp| *active_temp != other);
+                active.push(temp);
+            }
+        }
+    }
+
+    return regs;
+}
+
+// Decide which temporaries of a function are kept in a register rather than on the stack (like allocate_registers),
+// by coloring the interference graph: any two temporaries joined by a copy that don't interfere are merged
+// (so they share a register and the copy does nothing), as long as that can't stop the graph being colored.
+// Then, temporaries are taken out of the graph one at a time, each one with fewer neighbours than there are registers
+// if there is one, or otherwise the one with the most neighbours. Putting them back in the opposite order,
+// each one takes the lowest numbered register none of its neighbours have, or is left on the stack if there isn't one
+pub fn color_registers(func: &IrFunction, num_regs: usize, fixed: &[bool]) -> Vec<Option<usize>> {
+    let mut graph = get_interference_graph(func);
+
+    // Only temporaries that are actually set or used need a register (unless they don't need one at all,
+    // in which case they're taken out of the graph)
+    let mut present = vec![false; func.temps.len()];
+    for temp in func
+        .params
+        .iter()
+        .copied()
+        .chain(func.body.iter().flat_map(|instr| instr.get_defs()))
+        .chain(func.body.iter().flat_map(|instr| instr.get_uses()))
+    {
+        present[temp] = !fixed[temp];
+    }
+    for temp in 0..func.temps.len() {
+        if fixed[temp] {
+            for neighbour in graph[temp].clone() {
+                graph[neighbour].remove(&temp);
+            }
+            graph[temp].clear();
+        }
+    }
+
+    // Merge the two sides of each copy into a single temporary wherever possible (coalescing),
+    // keeping track of which temporary each one was merged into, going over the copies again whenever
+    // a merge has been made, since it can make more possible
+    let mut merged: Vec<Temp> = (0..func.temps.len()).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for instr in &func.body {
+            let Instr::Copy { dest, src } = instr else {
+                continue;
+            };
+            let dest = get_merged(&merged, *dest);
+            let src = get_merged(&merged, *src);
+            if dest == src || graph[dest].contains(&src) || !can_merge(&graph, num_regs, dest, src)
+            {
+                continue;
+            }
+
+            // Everything that interferes with the source now interferes with the destination instead
+            for neighbour in graph[src].clone() {
+                graph[neighbour].remove(&src);
+                graph[neighbour].insert(dest);
+                graph[dest].insert(neighbour);
+            }
+            graph[src].clear();
+            merged[src] = dest;
+            present[src] = false;
+            changed = true;
+        }
+    }
+
+    // Take every temporary out of the graph, keeping track of how many neighbours each one has left
+    let mut degrees: Vec<usize> = graph.iter().map(|neighbours| neighbours.len()).collect();
+    let mut removed = present
+        .iter()
+        .map(|present| !present)
+        .collect::<Vec<bool>>();
+    let mut stack = vec![];
+    while let Some(temp) = get_next_removal(&degrees, &removed, num_regs) {
+        removed[temp] = true;
+        for neighbour in &graph[temp] {
+            degrees[*neighbour] -= 1;
+        }
+        stack.push(temp);
+    }
+
+    // and put them back in, giving each one a register
+    let mut colors: Vec<Option<usize>> = vec![None; func.temps.len()];
+    while let Some(temp) = stack.pop() {
+        let taken: HashSet<usize> = graph[temp]
+            .iter()
+            .filter_map(|neighbour| colors[*neighbour])
+            .collect();
+        colors[temp] = (0..num_regs).find(|reg| !taken.contains(reg));
+    }
+
+    return (0..func.temps.len())
+        .map(|temp| colors[get_merged(&merged, temp)])
+        .collect();
+}
+
+// Get the temporary a temporary has been merged into, following it through any merges made after that
+fn get_merged(merged: &[Temp], mut temp: Temp) -> Temp {
+    while merged[temp] != temp {
+        temp = merged[temp];
+    }
+    return temp;
+}
+
+// Return true if merging two temporaries can't stop the graph being colored, which is the case when the merged
+// temporary would have fewer neighbours with at least as many neighbours as there are registers than there are registers
+// (since every other neighbour is sure to be taken out of the graph before it, leaving it with nothing in its way)
+fn can_merge(graph: &[HashSet<Temp>], num_regs: usize, a: Temp, b: Temp) -> bool {
+    let significant = graph[a]
+        .union(&graph[b])
+        .filter(|neighbour| graph[**neighbour].len() >= num_regs)
+        .count();
+    return significant < num_regs;
+}
+
+// Get the next temporary to take out of the graph, which is the first one with fewer neighbours
+// than there are registers (which is sure to get one), or else the one with the most neighbours
+// (which might still get one, if enough of its neighbours end up sharing registers)
+fn get_next_removal(degrees: &[usize], removed: &[bool], num_regs: usize) -> Option<Temp> {
+    let remaining = (0..degrees.len()).filter(|temp| !removed[*temp]);
+
+    if let Some(temp) = remaining.clone().find(|temp| degrees[*temp] < num_regs) {
+        return Some(temp);
+    }
+    return remaining.max_by_key(|temp| degrees[*temp]);
+}
+
+// Get the index of the first instruction a temporary is live at
+fn get_start(ranges: &[Option<(usize, usize)>], temp: Temp) -> usize {
+    return ranges[temp].unwrap().0;
+}
+
+// Get the index of the last instruction a temporary is live at
+fn get_end(ranges: &[Option<(usize, usize)>], temp: Temp) -> usize {
+    return ranges[temp].unwrap().1;
+}
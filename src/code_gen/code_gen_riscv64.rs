@@ -0,0 +1,815 @@
+use crate::ir::ir_data::*;
+
+use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::code_gen::code_gen_utils::{
+    gen_data, gen_entry_label, gen_func_label, gen_line, get_frame, get_func_label,
+    get_global_label, get_label_name, get_next_instr, get_return_label, COVERAGE_LABEL,
+    RUNTIME_ERROR_LABEL,
+};
+
+// -----------------------------------------------------------------------------------------
+// RISC-V (RV64GC, LINUX)
+// -----------------------------------------------------------------------------------------
+
+// Assembly is written for the standard RV64 calling convention, and linked against the C library
+// (which provides printf and exit). Every temporary is loaded into a scratch register (t0 - t2) before it's used
+// and stored back to its home (a callee-saved register or the stack) afterwards, unless it's a constant
+// that can be used as an immediate operand, just like on ARM64
+
+// The registers the first 8 arguments of a function are passed in
+const ARG_REGS: [&str; 8] = ["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+
+// The registers printf's first 7 format arguments are passed in (its format string goes in a0)
+const PRINT_ARG_REGS: [&str; 7] = ["a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+
+// The registers a function's return values are passed back in
+const RETURN_REGS: [&str; 2] = ["a0", "a1"];
+
+// The callee-saved registers temporaries can be kept in (s0 is the frame pointer)
+const SAVED_REGS: [&str; 11] = [
+    "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+];
+
+pub struct Riscv64Generator {
+    pub target: &'static TargetInfo,
+}
+
+impl CodeGenerator for Riscv64Generator {
+    fn gen_globals(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        gen_data(writer, self.target, program);
+    }
+
+    fn gen_asm_main(&self, writer: &mut ASMWriter, main_returns_int: bool) {
+        // The C library calls main, so save the return address (keeping the stack 16 byte aligned)
+        gen_entry_label(writer, self.target);
+        writer.write(&format!("{}:", self.target.entry_symbol));
+        writer.write("        addi    sp, sp, -16");
+        writer.write("        sd      ra, 8(sp)");
+
+        // Call the compilee's main function
+        writer.write(&format!(
+            "        call    {}",
+            get_func_label(self.target, "main")
+        ));
+
+        // If main returns an int, hold onto it so printing the last newline can't trample it
+        if main_returns_int {
+            writer.write("        sw      a0, 0(sp)");
+        }
+
+        writer.write("        lla     a0, last_newline");
+        writer.write("        call    printf");
+
+        // Exit the program (through the C library, so anything printf is still holding onto gets written out)
+        if main_returns_int {
+            writer.write("        lw      a0, 0(sp)");
+        } else {
+            writer.write("        li      a0, 0");
+        }
+        gen_exit(writer);
+    }
+
+    fn gen_function(&self, writer: &mut ASMWriter, func: &IrFunction) {
+        let frame = get_frame(func, self.target, writer.graph_coloring);
+
+        // Write function entry label, save the return address and frame pointer
+        // (leaving the frame pointer pointing at the stack pointer we were called with), and allocate the stack frame
+        gen_func_label(writer, self.target, func);
+        writer.write("        addi    sp, sp, -16");
+        writer.write("        sd      ra, 8(sp)");
+        writer.write("        sd      s0, 0(sp)");
+        writer.write("        addi    s0, sp, 16");
+        if frame.size != 0 {
+            gen_add_imm(writer, "sp", "sp", -frame.size);
+        }
+        for (reg, offset) in &frame.saved_regs {
+            gen_frame_access(writer, "sd", SAVED_REGS[*reg], *offset);
+        }
+
+        // Store any parameters in their temporaries, where the first 8 are passed in the argument passing registers
+        // and the rest are passed on the stack, starting right where the frame pointer points
+        for (slot, param) in func.params.iter().enumerate() {
+            if slot < ARG_REGS.len() {
+                gen_store_temp(writer, func, &frame, ARG_REGS[slot], *param);
+            } else {
+                writer.write(&format!(
+                    "        {:<8}t0, {}(s0)",
+                    get_load_op(func.temps[*param]),
+                    (slot - ARG_REGS.len()) * 8
+                ));
+                gen_store_temp(writer, func, &frame, "t0", *param);
+            }
+        }
+
+        for (i, instr) in func.body.iter().enumerate() {
+            gen_instr(
+                writer,
+                self.target,
+                func,
+                &frame,
+                instr,
+                get_next_instr(&func.body, i),
+            );
+        }
+
+        // Write function exit label, deallocate the stack frame, restore the return address and frame pointer and return
+        writer.write(&format!("{}:", get_return_label(self.target, func)));
+        for (reg, offset) in &frame.saved_regs {
+            gen_frame_access(writer, "ld", SAVED_REGS[*reg], *offset);
+        }
+        writer.write("        addi    sp, s0, -16");
+        writer.write("        ld      ra, 8(sp)");
+        writer.write("        ld      s0, 0(sp)");
+        writer.write("        addi    sp, sp, 16");
+        writer.write("        ret");
+    }
+
+    fn get_saved_reg_name(&self, reg: usize, _temp_type: IrType) -> String {
+        // Registers have the same name whatever the size of the value in them
+        return String::from(SAVED_REGS[reg]);
+    }
+
+    fn gen_coverage_routine(&self, writer: &mut ASMWriter, coverage: &Coverage) {
+        // Keep the return address, the exit code (in a0) and s1 safe
+        writer.write(&format!("\n{}:", COVERAGE_LABEL));
+        writer.write("        addi    sp, sp, -32");
+        writer.write("        sd      ra, 24(sp)");
+        writer.write("        sd      s1, 16(sp)");
+        writer.write("        sd      a0, 8(sp)");
+
+        // If the file can't be opened, there's nothing we can do about it
+        writer.write(&format!(
+            "        lla     a0, {}",
+            get_global_label(coverage.file)
+        ));
+        writer.write(&format!(
+            "        lla     a1, {}",
+            get_global_label(coverage.mode)
+        ));
+        writer.write("        call    fopen");
+        writer.write(&format!("        beqz    a0, {}_done", COVERAGE_LABEL));
+        writer.write("        mv      s1, a0");
+
+        // Write the header, followed by the counters
+        for (data, size, count) in [
+            (coverage.header, 1, coverage.header_len),
+            (coverage.counters, 4, coverage.num_counters),
+        ] {
+            writer.write(&format!("        lla     a0, {}", get_global_label(data)));
+            writer.write(&format!("        li      a1, {}", size));
+            writer.write(&format!("        li      a2, {}", count));
+            writer.write("        mv      a3, s1");
+            writer.write("        call    fwrite");
+        }
+        writer.write("        mv      a0, s1");
+        writer.write("        call    fclose");
+
+        writer.write(&format!("{}_done:", COVERAGE_LABEL));
+        writer.write("        ld      a0, 8(sp)");
+        writer.write("        ld      s1, 16(sp)");
+        writer.write("        ld      ra, 24(sp)");
+        writer.write("        addi    sp, sp, 32");
+        writer.write("        ret");
+    }
+
+    fn gen_runtime_lib(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        // The return code is passed into exit and is already in a0, so exit through the C library
+        writer.write(&format!("\n{}:", get_func_label(self.target, "exit")));
+        if writer.coverage.is_some() {
+            writer.write(&format!("        call    {}", COVERAGE_LABEL));
+        }
+        writer.write("        tail    exit");
+
+        if program.calls("sleep") {
+            // The number of milliseconds to sleep for is passed in a0, and is split into the seconds and nanoseconds
+            // of a timespec on the stack for nanosleep (after showing everything printed so far),
+            // where a negative number of milliseconds doesn't sleep at all
+            writer.write(&format!("\n{}:", get_func_label(self.target, "sleep")));
+            writer.write("        addi    sp, sp, -32");
+            writer.write("        sd      ra, 24(sp)");
+            writer.write("        sd      s1, 16(sp)");
+            writer.write("        sgtz    t0, a0");
+            writer.write("        neg     t0, t0");
+            writer.write("        and     s1, a0, t0");
+            writer.write("        li      a0, 0");
+            writer.write("        call    fflush");
+            writer.write("        li      t0, 1000");
+            writer.write("        divuw   t1, s1, t0");
+            writer.write("        remuw   t2, s1, t0");
+            writer.write("        li      t0, 1000000");
+            writer.write("        mul     t2, t2, t0");
+            writer.write("        sd      t1, 0(sp)");
+            writer.write("        sd      t2, 8(sp)");
+            writer.write("        mv      a0, sp");
+            writer.write("        li      a1, 0");
+            writer.write("        call    nanosleep");
+            writer.write("        ld      s1, 16(sp)");
+            writer.write("        ld      ra, 24(sp)");
+            writer.write("        addi    sp, sp, 32");
+            writer.write("        ret");
+        }
+
+        if program.calls("fopen") {
+            // The path and mode are passed in a0 and a1, which is where fopen wants them. A file is given back as its
+            // file descriptor, which is duplicated so the FILE fopen made can be closed (or as -1, if it couldn't be opened)
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fopen")));
+            writer.write("        addi    sp, sp, -32");
+            writer.write("        sd      ra, 24(sp)");
+            writer.write("        sd      s1, 16(sp)");
+            writer.write("        sd      s2, 8(sp)");
+            writer.write("        call    fopen");
+            writer.write("        mv      s1, a0");
+            writer.write("        li      s2, -1");
+            writer.write("        beqz    s1, 1f");
+            writer.write("        call    fileno");
+            writer.write("        call    dup");
+            writer.write("        mv      s2, a0");
+            writer.write("        mv      a0, s1");
+            writer.write("        call    fclose");
+            writer.write("1:");
+            writer.write("        mv      a0, s2");
+            writer.write("        ld      s2, 8(sp)");
+            writer.write("        ld      s1, 16(sp)");
+            writer.write("        ld      ra, 24(sp)");
+            writer.write("        addi    sp, sp, 32");
+            writer.write("        ret");
+        }
+
+        if program.calls("fread_line") {
+            // The file is passed in a0, and the array the line is read into in a1 (its address) and a2 (its length).
+            // The line is read a byte at a time (into the bottom of the stack frame), where every byte that fits
+            // is stored in the array, up to the newline, followed by a 0 if there's room for one. The number of bytes
+            // stored is given back, or -1 if the end of the file was reached before anything was read
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fread_line")));
+            writer.write("        addi    sp, sp, -64");
+            writer.write("        sd      ra, 56(sp)");
+            writer.write("        sd      s1, 48(sp)");
+            writer.write("        sd      s2, 40(sp)");
+            writer.write("        sd      s3, 32(sp)");
+            writer.write("        sd      s4, 24(sp)");
+            writer.write("        sd      s5, 16(sp)");
+            writer.write("        mv      s1, a0");
+            writer.write("        mv      s2, a1");
+            writer.write("        mv      s3, a2");
+            writer.write("        li      s4, 0  # Number of bytes stored");
+            writer.write("        li      s5, 0  # Whether anything was read");
+            writer.write("1:");
+            writer.write("        mv      a0, s1");
+            writer.write("        mv      a1, sp");
+            writer.write("        li      a2, 1");
+            writer.write("        call    read");
+            writer.write("        blez    a0, 2f");
+            writer.write("        li      s5, 1");
+            writer.write("        lbu     t0, 0(sp)");
+            writer.write("        li      t1, 10");
+            writer.write("        beq     t0, t1, 3f");
+            writer.write("        bgeu    s4, s3, 1b");
+            writer.write("        slli    t1, s4, 2");
+            writer.write("        add     t1, s2, t1");
+            writer.write("        sw      t0, 0(t1)");
+            writer.write("        addiw   s4, s4, 1");
+            writer.write("        j       1b");
+            writer.write("2:");
+            writer.write("        li      a0, -1");
+            writer.write("        beqz    s5, 5f");
+            writer.write("3:");
+            writer.write("        bgeu    s4, s3, 4f");
+            writer.write("        slli    t1, s4, 2");
+            writer.write("        add     t1, s2, t1");
+            writer.write("        sw      zero, 0(t1)");
+            writer.write("4:");
+            writer.write("        mv      a0, s4");
+            writer.write("5:");
+            writer.write("        ld      s5, 16(sp)");
+            writer.write("        ld      s4, 24(sp)");
+            writer.write("        ld      s3, 32(sp)");
+            writer.write("        ld      s2, 40(sp)");
+            writer.write("        ld      s1, 48(sp)");
+            writer.write("        ld      ra, 56(sp)");
+            writer.write("        addi    sp, sp, 64");
+            writer.write("        ret");
+        }
+
+        if program.calls("fclose") {
+            // The file is passed in a0, which is where close wants it
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fclose")));
+            writer.write("        tail    close");
+        }
+
+        if program.calls("parse_int") {
+            // The string or int array is passed in a0 (its address) and a1 (its length), the size of each of its
+            // characters in a2 and the line parse_int was called on in a3. The characters (up to the first 0) are
+            // an optional minus sign followed by at least one digit, built up into a positive number in t0
+            // (which is big enough to tell when it no longer fits in an int). Anything else exits the program
+            // with an error message, through the runtime error routine
+            writer.write(&format!("\n{}:", get_func_label(self.target, "parse_int")));
+            writer.write("        li      t0, 0");
+            writer.write("        li      t1, 0  # Index of the next character");
+            writer.write("        li      t2, 0  # Whether there's a minus sign");
+            writer.write("        li      t5, 2147483648");
+            writer.write("        li      t6, 10");
+            writer.write("1:");
+            writer.write("        bge     t1, a1, 4f");
+            writer.write("        li      t3, 1");
+            writer.write("        bne     a2, t3, 2f");
+            writer.write("        add     t3, a0, t1");
+            writer.write("        lbu     t3, 0(t3)");
+            writer.write("        j       3f");
+            writer.write("2:");
+            writer.write("        slli    t3, t1, 2");
+            writer.write("        add     t3, a0, t3");
+            writer.write("        lw      t3, 0(t3)");
+            writer.write("3:");
+            writer.write("        beqz    t3, 4f");
+            writer.write("        addiw   t1, t1, 1");
+            writer.write("        li      t4, 1");
+            writer.write("        bne     t1, t4, 5f");
+            writer.write("        li      t4, 45");
+            writer.write("        bne     t3, t4, 5f");
+            writer.write("        li      t2, 1");
+            writer.write("        j       1b");
+            writer.write("5:");
+            writer.write("        addiw   t3, t3, -48");
+            writer.write("        bgeu    t3, t6, 6f");
+            writer.write("        mul     t0, t0, t6");
+            writer.write("        add     t0, t0, t3");
+            writer.write("        bgtu    t0, t5, 7f");
+            writer.write("        j       1b");
+            writer.write("4:");
+            writer.write("        beq     t1, t2, 6f");
+            writer.write("        beqz    t2, 8f");
+            writer.write("        negw    a0, t0");
+            writer.write("        ret");
+            writer.write("8:");
+            writer.write("        bgeu    t0, t5, 7f");
+            writer.write("        sext.w  a0, t0");
+            writer.write("        ret");
+            writer.write("6:");
+            writer.write("        lla     a0, parse_int_invalid");
+            writer.write("        mv      a1, a3");
+            writer.write(&format!("        tail    {}", RUNTIME_ERROR_LABEL));
+            writer.write("7:");
+            writer.write("        lla     a0, parse_int_too_large");
+            writer.write("        mv      a1, a3");
+            writer.write(&format!("        tail    {}", RUNTIME_ERROR_LABEL));
+        }
+
+        if program.calls("to_string") {
+            // The number is passed in a0, the int array its characters are written into in a1 (its address)
+            // and a2 (its length), and the line to_string was called on in a3. The digits of the number are
+            // counted first (so an array that's too short can exit the program before anything is written to it),
+            // then written from last to first, followed by a 0 if there's room for one. The number of characters
+            // written is given back
+            writer.write(&format!("\n{}:", get_func_label(self.target, "to_string")));
+            writer.write("        sext.w  t1, a0");
+            writer.write("        sltz    t0, t1  # Whether there's a minus sign");
+            writer.write("        bgez    t1, 1f");
+            writer.write("        neg     t1, t1");
+            writer.write("1:");
+            writer.write("        mv      t2, t1");
+            writer.write("        mv      t3, t0  # Number of characters");
+            writer.write("        li      t4, 10");
+            writer.write("2:");
+            writer.write("        addiw   t3, t3, 1");
+            writer.write("        divu    t2, t2, t4");
+            writer.write("        bnez    t2, 2b");
+            writer.write("        bgt     t3, a2, 5f");
+            writer.write("        beqz    t0, 3f");
+            writer.write("        li      t5, 45");
+            writer.write("        sw      t5, 0(a1)");
+            writer.write("3:");
+            writer.write("        mv      t2, t3");
+            writer.write("4:");
+            writer.write("        addiw   t2, t2, -1");
+            writer.write("        remu    t5, t1, t4");
+            writer.write("        divu    t1, t1, t4");
+            writer.write("        addiw   t5, t5, 48");
+            writer.write("        slli    t6, t2, 2");
+            writer.write("        add     t6, a1, t6");
+            writer.write("        sw      t5, 0(t6)");
+            writer.write("        bnez    t1, 4b");
+            writer.write("        bge     t3, a2, 6f");
+            writer.write("        slli    t6, t3, 2");
+            writer.write("        add     t6, a1, t6");
+            writer.write("        sw      zero, 0(t6)");
+            writer.write("6:");
+            writer.write("        mv      a0, t3");
+            writer.write("        ret");
+            writer.write("5:");
+            writer.write("        lla     a0, to_string_too_short");
+            writer.write("        mv      a1, a3");
+            writer.write(&format!("        tail    {}", RUNTIME_ERROR_LABEL));
+        }
+
+        // The error message is passed in a0 and the line it happened on in a1, which is where printf wants them,
+        // then the program exits with an exit code of 1
+        writer.write(&format!("\n{}:", RUNTIME_ERROR_LABEL));
+        writer.write("        call    printf");
+        writer.write("        li      a0, 1");
+        gen_exit(writer);
+
+        // Let the linker know we don't need an executable stack
+        writer.write("\n        .section .note.GNU-stack,\"\",@progbits");
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// INSTRUCTIONS
+// -----------------------------------------------------------------------------------------
+
+fn gen_instr(
+    writer: &mut ASMWriter,
+    target: &TargetInfo,
+    func: &IrFunction,
+    frame: &Frame,
+    instr: &Instr,
+    next: Option<&Instr>,
+) {
+    match instr {
+        // A constant that's never kept anywhere is put straight into whatever uses it
+        Instr::Const { dest, .. } if matches!(frame.temp_homes[*dest], TempHome::Imm(_)) => {}
+        Instr::Const { dest, value } => {
+            writer.write(&format!("        li      t0, {}", value));
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        // A copy between two temporaries that were merged into the same register does nothing
+        Instr::Copy { dest, src } if frame.temp_homes[*dest] == frame.temp_homes[*src] => {}
+        Instr::Copy { dest, src } => {
+            gen_load_temp(writer, func, frame, "t0", *src);
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::BinOp { op, dest, lhs, rhs } => {
+            gen_load_temp(writer, func, frame, "t0", *lhs);
+            let lines = match frame.temp_homes[*rhs] {
+                TempHome::Imm(value) => get_imm_binop(*op, value),
+                _ => None,
+            };
+            match lines {
+                Some(lines) => {
+                    for line in lines {
+                        writer.write(&format!("        {}", line));
+                    }
+                }
+                None => {
+                    gen_load_temp(writer, func, frame, "t1", *rhs);
+                    gen_binop(writer, *op);
+                }
+            }
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::UnOp { op, dest, src } => {
+            gen_load_temp(writer, func, frame, "t0", *src);
+            match op {
+                UnOp::Neg => writer.write("        negw    t0, t0"),
+                // A bool is always 0 or 1, so flipping the lowest bit flips the bool
+                UnOp::Not => writer.write("        xori    t0, t0, 1"),
+            }
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => {
+            gen_load_temp(writer, func, frame, "t0", *cond);
+            gen_load_temp(writer, func, frame, "t1", *if_true);
+            gen_load_temp(writer, func, frame, "t2", *if_false);
+
+            // There's no conditional move, so turn the condition into a mask of all ones (if it's non-zero)
+            // or all zeros, which picks out the bits where if_true differs from if_false
+            writer.write("        snez    t0, t0");
+            writer.write("        neg     t0, t0");
+            writer.write("        xor     t1, t1, t2");
+            writer.write("        and     t1, t1, t0");
+            writer.write("        xor     t0, t1, t2");
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::SlotAddr { dest, slot } => {
+            gen_add_imm(writer, "t0", "sp", frame.slot_offsets[*slot]);
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::GlobalAddr { dest, global } => {
+            writer.write(&format!(
+                "        lla     t0, {}",
+                get_global_label(*global)
+            ));
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::ElementAddr {
+            dest,
+            base,
+            index,
+            scale,
+        } => {
+            // The index is sign extended to 64 bits when it's loaded, so it can be added straight to the address
+            gen_load_temp(writer, func, frame, "t0", *base);
+            gen_load_temp(writer, func, frame, "t1", *index);
+
+            if *scale == 4 {
+                writer.write("        slli    t1, t1, 2");
+            } else if *scale != 1 {
+                // Each element is a row of a multi-dimensional array, so the row is at address + (index * row size)
+                writer.write(&format!("        li      t2, {}", scale));
+                writer.write("        mul     t1, t1, t2");
+            }
+            writer.write("        add     t0, t0, t1");
+
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::Load { dest, addr, width } => {
+            gen_load_temp(writer, func, frame, "t0", *addr);
+            match width {
+                Width::Word => writer.write("        lw      t0, 0(t0)"),
+                Width::Byte => writer.write("        lbu     t0, 0(t0)"),
+            }
+            gen_store_temp(writer, func, frame, "t0", *dest);
+        }
+        Instr::Store { src, addr, width } => {
+            gen_load_temp(writer, func, frame, "t0", *addr);
+            gen_load_temp(writer, func, frame, "t1", *src);
+            match width {
+                Width::Word => writer.write("        sw      t1, 0(t0)"),
+                Width::Byte => writer.write("        sb      t1, 0(t0)"),
+            }
+        }
+        Instr::Call {
+            dests,
+            func: callee,
+            args,
+        } => {
+            // The first 8 arguments go in the argument passing registers, and the rest go on the stack,
+            // at the bottom of our stack frame (for example, argument 8 is stored at sp + 0, argument 9 at sp + 8, etc...)
+            for (slot, arg) in args.iter().enumerate() {
+                if slot < ARG_REGS.len() {
+                    gen_load_temp(writer, func, frame, ARG_REGS[slot], *arg);
+                } else {
+                    gen_load_temp(writer, func, frame, "t0", *arg);
+                    writer.write(&format!(
+                        "        sd      t0, {}(sp)",
+                        (slot - ARG_REGS.len()) * 8
+                    ));
+                }
+            }
+
+            writer.write(&format!(
+                "        call    {}",
+                get_func_label(target, callee)
+            ));
+
+            // Anything returned is passed back in a0 and a1
+            for (reg, dest) in dests.iter().enumerate() {
+                gen_store_temp(writer, func, frame, RETURN_REGS[reg], *dest);
+            }
+        }
+        Instr::Print { file, format, args } => {
+            // printf's first 7 format arguments go in registers, and the rest go on the stack, 8 bytes each
+            // (where dprintf, which prints to a file, takes the file in a0, leaving one less register for them)
+            let regs = &PRINT_ARG_REGS[file.is_some() as usize..];
+            for (slot, arg) in args.iter().enumerate() {
+                if slot < regs.len() {
+                    gen_load_temp(writer, func, frame, regs[slot], *arg);
+                } else {
+                    gen_load_temp(writer, func, frame, "t0", *arg);
+                    writer.write(&format!(
+                        "        sd      t0, {}(sp)",
+                        (slot - regs.len()) * 8
+                    ));
+                }
+            }
+
+            if let Some(file) = file {
+                gen_load_temp(writer, func, frame, "a0", *file);
+            }
+            gen_printf(writer, *format, file.is_some());
+        }
+        Instr::Label(label) => {
+            writer.write(&format!("{}:", get_label_name(target, func, *label)));
+        }
+        Instr::Jump(label) => {
+            // There's no need to jump to the very next instruction
+            if next != Some(&Instr::Label(*label)) {
+                writer.write(&format!(
+                    "        j       {}",
+                    get_label_name(target, func, *label)
+                ));
+            }
+        }
+        Instr::Branch {
+            cond,
+            if_true,
+            if_false,
+        } => {
+            gen_load_temp(writer, func, frame, "t0", *cond);
+
+            // Fall through to whichever label comes next, if either of them does
+            if next == Some(&Instr::Label(*if_true)) {
+                writer.write(&format!(
+                    "        beqz    t0, {}",
+                    get_label_name(target, func, *if_false)
+                ));
+            } else {
+                writer.write(&format!(
+                    "        bnez    t0, {}",
+                    get_label_name(target, func, *if_true)
+                ));
+                if next != Some(&Instr::Label(*if_false)) {
+                    writer.write(&format!(
+                        "        j       {}",
+                        get_label_name(target, func, *if_false)
+                    ));
+                }
+            }
+        }
+        Instr::Return(values) => {
+            // Values are returned in a0 and a1
+            for (reg, value) in values.iter().enumerate() {
+                gen_load_temp(writer, func, frame, RETURN_REGS[reg], *value);
+            }
+
+            // Jump to the function exit (unless it's the very next thing)
+            if next.is_some() {
+                writer.write(&format!(
+                    "        j       {}",
+                    get_return_label(target, func)
+                ));
+            }
+        }
+        Instr::Panic { message, line } => {
+            // Exit the program through the runtime library, which prints the error message
+            writer.write(&format!(
+                "        lla     a0, {}",
+                get_global_label(*message)
+            ));
+            writer.write(&format!("        li      a1, {}", line));
+            writer.write(&format!("        call    {}", RUNTIME_ERROR_LABEL));
+        }
+        Instr::Line(line) => gen_line(writer, *line),
+    }
+}
+
+// Combine t0 and t1 with the given operator, leaving the result in t0
+fn gen_binop(writer: &mut ASMWriter, op: BinOp) {
+    let lines: &[&str] = match op {
+        // The w versions of each instruction work on the lower 32 bits, and sign extend the result to 64 bits
+        BinOp::Add => &["addw    t0, t0, t1"],
+        BinOp::Sub => &["subw    t0, t0, t1"],
+        BinOp::Mul => &["mulw    t0, t0, t1"],
+        // Division never traps (INT_MIN / -1 wraps around to INT_MIN and INT_MIN % -1 is 0, just like on ARM64)
+        BinOp::Div => &["divw    t0, t0, t1"],
+        BinOp::Rem => &["remw    t0, t0, t1"],
+        BinOp::Eq => &["subw    t0, t0, t1", "seqz    t0, t0"],
+        BinOp::Ne => &["subw    t0, t0, t1", "snez    t0, t0"],
+        BinOp::Lt => &["slt     t0, t0, t1"],
+        BinOp::Gt => &["slt     t0, t1, t0"],
+        // a <= b is !(b < a), and a >= b is !(a < b)
+        BinOp::Le => &["slt     t0, t1, t0", "xori    t0, t0, 1"],
+        BinOp::Ge => &["slt     t0, t0, t1", "xori    t0, t0, 1"],
+        // Sign extending both sides to 64 bits keeps them in the same unsigned order
+        BinOp::ULt => &["sltu    t0, t0, t1"],
+        // Both sides are already sign extended to 64 bits, where the result always fits,
+        // so it overflows if the 64 bit result isn't the same as the 32 bit one
+        BinOp::AddOverflows => &[
+            "add     t2, t0, t1",
+            "addw    t0, t0, t1",
+            "sub     t0, t0, t2",
+            "snez    t0, t0",
+        ],
+        BinOp::SubOverflows => &[
+            "sub     t2, t0, t1",
+            "subw    t0, t0, t1",
+            "sub     t0, t0, t2",
+            "snez    t0, t0",
+        ],
+        BinOp::MulOverflows => &[
+            "mul     t2, t0, t1",
+            "mulw    t0, t0, t1",
+            "sub     t0, t0, t2",
+            "snez    t0, t0",
+        ],
+        BinOp::DivOverflows => &[
+            "div     t2, t0, t1",
+            "divw    t0, t0, t1",
+            "sub     t0, t0, t2",
+            "snez    t0, t0",
+        ],
+    };
+
+    for line in lines {
+        writer.write(&format!("        {}", line));
+    }
+}
+
+// Get the instructions that combine t0 and a constant with the given operator as an immediate operand,
+// which only adding, subtracting and comparing for (in)equality or less than can do (if the constant fits in 12 bits)
+fn get_imm_binop(op: BinOp, value: i32) -> Option<Vec<String>> {
+    let fits = |value: i32| (-2048..2048).contains(&value);
+
+    let lines = match op {
+        BinOp::Add if fits(value) => vec![format!("addiw   t0, t0, {}", value)],
+        BinOp::Sub if fits(-value) => vec![format!("addiw   t0, t0, {}", -value)],
+        BinOp::Eq if fits(-value) => vec![
+            format!("addiw   t0, t0, {}", -value),
+            String::from("seqz    t0, t0"),
+        ],
+        BinOp::Ne if fits(-value) => vec![
+            format!("addiw   t0, t0, {}", -value),
+            String::from("snez    t0, t0"),
+        ],
+        BinOp::Lt if fits(value) => vec![format!("slti    t0, t0, {}", value)],
+        BinOp::ULt if fits(value) => vec![format!("sltiu   t0, t0, {}", value)],
+        _ => return None,
+    };
+
+    return Some(lines);
+}
+
+// Call printf with the given format string global (once any format arguments are in place),
+// or dprintf if it's being printed to a file (once the file is in a0)
+fn gen_printf(writer: &mut ASMWriter, format: usize, to_file: bool) {
+    let (reg, callee) = if to_file {
+        ("a1", "dprintf")
+    } else {
+        ("a0", "printf")
+    };
+    writer.write(&format!(
+        "        lla     {}, {}",
+        reg,
+        get_global_label(format)
+    ));
+    writer.write(&format!("        call    {}", callee));
+}
+
+// Exit the program through the C library (so anything printf is still holding onto gets written out),
+// with the exit code already in a0, writing out the program's counts first if it's been instrumented for coverage
+fn gen_exit(writer: &mut ASMWriter) {
+    if writer.coverage.is_some() {
+        writer.write(&format!("        call    {}", COVERAGE_LABEL));
+    }
+    writer.write("        call    exit");
+}
+
+// -----------------------------------------------------------------------------------------
+// HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Load the given temporary into a register
+fn gen_load_temp(writer: &mut ASMWriter, func: &IrFunction, frame: &Frame, reg: &str, temp: Temp) {
+    match frame.temp_homes[temp] {
+        TempHome::Reg(home) => {
+            writer.write(&format!("        mv      {}, {}", reg, SAVED_REGS[home]))
+        }
+        TempHome::Stack(offset) => {
+            gen_frame_access(writer, get_load_op(func.temps[temp]), reg, offset)
+        }
+        TempHome::Imm(value) => writer.write(&format!("        li      {}, {}", reg, value)),
+    }
+}
+
+// Store a register into the given temporary, where an int kept in a register is sign extended to 64 bits
+// (just like loading it back from the stack would)
+fn gen_store_temp(writer: &mut ASMWriter, func: &IrFunction, frame: &Frame, reg: &str, temp: Temp) {
+    match (frame.temp_homes[temp], func.temps[temp]) {
+        (TempHome::Reg(home), IrType::Int) => {
+            writer.write(&format!("        sext.w  {}, {}", SAVED_REGS[home], reg))
+        }
+        (TempHome::Reg(home), IrType::Ptr) => {
+            writer.write(&format!("        mv      {}, {}", SAVED_REGS[home], reg))
+        }
+        (TempHome::Stack(offset), IrType::Int) => gen_frame_access(writer, "sw", reg, offset),
+        (TempHome::Stack(offset), IrType::Ptr) => gen_frame_access(writer, "sd", reg, offset),
+        // A constant is never set by anything but its const instruction, which is skipped
+        (TempHome::Imm(_), _) => {}
+    }
+}
+
+// Load or store a register at the given offset from the stack pointer
+fn gen_frame_access(writer: &mut ASMWriter, op: &str, reg: &str, offset: i32) {
+    // A load or store can only encode a 12 bit signed offset, so anything further away needs its address worked out first
+    if offset < 2048 {
+        writer.write(&format!("        {:<8}{}, {}(sp)", op, reg, offset));
+    } else {
+        gen_add_imm(writer, "t3", "sp", offset);
+        writer.write(&format!("        {:<8}{}, 0(t3)", op, reg));
+    }
+}
+
+// Add a constant to a register, putting the result in another register
+fn gen_add_imm(writer: &mut ASMWriter, dest: &str, src: &str, value: i32) {
+    // An addi can only encode a 12 bit signed value, so anything bigger is put in t3 first
+    if (-2048..2048).contains(&value) {
+        writer.write(&format!("        addi    {}, {}, {}", dest, src, value));
+    } else {
+        writer.write(&format!("        li      t3, {}", value));
+        writer.write(&format!("        add     {}, {}, t3", dest, src));
+    }
+}
+
+// Get the instruction that loads a value of the given type (sign extending an int to 64 bits)
+fn get_load_op(temp_type: IrType) -> &'static str {
+    return match temp_type {
+        IrType::Int => "lw",
+        IrType::Ptr => "ld",
+    };
+}
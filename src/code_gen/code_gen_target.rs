@@ -0,0 +1,193 @@
+// -----------------------------------------------------------------------------------------
+// TARGETS
+// -----------------------------------------------------------------------------------------
+
+// The instruction set a target's assembly is written in, which decides which code generator is used
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Arch {
+    Arm64,
+    X86_64,
+    Riscv64,
+    Wasm32,
+}
+
+impl Arch {
+    // Get the name of the architecture as Rust (and qemu, and the GNU cross compilers) know it, like "aarch64"
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            Arch::Arm64 => "aarch64",
+            Arch::X86_64 => "x86_64",
+            Arch::Riscv64 => "riscv64",
+            Arch::Wasm32 => "wasm32",
+        };
+    }
+}
+
+// The kind of object file a target's assembly is assembled into, which decides how the address of a global is written
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ObjectFormat {
+    MachO,
+    Elf,
+    Wasm,
+}
+
+// How a program exits, both at the end of main and on a runtime error
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExitAbi {
+    // Make the exit system call directly, by putting the system call number in the given register
+    // and executing the given instruction
+    Syscall {
+        reg: &'static str,
+        number: i32,
+        instr: &'static str,
+    },
+    // Call the C library's exit function, which also writes out anything printf is still holding onto
+    Libc,
+}
+
+// Everything the code generator needs to know about a target, beyond which instructions it has
+#[derive(PartialEq, Debug)]
+pub struct TargetInfo {
+    pub triple: &'static str,
+    pub arch: Arch,
+    pub object_format: ObjectFormat,
+    // Put in front of the name of every C library function (like "_printf" on macOS)
+    pub symbol_prefix: &'static str,
+    // The label the program starts at, which is the C library's main on targets that link against the C runtime
+    pub entry_symbol: &'static str,
+    pub exit_abi: ExitAbi,
+    // Put in front of a label to keep it out of the object file's symbol table
+    pub local_label_prefix: &'static str,
+    // The characters that start a comment
+    pub comment_prefix: &'static str,
+    // The section directive for read-only data, where strings go (since they're never written to)
+    pub rodata_section: &'static str,
+    // The data directives for a 4 byte int, a run of zero bytes and a null-terminated string
+    pub word_directive: &'static str,
+    pub zero_directive: &'static str,
+    pub string_directive: &'static str,
+    pub pointer_size: i32,
+    // The number of arguments passed in registers to a function, and to printf (not including its format string),
+    // since some targets pass every variadic argument on the stack
+    pub num_arg_regs: usize,
+    pub num_vararg_regs: usize,
+    // Whether the arguments passed to a function on the stack are packed together by their size (as Apple's ARM64 does),
+    // rather than each one taking up 8 bytes (printf's format arguments always take up 8 bytes each)
+    pub pack_stack_args: bool,
+    // The number of callee-saved registers temporaries can be kept in, rather than on the stack
+    pub num_saved_regs: usize,
+}
+
+// Every target the compiler can generate assembly for, the first of which is the default
+pub static TARGETS: [TargetInfo; 5] = [
+    TargetInfo {
+        triple: "arm64-apple-darwin",
+        arch: Arch::Arm64,
+        object_format: ObjectFormat::MachO,
+        symbol_prefix: "_",
+        entry_symbol: "_start",
+        exit_abi: ExitAbi::Syscall {
+            reg: "x16",
+            number: 1,
+            instr: "svc     0x80",
+        },
+        local_label_prefix: "L",
+        comment_prefix: "//",
+        rodata_section: ".section __TEXT,__cstring,cstring_literals",
+        word_directive: ".word",
+        zero_directive: ".space",
+        string_directive: ".string",
+        pointer_size: 8,
+        num_arg_regs: 8,
+        num_vararg_regs: 0,
+        pack_stack_args: true,
+        num_saved_regs: 10,
+    },
+    TargetInfo {
+        triple: "aarch64-unknown-linux-gnu",
+        arch: Arch::Arm64,
+        object_format: ObjectFormat::Elf,
+        symbol_prefix: "",
+        entry_symbol: "main",
+        exit_abi: ExitAbi::Libc,
+        local_label_prefix: ".L",
+        comment_prefix: "//",
+        rodata_section: ".section .rodata",
+        word_directive: ".word",
+        zero_directive: ".zero",
+        string_directive: ".string",
+        pointer_size: 8,
+        num_arg_regs: 8,
+        num_vararg_regs: 7,
+        pack_stack_args: false,
+        num_saved_regs: 10,
+    },
+    TargetInfo {
+        triple: "x86_64-unknown-linux-gnu",
+        arch: Arch::X86_64,
+        object_format: ObjectFormat::Elf,
+        symbol_prefix: "",
+        entry_symbol: "main",
+        exit_abi: ExitAbi::Libc,
+        local_label_prefix: ".L",
+        comment_prefix: "#",
+        rodata_section: ".section .rodata",
+        word_directive: ".long",
+        zero_directive: ".zero",
+        string_directive: ".string",
+        pointer_size: 8,
+        num_arg_regs: 6,
+        num_vararg_regs: 5,
+        pack_stack_args: false,
+        num_saved_regs: 5,
+    },
+    TargetInfo {
+        triple: "riscv64-unknown-linux-gnu",
+        arch: Arch::Riscv64,
+        object_format: ObjectFormat::Elf,
+        symbol_prefix: "",
+        entry_symbol: "main",
+        exit_abi: ExitAbi::Libc,
+        local_label_prefix: ".L",
+        comment_prefix: "#",
+        rodata_section: ".section .rodata",
+        word_directive: ".word",
+        zero_directive: ".zero",
+        string_directive: ".string",
+        pointer_size: 8,
+        num_arg_regs: 8,
+        num_vararg_regs: 7,
+        pack_stack_args: false,
+        num_saved_regs: 11,
+    },
+    // WebAssembly has no registers, so every argument is passed the same way (and printf's through memory)
+    TargetInfo {
+        triple: "wasm32-unknown-unknown",
+        arch: Arch::Wasm32,
+        object_format: ObjectFormat::Wasm,
+        symbol_prefix: "",
+        entry_symbol: "_start",
+        exit_abi: ExitAbi::Libc,
+        local_label_prefix: "",
+        comment_prefix: ";;",
+        rodata_section: "",
+        word_directive: "",
+        zero_directive: "",
+        string_directive: "",
+        pointer_size: 4,
+        num_arg_regs: 0,
+        num_vararg_regs: 0,
+        pack_stack_args: false,
+        num_saved_regs: 0,
+    },
+];
+
+// Get the description of the target with the given triple, or None if it isn't supported
+pub fn get_target_info(triple: &str) -> Option<&'static TargetInfo> {
+    return TARGETS.iter().find(|target| target.triple == triple);
+}
+
+// Get the triples of every supported target
+pub fn get_target_triples() -> Vec<&'static str> {
+    return TARGETS.iter().map(|target| target.triple).collect();
+}
@@ -1,113 +1,585 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::ir::ir_data::*;
+use crate::ir::ir_utils::{
+    get_error_format, PARSE_INT_INVALID, PARSE_INT_TOO_LARGE, TO_STRING_TOO_SHORT,
+};
 
-use crate::parser::parser_data::ASTNode;
-use crate::semantic::semantic_data::Symbol;
-
-use crate::code_gen::code_gen_data::ASMWriter;
+use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_registers::{allocate_registers, color_registers};
+use crate::code_gen::code_gen_target::{ObjectFormat, TargetInfo};
+use crate::semantic::semantic_data::RESERVED_PREFIX;
 
 // -----------------------------------------------------------------------------------------
-// FUNCTION DECLARATION HELPERS
+// STACK FRAME HELPERS
 // -----------------------------------------------------------------------------------------
 
-// Loop through all variables in a function declaration and assign them a memory address
-pub fn declare_variables(node: &mut ASTNode, current_offset: &mut i32) {
-    if node.node_type == "parameter" || node.node_type == "varDecl" {
-        // Add the local variable's offset to its symbol table entry
-        node.get_sym().borrow_mut().addr = Some(*current_offset);
+// Lay out the stack frame of a function, giving every temporary and stack slot a place to live
+// (where temporaries are given registers by graph coloring if asked for, or by linear scan otherwise)
+pub fn get_frame(func: &IrFunction, target: &TargetInfo, graph_coloring: bool) -> Frame {
+    // Any arguments that don't fit in the argument passing registers are passed on the stack,
+    // 8 bytes each, so make sure there's enough room at the bottom of the frame for the largest call we make
+    let mut offset = 0;
+    for instr in &func.body {
+        let num_stack_args = match instr {
+            Instr::Call { args, .. } => args.len().saturating_sub(target.num_arg_regs),
+            Instr::Print { file, args, .. } => args
+                .len()
+                .saturating_sub(get_num_print_regs(target, file.is_some())),
+            _ => 0,
+        };
+        offset = offset.max(num_stack_args as i32 * 8);
+    }
+
+    // Constants don't need a register, and any other temporary that didn't get one is an int or a pointer,
+    // and must be aligned to its size
+    let constants = get_constants(func);
+    let fixed: Vec<bool> = constants.iter().map(|value| value.is_some()).collect();
+    let regs = if graph_coloring {
+        color_registers(func, target.num_saved_regs, &fixed)
+    } else {
+        allocate_registers(func, target.num_saved_regs, &fixed)
+    };
+    let mut temp_homes = vec![];
+    for (temp, temp_type) in func.temps.iter().enumerate() {
+        if let Some(value) = constants[temp] {
+            temp_homes.push(TempHome::Imm(value));
+            continue;
+        }
+        if let Some(reg) = regs[temp] {
+            temp_homes.push(TempHome::Reg(reg));
+            continue;
+        }
+
+        let size = get_type_size(*temp_type, target);
+        offset = align_to(offset, size);
+        temp_homes.push(TempHome::Stack(offset));
+        offset += size;
+    }
 
-        // Increment the current offset by the size of this local variable for next time
-        *current_offset += 4;
+    // Stack slots hold arrays of ints, but are 8 byte aligned in case they are indexed as 64 bit values
+    let mut slot_offsets = vec![];
+    for size in &func.slots {
+        offset = align_to(offset, 8);
+        slot_offsets.push(offset);
+        offset += size;
     }
 
-    // Visit children
-    for child in &mut node.children {
-        declare_variables(child, current_offset);
+    // The caller expects every callee-saved register to be left the way it was, so save the ones we use
+    let mut num_used_regs = 0;
+    for reg in regs.iter().flatten() {
+        num_used_regs = num_used_regs.max(reg + 1);
+    }
+    let mut saved_regs = vec![];
+    offset = align_to(offset, 8);
+    for reg in 0..num_used_regs {
+        saved_regs.push((reg, offset));
+        offset += 8;
     }
+
+    return Frame {
+        temp_homes: temp_homes,
+        slot_offsets: slot_offsets,
+        saved_regs: saved_regs,
+        size: align_to(offset, 16),
+    };
 }
 
-// Calculate the number of bytes a function needs to allocate on the stack
-pub fn get_func_stack_alloc(node: &ASTNode) -> i32 {
-    // Calculate the number of bytes we need to allocate on the stack for local variables
-    let mut var_alloc = get_func_var_alloc(node);
+// Find the temporaries that only ever hold a constant small enough to be an immediate operand of an add or a subtract
+// on every target (12 bits, of either sign), which are set once by a const instruction and never by anything else
+fn get_constants(func: &IrFunction) -> Vec<Option<i32>> {
+    let mut constants = vec![None; func.temps.len()];
+    let mut num_defs = vec![0; func.temps.len()];
+    for param in &func.params {
+        num_defs[*param] += 1;
+    }
+    for instr in &func.body {
+        for temp in instr.get_defs() {
+            num_defs[temp] += 1;
+        }
+        if let Instr::Const { dest, value } = instr {
+            constants[*dest] = Some(*value);
+        }
+    }
 
-    // Make sure the amount of bytes is quad-word aligned
-    while var_alloc % 16 != 0 {
-        var_alloc += 4;
+    for temp in 0..func.temps.len() {
+        if num_defs[temp] != 1
+            || !constants[temp].is_some_and(|value| (-4095..4096).contains(&value))
+        {
+            constants[temp] = None;
+        }
     }
 
-    return var_alloc;
+    return constants;
 }
 
-// Calculate the number of bytes a function needs to allocate on the stack for its local variables
-pub fn get_func_var_alloc(node: &ASTNode) -> i32 {
-    let mut num_bytes = 0;
+// Get the offset of each argument passed on the stack (every argument after the ones passed in registers)
+// from the first one, where each one takes up 8 bytes, unless the target packs them together
+pub fn get_stack_arg_offsets(target: &TargetInfo, arg_types: &[IrType]) -> Vec<i32> {
+    let mut offsets = vec![];
+    let mut offset = 0;
+    for arg_type in arg_types.iter().skip(target.num_arg_regs) {
+        let size = if target.pack_stack_args {
+            get_type_size(*arg_type, target)
+        } else {
+            8
+        };
+        offset = align_to(offset, size);
+        offsets.push(offset);
+        offset += size;
+    }
+
+    return offsets;
+}
+
+// Get the number of format arguments passed to printf in registers, or to dprintf (which prints to a file,
+// for fwrite_line), where the file takes up the first register and pushes the format string along to the next
+pub fn get_num_print_regs(target: &TargetInfo, to_file: bool) -> usize {
+    if to_file {
+        return target.num_vararg_regs.saturating_sub(1);
+    }
+    return target.num_vararg_regs;
+}
+
+// Get the number of bytes taken up by a value of the given type on the given target
+pub fn get_type_size(temp_type: IrType, target: &TargetInfo) -> i32 {
+    return match temp_type {
+        IrType::Int => 4,
+        IrType::Ptr => target.pointer_size,
+    };
+}
+
+// Round the given offset up to the next multiple of the given alignment
+pub fn align_to(offset: i32, alignment: i32) -> i32 {
+    return (offset + alignment - 1) / alignment * alignment;
+}
+
+// Format the stack frame of a function (--dump-frame) as a table of where each of its parameters and local variables
+// lives: in a callee-saved register, at an offset from the stack pointer, or nowhere at all, for one that only ever
+// holds a constant. The size of the frame doesn't include the saved frame pointer and return address above it
+pub fn format_frame(
+    func: &IrFunction,
+    frame: &Frame,
+    target: &TargetInfo,
+    generator: &dyn CodeGenerator,
+) -> String {
+    let mut rows = vec![vec![
+        String::from("name"),
+        String::from("kind"),
+        String::from("size"),
+        String::from("home"),
+    ]];
+
+    // Parameters come first, in the order they're passed in, followed by the other variables in the order they're declared
+    let mut temps: Vec<(Temp, &str)> = func
+        .params
+        .iter()
+        .filter(|param| func.temp_names.contains_key(param))
+        .map(|param| (*param, "param"))
+        .collect();
+    let mut locals: Vec<Temp> = func
+        .temp_names
+        .keys()
+        .filter(|temp| !func.params.contains(temp))
+        .copied()
+        .collect();
+    locals.sort();
+    temps.extend(locals.into_iter().map(|temp| (temp, "local")));
+
+    for (temp, kind) in temps {
+        let home = match frame.temp_homes[temp] {
+            TempHome::Reg(reg) => generator.get_saved_reg_name(reg, func.temps[temp]),
+            TempHome::Stack(offset) => format!("[sp, {}]", offset),
+            TempHome::Imm(value) => format!("constant {}", value),
+        };
+        rows.push(vec![
+            func.temp_names[&temp].clone(),
+            String::from(kind),
+            get_type_size(func.temps[temp], target).to_string(),
+            home,
+        ]);
+    }
+
+    // Local arrays are stored in stack slots, which are numbered in the order they're declared
+    let mut slots: Vec<&usize> = func.slot_names.keys().collect();
+    slots.sort();
+    for slot in slots {
+        rows.push(vec![
+            func.slot_names[slot].clone(),
+            String::from("array"),
+            func.slots[*slot].to_string(),
+            format!("[sp, {}]", frame.slot_offsets[*slot]),
+        ]);
+    }
+
+    // Pad every column out to the width of its widest entry
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (i, entry) in row.iter().enumerate() {
+            widths[i] = widths[i].max(entry.len());
+        }
+    }
+
+    let num_saved = frame.saved_regs.len();
+    let mut text = format!(
+        "function '{}' ({} byte frame, saving {} register{}):\n",
+        func.name,
+        frame.size,
+        num_saved,
+        if num_saved == 1 { "" } else { "s" }
+    );
+    for row in &rows {
+        let entries: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| format!("{:<width$}", entry, width = widths[i]))
+            .collect();
+        text.push_str(&format!("    {}\n", entries.join("  ").trim_end()));
+    }
+    text.push('\n');
+
+    return text;
+}
 
-    if node.node_type == "parameter" || node.node_type == "varDecl" {
-        num_bytes += 4;
+// Mark where the instructions that follow came from in the source file, with a comment holding the line of soup
+// and/or a .loc directive (where .file directives number the source files from 1), if either has been asked for
+pub fn gen_line(writer: &mut ASMWriter, line: i32) {
+    if let Some(source_lines) = &writer.source_lines {
+        let text = match source_lines[writer.source_file].get(line as usize - 1) {
+            None => "",
+            Some(text) => text.trim(),
+        };
+        let comment = format!("        {} line {}: {}", writer.comment_prefix, line, text);
+        writer.lines.push(AsmLine::Comment(comment));
     }
 
-    // Visit children
-    for child in &node.children {
-        num_bytes += get_func_var_alloc(child);
+    if writer.debug_info {
+        writer.write(&format!("        .loc {} {}", writer.source_file + 1, line));
     }
+}
 
-    return num_bytes;
+// Get the instruction after the given one in the body of a function,
+// skipping over any line markers (since they don't generate anything)
+pub fn get_next_instr(body: &[Instr], i: usize) -> Option<&Instr> {
+    return body[i + 1..]
+        .iter()
+        .find(|next| !matches!(next, Instr::Line(_)));
 }
 
 // -----------------------------------------------------------------------------------------
-// STACK ALLOCATION HELPERS
+// REGISTER HELPERS
 // -----------------------------------------------------------------------------------------
 
-// Allocate/deallocate the requested amount of space on the stack
-// (positive for allocating, negative for deallocating)
-pub fn allocate_stack(writer: &mut ASMWriter, mut allocate: i32) {
-    // We always have to allocate an amount which is quad-word aligned
-    while allocate % 16 != 0 {
-        if allocate < 0 {
-            allocate -= 4;
-        } else {
-            allocate += 4;
-        }
+// The callee-saved registers temporaries can be kept in (x29 and x30 are the frame pointer and link register)
+pub const SAVED_REGS: [usize; 10] = [19, 20, 21, 22, 23, 24, 25, 26, 27, 28];
+
+// Get the name of a register, as a 32 bit w register for an int or a 64 bit x register for an address
+pub fn get_reg(reg: usize, temp_type: IrType) -> String {
+    return match temp_type {
+        IrType::Int => format!("w{}", reg),
+        IrType::Ptr => format!("x{}", reg),
+    };
+}
+
+// Move any 32 bit value into a register, which can take two instructions if the value doesn't fit in 16 bits
+pub fn gen_mov_imm(writer: &mut ASMWriter, reg: &str, value: i32) {
+    if (0..=0xffff).contains(&value) || (-0x10000..0).contains(&value) {
+        writer.write(&format!("        mov     {}, {}", reg, value));
+    } else {
+        writer.write(&format!(
+            "        movz    {}, {}",
+            reg,
+            value as u32 & 0xffff
+        ));
+        writer.write(&format!(
+            "        movk    {}, {}, lsl 16",
+            reg,
+            value as u32 >> 16
+        ));
     }
+}
 
-    // Move the stack pointer to allocate/deallocate the requested amount of space
-    if allocate < 0 {
-        writer.write(&format!("        add     sp, sp, {}", -allocate));
-    } else if allocate > 0 {
-        writer.write(&format!("        sub     sp, sp, {}", allocate));
-    } // else (allocate = 0), do nothing
+// Load the given temporary into a register
+pub fn gen_load_temp(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    reg: usize,
+    temp: Temp,
+) {
+    gen_load_reg(writer, func, frame, &get_reg(reg, func.temps[temp]), temp);
+}
 
-    // Correct the addresses of all local variables in the current function now that we've moved the stack pointer
-    increment_addrs(&writer.get_current_func(), allocate, &mut vec![]);
+// Load the given temporary into a register (given by name), which does nothing if it's already the temporary's home
+pub fn gen_load_reg(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    reg: &str,
+    temp: Temp,
+) {
+    match frame.temp_homes[temp] {
+        TempHome::Reg(home) => {
+            let home = get_reg(SAVED_REGS[home], func.temps[temp]);
+            if home != reg {
+                writer.write(&format!("        mov     {}, {}", reg, home));
+            }
+        }
+        TempHome::Stack(offset) => gen_frame_access(writer, "ldr", reg, offset),
+        TempHome::Imm(value) => gen_mov_imm(writer, reg, value),
+    }
 }
 
-// Loop through every variable declaration (including parameters) and increment their memory address by the given amount
-pub fn increment_addrs(
-    node: &ASTNode,
-    increment: i32,
-    already_incremented: &mut Vec<Rc<RefCell<Symbol>>>,
+// Store a register into the given temporary
+pub fn gen_store_temp(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    reg: usize,
+    temp: Temp,
 ) {
-    if node.node_type == "varDecl" || node.node_type == "parameter" {
-        match &node.sym {
-            None => {}
-            Some(sym) => {
-                if !already_incremented.contains(sym) {
-                    already_incremented.push(Rc::clone(sym));
-
-                    match &mut sym.borrow_mut().addr {
-                        None => {}
-                        Some(addr) => {
-                            *addr += increment;
-                        }
-                    }
-                }
+    gen_store_reg(writer, func, frame, &get_reg(reg, func.temps[temp]), temp);
+}
+
+// Store a register (given by name) into the given temporary, which does nothing if it's already the temporary's home
+pub fn gen_store_reg(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    reg: &str,
+    temp: Temp,
+) {
+    match frame.temp_homes[temp] {
+        TempHome::Reg(home) => {
+            let home = get_reg(SAVED_REGS[home], func.temps[temp]);
+            if home != reg {
+                writer.write(&format!("        mov     {}, {}", home, reg));
             }
         }
+        TempHome::Stack(offset) => gen_frame_access(writer, "str", reg, offset),
+        // A constant is never set by anything but its const instruction, which is skipped
+        TempHome::Imm(_) => {}
     }
+}
 
-    // Visit children
-    for child in &node.children {
-        increment_addrs(child, increment, already_incremented);
+// Get the register an instruction can read the given temporary from, which is its home if it's kept in a register,
+// and otherwise the given scratch register, after loading it there
+pub fn gen_use_temp(
+    writer: &mut ASMWriter,
+    func: &IrFunction,
+    frame: &Frame,
+    scratch: usize,
+    temp: Temp,
+) -> String {
+    if let TempHome::Reg(home) = frame.temp_homes[temp] {
+        return get_reg(SAVED_REGS[home], func.temps[temp]);
     }
+
+    gen_load_temp(writer, func, frame, scratch, temp);
+    return get_reg(scratch, func.temps[temp]);
+}
+
+// Get the register an instruction should put the given temporary in, which is its home if it's kept in a register,
+// and otherwise the given scratch register (which gen_store_reg then stores to its home)
+pub fn get_def_reg(func: &IrFunction, frame: &Frame, scratch: usize, temp: Temp) -> String {
+    return match frame.temp_homes[temp] {
+        TempHome::Reg(home) => get_reg(SAVED_REGS[home], func.temps[temp]),
+        _ => get_reg(scratch, func.temps[temp]),
+    };
+}
+
+// Save (or restore) the caller's values of every callee-saved register the function keeps temporaries in
+pub fn gen_saved_regs(writer: &mut ASMWriter, frame: &Frame, restore: bool) {
+    let op = if restore { "ldr" } else { "str" };
+    for (reg, offset) in &frame.saved_regs {
+        gen_frame_access(writer, op, &format!("x{}", SAVED_REGS[*reg]), *offset);
+    }
+}
+
+// Load or store a register at the given offset from the stack pointer
+fn gen_frame_access(writer: &mut ASMWriter, op: &str, reg: &str, offset: i32) {
+    // A load or store can only encode an offset of up to 4095 times the size of the register,
+    // so anything further away needs its address worked out first
+    let max_offset = if reg.starts_with('x') { 32760 } else { 16380 };
+
+    if offset <= max_offset {
+        writer.write(&format!("        {}     {}, [sp, {}]", op, reg, offset));
+    } else {
+        gen_sp_offset(writer, "x16", offset);
+        writer.write(&format!("        {}     {}, [x16]", op, reg));
+    }
+}
+
+// Put the address of the given offset from the stack pointer into an x register
+pub fn gen_sp_offset(writer: &mut ASMWriter, reg: &str, offset: i32) {
+    // An add can only encode a 12 bit value
+    if offset <= 4095 {
+        writer.write(&format!("        add     {}, sp, {}", reg, offset));
+    } else {
+        gen_mov_imm(writer, "w17", offset);
+        writer.write(&format!("        add     {}, sp, x17", reg));
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// DATA HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Write out every global, where the strings all go together in the target's read-only section,
+// then switch back to the text section for the code that follows
+pub fn gen_data(writer: &mut ASMWriter, target: &TargetInfo, program: &IrProgram) {
+    writer.write(".data");
+    for (i, global) in program.globals.iter().enumerate() {
+        if !matches!(global.kind, GlobalKind::String(_)) {
+            gen_global(writer, target, i, &global.kind);
+        }
+    }
+
+    writer.write(target.rodata_section);
+    for (i, global) in program.globals.iter().enumerate() {
+        if matches!(global.kind, GlobalKind::String(_)) {
+            gen_global(writer, target, i, &global.kind);
+        }
+    }
+    gen_last_newline(writer, target);
+    for (label, format) in get_runtime_messages(program) {
+        writer.write(&format!(
+            "{}: {} \"{}\"",
+            label,
+            target.string_directive,
+            escape_string(format.as_bytes())
+        ));
+    }
+
+    writer.write(".text");
+}
+
+// Write out a global with the target's data directives
+fn gen_global(writer: &mut ASMWriter, target: &TargetInfo, global: usize, kind: &GlobalKind) {
+    let label = get_global_label(global);
+
+    match kind {
+        GlobalKind::Word(value) => {
+            writer.write(".balign 4");
+            writer.write(&format!("{}: {} {}", label, target.word_directive, value));
+        }
+        GlobalKind::Zeroed(num_bytes) => {
+            writer.write(".balign 8");
+            writer.write(&format!(
+                "{}: {} {}",
+                label, target.zero_directive, num_bytes
+            ));
+        }
+        GlobalKind::String(bytes) => {
+            writer.write(&format!(
+                "{}: {} \"{}\"",
+                label,
+                target.string_directive,
+                escape_string(bytes)
+            ));
+        }
+    }
+}
+
+// Write out the newline printed at the very end of the program
+fn gen_last_newline(writer: &mut ASMWriter, target: &TargetInfo) {
+    writer.write(&format!(
+        "last_newline: {} \"\\n\"",
+        target.string_directive
+    ));
+}
+
+// -----------------------------------------------------------------------------------------
+// SYMBOL HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Write out the label the program starts at, which is weak on targets that link against the C runtime,
+// so a C program that links against the program can replace it with its own main
+pub fn gen_entry_label(writer: &mut ASMWriter, target: &TargetInfo) {
+    if target.object_format == ObjectFormat::Elf {
+        writer.write(&format!("\n        .weak   {}", target.entry_symbol));
+    } else {
+        writer.write(&format!("\n        .globl  {}", target.entry_symbol));
+    }
+}
+
+// Write out the label a function starts at, exported so it can be called from C
+pub fn gen_func_label(writer: &mut ASMWriter, target: &TargetInfo, func: &IrFunction) {
+    let label = get_func_label(target, &func.name);
+    writer.write(&format!("\n        .globl  {}", label));
+    if target.object_format == ObjectFormat::Elf {
+        writer.write(&format!("        .type   {}, %function", label));
+    }
+    writer.write(&format!("{}:", label));
+}
+
+// -----------------------------------------------------------------------------------------
+// NAMING HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the name a function (or a function in the runtime library) is exported under, like soup_add1 for add1,
+// which can't clash with anything else the compiler (or the C library) defines, like main or printf
+pub fn get_export_name(name: &str) -> String {
+    return format!("{}{}", RESERVED_PREFIX, name);
+}
+
+// Get the symbol a function is defined under in the assembly, which has the same prefix as every C function
+// (so on macOS, add1 is defined as _soup_add1, which C calls soup_add1)
+pub fn get_func_label(target: &TargetInfo, name: &str) -> String {
+    return format!("{}{}", target.symbol_prefix, get_export_name(name));
+}
+
+// Get the assembly label of a label in the body of a function, which is local to the object file
+// (and can't clash with another function's, since a label number is never followed by anything else)
+pub fn get_label_name(target: &TargetInfo, func: &IrFunction, label: Label) -> String {
+    return format!("{}fn_{}_{}", target.local_label_prefix, func.name, label);
+}
+
+// Get the assembly label of the end of a function, that returning from it jumps to
+pub fn get_return_label(target: &TargetInfo, func: &IrFunction) -> String {
+    return format!("{}fn_{}_ret", target.local_label_prefix, func.name);
+}
+
+// The label of the routine a program instrumented for coverage calls right before it exits, to write out its counts
+pub const COVERAGE_LABEL: &str = "write_coverage";
+
+// The label of the runtime library's routine every runtime error (like dividing by zero) exits the program through,
+// given the error message (a format string) and the line the error happened on
+pub const RUNTIME_ERROR_LABEL: &str = "__soup_runtime_error";
+
+// Get the labels and format strings of the error messages the functions in the runtime library the program calls can
+// exit the program with, which are written out along with its globals
+pub fn get_runtime_messages(program: &IrProgram) -> Vec<(&'static str, String)> {
+    let mut messages = vec![];
+    if program.calls("parse_int") {
+        messages.push(("parse_int_invalid", get_error_format(PARSE_INT_INVALID)));
+        messages.push(("parse_int_too_large", get_error_format(PARSE_INT_TOO_LARGE)));
+    }
+    if program.calls("to_string") {
+        messages.push(("to_string_too_short", get_error_format(TO_STRING_TOO_SHORT)));
+    }
+    return messages;
+}
+
+// Get the assembly label of a global
+pub fn get_global_label(global: usize) -> String {
+    return format!("LG{}", global);
+}
+
+// Convert the bytes of a string into the form expected by the assembler's .string directive
+pub fn escape_string(bytes: &[u8]) -> String {
+    let mut escaped = String::from("");
+
+    for byte in bytes {
+        match byte {
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            b'\r' => escaped.push_str("\\r"),
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str("\\\\"),
+            b' '..=b'~' => escaped.push(*byte as char),
+            // Anything else (including each byte of a multi-byte UTF-8 character) is written as an octal escape
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+
+    return escaped;
 }
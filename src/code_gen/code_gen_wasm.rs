@@ -0,0 +1,898 @@
+use std::collections::HashMap;
+
+use crate::ir::ir_data::*;
+
+use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_utils::{
+    align_to, gen_line, get_export_name, get_global_label, get_runtime_messages,
+    RUNTIME_ERROR_LABEL,
+};
+
+// -----------------------------------------------------------------------------------------
+// WEBASSEMBLY (WAT)
+// -----------------------------------------------------------------------------------------
+
+// Instead of assembly, a WebAssembly module is written in the text format (WAT), which imports these functions
+// from its host (the browser, or node):
+// - env.printf(format, args), where format is the address of a C format string (using only %d),
+//   and args is the address of an array of 4 byte ints to print
+// - env.exit(code), which stops the program with the given exit code
+// - env.sleep(ms), which waits for the given number of milliseconds (where a negative number doesn't wait at all)
+// - env.fopen(path, mode), where path and mode are the addresses of C strings (and mode is "r", "w" or "a"),
+//   which opens a file and gives back its number (or -1 if it couldn't be opened)
+// - env.dprintf(file, format, args), which is just like env.printf, but prints to the given file
+// - env.fread_line(file, line, len), which reads the next line of a file into the array of 4 byte ints at line
+//   (which has len elements), just like the runtime library's fread_line does on every other target
+// - env.fclose(file), which closes a file
+// (where sleep, fopen, fread_line and fclose are only imported by a program that calls them)
+// and exports its memory (so the host can read strings out of it) and a _start function which runs the program.
+//
+// Every temporary is a wasm local, and anything kept in memory (globals, strings and stack slots) lives in
+// the module's linear memory, where the globals start at DATA_START and the stack grows down from the top.
+//
+// WebAssembly only has structured control flow, so each function's body is split up into blocks (each starting at
+// a label) and run by a loop that jumps to the block whose index is in the $pc local, which a jump sets before
+// going around the loop again. A jump to the very next block just falls through to it instead
+
+// Where the globals start in memory (leaving address 0 unused, so it can never be a valid address)
+const DATA_START: i32 = 1024;
+
+// How many bytes of memory are set aside for the stack, above the globals
+const STACK_SIZE: i32 = 1 << 20;
+
+// How many bytes are in each page of memory
+const PAGE_SIZE: i32 = 1 << 16;
+
+pub struct WasmGenerator;
+
+impl CodeGenerator for WasmGenerator {
+    fn gen_globals(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        writer.write_raw("(module");
+        writer.write_raw("  (import \"env\" \"printf\" (func $printf (param i32 i32)))");
+        writer.write_raw("  (import \"env\" \"exit\" (func $exit (param i32)))");
+        writer.write_raw("  (import \"env\" \"dprintf\" (func $dprintf (param i32 i32 i32)))");
+
+        // The rest are only imported if the program calls the part of the runtime library that needs them
+        if program.calls("sleep") {
+            writer.write_raw("  (import \"env\" \"sleep\" (func $sleep (param i32)))");
+        }
+        if program.calls("fopen") {
+            writer.write_raw(
+                "  (import \"env\" \"fopen\" (func $fopen (param i32 i32) (result i32)))",
+            );
+        }
+        if program.calls("fread_line") {
+            writer.write_raw(
+                "  (import \"env\" \"fread_line\" (func $fread_line (param i32 i32 i32) (result i32)))",
+            );
+        }
+        if program.calls("fclose") {
+            writer.write_raw("  (import \"env\" \"fclose\" (func $fclose (param i32)))");
+        }
+
+        // Lay out every global in memory, followed by the newline printed at the end of the program
+        // (and the error messages of any functions in the runtime library the program calls)
+        let mut data = vec![];
+        let mut address = DATA_START;
+        for (i, global) in program.globals.iter().enumerate() {
+            let bytes = match &global.kind {
+                GlobalKind::Word(value) => {
+                    address = align_to(address, 4);
+                    value.to_le_bytes().to_vec()
+                }
+                GlobalKind::Zeroed(num_bytes) => {
+                    // Memory starts out zeroed, so there's nothing to fill it in with
+                    address = align_to(address, 8);
+                    data.push((get_global_label(i), address, None));
+                    address += num_bytes;
+                    continue;
+                }
+                GlobalKind::String(bytes) => {
+                    let mut bytes = bytes.clone();
+                    bytes.push(0);
+                    bytes
+                }
+            };
+            data.push((get_global_label(i), address, Some(bytes.clone())));
+            address += bytes.len() as i32;
+        }
+        data.push((
+            String::from("last_newline"),
+            address,
+            Some(b"\n\0".to_vec()),
+        ));
+        address += 2;
+        for (label, format) in get_runtime_messages(program) {
+            let mut bytes = format.into_bytes();
+            bytes.push(0);
+            let num_bytes = bytes.len() as i32;
+            data.push((String::from(label), address, Some(bytes)));
+            address += num_bytes;
+        }
+
+        // The stack sits above the globals, at the very top of memory
+        let num_pages = (align_to(address, 16) + STACK_SIZE + PAGE_SIZE - 1) / PAGE_SIZE;
+        writer.write_raw(&format!("  (memory (export \"memory\") {})", num_pages));
+        writer.write_raw(&format!(
+            "  (global $sp (mut i32) (i32.const {}))",
+            num_pages * PAGE_SIZE
+        ));
+
+        // Each global's address is kept in a wasm global of the same name, since functions don't know the layout
+        for (label, address, bytes) in data {
+            writer.write_raw(&format!(
+                "  (global ${} i32 (i32.const {}))",
+                label, address
+            ));
+            if let Some(bytes) = bytes {
+                writer.write_raw(&format!(
+                    "  (data (i32.const {}) \"{}\")",
+                    address,
+                    escape_data(&bytes)
+                ));
+            }
+        }
+    }
+
+    fn gen_asm_main(&self, writer: &mut ASMWriter, main_returns_int: bool) {
+        writer.write_raw("\n  (func (export \"_start\")");
+        writer.write_raw("    (local $code i32)");
+
+        // Call the compilee's main function, holding onto its exit code if it returns one
+        writer.write_raw(&format!("    call ${}", get_export_name("main")));
+        if main_returns_int {
+            writer.write_raw("    local.set $code");
+        }
+
+        writer.write_raw("    global.get $last_newline");
+        writer.write_raw("    i32.const 0");
+        writer.write_raw("    call $printf");
+
+        // Exit the program (with an exit code of 0, unless main returned one)
+        writer.write_raw("    local.get $code");
+        writer.write_raw("    call $exit");
+        writer.write_raw("  )");
+    }
+
+    fn gen_function(&self, writer: &mut ASMWriter, func: &IrFunction) {
+        let frame = get_frame(func);
+
+        // Write the function's signature, where each parameter and return value is an i32 (including addresses)
+        let mut signature = format!("\n  (func ${}", get_export_name(&func.name));
+        for param in &func.params {
+            signature.push_str(&format!(" (param {} i32)", get_local_name(func, *param)));
+        }
+        if !func.returns.is_empty() {
+            signature.push_str(" (result");
+            for _ in &func.returns {
+                signature.push_str(" i32");
+            }
+            signature.push(')');
+        }
+        writer.write_raw(&signature);
+
+        // Every other temporary is a local, along with the block to run next and the base of the stack frame
+        for temp in 0..func.temps.len() {
+            if !func.params.contains(&temp) {
+                writer.write_raw(&format!("    (local {} i32)", get_local_name(func, temp)));
+            }
+        }
+        writer.write_raw("    (local $pc i32)");
+        writer.write_raw("    (local $fp i32)");
+
+        // Allocate the stack frame
+        if frame.size != 0 {
+            writer.write_raw("    global.get $sp");
+            writer.write_raw(&format!("    i32.const {}", frame.size));
+            writer.write_raw("    i32.sub");
+            writer.write_raw("    local.tee $fp");
+            writer.write_raw("    global.set $sp");
+        }
+
+        // Split the body up into blocks, each starting at a label
+        let mut blocks: Vec<Vec<&Instr>> = vec![vec![]];
+        let mut block_indices = HashMap::new();
+        for instr in &func.body {
+            if let Instr::Label(label) = instr {
+                block_indices.insert(*label, blocks.len());
+                blocks.push(vec![]);
+            }
+            blocks.last_mut().unwrap().push(instr);
+        }
+
+        // Open a wasm block for each of our blocks, where branching out of a wasm block runs the code just after it,
+        // so the innermost one picks which block to run
+        writer.write_raw("    loop $dispatch");
+        for i in (0..blocks.len()).rev() {
+            writer.write_raw(&format!("    block $B{}", i));
+        }
+        writer.write_raw("    local.get $pc");
+        let targets: Vec<String> = (0..blocks.len()).map(|i| format!("$B{}", i)).collect();
+        writer.write_raw(&format!("    br_table {}", targets.join(" ")));
+
+        for (i, block) in blocks.iter().enumerate() {
+            writer.write_raw(&format!("    end ;; $B{}", i));
+
+            let gen = FunctionGen {
+                func: func,
+                frame: &frame,
+                block_indices: &block_indices,
+                block: i,
+            };
+            for instr in block {
+                gen.gen_instr(writer, instr);
+            }
+        }
+
+        // Every body ends by returning or panicking, so there's no way to reach the end of the loop
+        writer.write_raw("    end");
+        writer.write_raw("    unreachable");
+        writer.write_raw("  )");
+    }
+
+    fn get_saved_reg_name(&self, _reg: usize, _temp_type: IrType) -> String {
+        // Every temporary is kept in a local of its own, so there are no callee-saved registers to name
+        // (and no stack frames to dump, which the command line rejects before getting this far)
+        return String::new();
+    }
+
+    fn gen_coverage_routine(&self, _writer: &mut ASMWriter, _coverage: &Coverage) {
+        // A module has no file of its own to write to, so programs can't be compiled for coverage here
+        // (which the command line rejects before getting this far)
+    }
+
+    fn gen_runtime_lib(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        // The host stops the program when it exits, so it never returns
+        writer.write_raw(&format!(
+            "\n  (func ${} (param $code i32)",
+            get_export_name("exit")
+        ));
+        writer.write_raw("    local.get $code");
+        writer.write_raw("    call $exit");
+        writer.write_raw("    unreachable");
+        writer.write_raw("  )");
+
+        if program.calls("sleep") {
+            // Sleeping is left to the host
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $ms i32)",
+                get_export_name("sleep")
+            ));
+            writer.write_raw("    local.get $ms");
+            writer.write_raw("    call $sleep");
+            writer.write_raw("  )");
+        }
+
+        if program.calls("fopen") {
+            // And so are files
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $path i32) (param $mode i32) (result i32)",
+                get_export_name("fopen")
+            ));
+            writer.write_raw("    local.get $path");
+            writer.write_raw("    local.get $mode");
+            writer.write_raw("    call $fopen");
+            writer.write_raw("  )");
+        }
+
+        if program.calls("fread_line") {
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $file i32) (param $line i32) (param $len i32) (result i32)",
+                get_export_name("fread_line")
+            ));
+            writer.write_raw("    local.get $file");
+            writer.write_raw("    local.get $line");
+            writer.write_raw("    local.get $len");
+            writer.write_raw("    call $fread_line");
+            writer.write_raw("  )");
+        }
+
+        if program.calls("fclose") {
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $file i32)",
+                get_export_name("fclose")
+            ));
+            writer.write_raw("    local.get $file");
+            writer.write_raw("    call $fclose");
+            writer.write_raw("  )");
+        }
+
+        if program.calls("parse_int") {
+            // The characters of the string or int array (up to the first 0) are an optional minus sign followed by
+            // at least one digit, built up into a positive number in a 64 bit local (which is big enough to tell
+            // when it no longer fits in an int). Anything else exits the program with an error message
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $text i32) (param $len i32) (param $size i32) (param $line i32) (result i32)",
+                get_export_name("parse_int")
+            ));
+            writer.write_raw("    (local $value i64)");
+            writer.write_raw("    (local $index i32)");
+            writer.write_raw("    (local $negative i32)");
+            writer.write_raw("    (local $char i32)");
+            writer.write_raw("    block $end");
+            writer.write_raw("    loop $next");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    local.get $len");
+            writer.write_raw("    i32.ge_s");
+            writer.write_raw("    br_if $end");
+            writer.write_raw("    local.get $size");
+            writer.write_raw("    i32.const 1");
+            writer.write_raw("    i32.eq");
+            writer.write_raw("    if (result i32)");
+            writer.write_raw("    local.get $text");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    i32.load8_u");
+            writer.write_raw("    else");
+            writer.write_raw("    local.get $text");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    i32.const 2");
+            writer.write_raw("    i32.shl");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    i32.load");
+            writer.write_raw("    end");
+            writer.write_raw("    local.tee $char");
+            writer.write_raw("    i32.eqz");
+            writer.write_raw("    br_if $end");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    i32.eqz");
+            writer.write_raw("    local.get $char");
+            writer.write_raw("    i32.const 45");
+            writer.write_raw("    i32.eq");
+            writer.write_raw("    i32.and");
+            writer.write_raw("    if");
+            writer.write_raw("    i32.const 1");
+            writer.write_raw("    local.set $negative");
+            writer.write_raw("    else");
+            writer.write_raw("    local.get $char");
+            writer.write_raw("    i32.const 48");
+            writer.write_raw("    i32.sub");
+            writer.write_raw("    local.tee $char");
+            writer.write_raw("    i32.const 10");
+            writer.write_raw("    i32.ge_u");
+            writer.write_raw("    if");
+            writer.write_raw("    global.get $parse_int_invalid");
+            writer.write_raw("    local.get $line");
+            writer.write_raw(&format!("    call ${}", RUNTIME_ERROR_LABEL));
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i64.const 10");
+            writer.write_raw("    i64.mul");
+            writer.write_raw("    local.get $char");
+            writer.write_raw("    i64.extend_i32_u");
+            writer.write_raw("    i64.add");
+            writer.write_raw("    local.tee $value");
+            writer.write_raw("    i64.const 2147483648");
+            writer.write_raw("    i64.gt_u");
+            writer.write_raw("    if");
+            writer.write_raw("    global.get $parse_int_too_large");
+            writer.write_raw("    local.get $line");
+            writer.write_raw(&format!("    call ${}", RUNTIME_ERROR_LABEL));
+            writer.write_raw("    end");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    i32.const 1");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    local.set $index");
+            writer.write_raw("    br $next");
+            writer.write_raw("    end");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    local.get $negative");
+            writer.write_raw("    i32.eq");
+            writer.write_raw("    if");
+            writer.write_raw("    global.get $parse_int_invalid");
+            writer.write_raw("    local.get $line");
+            writer.write_raw(&format!("    call ${}", RUNTIME_ERROR_LABEL));
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $negative");
+            writer.write_raw("    i32.eqz");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i64.const 2147483647");
+            writer.write_raw("    i64.gt_u");
+            writer.write_raw("    i32.and");
+            writer.write_raw("    if");
+            writer.write_raw("    global.get $parse_int_too_large");
+            writer.write_raw("    local.get $line");
+            writer.write_raw(&format!("    call ${}", RUNTIME_ERROR_LABEL));
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i32.wrap_i64");
+            writer.write_raw("    local.set $char");
+            writer.write_raw("    i32.const 0");
+            writer.write_raw("    local.get $char");
+            writer.write_raw("    i32.sub");
+            writer.write_raw("    local.get $char");
+            writer.write_raw("    local.get $negative");
+            writer.write_raw("    select");
+            writer.write_raw("  )");
+        }
+
+        if program.calls("to_string") {
+            // The digits of the number are counted first (so an array that's too short can exit the program before
+            // anything is written to it), then written from last to first, followed by a 0 if there's room for one
+            writer.write_raw(&format!(
+                "\n  (func ${} (param $value i32) (param $chars i32) (param $len i32) (param $line i32) (result i32)",
+                get_export_name("to_string")
+            ));
+            writer.write_raw("    (local $magnitude i32)");
+            writer.write_raw("    (local $rest i32)");
+            writer.write_raw("    (local $count i32)");
+            writer.write_raw("    (local $index i32)");
+            writer.write_raw("    i32.const 0");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i32.sub");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i32.const 0");
+            writer.write_raw("    i32.lt_s");
+            writer.write_raw("    local.tee $count");
+            writer.write_raw("    select");
+            writer.write_raw("    local.tee $magnitude");
+            writer.write_raw("    local.set $rest");
+            writer.write_raw("    loop $count_digits");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("    i32.const 1");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    local.set $count");
+            writer.write_raw("    local.get $rest");
+            writer.write_raw("    i32.const 10");
+            writer.write_raw("    i32.div_u");
+            writer.write_raw("    local.tee $rest");
+            writer.write_raw("    br_if $count_digits");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("    local.get $len");
+            writer.write_raw("    i32.gt_s");
+            writer.write_raw("    if");
+            writer.write_raw("    global.get $to_string_too_short");
+            writer.write_raw("    local.get $line");
+            writer.write_raw(&format!("    call ${}", RUNTIME_ERROR_LABEL));
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $value");
+            writer.write_raw("    i32.const 0");
+            writer.write_raw("    i32.lt_s");
+            writer.write_raw("    if");
+            writer.write_raw("    local.get $chars");
+            writer.write_raw("    i32.const 45");
+            writer.write_raw("    i32.store");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("    local.set $index");
+            writer.write_raw("    loop $write_digits");
+            writer.write_raw("    local.get $index");
+            writer.write_raw("    i32.const 1");
+            writer.write_raw("    i32.sub");
+            writer.write_raw("    local.tee $index");
+            writer.write_raw("    i32.const 2");
+            writer.write_raw("    i32.shl");
+            writer.write_raw("    local.get $chars");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    local.get $magnitude");
+            writer.write_raw("    i32.const 10");
+            writer.write_raw("    i32.rem_u");
+            writer.write_raw("    i32.const 48");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    i32.store");
+            writer.write_raw("    local.get $magnitude");
+            writer.write_raw("    i32.const 10");
+            writer.write_raw("    i32.div_u");
+            writer.write_raw("    local.tee $magnitude");
+            writer.write_raw("    br_if $write_digits");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("    local.get $len");
+            writer.write_raw("    i32.lt_s");
+            writer.write_raw("    if");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("    i32.const 2");
+            writer.write_raw("    i32.shl");
+            writer.write_raw("    local.get $chars");
+            writer.write_raw("    i32.add");
+            writer.write_raw("    i32.const 0");
+            writer.write_raw("    i32.store");
+            writer.write_raw("    end");
+            writer.write_raw("    local.get $count");
+            writer.write_raw("  )");
+        }
+
+        // The line the error happened on is the error message's only format argument,
+        // which is passed to the host in an array just below the stack
+        writer.write_raw(&format!(
+            "\n  (func ${} (param $message i32) (param $line i32)",
+            RUNTIME_ERROR_LABEL
+        ));
+        writer.write_raw("    global.get $sp");
+        writer.write_raw("    i32.const 16");
+        writer.write_raw("    i32.sub");
+        writer.write_raw("    local.get $line");
+        writer.write_raw("    i32.store");
+        writer.write_raw("    local.get $message");
+        writer.write_raw("    global.get $sp");
+        writer.write_raw("    i32.const 16");
+        writer.write_raw("    i32.sub");
+        writer.write_raw("    call $printf");
+        writer.write_raw("    i32.const 1");
+        writer.write_raw("    call $exit");
+        writer.write_raw("    unreachable");
+        writer.write_raw("  )");
+        writer.write_raw(")");
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// STACK FRAME
+// -----------------------------------------------------------------------------------------
+
+// Temporaries are all wasm locals, so the stack frame (which $fp points to the bottom of) only holds
+// the format arguments passed to printf, followed by the function's stack slots
+struct WasmFrame {
+    slot_offsets: Vec<i32>,
+    size: i32,
+}
+
+fn get_frame(func: &IrFunction) -> WasmFrame {
+    // Make sure there's enough room for the largest call to printf, 4 bytes per argument
+    let mut offset = 0;
+    for instr in &func.body {
+        if let Instr::Print { args, .. } = instr {
+            offset = offset.max(args.len() as i32 * 4);
+        }
+    }
+
+    let mut slot_offsets = vec![];
+    for size in &func.slots {
+        offset = align_to(offset, 8);
+        slot_offsets.push(offset);
+        offset += size;
+    }
+
+    return WasmFrame {
+        slot_offsets: slot_offsets,
+        size: align_to(offset, 16),
+    };
+}
+
+// -----------------------------------------------------------------------------------------
+// INSTRUCTIONS
+// -----------------------------------------------------------------------------------------
+
+// Everything needed to generate the instructions of one block of a function
+struct FunctionGen<'a> {
+    func: &'a IrFunction,
+    frame: &'a WasmFrame,
+    block_indices: &'a HashMap<Label, usize>,
+    // The index of the block being generated
+    block: usize,
+}
+
+impl FunctionGen<'_> {
+    fn gen_instr(&self, writer: &mut ASMWriter, instr: &Instr) {
+        match instr {
+            Instr::Const { dest, value } => {
+                self.write(writer, &format!("i32.const {}", value));
+                self.gen_set(writer, *dest);
+            }
+            Instr::Copy { dest, src } => {
+                self.gen_get(writer, *src);
+                self.gen_set(writer, *dest);
+            }
+            Instr::BinOp { op, dest, lhs, rhs } => {
+                if *op == BinOp::Div {
+                    self.gen_division(writer, *lhs, *rhs);
+                } else if *op == BinOp::DivOverflows {
+                    // Dividing only overflows for the smallest int divided by -1
+                    self.gen_get(writer, *lhs);
+                    self.write(writer, "i32.const -2147483648");
+                    self.write(writer, "i32.eq");
+                    self.gen_get(writer, *rhs);
+                    self.write(writer, "i32.const -1");
+                    self.write(writer, "i32.eq");
+                    self.write(writer, "i32.and");
+                } else if let Some(instr) = get_overflow_instr(*op) {
+                    // Do the arithmetic on both sides sign extended to 64 bits, where the result always fits,
+                    // and check whether it's outside of the range of an int (i.e. whether adding 2^31 to it
+                    // puts it outside of the range of an unsigned int)
+                    self.gen_get(writer, *lhs);
+                    self.write(writer, "i64.extend_i32_s");
+                    self.gen_get(writer, *rhs);
+                    self.write(writer, "i64.extend_i32_s");
+                    self.write(writer, instr);
+                    self.write(writer, "i64.const 2147483648");
+                    self.write(writer, "i64.add");
+                    self.write(writer, "i64.const 4294967295");
+                    self.write(writer, "i64.gt_u");
+                } else {
+                    self.gen_get(writer, *lhs);
+                    self.gen_get(writer, *rhs);
+                    self.write(writer, get_binop_instr(*op));
+                }
+                self.gen_set(writer, *dest);
+            }
+            Instr::UnOp { op, dest, src } => {
+                match op {
+                    UnOp::Neg => {
+                        // There's no negation instruction, so subtract from 0 instead
+                        self.write(writer, "i32.const 0");
+                        self.gen_get(writer, *src);
+                        self.write(writer, "i32.sub");
+                    }
+                    UnOp::Not => {
+                        // A bool is always 0 or 1, so flipping the lowest bit flips the bool
+                        self.gen_get(writer, *src);
+                        self.write(writer, "i32.const 1");
+                        self.write(writer, "i32.xor");
+                    }
+                }
+                self.gen_set(writer, *dest);
+            }
+            Instr::Select {
+                dest,
+                cond,
+                if_true,
+                if_false,
+            } => {
+                self.gen_get(writer, *if_true);
+                self.gen_get(writer, *if_false);
+                self.gen_get(writer, *cond);
+                self.write(writer, "select");
+                self.gen_set(writer, *dest);
+            }
+            Instr::SlotAddr { dest, slot } => {
+                self.write(writer, "local.get $fp");
+                self.write(
+                    writer,
+                    &format!("i32.const {}", self.frame.slot_offsets[*slot]),
+                );
+                self.write(writer, "i32.add");
+                self.gen_set(writer, *dest);
+            }
+            Instr::GlobalAddr { dest, global } => {
+                self.write(
+                    writer,
+                    &format!("global.get ${}", get_global_label(*global)),
+                );
+                self.gen_set(writer, *dest);
+            }
+            Instr::ElementAddr {
+                dest,
+                base,
+                index,
+                scale,
+            } => {
+                self.gen_get(writer, *base);
+                self.gen_get(writer, *index);
+                if *scale != 1 {
+                    self.write(writer, &format!("i32.const {}", scale));
+                    self.write(writer, "i32.mul");
+                }
+                self.write(writer, "i32.add");
+                self.gen_set(writer, *dest);
+            }
+            Instr::Load { dest, addr, width } => {
+                self.gen_get(writer, *addr);
+                match width {
+                    Width::Word => self.write(writer, "i32.load"),
+                    Width::Byte => self.write(writer, "i32.load8_u"),
+                }
+                self.gen_set(writer, *dest);
+            }
+            Instr::Store { src, addr, width } => {
+                self.gen_get(writer, *addr);
+                self.gen_get(writer, *src);
+                match width {
+                    Width::Word => self.write(writer, "i32.store"),
+                    Width::Byte => self.write(writer, "i32.store8"),
+                }
+            }
+            Instr::Call {
+                dests,
+                func: callee,
+                args,
+            } => {
+                for arg in args {
+                    self.gen_get(writer, *arg);
+                }
+                self.write(writer, &format!("call ${}", get_export_name(callee)));
+
+                // Anything returned is left on the stack, with the last return value on top
+                for dest in dests.iter().rev() {
+                    self.gen_set(writer, *dest);
+                }
+            }
+            Instr::Print { file, format, args } => {
+                // The format arguments are passed to the host as an array at the bottom of the stack frame
+                for (i, arg) in args.iter().enumerate() {
+                    self.write(writer, "local.get $fp");
+                    self.gen_get(writer, *arg);
+                    self.write(writer, &format!("i32.store offset={}", i * 4));
+                }
+
+                // (after the file being printed to, if there is one)
+                if let Some(file) = file {
+                    self.gen_get(writer, *file);
+                }
+                self.write(
+                    writer,
+                    &format!("global.get ${}", get_global_label(*format)),
+                );
+                self.write(writer, "local.get $fp");
+                if file.is_some() {
+                    self.write(writer, "call $dprintf");
+                } else {
+                    self.write(writer, "call $printf");
+                }
+            }
+            // Labels start a new block, so there's nothing left to generate for them
+            Instr::Label(_) => {}
+            Instr::Jump(label) => {
+                self.gen_jump(writer, *label);
+            }
+            Instr::Branch {
+                cond,
+                if_true,
+                if_false,
+            } => {
+                self.gen_get(writer, *cond);
+
+                // Fall through to whichever block comes next, if either of them does
+                if self.is_next_block(*if_true) {
+                    self.write(writer, "i32.eqz");
+                    self.write(writer, "if");
+                    self.gen_jump(writer, *if_false);
+                } else {
+                    self.write(writer, "if");
+                    self.gen_jump(writer, *if_true);
+                    if !self.is_next_block(*if_false) {
+                        self.write(writer, "else");
+                        self.gen_jump(writer, *if_false);
+                    }
+                }
+                self.write(writer, "end");
+            }
+            Instr::Return(values) => {
+                // Deallocate the stack frame before returning
+                if self.frame.size != 0 {
+                    self.write(writer, "local.get $fp");
+                    self.write(writer, &format!("i32.const {}", self.frame.size));
+                    self.write(writer, "i32.add");
+                    self.write(writer, "global.set $sp");
+                }
+
+                for value in values {
+                    self.gen_get(writer, *value);
+                }
+                self.write(writer, "return");
+            }
+            Instr::Panic { message, line } => {
+                // Exit the program through the runtime library, which prints the error message
+                self.write(
+                    writer,
+                    &format!("global.get ${}", get_global_label(*message)),
+                );
+                self.write(writer, &format!("i32.const {}", line));
+                self.write(writer, &format!("call ${}", RUNTIME_ERROR_LABEL));
+                self.write(writer, "unreachable");
+            }
+            // WebAssembly has no debug info to give (here), but a line marker can still be commented
+            Instr::Line(line) => gen_line(writer, *line),
+        }
+    }
+
+    // Divide lhs by rhs, leaving the quotient on the stack
+    fn gen_division(&self, writer: &mut ASMWriter, lhs: Temp, rhs: Temp) {
+        // i32.div_s traps when the quotient doesn't fit (i.e. INT_MIN / -1), so dividing by -1 is done by hand instead,
+        // wrapping around just like it does on ARM64 (i32.rem_s already gives 0 for INT_MIN % -1)
+        self.gen_get(writer, rhs);
+        self.write(writer, "i32.const -1");
+        self.write(writer, "i32.eq");
+        self.write(writer, "if (result i32)");
+        self.write(writer, "i32.const 0");
+        self.gen_get(writer, lhs);
+        self.write(writer, "i32.sub");
+        self.write(writer, "else");
+        self.gen_get(writer, lhs);
+        self.gen_get(writer, rhs);
+        self.write(writer, "i32.div_s");
+        self.write(writer, "end");
+    }
+
+    // Run the block starting at the given label next, by going back around the dispatch loop
+    // (unless it's the very next block, which is run by falling through to it)
+    fn gen_jump(&self, writer: &mut ASMWriter, label: Label) {
+        if self.is_next_block(label) {
+            return;
+        }
+
+        self.write(writer, &format!("i32.const {}", self.block_indices[&label]));
+        self.write(writer, "local.set $pc");
+        self.write(writer, "br $dispatch");
+    }
+
+    // Return true if the block starting at the given label comes straight after the current one
+    fn is_next_block(&self, label: Label) -> bool {
+        return self.block_indices[&label] == self.block + 1;
+    }
+
+    // Push the value of a temporary onto the stack
+    fn gen_get(&self, writer: &mut ASMWriter, temp: Temp) {
+        self.write(
+            writer,
+            &format!("local.get {}", get_local_name(self.func, temp)),
+        );
+    }
+
+    // Pop the value on top of the stack into a temporary
+    fn gen_set(&self, writer: &mut ASMWriter, temp: Temp) {
+        self.write(
+            writer,
+            &format!("local.set {}", get_local_name(self.func, temp)),
+        );
+    }
+
+    // Write an instruction inside the body of a function
+    fn write(&self, writer: &mut ASMWriter, instr: &str) {
+        writer.write_raw(&format!("    {}", instr));
+    }
+}
+
+// Get the instruction that combines the top two values on the stack with the given operator
+// (other than division and overflow checks, which need more than one)
+fn get_binop_instr(op: BinOp) -> &'static str {
+    return match op {
+        BinOp::Add => "i32.add",
+        BinOp::Sub => "i32.sub",
+        BinOp::Mul => "i32.mul",
+        BinOp::Div => "i32.div_s",
+        BinOp::Rem => "i32.rem_s",
+        BinOp::Eq => "i32.eq",
+        BinOp::Ne => "i32.ne",
+        BinOp::Lt => "i32.lt_s",
+        BinOp::Gt => "i32.gt_s",
+        BinOp::Le => "i32.le_s",
+        BinOp::Ge => "i32.ge_s",
+        BinOp::ULt => "i32.lt_u",
+        // Should never happen, indicates an error on my end
+        BinOp::AddOverflows | BinOp::SubOverflows | BinOp::MulOverflows | BinOp::DivOverflows => {
+            "unreachable"
+        }
+    };
+}
+
+// Get the 64 bit instruction that an overflow check does its arithmetic with, or None if the operator isn't one
+fn get_overflow_instr(op: BinOp) -> Option<&'static str> {
+    return match op {
+        BinOp::AddOverflows => Some("i64.add"),
+        BinOp::SubOverflows => Some("i64.sub"),
+        BinOp::MulOverflows => Some("i64.mul"),
+        _ => None,
+    };
+}
+
+// -----------------------------------------------------------------------------------------
+// HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the name of the local holding a temporary, which includes the name of the variable it holds (if it holds one)
+fn get_local_name(func: &IrFunction, temp: Temp) -> String {
+    return match func.temp_names.get(&temp) {
+        None => format!("$t{}", temp),
+        Some(name) => format!("${}.{}", name, temp),
+    };
+}
+
+// Convert bytes into the form expected by a data segment's string, where anything other than
+// printable ASCII is written as a two digit hex escape
+fn escape_data(bytes: &[u8]) -> String {
+    let mut escaped = String::from("");
+
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => escaped.push_str(&format!("\\{}", *byte as char)),
+            b' '..=b'~' => escaped.push(*byte as char),
+            _ => escaped.push_str(&format!("\\{:02x}", byte)),
+        }
+    }
+
+    return escaped;
+}
@@ -0,0 +1,827 @@
+use crate::ir::ir_data::*;
+
+use crate::code_gen::code_gen_data::*;
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::code_gen::code_gen_utils::{
+    gen_data, gen_entry_label, gen_func_label, gen_line, get_frame, get_func_label,
+    get_global_label, get_label_name, get_next_instr, get_return_label, COVERAGE_LABEL,
+    RUNTIME_ERROR_LABEL,
+};
+
+// -----------------------------------------------------------------------------------------
+// X86_64 (LINUX, SYSTEM V)
+// -----------------------------------------------------------------------------------------
+
+// Assembly is written in Intel syntax, and linked against the C library (which provides printf and exit).
+// Every temporary is loaded into a scratch register (eax and ecx, or rax and rcx) before it's used
+// and stored back to its home (a callee-saved register or the stack) afterwards, unless it's a constant
+// that can be used as an immediate operand, just like on ARM64
+
+// The registers the first 6 arguments of a function are passed in
+const ARG_REGS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+// The registers printf's first 5 format arguments are passed in (its format string goes in rdi)
+const PRINT_ARG_REGS: [&str; 5] = ["rsi", "rdx", "rcx", "r8", "r9"];
+
+// The registers a function's return values are passed back in
+const RETURN_REGS: [&str; 2] = ["rax", "rdx"];
+
+// The callee-saved registers temporaries can be kept in (rbp is the frame pointer)
+const SAVED_REGS: [&str; 5] = ["rbx", "r12", "r13", "r14", "r15"];
+
+pub struct X86Generator {
+    pub target: &'static TargetInfo,
+}
+
+impl CodeGenerator for X86Generator {
+    fn gen_globals(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        writer.write(".intel_syntax noprefix");
+        gen_data(writer, self.target, program);
+    }
+
+    fn gen_asm_main(&self, writer: &mut ASMWriter, main_returns_int: bool) {
+        // The C library calls main, with the stack 8 bytes off of being 16 byte aligned (due to the return address),
+        // which pushing the frame pointer fixes
+        gen_entry_label(writer, self.target);
+        writer.write(&format!("{}:", self.target.entry_symbol));
+        writer.write("        push    rbp");
+        writer.write("        mov     rbp, rsp");
+        writer.write("        sub     rsp, 16");
+
+        // Call the compilee's main function
+        writer.write(&format!(
+            "        call    {}",
+            get_func_label(self.target, "main")
+        ));
+
+        // If main returns an int, hold onto it so printing the last newline can't trample it
+        if main_returns_int {
+            writer.write("        mov     DWORD PTR [rsp], eax");
+        }
+
+        writer.write("        lea     rdi, [rip + last_newline]");
+        writer.write("        xor     eax, eax");
+        writer.write("        call    printf@PLT");
+
+        // Exit the program (through the C library, so anything printf is still holding onto gets written out)
+        if main_returns_int {
+            writer.write("        mov     edi, DWORD PTR [rsp]");
+        } else {
+            writer.write("        mov     edi, 0");
+        }
+        gen_exit(writer);
+    }
+
+    fn gen_function(&self, writer: &mut ASMWriter, func: &IrFunction) {
+        let frame = get_frame(func, self.target, writer.graph_coloring);
+
+        // Write function entry label, save the frame pointer and allocate the stack frame
+        gen_func_label(writer, self.target, func);
+        writer.write("        push    rbp");
+        writer.write("        mov     rbp, rsp");
+        if frame.size != 0 {
+            writer.write(&format!("        sub     rsp, {}", frame.size));
+        }
+        for (reg, offset) in &frame.saved_regs {
+            writer.write(&format!(
+                "        mov     QWORD PTR [rsp + {}], {}",
+                offset, SAVED_REGS[*reg]
+            ));
+        }
+
+        // Store any parameters in their temporaries, where the first 6 are passed in the argument passing registers
+        // and the rest are passed on the stack, just above our saved frame pointer and return address
+        for (slot, param) in func.params.iter().enumerate() {
+            if slot < ARG_REGS.len() {
+                gen_store_temp(writer, func, &frame, ARG_REGS[slot], *param);
+            } else {
+                writer.write(&format!(
+                    "        mov     {}, {} PTR [rbp + {}]",
+                    get_reg("rax", func.temps[*param]),
+                    get_size_name(func.temps[*param]),
+                    16 + (slot - ARG_REGS.len()) * 8
+                ));
+                gen_store_temp(writer, func, &frame, "rax", *param);
+            }
+        }
+
+        for (i, instr) in func.body.iter().enumerate() {
+            gen_instr(
+                writer,
+                self.target,
+                func,
+                &frame,
+                instr,
+                get_next_instr(&func.body, i),
+            );
+        }
+
+        // Write function exit label, deallocate the stack frame and return
+        writer.write(&format!("{}:", get_return_label(self.target, func)));
+        for (reg, offset) in &frame.saved_regs {
+            writer.write(&format!(
+                "        mov     {}, QWORD PTR [rsp + {}]",
+                SAVED_REGS[*reg], offset
+            ));
+        }
+        writer.write("        mov     rsp, rbp");
+        writer.write("        pop     rbp");
+        writer.write("        ret");
+    }
+
+    fn get_saved_reg_name(&self, reg: usize, temp_type: IrType) -> String {
+        return get_reg(SAVED_REGS[reg], temp_type);
+    }
+
+    fn gen_coverage_routine(&self, writer: &mut ASMWriter, coverage: &Coverage) {
+        // Keep the exit code (in rdi) and rbx safe, which also keeps the stack 16 byte aligned for each call
+        writer.write(&format!("\n{}:", COVERAGE_LABEL));
+        writer.write("        push    rbp");
+        writer.write("        mov     rbp, rsp");
+        writer.write("        push    rdi");
+        writer.write("        push    rbx");
+
+        // If the file can't be opened, there's nothing we can do about it
+        writer.write(&format!(
+            "        lea     rdi, [rip + {}]",
+            get_global_label(coverage.file)
+        ));
+        writer.write(&format!(
+            "        lea     rsi, [rip + {}]",
+            get_global_label(coverage.mode)
+        ));
+        writer.write("        call    fopen@PLT");
+        writer.write("        test    rax, rax");
+        writer.write(&format!("        je      {}_done", COVERAGE_LABEL));
+        writer.write("        mov     rbx, rax");
+
+        // Write the header, followed by the counters
+        for (data, size, count) in [
+            (coverage.header, 1, coverage.header_len),
+            (coverage.counters, 4, coverage.num_counters),
+        ] {
+            writer.write(&format!(
+                "        lea     rdi, [rip + {}]",
+                get_global_label(data)
+            ));
+            writer.write(&format!("        mov     esi, {}", size));
+            writer.write(&format!("        mov     edx, {}", count));
+            writer.write("        mov     rcx, rbx");
+            writer.write("        call    fwrite@PLT");
+        }
+        writer.write("        mov     rdi, rbx");
+        writer.write("        call    fclose@PLT");
+
+        writer.write(&format!("{}_done:", COVERAGE_LABEL));
+        writer.write("        pop     rbx");
+        writer.write("        pop     rdi");
+        writer.write("        pop     rbp");
+        writer.write("        ret");
+    }
+
+    fn gen_runtime_lib(&self, writer: &mut ASMWriter, program: &IrProgram) {
+        // The return code is passed into exit and is already in edi, so exit through the C library
+        // (realigning the stack around the call to the coverage routine first, if there is one, since it's 8 bytes off here)
+        writer.write(&format!("\n{}:", get_func_label(self.target, "exit")));
+        if writer.coverage.is_some() {
+            writer.write("        sub     rsp, 8");
+            writer.write(&format!("        call    {}", COVERAGE_LABEL));
+            writer.write("        add     rsp, 8");
+        }
+        writer.write("        jmp     exit@PLT");
+
+        if program.calls("sleep") {
+            // The number of milliseconds to sleep for is passed in edi, and is split into the seconds and nanoseconds
+            // of a timespec on the stack for nanosleep (after showing everything printed so far), where a negative
+            // number of milliseconds doesn't sleep at all. Taking 24 bytes off the stack realigns it for the calls
+            writer.write(&format!("\n{}:", get_func_label(self.target, "sleep")));
+            writer.write("        push    rbx");
+            writer.write("        sub     rsp, 16");
+            writer.write("        xor     ebx, ebx");
+            writer.write("        test    edi, edi");
+            writer.write("        cmovg   ebx, edi");
+            writer.write("        xor     edi, edi");
+            writer.write("        call    fflush@PLT");
+            writer.write("        mov     eax, ebx");
+            writer.write("        xor     edx, edx");
+            writer.write("        mov     ecx, 1000");
+            writer.write("        div     ecx");
+            writer.write("        mov     qword ptr [rsp], rax");
+            writer.write("        imul    edx, edx, 1000000");
+            writer.write("        mov     qword ptr [rsp + 8], rdx");
+            writer.write("        mov     rdi, rsp");
+            writer.write("        xor     esi, esi");
+            writer.write("        call    nanosleep@PLT");
+            writer.write("        add     rsp, 16");
+            writer.write("        pop     rbx");
+            writer.write("        ret");
+        }
+
+        if program.calls("fopen") {
+            // The path and mode are passed in rdi and rsi, which is where fopen wants them. A file is given back as its
+            // file descriptor, which is duplicated so the FILE fopen made can be closed (or as -1, if it couldn't be opened)
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fopen")));
+            writer.write("        push    rbx");
+            writer.write("        push    r12");
+            writer.write("        sub     rsp, 8");
+            writer.write("        call    fopen@PLT");
+            writer.write("        mov     rbx, rax");
+            writer.write("        mov     r12d, -1");
+            writer.write("        test    rbx, rbx");
+            writer.write("        jz      1f");
+            writer.write("        mov     rdi, rbx");
+            writer.write("        call    fileno@PLT");
+            writer.write("        mov     edi, eax");
+            writer.write("        call    dup@PLT");
+            writer.write("        mov     r12d, eax");
+            writer.write("        mov     rdi, rbx");
+            writer.write("        call    fclose@PLT");
+            writer.write("1:");
+            writer.write("        mov     eax, r12d");
+            writer.write("        add     rsp, 8");
+            writer.write("        pop     r12");
+            writer.write("        pop     rbx");
+            writer.write("        ret");
+        }
+
+        if program.calls("fread_line") {
+            // The file is passed in edi, and the array the line is read into in rsi (its address) and edx (its length).
+            // The line is read a byte at a time (into the space left on the stack by realigning it), where every byte that
+            // fits is stored in the array, up to the newline, followed by a 0 if there's room for one. The number of bytes
+            // stored is given back, or -1 if the end of the file was reached before anything was read
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fread_line")));
+            writer.write("        push    rbx");
+            writer.write("        push    r12");
+            writer.write("        push    r13");
+            writer.write("        push    r14");
+            writer.write("        push    r15");
+            writer.write("        sub     rsp, 16");
+            writer.write("        mov     ebx, edi");
+            writer.write("        mov     r12, rsi");
+            writer.write("        mov     r13d, edx");
+            writer.write("        xor     r14d, r14d  # Number of bytes stored");
+            writer.write("        xor     r15d, r15d  # Whether anything was read");
+            writer.write("1:");
+            writer.write("        mov     edi, ebx");
+            writer.write("        mov     rsi, rsp");
+            writer.write("        mov     edx, 1");
+            writer.write("        call    read@PLT");
+            writer.write("        test    rax, rax");
+            writer.write("        jle     2f");
+            writer.write("        mov     r15d, 1");
+            writer.write("        movzx   eax, BYTE PTR [rsp]");
+            writer.write("        cmp     eax, 10");
+            writer.write("        je      3f");
+            writer.write("        cmp     r14d, r13d");
+            writer.write("        jae     1b");
+            writer.write("        mov     DWORD PTR [r12 + r14 * 4], eax");
+            writer.write("        inc     r14d");
+            writer.write("        jmp     1b");
+            writer.write("2:");
+            writer.write("        mov     eax, -1");
+            writer.write("        test    r15d, r15d");
+            writer.write("        jz      5f");
+            writer.write("3:");
+            writer.write("        cmp     r14d, r13d");
+            writer.write("        jae     4f");
+            writer.write("        mov     DWORD PTR [r12 + r14 * 4], 0");
+            writer.write("4:");
+            writer.write("        mov     eax, r14d");
+            writer.write("5:");
+            writer.write("        add     rsp, 16");
+            writer.write("        pop     r15");
+            writer.write("        pop     r14");
+            writer.write("        pop     r13");
+            writer.write("        pop     r12");
+            writer.write("        pop     rbx");
+            writer.write("        ret");
+        }
+
+        if program.calls("fclose") {
+            // The file is passed in edi, which is where close wants it
+            writer.write(&format!("\n{}:", get_func_label(self.target, "fclose")));
+            writer.write("        jmp     close@PLT");
+        }
+
+        if program.calls("parse_int") {
+            // The string or int array is passed in rdi (its address) and esi (its length), the size of each of its
+            // characters in edx and the line parse_int was called on in ecx. The characters (up to the first 0) are
+            // an optional minus sign followed by at least one digit, built up into a positive number in rax
+            // (which is big enough to tell when it no longer fits in an int). Anything else exits the program
+            // with an error message, through the runtime error routine (with the stack just as it was given to us)
+            writer.write(&format!("\n{}:", get_func_label(self.target, "parse_int")));
+            writer.write("        mov     r8d, ecx");
+            writer.write("        mov     r11d, 2147483648");
+            writer.write("        xor     eax, eax");
+            writer.write("        xor     ecx, ecx  # Index of the next character");
+            writer.write("        xor     r9d, r9d  # Whether there's a minus sign");
+            writer.write("1:");
+            writer.write("        cmp     ecx, esi");
+            writer.write("        jge     4f");
+            writer.write("        cmp     edx, 1");
+            writer.write("        jne     2f");
+            writer.write("        movzx   r10d, BYTE PTR [rdi + rcx]");
+            writer.write("        jmp     3f");
+            writer.write("2:");
+            writer.write("        mov     r10d, DWORD PTR [rdi + rcx * 4]");
+            writer.write("3:");
+            writer.write("        test    r10d, r10d");
+            writer.write("        jz      4f");
+            writer.write("        inc     ecx");
+            writer.write("        cmp     ecx, 1");
+            writer.write("        jne     5f");
+            writer.write("        cmp     r10d, 45");
+            writer.write("        jne     5f");
+            writer.write("        mov     r9d, 1");
+            writer.write("        jmp     1b");
+            writer.write("5:");
+            writer.write("        sub     r10d, 48");
+            writer.write("        cmp     r10d, 9");
+            writer.write("        ja      6f");
+            writer.write("        imul    rax, rax, 10");
+            writer.write("        add     rax, r10");
+            writer.write("        cmp     rax, r11");
+            writer.write("        ja      7f");
+            writer.write("        jmp     1b");
+            writer.write("4:");
+            writer.write("        cmp     ecx, r9d");
+            writer.write("        je      6f");
+            writer.write("        test    r9d, r9d");
+            writer.write("        jz      8f");
+            writer.write("        neg     eax");
+            writer.write("        ret");
+            writer.write("8:");
+            writer.write("        cmp     rax, r11");
+            writer.write("        jae     7f");
+            writer.write("        ret");
+            writer.write("6:");
+            writer.write("        lea     rdi, [rip + parse_int_invalid]");
+            writer.write("        mov     esi, r8d");
+            writer.write(&format!("        jmp     {}", RUNTIME_ERROR_LABEL));
+            writer.write("7:");
+            writer.write("        lea     rdi, [rip + parse_int_too_large]");
+            writer.write("        mov     esi, r8d");
+            writer.write(&format!("        jmp     {}", RUNTIME_ERROR_LABEL));
+        }
+
+        if program.calls("to_string") {
+            // The number is passed in edi, the int array its characters are written into in rsi (its address)
+            // and edx (its length), and the line to_string was called on in ecx. The digits of the number are
+            // counted first (so an array that's too short can exit the program before anything is written to it),
+            // then written from last to first, followed by a 0 if there's room for one. The number of characters
+            // written is given back
+            writer.write(&format!("\n{}:", get_func_label(self.target, "to_string")));
+            writer.write("        mov     r8d, ecx");
+            writer.write("        mov     r9d, edx");
+            writer.write("        mov     eax, edi");
+            writer.write("        xor     r10d, r10d  # Whether there's a minus sign");
+            writer.write("        test    edi, edi");
+            writer.write("        jns     1f");
+            writer.write("        neg     eax");
+            writer.write("        mov     r10d, 1");
+            writer.write("1:");
+            writer.write("        mov     r11d, eax");
+            writer.write("        mov     ecx, 10");
+            writer.write("        mov     edi, r10d  # Number of characters");
+            writer.write("2:");
+            writer.write("        inc     edi");
+            writer.write("        xor     edx, edx");
+            writer.write("        div     ecx");
+            writer.write("        test    eax, eax");
+            writer.write("        jnz     2b");
+            writer.write("        cmp     edi, r9d");
+            writer.write("        jg      5f");
+            writer.write("        test    r10d, r10d");
+            writer.write("        jz      3f");
+            writer.write("        mov     DWORD PTR [rsi], 45");
+            writer.write("3:");
+            writer.write("        mov     eax, r11d");
+            writer.write("        mov     r11d, edi");
+            writer.write("4:");
+            writer.write("        dec     r11d");
+            writer.write("        xor     edx, edx");
+            writer.write("        div     ecx");
+            writer.write("        add     edx, 48");
+            writer.write("        mov     DWORD PTR [rsi + r11 * 4], edx");
+            writer.write("        test    eax, eax");
+            writer.write("        jnz     4b");
+            writer.write("        cmp     edi, r9d");
+            writer.write("        jge     6f");
+            writer.write("        mov     DWORD PTR [rsi + rdi * 4], 0");
+            writer.write("6:");
+            writer.write("        mov     eax, edi");
+            writer.write("        ret");
+            writer.write("5:");
+            writer.write("        lea     rdi, [rip + to_string_too_short]");
+            writer.write("        mov     esi, r8d");
+            writer.write(&format!("        jmp     {}", RUNTIME_ERROR_LABEL));
+        }
+
+        // The error message is passed in rdi and the line it happened on in esi, which is where printf wants them,
+        // then the program exits with an exit code of 1 (once the stack is realigned, since it's 8 bytes off here)
+        writer.write(&format!("\n{}:", RUNTIME_ERROR_LABEL));
+        writer.write("        push    rbp");
+        writer.write("        xor     eax, eax");
+        writer.write("        call    printf@PLT");
+        writer.write("        mov     edi, 1");
+        gen_exit(writer);
+
+        // Let the linker know we don't need an executable stack
+        writer.write("\n        .section .note.GNU-stack,\"\",@progbits");
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// INSTRUCTIONS
+// -----------------------------------------------------------------------------------------
+
+fn gen_instr(
+    writer: &mut ASMWriter,
+    target: &TargetInfo,
+    func: &IrFunction,
+    frame: &Frame,
+    instr: &Instr,
+    next: Option<&Instr>,
+) {
+    match instr {
+        // A constant that's never kept anywhere is put straight into whatever uses it
+        Instr::Const { dest, .. } if matches!(frame.temp_homes[*dest], TempHome::Imm(_)) => {}
+        Instr::Const { dest, value } => {
+            writer.write(&format!("        mov     eax, {}", value));
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        // A copy between two temporaries that were merged into the same register does nothing
+        Instr::Copy { dest, src } if frame.temp_homes[*dest] == frame.temp_homes[*src] => {}
+        Instr::Copy { dest, src } => {
+            gen_load_temp(writer, func, frame, "rax", *src);
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::BinOp { op, dest, lhs, rhs } => {
+            gen_load_temp(writer, func, frame, "rax", *lhs);
+            let rhs = match frame.temp_homes[*rhs] {
+                // Everything but division can take a constant as an immediate operand
+                TempHome::Imm(value)
+                    if !matches!(op, BinOp::Div | BinOp::Rem | BinOp::DivOverflows) =>
+                {
+                    value.to_string()
+                }
+                _ => {
+                    gen_load_temp(writer, func, frame, "rcx", *rhs);
+                    String::from("ecx")
+                }
+            };
+            gen_binop(writer, *op, &rhs);
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::UnOp { op, dest, src } => {
+            gen_load_temp(writer, func, frame, "rax", *src);
+            match op {
+                UnOp::Neg => writer.write("        neg     eax"),
+                // A bool is always 0 or 1, so flipping the lowest bit flips the bool
+                UnOp::Not => writer.write("        xor     eax, 1"),
+            }
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => {
+            gen_load_temp(writer, func, frame, "rax", *if_false);
+            gen_load_temp(writer, func, frame, "rcx", *if_true);
+            gen_load_temp(writer, func, frame, "rdx", *cond);
+            writer.write("        test    edx, edx");
+            writer.write(&format!(
+                "        cmovne  {}, {}",
+                get_reg("rax", func.temps[*dest]),
+                get_reg("rcx", func.temps[*dest])
+            ));
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::SlotAddr { dest, slot } => {
+            writer.write(&format!(
+                "        lea     rax, [rsp + {}]",
+                frame.slot_offsets[*slot]
+            ));
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::GlobalAddr { dest, global } => {
+            writer.write(&format!(
+                "        lea     rax, [rip + {}]",
+                get_global_label(*global)
+            ));
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::ElementAddr {
+            dest,
+            base,
+            index,
+            scale,
+        } => {
+            gen_load_temp(writer, func, frame, "rax", *base);
+            gen_load_temp(writer, func, frame, "rcx", *index);
+            writer.write("        movsxd  rcx, ecx");
+
+            if *scale == 1 || *scale == 4 {
+                writer.write(&format!("        lea     rax, [rax + rcx * {}]", scale));
+            } else {
+                // Each element is a row of a multi-dimensional array, so the row is at address + (index * row size)
+                writer.write(&format!("        imul    rcx, rcx, {}", scale));
+                writer.write("        add     rax, rcx");
+            }
+
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::Load { dest, addr, width } => {
+            gen_load_temp(writer, func, frame, "rax", *addr);
+            match width {
+                Width::Word => writer.write("        mov     eax, DWORD PTR [rax]"),
+                Width::Byte => writer.write("        movzx   eax, BYTE PTR [rax]"),
+            }
+            gen_store_temp(writer, func, frame, "rax", *dest);
+        }
+        Instr::Store { src, addr, width } => {
+            gen_load_temp(writer, func, frame, "rax", *addr);
+            gen_load_temp(writer, func, frame, "rcx", *src);
+            match width {
+                Width::Word => writer.write("        mov     DWORD PTR [rax], ecx"),
+                Width::Byte => writer.write("        mov     BYTE PTR [rax], cl"),
+            }
+        }
+        Instr::Call {
+            dests,
+            func: callee,
+            args,
+        } => {
+            // The first 6 arguments go in the argument passing registers, and the rest go on the stack,
+            // at the bottom of our stack frame (for example, argument 6 is stored at rsp + 0, argument 7 at rsp + 8, etc...)
+            for (slot, arg) in args.iter().enumerate() {
+                if slot < ARG_REGS.len() {
+                    gen_load_temp(writer, func, frame, ARG_REGS[slot], *arg);
+                } else {
+                    gen_load_temp(writer, func, frame, "rax", *arg);
+                    writer.write(&format!(
+                        "        mov     {} PTR [rsp + {}], {}",
+                        get_size_name(func.temps[*arg]),
+                        (slot - ARG_REGS.len()) * 8,
+                        get_reg("rax", func.temps[*arg])
+                    ));
+                }
+            }
+
+            writer.write(&format!(
+                "        call    {}",
+                get_func_label(target, callee)
+            ));
+
+            // Anything returned is passed back in rax and rdx
+            for (reg, dest) in dests.iter().enumerate() {
+                gen_store_temp(writer, func, frame, RETURN_REGS[reg], *dest);
+            }
+        }
+        Instr::Print { file, format, args } => {
+            // printf's first 5 format arguments go in registers, and the rest go on the stack, 8 bytes each
+            // (where dprintf, which prints to a file, takes the file in edi, leaving one less register for them)
+            let regs = &PRINT_ARG_REGS[file.is_some() as usize..];
+            for (slot, arg) in args.iter().enumerate() {
+                if slot < regs.len() {
+                    gen_load_temp(writer, func, frame, regs[slot], *arg);
+                } else {
+                    gen_load_temp(writer, func, frame, "rax", *arg);
+                    writer.write(&format!(
+                        "        mov     DWORD PTR [rsp + {}], eax",
+                        (slot - regs.len()) * 8
+                    ));
+                }
+            }
+
+            if let Some(file) = file {
+                gen_load_temp(writer, func, frame, "rdi", *file);
+            }
+            gen_printf(writer, *format, file.is_some());
+        }
+        Instr::Label(label) => {
+            writer.write(&format!("{}:", get_label_name(target, func, *label)));
+        }
+        Instr::Jump(label) => {
+            // There's no need to jump to the very next instruction
+            if next != Some(&Instr::Label(*label)) {
+                writer.write(&format!(
+                    "        jmp     {}",
+                    get_label_name(target, func, *label)
+                ));
+            }
+        }
+        Instr::Branch {
+            cond,
+            if_true,
+            if_false,
+        } => {
+            gen_load_temp(writer, func, frame, "rax", *cond);
+            writer.write("        test    eax, eax");
+
+            // Fall through to whichever label comes next, if either of them does
+            if next == Some(&Instr::Label(*if_true)) {
+                writer.write(&format!(
+                    "        je      {}",
+                    get_label_name(target, func, *if_false)
+                ));
+            } else {
+                writer.write(&format!(
+                    "        jne     {}",
+                    get_label_name(target, func, *if_true)
+                ));
+                if next != Some(&Instr::Label(*if_false)) {
+                    writer.write(&format!(
+                        "        jmp     {}",
+                        get_label_name(target, func, *if_false)
+                    ));
+                }
+            }
+        }
+        Instr::Return(values) => {
+            // Values are returned in rax and rdx
+            for (reg, value) in values.iter().enumerate() {
+                gen_load_temp(writer, func, frame, RETURN_REGS[reg], *value);
+            }
+
+            // Jump to the function exit (unless it's the very next thing)
+            if next.is_some() {
+                writer.write(&format!(
+                    "        jmp     {}",
+                    get_return_label(target, func)
+                ));
+            }
+        }
+        Instr::Panic { message, line } => {
+            // Exit the program through the runtime library, which prints the error message
+            writer.write(&format!(
+                "        lea     rdi, [rip + {}]",
+                get_global_label(*message)
+            ));
+            writer.write(&format!("        mov     esi, {}", line));
+            writer.write(&format!("        call    {}", RUNTIME_ERROR_LABEL));
+        }
+        Instr::Line(line) => gen_line(writer, *line),
+    }
+}
+
+// Combine eax and the given operand (ecx, or an immediate unless dividing) with the given operator,
+// leaving the result in eax
+fn gen_binop(writer: &mut ASMWriter, op: BinOp, rhs: &str) {
+    // Multiplying by an immediate takes three operands
+    let mul = if rhs == "ecx" {
+        String::from("imul    eax, ecx")
+    } else {
+        format!("imul    eax, eax, {}", rhs)
+    };
+
+    let cond = match op {
+        BinOp::Add => return writer.write(&format!("        add     eax, {}", rhs)),
+        BinOp::Sub => return writer.write(&format!("        sub     eax, {}", rhs)),
+        BinOp::Mul => return writer.write(&format!("        {}", mul)),
+        BinOp::Div | BinOp::Rem => return gen_division(writer, op == BinOp::Rem),
+        BinOp::Eq => "e",
+        BinOp::Ne => "ne",
+        BinOp::Lt => "l",
+        BinOp::Gt => "g",
+        BinOp::Le => "le",
+        BinOp::Ge => "ge",
+        BinOp::ULt => "b",
+        // The overflow flag is set if the signed result doesn't fit
+        BinOp::AddOverflows => return gen_overflows(writer, &format!("add     eax, {}", rhs)),
+        BinOp::SubOverflows => return gen_overflows(writer, &format!("sub     eax, {}", rhs)),
+        BinOp::MulOverflows => return gen_overflows(writer, &mul),
+        // Dividing overflows if eax is the smallest int (so flipping its top bit leaves 0)
+        // and ecx is -1 (so flipping every bit leaves 0)
+        BinOp::DivOverflows => {
+            writer.write("        xor     eax, -2147483648");
+            writer.write("        not     ecx");
+            writer.write("        or      eax, ecx");
+            writer.write("        sete    al");
+            return writer.write("        movzx   eax, al");
+        }
+    };
+
+    // eax is 1 if the comparison holds and 0 otherwise
+    writer.write(&format!("        cmp     eax, {}", rhs));
+    writer.write(&format!("        set{}    al", cond));
+    writer.write("        movzx   eax, al");
+}
+
+// Do the given arithmetic on eax, leaving 1 in eax if it overflowed and 0 otherwise
+fn gen_overflows(writer: &mut ASMWriter, instr: &str) {
+    writer.write(&format!("        {}", instr));
+    writer.write("        seto    al");
+    writer.write("        movzx   eax, al");
+}
+
+// Divide eax by ecx, leaving either the quotient or the remainder in eax
+fn gen_division(writer: &mut ASMWriter, remainder: bool) {
+    // idiv traps when the quotient doesn't fit (i.e. INT_MIN / -1), so dividing by -1 is done by hand instead,
+    // wrapping around just like it does on ARM64
+    writer.write("        cmp     ecx, -1");
+    writer.write("        jne     1f");
+    if remainder {
+        writer.write("        xor     eax, eax");
+    } else {
+        writer.write("        neg     eax");
+    }
+    writer.write("        jmp     2f");
+
+    // The quotient ends up in eax and the remainder in edx
+    writer.write("1:");
+    writer.write("        cdq");
+    writer.write("        idiv    ecx");
+    if remainder {
+        writer.write("        mov     eax, edx");
+    }
+    writer.write("2:");
+}
+
+// Call printf with the given format string global (once any format arguments are in place),
+// or dprintf if it's being printed to a file (once the file is in edi)
+fn gen_printf(writer: &mut ASMWriter, format: usize, to_file: bool) {
+    let (reg, callee) = if to_file {
+        ("rsi", "dprintf")
+    } else {
+        ("rdi", "printf")
+    };
+    writer.write(&format!(
+        "        lea     {}, [rip + {}]",
+        reg,
+        get_global_label(format)
+    ));
+    // printf is variadic, so it needs to be told how many vector registers hold arguments (none)
+    writer.write("        xor     eax, eax");
+    writer.write(&format!("        call    {}@PLT", callee));
+}
+
+// Exit the program through the C library (so anything printf is still holding onto gets written out),
+// with the exit code already in edi, writing out the program's counts first if it's been instrumented for coverage
+fn gen_exit(writer: &mut ASMWriter) {
+    if writer.coverage.is_some() {
+        writer.write(&format!("        call    {}", COVERAGE_LABEL));
+    }
+    writer.write("        call    exit@PLT");
+}
+
+// -----------------------------------------------------------------------------------------
+// HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Load the given temporary into a register (given by its 64 bit name)
+fn gen_load_temp(writer: &mut ASMWriter, func: &IrFunction, frame: &Frame, reg: &str, temp: Temp) {
+    writer.write(&format!(
+        "        mov     {}, {}",
+        get_reg(reg, func.temps[temp]),
+        get_home(func, frame, temp)
+    ));
+}
+
+// Store a register (given by its 64 bit name) into the given temporary
+fn gen_store_temp(writer: &mut ASMWriter, func: &IrFunction, frame: &Frame, reg: &str, temp: Temp) {
+    // A constant is never set by anything but its const instruction, which is skipped
+    if let TempHome::Imm(_) = frame.temp_homes[temp] {
+        return;
+    }
+
+    writer.write(&format!(
+        "        mov     {}, {}",
+        get_home(func, frame, temp),
+        get_reg(reg, func.temps[temp])
+    ));
+}
+
+// Get the operand a temporary is kept in, which is either a register, a memory access to its place on the stack,
+// or the constant it holds (which is only ever loaded)
+fn get_home(func: &IrFunction, frame: &Frame, temp: Temp) -> String {
+    return match frame.temp_homes[temp] {
+        TempHome::Imm(value) => value.to_string(),
+        TempHome::Reg(reg) => get_reg(SAVED_REGS[reg], func.temps[temp]),
+        TempHome::Stack(offset) => {
+            format!("{} PTR [rsp + {}]", get_size_name(func.temps[temp]), offset)
+        }
+    };
+}
+
+// Get the name of a register (given by its 64 bit name), as a 32 bit register for an int or a 64 bit register for an address
+fn get_reg(reg: &str, temp_type: IrType) -> String {
+    return match temp_type {
+        IrType::Ptr => String::from(reg),
+        // r8 - r15 have a d on the end of their 32 bit names, and the rest swap their r for an e
+        IrType::Int if reg.starts_with("r") && reg[1..].parse::<u32>().is_ok() => {
+            format!("{}d", reg)
+        }
+        IrType::Int => format!("e{}", &reg[1..]),
+    };
+}
+
+// Get the size of a memory access to a value of the given type
+fn get_size_name(temp_type: IrType) -> &'static str {
+    return match temp_type {
+        IrType::Int => "DWORD",
+        IrType::Ptr => "QWORD",
+    };
+}
@@ -1,6 +1,17 @@
-pub mod code_gen_callbacks;
+pub mod code_gen_arm64;
+pub mod code_gen_c;
 pub mod code_gen_data;
 pub mod code_gen_driver;
 pub mod code_gen_generators;
-pub mod code_gen_traversals;
+pub mod code_gen_llvm;
+pub mod code_gen_peephole;
+pub mod code_gen_registers;
+pub mod code_gen_riscv64;
+pub mod code_gen_target;
 pub mod code_gen_utils;
+pub mod code_gen_wasm;
+pub mod code_gen_x86_64;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,1310 @@
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::cli::cli_test_suite::describe_mismatch;
+    use crate::code_gen::code_gen_c::format_c;
+    use crate::code_gen::code_gen_data::*;
+    use crate::code_gen::code_gen_driver::{code_gen, format_frames, get_code_generator};
+    use crate::code_gen::code_gen_llvm::format_llvm_ir;
+    use crate::code_gen::code_gen_peephole::optimize_asm;
+    use crate::code_gen::code_gen_registers::{allocate_registers, color_registers};
+    use crate::code_gen::code_gen_target::*;
+    use crate::code_gen::code_gen_utils::{
+        get_frame, get_func_label, get_label_name, get_return_label,
+    };
+    use crate::compile_str;
+    use crate::ir::ir_data::*;
+    use crate::ir::ir_driver::lower_program;
+    use crate::optimizer::optimizer_driver::optimize;
+    use crate::parser::parser_driver::{parse_program, parser};
+    use crate::scanner::scanner_driver::{scan_str, scanner};
+    use crate::semantic::semantic_data::Warnings;
+    use crate::semantic::semantic_driver::semantic_checker;
+
+    // Parse each line of assembly, run the peephole optimizer over it and write it back out
+    fn optimize_lines(lines: Vec<&str>) -> Vec<String> {
+        let lines = lines.iter().map(|line| AsmLine::parse(line)).collect();
+        return optimize_asm(lines)
+            .iter()
+            .map(|line| line.to_text())
+            .collect();
+    }
+
+    #[test]
+    fn test_parse_asm_line() {
+        let expected = AsmLine::Instr(AsmInstr {
+            op: String::from("stp"),
+            operands: vec![
+                String::from("x29"),
+                String::from("x30"),
+                String::from("[sp, -16]!"),
+            ],
+            comment: None,
+        });
+        assert_eq!(
+            expected,
+            AsmLine::parse("        stp     x29, x30, [sp, -16]!")
+        );
+        assert_eq!(
+            AsmLine::Label(String::from("soup_main")),
+            AsmLine::parse("soup_main:")
+        );
+
+        // Every kind of line is written back out exactly as it was generated
+        for line in [
+            "        mov     x0, 1  // Return code 1",
+            "        ret",
+            "LG0: .word 5",
+            "        .global _start",
+            "",
+        ] {
+            assert_eq!(line, AsmLine::parse(line).to_text());
+        }
+    }
+
+    #[test]
+    fn test_peephole_redundant_movs() {
+        let optimized = optimize_lines(vec![
+            "        mov     w9, w9",
+            "        mov     w9, w0",
+            "        mov     w0, w9",
+        ]);
+        assert_eq!(vec!["        mov     w9, w0"], optimized);
+    }
+
+    #[test]
+    fn test_peephole_load_after_store() {
+        // The load turns into a move of w9 into itself, which is then removed too
+        let optimized = optimize_lines(vec![
+            "        str     w9, [sp, 8]",
+            "        ldr     w9, [sp, 8]",
+            "        str     w9, [sp, 12]",
+            "        ldr     w10, [sp, 12]",
+        ]);
+        let expected = vec![
+            "        str     w9, [sp, 8]",
+            "        str     w9, [sp, 12]",
+            "        mov     w10, w9",
+        ];
+        assert_eq!(expected, optimized);
+
+        // A label in between could be jumped to, and a load of a different size reads something else
+        let lines = vec![
+            "        str     w9, [sp, 8]",
+            ".Lfn_main_0:",
+            "        ldr     w9, [sp, 8]",
+            "        str     w9, [sp, 16]",
+            "        ldr     x9, [sp, 16]",
+        ];
+        assert_eq!(lines, optimize_lines(lines.clone()));
+    }
+
+    #[test]
+    fn test_target_info() {
+        assert_eq!("arm64-apple-darwin", TARGETS[0].triple);
+        assert_eq!(
+            Arch::Riscv64,
+            get_target_info("riscv64-unknown-linux-gnu").unwrap().arch
+        );
+        assert!(get_target_info("z80").is_none());
+        assert_eq!(TARGETS.len(), get_target_triples().len());
+    }
+
+    #[test]
+    fn test_arm64_targets() {
+        // func main() returns void { printf("{}", 7); }
+        let mut func = IrFunction::new("main");
+        func.temps = vec![IrType::Int];
+        func.body = vec![
+            Instr::Const { dest: 0, value: 7 },
+            Instr::Print {
+                file: None,
+                format: 0,
+                args: vec![0],
+            },
+            Instr::Return(vec![]),
+        ];
+
+        let gen_lines = |triple: &str| -> Vec<String> {
+            let generator = get_code_generator(get_target_info(triple).unwrap());
+            let mut writer = ASMWriter::new();
+            generator.gen_function(&mut writer, &func);
+            generator.gen_runtime_lib(&mut writer, &IrProgram::new());
+            return writer.lines.iter().map(|line| line.to_text()).collect();
+        };
+
+        // macOS passes printf's format arguments on the stack, and exits with a system call
+        let lines = gen_lines("arm64-apple-darwin");
+        for expected in [
+            "        str     w9, [sp, 0]",
+            "        add     x0, x0, LG0@PAGEOFF",
+            "        bl      _printf",
+            "        svc     0x80  // Make system call",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+
+        // while Linux passes them in registers, and exits through the C library
+        let lines = gen_lines("aarch64-unknown-linux-gnu");
+        for expected in [
+            "        mov     w1, 7",
+            "        add     x0, x0, :lo12:LG0",
+            "        bl      printf",
+            "        bl      exit",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_arm64_stack_args() {
+        // func pass(int a, ..., int j, int[] k) returns int { return pass(a, ..., j, k); },
+        // where j and k (an address, then a length) are passed on the stack
+        let mut func = IrFunction::new("pass");
+        func.temps = vec![IrType::Int; 13];
+        func.temps[10] = IrType::Ptr;
+        func.params = (0..12).collect();
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::Call {
+                dests: vec![12],
+                func: String::from("pass"),
+                args: (0..12).collect(),
+            },
+            Instr::Return(vec![12]),
+        ];
+
+        let gen_lines = |triple: &str| -> Vec<String> {
+            let mut writer = ASMWriter::new();
+            get_code_generator(get_target_info(triple).unwrap()).gen_function(&mut writer, &func);
+            return writer.lines.iter().map(|line| line.to_text()).collect();
+        };
+
+        // macOS packs the stack arguments together by their size (while keeping the address 8 byte aligned)
+        let lines = gen_lines("arm64-apple-darwin");
+        for expected in [
+            "        ldr     w28, [x29, 20]",
+            "        ldr     x9, [x29, 24]",
+            "        ldr     w9, [x29, 32]",
+            "        str     w28, [sp, 4]",
+            "        str     x9, [sp, 8]",
+            "        str     w9, [sp, 16]",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+
+        // while Linux gives each one 8 bytes
+        let lines = gen_lines("aarch64-unknown-linux-gnu");
+        for expected in [
+            "        ldr     w28, [x29, 24]",
+            "        ldr     x9, [x29, 32]",
+            "        ldr     w9, [x29, 40]",
+            "        str     w28, [sp, 8]",
+            "        str     x9, [sp, 16]",
+            "        str     w9, [sp, 24]",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_rodata() {
+        let mut program = IrProgram::new();
+        program.add_global("format", GlobalKind::String(b"x = %d\n".to_vec()));
+        program.add_global("x", GlobalKind::Word(1));
+
+        // The strings are never written to, so they're collected into the read-only section after everything else
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        let mut writer = ASMWriter::new();
+        get_code_generator(target).gen_globals(&mut writer, &program);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        let expected = vec![
+            ".intel_syntax noprefix",
+            ".data",
+            ".balign 4",
+            "LG1: .long 1",
+            ".section .rodata",
+            "LG0: .string \"x = %d\\n\"",
+            "last_newline: .string \"\\n\"",
+            ".text",
+        ];
+        assert_eq!(expected, lines);
+
+        // and on macOS they go in the C string literal section
+        let target = get_target_info("arm64-apple-darwin").unwrap();
+        let mut writer = ASMWriter::new();
+        get_code_generator(target).gen_globals(&mut writer, &program);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        assert!(lines.contains(&String::from(".section __TEXT,__cstring,cstring_literals")));
+    }
+
+    #[test]
+    fn test_label_names() {
+        // Functions are exported under the reserved prefix (with the target's own symbol prefix in front of it),
+        // so a function named like one of our labels can't clash with it
+        let mut func = IrFunction::new("LG0");
+        func.body = vec![Instr::Label(1), Instr::Jump(1), Instr::Return(vec![])];
+
+        // while labels are local to the function and the object file, on both macOS and Linux
+        let darwin = get_target_info("arm64-apple-darwin").unwrap();
+        let linux = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!("_soup_LG0", get_func_label(darwin, &func.name));
+        assert_eq!("soup_LG0", get_func_label(linux, &func.name));
+        assert_eq!("Lfn_LG0_1", get_label_name(darwin, &func, 1));
+        assert_eq!(".Lfn_LG0_ret", get_return_label(linux, &func));
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(linux).gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        for expected in [
+            "        .globl  soup_LG0",
+            "        .type   soup_LG0, %function",
+            "soup_LG0:",
+            ".Lfn_LG0_1:",
+            "        jmp     .Lfn_LG0_1",
+            ".Lfn_LG0_ret:",
+        ] {
+            assert!(
+                lines.contains(&String::from(expected)),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_info() {
+        let mut func = IrFunction::new("main");
+        func.body = vec![Instr::Line(3), Instr::Return(vec![])];
+        let mut program = IrProgram::new();
+        program.functions.push(func);
+        program.files.push(String::from("main.soup"));
+
+        // Line markers only turn into .loc directives when we're generating debug info
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        let mut writer = ASMWriter::new();
+        get_code_generator(target).gen_function(&mut writer, &program.functions[0]);
+        assert!(!writer
+            .lines
+            .iter()
+            .any(|line| line.to_text().contains(".loc")));
+
+        writer.debug_info = true;
+        get_code_generator(target).gen_function(&mut writer, &program.functions[0]);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        assert!(lines.contains(&String::from("        .loc 1 3")));
+    }
+
+    #[test]
+    fn test_asm_comments() {
+        let mut func = IrFunction::new("main");
+        func.body = vec![Instr::Line(2), Instr::Return(vec![])];
+
+        // Each line marker is commented with its line of soup, in the target's own comment syntax
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        let mut writer = ASMWriter::new();
+        writer.comment_prefix = target.comment_prefix;
+        writer.source_lines = Some(vec![vec![
+            String::from("func main() {"),
+            String::from("    x = add1(return1());"),
+        ]]);
+        get_code_generator(target).gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        assert!(lines.contains(&String::from("        # line 2: x = add1(return1());")));
+        assert!(!lines.iter().any(|line| line.contains(".loc")));
+
+        // and the peephole optimizer looks straight past the comment
+        let lines = vec![
+            AsmLine::parse("        str     w9, [sp, 8]"),
+            AsmLine::Comment(String::from("        // line 2: x = 1;")),
+            AsmLine::parse("        ldr     w9, [sp, 8]"),
+        ];
+        let optimized: Vec<String> = optimize_asm(lines)
+            .iter()
+            .map(|line| line.to_text())
+            .collect();
+        let expected = vec!["        str     w9, [sp, 8]", "        // line 2: x = 1;"];
+        assert_eq!(expected, optimized);
+    }
+
+    #[test]
+    fn test_allocate_registers() {
+        // int i = 0; while (i < 10) { i += 1; }, with one temporary that's never used
+        let mut func = IrFunction::new("main");
+        func.temps = vec![IrType::Int; 5];
+        func.num_labels = 3;
+        func.body = vec![
+            Instr::Const { dest: 0, value: 0 },
+            Instr::Label(0),
+            Instr::Const { dest: 1, value: 10 },
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Branch {
+                cond: 2,
+                if_true: 1,
+                if_false: 2,
+            },
+            Instr::Label(1),
+            Instr::Const { dest: 3, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 0,
+                lhs: 0,
+                rhs: 3,
+            },
+            Instr::Jump(0),
+            Instr::Label(2),
+            Instr::Return(vec![]),
+        ];
+
+        // Registers are reused once whatever held them is no longer live
+        assert_eq!(
+            vec![Some(0), Some(1), Some(2), Some(1), None],
+            allocate_registers(&func, 3, &[false; 5])
+        );
+
+        // and when there aren't enough, i (which is live for the longest) is left on the stack
+        assert_eq!(
+            vec![None, Some(1), Some(0), Some(0), None],
+            allocate_registers(&func, 2, &[false; 5])
+        );
+        assert_eq!(vec![None; 5], allocate_registers(&func, 0, &[false; 5]));
+    }
+
+    #[test]
+    fn test_color_registers() {
+        // func inc(int x) returns int { int y = x + 1; return y; }
+        let mut func = IrFunction::new("inc");
+        func.temps = vec![IrType::Int; 4];
+        func.params = vec![0];
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::Const { dest: 1, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Copy { dest: 3, src: 2 },
+            Instr::Return(vec![3]),
+        ];
+
+        // Both sides of the copy share a register (so it does nothing), and so can the constant,
+        // which only interferes with x
+        assert_eq!(
+            vec![Some(1), Some(0), Some(0), Some(0)],
+            color_registers(&func, 2, &[false; 4])
+        );
+
+        // With only one register to go around, one of x and the constant is left on the stack
+        assert_eq!(
+            vec![Some(0), None, Some(0), Some(0)],
+            color_registers(&func, 1, &[false; 4])
+        );
+
+        // unless the constant doesn't need one (since it can be used as an immediate operand)
+        assert_eq!(
+            vec![Some(0), None, Some(0), Some(0)],
+            color_registers(&func, 1, &[false, true, false, false])
+        );
+        assert_eq!(vec![None; 4], color_registers(&func, 0, &[false; 4]));
+    }
+
+    #[test]
+    fn test_register_pressure() {
+        // Set 30 temporaries (to constants too big to be immediate operands), then add them all up,
+        // so they're all live at once
+        let mut func = IrFunction::new("sum");
+        func.temps = vec![IrType::Int; 59];
+        func.returns = vec![IrType::Int];
+        for temp in 0..30 {
+            func.body.push(Instr::Const {
+                dest: temp,
+                value: 10000 + temp as i32,
+            });
+        }
+        let mut total = 0;
+        for temp in 1..30 {
+            func.body.push(Instr::BinOp {
+                op: BinOp::Add,
+                dest: 29 + temp,
+                lhs: total,
+                rhs: temp,
+            });
+            total = 29 + temp;
+        }
+        func.body.push(Instr::Return(vec![total]));
+
+        // Every register gets used (and saved), and whatever doesn't fit is kept on the stack instead
+        for target in TARGETS.iter().filter(|target| target.arch != Arch::Wasm32) {
+            for graph_coloring in [false, true] {
+                let frame = get_frame(&func, target, graph_coloring);
+                let num_regs = frame
+                    .temp_homes
+                    .iter()
+                    .filter(|home| matches!(home, TempHome::Reg(_)))
+                    .count();
+                assert!(num_regs >= target.num_saved_regs, "{}", target.triple);
+                assert!(num_regs < func.temps.len(), "{}", target.triple);
+                assert_eq!(target.num_saved_regs, frame.saved_regs.len());
+            }
+
+            let mut writer = ASMWriter::new();
+            get_code_generator(target).gen_function(&mut writer, &func);
+        }
+    }
+
+    #[test]
+    fn test_format_frames() {
+        // func f(int x) returns int { int k = 7; int grid[3]; grid[0] = x; return k; }
+        let mut func = IrFunction::new("f");
+        let x = func.new_temp(IrType::Int);
+        let k = func.new_temp(IrType::Int);
+        let base = func.new_temp(IrType::Ptr);
+        let grid = func.new_slot(12);
+        func.params.push(x);
+        func.returns.push(IrType::Int);
+        func.name_temp(x, "x");
+        func.name_temp(k, "k");
+        func.name_slot(grid, "grid");
+        func.body = vec![
+            Instr::Const { dest: k, value: 7 },
+            Instr::SlotAddr {
+                dest: base,
+                slot: grid,
+            },
+            Instr::Store {
+                src: x,
+                addr: base,
+                width: Width::Word,
+            },
+            Instr::Return(vec![k]),
+        ];
+        let mut program = IrProgram::new();
+        program.functions.push(func);
+
+        let target = TARGETS
+            .iter()
+            .find(|target| target.triple == "x86_64-unknown-linux-gnu")
+            .unwrap();
+        // The constant never needs a home, and the slot's address is worked out into a register of its own
+        let expected = "function 'f' (32 byte frame, saving 2 registers):
+    name  kind   size  home
+    x     param  4     ebx
+    k     local  4     constant 7
+    grid  array  12    [sp, 0]
+
+";
+        assert_eq!(expected, format_frames(&program, 0, target));
+    }
+
+    #[test]
+    fn test_immediate_operands() {
+        // func f(int x) returns int { return (x + 1) * 2 < 5000; }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 8];
+        func.params = vec![0];
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::Const { dest: 1, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Const { dest: 3, value: 2 },
+            Instr::BinOp {
+                op: BinOp::Mul,
+                dest: 4,
+                lhs: 2,
+                rhs: 3,
+            },
+            Instr::Const {
+                dest: 5,
+                value: 5000,
+            },
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 6,
+                lhs: 4,
+                rhs: 5,
+            },
+            Instr::Return(vec![6]),
+        ];
+
+        let gen_lines = |triple: &str| -> Vec<String> {
+            let mut writer = ASMWriter::new();
+            get_code_generator(get_target_info(triple).unwrap()).gen_function(&mut writer, &func);
+            return writer.lines.iter().map(|line| line.to_text()).collect();
+        };
+
+        // Small constants are given straight to the instructions that can take them, or put in a scratch register
+        // by the ones that can't, while anything bigger is kept in a register like any other temporary
+        let expected = [
+            (
+                "arm64-apple-darwin",
+                [
+                    "        add     w20, w19, 1",
+                    "        mov     w10, 2",
+                    "        cmp     w19, w20",
+                ],
+            ),
+            (
+                "x86_64-unknown-linux-gnu",
+                [
+                    "        add     eax, 1",
+                    "        imul    eax, eax, 2",
+                    "        cmp     eax, ecx",
+                ],
+            ),
+            (
+                "riscv64-unknown-linux-gnu",
+                [
+                    "        addiw   t0, t0, 1",
+                    "        li      t1, 2",
+                    "        slt     t0, t0, t1",
+                ],
+            ),
+        ];
+        for (triple, expected) in expected {
+            let lines = gen_lines(triple);
+            for expected in expected {
+                assert!(
+                    lines.iter().any(|line| line == expected),
+                    "missing {} on {}",
+                    expected,
+                    triple
+                );
+            }
+            assert_eq!(1, lines.iter().filter(|line| line.contains("5000")).count());
+        }
+    }
+
+    #[test]
+    fn test_immediate_operand_range() {
+        // func f(int x) returns bool { return x + 4095 + -4095 - -1 + 4096 - -4096 < -5; }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 13];
+        func.params = vec![0];
+        func.returns = vec![IrType::Int];
+        let consts = [
+            (1, 4095),
+            (3, -4095),
+            (5, -1),
+            (7, 4096),
+            (9, -4096),
+            (11, -5),
+        ];
+        let ops = [
+            BinOp::Add,
+            BinOp::Add,
+            BinOp::Sub,
+            BinOp::Add,
+            BinOp::Sub,
+            BinOp::Lt,
+        ];
+        for ((rhs, value), op) in consts.into_iter().zip(ops) {
+            func.body.push(Instr::Const { dest: rhs, value });
+            func.body.push(Instr::BinOp {
+                op,
+                dest: rhs + 1,
+                lhs: rhs - 1,
+                rhs,
+            });
+        }
+        func.body.push(Instr::Return(vec![12]));
+
+        // Only constants that fit in 12 bits (of either sign) are kept out of registers
+        let target = get_target_info("arm64-apple-darwin").unwrap();
+        let frame = get_frame(&func, target, false);
+        assert_eq!(TempHome::Imm(4095), frame.temp_homes[1]);
+        assert_eq!(TempHome::Imm(-4095), frame.temp_homes[3]);
+        assert!(!matches!(frame.temp_homes[7], TempHome::Imm(_)));
+        assert!(!matches!(frame.temp_homes[9], TempHome::Imm(_)));
+
+        // and a negative one is given to the opposite instruction
+        let mut writer = ASMWriter::new();
+        get_code_generator(target).gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+        for expected in [
+            "        add     w20, w19, 4095",
+            "        sub     w19, w20, 4095",
+            "        add     w20, w19, 1",
+            "        mov     w19, 4096",
+            "        mov     w19, -4096",
+            "        sub     w20, w21, w19",
+            "        cmn     w20, 5",
+        ] {
+            assert!(
+                lines.iter().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_mul_add() {
+        // func f(int a, int b, int c) returns int, int, int {
+        //     return a * b + c, c - a * b, a * b - c;
+        // }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 9];
+        func.params = vec![0, 1, 2];
+        func.returns = vec![IrType::Int; 3];
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Mul,
+                dest: 3,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 4,
+                lhs: 3,
+                rhs: 2,
+            },
+            Instr::BinOp {
+                op: BinOp::Mul,
+                dest: 5,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Line(2),
+            Instr::BinOp {
+                op: BinOp::Sub,
+                dest: 6,
+                lhs: 2,
+                rhs: 5,
+            },
+            Instr::BinOp {
+                op: BinOp::Mul,
+                dest: 7,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::BinOp {
+                op: BinOp::Sub,
+                dest: 8,
+                lhs: 7,
+                rhs: 2,
+            },
+            Instr::Return(vec![4, 6, 8]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("arm64-apple-darwin").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // A product that's only added to something, or subtracted from something, is never kept anywhere
+        assert_eq!(
+            1,
+            lines
+                .iter()
+                .filter(|line| *line == "        madd    w23, w19, w20, w21")
+                .count()
+        );
+        assert_eq!(
+            1,
+            lines
+                .iter()
+                .filter(|line| *line == "        msub    w24, w19, w20, w21")
+                .count()
+        );
+
+        // but one that has something subtracted from it is
+        assert_eq!(
+            1,
+            lines
+                .iter()
+                .filter(|line| line.starts_with("        mul "))
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_compare_branches() {
+        // func f(int x, int y) returns int {
+        //     if x == 0 { return 1; }
+        //     if x < y { return 2; }
+        //     return 3;
+        // }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 8];
+        func.params = vec![0, 1];
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::Const { dest: 2, value: 0 },
+            Instr::BinOp {
+                op: BinOp::Eq,
+                dest: 3,
+                lhs: 0,
+                rhs: 2,
+            },
+            Instr::Branch {
+                cond: 3,
+                if_true: 0,
+                if_false: 1,
+            },
+            Instr::Label(0),
+            Instr::Const { dest: 4, value: 1 },
+            Instr::Return(vec![4]),
+            Instr::Label(1),
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 5,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Branch {
+                cond: 5,
+                if_true: 2,
+                if_false: 3,
+            },
+            Instr::Label(2),
+            Instr::Const { dest: 6, value: 2 },
+            Instr::Return(vec![6]),
+            Instr::Label(3),
+            Instr::Const { dest: 7, value: 3 },
+            Instr::Return(vec![7]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("arm64-apple-darwin").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // Each comparison is branched on directly (branching to the false label when it doesn't hold,
+        // since the true label comes next), where comparing with zero doesn't need a comparison at all
+        assert!(lines
+            .iter()
+            .any(|line| line == "        cbnz    w19, Lfn_f_1"));
+        assert!(lines.iter().any(|line| line == "        cmp     w19, w20"));
+        assert!(lines.iter().any(|line| line == "        b.ge    Lfn_f_3"));
+        assert!(!lines.iter().any(|line| line.contains("cset")));
+    }
+
+    #[test]
+    fn test_compare_other_branch() {
+        // func f(bool b, int x, int y) returns bool {
+        //     bool less = x < y;
+        //     if b { return less; }
+        //     return false;
+        // }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 5];
+        func.params = vec![0, 1, 2];
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 3,
+                lhs: 1,
+                rhs: 2,
+            },
+            Instr::Branch {
+                cond: 0,
+                if_true: 0,
+                if_false: 1,
+            },
+            Instr::Label(0),
+            Instr::Return(vec![3]),
+            Instr::Label(1),
+            Instr::Const { dest: 4, value: 0 },
+            Instr::Return(vec![4]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("arm64-apple-darwin").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // A comparison followed by a branch on something else isn't fused with it, so the comparison's result
+        // is kept, and the branch is on its own condition
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("        cset    ")));
+        assert!(lines
+            .iter()
+            .any(|line| line == "        cbz     w19, Lfn_f_1"));
+        assert!(!lines.iter().any(|line| line.starts_with("        b.")));
+    }
+
+    #[test]
+    fn test_select() {
+        // func f(int x, int a, int b) returns int, int { return x < a ? a : b, x != 0 ? a : b; }
+        let mut func = IrFunction::new("f");
+        func.temps = vec![IrType::Int; 7];
+        func.params = vec![0, 1, 2];
+        func.returns = vec![IrType::Int; 2];
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 3,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Select {
+                dest: 4,
+                cond: 3,
+                if_true: 1,
+                if_false: 2,
+            },
+            Instr::Select {
+                dest: 5,
+                cond: 0,
+                if_true: 1,
+                if_false: 2,
+            },
+            Instr::Return(vec![4, 5]),
+        ];
+
+        let gen_lines = |triple: &str| -> Vec<String> {
+            let mut writer = ASMWriter::new();
+            get_code_generator(get_target_info(triple).unwrap()).gen_function(&mut writer, &func);
+            return writer.lines.iter().map(|line| line.to_text()).collect();
+        };
+
+        // Neither select branches, where on ARM64 the comparison right before one is selected on directly
+        let expected = [
+            (
+                "arm64-apple-darwin",
+                vec![
+                    "        csel    w23, w20, w21, lt",
+                    "        csel    w22, w20, w21, ne",
+                ],
+            ),
+            ("x86_64-unknown-linux-gnu", vec!["        cmovne  eax, ecx"]),
+            (
+                "riscv64-unknown-linux-gnu",
+                vec!["        neg     t0, t0", "        and     t1, t1, t0"],
+            ),
+        ];
+        for (triple, expected) in expected {
+            let lines = gen_lines(triple);
+            for expected in expected {
+                assert!(
+                    lines.iter().any(|line| line == expected),
+                    "missing {} on {}",
+                    expected,
+                    triple
+                );
+            }
+        }
+        assert!(!gen_lines("arm64-apple-darwin")
+            .iter()
+            .any(|line| line.contains("cset")));
+    }
+
+    #[test]
+    fn test_arm64_register_homes() {
+        let source = "func f(int x, int y) returns int {\nint z = x + 1;\nint w = y * 2;\nreturn w + z;\n}\nfunc main() returns void {\nprintf(\"{}\\n\", f(3, 4));\n}\n";
+
+        let asm = compile_str(source, get_target_info("arm64-apple-darwin").unwrap(), 2).unwrap();
+        let body: Vec<&str> = asm
+            .lines()
+            .skip_while(|line| *line != "_soup_f:")
+            .take_while(|line| *line != "Lfn_f_ret:")
+            .collect();
+
+        // Every operation works on the registers its operands and result are kept in, so adding a constant
+        // is a single instruction, and nothing is moved through a scratch register on its way to or from them
+        assert!(body.iter().any(|line| {
+            let operands: Vec<&str> = line
+                .trim_start_matches("        add     ")
+                .split(", ")
+                .collect();
+            return line.starts_with("        add     ")
+                && operands.len() == 3
+                && operands[0].starts_with('w')
+                && operands[1].starts_with('w')
+                && operands[2] == "1";
+        }));
+        assert!(!body.iter().any(|line| line.contains("w9")));
+    }
+
+    #[test]
+    fn test_x86_64_function() {
+        // func div(int a, int b) returns int { return a / b; }
+        let mut func = IrFunction::new("div");
+        func.temps = vec![IrType::Int; 3];
+        func.params = vec![0, 1];
+        func.returns = vec![IrType::Int];
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Div,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Return(vec![2]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("x86_64-unknown-linux-gnu").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // The parameters arrive in edi and esi and are kept in callee-saved registers (which are saved and restored),
+        // the division can't trap on -1, and the result is returned in eax
+        for expected in [
+            "        mov     QWORD PTR [rsp + 0], rbx",
+            "        mov     ebx, edi",
+            "        mov     r12d, esi",
+            "        cmp     ecx, -1",
+            "        idiv    ecx",
+            "        mov     eax, r13d",
+            "        mov     rbx, QWORD PTR [rsp + 0]",
+        ] {
+            assert!(
+                lines.contains(&String::from(expected)),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_riscv64_function() {
+        // func less(int a, int b) returns bool { return a <= b; }, with a stack frame too big for an addi
+        let mut func = IrFunction::new("less");
+        func.temps = vec![IrType::Int; 3];
+        func.params = vec![0, 1];
+        func.returns = vec![IrType::Int];
+        func.slots = vec![4000];
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Le,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Return(vec![2]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("riscv64-unknown-linux-gnu").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // The parameters arrive in a0 and a1 (and are kept in s1 and s2, which are saved above the stack slot),
+        // a <= b is !(b < a), and the result is returned in a0
+        for expected in [
+            "        li      t3, -4032",
+            "        add     sp, sp, t3",
+            "        sd      s1, 0(t3)",
+            "        sext.w  s1, a0",
+            "        sext.w  s2, a1",
+            "        slt     t0, t1, t0",
+            "        xori    t0, t0, 1",
+            "        mv      a0, s3",
+        ] {
+            assert!(
+                lines.contains(&String::from(expected)),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_global_data() {
+        let source = "int x;\nint y = -7;\nbool b = true;\nint grid[2][3];\nfunc main() returns void {\nprintf(\"{}\\n\", x + y);\n}\n";
+
+        // Every int and bool global gets a word in the data section holding its initial value (zero if it isn't given one),
+        // and every global array gets its zeroed elements
+        for target in TARGETS.iter().filter(|target| target.arch != Arch::Wasm32) {
+            let asm = compile_str(source, target, 2).unwrap();
+            let data = asm.split(".data\n").nth(1).unwrap();
+            let data = data.split(target.rodata_section).next().unwrap();
+
+            for expected in [
+                format!("LG0: {} 0", target.word_directive),
+                format!("LG1: {} -7", target.word_directive),
+                format!("LG2: {} 1", target.word_directive),
+                format!("LG3: {} 24", target.zero_directive),
+            ] {
+                assert!(
+                    data.lines().any(|line| line == expected),
+                    "missing {} for {}",
+                    expected,
+                    target.triple
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_wasm_function() {
+        // func div(int a, int b) returns int { return a / b; }
+        let mut func = IrFunction::new("div");
+        func.temps = vec![IrType::Int; 3];
+        func.params = vec![0, 1];
+        func.returns = vec![IrType::Int];
+        func.name_temp(0, "a");
+        func.name_temp(1, "b");
+        func.body = vec![
+            Instr::BinOp {
+                op: BinOp::Div,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Return(vec![2]),
+        ];
+
+        let mut writer = ASMWriter::new();
+        get_code_generator(get_target_info("wasm32-unknown-unknown").unwrap())
+            .gen_function(&mut writer, &func);
+        let lines: Vec<String> = writer.lines.iter().map(|line| line.to_text()).collect();
+
+        // The parameters are named after their variables, the division can't trap on -1,
+        // and the result is left on the stack
+        for expected in [
+            "  (func $soup_div (param $a.0 i32) (param $b.1 i32) (result i32)",
+            "    (local $t2 i32)",
+            "    br_table $B0",
+            "    i32.const -1",
+            "    i32.div_s",
+            "    local.get $t2",
+            "    return",
+        ] {
+            assert!(
+                lines.contains(&String::from(expected)),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_llvm_ir() {
+        // func half(int a) returns int { if a { return a / 2; } return 0; }
+        let mut func = IrFunction::new("half");
+        func.temps = vec![IrType::Int; 3];
+        func.params = vec![0];
+        func.returns = vec![IrType::Int];
+        func.name_temp(0, "a");
+        func.body = vec![
+            Instr::Branch {
+                cond: 0,
+                if_true: 0,
+                if_false: 1,
+            },
+            Instr::Label(0),
+            Instr::Const { dest: 1, value: 2 },
+            Instr::BinOp {
+                op: BinOp::Div,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Return(vec![2]),
+            Instr::Label(1),
+            Instr::Const { dest: 2, value: 0 },
+            Instr::Return(vec![2]),
+        ];
+        let mut program = IrProgram::new();
+        program.functions.push(func);
+
+        let text = format_llvm_ir(&program);
+
+        // Each temporary lives in an alloca, each label starts a basic block, and division can't be undefined on -1
+        for expected in [
+            "define i32 @soup_half(i32 %arg0) {",
+            "  %a.0 = alloca i32",
+            "  store i32 %arg0, ptr %a.0",
+            "  br i1 %v2, label %L0, label %L1",
+            "L0:",
+            "  %v5 = icmp eq i32 %v4, -1",
+            "  %v8 = sdiv i32 %v3, %v6",
+            "  ret i32 %v10",
+            "L1:",
+        ] {
+            assert!(
+                text.lines().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_c() {
+        let file = env::temp_dir().join(format!("soup-c-{}.soup", std::process::id()));
+        fs::write(
+            &file,
+            "int total = 0;\n\
+             func add(int n) returns int { total = total + n; return total; }\n\
+             func main() returns int { int values[3]; values[1] = total + add(2); printf(\"{}\\n\", values[1] / 2); return 0; }\n",
+        )
+        .unwrap();
+        let tokens = scanner(&file.to_string_lossy());
+        let _ = fs::remove_file(&file);
+        let mut ast = parser(&tokens);
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        let text = format_c(&ast, false);
+
+        // Soup's main is renamed, and anything C could evaluate in a different order than soup is hoisted out first
+        for expected in [
+            "static int32_t total = 0;",
+            "static int32_t add(int32_t n);",
+            "static int32_t soup_main(void) {",
+            "    int32_t values[3] = {0};",
+            "    int32_t *soup_t1 = soup_int((soup_array){values, 3}, 1, 3);",
+            "    int32_t soup_t2 = total;",
+            "    *soup_t1 = soup_add(soup_t2, add(2));",
+            "    printf(\"%d\\n\", soup_div(*soup_int((soup_array){values, 3}, 1, 3), 2, 3));",
+            "    int32_t code = soup_main();",
+        ] {
+            assert!(
+                text.lines().any(|line| line == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_c_long_chain() {
+        let chain = vec!["x"; 250].join(" - ");
+        let tokens = scan_str(&format!(
+            "func main() returns void {{ int x = 1; printf(\"{{}}\\n\", {}); }}",
+            chain
+        ))
+        .unwrap();
+        let mut ast = parse_program(&tokens).unwrap();
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        let text = format_c(&ast, false);
+
+        // A chain is hoisted into a temporary every 100 operators, so C compilers don't have to nest it any deeper
+        let subtractions = |line: &str| line.matches("soup_sub(").count();
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| line.contains("soup_sub(soup_sub("))
+            .collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("    int32_t soup_t1 = soup_sub(soup_sub("));
+        assert_eq!(100, subtractions(lines[0]));
+        assert!(lines[1].starts_with("    int32_t soup_t2 = soup_sub(soup_sub("));
+        assert!(lines[1].contains("(soup_t1, x)"));
+        assert_eq!(100, subtractions(lines[1]));
+        assert!(lines[2].starts_with("    printf(\"%d\\n\", soup_sub(soup_sub("));
+        assert!(lines[2].contains("(soup_t2, x)"));
+        assert_eq!(49, subtractions(lines[2]));
+    }
+
+    // Compile a soup file all the way to assembly for the given target, at the given optimization level
+    #[test]
+    fn test_runtime_lib_calls() {
+        // Only the functions in the runtime library a program calls are written out along with it
+        let sleeps = "func main() returns void { sleep(10); }";
+        let prints = "func main() returns void { printf(\"zzz\\n\"); }";
+        for target in &TARGETS {
+            let sleeps = compile_str(sleeps, target, 0).unwrap();
+            let prints = compile_str(prints, target, 0).unwrap();
+            assert!(sleeps.contains("sleep"), "{}", target.triple);
+            assert!(!prints.contains("sleep"), "{}", target.triple);
+            assert!(!sleeps.contains("fopen"), "{}", target.triple);
+        }
+
+        let mut program = IrProgram::new();
+        let mut func = IrFunction::new("main");
+        func.temps = vec![IrType::Int];
+        func.body = vec![Instr::Const { dest: 0, value: 10 }, Instr::Return(vec![])];
+        program.functions.push(func);
+        assert!(!format_llvm_ir(&program).contains("sleep"));
+
+        program.functions[0].body.insert(
+            1,
+            Instr::Call {
+                dests: vec![],
+                func: String::from("sleep"),
+                args: vec![0],
+            },
+        );
+        let llvm_ir = format_llvm_ir(&program);
+        assert!(llvm_ir.contains("declare i32 @nanosleep(ptr, ptr)"));
+        assert!(!llvm_ir.contains("fopen"));
+    }
+
+    fn compile_file(file: &str, target: &str, opt_level: u32) -> String {
+        let tokens = scanner(file);
+        let mut ast = parser(&tokens);
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        let mut program = lower_program(&ast, false, false);
+        optimize(&mut program, opt_level);
+        return code_gen(
+            &program,
+            opt_level,
+            get_target_info(target).unwrap(),
+            false,
+            false,
+        );
+    }
+
+    // Compare the assembly generated for some representative programs with the copies checked in to
+    // test_files/snapshots, so that every change to the generated code shows up as a diff that can be reviewed.
+    // Run with SOUP_BLESS=1 to replace the checked in copies with whatever is generated now
+    #[test]
+    fn test_asm_snapshots() {
+        let bless = env::var("SOUP_BLESS").is_ok_and(|bless| bless == "1");
+        let mut mismatches = vec![];
+
+        for program in ["hello_world", "func_call3", "arrays2d", "nested_calls"] {
+            for (target, opt_level) in [
+                ("arm64-apple-darwin", 0),
+                ("arm64-apple-darwin", 2),
+                ("x86_64-unknown-linux-gnu", 2),
+            ] {
+                let asm = compile_file(&format!("test_files/{}.soup", program), target, opt_level);
+                let snapshot = format!(
+                    "test_files/snapshots/{}-{}-O{}.asm",
+                    program, target, opt_level
+                );
+
+                if bless {
+                    fs::write(&snapshot, &asm).unwrap();
+                } else if let Some(mismatch) =
+                    describe_mismatch(&fs::read_to_string(&snapshot).unwrap_or_default(), &asm)
+                {
+                    mismatches.push(format!("{}: {}", snapshot, mismatch));
+                }
+            }
+        }
+
+        assert!(
+            mismatches.is_empty(),
+            "The generated assembly doesn't match the snapshots (run with SOUP_BLESS=1 to update them):\n{}",
+            mismatches.join("\n")
+        );
+    }
+}
@@ -0,0 +1,22 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains data structures used by "soup doc", which documents the functions and globals of a program
+// -------------------------------------------------------------------------------------------------------------
+
+// The documentation of one of the files of a program, listing its functions and global variables
+// in the order they're declared in
+#[derive(Debug, PartialEq)]
+pub struct FileDocs {
+    pub file: String,
+    pub functions: Vec<ItemDocs>,
+    pub globals: Vec<ItemDocs>,
+}
+
+// The documentation of a single function or global variable
+#[derive(Debug, PartialEq)]
+pub struct ItemDocs {
+    pub name: String,
+    // How it's declared, like "func add(int x, int y) returns int" or "int total[3]"
+    pub signature: String,
+    // Its "///" documentation comment, without the slashes
+    pub doc: Option<String>,
+}
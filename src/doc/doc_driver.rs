@@ -0,0 +1,124 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the main logic of "soup doc", which writes out a summary of the functions and global variables
+// of a program, along with their "///" documentation comments, as Markdown or HTML
+// -------------------------------------------------------------------------------------------------------------
+
+use std::fs;
+
+use crate::cli::cli_data::DocFormat;
+use crate::doc::doc_data::{FileDocs, ItemDocs};
+use crate::doc::doc_printer::{format_html, format_markdown};
+use crate::formatter::formatter_printer::{format_signature, format_var_decl};
+use crate::parser::parser_data::{NodeRef, AST};
+use crate::parser::parser_driver::{merge_programs, parse_program_lossless};
+use crate::scanner::scanner_driver::scan_str_lossless;
+use crate::semantic::semantic_data::Warnings;
+use crate::semantic::semantic_driver::semantic_checker;
+use crate::{print_warnings, throw_error, write_output};
+
+// -----------------------------------------------------------------------------------------
+// DOCUMENTATION
+// -----------------------------------------------------------------------------------------
+
+// Document the program made up of the given soup files, writing the documentation to the output file (or stdout).
+// The files are parsed losslessly so their documentation comments are kept, and the program is checked like it
+// would be if it were being built (with the given warnings turned on), so only a program that compiles can be documented
+pub fn document_files(
+    files: &Vec<String>,
+    output_file: &str,
+    format: &DocFormat,
+    mut warnings: Warnings,
+) {
+    let mut programs = vec![];
+
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(_) => {
+                throw_error(&format!("Could not read file '{}'", file));
+                return;
+            }
+        };
+
+        let parsed = scan_str_lossless(&source)
+            .map_err(|error| error.to_string())
+            .and_then(|(tokens, trivia)| {
+                parse_program_lossless(&tokens, &trivia).map_err(|error| error.to_string())
+            });
+        match parsed {
+            Ok(program) => programs.push((file.clone(), program)),
+            Err(msg) => throw_error(&format!("Could not document '{}': {}", file, msg)),
+        }
+    }
+
+    // The declarations of each file are kept together, in order, in the merged program
+    let num_decls: Vec<(String, usize)> = programs
+        .iter()
+        .map(|(file, program)| (file.clone(), program.get_root().children.len()))
+        .collect();
+
+    let mut ast = match merge_programs(programs) {
+        Ok(ast) => ast,
+        Err(msg) => {
+            throw_error(&msg);
+            return;
+        }
+    };
+
+    let result = semantic_checker(&mut ast, &mut warnings);
+    print_warnings(&warnings.found);
+    if let Err(msg) = result {
+        throw_error(&msg);
+        return;
+    }
+
+    let docs = get_docs(&ast, &num_decls);
+    let text = match format {
+        DocFormat::Markdown => format_markdown(&docs),
+        DocFormat::Html => format_html(&docs),
+    };
+    write_output(output_file, &text);
+}
+
+// Collect the documentation of each file of a checked program, given the number of global declarations
+// each of its files contributed to it. The main function isn't documented, since nothing can call it
+pub fn get_docs(ast: &AST, num_decls: &Vec<(String, usize)>) -> Vec<FileDocs> {
+    let mut decls = ast.get_root().children();
+    let mut docs = vec![];
+
+    for (file, num_decls) in num_decls {
+        let mut file_docs = FileDocs {
+            file: file.clone(),
+            functions: vec![],
+            globals: vec![],
+        };
+
+        for decl in decls.by_ref().take(*num_decls) {
+            match decl.node_type.as_str() {
+                "funcDecl" => file_docs.functions.push(ItemDocs {
+                    name: decl.child(0).get_attr().to_string(),
+                    signature: format_signature(decl),
+                    doc: get_doc(decl),
+                }),
+                "globVarDecl" => file_docs.globals.push(ItemDocs {
+                    name: decl.child(1).get_attr().to_string(),
+                    signature: format_var_decl(decl).trim_end_matches(';').to_string(),
+                    doc: get_doc(decl),
+                }),
+                _ => {}
+            }
+        }
+
+        docs.push(file_docs);
+    }
+
+    return docs;
+}
+
+// Get the documentation comment the semantic checker attached to a declaration's symbol
+fn get_doc(decl: NodeRef) -> Option<String> {
+    return decl
+        .sym
+        .as_ref()
+        .and_then(|symbol| symbol.borrow().doc.clone());
+}
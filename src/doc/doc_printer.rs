@@ -0,0 +1,109 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the logic involved in writing out the documentation of a program, as Markdown or as HTML
+// -------------------------------------------------------------------------------------------------------------
+
+use crate::doc::doc_data::{FileDocs, ItemDocs};
+
+// -----------------------------------------------------------------
+// MARKDOWN
+// -----------------------------------------------------------------
+
+// Write out the documentation of a program as Markdown, with a section for each file.
+// Documentation comments are written as they are, so they can use Markdown themselves
+pub fn format_markdown(docs: &Vec<FileDocs>) -> String {
+    let mut sections = vec![];
+
+    for file_docs in docs {
+        let mut section = format!("# {}\n", file_docs.file);
+        section.push_str(&format_markdown_items("Functions", &file_docs.functions));
+        section.push_str(&format_markdown_items(
+            "Global variables",
+            &file_docs.globals,
+        ));
+        sections.push(section);
+    }
+
+    return sections.join("\n");
+}
+
+fn format_markdown_items(heading: &str, items: &Vec<ItemDocs>) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let mut text = format!("\n## {}\n", heading);
+    for item in items {
+        text.push_str(&format!(
+            "\n### `{}`\n\n```soup\n{}\n```\n",
+            item.name, item.signature
+        ));
+        if let Some(doc) = &item.doc {
+            text.push_str(&format!("\n{}\n", doc));
+        }
+    }
+
+    return text;
+}
+
+// -----------------------------------------------------------------
+// HTML
+// -----------------------------------------------------------------
+
+// Write out the documentation of a program as a standalone HTML page, with a section for each file.
+// Each paragraph of a documentation comment (separated by blank lines) is its own paragraph of the page
+pub fn format_html(docs: &Vec<FileDocs>) -> String {
+    let files: Vec<&str> = docs
+        .iter()
+        .map(|file_docs| file_docs.file.as_str())
+        .collect();
+
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>{}</title>\n</head>\n<body>\n",
+        escape_html(&files.join(", "))
+    ));
+
+    for file_docs in docs {
+        html.push_str(&format!("<h1>{}</h1>\n", escape_html(&file_docs.file)));
+        html.push_str(&format_html_items("Functions", &file_docs.functions));
+        html.push_str(&format_html_items("Global variables", &file_docs.globals));
+    }
+
+    html.push_str("</body>\n</html>\n");
+    return html;
+}
+
+fn format_html_items(heading: &str, items: &Vec<ItemDocs>) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let mut html = format!("<h2>{}</h2>\n", heading);
+    for item in items {
+        // Each item can be linked to by its name, which is unique within a program
+        html.push_str(&format!(
+            "<h3 id=\"{}\"><code>{}</code></h3>\n<pre><code>{}</code></pre>\n",
+            item.name,
+            item.name,
+            escape_html(&item.signature)
+        ));
+        if let Some(doc) = &item.doc {
+            for paragraph in doc.split("\n\n").map(|paragraph| paragraph.trim()) {
+                if !paragraph.is_empty() {
+                    html.push_str(&format!("<p>{}</p>\n", escape_html(paragraph)));
+                }
+            }
+        }
+    }
+
+    return html;
+}
+
+// Escape the characters that mean something in HTML, so text shows up as it is
+fn escape_html(text: &str) -> String {
+    return text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
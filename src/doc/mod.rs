@@ -0,0 +1,7 @@
+pub mod doc_data;
+pub mod doc_driver;
+pub mod doc_printer;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,78 @@
+mod tests {
+    use crate::doc::doc_data::ItemDocs;
+    use crate::doc::doc_driver::get_docs;
+    use crate::doc::doc_printer::{format_html, format_markdown};
+    use crate::parser::parser_driver::parse_program_lossless;
+    use crate::scanner::scanner_driver::scan_str_lossless;
+    use crate::semantic::semantic_data::Warnings;
+    use crate::semantic::semantic_driver::semantic_checker;
+
+    const PROGRAM: &str = "/// The running total.
+int total = 0;
+
+/// Not about flag
+
+bool flag;
+
+//// Not a doc comment
+/// Adds `n` to the total,
+/// giving back the new total.
+///
+/// Works with <negative> numbers & zero.
+func add(int n) returns int {
+    total = total + n;
+    return total;
+}
+
+func main() returns int {
+    /// Just a comment
+    int x = add(2);
+    return x;
+}
+";
+
+    #[test]
+    fn test_get_docs() {
+        let (tokens, trivia) = scan_str_lossless(PROGRAM).unwrap();
+        let mut ast = parse_program_lossless(&tokens, &trivia).unwrap();
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+        let docs = get_docs(&ast, &vec![(String::from("total.soup"), 4)]);
+
+        // Only the "///" comments just above a declaration are its documentation, and main isn't documented
+        assert_eq!(1, docs.len());
+        assert_eq!(
+            vec![ItemDocs {
+                name: String::from("add"),
+                signature: String::from("func add(int n) returns int"),
+                doc: Some(String::from(
+                    "Adds `n` to the total,\ngiving back the new total.\n\nWorks with <negative> numbers & zero."
+                )),
+            }],
+            docs[0].functions
+        );
+        assert_eq!(
+            vec![
+                ItemDocs {
+                    name: String::from("total"),
+                    signature: String::from("int total = 0"),
+                    doc: Some(String::from("The running total.")),
+                },
+                ItemDocs {
+                    name: String::from("flag"),
+                    signature: String::from("bool flag"),
+                    doc: None,
+                },
+            ],
+            docs[0].globals
+        );
+
+        let markdown = format_markdown(&docs);
+        assert!(markdown.starts_with("# total.soup\n\n## Functions\n\n### `add`\n\n```soup\nfunc add(int n) returns int\n```\n\nAdds `n` to the total,\n"));
+        assert!(markdown.ends_with("### `flag`\n\n```soup\nbool flag\n```\n"));
+
+        // Each paragraph is its own paragraph of the page, with anything that means something in HTML escaped
+        let html = format_html(&docs);
+        assert!(html.contains("<h3 id=\"add\"><code>add</code></h3>\n<pre><code>func add(int n) returns int</code></pre>\n<p>Adds `n` to the total,\ngiving back the new total.</p>\n<p>Works with &lt;negative&gt; numbers &amp; zero.</p>\n"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+}
@@ -0,0 +1,74 @@
+// -----------------------------------------------------------------------------------------------------------
+// This file contains the main logic of "soup fmt", which prints soup files back out in the canonical style
+// -----------------------------------------------------------------------------------------------------------
+
+use std::fs;
+use std::process;
+
+use crate::formatter::formatter_printer::format_program;
+use crate::parser::parser_driver::parse_program_lossless;
+use crate::scanner::scanner_driver::scan_str_lossless;
+use crate::{throw_error, write_output};
+
+// -----------------------------------------------------------------------------------------
+// FORMATTER
+// -----------------------------------------------------------------------------------------
+
+// Format the given soup files, rewriting each of them in place (or, if an output file is given,
+// writing the formatted version of the only file there instead). If check is true, nothing is written at all,
+// and instead the files that aren't already formatted are listed, exiting with an error if there are any.
+// Every file is formatted before any of them are written, so a file that can't be formatted leaves them all untouched
+pub fn format_files(files: &Vec<String>, output_file: &str, check: bool) {
+    let mut formatted_files = vec![];
+
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(_) => {
+                throw_error(&format!("Could not read file '{}'", file));
+                return;
+            }
+        };
+
+        match format_source(&source) {
+            Ok(formatted) => formatted_files.push((file, source, formatted)),
+            Err(msg) => throw_error(&format!("Could not format '{}': {}", file, msg)),
+        }
+    }
+
+    if check {
+        let unformatted: Vec<&String> = formatted_files
+            .iter()
+            .filter(|(_, source, formatted)| source != formatted)
+            .map(|(file, _, _)| *file)
+            .collect();
+
+        for file in &unformatted {
+            eprintln!("'{}' is not formatted", file);
+        }
+        if !unformatted.is_empty() {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if !output_file.is_empty() {
+        write_output(output_file, &formatted_files[0].2);
+        return;
+    }
+
+    for (file, source, formatted) in formatted_files {
+        // Files that are already formatted are left alone, so they aren't touched for no reason
+        if source != formatted && fs::write(file, formatted).is_err() {
+            throw_error(&format!("Could not write to file '{}'", file));
+        }
+    }
+}
+
+// Format a piece of soup source code, giving back the formatted version (with all of its comments kept),
+// or a message saying why it can't be formatted
+pub fn format_source(source: &str) -> Result<String, String> {
+    let (tokens, trivia) = scan_str_lossless(source).map_err(|error| error.to_string())?;
+    let ast = parse_program_lossless(&tokens, &trivia).map_err(|error| error.to_string())?;
+    return Ok(format_program(&ast));
+}
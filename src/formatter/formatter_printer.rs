@@ -0,0 +1,496 @@
+use crate::parser::parser_data::{Comment, NodeRef, NodeTrivia, AST, BINARY_OPERATORS};
+
+// The number of spaces each level of a block is indented by
+pub const INDENT_WIDTH: usize = 4;
+
+// How tightly each kind of expression binds, from assignments (the loosest) up to primaries (the tightest),
+// with the binary operators in between, which decides where parentheses are needed to keep an expression's shape
+const ASSIGNMENT_LEVEL: u32 = 0;
+const UNARY_LEVEL: u32 = 7;
+const PRIMARY_LEVEL: u32 = 8;
+
+// The trivia of a node that doesn't have any comments or blank lines attached to it
+static NO_TRIVIA: NodeTrivia = NodeTrivia {
+    leading_comments: Vec::new(),
+    blank_line_before: false,
+    trailing_comment: None,
+    inner_comments: Vec::new(),
+    closing_comments: Vec::new(),
+};
+
+// -----------------------------------------------------------------------------------------
+// PROGRAM PRINTER
+// -----------------------------------------------------------------------------------------
+
+// Print a parsed program back out as soup source code in the canonical style: four spaces of indentation,
+// opening braces on the same line as whatever they belong to, single spaces around binary operators and assignments,
+// and parentheses only where they're needed. Global variables declared one after another are kept together
+// (unless there was a blank line between them), and every other global declaration is separated from its neighbours
+// by a blank line. Any comments attached to the program (if it was parsed losslessly) are kept next to what they're about
+pub fn format_program(ast: &AST) -> String {
+    let mut text = String::from("");
+    let mut last_decl: Option<NodeRef> = None;
+
+    for decl in ast.get_root().children() {
+        let mut at_start = last_decl.is_none();
+        if let Some(last_decl) = last_decl {
+            if last_decl.node_type != "globVarDecl" || decl.node_type != "globVarDecl" {
+                text.push('\n');
+                at_start = true;
+            }
+        }
+
+        let decl_text = if decl.node_type == "globVarDecl" {
+            format_var_decl(decl)
+        } else {
+            format_function(decl)
+        };
+        text.push_str(&format_line(decl, &decl_text, 0, at_start));
+
+        last_decl = Some(decl);
+    }
+
+    text.push_str(&format_closing_comments(
+        ast.get_root(),
+        0,
+        last_decl.is_none(),
+    ));
+    return text;
+}
+
+// Print a function (or main function) declaration, which is of the form [id, parameters, returns, block]
+fn format_function(decl: NodeRef) -> String {
+    return format!(
+        "{} {}",
+        format_signature(decl),
+        format_block(decl.child(3), 0)
+    );
+}
+
+// Print the signature of a function (or main function) declaration, which is everything before its block
+pub fn format_signature(decl: NodeRef) -> String {
+    let params: Vec<String> = decl
+        .child(1)
+        .children()
+        .map(|param| {
+            format!(
+                "{} {}",
+                format_type(param.child(0)),
+                param.child(1).get_attr()
+            )
+        })
+        .collect();
+
+    // The return type is either void, or a type (which is left as an "int" node for main)
+    let return_type = decl.child(2).child(0);
+
+    return format!(
+        "func {}({}) returns {}",
+        decl.child(0).get_attr(),
+        params.join(", "),
+        return_type.node_type
+    );
+}
+
+// Print a variable declaration (global or local), which is of the form [type, id, (assignment)]
+pub fn format_var_decl(decl: NodeRef) -> String {
+    let mut text = format_declarator(decl);
+
+    if decl.children.len() == 3 {
+        text.push_str(&format!(
+            " = {}",
+            format_expr(decl.child(2), ASSIGNMENT_LEVEL)
+        ));
+    }
+
+    text.push(';');
+    return text;
+}
+
+// Print the type and name of a variable declaration, without whatever's assigned to it (e.g. "int grid[3][4]")
+pub fn format_declarator(decl: NodeRef) -> String {
+    let var_type = decl.child(0);
+    let mut text;
+
+    if var_type.children.is_empty() {
+        text = format!("{} {}", format_type(var_type), decl.child(1).get_attr());
+    } else {
+        // The sizes of an array are attached to its type, but are written after the identifier (e.g. "int grid[3][4]")
+        text = format!(
+            "{} {}",
+            var_type.node_type.trim_end_matches("[]"),
+            decl.child(1).get_attr()
+        );
+        for size in var_type.children() {
+            text.push_str(&format!("[{}]", size.get_attr()));
+        }
+    }
+
+    return text;
+}
+
+// Print a type, which is named after its kind (e.g. "int" or "bool[]")
+fn format_type(type_node: NodeRef) -> String {
+    return type_node.node_type.clone();
+}
+
+// -----------------------------------------------------------------------------------------
+// STATEMENT PRINTER
+// -----------------------------------------------------------------------------------------
+
+// Print a block, starting from its open brace and ending with its close brace,
+// with each of its statements on a line of its own one level further in than the given level
+fn format_block(block: NodeRef, level: usize) -> String {
+    let mut text = String::from("{\n");
+
+    for (i, stmt) in block.children().enumerate() {
+        text.push_str(&format_line(
+            stmt,
+            &format_stmt(stmt, level + 1),
+            level + 1,
+            i == 0,
+        ));
+    }
+
+    text.push_str(&format_closing_comments(block, level + 1, false));
+    text.push_str(&get_indent(level));
+    text.push('}');
+    return text;
+}
+
+// Print a statement at the given level of indentation (not including the indentation of its first line)
+fn format_stmt(stmt: NodeRef, level: usize) -> String {
+    match stmt.node_type.as_str() {
+        "block" => return format_block(stmt, level),
+        "voidStmt" => return String::from(";"),
+        "break" | "continue" => match &stmt.attr {
+            None => return format!("{};", stmt.node_type),
+            Some(label) => return format!("{} {};", stmt.node_type, label),
+        },
+        "varDecl" => return format_var_decl(stmt),
+        "return" => {
+            if stmt.children.is_empty() {
+                return String::from("return;");
+            }
+            return format!("return {};", format_expr(stmt.child(0), ASSIGNMENT_LEVEL));
+        }
+        "if" => {
+            return format!(
+                "if {}{}",
+                format_expr(stmt.child(0), ASSIGNMENT_LEVEL),
+                format_body(stmt.child(1), level)
+            );
+        }
+        "ifElse" => {
+            let mut text = format!(
+                "if {}{}",
+                format_expr(stmt.child(0), ASSIGNMENT_LEVEL),
+                format_body(stmt.child(1), level)
+            );
+
+            // The else either follows the close brace of the if's block, or goes on a line of its own
+            // (which it also does if there's a comment after the close brace)
+            let if_body = stmt.child(1);
+            if if_body.node_type == "block" && get_trivia(if_body).trailing_comment.is_none() {
+                text.push(' ');
+            } else {
+                text.push('\n');
+                text.push_str(&get_indent(level));
+            }
+            text.push_str("else");
+
+            // An if directly inside an else stays on the same line, making an "else if" chain
+            let else_stmt = stmt.child(2);
+            if else_stmt.node_type == "if" || else_stmt.node_type == "ifElse" {
+                text.push(' ');
+                text.push_str(&format_stmt(else_stmt, level));
+            } else {
+                text.push_str(&format_body(else_stmt, level));
+            }
+            return text;
+        }
+        "while" => {
+            return format!(
+                "{}while {}{}",
+                format_label(stmt),
+                format_expr(stmt.child(0), ASSIGNMENT_LEVEL),
+                format_body(stmt.child(1), level)
+            );
+        }
+        "loop" => {
+            return format!(
+                "{}loop{}",
+                format_label(stmt),
+                format_body(stmt.child(0), level)
+            );
+        }
+        "repeat" => {
+            let mut text = format!(
+                "{}repeat{}",
+                format_label(stmt),
+                format_body(stmt.child(0), level)
+            );
+
+            // Like an else, the until either follows the close brace of the body, or goes on a line of its own
+            let body = stmt.child(0);
+            if body.node_type == "block" && get_trivia(body).trailing_comment.is_none() {
+                text.push(' ');
+            } else {
+                text.push('\n');
+                text.push_str(&get_indent(level));
+            }
+            text.push_str(&format!(
+                "until {};",
+                format_expr(stmt.child(1), ASSIGNMENT_LEVEL)
+            ));
+            return text;
+        }
+
+        // Anything else is an assignment or a function call, used as a statement
+        _ => return format!("{};", format_expr(stmt, ASSIGNMENT_LEVEL)),
+    }
+}
+
+// Print the label of a loop (along with the colon after it), if it has one
+fn format_label(stmt: NodeRef) -> String {
+    return match &stmt.attr {
+        None => String::new(),
+        Some(label) => format!("{}: ", label),
+    };
+}
+
+// Print the body of an if, else or while, which goes on the same line if it's a block,
+// or otherwise on the next line, one level further in
+fn format_body(body: NodeRef, level: usize) -> String {
+    if body.node_type == "block" {
+        return format!(
+            " {}{}",
+            format_block(body, level),
+            format_trailing_comment(body)
+        );
+    }
+
+    return format!(
+        "\n{}{}{}{}",
+        format_leading_comments(body, level + 1, true),
+        get_indent(level + 1),
+        format_stmt(body, level + 1),
+        format_trailing_comment(body)
+    );
+}
+
+// -----------------------------------------------------------------------------------------
+// COMMENT PRINTER
+// -----------------------------------------------------------------------------------------
+
+// Print a statement or declaration (which has already been printed as text) on a line of its own at the given level,
+// along with the comments around it. at_start is true if it's the first thing in its block (or file),
+// where a blank line in front of it isn't kept
+fn format_line(stmt: NodeRef, text: &str, level: usize, at_start: bool) -> String {
+    return format!(
+        "{}{}{}{}\n",
+        format_leading_comments(stmt, level, at_start),
+        get_indent(level),
+        text,
+        format_trailing_comment(stmt)
+    );
+}
+
+// Print the comments in front of a statement, each on a line of its own at the given level, keeping
+// the blank lines between them (and in front of the statement) unless they're at the start of a block or file.
+// Comments that can't stay where they were, like ones in the middle of the statement, are moved to just above it
+fn format_leading_comments(stmt: NodeRef, level: usize, at_start: bool) -> String {
+    let trivia = get_trivia(stmt);
+    let mut text = format_comments(&trivia.leading_comments, level, at_start);
+
+    if trivia.blank_line_before && !(at_start && trivia.leading_comments.is_empty()) {
+        text.push('\n');
+    }
+
+    for comment in get_moved_comments(stmt) {
+        text.push_str(&format!(
+            "{}{}\n",
+            get_indent(level),
+            comment.text.trim_end()
+        ));
+    }
+
+    return text;
+}
+
+// Print the comments after the last statement of a block (or the last declaration of a program)
+fn format_closing_comments(node: NodeRef, level: usize, at_start: bool) -> String {
+    return format_comments(&get_trivia(node).closing_comments, level, at_start);
+}
+
+// Print the comment at the end of a statement's last line (along with the space in front of it), if it has one
+fn format_trailing_comment(stmt: NodeRef) -> String {
+    return match &get_trivia(stmt).trailing_comment {
+        Some(comment) => format!(" {}", comment.text.trim_end()),
+        None => String::new(),
+    };
+}
+
+// Print comments on lines of their own at the given level, keeping the blank lines between them
+// (and the one in front of the first, unless it's at the start of a block or file)
+fn format_comments(comments: &[Comment], level: usize, at_start: bool) -> String {
+    let mut text = String::new();
+
+    for (i, comment) in comments.iter().enumerate() {
+        if comment.blank_line_before && !(at_start && i == 0) {
+            text.push('\n');
+        }
+        text.push_str(&format!(
+            "{}{}\n",
+            get_indent(level),
+            comment.text.trim_end()
+        ));
+    }
+
+    return text;
+}
+
+// Get the comments attached to the parts of a statement that are printed on the same line as something else
+// (like the middle of it, or in front of the block of an if, or in front of the if of an "else if"),
+// which are moved to just above the statement so that they aren't lost
+fn get_moved_comments(stmt: NodeRef<'_>) -> Vec<&Comment> {
+    let mut comments: Vec<&Comment> = get_trivia(stmt).inner_comments.iter().collect();
+
+    // The statements of a block are each printed on a line of their own, so only the blocks of functions,
+    // ifs and whiles start on the same line as something else
+    if stmt.node_type != "block" {
+        for child in stmt.children() {
+            if child.node_type == "block" {
+                comments.extend(&get_trivia(child).leading_comments);
+            }
+        }
+    }
+
+    if stmt.node_type == "ifElse" {
+        let else_stmt = stmt.child(2);
+        if else_stmt.node_type == "if" || else_stmt.node_type == "ifElse" {
+            comments.extend(&get_trivia(else_stmt).leading_comments);
+            comments.extend(get_moved_comments(else_stmt));
+        }
+    }
+
+    return comments;
+}
+
+// Get the comments and blank lines attached to a node
+fn get_trivia(node: NodeRef<'_>) -> &NodeTrivia {
+    return node.ast[node.id].trivia.as_deref().unwrap_or(&NO_TRIVIA);
+}
+
+// Get the indentation of a line at the given level
+fn get_indent(level: usize) -> String {
+    return " ".repeat(level * INDENT_WIDTH);
+}
+
+// -----------------------------------------------------------------------------------------
+// EXPRESSION PRINTER
+// -----------------------------------------------------------------------------------------
+
+// Print an expression, putting it in parentheses if it binds more loosely than the given level
+// (which is the level of whatever it's a part of)
+fn format_expr(expr: NodeRef, min_level: u32) -> String {
+    let chain = expr.get_operator_chain();
+    if !chain.is_empty() {
+        return format_chain(&chain, min_level);
+    }
+
+    let level = get_level(expr);
+    let text = match expr.node_type.as_str() {
+        "id" | "number" | "true" | "false" => expr.get_attr().to_string(),
+        "string" => format!("\"{}\"", expr.get_attr()),
+        "u-" | "!" => {
+            // A minus directly in front of another is kept apart from it, so the two don't look like a decrement
+            let operand = format_expr(expr.child(0), UNARY_LEVEL);
+            if expr.node_type == "!" {
+                format!("!{}", operand)
+            } else if operand.starts_with('-') {
+                format!("-({})", operand)
+            } else {
+                format!("-{}", operand)
+            }
+        }
+        "cast" => format!(
+            "{}({})",
+            expr.get_attr(),
+            format_expr(expr.child(0), ASSIGNMENT_LEVEL)
+        ),
+        "index" => format!(
+            "{}[{}]",
+            format_expr(expr.child(0), PRIMARY_LEVEL),
+            format_expr(expr.child(1), ASSIGNMENT_LEVEL)
+        ),
+        "funcCall" => {
+            let args: Vec<String> = expr
+                .child(1)
+                .children()
+                .map(|arg| format_expr(arg.child(0), ASSIGNMENT_LEVEL))
+                .collect();
+            format!("{}({})", expr.child(0).get_attr(), args.join(", "))
+        }
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" => format!(
+            "{} {} {}",
+            format_expr(expr.child(0), PRIMARY_LEVEL),
+            expr.node_type,
+            format_expr(expr.child(1), ASSIGNMENT_LEVEL)
+        ),
+        _ => String::new(), // Should never happen, indicates an error on my end
+    };
+
+    if level < min_level {
+        return format!("({})", text);
+    }
+    return text;
+}
+
+// Print a chain of binary operators (see get_operator_chain) in a loop, from its leftmost operand up, putting it
+// in parentheses if it binds more loosely than the given level. Every binary operator is left associative,
+// so an operand on the right hand side needs parentheses if it has the same precedence (a - (b - c)),
+// but not on the left ((a - b) - c)
+fn format_chain(chain: &[NodeRef], min_level: u32) -> String {
+    let last = chain[chain.len() - 1];
+    let mut text = format_expr(last.child(0), get_level(last));
+
+    for (i, operator) in chain.iter().enumerate().rev() {
+        let level = get_level(*operator);
+        text.push_str(&format!(
+            " {} {}",
+            operator.node_type,
+            format_expr(operator.child(1), level + 1)
+        ));
+
+        // What the operator is the left hand side of (or part of, for the first one) is a level of its own
+        let outer_level = if i == 0 {
+            min_level
+        } else {
+            get_level(chain[i - 1])
+        };
+        if level < outer_level {
+            text = format!("({})", text);
+        }
+    }
+
+    return text;
+}
+
+// Get how tightly an expression binds, where each binary operator is a level above the assignments
+// for each level of precedence it has
+fn get_level(expr: NodeRef) -> u32 {
+    match expr.node_type.as_str() {
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" => return ASSIGNMENT_LEVEL,
+        "u-" | "!" => return UNARY_LEVEL,
+        node_type => {
+            return match BINARY_OPERATORS
+                .iter()
+                .find(|(_, _, operator)| *operator == node_type)
+            {
+                Some((_, precedence, _)) => precedence + 1,
+                None => PRIMARY_LEVEL,
+            };
+        }
+    }
+}
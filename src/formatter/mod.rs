@@ -0,0 +1,6 @@
+pub mod formatter_driver;
+pub mod formatter_printer;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
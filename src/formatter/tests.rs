@@ -0,0 +1,183 @@
+mod tests {
+    use crate::formatter::formatter_driver::format_source;
+
+    // Format the body of a main function, given as a single line
+    fn format_main(body: &str) -> String {
+        let formatted = format_source(&format!("func main() returns void {{ {} }}", body)).unwrap();
+        return formatted
+            .strip_prefix("func main() returns void {\n")
+            .and_then(|body| body.strip_suffix("}\n"))
+            .unwrap()
+            .to_string();
+    }
+
+    #[test]
+    fn test_format_program() {
+        let source = "int  x=1;bool flags [2];
+func main() returns int {
+  int i=0;while(i<10){if(i%2==0){x+=1;}else
+  printf(\"odd {}\\n\",i);
+  i +=1;}
+    return x;}
+func add(int a,int[] b)returns int{return a+b[0];}
+func nothing() returns void { ; }
+";
+        let expected = "int x = 1;
+bool flags[2];
+
+func main() returns int {
+    int i = 0;
+    while i < 10 {
+        if i % 2 == 0 {
+            x += 1;
+        } else
+            printf(\"odd {}\\n\", i);
+        i += 1;
+    }
+    return x;
+}
+
+func add(int a, int[] b) returns int {
+    return a + b[0];
+}
+
+func nothing() returns void {
+    ;
+}
+";
+        assert_eq!(expected, format_source(source).unwrap());
+
+        // Formatting something that's already formatted leaves it as it is
+        assert_eq!(expected, format_source(expected).unwrap());
+
+        // An empty file stays empty
+        assert_eq!("", format_source("").unwrap());
+    }
+
+    #[test]
+    fn test_format_statements() {
+        assert_eq!(
+            "    if x {\n        x = 1;\n    } else if y {\n        x = 2;\n    } else {\n        x = 3;\n    }\n",
+            format_main("if x { x = 1; } else if y { x = 2; } else { x = 3; }")
+        );
+        assert_eq!(
+            "    if x\n        x = 1;\n    else\n        x = 2;\n",
+            format_main("if x x = 1; else x = 2;")
+        );
+        assert_eq!(
+            "    while true\n        while false {\n            break;\n        }\n",
+            format_main("while true while false { break; }")
+        );
+        assert_eq!(
+            "    loop {\n        break;\n    }\n",
+            format_main("loop{break;}")
+        );
+        assert_eq!(
+            "    repeat {\n        x += 1;\n    } until x == 3;\n    repeat\n        x += 1;\n    until x == 4;\n",
+            format_main("repeat { x += 1; } until (x == 3); repeat x += 1; until ((x == 4));")
+        );
+        assert_eq!(
+            "    outer: while true {\n        inner: loop\n            break outer;\n        continue;\n    }\n",
+            format_main("outer : while true { inner:loop break outer; continue; }")
+        );
+        assert_eq!(
+            "    {\n        int grid[3][4];\n        return;\n    }\n",
+            format_main("{ int grid [3] [4]; return; }")
+        );
+    }
+
+    #[test]
+    fn test_format_parentheses() {
+        let expr = |source: &str| {
+            let formatted = format_main(&format!("x = {};", source));
+            return formatted
+                .trim()
+                .strip_prefix("x = ")
+                .and_then(|expr| expr.strip_suffix(';'))
+                .unwrap()
+                .to_string();
+        };
+
+        // Parentheses that don't change anything are dropped
+        assert_eq!("a + b * c", expr("(a) + (b * c)"));
+        assert_eq!("a - b - c", expr("(a - b) - c"));
+        assert_eq!("-a[1]", expr("-(a[1])"));
+        assert_eq!("x = y = 1", expr("(x = (y = 1))"));
+        assert_eq!("-int(a + b)", expr("-(int((a + b)))"));
+
+        // but the ones that do are kept
+        assert_eq!("(a + b) * c", expr("(a + b) * c"));
+        assert_eq!("a - (b - c)", expr("a - (b - c)"));
+        assert_eq!("!(a && b) || c", expr("!(a && b) || c"));
+        assert_eq!("(a || b) && c", expr("(a || b) && c"));
+        assert_eq!("(x = 1) + 2", expr("(x = 1) + 2"));
+        assert_eq!("(-a)[0]", expr("(-a)[0]"));
+        assert_eq!("(a + b)[0]", expr("(a + b)[0]"));
+        assert_eq!("-(-a)", expr("- -a"));
+        assert_eq!("f(a, (b + c) * d)", expr("f (a,(b+c)*d)"));
+        assert_eq!("\"soup\"[0]", expr("\"soup\"[0]"));
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let source = "// The counter
+int x;   // starts at zero
+
+
+int y;
+func main() returns void {
+// set it
+  x = 1; // once
+
+  if x == 1 { x = 2; } // twice
+  else
+    x = 3;
+  while x < 10 // until ten
+  { x += 1;
+    // done
+  }
+}
+// the end
+";
+        let expected = "// The counter
+int x; // starts at zero
+
+int y;
+
+func main() returns void {
+    // set it
+    x = 1; // once
+
+    if x == 1 {
+        x = 2;
+    } // twice
+    else
+        x = 3;
+    // until ten
+    while x < 10 {
+        x += 1;
+        // done
+    }
+}
+// the end
+";
+        assert_eq!(expected, format_source(source).unwrap());
+        assert_eq!(expected, format_source(expected).unwrap());
+
+        // "//" inside a string literal isn't a comment
+        assert_eq!(
+            "func main() returns void {\n    printf(\"a // b\");\n}\n",
+            format_source("func main() returns void { printf(\"a // b\"); }").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format_source_errors() {
+        assert_eq!(
+            Err(String::from(
+                "Syntax Error on line 1: variable declaration must end with a semicolon \";\""
+            )),
+            format_source("int x")
+        );
+    }
+}
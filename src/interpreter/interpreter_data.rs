@@ -0,0 +1,155 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains data structures used by the interpreter, which runs a checked program straight from its AST
+// -------------------------------------------------------------------------------------------------------------
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::semantic::semantic_data::{Symbol, Type};
+
+// A value a soup expression can have while the program is running
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    // An int, or a bool (which is 0 for false and 1 for true, like it is in the generated assembly)
+    Int(i32),
+    // The bytes of a string, with every escape sequence already replaced
+    String(Rc<Vec<u8>>),
+    Array(ArrayRef),
+    // What a void function call gives back
+    Void,
+}
+
+impl Value {
+    // Get the value a variable of the given type starts off with, before anything is assigned to it
+    // (an array declared with a size has its elements zeroed too, given its sizes)
+    pub fn default_value(type_sig: &Type, dims: &Vec<i32>) -> Value {
+        return match type_sig {
+            Type::String => Value::String(Rc::new(vec![])),
+            Type::Array(_) => Value::Array(ArrayRef::new(dims)),
+            _ => Value::Int(0),
+        };
+    }
+
+    // Get the int (or bool) this value holds, where anything else should never happen,
+    // since the semantic checker makes sure every value is used as its type
+    pub fn as_int(&self) -> i32 {
+        return match self {
+            Value::Int(value) => *value,
+            _ => 0, // Should never happen, indicates an error on my end
+        };
+    }
+}
+
+// A reference to an array, or to one of the rows of a multi-dimensional array, which shares its elements with
+// every other reference to the same array (arrays are passed to and returned from functions by reference)
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArrayRef {
+    // Every element of the whole array, one row after another
+    pub elements: Rc<RefCell<Vec<i32>>>,
+    // The position of the first element of this row in the whole array
+    pub start: usize,
+    // The sizes of each dimension of this row, the first of which is its length
+    pub dims: Vec<i32>,
+}
+
+impl ArrayRef {
+    // Create a new array with the given sizes, with every element zeroed
+    pub fn new(dims: &Vec<i32>) -> ArrayRef {
+        let num_elements = dims.iter().product::<i32>().max(0) as usize;
+        return ArrayRef {
+            elements: Rc::new(RefCell::new(vec![0; num_elements])),
+            start: 0,
+            dims: dims.clone(),
+        };
+    }
+
+    pub fn get_len(&self) -> i32 {
+        return self.dims.first().copied().unwrap_or(0);
+    }
+
+    // Get the number of elements taken up by each element of this row (e.g. 20 for "grid", given "int grid[10][20]")
+    pub fn get_element_size(&self) -> usize {
+        return self.dims.iter().skip(1).product::<i32>() as usize;
+    }
+
+    // Get the elements of this row, in order, where an element of a multi-dimensional row is a row itself
+    pub fn get_elements(&self) -> Vec<Value> {
+        let mut elements = vec![];
+        for i in 0..self.get_len() as usize {
+            elements.push(self.get_element(i));
+        }
+        return elements;
+    }
+
+    // Get the element at a position in this row (which has already been bounds checked)
+    pub fn get_element(&self, index: usize) -> Value {
+        if self.dims.len() > 1 {
+            return Value::Array(ArrayRef {
+                elements: self.elements.clone(),
+                start: self.start + index * self.get_element_size(),
+                dims: self.dims[1..].to_vec(),
+            });
+        }
+
+        return Value::Int(self.elements.borrow()[self.start + index]);
+    }
+
+    pub fn set_element(&self, index: usize, value: i32) {
+        self.elements.borrow_mut()[self.start + index] = value;
+    }
+}
+
+// The reason a program stopped running before it was finished
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stop {
+    // The program called exit, with the given exit code
+    Exit(i32),
+    // The program hit a runtime error (like dividing by zero), with the message it would have printed,
+    // like "Error: Line 3: Division by zero\n"
+    Error(String),
+}
+
+// How a statement finished running, which tells whoever ran it where to carry on from
+#[derive(Clone, PartialEq, Debug)]
+pub enum Flow {
+    Normal,
+    // A break or continue statement, with the label of the loop it's meant for (if it isn't the innermost one)
+    Break(Option<Arc<str>>),
+    Continue(Option<Arc<str>>),
+    // A return statement, with the value it returned (if it returned one)
+    Return(Option<Value>),
+}
+
+// The parameters and local variables of a function call, keyed by the symbol each of them was declared with
+// (so a variable in an inner block that hides one from an outer block is kept separate from it)
+pub struct Frame {
+    locals: HashMap<*const RefCell<Symbol>, Value>,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Frame {
+    pub fn new() -> Frame {
+        return Frame {
+            locals: HashMap::new(),
+        };
+    }
+
+    pub fn get(&self, symbol: &Rc<RefCell<Symbol>>) -> Option<&Value> {
+        return self.locals.get(&Rc::as_ptr(symbol));
+    }
+
+    pub fn get_mut(&mut self, symbol: &Rc<RefCell<Symbol>>) -> Option<&mut Value> {
+        return self.locals.get_mut(&Rc::as_ptr(symbol));
+    }
+
+    pub fn insert(&mut self, symbol: &Rc<RefCell<Symbol>>, value: Value) {
+        self.locals.insert(Rc::as_ptr(symbol), value);
+    }
+}
@@ -0,0 +1,685 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the interpreter, which runs a checked program by walking its AST, behaving just like the program
+// would if it were compiled (ints wrap around, and runtime errors give the same messages as the generated code)
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::panic;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::interpreter::interpreter_data::{Flow, Frame, Stop, Value};
+use crate::ir::ir_constants::{evaluate_binop, evaluate_unop, get_global_value};
+use crate::ir::ir_data::UnOp;
+use crate::ir::ir_utils::{
+    convert_string_literal, get_binop, get_format_arg, get_literal_value, parse_int_chars,
+    split_format_string, TO_STRING_TOO_SHORT,
+};
+use crate::parser::parser_data::{NodeId, NodeRef, AST};
+use crate::semantic::semantic_data::Type;
+use crate::semantic::semantic_utils::is_unary;
+use crate::throw_error;
+
+// The most function calls that can be in progress at once, past which the program is stopped with an error
+// (rather than overflowing the interpreter's own stack, which is what running out of stack does to a compiled program)
+pub const MAX_CALL_DEPTH: usize = 10000;
+
+// The size of the stack the interpreter should be run on, which is enough for MAX_CALL_DEPTH nested calls
+// of functions with deeply nested statements and expressions
+pub const INTERPRETER_STACK_SIZE: usize = 512 * 1024 * 1024;
+
+pub struct Interpreter<'a> {
+    functions: HashMap<String, NodeRef<'a>>,
+    // The value of each global variable, by name
+    pub globals: HashMap<String, Value>,
+    // The bytes of each string literal and the pieces of each printf format string, worked out the first time they're used
+    strings: HashMap<NodeId, Rc<Vec<u8>>>,
+    formats: HashMap<NodeId, Rc<Vec<Vec<u8>>>>,
+    output: &'a mut dyn Write,
+    // The files the program has open, numbered like the file descriptors a compiled program is given for them
+    files: HashMap<i32, File>,
+    overflow_checks: bool,
+    call_depth: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    // Create an interpreter for a checked program, which prints to the given output.
+    // Any global variable that doesn't already have a value starts off with the value it's declared with
+    pub fn new(
+        ast: &'a AST,
+        mut globals: HashMap<String, Value>,
+        output: &'a mut dyn Write,
+        overflow_checks: bool,
+    ) -> Interpreter<'a> {
+        let mut functions = HashMap::new();
+
+        for decl in ast.get_root().children() {
+            if decl.node_type == "funcDecl" || decl.node_type == "mainFuncDecl" {
+                functions.insert(decl.get_func_name(), decl);
+            } else if decl.node_type == "globVarDecl" {
+                let sym = decl.get_sym();
+                let sym = sym.borrow();
+
+                globals.entry(sym.name.to_string()).or_insert_with(|| {
+                    if decl.has_assignment() {
+                        Value::Int(get_global_value(decl))
+                    } else {
+                        Value::default_value(&sym.type_sig, &sym.array_dims)
+                    }
+                });
+            }
+        }
+
+        return Interpreter {
+            functions: functions,
+            globals: globals,
+            strings: HashMap::new(),
+            formats: HashMap::new(),
+            output: output,
+            files: HashMap::new(),
+            overflow_checks: overflow_checks,
+            call_depth: 0,
+        };
+    }
+
+    // Run the program's main function, giving back its exit code.
+    // Just like a compiled program, a newline is printed once main returns
+    pub fn run_main(&mut self) -> Result<i32, Stop> {
+        let code = self.call_function("main", vec![])?;
+        self.write(b"\n");
+
+        return match code {
+            Value::Int(code) => Ok(code),
+            _ => Ok(0),
+        };
+    }
+
+    // Call one of the program's functions with the given arguments, giving back what it returns
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Stop> {
+        let decl = match self.functions.get(name) {
+            Some(decl) => *decl,
+            None => return Ok(Value::Void), // Should never happen, indicates an error on my end
+        };
+
+        let mut frame = Frame::new();
+        for (param, value) in decl.child(1).children().zip(args) {
+            frame.insert(&param.get_sym(), value);
+        }
+
+        self.call_depth += 1;
+        let flow = self.execute_statement(decl.child(3), &mut frame);
+        self.call_depth -= 1;
+
+        if let Flow::Return(Some(value)) = flow? {
+            return Ok(value);
+        }
+
+        if decl.get_sym().borrow().get_returns() == Type::Void {
+            return Ok(Value::Void);
+        }
+
+        // If we make it to the end of a non-void function, there's no value to return
+        return Err(Stop::Error(format!("Error: Line {}: A control path reaches the end of a non-void function without returning a value\n", decl.get_line_num())));
+    }
+
+    // -----------------------------------------------------------------------------------------
+    // STATEMENTS
+    // -----------------------------------------------------------------------------------------
+
+    // Run a statement, giving back whether it finished normally, broke out of (or continued) a loop
+    // or returned from its function
+    pub fn execute_statement(
+        &mut self,
+        node: NodeRef<'a>,
+        frame: &mut Frame,
+    ) -> Result<Flow, Stop> {
+        match node.node_type.as_str() {
+            "block" => {
+                for child in node.children() {
+                    let flow = self.execute_statement(child, frame)?;
+                    if flow != Flow::Normal {
+                        return Ok(flow);
+                    }
+                }
+            }
+            "varDecl" => {
+                let sym = node.get_sym();
+                let value = if node.has_assignment() {
+                    self.evaluate(node.child(2), frame)?
+                } else {
+                    let sym = sym.borrow();
+                    Value::default_value(&sym.type_sig, &sym.array_dims)
+                };
+                frame.insert(&sym, value);
+            }
+            "=" | "+=" | "-=" | "*=" | "/=" | "%=" => self.execute_assignment(node, frame)?,
+            "funcCall" => {
+                self.call(node, frame)?;
+            }
+            "return" => {
+                if node.children.is_empty() {
+                    return Ok(Flow::Return(None));
+                }
+                return Ok(Flow::Return(Some(self.evaluate(node.child(0), frame)?)));
+            }
+            "if" | "ifElse" => {
+                if self.evaluate(node.child(0), frame)?.as_int() != 0 {
+                    return self.execute_statement(node.child(1), frame);
+                } else if node.node_type == "ifElse" {
+                    return self.execute_statement(node.child(2), frame);
+                }
+            }
+            "while" => {
+                // Test the condition before every iteration, including the first
+                while self.evaluate(node.child(0), frame)?.as_int() != 0 {
+                    if let Some(flow) = self.execute_loop_body(node, node.child(1), frame)? {
+                        return Ok(flow);
+                    }
+                }
+            }
+            "loop" => loop {
+                if let Some(flow) = self.execute_loop_body(node, node.child(0), frame)? {
+                    return Ok(flow);
+                }
+            },
+            "repeat" => loop {
+                // Test the condition after every iteration, so the body always runs at least once
+                if let Some(flow) = self.execute_loop_body(node, node.child(0), frame)? {
+                    return Ok(flow);
+                }
+                if self.evaluate(node.child(1), frame)?.as_int() != 0 {
+                    break;
+                }
+            },
+            "break" => return Ok(Flow::Break(node.attr.clone())),
+            "continue" => return Ok(Flow::Continue(node.attr.clone())),
+            // Otherwise, we have an empty statement
+            _ => {}
+        }
+
+        return Ok(Flow::Normal);
+    }
+
+    // Run the body of a loop once, giving back nothing if the loop carries on to its next iteration, or else how the
+    // loop itself finished: normally if it was broken out of, or with a return, or a break or continue meant for a loop
+    // around it
+    fn execute_loop_body(
+        &mut self,
+        node: NodeRef<'a>,
+        body: NodeRef<'a>,
+        frame: &mut Frame,
+    ) -> Result<Option<Flow>, Stop> {
+        // A break or continue is meant for this loop if it's the innermost one, or the one it names
+        let is_target = |label: &Option<Arc<str>>| label.is_none() || *label == node.attr;
+
+        return match self.execute_statement(body, frame)? {
+            Flow::Normal => Ok(None),
+            Flow::Break(label) if is_target(&label) => Ok(Some(Flow::Normal)),
+            Flow::Continue(label) if is_target(&label) => Ok(None),
+            flow => Ok(Some(flow)),
+        };
+    }
+
+    fn execute_assignment(&mut self, node: NodeRef<'a>, frame: &mut Frame) -> Result<(), Stop> {
+        let lhs = node.child(0);
+
+        // An array element is found (and bounds checked) before anything else,
+        // and for a compound assignment like "x += 1", the current value is read before evaluating the right hand side
+        if lhs.node_type == "index" {
+            let (array, index) = self.evaluate_index(lhs, frame)?;
+            let current = match node.node_type == "=" {
+                true => None,
+                false => Some(get_element(&array, index).as_int()),
+            };
+
+            let mut value = self.evaluate(node.child(1), frame)?.as_int();
+            if let Some(current) = current {
+                value = self.apply_binop(node, current, value)?;
+            }
+
+            // Arrays are the only thing that can be indexed and assigned to, which the semantic checker makes sure of
+            if let Value::Array(array) = array {
+                array.set_element(index, value);
+            }
+            return Ok(());
+        }
+
+        let current = match node.node_type == "=" {
+            true => None,
+            false => Some(self.get_var(lhs, frame).as_int()),
+        };
+
+        let mut value = self.evaluate(node.child(1), frame)?;
+        if let Some(current) = current {
+            value = Value::Int(self.apply_binop(node, current, value.as_int())?);
+        }
+
+        self.set_var(lhs, frame, value);
+        return Ok(());
+    }
+
+    // -----------------------------------------------------------------------------------------
+    // EXPRESSIONS
+    // -----------------------------------------------------------------------------------------
+
+    // Work out the value of an expression
+    pub fn evaluate(&mut self, node: NodeRef<'a>, frame: &mut Frame) -> Result<Value, Stop> {
+        // A chain of binary operators is worked out in a loop (see get_operator_chain), from its leftmost operand up
+        let chain = node.get_operator_chain();
+        if let Some(last) = chain.last() {
+            let mut value = self.evaluate(last.child(0), frame)?.as_int();
+            for operator in chain.iter().rev() {
+                if operator.node_type == "&&" || operator.node_type == "||" {
+                    // The right hand side is only evaluated if the left hand side doesn't decide the result
+                    if (operator.node_type == "&&") != (value == 0) {
+                        value = self.evaluate(operator.child(1), frame)?.as_int();
+                    }
+                } else {
+                    let rhs = self.evaluate(operator.child(1), frame)?.as_int();
+                    value = self.apply_binop(*operator, value, rhs)?;
+                }
+            }
+            return Ok(Value::Int(value));
+        } else if node.node_type == "cast" {
+            // A bool is already 0 or 1, and an int is true if it isn't zero
+            let value = self.evaluate(node.child(0), frame)?.as_int();
+            return match &*node.get_attr() {
+                "bool" => Ok(Value::Int((value != 0) as i32)),
+                _ => Ok(Value::Int(value)),
+            };
+        } else if is_unary(node) {
+            let op = if node.node_type == "!" {
+                UnOp::Not
+            } else {
+                UnOp::Neg
+            };
+            let value = self.evaluate(node.child(0), frame)?.as_int();
+
+            // Negating the smallest int overflows, which a number (being no bigger than the biggest int) never is
+            let overflow_checks = self.overflow_checks && node.child(0).node_type != "number";
+            return match evaluate_unop(op, value, overflow_checks) {
+                Ok(value) => Ok(Value::Int(value)),
+                Err(error) => Err(runtime_error(node, error.get_message())),
+            };
+        } else if node.node_type == "id" {
+            return Ok(self.get_var(node, frame));
+        } else if node.node_type == "index" {
+            let (sequence, index) = self.evaluate_index(node, frame)?;
+            return Ok(get_element(&sequence, index));
+        } else if node.node_type == "funcCall" {
+            return self.call(node, frame);
+        } else if node.node_type == "string" {
+            return Ok(Value::String(self.get_string(node)));
+        }
+
+        // Otherwise, we have a literal (a number, true or false)
+        return Ok(Value::Int(get_literal_value(node)));
+    }
+
+    // Combine two values with the binary (or compound assignment) operator of the given node
+    // (where dividing by zero, and overflowing if we're checking for it, is a runtime error)
+    fn apply_binop(&self, node: NodeRef, lhs: i32, rhs: i32) -> Result<i32, Stop> {
+        let op = get_binop(&node.node_type);
+
+        return match evaluate_binop(op, lhs, rhs, self.overflow_checks) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(runtime_error(node, error.get_message())),
+        };
+    }
+
+    // Work out the array (or string) an index node indexes into and the position it indexes,
+    // after checking the position is in bounds
+    fn evaluate_index(
+        &mut self,
+        node: NodeRef<'a>,
+        frame: &mut Frame,
+    ) -> Result<(Value, usize), Stop> {
+        let sequence = self.evaluate(node.child(0), frame)?;
+        let index = self.evaluate(node.child(1), frame)?.as_int();
+
+        let (len, indexed) = match &sequence {
+            Value::String(bytes) => (bytes.len() as i32, "String"),
+            Value::Array(array) => (array.get_len(), "Array"),
+            _ => (0, "Array"), // Should never happen, indicates an error on my end
+        };
+
+        // A negative index is out of bounds too, since it's compared as an unsigned int
+        if index as u32 >= len as u32 {
+            return Err(runtime_error(
+                node,
+                &format!("{} index out of bounds", indexed),
+            ));
+        }
+
+        return Ok((sequence, index as usize));
+    }
+
+    // Get the value of a variable, which is either one of the current function's parameters or local variables,
+    // or a global variable
+    fn get_var(&self, node: NodeRef, frame: &Frame) -> Value {
+        let sym = node.get_sym();
+        if let Some(value) = frame.get(&sym) {
+            return value.clone();
+        }
+
+        return match self.globals.get(&*sym.borrow().name) {
+            Some(value) => value.clone(),
+            None => Value::Int(0), // Should never happen, indicates an error on my end
+        };
+    }
+
+    fn set_var(&mut self, node: NodeRef, frame: &mut Frame, value: Value) {
+        let sym = node.get_sym();
+        if let Some(local) = frame.get_mut(&sym) {
+            *local = value;
+            return;
+        }
+
+        self.globals.insert(sym.borrow().name.to_string(), value);
+    }
+
+    // Get the bytes of a string literal
+    fn get_string(&mut self, node: NodeRef) -> Rc<Vec<u8>> {
+        return self
+            .strings
+            .entry(node.id)
+            .or_insert_with(|| Rc::new(convert_string_literal(node).unwrap_or_default()))
+            .clone();
+    }
+
+    // -----------------------------------------------------------------------------------------
+    // FUNCTION CALLS
+    // -----------------------------------------------------------------------------------------
+
+    // Run a function call (to one of the program's functions, or to the runtime library), giving back what it returns
+    fn call(&mut self, node: NodeRef<'a>, frame: &mut Frame) -> Result<Value, Stop> {
+        let func = node.get_func_name();
+        let args: Vec<NodeRef> = node.child(1).children().collect();
+
+        if func == "printf" || func == "fwrite_line" {
+            return self.call_printf(node, &args, frame);
+        }
+
+        if func == "len" {
+            return match self.evaluate(args[0].child(0), frame)? {
+                Value::String(bytes) => Ok(Value::Int(bytes.len() as i32)),
+                Value::Array(array) => Ok(Value::Int(array.get_len())),
+                _ => Ok(Value::Int(0)), // Should never happen, indicates an error on my end
+            };
+        }
+
+        if func == "parse_int" {
+            let chars: Vec<i32> = match self.evaluate(args[0].child(0), frame)? {
+                Value::String(bytes) => bytes.iter().map(|byte| *byte as i32).collect(),
+                Value::Array(array) => array
+                    .get_elements()
+                    .iter()
+                    .map(|char| char.as_int())
+                    .collect(),
+                _ => vec![], // Should never happen, indicates an error on my end
+            };
+            return match parse_int_chars(&chars) {
+                Ok(value) => Ok(Value::Int(value)),
+                Err(message) => Err(runtime_error(node, message)),
+            };
+        }
+
+        let mut values = vec![];
+        for arg in &args {
+            values.push(self.evaluate(arg.child(0), frame)?);
+        }
+
+        if func == "sleep" {
+            // Everything printed so far is shown before the program stops for a while, and sleeping for a negative
+            // number of milliseconds doesn't sleep at all
+            let _ = self.output.flush();
+            thread::sleep(Duration::from_millis(values[0].as_int().max(0) as u64));
+            return Ok(Value::Void);
+        }
+
+        if func == "to_string" {
+            let (chars, Value::Array(array)) = (values[0].as_int().to_string(), &values[1]) else {
+                return Ok(Value::Int(0)); // Should never happen, indicates an error on my end
+            };
+            if chars.len() > array.get_len().max(0) as usize {
+                return Err(runtime_error(node, TO_STRING_TOO_SHORT));
+            }
+
+            // The number is followed by a 0, if there's room for one
+            for (i, char) in chars.bytes().enumerate() {
+                array.set_element(i, char as i32);
+            }
+            if chars.len() < array.get_len() as usize {
+                array.set_element(chars.len(), 0);
+            }
+            return Ok(Value::Int(chars.len() as i32));
+        }
+
+        if func == "fopen" {
+            return Ok(Value::Int(self.open_file(&values[0], &values[1])));
+        }
+
+        if func == "fread_line" {
+            return Ok(Value::Int(self.read_line(values[0].as_int(), &values[1])));
+        }
+
+        if func == "fclose" {
+            // Closing a file that isn't open does nothing
+            self.files.remove(&values[0].as_int());
+            return Ok(Value::Void);
+        }
+
+        if !self.functions.contains_key(&func) {
+            // The only other function in the runtime library is exit, which stops the program straight away
+            return Err(Stop::Exit(values[0].as_int()));
+        }
+
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(runtime_error(
+                node,
+                &format!(
+                    "Stack overflow, more than {} function calls in progress at once",
+                    MAX_CALL_DEPTH
+                ),
+            ));
+        }
+
+        return self.call_function(&func, values);
+    }
+
+    fn call_printf(
+        &mut self,
+        node: NodeRef<'a>,
+        args: &Vec<NodeRef<'a>>,
+        frame: &mut Frame,
+    ) -> Result<Value, Stop> {
+        let pieces = self
+            .formats
+            .entry(node.id)
+            .or_insert_with(|| Rc::new(split_format_string(node).unwrap_or_default()))
+            .clone();
+
+        // fwrite_line prints to the file it's given, which comes before its format string
+        let format_arg = get_format_arg(node);
+        let file = if format_arg > 0 {
+            Some(self.evaluate(args[0].child(0), frame)?.as_int())
+        } else {
+            None
+        };
+
+        // Every format argument is evaluated before anything is printed
+        let mut values = vec![];
+        for arg in &args[format_arg + 1..] {
+            values.push(self.evaluate(arg.child(0), frame)?.as_int());
+        }
+
+        let mut text = pieces[0].clone();
+        for (value, piece) in values.iter().zip(&pieces[1..]) {
+            text.extend_from_slice(value.to_string().as_bytes());
+            text.extend_from_slice(piece);
+        }
+
+        match file {
+            // Printing to a file that isn't open does nothing
+            Some(file) => {
+                if let Some(file) = self.files.get_mut(&file) {
+                    let _ = file.write_all(&text);
+                }
+            }
+            None => self.write(&text),
+        }
+        return Ok(Value::Void);
+    }
+
+    // Print some output from the program (where there's nothing the program could do if it couldn't be printed)
+    fn write(&mut self, bytes: &[u8]) {
+        let _ = self.output.write_all(bytes);
+    }
+
+    // -----------------------------------------------------------------------------------------
+    // FILES
+    // -----------------------------------------------------------------------------------------
+
+    // Open the file at the given path in the given mode ("r", "w" or "a"), giving back its number, which is the lowest
+    // one not already taken from 3 up (after stdin, stdout and stderr), or -1 if it couldn't be opened
+    fn open_file(&mut self, path: &Value, mode: &Value) -> i32 {
+        let (path, mode) = match (path, mode) {
+            (Value::String(path), Value::String(mode)) => (path, mode),
+            _ => return -1, // Should never happen, indicates an error on my end
+        };
+
+        // Just like a C string, the path ends at its first null byte
+        let path = path.split(|byte| *byte == 0).next().unwrap_or(&[]);
+
+        let mut options = OpenOptions::new();
+        match mode.as_slice() {
+            b"r" => options.read(true),
+            b"w" => options.write(true).create(true).truncate(true),
+            _ => options.append(true).create(true),
+        };
+        let file = match options.open(&*String::from_utf8_lossy(path)) {
+            Ok(file) => file,
+            Err(_) => return -1,
+        };
+
+        let mut number = 3;
+        while self.files.contains_key(&number) {
+            number += 1;
+        }
+        self.files.insert(number, file);
+        return number;
+    }
+
+    // Read the next line of a file into an array, a byte at a time, just like the compiled program does:
+    // every byte that fits is stored in the array, up to the newline, followed by a 0 if there's room for one.
+    // Gives back the number of bytes stored, or -1 if the end of the file was reached before anything was read
+    // (or the file isn't open)
+    fn read_line(&mut self, file: i32, line: &Value) -> i32 {
+        let (file, line) = match (self.files.get_mut(&file), line) {
+            (Some(file), Value::Array(line)) => (file, line),
+            _ => return -1,
+        };
+
+        let len = line.get_len().max(0) as usize;
+        let mut num_stored = 0;
+        let mut read_anything = false;
+        let mut byte = [0];
+        while let Ok(1) = file.read(&mut byte) {
+            read_anything = true;
+            if byte[0] == b'\n' {
+                break;
+            }
+
+            if num_stored < len {
+                line.set_element(num_stored, byte[0] as i32);
+                num_stored += 1;
+            }
+        }
+
+        if !read_anything {
+            return -1;
+        }
+        if num_stored < len {
+            line.set_element(num_stored, 0);
+        }
+        return num_stored as i32;
+    }
+}
+
+// A checked program, being handed to the thread that interprets it
+struct CheckedProgram<'a>(&'a AST);
+
+// SAFETY: An AST is only kept from being Send by the symbol table entries the semantic checker attaches to it,
+// which are shared through Rcs. The thread that hands a program over waits for the interpreter's thread to finish
+// without touching the program in the meantime, so those Rcs are never used from two threads at once
+unsafe impl Send for CheckedProgram<'_> {}
+
+impl<'a> CheckedProgram<'a> {
+    fn get_ast(&self) -> &'a AST {
+        return self.0;
+    }
+}
+
+// Run a checked program with the interpreter, printing everything it prints (and any runtime error) to stdout
+// just like the compiled program would, and giving back its exit code.
+// The interpreter recurses for every function call, so it's run on a thread with a much bigger stack than the main thread has
+pub fn interpret_program(ast: &AST, overflow_checks: bool) -> i32 {
+    let program = CheckedProgram(ast);
+    return thread::scope(|scope| {
+        let interpreter = thread::Builder::new()
+            .stack_size(INTERPRETER_STACK_SIZE)
+            .spawn_scoped(scope, move || {
+                let stdout = io::stdout();
+                let mut output = BufWriter::new(stdout.lock());
+                let result = Interpreter::new(
+                    program.get_ast(),
+                    HashMap::new(),
+                    &mut output,
+                    overflow_checks,
+                )
+                .run_main();
+
+                let code = match result {
+                    Ok(code) | Err(Stop::Exit(code)) => code,
+                    Err(Stop::Error(message)) => {
+                        let _ = output.write_all(message.as_bytes());
+                        1
+                    }
+                };
+                let _ = output.flush();
+                code
+            });
+
+        return match interpreter {
+            Ok(interpreter) => interpreter
+                .join()
+                .unwrap_or_else(|payload| panic::resume_unwind(payload)),
+            Err(_) => {
+                throw_error("Could not start a thread to run the interpreter on");
+                1
+            }
+        };
+    });
+}
+
+// Get the element at a position of an array (or the byte at a position of a string), which has already been bounds checked
+fn get_element(sequence: &Value, index: usize) -> Value {
+    return match sequence {
+        Value::String(bytes) => Value::Int(bytes[index] as i32),
+        Value::Array(array) => array.get_element(index),
+        _ => Value::Int(0), // Should never happen, indicates an error on my end
+    };
+}
+
+// Create the error a compiled program would exit with, for a runtime error caused by the given node
+fn runtime_error(node: NodeRef, message: &str) -> Stop {
+    return Stop::Error(format!(
+        "Error: Line {}: {}\n",
+        node.get_line_num(),
+        message
+    ));
+}
@@ -0,0 +1,6 @@
+pub mod interpreter_data;
+pub mod interpreter_driver;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
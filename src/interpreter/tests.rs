@@ -0,0 +1,155 @@
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::interpreter::interpreter_data::Stop;
+    use crate::interpreter::interpreter_driver::Interpreter;
+    use crate::parser::parser_driver::parse_program;
+    use crate::scanner::scanner_driver::scan_str;
+    use crate::semantic::semantic_data::Warnings;
+    use crate::semantic::semantic_driver::semantic_checker;
+
+    // Run a program, giving back what it printed and how it finished
+    fn run(program: &str, overflow_checks: bool) -> (String, Result<i32, Stop>) {
+        let mut ast = parse_program(&scan_str(program).unwrap()).unwrap();
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        let mut output = vec![];
+        let result =
+            Interpreter::new(&ast, HashMap::new(), &mut output, overflow_checks).run_main();
+        return (String::from_utf8(output).unwrap(), result);
+    }
+
+    #[test]
+    fn test_interpret_program() {
+        let program = "int calls;
+int grid[2][3];
+
+func fib(int n) returns int {
+    calls += 1;
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+func fill(int[] row, int value) returns void {
+    int i = 0;
+    while true {
+        if i == len(row) {
+            break;
+        }
+        row[i] = value * i;
+        i += 1;
+    }
+}
+
+func main() returns int {
+    fill(grid[1], 5);
+    printf(\"{} {} {}\\n\", fib(10), calls, grid[1][2] - grid[0][2]);
+    printf(\"{} {} {}\", len(\"soup\\n\"), \"soup\"[1], -7 / 2 * (3 % -2));
+    bool both = false && 1 / calls == 0;
+    if !both || 2147483647 + 1 < 0 {
+        return 3;
+    }
+    return 0;
+}
+";
+        let (output, result) = run(program, false);
+        assert_eq!("55 177 10\n5 111 -3\n", output);
+        assert_eq!(Ok(3), result);
+    }
+
+    #[test]
+    fn test_interpret_runtime_errors() {
+        let program = "int zero;
+
+func main() returns void {
+    printf(\"before\\n\");
+    int x = 1 / zero;
+    printf(\"after\\n\");
+}
+";
+        let (output, result) = run(program, false);
+        assert_eq!("before\n", output);
+        assert_eq!(
+            Err(Stop::Error(String::from(
+                "Error: Line 5: Division by zero\n"
+            ))),
+            result
+        );
+
+        let program = "func main() returns void {
+    int a[3];
+    a[1 - 2] = 0;
+}
+";
+        assert_eq!(
+            Err(Stop::Error(String::from(
+                "Error: Line 3: Array index out of bounds\n"
+            ))),
+            run(program, false).1
+        );
+
+        let program = "func f(int n) returns int {
+    if n > 0 {
+        return n;
+    }
+}
+
+func main() returns void {
+    f(0);
+}
+";
+        assert_eq!(
+            Err(Stop::Error(String::from("Error: Line 1: A control path reaches the end of a non-void function without returning a value\n"))),
+            run(program, false).1
+        );
+
+        // Overflowing is only an error if we're checking for it
+        let program = "func main() returns int {
+    int x = 2147483647;
+    return x + 1;
+}
+";
+        assert_eq!(Ok(-2147483648), run(program, false).1);
+        assert_eq!(
+            Err(Stop::Error(String::from(
+                "Error: Line 3: Integer overflow\n"
+            ))),
+            run(program, true).1
+        );
+
+        // and so is dividing the smallest int by -1 (though its remainder is always 0)
+        let program = "func main() returns int {
+    int x = -2147483647 - 1;
+    printf(\"{}\\n\", x % -1);
+    return x / -1;
+}
+";
+        assert_eq!(
+            (String::from("0\n\n"), Ok(-2147483648)),
+            run(program, false)
+        );
+        assert_eq!(
+            (
+                String::from("0\n"),
+                Err(Stop::Error(String::from(
+                    "Error: Line 4: Integer overflow\n"
+                )))
+            ),
+            run(program, true)
+        );
+
+        // Exiting stops the program straight away, without printing anything else
+        let program = "func main() returns void {
+    printf(\"bye\");
+    exit(4);
+    printf(\"!\");
+}
+";
+        assert_eq!(
+            (String::from("bye"), Err(Stop::Exit(4))),
+            run(program, false)
+        );
+    }
+}
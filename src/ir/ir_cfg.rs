@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// CONTROL FLOW GRAPH
+// -----------------------------------------------------------------
+
+// A straight-line run of instructions, which can only be entered at the top and only left at the bottom
+#[derive(Clone, PartialEq, Debug)]
+pub struct BasicBlock {
+    // The label marking the start of the block, if anything jumps to it
+    pub label: Option<Label>,
+    // Every instruction in the block (not including its label), where only the last one can be a terminator
+    pub instrs: Vec<Instr>,
+    pub succs: Vec<usize>,
+    pub preds: Vec<usize>,
+}
+
+// The basic blocks of a function, in the order they appear in the function's body,
+// where the first block is the entry of the function
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Cfg {
+    // Split the body of a function up into basic blocks, and connect each block to the blocks it can continue on to
+    pub fn new(func: &IrFunction) -> Cfg {
+        let mut blocks = vec![BasicBlock {
+            label: None,
+            instrs: vec![],
+            succs: vec![],
+            preds: vec![],
+        }];
+
+        for instr in &func.body {
+            let current = blocks.len() - 1;
+
+            // A label always starts a new block (unless the current block is still empty and unlabelled)
+            if let Instr::Label(label) = instr {
+                if blocks[current].label.is_none() && blocks[current].instrs.is_empty() {
+                    blocks[current].label = Some(*label);
+                } else {
+                    blocks.push(BasicBlock {
+                        label: Some(*label),
+                        instrs: vec![],
+                        succs: vec![],
+                        preds: vec![],
+                    });
+                }
+                continue;
+            }
+
+            // Anything after a terminator starts a new (unlabelled, and so unreachable) block
+            if blocks[current]
+                .instrs
+                .last()
+                .is_some_and(|last| last.is_terminator())
+            {
+                blocks.push(BasicBlock {
+                    label: None,
+                    instrs: vec![],
+                    succs: vec![],
+                    preds: vec![],
+                });
+            }
+
+            let current = blocks.len() - 1;
+            blocks[current].instrs.push(instr.clone());
+        }
+
+        let mut cfg = Cfg { blocks: blocks };
+        cfg.connect_blocks();
+        return cfg;
+    }
+
+    // Work out the successors and predecessors of every block from the instructions at the end of each one
+    pub fn connect_blocks(&mut self) {
+        let label_blocks = self.get_label_blocks();
+
+        for block in &mut self.blocks {
+            block.succs.clear();
+            block.preds.clear();
+        }
+
+        for i in 0..self.blocks.len() {
+            let succs = match self.blocks[i].instrs.last() {
+                Some(Instr::Jump(label)) => vec![label_blocks[label]],
+                Some(Instr::Branch {
+                    if_true, if_false, ..
+                }) => {
+                    if if_true == if_false {
+                        vec![label_blocks[if_true]]
+                    } else {
+                        vec![label_blocks[if_true], label_blocks[if_false]]
+                    }
+                }
+                Some(Instr::Return(_)) | Some(Instr::Panic { .. }) => vec![],
+                // Any other block falls through to the next one (if there is a next one)
+                _ if i + 1 < self.blocks.len() => vec![i + 1],
+                _ => vec![],
+            };
+
+            for succ in &succs {
+                self.blocks[*succ].preds.push(i);
+            }
+            self.blocks[i].succs = succs;
+        }
+    }
+
+    // Get the block that starts with each label
+    pub fn get_label_blocks(&self) -> HashMap<Label, usize> {
+        let mut label_blocks = HashMap::new();
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            if let Some(label) = block.label {
+                label_blocks.insert(label, i);
+            }
+        }
+
+        return label_blocks;
+    }
+
+    // Get every block reachable from the entry block in reverse postorder, which visits every block before its successors
+    // (other than along the back edge of a loop), making it the natural order to run a forward dataflow analysis in
+    pub fn reverse_postorder(&self) -> Vec<usize> {
+        let mut postorder = vec![];
+        let mut visited = vec![false; self.blocks.len()];
+
+        // Walk the graph depth first, keeping a stack of (block, index of the next successor to visit)
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+
+        while let Some((block, next_succ)) = stack.pop() {
+            match self.blocks[block].succs.get(next_succ) {
+                Some(succ) => {
+                    stack.push((block, next_succ + 1));
+
+                    if !visited[*succ] {
+                        visited[*succ] = true;
+                        stack.push((*succ, 0));
+                    }
+                }
+                // Once all of a block's successors have been visited, the block itself is finished
+                None => postorder.push(block),
+            }
+        }
+
+        postorder.reverse();
+        return postorder;
+    }
+
+    // Put the blocks back together into the body of a function
+    pub fn to_body(&self) -> Vec<Instr> {
+        let mut body = vec![];
+
+        for block in &self.blocks {
+            if let Some(label) = block.label {
+                body.push(Instr::Label(label));
+            }
+            body.extend(block.instrs.iter().cloned());
+        }
+
+        return body;
+    }
+}
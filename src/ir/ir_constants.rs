@@ -0,0 +1,168 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the arithmetic shared by everything that works out the value of an expression before (or
+// without) the generated code running: constant folding, the semantic checker, global initializers and the interpreter.
+// An int overflowing wraps around, unless overflow is being checked for (in which case adding, subtracting,
+// multiplying or negating an int can overflow), and dividing by zero is always an error
+// -------------------------------------------------------------------------------------------------------------
+
+use crate::ir::ir_data::{BinOp, UnOp};
+use crate::ir::ir_utils::get_binop;
+use crate::parser::parser_data::NodeRef;
+
+// The reasons an expression can't be worked out
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EvalError {
+    // The expression depends on something that isn't known until the program runs (a variable or a function call)
+    NotConstant,
+    DivisionByZero,
+    // Only possible when overflow is being checked for
+    Overflow,
+}
+
+impl EvalError {
+    // Get the message the generated code exits with when this happens at runtime
+    pub fn get_message(&self) -> &'static str {
+        return match self {
+            EvalError::NotConstant => "Not a constant",
+            EvalError::DivisionByZero => "Division by zero",
+            EvalError::Overflow => "Integer overflow",
+        };
+    }
+}
+
+// Work out the result of a binary operator on two known values
+pub fn evaluate_binop(
+    op: BinOp,
+    lhs: i32,
+    rhs: i32,
+    overflow_checks: bool,
+) -> Result<i32, EvalError> {
+    let overflows = match op {
+        BinOp::Add => lhs.checked_add(rhs).is_none(),
+        BinOp::Sub => lhs.checked_sub(rhs).is_none(),
+        BinOp::Mul => lhs.checked_mul(rhs).is_none(),
+        BinOp::Div => lhs == i32::MIN && rhs == -1,
+        _ => false,
+    };
+    if overflow_checks && overflows {
+        return Err(EvalError::Overflow);
+    }
+
+    return match op {
+        BinOp::Add => Ok(lhs.wrapping_add(rhs)),
+        BinOp::Sub => Ok(lhs.wrapping_sub(rhs)),
+        BinOp::Mul => Ok(lhs.wrapping_mul(rhs)),
+        BinOp::Div | BinOp::Rem if rhs == 0 => Err(EvalError::DivisionByZero),
+        // Dividing rounds towards zero, so the remainder always has the same sign as lhs (-7 % 3 is -1, and 7 % -3 is 1),
+        // just like C and the hardware. Dividing the smallest int by -1 wraps around (leaving a remainder of 0)
+        BinOp::Div => Ok(lhs.wrapping_div(rhs)),
+        BinOp::Rem => Ok(lhs.wrapping_rem(rhs)),
+        BinOp::Eq => Ok((lhs == rhs) as i32),
+        BinOp::Ne => Ok((lhs != rhs) as i32),
+        BinOp::Lt => Ok((lhs < rhs) as i32),
+        BinOp::Gt => Ok((lhs > rhs) as i32),
+        BinOp::Le => Ok((lhs <= rhs) as i32),
+        BinOp::Ge => Ok((lhs >= rhs) as i32),
+        BinOp::ULt => Ok(((lhs as u32) < (rhs as u32)) as i32),
+        BinOp::AddOverflows => Ok(lhs.checked_add(rhs).is_none() as i32),
+        BinOp::SubOverflows => Ok(lhs.checked_sub(rhs).is_none() as i32),
+        BinOp::MulOverflows => Ok(lhs.checked_mul(rhs).is_none() as i32),
+        BinOp::DivOverflows => Ok((lhs == i32::MIN && rhs == -1) as i32),
+    };
+}
+
+// Work out the result of a unary operator on a known value (where only negating the smallest int can overflow)
+pub fn evaluate_unop(op: UnOp, value: i32, overflow_checks: bool) -> Result<i32, EvalError> {
+    return match op {
+        UnOp::Neg if overflow_checks && value == i32::MIN => Err(EvalError::Overflow),
+        UnOp::Neg => Ok(value.wrapping_neg()),
+        UnOp::Not => Ok(value ^ 1),
+    };
+}
+
+// Work out the value of an expression that doesn't depend on anything only known at runtime, with a bool being
+// 1 for true and 0 for false
+pub fn evaluate_constant(node: NodeRef, overflow_checks: bool) -> Result<i32, EvalError> {
+    // A chain of binary operators is worked out in a loop (see get_operator_chain), from its leftmost operand up
+    let chain = node.get_operator_chain();
+    if let Some(last) = chain.last() {
+        let mut value = evaluate_constant(last.child(0), overflow_checks);
+        for operator in chain.iter().rev() {
+            value = evaluate_operator(*operator, value, overflow_checks);
+        }
+        return value;
+    }
+
+    match node.node_type.as_str() {
+        "number" => return evaluate_number(&node.get_attr(), overflow_checks),
+        "true" => return Ok(1),
+        "false" => return Ok(0),
+        // A negative number is worked out as a whole, so the smallest int ("-2147483648") doesn't overflow
+        "u-" if node.child(0).node_type == "number" => {
+            return evaluate_number(&format!("-{}", node.child(0).get_attr()), overflow_checks);
+        }
+        "u-" => {
+            let value = evaluate_constant(node.child(0), overflow_checks)?;
+            return evaluate_unop(UnOp::Neg, value, overflow_checks);
+        }
+        "!" => {
+            let value = evaluate_constant(node.child(0), overflow_checks)?;
+            return evaluate_unop(UnOp::Not, value, overflow_checks);
+        }
+        // Converting to an int changes nothing (since a bool is already 0 or 1), but an int is true if it isn't zero
+        "cast" => {
+            let value = evaluate_constant(node.child(0), overflow_checks)?;
+            return match &*node.get_attr() {
+                "bool" => Ok((value != 0) as i32),
+                _ => Ok(value),
+            };
+        }
+        _ => return Err(EvalError::NotConstant),
+    }
+}
+
+// Work out the value of a binary operator, given the value of its left hand side (or why it couldn't be worked out).
+// Either side of && or || can decide the result on its own (false for &&, and true for ||),
+// in which case it doesn't matter whether the other side can be worked out
+fn evaluate_operator(
+    node: NodeRef,
+    left: Result<i32, EvalError>,
+    overflow_checks: bool,
+) -> Result<i32, EvalError> {
+    if node.node_type == "&&" || node.node_type == "||" {
+        let decides = if node.node_type == "&&" { 0 } else { 1 };
+        let right = evaluate_constant(node.child(1), overflow_checks);
+
+        if left == Ok(decides) || right == Ok(decides) {
+            return Ok(decides);
+        }
+
+        // Otherwise, the result is only known if both sides are
+        left.and(right)?;
+        return Ok(1 - decides);
+    }
+
+    let lhs = left?;
+    let rhs = evaluate_constant(node.child(1), overflow_checks)?;
+    return evaluate_binop(get_binop(&node.node_type), lhs, rhs, overflow_checks);
+}
+
+// Get the value a global variable starts off with, which the semantic checker made sure can be worked out
+pub fn get_global_value(decl: NodeRef) -> i32 {
+    if !decl.has_assignment() {
+        return 0;
+    }
+    return evaluate_constant(decl.child(2), true).unwrap_or(0);
+}
+
+// Get the value of a number (which can have a minus sign in front of it), where a number too large to fit in an int
+// overflows (or wraps around, just like get_literal_value, if overflow isn't being checked for)
+fn evaluate_number(number: &str, overflow_checks: bool) -> Result<i32, EvalError> {
+    let value = number.parse::<i64>().ok();
+
+    return match value.and_then(|value| i32::try_from(value).ok()) {
+        Some(value) => Ok(value),
+        None if overflow_checks => Err(EvalError::Overflow),
+        None => Ok(value.unwrap_or(0) as i32),
+    };
+}
@@ -0,0 +1,147 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file instruments a program for coverage (--coverage): every line of soup that starts a statement gets a
+// counter, which is added to every time the statement is run, and the program writes all of its counters out to a file
+// when it exits (however it exits). The file starts with a header saying which line of which file each counter
+// counts, which is followed by the counters themselves, as 4 byte little-endian unsigned ints:
+//
+// soup coverage
+// files 2
+// main.soup
+// math.soup
+// counters 3
+// 0 2
+// 0 3
+// 1 5
+// <the three counters>
+//
+// (where each counter is given as the index of its file, followed by its line)
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+
+use crate::ir::ir_data::*;
+
+// The first line of every coverage file
+pub const COVERAGE_MAGIC: &str = "soup coverage";
+
+// The file a program instrumented for coverage writes its counts to, unless it was compiled with --coverage=<file>
+pub const DEFAULT_COVERAGE_FILE: &str = "soup.cov";
+
+// Instrument a program for coverage, writing its counts to the given file when it exits.
+// This has to be done once each function's source file is known (see set_source_files), but before optimizing,
+// so any line that's optimized away (like an unreachable one) is still counted, just never run
+pub fn instrument_coverage(program: &mut IrProgram, coverage_file: &str) {
+    // The counters are numbered in the order their lines are first found
+    let mut counters: Vec<(usize, i32)> = vec![];
+    let mut counter_indices: HashMap<(usize, i32), usize> = HashMap::new();
+
+    let counters_global = program.add_global("coverage.counters", GlobalKind::Zeroed(0));
+
+    for func in &mut program.functions {
+        let body = mem::take(&mut func.body);
+
+        for instr in body {
+            let line = match instr {
+                Instr::Line(line) => line,
+                _ => {
+                    func.body.push(instr);
+                    continue;
+                }
+            };
+            func.body.push(instr);
+
+            let counter = *counter_indices.entry((func.file, line)).or_insert_with(|| {
+                counters.push((func.file, line));
+                return counters.len() - 1;
+            });
+            gen_increment(func, counters_global, counter);
+        }
+    }
+
+    // Now that every counter has been found, the array can be given its size
+    program.globals[counters_global].kind = GlobalKind::Zeroed(counters.len().max(1) as i32 * 4);
+
+    let header = format_coverage_header(&program.files, &counters);
+    let header_len = header.len();
+    let header = program.add_global("coverage.header", GlobalKind::String(header.into_bytes()));
+    let file = program.add_global(
+        "coverage.file",
+        GlobalKind::String(coverage_file.as_bytes().to_vec()),
+    );
+    let mode = program.add_global("coverage.mode", GlobalKind::String(b"wb".to_vec()));
+
+    program.coverage = Some(Coverage {
+        counters: counters_global,
+        num_counters: counters.len(),
+        header: header,
+        header_len: header_len,
+        file: file,
+        mode: mode,
+    });
+}
+
+// Add one to the given counter
+fn gen_increment(func: &mut IrFunction, counters: usize, counter: usize) {
+    let base = func.new_temp(IrType::Ptr);
+    let index = func.new_temp(IrType::Int);
+    let addr = func.new_temp(IrType::Ptr);
+    let count = func.new_temp(IrType::Int);
+    let one = func.new_temp(IrType::Int);
+    let new_count = func.new_temp(IrType::Int);
+
+    func.body.push(Instr::GlobalAddr {
+        dest: base,
+        global: counters,
+    });
+    func.body.push(Instr::Const {
+        dest: index,
+        value: counter as i32,
+    });
+    func.body.push(Instr::ElementAddr {
+        dest: addr,
+        base: base,
+        index: index,
+        scale: 4,
+    });
+    func.body.push(Instr::Load {
+        dest: count,
+        addr: addr,
+        width: Width::Word,
+    });
+    func.body.push(Instr::Const {
+        dest: one,
+        value: 1,
+    });
+    func.body.push(Instr::BinOp {
+        op: BinOp::Add,
+        dest: new_count,
+        lhs: count,
+        rhs: one,
+    });
+    func.body.push(Instr::Store {
+        src: new_count,
+        addr: addr,
+        width: Width::Word,
+    });
+}
+
+// Format the header of a coverage file, saying which line of which file each counter counts.
+// The files are written as full paths (where they can be found), so the report can be made from any directory
+pub fn format_coverage_header(files: &[String], counters: &[(usize, i32)]) -> String {
+    let mut header = format!("{}\nfiles {}\n", COVERAGE_MAGIC, files.len());
+    for file in files {
+        let path = match fs::canonicalize(file) {
+            Ok(path) => path.to_string_lossy().to_string(),
+            Err(_) => file.clone(),
+        };
+        header.push_str(&format!("{}\n", path));
+    }
+
+    header.push_str(&format!("counters {}\n", counters.len()));
+    for (file, line) in counters {
+        header.push_str(&format!("{} {}\n", file, line));
+    }
+    return header;
+}
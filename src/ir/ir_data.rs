@@ -0,0 +1,486 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::parser::parser_data::NodeRef;
+use crate::semantic::semantic_data::Symbol;
+
+// -----------------------------------------------------------------
+// IR PROGRAM
+// -----------------------------------------------------------------
+
+// A soup program lowered into three-address code, made up of the data the program needs
+// (global variables, string literals and error messages) and the functions that make up the program
+#[derive(Clone, PartialEq, Debug)]
+pub struct IrProgram {
+    pub globals: Vec<IrGlobal>,
+    pub functions: Vec<IrFunction>,
+    // The source files the program was compiled from (which are only known once they've been recorded by
+    // set_source_files, and are only needed for debug info and coverage)
+    pub files: Vec<String>,
+    // Where the program keeps count of the lines it runs, if it's been instrumented for coverage
+    pub coverage: Option<Coverage>,
+}
+
+impl Default for IrProgram {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl IrProgram {
+    pub fn new() -> IrProgram {
+        return IrProgram {
+            globals: vec![],
+            functions: vec![],
+            files: vec![],
+            coverage: None,
+        };
+    }
+
+    // Add a global to the program, returning the index it can be referred to by
+    pub fn add_global(&mut self, name: &str, kind: GlobalKind) -> usize {
+        self.globals.push(IrGlobal {
+            name: String::from(name),
+            kind: kind,
+        });
+
+        return self.globals.len() - 1;
+    }
+
+    // Find the function with the given name
+    pub fn get_function(&self, name: &str) -> Option<&IrFunction> {
+        return self.functions.iter().find(|func| func.name == name);
+    }
+
+    // Whether any function in the program calls the function with the given name
+    // (which decides which functions in the runtime library are written out along with it)
+    pub fn calls(&self, name: &str) -> bool {
+        return self.functions.iter().any(|func| {
+            func.body
+                .iter()
+                .any(|instr| matches!(instr, Instr::Call { func: callee, .. } if callee == name))
+        });
+    }
+}
+
+// The globals a program instrumented for coverage (--coverage) keeps count of the lines it runs in,
+// all of which it writes out to a file when it exits (see ir_coverage.rs)
+#[derive(Clone, PartialEq, Debug)]
+pub struct Coverage {
+    // An array of an int for each line of soup being counted, which is added to every time the line is run
+    pub counters: usize,
+    pub num_counters: usize,
+    // A string describing which line each counter counts, which is written out in front of the counts
+    pub header: usize,
+    pub header_len: usize,
+    // The name of the file the counts are written to, and the mode it's opened in (as fopen takes them)
+    pub file: usize,
+    pub mode: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct IrGlobal {
+    pub name: String,
+    pub kind: GlobalKind,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum GlobalKind {
+    // A single int or bool, initialized to the given value
+    Word(i32),
+    // The given number of bytes, all initialized to zero (i.e. a global array)
+    Zeroed(i32),
+    // The bytes of a string (with any escape sequences already converted), which are followed by a null byte in memory
+    String(Vec<u8>),
+}
+
+// -----------------------------------------------------------------
+// IR FUNCTION
+// -----------------------------------------------------------------
+
+// A temporary is a virtual register, and a function can use as many of them as it likes
+// (every local variable and parameter is a temporary, which may be assigned to any number of times)
+pub type Temp = usize;
+
+// A label marks a position in the body of a function that can be jumped to
+pub type Label = usize;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IrType {
+    // A 32 bit value, either an int or a bool (which is always 0 or 1)
+    Int,
+    // A 64 bit address, such as a pointer to the first element of an array
+    Ptr,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct IrFunction {
+    pub name: String,
+    // The temporaries holding each parameter, where an array parameter is passed as two
+    // (the address of its first element, followed by its length)
+    pub params: Vec<Temp>,
+    // The types of the values returned by the function, which is empty for a void function,
+    // and again made up of an address and a length for a function returning an array
+    pub returns: Vec<IrType>,
+    // The type of every temporary used by the function
+    pub temps: Vec<IrType>,
+    // The name of the variable held by each temporary that holds one (only used to make dumps of the IR readable)
+    pub temp_names: HashMap<Temp, String>,
+    // The size in bytes of every stack slot allocated by the function (to hold its local arrays)
+    pub slots: Vec<i32>,
+    // The name of the array held by each stack slot (only used to make dumps of the stack frame readable)
+    pub slot_names: HashMap<usize, String>,
+    pub num_labels: usize,
+    pub body: Vec<Instr>,
+    // The index of the source file (in the program's files) the function was declared in,
+    // which the line markers in its body refer to
+    pub file: usize,
+}
+
+impl IrFunction {
+    pub fn new(name: &str) -> IrFunction {
+        return IrFunction {
+            name: String::from(name),
+            params: vec![],
+            returns: vec![],
+            temps: vec![],
+            temp_names: HashMap::new(),
+            slots: vec![],
+            slot_names: HashMap::new(),
+            num_labels: 0,
+            body: vec![],
+            file: 0,
+        };
+    }
+
+    pub fn new_temp(&mut self, temp_type: IrType) -> Temp {
+        self.temps.push(temp_type);
+        return self.temps.len() - 1;
+    }
+
+    pub fn name_temp(&mut self, temp: Temp, name: &str) {
+        self.temp_names.insert(temp, String::from(name));
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        self.num_labels += 1;
+        return self.num_labels - 1;
+    }
+
+    pub fn new_slot(&mut self, num_bytes: i32) -> usize {
+        self.slots.push(num_bytes);
+        return self.slots.len() - 1;
+    }
+
+    pub fn name_slot(&mut self, slot: usize, name: &str) {
+        self.slot_names.insert(slot, String::from(name));
+    }
+
+    pub fn push(&mut self, instr: Instr) {
+        self.body.push(instr);
+    }
+}
+
+// -----------------------------------------------------------------
+// INSTRUCTIONS
+// -----------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    // Division and remainder assume the right hand side isn't zero,
+    // since a check for that is generated before them
+    Div,
+    Rem,
+    // Comparisons give 1 if the comparison holds, and 0 otherwise
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    // An unsigned less than, which is how indexes are checked against the length of an array
+    // (since a negative index looks like a huge unsigned number)
+    ULt,
+    // Give 1 if adding, subtracting, multiplying or dividing the two sides would overflow an int, and 0 otherwise
+    // (which is how arithmetic is checked with --overflow-checks). Dividing only overflows for the smallest int
+    // divided by -1
+    AddOverflows,
+    SubOverflows,
+    MulOverflows,
+    DivOverflows,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnOp {
+    Neg,
+    // The logical not of a bool
+    Not,
+}
+
+// The number of bytes read or written by a load or store
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Width {
+    // An int or bool
+    Word,
+    // A character of a string
+    Byte,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Instr {
+    // dest = value
+    Const {
+        dest: Temp,
+        value: i32,
+    },
+    // dest = src
+    Copy {
+        dest: Temp,
+        src: Temp,
+    },
+    // dest = lhs op rhs
+    BinOp {
+        op: BinOp,
+        dest: Temp,
+        lhs: Temp,
+        rhs: Temp,
+    },
+    // dest = op src
+    UnOp {
+        op: UnOp,
+        dest: Temp,
+        src: Temp,
+    },
+    // dest = if_true if cond is non-zero, and if_false otherwise
+    Select {
+        dest: Temp,
+        cond: Temp,
+        if_true: Temp,
+        if_false: Temp,
+    },
+    // dest = the address of the given stack slot
+    SlotAddr {
+        dest: Temp,
+        slot: usize,
+    },
+    // dest = the address of the given global
+    GlobalAddr {
+        dest: Temp,
+        global: usize,
+    },
+    // dest = base + (index * scale), the address of an element of an array
+    ElementAddr {
+        dest: Temp,
+        base: Temp,
+        index: Temp,
+        scale: i32,
+    },
+    // dest = the value at the given address
+    Load {
+        dest: Temp,
+        addr: Temp,
+        width: Width,
+    },
+    // Store src at the given address
+    Store {
+        src: Temp,
+        addr: Temp,
+        width: Width,
+    },
+    // Call a function (either one of the program's or one from the runtime library), putting anything it returns in dests
+    Call {
+        dests: Vec<Temp>,
+        func: String,
+        args: Vec<Temp>,
+    },
+    // Print the given format string global, with the given ints filling in its formatters,
+    // to stdout or to the given file (a file descriptor, for fwrite_line)
+    Print {
+        file: Option<Temp>,
+        format: usize,
+        args: Vec<Temp>,
+    },
+    Label(Label),
+    Jump(Label),
+    // Jump to if_true if cond is non-zero, and to if_false otherwise
+    Branch {
+        cond: Temp,
+        if_true: Label,
+        if_false: Label,
+    },
+    Return(Vec<Temp>),
+    // Print the given error message global (a format string with a single "%d", for the line the error happened on)
+    // and exit the program with an exit code of 1, through the runtime library's shared error routine
+    Panic {
+        message: usize,
+        line: i32,
+    },
+    // Marks the instructions after it as coming from the given line of the source code
+    Line(i32),
+}
+
+impl Instr {
+    // Return true if control never continues on to the next instruction after this one
+    pub fn is_terminator(&self) -> bool {
+        return matches!(
+            self,
+            Instr::Jump(_) | Instr::Branch { .. } | Instr::Return(_) | Instr::Panic { .. }
+        );
+    }
+
+    // Return true if this instruction does anything other than set its destination temporaries,
+    // meaning it can't be removed even if nothing uses the values it produces
+    pub fn has_side_effects(&self) -> bool {
+        return matches!(
+            self,
+            Instr::Store { .. }
+                | Instr::Call { .. }
+                | Instr::Print { .. }
+                | Instr::Label(_)
+                | Instr::Jump(_)
+                | Instr::Branch { .. }
+                | Instr::Return(_)
+                | Instr::Panic { .. }
+                // A line marker doesn't do anything, but it has to stay where it is to remain accurate
+                | Instr::Line(_)
+        );
+    }
+
+    // Get the temporaries this instruction sets
+    pub fn get_defs(&self) -> Vec<Temp> {
+        return match self {
+            Instr::Const { dest, .. }
+            | Instr::Copy { dest, .. }
+            | Instr::BinOp { dest, .. }
+            | Instr::UnOp { dest, .. }
+            | Instr::Select { dest, .. }
+            | Instr::SlotAddr { dest, .. }
+            | Instr::GlobalAddr { dest, .. }
+            | Instr::ElementAddr { dest, .. }
+            | Instr::Load { dest, .. } => vec![*dest],
+            Instr::Call { dests, .. } => dests.clone(),
+            _ => vec![],
+        };
+    }
+
+    // Get the temporaries this instruction reads
+    pub fn get_uses(&self) -> Vec<Temp> {
+        return match self {
+            Instr::Copy { src, .. } | Instr::UnOp { src, .. } => vec![*src],
+            Instr::BinOp { lhs, rhs, .. } => vec![*lhs, *rhs],
+            Instr::Select {
+                cond,
+                if_true,
+                if_false,
+                ..
+            } => vec![*cond, *if_true, *if_false],
+            Instr::ElementAddr { base, index, .. } => vec![*base, *index],
+            Instr::Load { addr, .. } => vec![*addr],
+            Instr::Store { src, addr, .. } => vec![*src, *addr],
+            Instr::Call { args, .. } => args.clone(),
+            Instr::Print { file, args, .. } => file.iter().chain(args).copied().collect(),
+            Instr::Branch { cond, .. } => vec![*cond],
+            Instr::Return(values) => values.clone(),
+            _ => vec![],
+        };
+    }
+}
+
+// -----------------------------------------------------------------
+// IR BUILDER
+// -----------------------------------------------------------------
+
+// Where the value of a variable lives
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VarLoc {
+    // An int or bool local variable or parameter, held in a temporary
+    Scalar(Temp),
+    // An array parameter, held in a pair of temporaries (the address of its first element, followed by its length)
+    ArrayRef(Temp, Temp),
+    // An array declared in a function, whose elements are stored in a stack slot
+    Slot(usize),
+    // A global variable or array
+    Global(usize),
+}
+
+// Where a break or continue statement meant for a loop jumps to
+pub struct LoopTargets {
+    // The label the loop was given in the source, if it was given one
+    pub name: Option<Arc<str>>,
+    // The label just after the loop, for a break
+    pub end: Label,
+    // The label that starts its next iteration (after testing its condition, if it has one first), for a continue
+    pub next: Label,
+}
+
+pub struct IrBuilder {
+    pub program: IrProgram,
+    // The function currently being lowered
+    pub func: IrFunction,
+    // The location of every variable seen so far, keyed by its symbol table entry
+    pub vars: HashMap<*const RefCell<Symbol>, VarLoc>,
+    // Where break and continue statements jump to for each loop we are currently inside of, innermost last
+    pub loops: Vec<LoopTargets>,
+    // Whether arithmetic that overflows is a runtime error (--overflow-checks), rather than wrapping around
+    pub overflow_checks: bool,
+    // Whether local variables and arrays declared without a value start off at zero (--zero-init),
+    // rather than with whatever was left in their register or stack slot
+    pub zero_init: bool,
+    // The global holding each runtime error message lowered so far, so every error of the same kind shares one
+    pub error_messages: HashMap<String, usize>,
+}
+
+impl Default for IrBuilder {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl IrBuilder {
+    pub fn new() -> IrBuilder {
+        return IrBuilder {
+            program: IrProgram::new(),
+            func: IrFunction::new(""),
+            vars: HashMap::new(),
+            loops: vec![],
+            overflow_checks: false,
+            zero_init: false,
+            error_messages: HashMap::new(),
+        };
+    }
+
+    // Start lowering a new function
+    pub fn enter_func(&mut self, name: &str) {
+        self.func = IrFunction::new(name);
+    }
+
+    // Add the function we've finished lowering to the program
+    pub fn exit_func(&mut self) {
+        let func = mem::replace(&mut self.func, IrFunction::new(""));
+        self.program.functions.push(func);
+    }
+
+    // Add an instruction to the end of the function currently being lowered
+    pub fn emit(&mut self, instr: Instr) {
+        self.func.push(instr);
+    }
+
+    // Remember where the variable declared (or referred to) by the given node lives
+    pub fn add_var(&mut self, node: NodeRef, loc: VarLoc) {
+        self.vars.insert(Rc::as_ptr(&node.get_sym()), loc);
+    }
+
+    // Find where the variable referred to by the given node lives
+    pub fn get_var(&self, node: NodeRef) -> VarLoc {
+        return match self.vars.get(&Rc::as_ptr(&node.get_sym())) {
+            None => VarLoc::Scalar(0), // Should never happen, indicates an error on my end
+            Some(loc) => *loc,
+        };
+    }
+}
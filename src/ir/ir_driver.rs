@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::ir::ir_data::*;
+use crate::ir::ir_lowering::*;
+use crate::parser::parser_data::AST;
+use crate::passes::passes_trace::log_span;
+
+// -----------------------------------------------------------------
+// IR LOWERING
+// -----------------------------------------------------------------
+
+// Lower a semantically checked program into three-address code
+pub fn lower_program(ast: &AST, overflow_checks: bool, zero_init: bool) -> IrProgram {
+    let mut builder = IrBuilder::new();
+    builder.overflow_checks = overflow_checks;
+    builder.zero_init = zero_init;
+
+    // Every global can be used by every function, no matter where it's declared, so lower them all first
+    for decl in ast.get_root().children() {
+        if decl.node_type == "globVarDecl" {
+            lower_global(&mut builder, decl);
+        }
+    }
+
+    for decl in ast.get_root().children() {
+        if decl.node_type == "funcDecl" || decl.node_type == "mainFuncDecl" {
+            log_span(
+                || format!("function '{}'", decl.get_func_name()),
+                || lower_function(&mut builder, decl),
+            );
+        }
+    }
+
+    return builder.program;
+}
+
+// Record the source file each function was declared in (given by the name of the function),
+// so the line markers in its body can be traced back to the right file
+pub fn set_source_files(program: &mut IrProgram, function_files: &HashMap<String, String>) {
+    for func in &mut program.functions {
+        let file = match function_files.get(&func.name) {
+            None => continue, // Should never happen, indicates an error on my end
+            Some(file) => file,
+        };
+
+        func.file = match program.files.iter().position(|other| other == file) {
+            Some(index) => index,
+            None => {
+                program.files.push(file.clone());
+                program.files.len() - 1
+            }
+        };
+    }
+}
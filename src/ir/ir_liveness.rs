@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use crate::ir::ir_cfg::Cfg;
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// LIVENESS
+// -----------------------------------------------------------------
+
+// Work out which temporaries are live (hold a value that might still be used later on) at the start and at the end
+// of every block of a control flow graph, returning (live in, live out) for each block
+pub fn get_block_liveness(cfg: &Cfg) -> (Vec<HashSet<Temp>>, Vec<HashSet<Temp>>) {
+    // Find the temporaries each block uses before setting them, and the temporaries each block sets
+    let mut uses = vec![];
+    let mut defs = vec![];
+    for block in &cfg.blocks {
+        let mut block_uses = HashSet::new();
+        let mut block_defs = HashSet::new();
+        for instr in &block.instrs {
+            for temp in instr.get_uses() {
+                if !block_defs.contains(&temp) {
+                    block_uses.insert(temp);
+                }
+            }
+            block_defs.extend(instr.get_defs());
+        }
+        uses.push(block_uses);
+        defs.push(block_defs);
+    }
+
+    // A temporary is live going into a block if the block uses it, or if it's live coming out of the block
+    // and the block doesn't set it. Liveness flows backwards, so keep going over the blocks from last to first
+    // until nothing changes (which takes more than one go whenever there's a loop)
+    let mut live_in = vec![HashSet::new(); cfg.blocks.len()];
+    let mut live_out = vec![HashSet::new(); cfg.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for i in (0..cfg.blocks.len()).rev() {
+            let mut block_out = HashSet::new();
+            for succ in &cfg.blocks[i].succs {
+                block_out.extend(live_in[*succ].iter().copied());
+            }
+
+            let mut block_in = uses[i].clone();
+            block_in.extend(block_out.difference(&defs[i]).copied());
+
+            if block_in != live_in[i] || block_out != live_out[i] {
+                changed = true;
+                live_in[i] = block_in;
+                live_out[i] = block_out;
+            }
+        }
+    }
+
+    return (live_in, live_out);
+}
+
+// Get the range of instructions (as the indexes of the first and last instructions in the body of the function)
+// that each temporary is live across, or None for a temporary that's never set or used.
+// A range covers every instruction the temporary is live at, but can also cover some it isn't live at,
+// like the instructions in between the two halves of an if-else that both set it
+pub fn get_live_ranges(func: &IrFunction) -> Vec<Option<(usize, usize)>> {
+    let cfg = Cfg::new(func);
+    let (live_in, live_out) = get_block_liveness(&cfg);
+
+    let mut ranges: Vec<Option<(usize, usize)>> = vec![None; func.temps.len()];
+    let mut extend = |temp: Temp, index: usize| {
+        ranges[temp] = match ranges[temp] {
+            None => Some((index, index)),
+            Some((start, end)) => Some((start.min(index), end.max(index))),
+        };
+    };
+
+    // Parameters are set before the first instruction, when the function is entered
+    for param in &func.params {
+        extend(*param, 0);
+    }
+
+    // The blocks are in the same order as the body they were split up from, so walk through them
+    // keeping track of where each instruction was in the body
+    let mut index = 0;
+    for (i, block) in cfg.blocks.iter().enumerate() {
+        let start = index;
+        if block.label.is_some() {
+            index += 1;
+        }
+
+        for instr in &block.instrs {
+            for temp in instr.get_defs().into_iter().chain(instr.get_uses()) {
+                extend(temp, index);
+            }
+            index += 1;
+        }
+
+        // Anything live going into or coming out of the block is live across the whole thing
+        let end = index.saturating_sub(1).max(start);
+        for temp in &live_in[i] {
+            extend(*temp, start);
+        }
+        for temp in &live_out[i] {
+            extend(*temp, end);
+        }
+    }
+
+    return ranges;
+}
+
+// Work out which temporaries interfere with each other (are live at the same time, so can't share a register),
+// as the set of temporaries each one interferes with. A temporary set by an instruction interferes with everything
+// live after it, apart from the temporary a copy copies from, since the two hold the same value
+pub fn get_interference_graph(func: &IrFunction) -> Vec<HashSet<Temp>> {
+    let cfg = Cfg::new(func);
+    let (live_in, live_out) = get_block_liveness(&cfg);
+
+    let mut graph = vec![HashSet::new(); func.temps.len()];
+    let mut add_edge = |a: Temp, b: Temp| {
+        if a != b {
+            graph[a].insert(b);
+            graph[b].insert(a);
+        }
+    };
+
+    // Walk backwards through each block, keeping track of what's live after each instruction
+    for (i, block) in cfg.blocks.iter().enumerate() {
+        let mut live = live_out[i].clone();
+
+        for instr in block.instrs.iter().rev() {
+            let copied = match instr {
+                Instr::Copy { src, .. } => Some(*src),
+                _ => None,
+            };
+
+            let defs = instr.get_defs();
+            for def in &defs {
+                for temp in &live {
+                    if Some(*temp) != copied {
+                        add_edge(*def, *temp);
+                    }
+                }
+            }
+
+            // An instruction that sets more than one temporary sets them one after the other,
+            // so they can't share a register either
+            for a in &defs {
+                for b in &defs {
+                    add_edge(*a, *b);
+                }
+            }
+
+            for def in &defs {
+                live.remove(def);
+            }
+            live.extend(instr.get_uses());
+        }
+    }
+
+    // Parameters are all set (one after the other) when the function is entered
+    for param in &func.params {
+        for other in func.params.iter().chain(live_in[0].iter()) {
+            add_edge(*param, *other);
+        }
+    }
+
+    return graph;
+}
@@ -0,0 +1,1019 @@
+use crate::ir::ir_constants::get_global_value;
+use crate::ir::ir_data::*;
+use crate::ir::ir_utils::*;
+use crate::parser::parser_data::{is_binary_operator, NodeRef};
+use crate::semantic::semantic_data::Type;
+use crate::semantic::semantic_utils::{is_array_type, is_binary, is_unary};
+
+// -----------------------------------------------------------------------------------------
+// DECLARATIONS
+// -----------------------------------------------------------------------------------------
+
+pub fn lower_global(builder: &mut IrBuilder, node: NodeRef) {
+    let sym = node.get_sym();
+
+    let kind = if sym.borrow().is_array_storage() {
+        // Arrays can't have an assignment attached to them, so every element is initialized to zero
+        GlobalKind::Zeroed(sym.borrow().get_num_elements() * 4)
+    } else {
+        GlobalKind::Word(get_global_value(node))
+    };
+
+    let global = builder.program.add_global(&sym.borrow().name, kind);
+    builder.add_var(node, VarLoc::Global(global));
+}
+
+pub fn lower_function(builder: &mut IrBuilder, node: NodeRef) {
+    builder.enter_func(&node.get_func_name());
+
+    // Every parameter is held in a temporary (or two, for an array)
+    for param in node.child(1).children() {
+        if is_array_type(&param.get_sym().borrow().type_sig) {
+            let ptr = builder.func.new_temp(IrType::Ptr);
+            let len = builder.func.new_temp(IrType::Int);
+            builder.func.params.push(ptr);
+            builder.func.params.push(len);
+            builder.add_var(param, VarLoc::ArrayRef(ptr, len));
+
+            let name = param.get_sym().borrow().name.clone();
+            builder.func.name_temp(ptr, &name);
+            builder.func.name_temp(len, &format!("{}.len", name));
+        } else {
+            let temp = builder.func.new_temp(IrType::Int);
+            builder.func.params.push(temp);
+            builder.add_var(param, VarLoc::Scalar(temp));
+            builder.func.name_temp(temp, &param.get_sym().borrow().name);
+        }
+    }
+
+    let returns = node.get_sym().borrow().get_returns();
+    builder.func.returns = if returns == Type::Void {
+        vec![]
+    } else if is_array_type(&returns) {
+        vec![IrType::Ptr, IrType::Int]
+    } else {
+        vec![IrType::Int]
+    };
+
+    lower_statement(builder, node.child(3));
+
+    if returns == Type::Void {
+        builder.emit(Instr::Return(vec![]));
+    } else {
+        // If we make it to the end of a non-void function, there's no value to return
+        lower_panic(
+            builder,
+            "A control path reaches the end of a non-void function without returning a value",
+            node.get_line_num(),
+        );
+    }
+
+    builder.exit_func();
+}
+
+// -----------------------------------------------------------------------------------------
+// STATEMENTS
+// -----------------------------------------------------------------------------------------
+
+pub fn lower_statement(builder: &mut IrBuilder, node: NodeRef) {
+    // Mark where every statement (other than a block, whose statements each get their own mark) came from
+    if node.node_type != "block" {
+        if let Some(line) = node.line_num {
+            builder.emit(Instr::Line(line));
+        }
+    }
+
+    if node.node_type == "block" {
+        for child in node.children() {
+            lower_statement(builder, child);
+        }
+    } else if node.node_type == "varDecl" {
+        lower_var_decl(builder, node);
+    } else if node.node_type == "="
+        || node.node_type == "+="
+        || node.node_type == "-="
+        || node.node_type == "*="
+        || node.node_type == "/="
+        || node.node_type == "%="
+    {
+        lower_assignment(builder, node);
+    } else if node.node_type == "funcCall" {
+        lower_func_call(builder, node);
+    } else if node.node_type == "return" {
+        lower_return(builder, node);
+    } else if node.node_type == "if" || node.node_type == "ifElse" {
+        lower_if(builder, node);
+    } else if node.node_type == "while" {
+        lower_while(builder, node);
+    } else if node.node_type == "loop" {
+        lower_loop(builder, node);
+    } else if node.node_type == "repeat" {
+        lower_repeat(builder, node);
+    } else if node.node_type == "break" || node.node_type == "continue" {
+        // Jump past the end of the loop, or to its next iteration: the innermost loop, or the one with the given label
+        // (the semantic checker made sure we're inside of it)
+        let target = builder
+            .loops
+            .iter()
+            .rev()
+            .find(|target| node.attr.is_none() || target.name == node.attr);
+        if let Some(target) = target {
+            let label = match node.node_type.as_str() {
+                "break" => target.end,
+                _ => target.next,
+            };
+            builder.emit(Instr::Jump(label));
+        }
+    }
+}
+
+fn lower_var_decl(builder: &mut IrBuilder, node: NodeRef) {
+    let sym = node.get_sym();
+
+    if sym.borrow().is_array_storage() {
+        // The elements of an array declared in a function are stored in the function's stack frame
+        let slot = builder.func.new_slot(sym.borrow().get_num_elements() * 4);
+        builder.add_var(node, VarLoc::Slot(slot));
+        builder.func.name_slot(slot, &sym.borrow().name);
+
+        if builder.zero_init {
+            lower_zero_slot(builder, slot, sym.borrow().get_num_elements());
+        }
+        return;
+    }
+
+    let temp = builder.func.new_temp(IrType::Int);
+    builder.add_var(node, VarLoc::Scalar(temp));
+    builder.func.name_temp(temp, &sym.borrow().name);
+
+    if node.has_assignment() {
+        let value = lower_expr(builder, node.child(2));
+        builder.emit(Instr::Copy {
+            dest: temp,
+            src: value,
+        });
+    } else if builder.zero_init {
+        builder.emit(Instr::Const {
+            dest: temp,
+            value: 0,
+        });
+    }
+}
+
+// Set every element of an array stored in a stack slot to zero, each time its declaration is reached
+// (just like the interpreter, which gives every array declared without a value a fresh set of zeroed elements)
+fn lower_zero_slot(builder: &mut IrBuilder, slot: usize, num_elements: i32) {
+    let base = builder.func.new_temp(IrType::Ptr);
+    let len = builder.func.new_temp(IrType::Int);
+    let index = builder.func.new_temp(IrType::Int);
+    let zero = builder.func.new_temp(IrType::Int);
+
+    builder.emit(Instr::SlotAddr {
+        dest: base,
+        slot: slot,
+    });
+    builder.emit(Instr::Const {
+        dest: len,
+        value: num_elements,
+    });
+    builder.emit(Instr::Const {
+        dest: zero,
+        value: 0,
+    });
+    builder.emit(Instr::Copy {
+        dest: index,
+        src: zero,
+    });
+
+    // Loop over the elements, storing zero into each one
+    let test_label = builder.func.new_label();
+    let body_label = builder.func.new_label();
+    let after_label = builder.func.new_label();
+
+    builder.emit(Instr::Label(test_label));
+    let in_bounds = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::BinOp {
+        op: BinOp::Lt,
+        dest: in_bounds,
+        lhs: index,
+        rhs: len,
+    });
+    builder.emit(Instr::Branch {
+        cond: in_bounds,
+        if_true: body_label,
+        if_false: after_label,
+    });
+
+    builder.emit(Instr::Label(body_label));
+    let addr = builder.func.new_temp(IrType::Ptr);
+    builder.emit(Instr::ElementAddr {
+        dest: addr,
+        base: base,
+        index: index,
+        scale: 4,
+    });
+    builder.emit(Instr::Store {
+        src: zero,
+        addr: addr,
+        width: Width::Word,
+    });
+
+    let one = builder.func.new_temp(IrType::Int);
+    let next = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::Const {
+        dest: one,
+        value: 1,
+    });
+    builder.emit(Instr::BinOp {
+        op: BinOp::Add,
+        dest: next,
+        lhs: index,
+        rhs: one,
+    });
+    builder.emit(Instr::Copy {
+        dest: index,
+        src: next,
+    });
+
+    builder.emit(Instr::Jump(test_label));
+    builder.emit(Instr::Label(after_label));
+}
+
+fn lower_assignment(builder: &mut IrBuilder, node: NodeRef) {
+    let lhs = node.child(0);
+
+    // An array element is written to through its address, which is only worked out (and bounds checked) once,
+    // even if we also need to read the element's current value for a compound assignment
+    let elem_addr = if lhs.node_type == "index" {
+        Some(lower_index_addr(builder, lhs))
+    } else {
+        None
+    };
+
+    // For a compound assignment like "x += 1", read the current value before evaluating the right hand side
+    let current = if node.node_type == "=" {
+        None
+    } else {
+        match elem_addr {
+            Some(addr) => {
+                let current = builder.func.new_temp(IrType::Int);
+                builder.emit(Instr::Load {
+                    dest: current,
+                    addr: addr,
+                    width: Width::Word,
+                });
+                Some(current)
+            }
+            None => Some(lower_expr(builder, lhs)),
+        }
+    };
+
+    let mut value = lower_expr(builder, node.child(1));
+
+    if let Some(current) = current {
+        value = lower_binop(builder, node, current, value);
+    }
+
+    match (elem_addr, builder.get_var(lhs)) {
+        (Some(addr), _) => builder.emit(Instr::Store {
+            src: value,
+            addr: addr,
+            width: Width::Word,
+        }),
+        (None, VarLoc::Global(global)) => {
+            let addr = builder.func.new_temp(IrType::Ptr);
+            builder.emit(Instr::GlobalAddr {
+                dest: addr,
+                global: global,
+            });
+            builder.emit(Instr::Store {
+                src: value,
+                addr: addr,
+                width: Width::Word,
+            });
+        }
+        (None, VarLoc::Scalar(temp)) => builder.emit(Instr::Copy {
+            dest: temp,
+            src: value,
+        }),
+        // Arrays can't be assigned to, which the semantic checker makes sure of
+        (None, _) => {}
+    }
+}
+
+fn lower_return(builder: &mut IrBuilder, node: NodeRef) {
+    if node.children.is_empty() {
+        builder.emit(Instr::Return(vec![]));
+    } else if is_array_type(&node.get_type()) {
+        // An array is returned as the address of its first element and its length
+        let (ptr, len) = lower_array_ref(builder, node.child(0));
+        builder.emit(Instr::Return(vec![ptr, len]));
+    } else {
+        let value = lower_expr(builder, node.child(0));
+        builder.emit(Instr::Return(vec![value]));
+    }
+}
+
+fn lower_if(builder: &mut IrBuilder, node: NodeRef) {
+    // An if-else that only assigns one of two simple values to the same variable
+    // picks between them instead of branching
+    // (where the values only depend on local variables, which the condition can't change, so the condition can be
+    // worked out last, right before it's used)
+    if let Some((var, then_value, else_value)) = get_select(builder, node) {
+        let if_true = lower_expr(builder, then_value);
+        let if_false = lower_expr(builder, else_value);
+        let cond = lower_expr(builder, node.child(0));
+        builder.emit(Instr::Select {
+            dest: var,
+            cond: cond,
+            if_true: if_true,
+            if_false: if_false,
+        });
+        return;
+    }
+
+    let then_label = builder.func.new_label();
+    let after_label = builder.func.new_label();
+
+    // Without an else block, a false condition skips straight past the if block
+    let else_label = if node.node_type == "ifElse" {
+        builder.func.new_label()
+    } else {
+        after_label
+    };
+
+    let cond = lower_expr(builder, node.child(0));
+    builder.emit(Instr::Branch {
+        cond: cond,
+        if_true: then_label,
+        if_false: else_label,
+    });
+
+    builder.emit(Instr::Label(then_label));
+    lower_statement(builder, node.child(1));
+
+    if node.node_type == "ifElse" {
+        builder.emit(Instr::Jump(after_label));
+        builder.emit(Instr::Label(else_label));
+        lower_statement(builder, node.child(2));
+    }
+
+    builder.emit(Instr::Label(after_label));
+}
+
+// Get the variable an if-else assigns, and the values it assigns it in each block, if each block is nothing but
+// an assignment to the same local variable (like "if c { x = a; } else { x = b; }") of a value that's simple enough
+// to work out even when it isn't needed
+fn get_select<'a>(
+    builder: &IrBuilder,
+    node: NodeRef<'a>,
+) -> Option<(Temp, NodeRef<'a>, NodeRef<'a>)> {
+    if node.node_type != "ifElse" {
+        return None;
+    }
+
+    let then_assignment = get_only_assignment(node.child(1))?;
+    let else_assignment = get_only_assignment(node.child(2))?;
+    let VarLoc::Scalar(var) = builder.get_var(then_assignment.child(0)) else {
+        return None;
+    };
+    if builder.get_var(else_assignment.child(0)) != VarLoc::Scalar(var)
+        || !is_simple_expr(builder, then_assignment.child(1))
+        || !is_simple_expr(builder, else_assignment.child(1))
+    {
+        return None;
+    }
+
+    return Some((var, then_assignment.child(1), else_assignment.child(1)));
+}
+
+// Get the assignment a block consists of, if it's nothing but a single (non-compound) assignment to a variable
+fn get_only_assignment(node: NodeRef) -> Option<NodeRef> {
+    if node.node_type != "block" || node.children.len() != 1 {
+        return None;
+    }
+
+    let statement = node.child(0);
+    if statement.node_type != "=" || statement.child(0).node_type != "id" {
+        return None;
+    }
+    return Some(statement);
+}
+
+// Return true if an expression can be worked out without any branches, and without any chance of a runtime error:
+// a literal, a local variable, or an operator on them that can't divide by zero (or overflow, if we're checking for it)
+fn is_simple_expr(builder: &IrBuilder, node: NodeRef) -> bool {
+    if node.node_type == "number" || node.node_type == "true" || node.node_type == "false" {
+        return true;
+    } else if node.node_type == "id" {
+        return matches!(builder.get_var(node), VarLoc::Scalar(_));
+    } else if node.node_type == "!" {
+        return is_simple_expr(builder, node.child(0));
+    } else if node.node_type == "u-" {
+        return !builder.overflow_checks && is_simple_expr(builder, node.child(0));
+    } else if node.node_type == "cast" {
+        return is_simple_expr(builder, node.child(0));
+    } else if is_binary_operator(&node.node_type) {
+        // A chain of binary operators is checked in a loop (see get_operator_chain), where && and || need branches
+        let chain = node.get_operator_chain();
+        let can_fail = |operator: &NodeRef| match operator.node_type.as_str() {
+            "&&" | "||" => true,
+            _ => match get_binop(&operator.node_type) {
+                BinOp::Div | BinOp::Rem => true,
+                BinOp::Add | BinOp::Sub | BinOp::Mul => builder.overflow_checks,
+                _ => false,
+            },
+        };
+        return chain
+            .iter()
+            .all(|operator| !can_fail(operator) && is_simple_expr(builder, operator.child(1)))
+            && is_simple_expr(builder, chain[chain.len() - 1].child(0));
+    }
+
+    return false;
+}
+
+fn lower_while(builder: &mut IrBuilder, node: NodeRef) {
+    let test_label = builder.func.new_label();
+    let body_label = builder.func.new_label();
+    let after_label = builder.func.new_label();
+
+    // Test the condition before every iteration, including the first
+    builder.emit(Instr::Label(test_label));
+    let cond = lower_expr(builder, node.child(0));
+    builder.emit(Instr::Branch {
+        cond: cond,
+        if_true: body_label,
+        if_false: after_label,
+    });
+
+    // Any break statements in the body jump to the after label, and any continue statements go back to the test
+    builder.emit(Instr::Label(body_label));
+    builder.loops.push(LoopTargets {
+        name: node.attr.clone(),
+        end: after_label,
+        next: test_label,
+    });
+    lower_statement(builder, node.child(1));
+    builder.loops.pop();
+
+    builder.emit(Instr::Jump(test_label));
+    builder.emit(Instr::Label(after_label));
+}
+
+fn lower_loop(builder: &mut IrBuilder, node: NodeRef) {
+    let body_label = builder.func.new_label();
+    let after_label = builder.func.new_label();
+
+    // There's no condition, so the end of the body (or a continue) jumps straight back to the start of it,
+    // and only a break (or a return or exit) gets out of it
+    builder.emit(Instr::Label(body_label));
+    builder.loops.push(LoopTargets {
+        name: node.attr.clone(),
+        end: after_label,
+        next: body_label,
+    });
+    lower_statement(builder, node.child(0));
+    builder.loops.pop();
+
+    builder.emit(Instr::Jump(body_label));
+    builder.emit(Instr::Label(after_label));
+}
+
+fn lower_repeat(builder: &mut IrBuilder, node: NodeRef) {
+    let body_label = builder.func.new_label();
+    let test_label = builder.func.new_label();
+    let after_label = builder.func.new_label();
+
+    // The body always runs once before the condition is tested, which a continue skips ahead to
+    builder.emit(Instr::Label(body_label));
+    builder.loops.push(LoopTargets {
+        name: node.attr.clone(),
+        end: after_label,
+        next: test_label,
+    });
+    lower_statement(builder, node.child(0));
+    builder.loops.pop();
+
+    // Then the condition is tested after every iteration (on the line the until is on),
+    // going back to the start of the body until it's true
+    builder.emit(Instr::Label(test_label));
+    if let Some(line) = node.child(1).line_num {
+        builder.emit(Instr::Line(line));
+    }
+    let cond = lower_expr(builder, node.child(1));
+    builder.emit(Instr::Branch {
+        cond: cond,
+        if_true: after_label,
+        if_false: body_label,
+    });
+    builder.emit(Instr::Label(after_label));
+}
+
+// -----------------------------------------------------------------------------------------
+// EXPRESSIONS
+// -----------------------------------------------------------------------------------------
+
+// Lower an int or bool expression, returning the temporary holding its value
+pub fn lower_expr(builder: &mut IrBuilder, node: NodeRef) -> Temp {
+    // A chain of binary operators is lowered in a loop (see get_operator_chain), from its leftmost operand up,
+    // where the result and labels of each && and || are made before its left hand side is lowered
+    let chain = node.get_operator_chain();
+    if let Some(last) = chain.last() {
+        let targets: Vec<(Temp, Label, Label)> = chain
+            .iter()
+            .filter(|operator| operator.node_type == "&&" || operator.node_type == "||")
+            .map(|_| {
+                let dest = builder.func.new_temp(IrType::Int);
+                return (dest, builder.func.new_label(), builder.func.new_label());
+            })
+            .collect();
+        let mut targets = targets.into_iter().rev();
+
+        let mut value = lower_expr(builder, last.child(0));
+        for operator in chain.iter().rev() {
+            if operator.node_type == "&&" || operator.node_type == "||" {
+                let targets = targets.next().unwrap();
+                value = lower_short_circuit(builder, *operator, value, targets);
+            } else {
+                let rhs = lower_expr(builder, operator.child(1));
+                value = lower_binop(builder, *operator, value, rhs);
+            }
+        }
+        return value;
+    } else if is_binary(node) {
+        let lhs = lower_expr(builder, node.child(0));
+        let rhs = lower_expr(builder, node.child(1));
+        return lower_binop(builder, node, lhs, rhs);
+    } else if node.node_type == "cast" {
+        return lower_cast(builder, node);
+    } else if is_unary(node) {
+        let src = lower_expr(builder, node.child(0));
+        let dest = builder.func.new_temp(IrType::Int);
+        let op = if node.node_type == "u-" {
+            UnOp::Neg
+        } else {
+            UnOp::Not
+        };
+
+        // Negating the smallest int overflows, which a number (being no bigger than the biggest int) never is
+        if op == UnOp::Neg && builder.overflow_checks && node.child(0).node_type != "number" {
+            let zero = builder.func.new_temp(IrType::Int);
+            builder.emit(Instr::Const {
+                dest: zero,
+                value: 0,
+            });
+            lower_overflow_check(builder, node, BinOp::SubOverflows, zero, src);
+        }
+
+        builder.emit(Instr::UnOp {
+            op: op,
+            dest: dest,
+            src: src,
+        });
+        return dest;
+    } else if node.node_type == "id" {
+        match builder.get_var(node) {
+            VarLoc::Global(global) => {
+                // A global variable has to be loaded from memory every time, since any function call could change it
+                let addr = builder.func.new_temp(IrType::Ptr);
+                let dest = builder.func.new_temp(IrType::Int);
+                builder.emit(Instr::GlobalAddr {
+                    dest: addr,
+                    global: global,
+                });
+                builder.emit(Instr::Load {
+                    dest: dest,
+                    addr: addr,
+                    width: Width::Word,
+                });
+                return dest;
+            }
+            VarLoc::Scalar(temp) => return temp,
+            // Arrays aren't ints or bools, and are lowered by lower_array_ref instead
+            _ => return 0,
+        }
+    } else if node.node_type == "index" {
+        // Get the address of the array element, and then load the value stored there
+        // (a character of a string is a single byte)
+        let addr = lower_index_addr(builder, node);
+        let dest = builder.func.new_temp(IrType::Int);
+        let width = if node.child(0).get_type() == Type::String {
+            Width::Byte
+        } else {
+            Width::Word
+        };
+
+        builder.emit(Instr::Load {
+            dest: dest,
+            addr: addr,
+            width: width,
+        });
+        return dest;
+    } else if node.node_type == "funcCall" {
+        let dests = lower_func_call(builder, node);
+        return dests[0];
+    }
+
+    // Otherwise, we have a literal (a number, true or false)
+    let dest = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::Const {
+        dest: dest,
+        value: get_literal_value(node),
+    });
+    return dest;
+}
+
+// Combine two values with the binary (or compound assignment) operator of the given node
+fn lower_binop(builder: &mut IrBuilder, node: NodeRef, lhs: Temp, rhs: Temp) -> Temp {
+    let op = get_binop(&node.node_type);
+
+    // Dividing by zero is a runtime error
+    if op == BinOp::Div || op == BinOp::Rem {
+        let zero = builder.func.new_temp(IrType::Int);
+        let non_zero = builder.func.new_temp(IrType::Int);
+        builder.emit(Instr::Const {
+            dest: zero,
+            value: 0,
+        });
+        builder.emit(Instr::BinOp {
+            op: BinOp::Ne,
+            dest: non_zero,
+            lhs: rhs,
+            rhs: zero,
+        });
+        lower_check(builder, non_zero, "Division by zero", node.get_line_num());
+    }
+
+    // and so is overflowing, if we're checking for it
+    if builder.overflow_checks {
+        match op {
+            BinOp::Add => lower_overflow_check(builder, node, BinOp::AddOverflows, lhs, rhs),
+            BinOp::Sub => lower_overflow_check(builder, node, BinOp::SubOverflows, lhs, rhs),
+            BinOp::Mul => lower_overflow_check(builder, node, BinOp::MulOverflows, lhs, rhs),
+            BinOp::Div => lower_overflow_check(builder, node, BinOp::DivOverflows, lhs, rhs),
+            _ => {}
+        }
+    }
+
+    let dest = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::BinOp {
+        op: op,
+        dest: dest,
+        lhs: lhs,
+        rhs: rhs,
+    });
+    return dest;
+}
+
+// Lower a conversion to an int or a bool. A bool is already 0 or 1, so it can be used as an int as it is,
+// but an int has to be compared against zero to get a bool
+fn lower_cast(builder: &mut IrBuilder, node: NodeRef) -> Temp {
+    let src = lower_expr(builder, node.child(0));
+    if node.get_type() != Type::Bool || node.child(0).get_type() == Type::Bool {
+        return src;
+    }
+
+    let zero = builder.func.new_temp(IrType::Int);
+    let dest = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::Const {
+        dest: zero,
+        value: 0,
+    });
+    builder.emit(Instr::BinOp {
+        op: BinOp::Ne,
+        dest: dest,
+        lhs: src,
+        rhs: zero,
+    });
+    return dest;
+}
+
+// Lower a logical and/or, given the temporary holding its left hand side, along with the temporary to put its result in
+// and the labels of its right hand side and of what comes after it. The right hand side is only evaluated
+// if the left hand side doesn't decide the result
+fn lower_short_circuit(
+    builder: &mut IrBuilder,
+    node: NodeRef,
+    lhs: Temp,
+    (dest, rhs_label, after_label): (Temp, Label, Label),
+) -> Temp {
+    // The result is the left hand side if it decides the result, and the right hand side otherwise
+    builder.emit(Instr::Copy {
+        dest: dest,
+        src: lhs,
+    });
+
+    let (if_true, if_false) = if node.node_type == "&&" {
+        (rhs_label, after_label)
+    } else {
+        (after_label, rhs_label)
+    };
+    builder.emit(Instr::Branch {
+        cond: lhs,
+        if_true: if_true,
+        if_false: if_false,
+    });
+
+    builder.emit(Instr::Label(rhs_label));
+    let rhs = lower_expr(builder, node.child(1));
+    builder.emit(Instr::Copy {
+        dest: dest,
+        src: rhs,
+    });
+
+    builder.emit(Instr::Label(after_label));
+    return dest;
+}
+
+// Lower an expression of array (or string) type, returning the temporaries holding
+// (the address of its first element, its length)
+pub fn lower_array_ref(builder: &mut IrBuilder, node: NodeRef) -> (Temp, Temp) {
+    if node.node_type == "funcCall" {
+        // A function returning an array passes back both its address and its length
+        let dests = lower_func_call(builder, node);
+        return (dests[0], dests[1]);
+    }
+
+    let ptr = builder.func.new_temp(IrType::Ptr);
+    let len = builder.func.new_temp(IrType::Int);
+
+    let num_elements = if node.node_type == "index" {
+        // We have a row of a multi-dimensional array (e.g. "grid[i]"), which starts at the address of the element
+        // and whose length is the next dimension of the array
+        let row = lower_index_addr(builder, node);
+        builder.emit(Instr::Copy {
+            dest: ptr,
+            src: row,
+        });
+        get_expr_array_dims(node)[0]
+    } else if node.node_type == "string" {
+        // A string literal is stored as a global, just like a global array
+        let bytes = convert_string_literal(node).unwrap_or_default();
+        let num_bytes = bytes.len() as i32;
+        let global = builder
+            .program
+            .add_global("string", GlobalKind::String(bytes));
+
+        builder.emit(Instr::GlobalAddr {
+            dest: ptr,
+            global: global,
+        });
+        num_bytes
+    } else {
+        // Otherwise, we have an identifier, which is either an array stored in this function's stack frame,
+        // a reference to an array passed in as a parameter, or a global array
+        match builder.get_var(node) {
+            VarLoc::ArrayRef(param_ptr, param_len) => return (param_ptr, param_len),
+            VarLoc::Slot(slot) => builder.emit(Instr::SlotAddr {
+                dest: ptr,
+                slot: slot,
+            }),
+            VarLoc::Global(global) => builder.emit(Instr::GlobalAddr {
+                dest: ptr,
+                global: global,
+            }),
+            VarLoc::Scalar(_) => {} // Should never happen, indicates an error on my end
+        }
+        node.get_sym().borrow().array_dims[0]
+    };
+
+    builder.emit(Instr::Const {
+        dest: len,
+        value: num_elements,
+    });
+    return (ptr, len);
+}
+
+// Lower the address of an array element (the result of an index node), after checking the index is in bounds
+fn lower_index_addr(builder: &mut IrBuilder, node: NodeRef) -> Temp {
+    let (ptr, len) = lower_array_ref(builder, node.child(0));
+    let index = lower_expr(builder, node.child(1));
+
+    // Make sure we aren't reading or writing outside of the array
+    let in_bounds = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::BinOp {
+        op: BinOp::ULt,
+        dest: in_bounds,
+        lhs: index,
+        rhs: len,
+    });
+
+    let indexed = if node.child(0).get_type() == Type::String {
+        "String"
+    } else {
+        "Array"
+    };
+    lower_check(
+        builder,
+        in_bounds,
+        &format!("{} index out of bounds", indexed),
+        node.get_line_num(),
+    );
+
+    let dest = builder.func.new_temp(IrType::Ptr);
+    builder.emit(Instr::ElementAddr {
+        dest: dest,
+        base: ptr,
+        index: index,
+        scale: get_array_element_size(node.child(0)),
+    });
+    return dest;
+}
+
+// -----------------------------------------------------------------------------------------
+// FUNCTION CALLS
+// -----------------------------------------------------------------------------------------
+
+// Lower a function call, returning the temporaries holding whatever the function returns
+pub fn lower_func_call(builder: &mut IrBuilder, node: NodeRef) -> Vec<Temp> {
+    let func = node.get_func_name();
+    let args: Vec<NodeRef> = node.child(1).children().collect();
+
+    if func == "printf" || func == "fwrite_line" {
+        // fwrite_line prints to the file it's given, which comes before its format string
+        let format_arg = get_format_arg(node);
+        let file = if format_arg > 0 {
+            Some(lower_expr(builder, args[0].child(0)))
+        } else {
+            None
+        };
+
+        // The format string is converted into a C format string and stored as a global
+        let format = builder.program.add_global(
+            "format",
+            GlobalKind::String(convert_format_string(node).unwrap_or_default()),
+        );
+
+        let mut values = vec![];
+        for arg in &args[format_arg + 1..] {
+            values.push(lower_expr(builder, arg.child(0)));
+        }
+
+        builder.emit(Instr::Print {
+            file: file,
+            format: format,
+            args: values,
+        });
+        return vec![];
+    }
+
+    if func == "len" {
+        // The length of a string or array is always kept alongside its address, so there's nothing to call
+        let (_, len) = lower_array_ref(builder, args[0].child(0));
+        return vec![len];
+    }
+
+    if func == "parse_int" {
+        // parse_int is also told how big each character of the string or int array is, and the line it's called on
+        // (for the error it exits the program with if they aren't a number that fits in an int)
+        let text = args[0].child(0);
+        let (ptr, len) = lower_array_ref(builder, text);
+        let size = get_array_element_size(text);
+        return lower_runtime_call(
+            builder,
+            "parse_int",
+            vec![ptr, len],
+            &[size, node.get_line_num()],
+        );
+    }
+
+    if func == "to_string" {
+        // to_string is also told the line it's called on, for the error it exits the program with if the array
+        // is too short to hold every character of the number
+        let value = lower_expr(builder, args[0].child(0));
+        let (ptr, len) = lower_array_ref(builder, args[1].child(0));
+        return lower_runtime_call(
+            builder,
+            "to_string",
+            vec![value, ptr, len],
+            &[node.get_line_num()],
+        );
+    }
+
+    // Arrays are passed as the address of their first element, followed by their length,
+    // and strings (only ever passed to fopen) are passed as just the address of their first byte,
+    // since a string literal always ends with a null byte, just like a C string
+    let mut values = vec![];
+    for arg in &args {
+        if arg.child(0).get_type() == Type::String {
+            let (ptr, _) = lower_array_ref(builder, arg.child(0));
+            values.push(ptr);
+        } else if is_array_type(&arg.child(0).get_type()) {
+            let (ptr, len) = lower_array_ref(builder, arg.child(0));
+            values.push(ptr);
+            values.push(len);
+        } else {
+            values.push(lower_expr(builder, arg.child(0)));
+        }
+    }
+
+    let returns = node.get_sym().borrow().get_returns();
+    let dests = if returns == Type::Void {
+        vec![]
+    } else if is_array_type(&returns) {
+        vec![
+            builder.func.new_temp(IrType::Ptr),
+            builder.func.new_temp(IrType::Int),
+        ]
+    } else {
+        vec![builder.func.new_temp(IrType::Int)]
+    };
+
+    builder.emit(Instr::Call {
+        dests: dests.clone(),
+        func: func,
+        args: values,
+    });
+    return dests;
+}
+
+// Lower a call to a function in the runtime library that takes constants after its arguments (like the line it's called
+// on, for any error it exits the program with), giving back the temporary holding the int it returns
+fn lower_runtime_call(
+    builder: &mut IrBuilder,
+    func: &str,
+    mut args: Vec<Temp>,
+    consts: &[i32],
+) -> Vec<Temp> {
+    for value in consts {
+        let arg = builder.func.new_temp(IrType::Int);
+        builder.emit(Instr::Const {
+            dest: arg,
+            value: *value,
+        });
+        args.push(arg);
+    }
+
+    let dest = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::Call {
+        dests: vec![dest],
+        func: String::from(func),
+        args: args,
+    });
+    return vec![dest];
+}
+
+// -----------------------------------------------------------------------------------------
+// RUNTIME ERRORS
+// -----------------------------------------------------------------------------------------
+
+// Continue on if the given condition holds, and otherwise exit the program with the given error message
+fn lower_check(builder: &mut IrBuilder, cond: Temp, message: &str, line: i32) {
+    let ok_label = builder.func.new_label();
+    let error_label = builder.func.new_label();
+
+    builder.emit(Instr::Branch {
+        cond: cond,
+        if_true: ok_label,
+        if_false: error_label,
+    });
+    builder.emit(Instr::Label(error_label));
+    lower_panic(builder, message, line);
+    builder.emit(Instr::Label(ok_label));
+}
+
+// Continue on if combining two values with the given overflow check doesn't overflow,
+// and otherwise exit the program with an error message
+fn lower_overflow_check(
+    builder: &mut IrBuilder,
+    node: NodeRef,
+    check: BinOp,
+    lhs: Temp,
+    rhs: Temp,
+) {
+    let overflows = builder.func.new_temp(IrType::Int);
+    let fits = builder.func.new_temp(IrType::Int);
+    builder.emit(Instr::BinOp {
+        op: check,
+        dest: overflows,
+        lhs: lhs,
+        rhs: rhs,
+    });
+    builder.emit(Instr::UnOp {
+        op: UnOp::Not,
+        dest: fits,
+        src: overflows,
+    });
+    lower_check(builder, fits, "Integer overflow", node.get_line_num());
+}
+
+// Exit the program with the given error message, which is printed along with the line the error happened on
+// (where every error with the same message shares the same string, however many lines it can happen on)
+fn lower_panic(builder: &mut IrBuilder, message: &str, line: i32) {
+    let message = match builder.error_messages.get(message) {
+        Some(global) => *global,
+        None => {
+            let format = get_error_format(message);
+            let global = builder
+                .program
+                .add_global("error", GlobalKind::String(format.into_bytes()));
+            builder.error_messages.insert(String::from(message), global);
+            global
+        }
+    };
+    builder.emit(Instr::Panic {
+        message: message,
+        line: line,
+    });
+}
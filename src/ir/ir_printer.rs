@@ -0,0 +1,195 @@
+use std::fmt::Debug;
+
+use crate::ir::ir_cfg::Cfg;
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------------------------------
+// IR PRINTER
+// -----------------------------------------------------------------------------------------
+
+// Get a human readable dump of a lowered program, for debugging both soup programs and the optimizer.
+// If show_cfg is true, the body of each function is split up into its basic blocks, along with how they're connected
+pub fn format_program(program: &IrProgram, show_cfg: bool) -> String {
+    let mut text = String::from("");
+
+    for (i, global) in program.globals.iter().enumerate() {
+        let kind = match &global.kind {
+            GlobalKind::Word(value) => format!("word {}", value),
+            GlobalKind::Zeroed(num_bytes) => format!("zeroed {}", num_bytes),
+            GlobalKind::String(bytes) => {
+                format!(
+                    "string \"{}\"",
+                    String::from_utf8_lossy(bytes).escape_debug()
+                )
+            }
+        };
+        text.push_str(&format!("@{} {} = {}\n", i, global.name, kind));
+    }
+
+    for func in &program.functions {
+        text.push('\n');
+        text.push_str(&format_function(func, show_cfg));
+    }
+
+    return text;
+}
+
+// Get a human readable dump of a single function
+pub fn format_function(func: &IrFunction, show_cfg: bool) -> String {
+    let params: Vec<String> = func
+        .params
+        .iter()
+        .map(|param| {
+            format!(
+                "{}: {}",
+                get_temp_name(func, *param),
+                get_type_name(func.temps[*param])
+            )
+        })
+        .collect();
+    let returns: Vec<&str> = func.returns.iter().map(|ret| get_type_name(*ret)).collect();
+    let returns = if returns.is_empty() {
+        String::from("void")
+    } else {
+        returns.join(", ")
+    };
+
+    let mut text = format!("func {}({}) -> {}\n", func.name, params.join(", "), returns);
+
+    if !show_cfg {
+        for instr in &func.body {
+            text.push_str(&format_instr(func, instr));
+        }
+        return text;
+    }
+
+    let cfg = Cfg::new(func);
+    let reachable = cfg.reverse_postorder();
+
+    for (i, block) in cfg.blocks.iter().enumerate() {
+        let label = match block.label {
+            Some(label) => format!(" (L{})", label),
+            None => String::from(""),
+        };
+        let unreachable = if reachable.contains(&i) {
+            ""
+        } else {
+            ", unreachable"
+        };
+        text.push_str(&format!(
+            "  block {}{}: preds {:?}, succs {:?}{}\n",
+            i, label, block.preds, block.succs, unreachable
+        ));
+
+        for instr in &block.instrs {
+            text.push_str(&format_instr(func, instr));
+        }
+    }
+
+    return text;
+}
+
+// Get a single instruction as a line of the dump
+fn format_instr(func: &IrFunction, instr: &Instr) -> String {
+    let temp = |temp: &Temp| get_temp_name(func, *temp);
+    let temps = |temps: &Vec<Temp>| temps.iter().map(temp).collect::<Vec<String>>().join(", ");
+
+    let text = match instr {
+        Instr::Const { dest, value } => format!("{} = const {}", temp(dest), value),
+        Instr::Copy { dest, src } => format!("{} = copy {}", temp(dest), temp(src)),
+        Instr::BinOp { op, dest, lhs, rhs } => format!(
+            "{} = {} {}, {}",
+            temp(dest),
+            get_op_name(op),
+            temp(lhs),
+            temp(rhs)
+        ),
+        Instr::UnOp { op, dest, src } => {
+            format!("{} = {} {}", temp(dest), get_op_name(op), temp(src))
+        }
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => format!(
+            "{} = select {}, {}, {}",
+            temp(dest),
+            temp(cond),
+            temp(if_true),
+            temp(if_false)
+        ),
+        Instr::SlotAddr { dest, slot } => format!("{} = slot_addr ${}", temp(dest), slot),
+        Instr::GlobalAddr { dest, global } => format!("{} = global_addr @{}", temp(dest), global),
+        Instr::ElementAddr {
+            dest,
+            base,
+            index,
+            scale,
+        } => format!(
+            "{} = element_addr {}, {}, {}",
+            temp(dest),
+            temp(base),
+            temp(index),
+            scale
+        ),
+        Instr::Load { dest, addr, width } => format!(
+            "{} = load.{} {}",
+            temp(dest),
+            get_op_name(width),
+            temp(addr)
+        ),
+        Instr::Store { src, addr, width } => {
+            format!("store.{} {}, {}", get_op_name(width), temp(src), temp(addr))
+        }
+        Instr::Call { dests, func, args } if dests.is_empty() => {
+            format!("call {}({})", func, temps(args))
+        }
+        Instr::Call { dests, func, args } => {
+            format!("{} = call {}({})", temps(dests), func, temps(args))
+        }
+        Instr::Print {
+            file: None,
+            format,
+            args,
+        } => format!("print @{}({})", format, temps(args)),
+        Instr::Print {
+            file: Some(file),
+            format,
+            args,
+        } => format!("print {}, @{}({})", temp(file), format, temps(args)),
+        // Labels stand out from the instructions around them
+        Instr::Label(label) => return format!("L{}:\n", label),
+        Instr::Jump(label) => format!("jump L{}", label),
+        Instr::Branch {
+            cond,
+            if_true,
+            if_false,
+        } => format!("branch {}, L{}, L{}", temp(cond), if_true, if_false),
+        Instr::Return(values) => format!("return {}", temps(values)).trim_end().to_string(),
+        Instr::Panic { message, line } => format!("panic @{}, line {}", message, line),
+        Instr::Line(line) => format!("// line {}", line),
+    };
+
+    return format!("    {}\n", text);
+}
+
+// Get the name of a temporary, which includes the name of the variable it holds (if it holds one)
+fn get_temp_name(func: &IrFunction, temp: Temp) -> String {
+    return match func.temp_names.get(&temp) {
+        None => format!("%{}", temp),
+        Some(name) => format!("%{}.{}", name, temp),
+    };
+}
+
+fn get_type_name(temp_type: IrType) -> &'static str {
+    return match temp_type {
+        IrType::Int => "int",
+        IrType::Ptr => "ptr",
+    };
+}
+
+// Get the name of an operator (or the width of a load or store), like "add" for BinOp::Add
+fn get_op_name<T: Debug>(op: T) -> String {
+    return format!("{:?}", op).to_lowercase();
+}
@@ -0,0 +1,83 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file instruments a program to trace its execution (--trace-exec): every statement prints the file and line
+// it's on, along with the text of the line, just before it runs, so the path a program takes can be followed
+// without a debugger:
+//
+// [trace] main.soup:4: int x = 0;
+// [trace] main.soup:5: while x < 10 {
+// [trace] main.soup:6: x = x + 1;
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+
+use crate::ir::ir_data::*;
+
+// The start of every line a traced program prints
+pub const TRACE_PREFIX: &str = "[trace]";
+
+// Instrument a program to trace every statement it runs.
+// Like coverage, this has to be done once each function's source file is known (see set_source_files),
+// but before optimizing, so every statement is still traced in the order it's written
+pub fn instrument_trace(program: &mut IrProgram) {
+    // The text of each line is only included if the file it's from can still be read
+    let sources: Vec<Option<String>> = program
+        .files
+        .iter()
+        .map(|file| fs::read_to_string(file).ok())
+        .collect();
+
+    // Each line only needs one message, no matter how many statements start on it
+    let mut messages: HashMap<(usize, i32), usize> = HashMap::new();
+
+    for func in &mut program.functions {
+        let body = mem::take(&mut func.body);
+
+        for instr in body {
+            let line = match instr {
+                Instr::Line(line) => line,
+                _ => {
+                    func.body.push(instr);
+                    continue;
+                }
+            };
+            func.body.push(instr);
+
+            let message = *messages.entry((func.file, line)).or_insert_with(|| {
+                let file = program.files.get(func.file).map(|file| file.as_str());
+                let source = sources.get(func.file).and_then(|source| source.as_deref());
+                let message = format_trace_message(file.unwrap_or("<unknown>"), line, source);
+
+                // (the program itself is already borrowed for its functions, so the global is added by hand)
+                program.globals.push(IrGlobal {
+                    name: String::from("trace"),
+                    kind: GlobalKind::String(message.into_bytes()),
+                });
+                return program.globals.len() - 1;
+            });
+            func.body.push(Instr::Print {
+                file: None,
+                format: message,
+                args: vec![],
+            });
+        }
+    }
+}
+
+// Format the message traced for the given line of the given file, as a format string (with every "%" doubled),
+// including the text of the line (without its indentation) if the source of the file is known
+pub fn format_trace_message(file: &str, line: i32, source: Option<&str>) -> String {
+    let text = source
+        .zip((line as usize).checked_sub(1))
+        .and_then(|(source, index)| source.lines().nth(index))
+        .map(|text| text.trim())
+        .unwrap_or("");
+
+    let message = if text.is_empty() {
+        format!("{} {}:{}", TRACE_PREFIX, file, line)
+    } else {
+        format!("{} {}:{}: {}", TRACE_PREFIX, file, line, text)
+    };
+    return format!("{}\n", message.replace('%', "%%"));
+}
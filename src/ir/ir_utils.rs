@@ -0,0 +1,343 @@
+use crate::ir::ir_data::BinOp;
+use crate::parser::parser_data::NodeRef;
+use crate::semantic::semantic_data::Type;
+
+// The error messages parse_int exits the program with, when it isn't given a number or the number doesn't fit in an int
+pub const PARSE_INT_INVALID: &str = "parse_int was given something that isn't a number";
+pub const PARSE_INT_TOO_LARGE: &str = "Number given to parse_int is too large to fit in an int";
+
+// The error message to_string exits the program with, when the array it's given can't hold every character of the number
+pub const TO_STRING_TOO_SHORT: &str = "Array given to to_string is too short to hold the number";
+
+// Get the format string a runtime error is printed with, which is given the line the error happened on
+pub fn get_error_format(message: &str) -> String {
+    return format!("Error: Line %d: {}\n", message);
+}
+
+// -----------------------------------------------------------------------------------------
+// EXPRESSION HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the IR operator for a binary (or compound assignment) operator node, such as BinOp::Add for "+" or "+="
+pub fn get_binop(node_type: &str) -> BinOp {
+    return match node_type {
+        "+" | "+=" => BinOp::Add,
+        "-" | "-=" => BinOp::Sub,
+        "*" | "*=" => BinOp::Mul,
+        "/" | "/=" => BinOp::Div,
+        "%" | "%=" => BinOp::Rem,
+        "==" => BinOp::Eq,
+        "!=" => BinOp::Ne,
+        "<" => BinOp::Lt,
+        ">" => BinOp::Gt,
+        "<=" => BinOp::Le,
+        ">=" => BinOp::Ge,
+        _ => BinOp::Add, // Should never happen, indicates an error on my end
+    };
+}
+
+// Get the value of a literal (a number, true or false)
+pub fn get_literal_value(node: NodeRef) -> i32 {
+    if node.node_type == "true" {
+        return 1;
+    } else if node.node_type == "false" {
+        return 0;
+    }
+
+    // A number too large to fit in an int wraps around (which makes "-2147483648" come out right)
+    return node.get_attr().parse::<i64>().unwrap_or(0) as i32;
+}
+
+// -----------------------------------------------------------------------------------------
+// ARRAY HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the compile-time dimensions of an array expression (e.g. [20] for "grid[i]", given "int grid[10][20]"),
+// which is empty if the dimensions are only known at runtime (array parameters and arrays returned from functions)
+pub fn get_expr_array_dims(node: NodeRef) -> Vec<i32> {
+    if node.node_type == "id" {
+        return node.get_sym().borrow().array_dims.clone();
+    } else if node.node_type == "index" {
+        // Indexing into an array strips off its outermost dimension
+        let dims = get_expr_array_dims(node.child(0));
+
+        if dims.len() > 1 {
+            return dims[1..].to_vec();
+        }
+    }
+
+    return vec![];
+}
+
+// Get the number of bytes taken up by each element of an array expression
+// (e.g. 80 for "grid", given "int grid[10][20]", since each element is a row of 20 ints)
+pub fn get_array_element_size(node: NodeRef) -> i32 {
+    // Each character of a string is a single byte
+    if node.get_type() == Type::String {
+        return 1;
+    }
+
+    let dims = get_expr_array_dims(node);
+
+    if dims.len() > 1 {
+        return dims[1..].iter().product::<i32>() * 4;
+    }
+
+    return 4;
+}
+
+// -----------------------------------------------------------------------------------------
+// STRING HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Every string in a program is checked by the semantic checker (see check_strings), so once a program has been checked,
+// converting any of its strings can't fail
+
+// Convert a string literal into the bytes it stands for, replacing each escape sequence with the character it represents
+pub fn convert_string_literal(node: NodeRef) -> Result<Vec<u8>, String> {
+    let literal = node.get_attr();
+    let mut bytes = vec![];
+
+    let mut chars = literal.chars();
+    while let Some(char) = chars.next() {
+        if char == '\\' {
+            // Braces only need escaping in format strings, so they can't be escaped anywhere else
+            let next_char = chars.next().unwrap_or(' ');
+            match get_escaped_char(next_char) {
+                Some(escaped) if escaped != '{' && escaped != '}' => bytes.push(escaped as u8),
+                _ => {
+                    return Err(format!(
+                        "{}: Invalid escape character '{}'",
+                        node.get_location(),
+                        next_char
+                    ))
+                }
+            }
+        } else {
+            push_char(&mut bytes, char);
+        }
+    }
+
+    return Ok(bytes);
+}
+
+// Get the position of the format string among the arguments of a call to printf or fwrite_line
+// (which is passed the file it writes to first)
+pub fn get_format_arg(node: NodeRef) -> usize {
+    return if node.get_func_name() == "fwrite_line" {
+        1
+    } else {
+        0
+    };
+}
+
+// Convert the format string passed into a printf (or fwrite_line) call into the bytes of a C format string,
+// replacing each escape sequence with the character it represents and each formatter with "%d",
+// after checking that the formatters match up with the format arguments passed in
+pub fn convert_format_string(node: NodeRef) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+
+    for (i, piece) in split_format_string(node)?.iter().enumerate() {
+        if i > 0 {
+            bytes.extend_from_slice(b"%d");
+        }
+
+        // A percent sign is only ever printed as it is, so it's doubled up to keep printf from taking it as a formatter
+        for byte in piece {
+            if *byte == b'%' {
+                bytes.push(b'%');
+            }
+            bytes.push(*byte);
+        }
+    }
+
+    return Ok(bytes);
+}
+
+// Split the format string passed into a printf (or fwrite_line) call into the bytes of the text around each of its
+// formatters (so there's one more piece than there are formatters), replacing each escape sequence with the character
+// it represents, after checking that the formatters match up with the format arguments passed in.
+// fwrite_line writes a whole line, so its last piece ends with a newline
+pub fn split_format_string(node: NodeRef) -> Result<Vec<Vec<u8>>, String> {
+    let func = node.get_func_name();
+    let format_arg = get_format_arg(node);
+    let fstring = node.child(1).child(format_arg).child(0).get_attr();
+    let num_args = node.child(1).children.len() - format_arg - 1;
+    let mut num_formatters = 0;
+    let mut pieces = vec![];
+    let mut bytes = vec![];
+
+    let mut chars = fstring.chars().peekable();
+    while let Some(char) = chars.next() {
+        // If we find a backslash, the next character is escaped
+        if char == '\\' {
+            let next_char = chars.next().unwrap_or(' ');
+            match get_escaped_char(next_char) {
+                Some(escaped) => bytes.push(escaped as u8),
+                None => {
+                    return Err(format!(
+                        "{}: Invalid escape character '{}'",
+                        node.get_location(),
+                        next_char
+                    ))
+                }
+            }
+        } else if char == '{' {
+            // We are probably seeing the beginning of a formatter
+            if chars.next_if_eq(&'}').is_none() {
+                return Err(format!("{}: Invalid formatter, opening {{ without a closing }}, did you mean \"\\{{\"?",
+                                          node.get_location()));
+            }
+
+            num_formatters += 1;
+
+            if num_formatters == 6 {
+                return Err(format!(
+                    "{}: {} only accepts 5 format arguments",
+                    node.get_location(),
+                    func
+                ));
+            }
+
+            // Check to see if there are enough arguments passed in to match the current amount of formatters,
+            // and that the value being passed into this formatter is one we know how to print
+            if num_args < num_formatters {
+                return Err(format!(
+                    "{}: {} formatter(s) given to {}, but only {} format argument(s) passed in",
+                    node.get_location(),
+                    num_formatters,
+                    func,
+                    num_args
+                ));
+            } else {
+                let value = node.child(1).child(format_arg + num_formatters).child(0);
+
+                if value.get_type() != Type::Int {
+                    return Err(format!(
+                        "{}: Invalid format type '{}' passed into {}, must only be int",
+                        node.get_location(),
+                        value.get_type(),
+                        func
+                    ));
+                }
+            }
+
+            pieces.push(std::mem::take(&mut bytes));
+        } else if char == '}' {
+            return Err(format!(
+                "{}: Invalid formatter, closing }} without an opening {{, did you mean \"\\}}\"?",
+                node.get_location()
+            ));
+        } else {
+            push_char(&mut bytes, char);
+        }
+    }
+
+    // Check if too many format arguments were passed into printf
+    if num_args != num_formatters {
+        return Err(format!(
+            "{}: {} format argument(s) passed into to {}, but only {} formatter(s) given",
+            node.get_location(),
+            num_args,
+            func,
+            num_formatters
+        ));
+    }
+
+    if func == "fwrite_line" {
+        bytes.push(b'\n');
+    }
+    pieces.push(bytes);
+    return Ok(pieces);
+}
+
+// Check every string literal and printf (or fwrite_line) format string in part of a program, which can only be done
+// once the rest of it has been semantically checked (since a format argument has to be an int)
+pub fn check_strings(node: NodeRef) -> Result<(), String> {
+    // The nodes still to be checked are kept on a stack, rather than recursing
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if node.node_type == "funcCall"
+            && matches!(&*node.get_func_name(), "printf" | "fwrite_line")
+        {
+            // A format string has its own escape sequences, so it isn't checked like any other string literal
+            split_format_string(node)?;
+            let format_arg = get_format_arg(node);
+            for (i, arg) in node.child(1).children().enumerate().rev() {
+                if i != format_arg {
+                    stack.push(arg);
+                }
+            }
+            continue;
+        }
+
+        if node.node_type == "string" {
+            convert_string_literal(node)?;
+        }
+        stack.extend(node.children().rev());
+    }
+    return Ok(());
+}
+
+// Get the character represented by an escape sequence (the backslash followed by the given character),
+// or None if it isn't a valid escape sequence
+fn get_escaped_char(char: char) -> Option<char> {
+    return match char {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        '\\' => Some('\\'),
+        '{' => Some('{'),
+        '}' => Some('}'),
+        _ => None,
+    };
+}
+
+// Add the UTF-8 encoding of a character to a string of bytes
+fn push_char(bytes: &mut Vec<u8>, char: char) {
+    let mut buffer = [0; 4];
+    bytes.extend_from_slice(char.encode_utf8(&mut buffer).as_bytes());
+}
+
+// -----------------------------------------------------------------------------------------
+// NUMBER HELPERS
+// -----------------------------------------------------------------------------------------
+
+// Get the number written in the characters given to parse_int (an optional minus sign followed by at least one digit),
+// which end at the first 0, or the error message parse_int exits the program with if they aren't a number that fits in
+// an int. The interpreter parses numbers with this, and the runtime library does exactly the same thing on every target
+pub fn parse_int_chars(chars: &[i32]) -> Result<i32, &'static str> {
+    let len = chars
+        .iter()
+        .position(|char| *char == 0)
+        .unwrap_or(chars.len());
+    let (negative, digits) = match chars[..len].split_first() {
+        Some((sign, digits)) if *sign == '-' as i32 => (true, digits),
+        _ => (false, &chars[..len]),
+    };
+    if digits.is_empty() {
+        return Err(PARSE_INT_INVALID);
+    }
+
+    // The number is built up as a negative number, since the smallest int is one further from zero than the largest
+    let mut value: i32 = 0;
+    for char in digits {
+        if !('0' as i32..='9' as i32).contains(char) {
+            return Err(PARSE_INT_INVALID);
+        }
+        value = match value
+            .checked_mul(10)
+            .and_then(|value| value.checked_sub(char - '0' as i32))
+        {
+            Some(value) => value,
+            None => return Err(PARSE_INT_TOO_LARGE),
+        };
+    }
+
+    if negative {
+        return Ok(value);
+    }
+    return value.checked_neg().ok_or(PARSE_INT_TOO_LARGE);
+}
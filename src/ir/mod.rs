@@ -0,0 +1,14 @@
+pub mod ir_cfg;
+pub mod ir_constants;
+pub mod ir_coverage;
+pub mod ir_data;
+pub mod ir_driver;
+pub mod ir_liveness;
+pub mod ir_lowering;
+pub mod ir_printer;
+pub mod ir_trace;
+pub mod ir_utils;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,734 @@
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::ir::ir_cfg::Cfg;
+    use crate::ir::ir_constants::*;
+    use crate::ir::ir_coverage::*;
+    use crate::ir::ir_data::*;
+    use crate::ir::ir_driver::lower_program;
+    use crate::ir::ir_liveness::*;
+    use crate::ir::ir_printer::format_program;
+    use crate::ir::ir_trace::*;
+    use crate::ir::ir_utils::*;
+    use crate::parser::parser_driver::{parse_expression, parser};
+    use crate::scanner::scanner_driver::{scan_str, scanner};
+    use crate::semantic::semantic_data::Warnings;
+    use crate::semantic::semantic_driver::semantic_checker;
+
+    // Scan, parse, check and lower the given source code (written to a file named after the test, since the scanner reads files)
+    fn lower_source(name: &str, source: &str) -> IrProgram {
+        return lower_source_with_checks(name, source, false);
+    }
+
+    fn lower_source_with_checks(name: &str, source: &str, overflow_checks: bool) -> IrProgram {
+        return lower_source_with_options(name, source, overflow_checks, false);
+    }
+
+    fn lower_source_with_options(
+        name: &str,
+        source: &str,
+        overflow_checks: bool,
+        zero_init: bool,
+    ) -> IrProgram {
+        let file = env::temp_dir().join(format!("soup-ir-{}-{}.soup", name, std::process::id()));
+        fs::write(&file, source).unwrap();
+
+        let tokens = scanner(&file.to_string_lossy());
+        let _ = fs::remove_file(&file);
+
+        let mut ast = parser(&tokens);
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        return lower_program(&ast, overflow_checks, zero_init);
+    }
+
+    #[test]
+    fn test_lower_globals() {
+        let program = lower_source(
+            "globals",
+            "int x = 5;\nbool b = true;\nint grid[2][3];\nint size = -4 * (1 + 1023);\nfunc main() returns void {;}\n",
+        );
+
+        assert_eq!(GlobalKind::Word(5), program.globals[0].kind);
+        assert_eq!(GlobalKind::Word(1), program.globals[1].kind);
+        assert_eq!(GlobalKind::Zeroed(24), program.globals[2].kind);
+        assert_eq!("grid", program.globals[2].name);
+        assert_eq!(GlobalKind::Word(-4096), program.globals[3].kind);
+    }
+
+    #[test]
+    fn test_evaluate_binop() {
+        assert_eq!(Ok(i32::MIN), evaluate_binop(BinOp::Add, i32::MAX, 1, false));
+        assert_eq!(
+            Err(EvalError::Overflow),
+            evaluate_binop(BinOp::Add, i32::MAX, 1, true)
+        );
+        assert_eq!(Ok(-3), evaluate_binop(BinOp::Div, -7, 2, false));
+        assert_eq!(Ok(-1), evaluate_binop(BinOp::Rem, -7, 2, false));
+        assert_eq!(
+            Ok(i32::MIN),
+            evaluate_binop(BinOp::Div, i32::MIN, -1, false)
+        );
+        assert_eq!(
+            Err(EvalError::DivisionByZero),
+            evaluate_binop(BinOp::Div, 1, 0, false)
+        );
+        assert_eq!(Ok(0), evaluate_binop(BinOp::ULt, -1, 5, false));
+        assert_eq!(
+            Ok(1),
+            evaluate_binop(BinOp::AddOverflows, i32::MAX, 1, false)
+        );
+        assert_eq!(
+            Ok(0),
+            evaluate_binop(BinOp::SubOverflows, -1, i32::MAX, false)
+        );
+        assert_eq!(
+            Ok(1),
+            evaluate_binop(BinOp::MulOverflows, 65536, 32768, true)
+        );
+        assert_eq!(
+            Err(EvalError::Overflow),
+            evaluate_unop(UnOp::Neg, i32::MIN, true)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_remainder() {
+        // The remainder takes the sign of lhs, whatever the sign of rhs
+        for (lhs, rhs, quotient, remainder) in [
+            (7, 3, 2, 1),
+            (-7, 3, -2, -1),
+            (7, -3, -2, 1),
+            (-7, -3, 2, -1),
+            (-6, 3, -2, 0),
+            (i32::MIN, -1, i32::MIN, 0),
+        ] {
+            assert_eq!(Ok(quotient), evaluate_binop(BinOp::Div, lhs, rhs, false));
+            assert_eq!(Ok(remainder), evaluate_binop(BinOp::Rem, lhs, rhs, false));
+        }
+        assert_eq!(
+            Err(EvalError::DivisionByZero),
+            evaluate_binop(BinOp::Rem, -7, 0, false)
+        );
+
+        // Only the quotient of the smallest int divided by -1 overflows, which is an error if we're checking for it
+        assert_eq!(
+            Err(EvalError::Overflow),
+            evaluate_binop(BinOp::Div, i32::MIN, -1, true)
+        );
+        assert_eq!(Ok(0), evaluate_binop(BinOp::Rem, i32::MIN, -1, true));
+        assert_eq!(
+            Ok(1),
+            evaluate_binop(BinOp::DivOverflows, i32::MIN, -1, false)
+        );
+        assert_eq!(
+            Ok(0),
+            evaluate_binop(BinOp::DivOverflows, i32::MIN, 1, false)
+        );
+        assert_eq!(
+            Ok(0),
+            evaluate_binop(BinOp::DivOverflows, i32::MAX, -1, false)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_constant() {
+        let value = |source: &str, overflow_checks: bool| {
+            let ast = parse_expression(&scan_str(source).unwrap()).unwrap();
+            evaluate_constant(ast.get_root(), overflow_checks)
+        };
+
+        assert_eq!(Ok(7), value("1 + 2 * 3", true));
+        assert_eq!(Ok(i32::MIN), value("-2147483648", true));
+        assert_eq!(Ok(i32::MIN), value("2147483647 + 1", false));
+        assert_eq!(Err(EvalError::Overflow), value("2147483647 + 1", true));
+        assert_eq!(Err(EvalError::Overflow), value("-(-2147483648)", true));
+        assert_eq!(Ok(1), value("bool(-3) && int(true) == 1", true));
+        assert_eq!(Ok(0), value("int(bool(0))", true));
+        assert_eq!(Err(EvalError::DivisionByZero), value("1 / (2 - 2)", false));
+        assert_eq!(Err(EvalError::NotConstant), value("x + 1", true));
+
+        // Either side of || can decide the result, even if the other side can't be worked out
+        assert_eq!(Ok(1), value("1 / 0 == 0 || true", true));
+        assert_eq!(
+            Err(EvalError::DivisionByZero),
+            value("1 / 0 == 0 || false", true)
+        );
+    }
+
+    #[test]
+    fn test_lower_local_variables() {
+        // int x = 2; x += 3; return x;
+        let program = lower_source(
+            "locals",
+            "func main() returns int {\nint x = 2;\nx += 3;\nreturn x;\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        let expected = vec![
+            Instr::Line(2),
+            Instr::Const { dest: 1, value: 2 },
+            Instr::Copy { dest: 0, src: 1 },
+            Instr::Line(3),
+            Instr::Const { dest: 2, value: 3 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 3,
+                lhs: 0,
+                rhs: 2,
+            },
+            Instr::Copy { dest: 0, src: 3 },
+            Instr::Line(4),
+            Instr::Return(vec![0]),
+        ];
+
+        assert_eq!(expected, main.body[..9].to_vec());
+        assert_eq!(vec![IrType::Int], main.returns);
+    }
+
+    #[test]
+    fn test_lower_short_circuit() {
+        // The right hand side of && is only evaluated if the left hand side is true
+        let program = lower_source(
+            "short_circuit",
+            "func f() returns bool {\nreturn true;\n}\nfunc main() returns void {\nbool b = false && f();\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        let branch = main
+            .body
+            .iter()
+            .position(|instr| matches!(instr, Instr::Branch { .. }))
+            .unwrap();
+        let call = main
+            .body
+            .iter()
+            .position(|instr| matches!(instr, Instr::Call { .. }))
+            .unwrap();
+
+        assert!(branch < call);
+    }
+
+    #[test]
+    fn test_lower_division_check() {
+        let program = lower_source(
+            "division",
+            "func main() returns void {\nint x = 1;\nint y = 0;\nx = x / y;\ny = y % x;\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        // Division by zero panics before the division happens
+        let panic = main
+            .body
+            .iter()
+            .position(|instr| matches!(instr, Instr::Panic { .. }))
+            .unwrap();
+        let div = main
+            .body
+            .iter()
+            .position(|instr| matches!(instr, Instr::BinOp { op: BinOp::Div, .. }))
+            .unwrap();
+
+        assert!(panic < div);
+        match &main.body[panic] {
+            Instr::Panic { message, line } => {
+                assert_eq!(
+                    GlobalKind::String(b"Error: Line %d: Division by zero\n".to_vec()),
+                    program.globals[*message].kind
+                );
+                assert_eq!(4, *line);
+            }
+            _ => unreachable!(),
+        }
+
+        // Both divisions share the same error message, each with its own line
+        let panics: Vec<(usize, i32)> = main
+            .body
+            .iter()
+            .filter_map(|instr| match instr {
+                Instr::Panic { message, line } => Some((*message, *line)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(2, panics.len());
+        assert_eq!(panics[0].0, panics[1].0);
+        assert_eq!(5, panics[1].1);
+    }
+
+    #[test]
+    fn test_lower_overflow_checks() {
+        let source =
+            "func main() returns int {\nint x = 1;\nx = -x * 2;\nx += 3;\nx = x / (x % 2);\nreturn x;\n}\n";
+        let overflow_ops = |program: &IrProgram| -> Vec<BinOp> {
+            let main = program.get_function("main").unwrap();
+            return main
+                .body
+                .iter()
+                .filter_map(|instr| match instr {
+                    Instr::BinOp { op, .. } => Some(*op),
+                    _ => None,
+                })
+                .filter(|op| {
+                    matches!(
+                        op,
+                        BinOp::AddOverflows
+                            | BinOp::SubOverflows
+                            | BinOp::MulOverflows
+                            | BinOp::DivOverflows
+                    )
+                })
+                .collect();
+        };
+
+        // Arithmetic is only checked when asked for, where negation is checked as a subtraction from 0
+        // (and a remainder can never overflow)
+        assert!(overflow_ops(&lower_source("unchecked", source)).is_empty());
+        let program = lower_source_with_checks("checked", source, true);
+        assert_eq!(
+            vec![
+                BinOp::SubOverflows,
+                BinOp::MulOverflows,
+                BinOp::AddOverflows,
+                BinOp::DivOverflows
+            ],
+            overflow_ops(&program)
+        );
+        assert!(program.globals.iter().any(|global| global.kind
+            == GlobalKind::String(b"Error: Line %d: Integer overflow\n".to_vec())));
+    }
+
+    #[test]
+    fn test_lower_parse_int() {
+        let program = lower_source(
+            "parse_int",
+            "func main() returns int {\nint digits[3];\nreturn parse_int(\"12\") + parse_int(digits);\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        // Each one is a single call to the runtime library, which is told how big each character is
+        // (a byte for the string and an int for the array) and the line it was called on
+        let get_const = |temp| {
+            main.body.iter().find_map(|instr| match instr {
+                Instr::Const { dest, value } if *dest == temp => Some(*value),
+                _ => None,
+            })
+        };
+        let calls: Vec<&Vec<Temp>> = main
+            .body
+            .iter()
+            .filter_map(|instr| match instr {
+                Instr::Call { func, args, .. } if func == "parse_int" => Some(args),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(2, calls.len());
+        for (args, size) in calls.iter().zip([1, 4]) {
+            assert_eq!(4, args.len());
+            assert_eq!(Some(size), get_const(args[2]));
+            assert_eq!(Some(3), get_const(args[3]));
+        }
+        assert!(!main
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Load { .. })));
+
+        // Which the interpreter does in just the same way
+        let chars = |text: &str| -> Vec<i32> { text.bytes().map(|byte| byte as i32).collect() };
+        assert_eq!(Ok(42), parse_int_chars(&chars("42\0x")));
+        assert_eq!(Ok(-2147483648), parse_int_chars(&chars("-2147483648")));
+        assert_eq!(
+            Err(PARSE_INT_TOO_LARGE),
+            parse_int_chars(&chars("2147483648"))
+        );
+        assert_eq!(Err(PARSE_INT_INVALID), parse_int_chars(&chars("-")));
+        assert_eq!(Err(PARSE_INT_INVALID), parse_int_chars(&chars("+1")));
+        assert_eq!(Err(PARSE_INT_INVALID), parse_int_chars(&[]));
+    }
+
+    #[test]
+    fn test_lower_to_string() {
+        let program = lower_source(
+            "to_string",
+            "func main() returns int {\nint chars[11];\nreturn to_string(-12, chars);\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        // It's a single call to the runtime library, which is told the line it was called on
+        let args = main
+            .body
+            .iter()
+            .find_map(|instr| match instr {
+                Instr::Call { func, args, .. } if func == "to_string" => Some(args),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(4, args.len());
+        assert!(main.body.contains(&Instr::Const {
+            dest: args[3],
+            value: 3
+        }));
+        assert!(!main
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Store { .. })));
+        assert!(program.globals.iter().all(|global| global.kind
+            != GlobalKind::String(get_error_format(TO_STRING_TOO_SHORT).into_bytes())));
+    }
+
+    #[test]
+    fn test_lower_array_param() {
+        // An array parameter is passed as its address followed by its length
+        let program = lower_source(
+            "array_param",
+            "func first(int[] a) returns int {\nreturn a[0];\n}\nfunc main() returns void {\nint a[3];\nfirst(a);\n}\n",
+        );
+        let first = program.get_function("first").unwrap();
+
+        assert_eq!(vec![0, 1], first.params);
+        assert_eq!(IrType::Ptr, first.temps[0]);
+        assert_eq!(IrType::Int, first.temps[1]);
+
+        let main = program.get_function("main").unwrap();
+        assert_eq!(vec![12], main.slots);
+        assert!(main.body.iter().any(|instr| matches!(
+            instr,
+            Instr::Call { func, args, .. } if func == "first" && args.len() == 2
+        )));
+    }
+
+    #[test]
+    fn test_lower_nested_calls() {
+        let program = lower_source(
+            "nested_calls",
+            "func return1() returns int {\nreturn 1;\n}\nfunc add(int a, int b) returns int {\nreturn a + b;\n}\n\
+             func main() returns int {\nreturn add(add(return1(), 2), return1());\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+
+        // Every argument is worked out (including any calls it makes) and kept in its own temporary
+        // before the call it's passed to, so nothing is left in an argument passing register while another call is made
+        let calls: Vec<(&String, &Vec<Temp>, &Vec<Temp>)> = main
+            .body
+            .iter()
+            .filter_map(|instr| match instr {
+                Instr::Call { dests, func, args } => Some((func, dests, args)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(4, calls.len());
+
+        let (outer, _, outer_args) = calls[3];
+        assert_eq!("add", outer);
+        assert_eq!(calls[1].1[0], outer_args[0]);
+        assert_eq!(calls[2].1[0], outer_args[1]);
+    }
+
+    #[test]
+    fn test_lower_select() {
+        let count_selects = |func: &IrFunction| {
+            func.body
+                .iter()
+                .filter(|instr| matches!(instr, Instr::Select { .. }))
+                .count()
+        };
+
+        // An if-else that only assigns one of two values to the same variable doesn't need to branch
+        let program = lower_source(
+            "select",
+            "func main() returns int {\nint x = 1;\nint y;\nif (x > 0) {\ny = x * 2;\n} else {\ny = 3;\n}\nreturn y;\n}\n",
+        );
+        let main = program.get_function("main").unwrap();
+        assert_eq!(1, count_selects(main));
+        assert!(!main
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Branch { .. })));
+
+        // but it does if either value could cause a runtime error (or do anything else) when it isn't needed
+        for value in ["x / 2", "x + 1", "f()"] {
+            let program = lower_source_with_checks(
+                "no_select",
+                &format!(
+                    "func f() returns int {{\nreturn 1;\n}}\nfunc main() returns int {{\nint x = 1;\nint y;\n\
+                     if (x > 0) {{\ny = {};\n}} else {{\ny = 3;\n}}\nreturn y;\n}}\n",
+                    value
+                ),
+                true,
+            );
+            assert_eq!(0, count_selects(program.get_function("main").unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_lower_zero_init() {
+        let source =
+            "func main() returns void {\nint x;\nint a[3];\nprintf(\"{} {}\\n\", x, a[1]);\n}\n";
+
+        // Without --zero-init, neither the variable nor the array is given a value
+        let program = lower_source("no-zero-init", source);
+        let main = program.get_function("main").unwrap();
+        assert!(!main
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Store { .. })));
+
+        // With it, the variable starts off at zero, and every element of the array is zeroed by a loop
+        let program = lower_source_with_options("zero-init", source, false, true);
+        let text = format_program(&program, false);
+        assert!(text.contains("    %x.0 = const 0\n"));
+        assert!(text.contains("    %1 = slot_addr $0\n    %2 = const 3\n"));
+        assert!(text.contains("    store.word %4, %6\n"));
+    }
+
+    #[test]
+    fn test_lower_printf() {
+        let program = lower_source(
+            "printf",
+            "func main() returns void {\nprintf(\"x = {}\\n\", 1);\nprintf(\"100% of {}%d\\n\", 2);\n}\n",
+        );
+
+        assert_eq!(
+            GlobalKind::String(b"x = %d\n".to_vec()),
+            program.globals[0].kind
+        );
+
+        // A percent sign is only ever printed as it is, never taken as one of C's formatters
+        assert_eq!(
+            GlobalKind::String(b"100%% of %d%%d\n".to_vec()),
+            program.globals[1].kind
+        );
+    }
+
+    #[test]
+    fn test_cfg_if_else() {
+        let program = lower_source(
+            "cfg_if_else",
+            "func main() returns int {\nint x = 1;\nif (x > 0) {\nx = 2;\nx += 1;\n} else {\nx = 3;\n}\nreturn x;\n}\n",
+        );
+        let cfg = Cfg::new(program.get_function("main").unwrap());
+
+        // entry -> then, else -> after, followed by the unreachable panic at the end of main
+        assert_eq!(vec![1, 2], cfg.blocks[0].succs);
+        assert_eq!(vec![3], cfg.blocks[1].succs);
+        assert_eq!(vec![3], cfg.blocks[2].succs);
+        assert_eq!(vec![1, 2], cfg.blocks[3].preds);
+        assert!(cfg.blocks[3].succs.is_empty());
+        assert!(cfg.blocks[4].preds.is_empty());
+
+        assert_eq!(vec![0, 2, 1, 3], cfg.reverse_postorder());
+    }
+
+    #[test]
+    fn test_cfg_while() {
+        let program = lower_source(
+            "cfg_while",
+            "func main() returns void {\nint x = 0;\nwhile (x < 10) {\nx += 1;\n}\n}\n",
+        );
+        let func = program.get_function("main").unwrap();
+        let cfg = Cfg::new(func);
+
+        // entry -> test -> body -> test (the back edge of the loop), test -> after
+        assert_eq!(vec![1], cfg.blocks[0].succs);
+        assert_eq!(vec![2, 3], cfg.blocks[1].succs);
+        assert_eq!(vec![0, 2], cfg.blocks[1].preds);
+        assert_eq!(vec![1], cfg.blocks[2].succs);
+
+        // The test comes before both the body and whatever comes after the loop
+        let rpo = cfg.reverse_postorder();
+        assert_eq!(0, rpo[0]);
+        assert_eq!(1, rpo[1]);
+
+        // Splitting a function into blocks and putting it back together changes nothing
+        assert_eq!(func.body, cfg.to_body());
+    }
+
+    #[test]
+    fn test_cfg_loop() {
+        let program = lower_source(
+            "cfg_loop",
+            "func main() returns void {\nint x = 0;\nloop {\nx += 1;\nif (x == 10) {\nbreak;\n}\n}\n}\n",
+        );
+        let func = program.get_function("main").unwrap();
+        let cfg = Cfg::new(func);
+
+        // entry -> body, with no test, so the only way out of the body is the break, and the back edge goes straight to it
+        assert_eq!(vec![1], cfg.blocks[0].succs);
+        assert_eq!(vec![0, 3], cfg.blocks[1].preds);
+        assert_eq!(vec![2, 3], cfg.blocks[1].succs);
+        assert_eq!(vec![4], cfg.blocks[2].succs);
+        assert_eq!(vec![1], cfg.blocks[3].succs);
+
+        assert_eq!(func.body, cfg.to_body());
+    }
+
+    #[test]
+    fn test_live_ranges() {
+        // int i = 0; while (i < 10) { i += 1; }, with one temporary that's never used
+        let mut func = IrFunction::new("main");
+        func.temps = vec![IrType::Int; 5];
+        func.num_labels = 3;
+        func.body = vec![
+            Instr::Const { dest: 0, value: 0 },
+            Instr::Label(0),
+            Instr::Const { dest: 1, value: 10 },
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Branch {
+                cond: 2,
+                if_true: 1,
+                if_false: 2,
+            },
+            Instr::Label(1),
+            Instr::Const { dest: 3, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 0,
+                lhs: 0,
+                rhs: 3,
+            },
+            Instr::Jump(0),
+            Instr::Label(2),
+            Instr::Return(vec![]),
+        ];
+
+        // i is live all the way around the loop (but not after it), and everything else only lives between two instructions
+        let (live_in, live_out) = get_block_liveness(&Cfg::new(&func));
+        assert!(live_in[1].contains(&0));
+        assert!(live_out[2].contains(&0));
+        assert!(live_in[3].is_empty());
+
+        assert_eq!(
+            vec![Some((0, 8)), Some((2, 3)), Some((3, 4)), Some((6, 7)), None],
+            get_live_ranges(&func)
+        );
+
+        // so i interferes with everything else, which don't interfere with each other
+        let graph = get_interference_graph(&func);
+        assert_eq!(3, graph[0].len());
+        assert_eq!(1, graph[1].len());
+        assert!(graph[4].is_empty());
+    }
+
+    #[test]
+    fn test_format_program() {
+        let program = lower_source(
+            "format",
+            "int g = 7;\nfunc add(int a, int b) returns int {\nreturn a + b;\n}\nfunc main() returns void {\nint x = add(g, 2);\n}\n",
+        );
+
+        // Temporaries holding variables are named after them, and each statement is marked with its line
+        let expected = "\
+@0 g = word 7
+@1 error = string \"Error: Line %d: A control path reaches the end of a non-void function without returning a value\\n\"
+
+func add(%a.0: int, %b.1: int) -> int
+    // line 3
+    %2 = add %a.0, %b.1
+    return %2
+    panic @1, line 2
+
+func main() -> void
+    // line 6
+    %1 = global_addr @0
+    %2 = load.word %1
+    %3 = const 2
+    %4 = call add(%2, %3)
+    %x.0 = copy %4
+    return
+";
+        assert_eq!(expected, format_program(&program, false));
+    }
+
+    #[test]
+    fn test_instrument_coverage() {
+        let mut program = lower_source(
+            "coverage",
+            "func main() returns void {\nint x = 1;\nwhile (x < 3) {\nx += 1;\n}\n}\n",
+        );
+        program.files = vec![String::from("main.soup")];
+        instrument_coverage(&mut program, "out.cov");
+
+        // Every line that starts a statement gets a counter, numbered in the order they're found
+        let coverage = program.coverage.clone().unwrap();
+        assert_eq!(3, coverage.num_counters);
+        assert_eq!(
+            GlobalKind::Zeroed(12),
+            program.globals[coverage.counters].kind
+        );
+        assert_eq!(
+            GlobalKind::String(b"out.cov".to_vec()),
+            program.globals[coverage.file].kind
+        );
+
+        let header = format_coverage_header(&program.files, &[(0, 2), (0, 3), (0, 4)]);
+        assert_eq!(
+            GlobalKind::String(header.clone().into_bytes()),
+            program.globals[coverage.header].kind
+        );
+        assert_eq!(header.len(), coverage.header_len);
+        assert!(header.ends_with("\ncounters 3\n0 2\n0 3\n0 4\n"));
+
+        // Each counter is added to right after the marker for its line
+        let main = program.get_function("main").unwrap();
+        let markers = main
+            .body
+            .iter()
+            .filter(|instr| matches!(instr, Instr::Line(_)))
+            .count();
+        let increments = main.body.windows(2).filter(|pair| {
+            return matches!(pair[0], Instr::Line(_))
+                && matches!(pair[1], Instr::GlobalAddr { global, .. } if global == coverage.counters);
+        });
+        assert_eq!(markers, increments.count());
+    }
+
+    #[test]
+    fn test_instrument_trace() {
+        let mut program = lower_source(
+            "trace",
+            "func main() returns void {\nint x = 1;\nwhile (x < 3) {\nx += 1;\n}\n}\n",
+        );
+        program.files = vec![String::from("missing.soup")];
+        let num_globals = program.globals.len();
+        instrument_trace(&mut program);
+
+        // Each line that starts a statement gets a single message, which is printed right after its marker
+        assert_eq!(num_globals + 3, program.globals.len());
+        assert_eq!(
+            GlobalKind::String(b"[trace] missing.soup:3\n".to_vec()),
+            program.globals[num_globals + 1].kind
+        );
+
+        let main = program.get_function("main").unwrap();
+        for (i, instr) in main.body.iter().enumerate() {
+            if matches!(instr, Instr::Line(_)) {
+                assert!(
+                    matches!(&main.body[i + 1], Instr::Print { file: None, format, args }
+                    if *format >= num_globals && args.is_empty())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_trace_message() {
+        let source = "func main() returns void {\n    printf(\"100%\\n\");\n}\n";
+        assert_eq!(
+            "[trace] main.soup:2: printf(\"100%%\\n\");\n",
+            format_trace_message("main.soup", 2, Some(source))
+        );
+        assert_eq!(
+            "[trace] main.soup:2\n",
+            format_trace_message("main.soup", 2, None)
+        );
+        assert_eq!(
+            "[trace] main.soup:9\n",
+            format_trace_message("main.soup", 9, Some(source))
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// The compiler is written in a deliberately explicit style (explicit returns, spelled out
+// struct fields, and &Vec parameters), so silence the clippy lints that fight against it
+#![allow(
+    clippy::needless_return,
+    clippy::ptr_arg,
+    clippy::redundant_field_names
+)]
+
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::process;
+
+pub mod build;
+pub mod cli;
+pub mod code_gen;
+pub mod doc;
+pub mod formatter;
+pub mod interpreter;
+pub mod ir;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod passes;
+pub mod repl;
+pub mod scanner;
+pub mod semantic;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
+
+use crate::code_gen::code_gen_driver::code_gen;
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::ir::ir_driver::lower_program;
+use crate::optimizer::optimizer_driver::optimize;
+use crate::parser::parser_driver::parse_program;
+use crate::scanner::scanner_driver::scan_str;
+use crate::semantic::semantic_data::Warnings;
+use crate::semantic::semantic_driver::semantic_checker;
+
+// Open the given file to write the result of a stage of the compiler to,
+// or stdout if no file is given or the file is "-"
+fn open_output(output_file: &str) -> Box<dyn Write> {
+    if output_file.is_empty() || output_file == "-" {
+        return Box::new(BufWriter::new(io::stdout()));
+    }
+
+    match File::create(output_file) {
+        Ok(file) => return Box::new(BufWriter::new(file)),
+        Err(_) => {
+            throw_error(&format!("Could not create file '{}'", output_file));
+            process::exit(1);
+        }
+    }
+}
+
+// Write the result of a stage of the compiler to the given file (or stdout)
+pub fn write_output(output_file: &str, contents: &str) {
+    let mut output = open_output(output_file);
+
+    if output.write_all(contents.as_bytes()).is_err() || output.flush().is_err() {
+        throw_error(&format!("Could not write to file '{}'", output_file));
+    }
+}
+
+// Report the progress of the compiler to stderr (so it never gets mixed up with any output on stdout),
+// but only if we were asked to be verbose
+pub fn log_verbose(verbose: bool, msg: &str) {
+    if verbose {
+        eprintln!("soup: {}", msg);
+    }
+}
+
+// Print the warnings found while compiling a program
+pub fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
+pub fn throw_error(msg: &str) {
+    eprintln!("Error: {}", msg);
+    process::exit(1);
+}
+
+// Compile a piece of soup source code into assembly for the given target, without reading or writing any files.
+// Unlike the rest of the compiler this never prints anything or exits the process: anything wrong with the source
+// is given back as an error (just as the compiler would report it), and anything else that goes wrong is a bug,
+// so it's a good target for fuzzing (see fuzz/)
+pub fn compile_str(
+    source: &str,
+    target: &'static TargetInfo,
+    opt_level: u32,
+) -> Result<String, String> {
+    let tokens = scan_str(source).map_err(|error| error.to_string())?;
+    let mut ast = parse_program(&tokens).map_err(|error| error.to_string())?;
+    semantic_checker(&mut ast, &mut Warnings::default())?;
+
+    let mut program = lower_program(&ast, false, false);
+    optimize(&mut program, opt_level);
+    return Ok(code_gen(&program, opt_level, target, false, false));
+}
@@ -0,0 +1,380 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the language server (run by "soup lsp"), which talks to an editor over stdin and stdout,
+// checking each document the editor has open as it changes, and answering questions about them
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use crate::lsp::lsp_json::{parse_json, Json};
+use crate::lsp::lsp_utils::{
+    describe_symbol, find_name, find_symbol, format_diagnostic, format_range, get_name_at,
+    get_offset, read_position,
+};
+use crate::parser::parser_data::{ParseError, AST};
+use crate::parser::parser_incremental::{ParsedFile, TextEdit};
+use crate::semantic::semantic_data::Warnings;
+use crate::semantic::semantic_driver::semantic_checker;
+
+// The error code for a request the server doesn't know how to answer
+const METHOD_NOT_FOUND: i32 = -32601;
+
+// Run the language server until the editor tells it to exit, checking documents with the given warnings turned on
+pub fn run_language_server(warnings: Warnings) {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut server = LanguageServer::new(warnings);
+
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            // The editor went away without telling us to exit first
+            Ok(None) => process::exit(1),
+            Err(msg) => {
+                eprintln!("soup lsp: {}", msg);
+                continue;
+            }
+        };
+
+        // Exiting is only a success if the editor asked us to shut down first
+        if message.get("method").as_str() == Some("exit") {
+            process::exit(if server.shut_down { 0 } else { 1 });
+        }
+
+        for reply in server.handle_message(&message) {
+            write_message(&mut output, &reply);
+        }
+    }
+}
+
+// Read the next message from the editor, which is a header (giving the length of the message) followed by a JSON body.
+// Gives back None once there's nothing left to read, and an error if the message couldn't be understood
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<Json>, String> {
+    let mut content_length = None;
+
+    // The header ends with a blank line
+    loop {
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) | Err(_) => return Ok(None),
+            Ok(_) => {}
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        } else if let Some(length) = line.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = match content_length {
+        Some(content_length) => content_length,
+        None => return Err(String::from("Message has no Content-Length header")),
+    };
+
+    let mut body = vec![0; content_length];
+    if input.read_exact(&mut body).is_err() {
+        return Ok(None);
+    }
+
+    return match String::from_utf8(body) {
+        Ok(body) => parse_json(&body).map(Some),
+        Err(_) => Err(String::from("Message is not valid UTF-8")),
+    };
+}
+
+// Send a message to the editor, with the header giving its length in front of it
+pub fn write_message(output: &mut impl Write, message: &Json) {
+    let body = message.to_string();
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+
+// -----------------------------------------------------------------
+// LANGUAGE SERVER
+// -----------------------------------------------------------------
+
+// A document the editor has open, along with what was found out about it the last time it changed
+struct Document {
+    text: String,
+    // The document's tokens and AST, or the error that stopped it from being scanned or parsed
+    parsed: Result<ParsedFile, ParseError>,
+    // The AST after the semantic checker has been run over it
+    // (which is only partly filled in if the checker found an error part of the way through)
+    checked: Option<AST>,
+    // The errors and warnings found in the document, as diagnostics
+    diagnostics: Vec<Json>,
+}
+
+impl Document {
+    fn new(text: &str, warnings: &Warnings) -> Document {
+        let mut document = Document {
+            text: String::from(text),
+            parsed: ParsedFile::parse(text),
+            checked: None,
+            diagnostics: vec![],
+        };
+        document.check(warnings);
+        return document;
+    }
+
+    // Apply a change from the editor, which either replaces a range of the document,
+    // or the whole document if it doesn't have a range
+    fn apply_change(&mut self, change: &Json) {
+        let new_text = change.get("text").as_str().unwrap_or("");
+
+        let range = change.get("range");
+        let (start, end) = match (
+            read_position(range.get("start")),
+            read_position(range.get("end")),
+        ) {
+            (Some(start), Some(end)) => {
+                (get_offset(&self.text, start), get_offset(&self.text, end))
+            }
+            _ => (0, self.text.len()),
+        };
+        let edit = TextEdit {
+            start: start,
+            end: end.max(start),
+            new_text: String::from(new_text),
+        };
+        self.text
+            .replace_range(edit.start..edit.end, &edit.new_text);
+
+        // Only the declarations the change touched need to be parsed again, as long as the rest of the document parsed
+        self.parsed = match &self.parsed {
+            Ok(parsed) => parsed.reparse(&edit),
+            Err(_) => ParsedFile::parse(&self.text),
+        };
+    }
+
+    // Run the semantic checker over the document (if it parsed), collecting its errors and warnings as diagnostics
+    fn check(&mut self, warnings: &Warnings) {
+        self.diagnostics = vec![];
+        self.checked = None;
+
+        let parsed = match &self.parsed {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                self.diagnostics.push(format_diagnostic(
+                    &self.text,
+                    &error.message,
+                    Some(error.line_num),
+                    1,
+                ));
+                return;
+            }
+        };
+
+        let mut ast = parsed.ast.clone();
+        let mut warnings = warnings.clone();
+        let result = semantic_checker(&mut ast, &mut warnings);
+
+        for warning in warnings.found {
+            self.diagnostics
+                .push(format_diagnostic(&self.text, &warning, None, 2));
+        }
+        if let Err(error) = result {
+            self.diagnostics
+                .push(format_diagnostic(&self.text, &error, None, 1));
+        }
+        self.checked = Some(ast);
+    }
+}
+
+// The state of the language server, which is every document the editor has open
+pub struct LanguageServer {
+    documents: HashMap<String, Document>,
+    // The warnings turned on for every document
+    warnings: Warnings,
+    // Whether the editor has asked the server to shut down (which it does before telling it to exit)
+    pub shut_down: bool,
+}
+
+impl Default for LanguageServer {
+    fn default() -> Self {
+        return Self::new(Warnings::default());
+    }
+}
+
+impl LanguageServer {
+    pub fn new(warnings: Warnings) -> LanguageServer {
+        return LanguageServer {
+            documents: HashMap::new(),
+            warnings: warnings,
+            shut_down: false,
+        };
+    }
+
+    // Handle a message from the editor, giving back the messages to send back to it
+    // (the response to a request, and any diagnostics that changed)
+    pub fn handle_message(&mut self, message: &Json) -> Vec<Json> {
+        let id = message.get("id");
+        let params = message.get("params");
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+
+        match message.get("method").as_str().unwrap_or("") {
+            "initialize" => return vec![format_response(id, get_capabilities())],
+            "shutdown" => {
+                self.shut_down = true;
+                return vec![format_response(id, Json::Null)];
+            }
+            "textDocument/didOpen" => {
+                let text = params
+                    .get("textDocument")
+                    .get("text")
+                    .as_str()
+                    .unwrap_or("");
+                self.documents
+                    .insert(String::from(uri), Document::new(text, &self.warnings));
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didChange" => {
+                let document = match self.documents.get_mut(uri) {
+                    Some(document) => document,
+                    None => return vec![],
+                };
+                for change in params.get("contentChanges").as_array() {
+                    document.apply_change(change);
+                }
+                document.check(&self.warnings);
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![self.publish_diagnostics(uri)];
+            }
+            "textDocument/hover" => {
+                let hover = self.hover(uri, params.get("position"));
+                return vec![format_response(id, hover.unwrap_or(Json::Null))];
+            }
+            "textDocument/definition" => {
+                let definition = self.definition(uri, params.get("position"));
+                return vec![format_response(id, definition.unwrap_or(Json::Null))];
+            }
+            // Notifications we don't need to do anything about (like "initialized") are ignored,
+            // but a request always needs a response
+            method if !id.is_null() => {
+                return vec![format_error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("Unknown method '{}'", method),
+                )]
+            }
+            _ => return vec![],
+        }
+    }
+
+    // Tell the editor about the errors and warnings in a document (where a closed document has none)
+    fn publish_diagnostics(&self, uri: &str) -> Json {
+        let diagnostics = match self.documents.get(uri) {
+            Some(document) => document.diagnostics.clone(),
+            None => vec![],
+        };
+
+        return Json::object(vec![
+            ("jsonrpc", Json::String(String::from("2.0"))),
+            (
+                "method",
+                Json::String(String::from("textDocument/publishDiagnostics")),
+            ),
+            (
+                "params",
+                Json::object(vec![
+                    ("uri", Json::String(String::from(uri))),
+                    ("diagnostics", Json::Array(diagnostics)),
+                ]),
+            ),
+        ]);
+    }
+
+    // Describe the identifier at a position in a document (like "int x"), if there's one there that the checker understood
+    fn hover(&self, uri: &str, position: &Json) -> Option<Json> {
+        let document = self.documents.get(uri)?;
+        let (token, start, end) = get_name_at(&document.text, read_position(position)?)?;
+        let symbol = find_symbol(document.checked.as_ref()?, &token)?;
+
+        return Some(Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", Json::String(String::from("markdown"))),
+                    (
+                        "value",
+                        Json::String(format!("```soup\n{}\n```", describe_symbol(&symbol))),
+                    ),
+                ]),
+            ),
+            ("range", format_range(start, end)),
+        ]));
+    }
+
+    // Find where the identifier at a position in a document was declared
+    // (which is nowhere in the document if it's part of the runtime library)
+    fn definition(&self, uri: &str, position: &Json) -> Option<Json> {
+        let document = self.documents.get(uri)?;
+        let (token, _, _) = get_name_at(&document.text, read_position(position)?)?;
+        let symbol = find_symbol(document.checked.as_ref()?, &token)?;
+        let (start, end) = find_name(&document.text, &symbol.name, symbol.line_num?)?;
+
+        return Some(Json::object(vec![
+            ("uri", Json::String(String::from(uri))),
+            ("range", format_range(start, end)),
+        ]));
+    }
+}
+
+// What the language server can do, which is sent back in response to the editor's first request
+fn get_capabilities() -> Json {
+    return Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                // Documents are kept up to date with the edits made to them, rather than being sent in full every time
+                (
+                    "textDocumentSync",
+                    Json::object(vec![
+                        ("openClose", Json::Bool(true)),
+                        ("change", Json::Number(2.0)),
+                    ]),
+                ),
+                ("hoverProvider", Json::Bool(true)),
+                ("definitionProvider", Json::Bool(true)),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", Json::String(String::from("soup"))),
+                (
+                    "version",
+                    Json::String(String::from(env!("CARGO_PKG_VERSION"))),
+                ),
+            ]),
+        ),
+    ]);
+}
+
+fn format_response(id: &Json, result: Json) -> Json {
+    return Json::object(vec![
+        ("jsonrpc", Json::String(String::from("2.0"))),
+        ("id", id.clone()),
+        ("result", result),
+    ]);
+}
+
+fn format_error(id: &Json, code: i32, message: &str) -> Json {
+    return Json::object(vec![
+        ("jsonrpc", Json::String(String::from("2.0"))),
+        ("id", id.clone()),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", Json::String(String::from(message))),
+            ]),
+        ),
+    ]);
+}
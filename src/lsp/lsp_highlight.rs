@@ -0,0 +1,174 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the logic involved in classifying the tokens and comments of a piece of soup source code
+// for syntax highlighting, using what the semantic checker worked out about each identifier where it can
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::parser::parser_data::AST;
+use crate::parser::parser_driver::parse_program;
+use crate::scanner::scanner_data::{ScanError, TokenType, TriviaPiece};
+use crate::scanner::scanner_driver::scan_str_lossless;
+use crate::semantic::semantic_data::{Type, Warnings};
+use crate::semantic::semantic_driver::semantic_checker;
+
+// The kind of thing a piece of source code is, as far as highlighting it goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenCategory {
+    Keyword,
+    Identifier,
+    Function,
+    Literal,
+    Operator,
+    Comment,
+}
+
+// A piece of source code to highlight, between two byte offsets of the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    pub category: TokenCategory,
+}
+
+// Classify every token and comment of a piece of source code, in the order they appear in it.
+// An identifier is a function if the semantic checker found it refers to (or declares) one, and if the checker
+// never got to it (because the program has an error before it), if it's followed by an open parenthesis.
+// Punctuation (like parentheses, braces and semicolons) isn't highlighted, so it's left out
+pub fn classify_tokens(source: &str) -> Result<Vec<HighlightSpan>, ScanError> {
+    let (tokens, trivia) = scan_str_lossless(source)?;
+
+    // Work out which identifiers are functions, as far as the checker gets
+    let functions = match parse_program(&tokens) {
+        Ok(mut ast) => {
+            let _ = semantic_checker(&mut ast, &mut Warnings::default());
+            get_function_names(&ast)
+        }
+        Err(_) => HashMap::new(),
+    };
+
+    let mut spans = vec![];
+    let mut offset = 0;
+
+    for (i, (token, trivia)) in tokens.iter().zip(&trivia).enumerate() {
+        for piece in &trivia.pieces {
+            let start = offset;
+            match piece {
+                TriviaPiece::Whitespace(text) => offset = skip_text(source, offset, text),
+                TriviaPiece::Newline => offset = skip_text(source, offset, "\n"),
+                TriviaPiece::Comment(text) => {
+                    offset = skip_text(source, offset, text);
+                    spans.push(HighlightSpan {
+                        start: start,
+                        end: offset,
+                        category: TokenCategory::Comment,
+                    });
+                }
+            }
+        }
+
+        let start = offset;
+        offset = skip_text(source, offset, &token.source_text());
+
+        let category = match token.token_type {
+            TokenType::ID => {
+                let is_function = match functions.get(&(token.line_num, token.lexeme.clone())) {
+                    Some(is_function) => *is_function,
+                    None => {
+                        tokens.get(i + 1).map(|next| next.token_type) == Some(TokenType::OPENPAR)
+                    }
+                };
+                match is_function {
+                    true => TokenCategory::Function,
+                    false => TokenCategory::Identifier,
+                }
+            }
+            TokenType::MAIN => TokenCategory::Function,
+            TokenType::STRLIT | TokenType::INTLIT | TokenType::TRUE | TokenType::FALSE => {
+                TokenCategory::Literal
+            }
+            TokenType::INT
+            | TokenType::BOOL
+            | TokenType::VOID
+            | TokenType::IF
+            | TokenType::ELSE
+            | TokenType::WHILE
+            | TokenType::LOOP
+            | TokenType::REPEAT
+            | TokenType::UNTIL
+            | TokenType::BREAK
+            | TokenType::CONTINUE
+            | TokenType::RETURN
+            | TokenType::FUNC
+            | TokenType::RETURNS => TokenCategory::Keyword,
+            TokenType::OPENPAR
+            | TokenType::CLOSEPAR
+            | TokenType::OPENBRACE
+            | TokenType::CLOSEBRACE
+            | TokenType::OPENBRACKET
+            | TokenType::CLOSEBRACKET
+            | TokenType::SEMICOLON
+            | TokenType::COLON
+            | TokenType::COMMA
+            | TokenType::EOF => continue,
+            _ => TokenCategory::Operator,
+        };
+        spans.push(HighlightSpan {
+            start: start,
+            end: offset,
+            category: category,
+        });
+    }
+
+    return Ok(spans);
+}
+
+// Get whether each identifier the checker resolved (by its line and name) refers to or declares a function
+fn get_function_names(ast: &AST) -> HashMap<(i32, Arc<str>), bool> {
+    let mut parents = HashMap::new();
+    for (id, node) in ast.nodes.iter().enumerate() {
+        for child in &node.children {
+            parents.insert(*child, id);
+        }
+    }
+
+    let mut functions = HashMap::new();
+    for (id, node) in ast.nodes.iter().enumerate() {
+        let (name, line_num) = match (&node.attr, node.line_num) {
+            (Some(name), Some(line_num)) if node.node_type == "id" => (name, line_num),
+            _ => continue,
+        };
+
+        // The names of declarations don't have symbols of their own, but the declarations they're in do
+        let symbol = match &node.sym {
+            Some(symbol) => Some(symbol),
+            None => parents
+                .get(&id)
+                .and_then(|parent| ast[*parent].sym.as_ref()),
+        };
+        if let Some(symbol) = symbol {
+            let is_function = matches!(symbol.borrow().type_sig, Type::Func { .. });
+            functions.insert((line_num, name.clone()), is_function);
+        }
+    }
+
+    return functions;
+}
+
+// Move a byte offset of the source past some text the scanner read from it,
+// where each line ending the scanner read might have been a "\r\n" or a "\r" on its own in the source
+fn skip_text(source: &str, mut offset: usize, text: &str) -> usize {
+    for char in text.chars() {
+        let rest = &source[offset..];
+        if char == '\n' && rest.starts_with("\r\n") {
+            offset += 2;
+        } else if char == '\n' && rest.starts_with('\r') {
+            offset += 1;
+        } else {
+            offset += char.len_utf8();
+        }
+    }
+
+    return offset;
+}
@@ -0,0 +1,278 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains a small JSON reader and writer, for the messages the language server sends and receives
+// -------------------------------------------------------------------------------------------------------------
+
+use std::fmt;
+
+use crate::parser::parser_data::format_json_string;
+
+// A JSON value, where an object's members are kept in the order they were written in
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// What a member of an object that isn't there is given back as
+static NULL: Json = Json::Null;
+
+impl Json {
+    // Create an object out of the given members
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        return Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (String::from(key), value))
+                .collect(),
+        );
+    }
+
+    // Get a member of an object, or null if it doesn't have one with the given key (or isn't an object at all)
+    pub fn get(&self, key: &str) -> &Json {
+        if let Json::Object(members) = self {
+            if let Some((_, value)) = members.iter().find(|(member, _)| member == key) {
+                return value;
+            }
+        }
+        return &NULL;
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        return match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        };
+    }
+
+    // Get a number that's a whole number (like a line number, or the id of a message)
+    pub fn as_u32(&self) -> Option<u32> {
+        return match self {
+            Json::Number(number) if number.fract() == 0.0 && *number >= 0.0 => Some(*number as u32),
+            _ => None,
+        };
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        return match self {
+            Json::Array(elements) => elements,
+            _ => &[],
+        };
+    }
+
+    pub fn is_null(&self) -> bool {
+        return *self == Json::Null;
+    }
+}
+
+// JSON is written out compactly, all on one line
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => return write!(f, "null"),
+            Json::Bool(value) => return write!(f, "{}", value),
+            Json::Number(number) => return write!(f, "{}", number),
+            Json::String(text) => return write!(f, "{}", format_json_string(text)),
+            Json::Array(elements) => {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| element.to_string()).collect();
+                return write!(f, "[{}]", elements.join(","));
+            }
+            Json::Object(members) => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", format_json_string(key), value))
+                    .collect();
+                return write!(f, "{{{}}}", members.join(","));
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------
+// JSON READER
+// -----------------------------------------------------------------
+
+// Read a JSON value, which has to make up the whole of the given text
+pub fn parse_json(text: &str) -> Result<Json, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    let value = parse_value(&chars, &mut i)?;
+
+    skip_whitespace(&chars, &mut i);
+    if i < chars.len() {
+        return Err(format!(
+            "Unexpected '{}' after the end of the JSON value",
+            chars[i]
+        ));
+    }
+
+    return Ok(value);
+}
+
+// Read the JSON value starting at the character at index i
+fn parse_value(chars: &Vec<char>, i: &mut usize) -> Result<Json, String> {
+    skip_whitespace(chars, i);
+
+    match chars.get(*i) {
+        None => return Err(String::from("Unexpected end of JSON")),
+        Some('{') => {
+            *i += 1;
+            let mut members = vec![];
+
+            skip_whitespace(chars, i);
+            if chars.get(*i) == Some(&'}') {
+                *i += 1;
+                return Ok(Json::Object(members));
+            }
+
+            loop {
+                skip_whitespace(chars, i);
+                let key = parse_string(chars, i)?;
+
+                skip_whitespace(chars, i);
+                expect_char(chars, i, ':')?;
+                members.push((key, parse_value(chars, i)?));
+
+                skip_whitespace(chars, i);
+                if chars.get(*i) == Some(&'}') {
+                    *i += 1;
+                    return Ok(Json::Object(members));
+                }
+                expect_char(chars, i, ',')?;
+            }
+        }
+        Some('[') => {
+            *i += 1;
+            let mut elements = vec![];
+
+            skip_whitespace(chars, i);
+            if chars.get(*i) == Some(&']') {
+                *i += 1;
+                return Ok(Json::Array(elements));
+            }
+
+            loop {
+                elements.push(parse_value(chars, i)?);
+
+                skip_whitespace(chars, i);
+                if chars.get(*i) == Some(&']') {
+                    *i += 1;
+                    return Ok(Json::Array(elements));
+                }
+                expect_char(chars, i, ',')?;
+            }
+        }
+        Some('"') => return Ok(Json::String(parse_string(chars, i)?)),
+        Some('-' | '0'..='9') => {
+            let start = *i;
+            while *i < chars.len() && "+-0123456789.eE".contains(chars[*i]) {
+                *i += 1;
+            }
+
+            let number: String = chars[start..*i].iter().collect();
+            return match number.parse() {
+                Ok(number) => Ok(Json::Number(number)),
+                Err(_) => Err(format!("Invalid number '{}' in JSON", number)),
+            };
+        }
+        Some(_) => {
+            for (word, value) in [
+                ("true", Json::Bool(true)),
+                ("false", Json::Bool(false)),
+                ("null", Json::Null),
+            ] {
+                if chars[*i..].starts_with(&word.chars().collect::<Vec<char>>()) {
+                    *i += word.len();
+                    return Ok(value);
+                }
+            }
+            return Err(format!("Unexpected '{}' in JSON", chars[*i]));
+        }
+    }
+}
+
+// Read the JSON string starting at the character at index i (which should be its open quote)
+fn parse_string(chars: &Vec<char>, i: &mut usize) -> Result<String, String> {
+    expect_char(chars, i, '"')?;
+    let mut text = String::new();
+
+    loop {
+        match chars.get(*i) {
+            None => return Err(String::from("Unterminated string in JSON")),
+            Some('"') => {
+                *i += 1;
+                return Ok(text);
+            }
+            Some('\\') => {
+                *i += 1;
+                match chars.get(*i) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let mut code = parse_hex(chars, i)?;
+
+                        // A character outside of the basic multilingual plane is written as a pair of surrogates
+                        if (0xD800..0xDC00).contains(&code)
+                            && chars.get(*i + 1) == Some(&'\\')
+                            && chars.get(*i + 2) == Some(&'u')
+                        {
+                            *i += 2;
+                            let low = parse_hex(chars, i)?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        text.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    Some(char) => text.push(*char),
+                    None => return Err(String::from("Unterminated string in JSON")),
+                }
+                *i += 1;
+            }
+            Some(char) => {
+                text.push(*char);
+                *i += 1;
+            }
+        }
+    }
+}
+
+// Read the four hex digits after the "\u" of an escaped character, leaving i at the last of them
+fn parse_hex(chars: &Vec<char>, i: &mut usize) -> Result<u32, String> {
+    let digits: String = chars.iter().skip(*i + 1).take(4).collect();
+    *i += 4;
+
+    return match u32::from_str_radix(&digits, 16) {
+        Ok(code) if digits.len() == 4 => Ok(code),
+        _ => Err(format!("Invalid escape '\\u{}' in JSON", digits)),
+    };
+}
+
+// Move past the given character, giving back an error if it isn't the next one
+fn expect_char(chars: &Vec<char>, i: &mut usize, expected: char) -> Result<(), String> {
+    match chars.get(*i) {
+        Some(char) if *char == expected => {
+            *i += 1;
+            return Ok(());
+        }
+        Some(char) => return Err(format!("Expected '{}' in JSON, found '{}'", expected, char)),
+        None => {
+            return Err(format!(
+                "Expected '{}' in JSON, found the end of it",
+                expected
+            ))
+        }
+    }
+}
+
+fn skip_whitespace(chars: &Vec<char>, i: &mut usize) {
+    while *i < chars.len() && chars[*i].is_whitespace() {
+        *i += 1;
+    }
+}
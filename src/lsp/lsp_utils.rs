@@ -0,0 +1,243 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the helpers the language server uses to go between the positions an editor talks about
+// and the tokens, nodes and symbols of a program, and to describe what it finds in the messages it sends back
+// -------------------------------------------------------------------------------------------------------------
+
+use crate::lsp::lsp_json::Json;
+use crate::parser::parser_data::AST;
+use crate::scanner::scanner_data::{Token, TokenType, TriviaPiece};
+use crate::scanner::scanner_driver::scan_str_lossless;
+use crate::semantic::semantic_data::{Symbol, Type};
+
+// A position in a document, as the language server protocol gives them:
+// a line and a column (in UTF-16 code units, like JavaScript strings), both numbered from 0
+pub type Position = (u32, u32);
+
+// -----------------------------------------------------------------
+// POSITIONS
+// -----------------------------------------------------------------
+
+// Get the byte offset of a position in some text, where a column past the end of its line is the end of the line
+// (and a line past the end of the text is the end of the text)
+pub fn get_offset(text: &str, (line, column): Position) -> usize {
+    let mut current_line = 0;
+    let mut current_column = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, char)) = chars.next() {
+        if current_line == line && (current_column >= column || char == '\n' || char == '\r') {
+            return offset;
+        }
+
+        // Lines end the same way they do for the scanner (with a "\n", a "\r\n", or a "\r" on its own)
+        if char == '\n' || (char == '\r' && chars.peek().map(|(_, next)| *next) != Some('\n')) {
+            current_line += 1;
+            current_column = 0;
+        } else if char != '\r' {
+            current_column += char.len_utf16() as u32;
+        }
+    }
+
+    return text.len();
+}
+
+// Move a position past some text
+fn advance(position: &mut Position, text: &str) {
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if char == '\n' || (char == '\r' && chars.peek() != Some(&'\n')) {
+            *position = (position.0 + 1, 0);
+        } else if char != '\r' {
+            position.1 += char.len_utf16() as u32;
+        }
+    }
+}
+
+// Get the tokens of a document along with the positions they start and end at (or nothing if it can't be scanned)
+pub fn get_token_positions(text: &str) -> Vec<(Token, Position, Position)> {
+    let (tokens, trivia) = match scan_str_lossless(text) {
+        Ok(scanned) => scanned,
+        Err(_) => return vec![],
+    };
+
+    let mut positions = vec![];
+    let mut position = (0, 0);
+
+    for (token, trivia) in tokens.into_iter().zip(trivia) {
+        for piece in &trivia.pieces {
+            match piece {
+                TriviaPiece::Newline => position = (position.0 + 1, 0),
+                TriviaPiece::Whitespace(text) | TriviaPiece::Comment(text) => {
+                    advance(&mut position, text)
+                }
+            }
+        }
+
+        let start = position;
+        advance(&mut position, &token.source_text());
+        positions.push((token, start, position));
+    }
+
+    return positions;
+}
+
+// Get the identifier (or main) token at a position in a document, including the position just after its last character,
+// along with where it starts and ends
+pub fn get_name_at(text: &str, position: Position) -> Option<(Token, Position, Position)> {
+    return get_token_positions(text)
+        .into_iter()
+        .find(|(token, start, end)| {
+            (token.token_type == TokenType::ID || token.token_type == TokenType::MAIN)
+                && *start <= position
+                && position <= *end
+        });
+}
+
+// Get where the first identifier (or main) with the given name on a line (numbered from 1) of a document starts and ends
+pub fn find_name(text: &str, name: &str, line_num: i32) -> Option<(Position, Position)> {
+    return get_token_positions(text)
+        .into_iter()
+        .find(|(token, _, _)| {
+            (token.token_type == TokenType::ID || token.token_type == TokenType::MAIN)
+                && *token.lexeme == *name
+                && token.line_num == line_num
+        })
+        .map(|(_, start, end)| (start, end));
+}
+
+// Get the range covering the whole of a line (numbered from 1) of a document,
+// which is the closest the compiler's errors can be pinned down to
+pub fn get_line_range(text: &str, line_num: i32) -> (Position, Position) {
+    let num_lines = text.lines().count().max(1) as u32;
+    let line = (line_num.max(1) as u32 - 1).min(num_lines - 1);
+
+    let start = get_offset(text, (line, 0));
+    let end = get_offset(text, (line, u32::MAX));
+    let length: usize = text[start..end].chars().map(|char| char.len_utf16()).sum();
+
+    return ((line, 0), (line, length as u32));
+}
+
+// -----------------------------------------------------------------
+// SYMBOLS
+// -----------------------------------------------------------------
+
+// Get the symbol an identifier token (in a checked AST) refers to, or declares
+pub fn find_symbol(ast: &AST, token: &Token) -> Option<Symbol> {
+    for id in 0..ast.nodes.len() {
+        let node = &ast[id];
+        if node.node_type != "id"
+            || node.attr.as_deref() != Some(&*token.lexeme)
+            || node.line_num != Some(token.line_num)
+        {
+            continue;
+        }
+
+        // The names of declarations don't have symbols of their own, but the declarations they're in do
+        let symbol = match &node.sym {
+            Some(symbol) => Some(symbol.clone()),
+            None => ast
+                .nodes
+                .iter()
+                .find(|parent| parent.children.contains(&id))
+                .and_then(|parent| parent.sym.clone()),
+        };
+        if let Some(symbol) = symbol {
+            return Some(symbol.borrow().clone());
+        }
+    }
+
+    return None;
+}
+
+// Describe a symbol the way it would be declared, like "int x[10]" or "func add(int, int) returns int"
+pub fn describe_symbol(symbol: &Symbol) -> String {
+    if let Type::Func { params, ret } = &symbol.type_sig {
+        let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        return format!(
+            "func {}({}) returns {}",
+            symbol.name,
+            params.join(", "),
+            ret
+        );
+    }
+
+    // An array declared with a size has its sizes after its name, rather than as part of its type
+    if !symbol.array_dims.is_empty() {
+        let dims: Vec<String> = symbol
+            .array_dims
+            .iter()
+            .map(|dim| format!("[{}]", dim))
+            .collect();
+        let element_type = symbol.type_sig.to_string();
+        return format!(
+            "{} {}{}",
+            element_type.trim_end_matches("[]"),
+            symbol.name,
+            dims.join("")
+        );
+    }
+
+    return format!("{} {}", symbol.type_sig, symbol.name);
+}
+
+// -----------------------------------------------------------------
+// MESSAGES
+// -----------------------------------------------------------------
+
+// Write a position the way the protocol does
+pub fn format_position((line, column): Position) -> Json {
+    return Json::object(vec![
+        ("line", Json::Number(line as f64)),
+        ("character", Json::Number(column as f64)),
+    ]);
+}
+
+pub fn format_range(start: Position, end: Position) -> Json {
+    return Json::object(vec![
+        ("start", format_position(start)),
+        ("end", format_position(end)),
+    ]);
+}
+
+// Read a position out of the parameters of a request, if it has one
+pub fn read_position(position: &Json) -> Option<Position> {
+    return Some((
+        position.get("line").as_u32()?,
+        position.get("character").as_u32()?,
+    ));
+}
+
+// Turn an error or warning from the compiler into a diagnostic on the line it's about, with the given severity
+// (1 for an error and 2 for a warning). Errors and warnings from the semantic checker start with the line they're on
+// (like "Line 3: ..."), which is left out of the diagnostic, since the editor already shows where it is
+pub fn format_diagnostic(text: &str, message: &str, line_num: Option<i32>, severity: u32) -> Json {
+    let (line_num, message) = match line_num {
+        Some(line_num) => (line_num, message),
+        None => split_line_num(message),
+    };
+    let (start, end) = get_line_range(text, line_num);
+
+    return Json::object(vec![
+        ("range", format_range(start, end)),
+        ("severity", Json::Number(severity as f64)),
+        ("source", Json::String(String::from("soup"))),
+        ("message", Json::String(String::from(message))),
+    ]);
+}
+
+// Split the line number off the front of a message from the semantic checker,
+// where anything that isn't about a particular line is put on the first line
+fn split_line_num(message: &str) -> (i32, &str) {
+    if let Some((line_num, rest)) = message
+        .strip_prefix("Line ")
+        .and_then(|message| message.split_once(": "))
+    {
+        if let Ok(line_num) = line_num.parse() {
+            return (line_num, rest);
+        }
+    }
+
+    return (1, message);
+}
@@ -0,0 +1,8 @@
+pub mod lsp_driver;
+pub mod lsp_highlight;
+pub mod lsp_json;
+pub mod lsp_utils;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
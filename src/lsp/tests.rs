@@ -0,0 +1,236 @@
+mod tests {
+    use crate::lsp::lsp_driver::{read_message, write_message, LanguageServer};
+    use crate::lsp::lsp_highlight::{classify_tokens, TokenCategory};
+    use crate::lsp::lsp_json::{parse_json, Json};
+    use crate::lsp::lsp_utils::get_offset;
+    use crate::semantic::semantic_data::Warnings;
+
+    // Create a message from the editor (with an id if it's a request rather than a notification)
+    fn message(id: Option<u32>, method: &str, params: &str) -> Json {
+        let id = match id {
+            Some(id) => format!("\"id\":{},", id),
+            None => String::new(),
+        };
+        return parse_json(&format!(
+            "{{\"jsonrpc\":\"2.0\",{}\"method\":\"{}\",\"params\":{}}}",
+            id, method, params
+        ))
+        .unwrap();
+    }
+
+    // Open a document in the language server, giving back the diagnostics published for it
+    fn open(server: &mut LanguageServer, text: &str) -> Json {
+        let params = format!(
+            "{{\"textDocument\":{{\"uri\":\"file:///a.soup\",\"text\":{}}}}}",
+            Json::String(String::from(text))
+        );
+        let replies = server.handle_message(&message(None, "textDocument/didOpen", &params));
+        return replies[0].get("params").get("diagnostics").clone();
+    }
+
+    // Ask the language server something about a position in the open document
+    fn ask(server: &mut LanguageServer, method: &str, line: u32, character: u32) -> Json {
+        let params = format!(
+            "{{\"textDocument\":{{\"uri\":\"file:///a.soup\"}},\"position\":{{\"line\":{},\"character\":{}}}}}",
+            line, character
+        );
+        let replies = server.handle_message(&message(Some(1), method, &params));
+        return replies[0].get("result").clone();
+    }
+
+    #[test]
+    fn test_json() {
+        let text =
+            "{\"a\":[1,-2.5,true,null],\"b\":\"x\\n\\\"y\\\" \\u00e9\\ud83c\\udf72\",\"c\":{}}";
+        let json = parse_json(text).unwrap();
+        assert_eq!(Some("x\n\"y\" é🍲"), json.get("b").as_str());
+        assert_eq!(Some(1), json.get("a").as_array()[0].as_u32());
+        assert!(json.get("d").is_null());
+        assert_eq!(
+            "{\"a\":[1,-2.5,true,null],\"b\":\"x\\n\\\"y\\\" é🍲\",\"c\":{}}",
+            json.to_string()
+        );
+
+        assert!(parse_json("{\"a\":1").is_err());
+        assert!(parse_json("[1,]").is_err());
+        assert!(parse_json("1 2").is_err());
+    }
+
+    #[test]
+    fn test_read_and_write_message() {
+        let mut output = vec![];
+        write_message(&mut output, &Json::object(vec![("id", Json::Number(1.0))]));
+        assert_eq!(b"Content-Length: 8\r\n\r\n{\"id\":1}".to_vec(), output);
+
+        let mut input = &output[..];
+        assert_eq!(
+            Some(Json::object(vec![("id", Json::Number(1.0))])),
+            read_message(&mut input).unwrap()
+        );
+        assert_eq!(None, read_message(&mut input).unwrap());
+    }
+
+    #[test]
+    fn test_get_offset() {
+        // Columns are counted in UTF-16 code units, and a column past the end of a line is the end of the line
+        let text = "a🍲b\r\ncd\rx";
+        assert_eq!(5, get_offset(text, (0, 3)));
+        assert_eq!(6, get_offset(text, (0, 9)));
+        assert_eq!(9, get_offset(text, (1, 1)));
+        assert_eq!(11, get_offset(text, (2, 0)));
+        assert_eq!(12, get_offset(text, (5, 0)));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut server = LanguageServer::new(Warnings::default());
+        let replies = server.handle_message(&message(Some(0), "initialize", "{}"));
+        assert_eq!(
+            Json::Bool(true),
+            *replies[0]
+                .get("result")
+                .get("capabilities")
+                .get("hoverProvider")
+        );
+
+        // Errors are reported on the line they're about, without the line number in front of them
+        let diagnostics = open(&mut server, "func main() returns int {\n    return y;\n}\n");
+        assert_eq!(1, diagnostics.as_array().len());
+        let diagnostic = &diagnostics.as_array()[0];
+        assert_eq!(
+            Some("Unknown identifier 'y'"),
+            diagnostic.get("message").as_str()
+        );
+        assert_eq!(
+            Some(1),
+            diagnostic.get("range").get("start").get("line").as_u32()
+        );
+        assert_eq!(
+            Some(13),
+            diagnostic.get("range").get("end").get("character").as_u32()
+        );
+
+        // Fixing the error clears it
+        let change = "{\"textDocument\":{\"uri\":\"file:///a.soup\"},\"contentChanges\":[{\"range\":{\"start\":{\"line\":1,\"character\":11},\"end\":{\"line\":1,\"character\":12}},\"text\":\"0\"}]}";
+        let replies = server.handle_message(&message(None, "textDocument/didChange", change));
+        assert_eq!(
+            Json::Array(vec![]),
+            *replies[0].get("params").get("diagnostics")
+        );
+
+        // Syntax errors are reported too
+        let diagnostics = open(&mut server, "func main() returns int {\n    return 0\n}\n");
+        assert_eq!(
+            Some(2),
+            diagnostics.as_array()[0]
+                .get("range")
+                .get("start")
+                .get("line")
+                .as_u32()
+        );
+
+        // A request the server doesn't know about gets an error back
+        let replies = server.handle_message(&message(Some(2), "textDocument/rename", "{}"));
+        assert_eq!(
+            Some(-32601.0),
+            match replies[0].get("error").get("code") {
+                Json::Number(code) => Some(*code),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_hover_and_definition() {
+        let mut server = LanguageServer::new(Warnings::default());
+        open(
+            &mut server,
+            "int total[3];\n\nfunc add(int x, bool y) returns int {\n    return x + total[0];\n}\n\nfunc main() returns int {\n    printf(\"{}\", add(1, true));\n    return 0;\n}\n",
+        );
+
+        // Hovering over an identifier shows how it was declared
+        let hover = ask(&mut server, "textDocument/hover", 3, 12);
+        assert_eq!(
+            Some("```soup\nint x\n```"),
+            hover.get("contents").get("value").as_str()
+        );
+        let hover = ask(&mut server, "textDocument/hover", 3, 16);
+        assert_eq!(
+            Some("```soup\nint total[3]\n```"),
+            hover.get("contents").get("value").as_str()
+        );
+        let hover = ask(&mut server, "textDocument/hover", 7, 20);
+        assert_eq!(
+            Some("```soup\nfunc add(int, bool) returns int\n```"),
+            hover.get("contents").get("value").as_str()
+        );
+        assert!(ask(&mut server, "textDocument/hover", 1, 0).is_null());
+
+        // Going to the definition of an identifier finds its name in its declaration
+        let definition = ask(&mut server, "textDocument/definition", 7, 19);
+        assert_eq!(
+            Some(2),
+            definition.get("range").get("start").get("line").as_u32()
+        );
+        assert_eq!(
+            Some(5),
+            definition
+                .get("range")
+                .get("start")
+                .get("character")
+                .as_u32()
+        );
+        assert_eq!(
+            Some(8),
+            definition.get("range").get("end").get("character").as_u32()
+        );
+
+        // The runtime library isn't declared anywhere in the document
+        assert!(ask(&mut server, "textDocument/definition", 7, 6).is_null());
+    }
+
+    #[test]
+    fn test_classify_tokens() {
+        let source = "func f(int n) returns int {\r\n    return n * 2; // double\r\n}\n\nfunc main() returns void {\n    printf(\"{}\", f(1));\n}\n";
+        let spans: Vec<(&str, TokenCategory)> = classify_tokens(source)
+            .unwrap()
+            .iter()
+            .map(|span| (&source[span.start..span.end], span.category))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("func", TokenCategory::Keyword),
+                ("f", TokenCategory::Function),
+                ("int", TokenCategory::Keyword),
+                ("n", TokenCategory::Identifier),
+                ("returns", TokenCategory::Keyword),
+                ("int", TokenCategory::Keyword),
+                ("return", TokenCategory::Keyword),
+                ("n", TokenCategory::Identifier),
+                ("*", TokenCategory::Operator),
+                ("2", TokenCategory::Literal),
+                ("// double", TokenCategory::Comment),
+                ("func", TokenCategory::Keyword),
+                ("main", TokenCategory::Function),
+                ("returns", TokenCategory::Keyword),
+                ("void", TokenCategory::Keyword),
+                ("printf", TokenCategory::Function),
+                ("\"{}\"", TokenCategory::Literal),
+                ("f", TokenCategory::Function),
+                ("1", TokenCategory::Literal),
+            ],
+            spans
+        );
+
+        // Identifiers the checker never gets to are functions if they're called
+        let source = "func main() returns void {\n    g(x);\n    return 1 +;\n}\n";
+        let categories: Vec<TokenCategory> = classify_tokens(source)
+            .unwrap()
+            .iter()
+            .map(|span| span.category)
+            .collect();
+        assert_eq!(TokenCategory::Function, categories[4]);
+        assert_eq!(TokenCategory::Identifier, categories[5]);
+    }
+}
@@ -1,45 +1,368 @@
 use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
 use std::process;
+use std::thread;
 
-pub mod code_gen;
-pub mod parser;
-pub mod scanner;
-pub mod semantic;
+use soup::cli::cli_config::load_config;
+use soup::cli::cli_coverage::report_coverage;
+use soup::cli::cli_data::{get_version_string, Command, Emit, USAGE};
+use soup::cli::cli_driver::{get_save_temps_file, get_watch_args, parse_args};
+use soup::cli::cli_runner::{assemble_and_link, run_executable};
+use soup::cli::cli_test_suite::run_test_suite;
+use soup::cli::cli_watch::watch;
+use soup::code_gen::code_gen_c::format_c;
+use soup::code_gen::code_gen_driver::{code_gen, format_frames, get_largest_frame};
+use soup::code_gen::code_gen_llvm::format_llvm_ir;
+use soup::code_gen::code_gen_target::{get_target_info, TARGETS};
+use soup::doc::doc_driver::document_files;
+use soup::formatter::formatter_driver::format_files;
+use soup::interpreter::interpreter_driver::{interpret_program, INTERPRETER_STACK_SIZE};
+use soup::ir::ir_coverage::instrument_coverage;
+use soup::ir::ir_driver::{lower_program, set_source_files};
+use soup::ir::ir_printer::format_program;
+use soup::ir::ir_trace::instrument_trace;
+use soup::lsp::lsp_driver::run_language_server;
+use soup::optimizer::optimizer_driver::optimize;
+use soup::parser::parser_data::{format_ast, format_ast_dot, format_ast_json, print_ast};
+use soup::parser::parser_driver::{get_function_files, merge_programs, scan_and_parse_files};
+use soup::passes::passes_stats::{
+    count_asm_instrs, count_ir_instrs, count_nodes, count_symbols, format_stats, CompileStats,
+};
+use soup::passes::passes_timing::{set_time_passes, time_pass, CountingAllocator, PassTimesReport};
+use soup::repl::repl_driver::run_repl;
+use soup::semantic::semantic_data::Warnings;
+use soup::semantic::semantic_driver::{format_symbols, semantic_checker};
+use soup::{log_verbose, print_warnings, throw_error, write_output};
 
-use crate::code_gen::code_gen_driver::code_gen;
-use crate::parser::parser_driver::parser;
-use crate::scanner::scanner_driver::scanner;
-use crate::semantic::semantic_driver::semantic_checker;
+// Keep count of the memory the compiler allocates, for --time-passes
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 fn main() {
-    // Get command line arguments
-    let args: Vec<String> = env::args().collect();
+    // Get command line arguments (skipping the name of the executable)
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    if args.len() < 3 {
-        throw_error("No file given to compile, exiting now");
+    // Load the project configuration, if we're in a soup project
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(msg) => {
+            throw_error(&msg);
+            return;
+        }
+    };
+
+    let options = match parse_args(&args, config.as_ref()) {
+        Ok(options) => options,
+        Err(msg) => {
+            throw_error(&format!("{}\n\n{}", msg, USAGE));
+            return;
+        }
+    };
+
+    if options.command == Command::Help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if options.command == Command::Version {
+        println!("{}", get_version_string());
+        return;
+    }
+
+    let warnings = Warnings::new(&options.warnings, options.warnings_as_errors);
+
+    // In watch mode, we don't compile anything ourselves, we just keep recompiling whenever something changes
+    if options.watch {
+        watch(&options.input_files, &get_watch_args(&args));
+        return;
+    }
+
+    // Formatting doesn't compile anything either, it just rewrites the given files
+    if options.command == Command::Fmt {
+        format_files(&options.input_files, &options.output_file, options.check);
+        return;
+    }
+
+    // A coverage report is made from the counts a program wrote out, and the files it was built from
+    if options.command == Command::CovReport {
+        report_coverage(&options.input_files[0], &options.output_file);
+        return;
+    }
+
+    // The test suite doesn't compile anything itself, each program in it is run by a fresh copy of the compiler
+    if options.command == Command::TestSuite {
+        let passed = run_test_suite(&options);
+        process::exit(if passed { 0 } else { 1 });
+    }
+
+    // Documenting a program doesn't compile it either, it's only checked
+    if options.command == Command::Doc {
+        document_files(
+            &options.input_files,
+            &options.output_file,
+            &options.doc_format,
+            warnings,
+        );
+        return;
+    }
+
+    // Neither does the language server, which checks whatever files the editor opens, until it's told to exit
+    if options.command == Command::Lsp {
+        run_language_server(warnings);
+        return;
+    }
+
+    // The REPL reads everything it runs from stdin, running it with the interpreter rather than compiling it.
+    // The interpreter recurses for every function call, so it's given a much bigger stack than the main thread has
+    if options.command == Command::Repl {
+        let overflow_checks = options.overflow_checks;
+        let repl = thread::Builder::new()
+            .stack_size(INTERPRETER_STACK_SIZE)
+            .spawn(move || run_repl(overflow_checks, warnings));
+        if repl.map(|repl| repl.join()).is_err() {
+            throw_error("Could not start the REPL");
+        }
+        return;
+    }
+
+    // Everything from here on compiles the program, so if we're timing its stages, they're reported once it's done
+    set_time_passes(options.time_passes);
+    let pass_times = PassTimesReport;
+
+    let mut asm_file = options.output_file.clone();
+
+    // The command line parser has already made sure the target is supported
+    let target = get_target_info(&options.target).unwrap_or(&TARGETS[0]);
+
+    // If we're compiling the program to run it, everything we generate goes into a temporary directory
+    let temp_dir = env::temp_dir().join(format!("soup-{}", process::id()));
+    if options.command == Command::Run && !options.interpret {
+        if fs::create_dir_all(&temp_dir).is_err() {
+            throw_error(&format!(
+                "Could not create temporary directory '{}'",
+                temp_dir.display()
+            ));
+        }
+        asm_file = temp_dir.join("soup.asm").to_string_lossy().to_string();
     }
 
-    let code_file = &args[1];
-    let asm_file = &args[2];
+    // Make sure every file we're compiling actually exists before we try to scan any of them
+    for code_file in &options.input_files {
+        if !Path::new(code_file).is_file() {
+            throw_error(&format!("File '{}' does not exist", code_file));
+        }
+    }
+
+    // Scanner and parser (each file is scanned and parsed on its own thread)
+    let results = match scan_and_parse_files(
+        &options.input_files,
+        options.emit == Emit::Tokens,
+        options.verbose,
+    ) {
+        Ok(results) => results,
+        Err(msg) => {
+            throw_error(&msg);
+            return;
+        }
+    };
+
+    // With --save-temps, the result of each stage is also written next to the first file, as it's reached
+    let save_temp = |extension: &str, contents: &dyn Fn() -> String| {
+        if options.save_temps {
+            let file = get_save_temps_file(&options.input_files[0], extension);
+            log_verbose(options.verbose, &format!("Saving '{}'", file));
+            write_output(&file, &contents());
+        }
+    };
+
+    let format_tokens = || {
+        let token_strings: Vec<String> = results
+            .iter()
+            .flat_map(|result| result.tokens.iter().map(|token| token.display_string()))
+            .collect();
+        format!("{}\n", token_strings.join("\n"))
+    };
+    if options.emit == Emit::Tokens {
+        write_output(&asm_file, &format_tokens());
+        return;
+    }
+    save_temp("tokens", &format_tokens);
+    let num_tokens = results.iter().map(|result| result.tokens.len()).sum();
 
-    // Scanner
-    let tokens = scanner(code_file);
+    // Combine the global declarations of every file into a single program
+    // (remembering which file each function came from, for debug info)
+    let mut programs = vec![];
+    for result in results {
+        if let Some(program) = result.program {
+            programs.push((result.file, program));
+        }
+    }
+    let function_files = get_function_files(&programs);
+    let mut ast = match merge_programs(programs) {
+        Ok(ast) => ast,
+        Err(msg) => {
+            throw_error(&msg);
+            return;
+        }
+    };
+    save_temp("ast", &|| format_ast(ast.get_root(), 0));
 
-    // Parser
-    let mut ast = parser(&tokens);
+    if options.emit == Emit::Ast {
+        write_output(&asm_file, &format_ast(ast.get_root(), 0));
+        return;
+    }
 
     // Semantic checker
-    semantic_checker(&mut ast);
+    log_verbose(options.verbose, "Checking semantics");
+    let mut warnings = warnings;
+    let result = semantic_checker(&mut ast, &mut warnings);
+    print_warnings(&warnings.found);
+    if let Err(msg) = result {
+        throw_error(&msg);
+        return;
+    }
+    save_temp("ast.json", &|| {
+        format!("{}\n", format_ast_json(ast.get_root(), 0))
+    });
+
+    if options.print_ast {
+        print_ast(&ast);
+    }
+
+    if options.dump_symbols {
+        print!("{}", format_symbols(&ast, target));
+    }
+
+    // The interpreter runs the checked program as it is, so there's nothing more to generate
+    if options.interpret {
+        log_verbose(options.verbose, "Interpreting");
+        drop(pass_times);
+        let _ = io::stdout().flush();
+        process::exit(interpret_program(&ast, options.overflow_checks));
+    }
+
+    if options.emit == Emit::AstJson {
+        write_output(
+            &asm_file,
+            &format!("{}\n", format_ast_json(ast.get_root(), 0)),
+        );
+        return;
+    }
+
+    if options.emit == Emit::AstDot {
+        write_output(&asm_file, &format_ast_dot(ast.get_root()));
+        return;
+    }
+
+    // The C backend works straight from the checked AST, so the C compiler gets to do all of the optimizing
+    if options.emit == Emit::C {
+        let c = time_pass("C generation", || format_c(&ast, options.overflow_checks));
+        write_output(&asm_file, &c);
+        return;
+    }
+
+    // Lowering into three-address code
+    log_verbose(options.verbose, "Lowering to IR");
+    let mut program = time_pass("lowering", || {
+        lower_program(&ast, options.overflow_checks, options.zero_init)
+    });
+    set_source_files(&mut program, &function_files);
+
+    // Coverage instrumentation (which counts lines before they're optimized, so every line is counted)
+    if let Some(coverage_file) = &options.coverage {
+        log_verbose(options.verbose, "Instrumenting for coverage");
+        time_pass("coverage instrumentation", || {
+            instrument_coverage(&mut program, coverage_file)
+        });
+    }
+
+    // Execution tracing (which is also done before optimizing, so every statement is traced)
+    if options.trace_exec {
+        log_verbose(options.verbose, "Instrumenting for execution tracing");
+        time_pass("trace instrumentation", || instrument_trace(&mut program));
+    }
+
+    // Optimization (which leaves the program alone at -O0)
+    log_verbose(
+        options.verbose,
+        &format!("Optimizing at -O{}", options.opt_level),
+    );
+    optimize(&mut program, options.opt_level);
+    save_temp("ir", &|| format_program(&program, false));
+
+    if options.dump_frame {
+        print!("{}", format_frames(&program, options.opt_level, target));
+    }
+
+    if options.emit == Emit::Ir || options.emit == Emit::Cfg {
+        write_output(
+            &asm_file,
+            &format_program(&program, options.emit == Emit::Cfg),
+        );
+        return;
+    }
+
+    if options.emit == Emit::LlvmIr {
+        let llvm_ir = time_pass("LLVM IR generation", || format_llvm_ir(&program));
+        write_output(&asm_file, &llvm_ir);
+        return;
+    }
 
     // Code generation
-    code_gen(&asm_file, &mut ast);
-}
+    log_verbose(
+        options.verbose,
+        &format!("Generating assembly into '{}'", asm_file),
+    );
+    let asm = time_pass("code generation", || {
+        code_gen(
+            &program,
+            options.opt_level,
+            target,
+            options.debug_info,
+            options.asm_comments,
+        )
+    });
+    write_output(&asm_file, &asm);
+    save_temp("s", &|| asm.clone());
 
-pub fn throw_warning(msg: &str) {
-    eprintln!("Warning: {}", msg);
-}
+    if options.stats {
+        let stats = CompileStats {
+            tokens: num_tokens,
+            ast_nodes: count_nodes(ast.get_root(), &|_| true),
+            symbols: count_symbols(&ast),
+            string_literals: count_nodes(ast.get_root(), &|node| node.node_type == "string"),
+            ir_instrs: count_ir_instrs(&program),
+            asm_instrs: count_asm_instrs(&asm, target),
+            largest_frame: get_largest_frame(&program, options.opt_level, target),
+        };
+        eprint!("{}", format_stats(&stats));
+    }
+
+    if options.command == Command::Run {
+        // Only compiling the program is timed, not running it
+        drop(pass_times);
+        let exe_file = temp_dir.join("soup");
 
-pub fn throw_error(msg: &str) {
-    eprintln!("Error: {}", msg);
-    process::exit(1);
+        log_verbose(options.verbose, "Assembling and linking");
+        if let Err(msg) = assemble_and_link(Path::new(&asm_file), &exe_file, target) {
+            let _ = fs::remove_dir_all(&temp_dir);
+            throw_error(&msg);
+        }
+
+        log_verbose(
+            options.verbose,
+            &format!("Running '{}'", exe_file.display()),
+        );
+        let exit_code = run_executable(&exe_file, target);
+
+        // Clean up everything we generated before passing on the program's exit code
+        let _ = fs::remove_dir_all(&temp_dir);
+        match exit_code {
+            Ok(exit_code) => process::exit(exit_code),
+            Err(msg) => throw_error(&msg),
+        }
+    }
 }
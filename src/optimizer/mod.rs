@@ -0,0 +1,10 @@
+pub mod optimizer_constants;
+pub mod optimizer_dead_code;
+pub mod optimizer_driver;
+pub mod optimizer_inline;
+pub mod optimizer_passes;
+pub mod optimizer_unreachable;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -0,0 +1,205 @@
+use crate::ir::ir_cfg::Cfg;
+use crate::ir::ir_constants::{evaluate_binop, evaluate_unop};
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// CONSTANT PROPAGATION
+// -----------------------------------------------------------------
+
+// What we know about the value of a temporary at some point in a function
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+    // Nothing has been assigned to the temporary yet (along any path we've seen so far)
+    Undefined,
+    // The temporary always holds this value
+    Const(i32),
+    // The temporary could hold different values
+    Varying,
+}
+
+impl Value {
+    // Combine what we know about a temporary along two different paths
+    pub fn meet(self, other: Value) -> Value {
+        return match (self, other) {
+            (Value::Undefined, value) | (value, Value::Undefined) => value,
+            (Value::Const(a), Value::Const(b)) if a == b => Value::Const(a),
+            _ => Value::Varying,
+        };
+    }
+}
+
+// Work out which temporaries hold a known constant at each point in a function,
+// and replace any instruction that computes a known constant with that constant (and any branch on one with a jump)
+pub fn propagate_constants(func: &mut IrFunction) {
+    let mut cfg = Cfg::new(func);
+    let order = cfg.reverse_postorder();
+
+    // The parameters could be anything, but every other temporary starts off unassigned
+    let mut entry = vec![Value::Undefined; func.temps.len()];
+    for param in &func.params {
+        entry[*param] = Value::Varying;
+    }
+
+    // Keep passing over the blocks (in reverse postorder, so each block usually sees its predecessors first)
+    // until what we know at the end of each block stops changing, which happens once the values around every loop settle
+    let mut outs = vec![vec![Value::Undefined; func.temps.len()]; cfg.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for block in &order {
+            let mut values = get_block_entry(&cfg, &outs, &entry, *block);
+
+            for instr in &cfg.blocks[*block].instrs {
+                transfer(instr, &mut values);
+            }
+
+            if values != outs[*block] {
+                outs[*block] = values;
+                changed = true;
+            }
+        }
+    }
+
+    // Now that we know everything we can, replace anything with a known value by a constant
+    for block in &order {
+        let mut values = get_block_entry(&cfg, &outs, &entry, *block);
+
+        for instr in &mut cfg.blocks[*block].instrs {
+            transfer(instr, &mut values);
+
+            let replace = match instr {
+                Instr::Copy { dest, .. }
+                | Instr::BinOp { dest, .. }
+                | Instr::UnOp { dest, .. }
+                | Instr::Select { dest, .. } => match values[*dest] {
+                    Value::Const(value) => Some((*dest, value)),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            if let Some((dest, value)) = replace {
+                *instr = Instr::Const {
+                    dest: dest,
+                    value: value,
+                };
+            }
+
+            // A select on a known condition always picks the same value
+            if let Instr::Select {
+                dest,
+                cond,
+                if_true,
+                if_false,
+            } = instr
+            {
+                match values[*cond] {
+                    Value::Const(0) => {
+                        *instr = Instr::Copy {
+                            dest: *dest,
+                            src: *if_false,
+                        }
+                    }
+                    Value::Const(_) => {
+                        *instr = Instr::Copy {
+                            dest: *dest,
+                            src: *if_true,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            // A branch on a known condition always goes the same way
+            // (leaving the other way unreachable, unless something else jumps there)
+            if let Instr::Branch {
+                cond,
+                if_true,
+                if_false,
+            } = instr
+            {
+                match values[*cond] {
+                    Value::Const(0) => *instr = Instr::Jump(*if_false),
+                    Value::Const(_) => *instr = Instr::Jump(*if_true),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    func.body = cfg.to_body();
+}
+
+// Get what we know at the start of a block, which is everything we know at the end of every block leading to it
+// (along with what we know on entry to the function, for the entry block, which may also be the top of a loop)
+fn get_block_entry(
+    cfg: &Cfg,
+    outs: &Vec<Vec<Value>>,
+    entry: &Vec<Value>,
+    block: usize,
+) -> Vec<Value> {
+    let mut values = if block == 0 {
+        entry.clone()
+    } else {
+        vec![Value::Undefined; entry.len()]
+    };
+
+    for pred in &cfg.blocks[block].preds {
+        for (value, pred_value) in values.iter_mut().zip(&outs[*pred]) {
+            *value = value.meet(*pred_value);
+        }
+    }
+
+    return values;
+}
+
+// Update what we know about each temporary after the given instruction
+fn transfer(instr: &Instr, values: &mut Vec<Value>) {
+    match instr {
+        Instr::Const { dest, value } => values[*dest] = Value::Const(*value),
+        Instr::Copy { dest, src } => values[*dest] = values[*src],
+        Instr::BinOp { op, dest, lhs, rhs } => {
+            values[*dest] = match (values[*lhs], values[*rhs]) {
+                // Overflow checks are separate instructions by now, so the operator itself wraps around
+                (Value::Const(lhs), Value::Const(rhs)) => {
+                    match evaluate_binop(*op, lhs, rhs, false) {
+                        Ok(value) => Value::Const(value),
+                        Err(_) => Value::Varying,
+                    }
+                }
+                (Value::Varying, _) | (_, Value::Varying) => Value::Varying,
+                _ => Value::Undefined,
+            };
+        }
+        Instr::UnOp { op, dest, src } => {
+            values[*dest] = match values[*src] {
+                Value::Const(value) => match evaluate_unop(*op, value, false) {
+                    Ok(value) => Value::Const(value),
+                    Err(_) => Value::Varying,
+                },
+                value => value,
+            };
+        }
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => {
+            values[*dest] = match values[*cond] {
+                Value::Const(0) => values[*if_false],
+                Value::Const(_) => values[*if_true],
+                // Either value could be picked, so we only know the result if they're the same
+                Value::Varying => values[*if_true].meet(values[*if_false]),
+                Value::Undefined => Value::Undefined,
+            };
+        }
+        // Anything else produces a value we can't know until the program runs
+        _ => {
+            for def in instr.get_defs() {
+                values[def] = Value::Varying;
+            }
+        }
+    }
+}
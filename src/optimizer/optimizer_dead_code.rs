@@ -0,0 +1,28 @@
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// DEAD CODE ELIMINATION
+// -----------------------------------------------------------------
+
+// Remove every instruction whose only effect is setting temporaries that are never used
+// (such as the constants left behind once constant propagation has folded everything that used them)
+pub fn remove_dead_code(func: &mut IrFunction) {
+    // Removing an instruction can leave the instructions it used with nothing using them, so keep going until nothing changes
+    loop {
+        let mut used = vec![false; func.temps.len()];
+        for instr in &func.body {
+            for temp in instr.get_uses() {
+                used[temp] = true;
+            }
+        }
+
+        let num_instrs = func.body.len();
+        func.body.retain(|instr| {
+            instr.has_side_effects() || instr.get_defs().iter().any(|temp| used[*temp])
+        });
+
+        if func.body.len() == num_instrs {
+            return;
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ir::ir_data::IrProgram;
+use crate::optimizer::optimizer_inline::{get_inline_threshold, inline_functions};
+use crate::optimizer::optimizer_passes::*;
+use crate::passes::passes_timing::time_pass;
+use crate::passes::passes_trace::log_span;
+
+// -----------------------------------------------------------------
+// OPTIMIZER
+// -----------------------------------------------------------------
+
+// Optimize every function in the program, doing more work at higher optimization levels
+pub fn optimize(program: &mut IrProgram, opt_level: u32) {
+    // At -O0, the program is left exactly as it was lowered
+    if opt_level == 0 {
+        return;
+    }
+
+    // Inline first (which needs the whole program at once),
+    // so that constants passed to an inlined function can be propagated through its body
+    let threshold = get_inline_threshold(opt_level);
+    if threshold > 0 {
+        time_pass("inlining", || inline_functions(program, threshold));
+    }
+
+    let mut passes = get_function_passes();
+    for func in &mut program.functions {
+        let name = func.name.clone();
+        log_span(
+            || format!("function '{}'", name),
+            || {
+                for pass in &mut passes {
+                    time_pass(pass.get_name(), || pass.run(func));
+                }
+            },
+        );
+    }
+}
+
+// Get the passes run over each function, in the order they run in
+pub fn get_function_passes() -> Vec<Box<dyn FunctionPass>> {
+    return vec![Box::new(ConstantFoldingPass), Box::new(DeadCodePass)];
+}
@@ -0,0 +1,216 @@
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// INLINING
+// -----------------------------------------------------------------
+
+// Get the largest number of instructions a function can have for it to be inlined at the given optimization level
+pub fn get_inline_threshold(opt_level: u32) -> usize {
+    return match opt_level {
+        0 | 1 => 0,
+        2 => 12,
+        _ => 40,
+    };
+}
+
+// Replace every call to a small leaf function (one that doesn't call any other functions) with the body of that function
+pub fn inline_functions(program: &mut IrProgram, threshold: usize) {
+    // Only functions that are small enough, and don't call anything themselves
+    // (which also means they can't be recursive) are worth inlining
+    let inlinable: Vec<IrFunction> = program
+        .functions
+        .iter()
+        .filter(|func| is_inlinable(func, threshold))
+        .cloned()
+        .collect();
+
+    if inlinable.is_empty() {
+        return;
+    }
+
+    for func in &mut program.functions {
+        let body = std::mem::take(&mut func.body);
+
+        for instr in body {
+            let callee = match &instr {
+                Instr::Call { func: name, .. } => {
+                    inlinable.iter().find(|callee| callee.name == *name)
+                }
+                _ => None,
+            };
+
+            match (callee, instr) {
+                (Some(callee), Instr::Call { dests, args, .. }) => {
+                    inline_call(func, callee, &dests, &args)
+                }
+                (_, instr) => func.push(instr),
+            }
+        }
+    }
+}
+
+// Return true if the given function is small enough to be inlined, and doesn't call any other functions
+fn is_inlinable(func: &IrFunction, threshold: usize) -> bool {
+    let num_instrs = func
+        .body
+        .iter()
+        .filter(|instr| !matches!(instr, Instr::Label(_) | Instr::Line(_)))
+        .count();
+
+    return func.name != "main"
+        && num_instrs <= threshold
+        && !func
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Call { .. }));
+}
+
+// Add the body of the callee to the end of the caller, in place of a call to it
+fn inline_call(caller: &mut IrFunction, callee: &IrFunction, dests: &Vec<Temp>, args: &Vec<Temp>) {
+    // Every temporary, label and stack slot of the callee gets a fresh one in the caller
+    let temps: Vec<Temp> = callee
+        .temps
+        .iter()
+        .map(|temp_type| caller.new_temp(*temp_type))
+        .collect();
+    let labels: Vec<Label> = (0..callee.num_labels).map(|_| caller.new_label()).collect();
+    let slots: Vec<usize> = callee
+        .slots
+        .iter()
+        .map(|size| caller.new_slot(*size))
+        .collect();
+    let after_label = caller.new_label();
+
+    // The callee's variables keep their names (qualified by the callee's name) in dumps of the IR
+    for (temp, name) in &callee.temp_names {
+        caller.name_temp(temps[*temp], &format!("{}.{}", callee.name, name));
+    }
+    for (slot, name) in &callee.slot_names {
+        caller.name_slot(slots[*slot], &format!("{}.{}", callee.name, name));
+    }
+
+    // The arguments are passed in by copying them into the callee's parameters
+    for (param, arg) in callee.params.iter().zip(args) {
+        caller.push(Instr::Copy {
+            dest: temps[*param],
+            src: *arg,
+        });
+    }
+
+    for instr in &callee.body {
+        match instr {
+            // Returning copies the returned values into the call's destinations, and then carries on after the call
+            Instr::Return(values) => {
+                for (dest, value) in dests.iter().zip(values) {
+                    caller.push(Instr::Copy {
+                        dest: *dest,
+                        src: temps[*value],
+                    });
+                }
+                caller.push(Instr::Jump(after_label));
+            }
+            _ => caller.push(rename_instr(instr, &temps, &labels, &slots)),
+        }
+    }
+
+    caller.push(Instr::Label(after_label));
+}
+
+// Copy an instruction, replacing every temporary, label and stack slot it refers to with the one it maps to
+fn rename_instr(
+    instr: &Instr,
+    temps: &Vec<Temp>,
+    labels: &Vec<Label>,
+    slots: &Vec<usize>,
+) -> Instr {
+    let rename_all = |values: &Vec<Temp>| values.iter().map(|temp| temps[*temp]).collect();
+
+    return match instr {
+        Instr::Const { dest, value } => Instr::Const {
+            dest: temps[*dest],
+            value: *value,
+        },
+        Instr::Copy { dest, src } => Instr::Copy {
+            dest: temps[*dest],
+            src: temps[*src],
+        },
+        Instr::BinOp { op, dest, lhs, rhs } => Instr::BinOp {
+            op: *op,
+            dest: temps[*dest],
+            lhs: temps[*lhs],
+            rhs: temps[*rhs],
+        },
+        Instr::UnOp { op, dest, src } => Instr::UnOp {
+            op: *op,
+            dest: temps[*dest],
+            src: temps[*src],
+        },
+        Instr::Select {
+            dest,
+            cond,
+            if_true,
+            if_false,
+        } => Instr::Select {
+            dest: temps[*dest],
+            cond: temps[*cond],
+            if_true: temps[*if_true],
+            if_false: temps[*if_false],
+        },
+        Instr::SlotAddr { dest, slot } => Instr::SlotAddr {
+            dest: temps[*dest],
+            slot: slots[*slot],
+        },
+        Instr::GlobalAddr { dest, global } => Instr::GlobalAddr {
+            dest: temps[*dest],
+            global: *global,
+        },
+        Instr::ElementAddr {
+            dest,
+            base,
+            index,
+            scale,
+        } => Instr::ElementAddr {
+            dest: temps[*dest],
+            base: temps[*base],
+            index: temps[*index],
+            scale: *scale,
+        },
+        Instr::Load { dest, addr, width } => Instr::Load {
+            dest: temps[*dest],
+            addr: temps[*addr],
+            width: *width,
+        },
+        Instr::Store { src, addr, width } => Instr::Store {
+            src: temps[*src],
+            addr: temps[*addr],
+            width: *width,
+        },
+        Instr::Call { dests, func, args } => Instr::Call {
+            dests: rename_all(dests),
+            func: func.clone(),
+            args: rename_all(args),
+        },
+        Instr::Print { file, format, args } => Instr::Print {
+            file: file.map(|file| temps[file]),
+            format: *format,
+            args: rename_all(args),
+        },
+        Instr::Label(label) => Instr::Label(labels[*label]),
+        Instr::Jump(label) => Instr::Jump(labels[*label]),
+        Instr::Branch {
+            cond,
+            if_true,
+            if_false,
+        } => Instr::Branch {
+            cond: temps[*cond],
+            if_true: labels[*if_true],
+            if_false: labels[*if_false],
+        },
+        Instr::Return(values) => Instr::Return(rename_all(values)),
+        Instr::Panic { message, line } => Instr::Panic {
+            message: *message,
+            line: *line,
+        },
+        Instr::Line(line) => Instr::Line(*line),
+    };
+}
@@ -0,0 +1,73 @@
+use crate::ir::ir_data::IrFunction;
+use crate::optimizer::optimizer_constants::propagate_constants;
+use crate::optimizer::optimizer_dead_code::remove_dead_code;
+use crate::optimizer::optimizer_unreachable::remove_unreachable_code;
+use crate::passes::passes_trace::{log_event, LogLevel};
+
+// -----------------------------------------------------------------
+// FUNCTION PASSES
+// -----------------------------------------------------------------
+
+// A pass over a single function, which changes the function as a whole rather than walking over it like the
+// semantic passes walk over the AST, since its instructions aren't a tree
+pub trait FunctionPass {
+    // The name the pass is known by when it's timed (like "constant folding")
+    fn get_name(&self) -> &'static str;
+
+    fn run(&mut self, func: &mut IrFunction);
+}
+
+// Constant propagation, which turns branches on known conditions into jumps (leaving the other way unreachable),
+// followed by removing the unreachable code, repeated until nothing changes
+// (since once the unreachable code is gone, any values it assigned no longer get in the way of finding more constants)
+pub struct ConstantFoldingPass;
+
+impl FunctionPass for ConstantFoldingPass {
+    fn get_name(&self) -> &'static str {
+        return "constant folding";
+    }
+
+    fn run(&mut self, func: &mut IrFunction) {
+        let num_instrs = func.body.len();
+        let mut num_rounds = 0;
+
+        loop {
+            let before = func.body.clone();
+            propagate_constants(func);
+            remove_unreachable_code(func);
+            num_rounds += 1;
+            if func.body == before {
+                break;
+            }
+        }
+
+        log_event(LogLevel::Debug, || {
+            format!(
+                "{} instructions left of {}, after {} round(s)",
+                func.body.len(),
+                num_instrs,
+                num_rounds
+            )
+        });
+    }
+}
+
+pub struct DeadCodePass;
+
+impl FunctionPass for DeadCodePass {
+    fn get_name(&self) -> &'static str {
+        return "dead code elimination";
+    }
+
+    fn run(&mut self, func: &mut IrFunction) {
+        let num_instrs = func.body.len();
+        remove_dead_code(func);
+        log_event(LogLevel::Debug, || {
+            format!(
+                "removed {} of {} instructions",
+                num_instrs - func.body.len(),
+                num_instrs
+            )
+        });
+    }
+}
@@ -0,0 +1,28 @@
+use crate::ir::ir_cfg::Cfg;
+use crate::ir::ir_data::*;
+
+// -----------------------------------------------------------------
+// UNREACHABLE CODE ELIMINATION
+// -----------------------------------------------------------------
+
+// Remove every block that can't be reached from the start of the function,
+// such as the arm of an if statement whose condition is always false, or the error handling after a check that always passes
+pub fn remove_unreachable_code(func: &mut IrFunction) {
+    let mut cfg = Cfg::new(func);
+
+    let mut reachable = vec![false; cfg.blocks.len()];
+    for block in cfg.reverse_postorder() {
+        reachable[block] = true;
+    }
+
+    // Keep the blocks that are left in the same order, so that any fall throughs between them still work
+    cfg.blocks = cfg
+        .blocks
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| reachable[*i])
+        .map(|(_, block)| block)
+        .collect();
+
+    func.body = cfg.to_body();
+}
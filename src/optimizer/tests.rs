@@ -0,0 +1,295 @@
+mod tests {
+    use crate::ir::ir_data::*;
+    use crate::optimizer::optimizer_constants::propagate_constants;
+    use crate::optimizer::optimizer_dead_code::remove_dead_code;
+    use crate::optimizer::optimizer_driver::optimize;
+    use crate::optimizer::optimizer_inline::inline_functions;
+    use crate::optimizer::optimizer_unreachable::remove_unreachable_code;
+
+    // Create a function with the given number of int temporaries and the given body
+    fn new_func(num_temps: usize, body: Vec<Instr>) -> IrFunction {
+        let mut func = IrFunction::new("test_func");
+        func.temps = vec![IrType::Int; num_temps];
+        func.num_labels = 3;
+        func.body = body;
+        return func;
+    }
+
+    #[test]
+    fn test_propagate_constants_straight_line() {
+        // int x = 2; int y = x * 3; return y;
+        let mut func = new_func(
+            5,
+            vec![
+                Instr::Const { dest: 1, value: 2 },
+                Instr::Copy { dest: 0, src: 1 },
+                Instr::Const { dest: 3, value: 3 },
+                Instr::BinOp {
+                    op: BinOp::Mul,
+                    dest: 4,
+                    lhs: 0,
+                    rhs: 3,
+                },
+                Instr::Copy { dest: 2, src: 4 },
+                Instr::Return(vec![2]),
+            ],
+        );
+
+        propagate_constants(&mut func);
+        remove_dead_code(&mut func);
+
+        let expected = vec![Instr::Const { dest: 2, value: 6 }, Instr::Return(vec![2])];
+        assert_eq!(expected, func.body);
+    }
+
+    #[test]
+    fn test_propagate_constants_branches() {
+        // x = 1; if (c) { y = x + 1; } else { y = 2; } return y;
+        // (y is 2 either way, but c is a parameter, so it isn't known)
+        let mut func = new_func(
+            5,
+            vec![
+                Instr::Const { dest: 1, value: 1 },
+                Instr::Branch {
+                    cond: 0,
+                    if_true: 0,
+                    if_false: 1,
+                },
+                Instr::Label(0),
+                Instr::Const { dest: 3, value: 1 },
+                Instr::BinOp {
+                    op: BinOp::Add,
+                    dest: 2,
+                    lhs: 1,
+                    rhs: 3,
+                },
+                Instr::Jump(2),
+                Instr::Label(1),
+                Instr::Const { dest: 2, value: 2 },
+                Instr::Label(2),
+                Instr::Copy { dest: 4, src: 2 },
+                Instr::Return(vec![4]),
+            ],
+        );
+        func.params = vec![0];
+
+        propagate_constants(&mut func);
+
+        assert_eq!(Instr::Const { dest: 2, value: 2 }, func.body[4]);
+        assert_eq!(Instr::Const { dest: 4, value: 2 }, func.body[9]);
+    }
+
+    #[test]
+    fn test_propagate_constants_loop() {
+        // x = 0; while (x < 10) { x = x + 1; } return x;
+        // (x changes around the loop, so it can't be replaced by a constant)
+        let body = vec![
+            Instr::Const { dest: 0, value: 0 },
+            Instr::Label(0),
+            Instr::Const { dest: 1, value: 10 },
+            Instr::BinOp {
+                op: BinOp::Lt,
+                dest: 2,
+                lhs: 0,
+                rhs: 1,
+            },
+            Instr::Branch {
+                cond: 2,
+                if_true: 1,
+                if_false: 2,
+            },
+            Instr::Label(1),
+            Instr::Const { dest: 3, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 4,
+                lhs: 0,
+                rhs: 3,
+            },
+            Instr::Copy { dest: 0, src: 4 },
+            Instr::Jump(0),
+            Instr::Label(2),
+            Instr::Return(vec![0]),
+        ];
+        let mut func = new_func(5, body.clone());
+
+        propagate_constants(&mut func);
+
+        assert_eq!(body, func.body);
+    }
+
+    #[test]
+    fn test_fold_constant_branches() {
+        // if (true) { y = 1; } else { y = 2; } return y;
+        let mut func = new_func(
+            2,
+            vec![
+                Instr::Const { dest: 0, value: 1 },
+                Instr::Branch {
+                    cond: 0,
+                    if_true: 0,
+                    if_false: 1,
+                },
+                Instr::Label(0),
+                Instr::Const { dest: 1, value: 1 },
+                Instr::Jump(2),
+                Instr::Label(1),
+                Instr::Const { dest: 1, value: 2 },
+                Instr::Label(2),
+                Instr::Return(vec![1]),
+            ],
+        );
+
+        propagate_constants(&mut func);
+        remove_unreachable_code(&mut func);
+        remove_dead_code(&mut func);
+
+        // The branch is now a jump straight into the if block, and the else block is gone
+        let expected = vec![
+            Instr::Jump(0),
+            Instr::Label(0),
+            Instr::Const { dest: 1, value: 1 },
+            Instr::Jump(2),
+            Instr::Label(2),
+            Instr::Return(vec![1]),
+        ];
+        assert_eq!(expected, func.body);
+    }
+
+    #[test]
+    fn test_fold_constant_selects() {
+        // y = true ? x : 2; z = x > 0 ? 5 : 5; return y, z;
+        let mut func = new_func(
+            7,
+            vec![
+                Instr::Const { dest: 1, value: 1 },
+                Instr::Const { dest: 2, value: 2 },
+                Instr::Select {
+                    dest: 3,
+                    cond: 1,
+                    if_true: 0,
+                    if_false: 2,
+                },
+                Instr::Const { dest: 4, value: 5 },
+                Instr::BinOp {
+                    op: BinOp::Gt,
+                    dest: 5,
+                    lhs: 0,
+                    rhs: 4,
+                },
+                Instr::Select {
+                    dest: 6,
+                    cond: 5,
+                    if_true: 4,
+                    if_false: 4,
+                },
+                Instr::Return(vec![3, 6]),
+            ],
+        );
+        func.params = vec![0];
+
+        propagate_constants(&mut func);
+        remove_dead_code(&mut func);
+
+        // A select on a known condition picks the same value every time,
+        // and so does a select between two of the same constant
+        let expected = vec![
+            Instr::Copy { dest: 3, src: 0 },
+            Instr::Const { dest: 6, value: 5 },
+            Instr::Return(vec![3, 6]),
+        ];
+        assert_eq!(expected, func.body);
+    }
+
+    // Create a program with an add1 function (int add1(int x) { return x + 1; }) and a main function calling it
+    fn new_add1_program() -> IrProgram {
+        let mut add1 = new_func(
+            3,
+            vec![
+                Instr::Const { dest: 1, value: 1 },
+                Instr::BinOp {
+                    op: BinOp::Add,
+                    dest: 2,
+                    lhs: 0,
+                    rhs: 1,
+                },
+                Instr::Return(vec![2]),
+            ],
+        );
+        add1.name = String::from("add1");
+        add1.params = vec![0];
+        add1.returns = vec![IrType::Int];
+        add1.num_labels = 0;
+
+        // return add1(5);
+        let mut main = new_func(
+            2,
+            vec![
+                Instr::Const { dest: 0, value: 5 },
+                Instr::Call {
+                    dests: vec![1],
+                    func: String::from("add1"),
+                    args: vec![0],
+                },
+                Instr::Return(vec![1]),
+            ],
+        );
+        main.name = String::from("main");
+        main.returns = vec![IrType::Int];
+        main.num_labels = 0;
+
+        let mut program = IrProgram::new();
+        program.functions = vec![add1, main];
+        return program;
+    }
+
+    #[test]
+    fn test_inline_functions() {
+        let mut program = new_add1_program();
+        inline_functions(&mut program, 10);
+
+        // The callee's temporaries are renumbered after the caller's, and its return jumps to just after the call
+        let expected = vec![
+            Instr::Const { dest: 0, value: 5 },
+            Instr::Copy { dest: 2, src: 0 },
+            Instr::Const { dest: 3, value: 1 },
+            Instr::BinOp {
+                op: BinOp::Add,
+                dest: 4,
+                lhs: 2,
+                rhs: 3,
+            },
+            Instr::Copy { dest: 1, src: 4 },
+            Instr::Jump(0),
+            Instr::Label(0),
+            Instr::Return(vec![1]),
+        ];
+        assert_eq!(expected, program.functions[1].body);
+        assert_eq!(5, program.functions[1].temps.len());
+        assert_eq!(1, program.functions[1].num_labels);
+    }
+
+    #[test]
+    fn test_inline_threshold() {
+        // add1 has 3 instructions, so it is too big to inline with a threshold of 2
+        let mut program = new_add1_program();
+        let before = program.clone();
+        inline_functions(&mut program, 2);
+        assert_eq!(before, program);
+
+        // And inlining isn't done at all at -O1
+        let mut program = new_add1_program();
+        optimize(&mut program, 1);
+        assert!(program.functions[1]
+            .body
+            .iter()
+            .any(|instr| matches!(instr, Instr::Call { .. })));
+
+        // But at -O2 the call is inlined, and the result is propagated all the way to the return
+        let mut program = new_add1_program();
+        optimize(&mut program, 2);
+        assert!(program.functions[1]
+            .body
+            .contains(&Instr::Const { dest: 1, value: 6 }));
+    }
+}
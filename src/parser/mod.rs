@@ -1,6 +1,9 @@
 pub mod parser_data;
 pub mod parser_driver;
 pub mod parser_grammar;
+pub mod parser_incremental;
+pub mod parser_trivia;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
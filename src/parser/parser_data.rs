@@ -1,22 +1,231 @@
 use std::cell::RefCell;
-use std::io;
-use std::io::Write;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, Index, IndexMut};
 use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::scanner::scanner_data::{ScanError, TokenType};
+use crate::semantic::semantic_data::{Symbol, Type};
+
+// The binary operators, along with how tightly each one binds (its precedence, where operators with
+// a higher precedence are parsed first) and the type of the AST node it's parsed into
+pub const BINARY_OPERATORS: &[(TokenType, u32, &str)] = &[
+    (TokenType::OR, 0, "||"),
+    (TokenType::AND, 1, "&&"),
+    (TokenType::EQ, 2, "=="),
+    (TokenType::NEQ, 2, "!="),
+    (TokenType::LT, 3, "<"),
+    (TokenType::GT, 3, ">"),
+    (TokenType::LEQ, 3, "<="),
+    (TokenType::GEQ, 3, ">="),
+    (TokenType::PLUS, 4, "+"),
+    (TokenType::MINUS, 4, "-"),
+    (TokenType::MULT, 5, "*"),
+    (TokenType::DIV, 5, "/"),
+    (TokenType::MOD, 5, "%"),
+];
+
+// Get the precedence of a binary operator and the type of the AST node it's parsed into,
+// or None if the token isn't a binary operator
+pub fn get_binary_operator(token_type: TokenType) -> Option<(u32, &'static str)> {
+    return BINARY_OPERATORS
+        .iter()
+        .find(|(operator, _, _)| *operator == token_type)
+        .map(|(_, precedence, node_type)| (*precedence, *node_type));
+}
+
+// Check if the type of an AST node is one of the binary operators (and not an assignment)
+pub fn is_binary_operator(node_type: &str) -> bool {
+    return BINARY_OPERATORS
+        .iter()
+        .any(|(_, _, operator)| *operator == node_type);
+}
+
+// A syntax error found while parsing, which stops the parse, along with the line it was found on
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line_num: i32,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+// An error found while scanning stops the parse too, so it can be given back in place of a syntax error
+impl From<ScanError> for ParseError {
+    fn from(error: ScanError) -> ParseError {
+        return ParseError {
+            message: error.message,
+            line_num: error.line_num,
+        };
+    }
+}
+
+// The position of a node in the arena of the AST it belongs to
+pub type NodeId = usize;
+
+// An abstract syntax tree, whose nodes are all kept together in a single arena (so they're never copied
+// or moved around once they've been created), with each node referring to its children by their position in it
+#[derive(Debug, Clone)]
+pub struct AST {
+    pub nodes: Vec<ASTNode>,
+    pub root: NodeId,
+    // The tokens each statement, declaration and block was parsed from, as the position of its first token
+    // and of the token just after its last, which is how the comments around it are found when parsing losslessly
+    pub spans: HashMap<NodeId, (usize, usize)>,
+    // When the program was merged from several files, the file each one was parsed from, along with the position
+    // of its first node (every node from then on, up to the first node of the next file, came from the same file)
+    pub files: Vec<(NodeId, String)>,
+}
+
+impl Default for AST {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl AST {
+    pub fn new() -> AST {
+        return AST {
+            nodes: vec![],
+            root: 0,
+            spans: HashMap::new(),
+            files: vec![],
+        };
+    }
+
+    // Add a node (whose children have already been added) to the arena, returning its position in it
+    pub fn add_node(&mut self, node: ASTNode) -> NodeId {
+        self.nodes.push(node);
+        return self.nodes.len() - 1;
+    }
+
+    // Move every node of another AST into this one, returning the new position of its root
+    // (its spans aren't kept, since they're positions in the tokens of a different file)
+    pub fn append(&mut self, other: AST) -> NodeId {
+        let offset = self.nodes.len();
+
+        for mut node in other.nodes {
+            for child in &mut node.children {
+                *child += offset;
+            }
+            self.nodes.push(node);
+        }
+
+        return other.root + offset;
+    }
+
+    // Get the file the node at the given position was parsed from,
+    // if the program was merged from several files (so there's more than one it could have come from)
+    pub fn get_file(&self, id: NodeId) -> Option<&str> {
+        if self.files.len() < 2 {
+            return None;
+        }
+
+        return self
+            .files
+            .iter()
+            .rev()
+            .find(|(first_node, _)| *first_node <= id)
+            .map(|(_, file)| file.as_str());
+    }
+
+    // Get the node at the given position, along with the rest of the tree so that its children can be reached
+    pub fn get(&self, id: NodeId) -> NodeRef<'_> {
+        return NodeRef { ast: self, id: id };
+    }
+
+    pub fn get_root(&self) -> NodeRef<'_> {
+        return self.get(self.root);
+    }
+}
+
+impl Index<NodeId> for AST {
+    type Output = ASTNode;
+
+    fn index(&self, id: NodeId) -> &ASTNode {
+        return &self.nodes[id];
+    }
+}
 
-use crate::semantic::semantic_data::Symbol;
+impl IndexMut<NodeId> for AST {
+    fn index_mut(&mut self, id: NodeId) -> &mut ASTNode {
+        return &mut self.nodes[id];
+    }
+}
+
+// A comment kept in an AST that was parsed losslessly, with its "//" (but not the end of its line)
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comment {
+    pub text: String,
+    // Whether there's a blank line between the comment and whatever comes before it
+    pub blank_line_before: bool,
+}
+
+// The comments and blank lines around a statement or declaration, which are only attached to it
+// when the program is parsed losslessly (see parse_program_lossless)
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct NodeTrivia {
+    // The comments on lines of their own just before the statement
+    pub leading_comments: Vec<Comment>,
+    // Whether there's a blank line just before the statement (after any comments in front of it)
+    pub blank_line_before: bool,
+    // The comment at the end of the statement's last line
+    pub trailing_comment: Option<Comment>,
+    // The comments somewhere in the middle of the statement, but not inside any statement nested in it
+    pub inner_comments: Vec<Comment>,
+    // The comments after the last statement of a block (or the last declaration of a program),
+    // before its close brace (or the end of the file)
+    pub closing_comments: Vec<Comment>,
+}
+
+impl NodeTrivia {
+    // Get the documentation comment of a global declaration, which is the "///" comments on the lines just before it
+    // (with no blank line between them, or after them), without their slashes or the space after them.
+    // Like in Rust, a comment starting with four slashes is just an ordinary comment
+    pub fn get_doc_comment(&self) -> Option<String> {
+        if self.blank_line_before {
+            return None;
+        }
+
+        let mut lines = vec![];
+        for comment in self.leading_comments.iter().rev() {
+            match comment.text.strip_prefix("///") {
+                Some(line) if !line.starts_with('/') => {
+                    lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end())
+                }
+                _ => break,
+            }
+            if comment.blank_line_before {
+                break;
+            }
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        return Some(lines.join("\n"));
+    }
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct ASTNode {
     pub node_type: String,
-    pub attr: Option<String>,
+    pub attr: Option<Arc<str>>,
     pub line_num: Option<i32>,
-    pub type_sig: Option<String>,
+    pub type_sig: Option<Type>,
     pub sym: Option<Rc<RefCell<Symbol>>>,
-    pub children: Vec<ASTNode>,
+    pub children: Vec<NodeId>,
+    pub trivia: Option<Box<NodeTrivia>>,
 }
 
 impl ASTNode {
-    pub fn new(node_type: &str, attr: Option<String>, line_num: Option<i32>) -> ASTNode {
+    pub fn new(node_type: &str, attr: Option<Arc<str>>, line_num: Option<i32>) -> ASTNode {
         return ASTNode {
             node_type: String::from(node_type),
             attr: attr,
@@ -24,6 +233,7 @@ impl ASTNode {
             type_sig: None,
             sym: None,
             children: vec![],
+            trivia: None,
         };
     }
 
@@ -31,35 +241,16 @@ impl ASTNode {
     // Adders
     // ---------------------------------------------------------------------------------------
 
-    pub fn add_child(&mut self, new_node: ASTNode) {
+    pub fn add_child(&mut self, new_node: NodeId) {
         self.children.push(new_node);
     }
 
-    pub fn add_children(&mut self, new_nodes: Vec<ASTNode>) {
+    pub fn add_children(&mut self, new_nodes: Vec<NodeId>) {
         for node in new_nodes {
             self.children.push(node);
         }
     }
 
-    pub fn add_child_to_front(&mut self, new_node: ASTNode) {
-        // Get the current vector of children
-        let children = &self.children;
-
-        // Create a new vector for children
-        let mut new_children = Vec::new();
-
-        // Insert the new node at the beginning of this new vector
-        new_children.push(new_node);
-
-        // Add all of the old children after the new node
-        for child in children {
-            new_children.push(child.clone());
-        }
-
-        // Replace the old children vector with the new one
-        self.children = new_children;
-    }
-
     pub fn add_sym(&mut self, new_sym: Rc<RefCell<Symbol>>) {
         self.sym = Some(new_sym);
     }
@@ -68,35 +259,28 @@ impl ASTNode {
     // Getters
     // ---------------------------------------------------------------------------------------
 
-    pub fn get_attr(&self) -> String {
+    pub fn get_attr(&self) -> Arc<str> {
         match &self.attr {
-            None => String::from("ATTR"), // Should never happen, indicates an error on my end
-            Some(attr) => attr.clone(),
+            None => Arc::from("ATTR"), // Should never happen, indicates an error on my end
+            Some(attr) => Arc::clone(attr),
         }
     }
 
     pub fn get_line_num(&self) -> i32 {
-        match self.line_num {
-            None => 0, // Should never happen, indicates an error on my end
-            Some(line_num) => line_num,
-        }
+        // None should never happen, and indicates an error on my end
+        return self.line_num.unwrap_or_default();
     }
 
-    pub fn get_type(&self) -> String {
+    pub fn get_type(&self) -> Type {
         match &self.type_sig {
             None => {
                 match &self.sym {
-                    None => {
-                        if self.node_type == "int"
-                            || self.node_type == "bool"
-                            || self.node_type == "string"
-                        {
-                            self.node_type.clone()
-                        } else {
-                            String::from("NO TYPE") // Should never happen, indicates an error on my end
-                        }
-                    }
-                    Some(sym) => sym.borrow().returns.clone(),
+                    // Type nodes (and string literals) are named after their type
+                    None => match Type::parse(&self.node_type) {
+                        None => Type::Unknown, // Should never happen, indicates an error on my end
+                        Some(type_sig) => type_sig,
+                    },
+                    Some(sym) => sym.borrow().get_returns(),
                 }
             }
             Some(type_sig) => type_sig.clone(),
@@ -106,7 +290,7 @@ impl ASTNode {
     pub fn get_func_name(&self) -> String {
         return match &self.sym {
             None => String::from("FUNC"), // Should never happen, indicates an error on my end
-            Some(sym) => sym.borrow().name.clone(),
+            Some(sym) => sym.borrow().name.to_string(),
         };
     }
 
@@ -114,9 +298,8 @@ impl ASTNode {
         return match &self.sym {
             // Should never happen, indicates an error on my end
             None => Rc::new(RefCell::new(Symbol::new(
-                String::from("SYMBOL"),
-                String::from("SYMBOL"),
-                String::from("SYMBOL"),
+                Arc::from("SYMBOL"),
+                Type::Unknown,
             ))),
             Some(sym) => Rc::clone(sym),
         };
@@ -126,54 +309,17 @@ impl ASTNode {
     // Misc
     // ---------------------------------------------------------------------------------------
 
-    // Generate the function signature for a function node
-    pub fn get_func_sig(&self) -> String {
-        // Open func sig
-        let mut func_sig = String::from("f(");
-
-        // Loop through parameters
-        let mut param_num = 0;
-        for param in &self.children[1].children {
-            param_num += 1;
-
-            // Function parameters must be comma separated, so any parameter after the first must be prefixed by ", "
-            if param_num > 1 {
-                func_sig.push_str(", ");
-            }
-
-            // Add parameter/argument type to func sig
-            func_sig.push_str(&&param.children[0].get_type());
-        }
-
-        // Close func sig
-        func_sig.push_str(")");
-
-        return func_sig;
-    }
-
-    // Check if the current node or any of its children are a return node
-    pub fn has_nonempty_return(&self) -> bool {
-        // If the current node is a return node, return true
-        if self.node_type == "return" && self.get_type() != "void" {
-            return true;
-        } else {
-            // Otherwise, if any of the children are or have a return node, return true
-            for child in &self.children {
-                if child.has_nonempty_return() {
-                    return true;
-                }
-            }
-
-            // If none of the children are or have a return node, return false
-            return false;
-        }
-    }
-
     // Check if the current (variable declaration) node has an assignment attached to it
     pub fn has_assignment(&self) -> bool {
         return self.children.len() == 3;
     }
 
+    // Check if the current node is a loop, which break and continue statements can refer to
+    // (by the label it was given, which is kept as its attr)
+    pub fn is_loop(&self) -> bool {
+        return self.node_type == "while" || self.node_type == "loop" || self.node_type == "repeat";
+    }
+
     // Format the data contained in this node
     pub fn display_string(&self) -> String {
         let mut display_string = format!("{{{}", self.node_type);
@@ -210,30 +356,10 @@ impl ASTNode {
                     ", sym: {{name: {}, sig: {}, returns: {}",
                     symbol_entry.borrow().name,
                     symbol_entry.borrow().type_sig,
-                    symbol_entry.borrow().returns
+                    symbol_entry.borrow().get_returns()
                 );
 
-                // Add label if it exists
-                let label_string = match &symbol_entry.borrow().label {
-                    None => String::from(""),
-                    Some(label) => {
-                        format!(", label: {}", label)
-                    }
-                };
-
-                sym_string.push_str(&label_string);
-
-                // Add memory address if it exists
-                let addr_string = match &symbol_entry.borrow().addr {
-                    None => String::from(""),
-                    Some(addr) => {
-                        format!(", addr: {}", addr)
-                    }
-                };
-
-                sym_string.push_str(&addr_string);
-
-                sym_string.push_str("}");
+                sym_string.push('}');
 
                 sym_string
             }
@@ -242,37 +368,316 @@ impl ASTNode {
         display_string.push_str(&sym);
 
         // Print node close brace
-        display_string.push_str("}");
+        display_string.push('}');
 
         return display_string;
     }
 }
 
-// Print the current node, called by print_ast
-fn print_node(node: &ASTNode, num_tabs: i32) {
-    // Add the correct indentation by adding num_tabs tabs
-    for _i in 0..num_tabs {
-        print!("\t"); // Print a tab without a newline at the end
-        io::stdout().flush().unwrap(); //
+// A node of an AST, along with the AST it belongs to (so that its children can be reached from it)
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    pub ast: &'a AST,
+    pub id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    // Get the child of this node at the given position
+    pub fn child(&self, i: usize) -> NodeRef<'a> {
+        return self.ast.get(self.ast[self.id].children[i]);
     }
 
-    // Print current node
-    println!("{}", node.display_string());
+    // Get each of the children of this node, in order
+    pub fn children(&self) -> impl DoubleEndedIterator<Item = NodeRef<'a>> + ExactSizeIterator {
+        let ast = self.ast;
+        return ast[self.id]
+            .children
+            .iter()
+            .map(move |child| ast.get(*child));
+    }
+
+    // Get where this node is in the program, for an error or warning about it: its line,
+    // along with the file it's in if the program was merged from several files
+    pub fn get_location(&self) -> String {
+        return match self.ast.get_file(self.id) {
+            Some(file) => format!("{}: Line {}", file, self.get_line_num()),
+            None => format!("Line {}", self.get_line_num()),
+        };
+    }
+
+    // Get the types of the parameters of a function node (or of the arguments of a function call node)
+    pub fn get_param_types(&self) -> Vec<Type> {
+        let mut param_types = vec![];
+
+        for param in self.child(1).children() {
+            param_types.push(param.child(0).get_type());
+        }
+
+        return param_types;
+    }
+
+    // Get this node and every node below it, each one before its children (without recursing,
+    // since a long chain of binary operators makes a tree far deeper than the stack allows)
+    pub fn descendants(&self) -> Vec<NodeRef<'a>> {
+        let mut nodes = vec![];
+        let mut stack = vec![*self];
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children().rev());
+        }
+        return nodes;
+    }
+
+    // Get the binary operators down the left hand side of this node, starting with this node itself (or none,
+    // if it isn't a binary operator). A chain like 1 + 1 + ... + 1 is a left-leaning tree as deep as it is long,
+    // so anything that walks an expression goes down the chain in a loop, starting from the left hand side
+    // of its last operator, and only recurses into the right hand side of each one
+    pub fn get_operator_chain(&self) -> Vec<NodeRef<'a>> {
+        let mut chain = vec![];
+        let mut node = *self;
+        while is_binary_operator(&node.node_type) {
+            chain.push(node);
+            node = node.child(0);
+        }
+        return chain;
+    }
+
+    // Check if the current node or any of its children are a return node
+    pub fn has_nonempty_return(&self) -> bool {
+        return self
+            .descendants()
+            .iter()
+            .any(|node| node.node_type == "return" && node.get_type() != Type::Void);
+    }
+}
+
+impl Deref for NodeRef<'_> {
+    type Target = ASTNode;
+
+    fn deref(&self) -> &ASTNode {
+        return &self.ast[self.id];
+    }
+}
+
+// Two nodes are equal if they hold the same data and their children are equal, wherever they are in their arenas
+// (which is the case if every node below them, taken in the same order, holds the same data and has as many children)
+impl PartialEq for NodeRef<'_> {
+    fn eq(&self, other: &NodeRef) -> bool {
+        let (nodes, other_nodes) = (self.descendants(), other.descendants());
+        return nodes.len() == other_nodes.len()
+            && nodes.iter().zip(&other_nodes).all(|(a, b)| {
+                a.node_type == b.node_type
+                    && a.attr == b.attr
+                    && a.line_num == b.line_num
+                    && a.type_sig == b.type_sig
+                    && a.sym == b.sym
+                    && a.children.len() == b.children.len()
+            });
+    }
+}
+
+impl fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "\n{}", format_ast(*self, 0));
+    }
+}
+
+// Format the current node and all of its children, one per line, indented by their depth in the tree
+pub fn format_ast(node: NodeRef, num_tabs: usize) -> String {
+    let mut ast_string = String::new();
 
-    // Call recursively on the nodes children
-    for child in &node.children {
-        print_node(child, num_tabs + 1);
+    // The nodes still to be formatted, along with their depth, are kept on a stack rather than recursing
+    let mut stack = vec![(node, num_tabs)];
+    while let Some((node, num_tabs)) = stack.pop() {
+        // Add the correct indentation by adding num_tabs tabs, followed by the current node
+        ast_string.push_str(&"\t".repeat(num_tabs));
+        ast_string.push_str(&node.display_string());
+        ast_string.push('\n');
+
+        stack.extend(node.children().rev().map(|child| (child, num_tabs + 1)));
     }
+
+    return ast_string;
 }
 
 // Small wrapper to improve the printing quality of the AST print
 // and abstract away the need to explicitly give the initial tab level
-pub fn print_ast(node: &ASTNode) {
+pub fn print_ast(ast: &AST) {
+    let node = ast.get_root();
+
     println!("\n--------------------------------------------------------------------------------------------------------------------------------------------------------------");
     println!("AST: beginning from {{{}}} node", node.node_type);
     println!("--------------------------------------------------------------------------------------------------------------------------------------------------------------");
 
-    print_node(node, 0);
+    print!("{}", format_ast(node, 0));
 
     println!("--------------------------------------------------------------------------------------------------------------------------------------------------------------\n");
 }
+
+// Format the given node and all of its children as JSON, indented by the given number of levels, for tools
+// that want to read the tree without parsing the format above. Each node is an object with its kind, attr,
+// line number and type (any of which may be null), the symbol table entry attached to it (if any) and its children
+pub fn format_ast_json(node: NodeRef, indent: usize) -> String {
+    let mut json = String::new();
+
+    // The nodes still to be formatted (along with how far they're indented), and the text that goes between them,
+    // are kept on a stack rather than recursing
+    let mut stack = vec![JsonPart::Node(node, indent)];
+    while let Some(part) = stack.pop() {
+        let (node, indent) = match part {
+            JsonPart::Text(text) => {
+                json.push_str(&text);
+                continue;
+            }
+            JsonPart::Node(node, indent) => (node, indent),
+        };
+        let outer = "  ".repeat(indent);
+        let inner = "  ".repeat(indent + 1);
+
+        json.push_str("{\n");
+        for field in get_json_fields(node) {
+            json.push_str(&format!("{}{},\n", inner, field));
+        }
+
+        if node.children.is_empty() {
+            json.push_str(&format!("{}\"children\": []\n{}}}", inner, outer));
+            continue;
+        }
+
+        // Each child is formatted once everything before it has been, followed by the end of this node
+        json.push_str(&format!("{}\"children\": [\n", inner));
+        stack.push(JsonPart::Text(format!("\n{}]\n{}}}", inner, outer)));
+        for (i, child) in node.children().enumerate().rev() {
+            stack.push(JsonPart::Node(child, indent + 2));
+            let separator = if i == 0 { "" } else { ",\n" };
+            stack.push(JsonPart::Text(format!("{}{}  ", separator, inner)));
+        }
+    }
+
+    return json;
+}
+
+// A part of the JSON for a tree that's still to be added: a node (indented by the given number of levels),
+// or the text that goes between nodes
+enum JsonPart<'a> {
+    Node(NodeRef<'a>, usize),
+    Text(String),
+}
+
+// Get every field of the JSON object for a node apart from its children
+fn get_json_fields(node: NodeRef) -> Vec<String> {
+    let mut fields = vec![];
+
+    fields.push(format!("\"kind\": {}", format_json_string(&node.node_type)));
+    fields.push(format!(
+        "\"attr\": {}",
+        node.attr
+            .as_ref()
+            .map_or(String::from("null"), |attr| format_json_string(attr))
+    ));
+    fields.push(format!(
+        "\"line\": {}",
+        node.line_num
+            .map_or(String::from("null"), |line_num| line_num.to_string())
+    ));
+    fields.push(format!(
+        "\"type\": {}",
+        node.type_sig
+            .as_ref()
+            .map_or(String::from("null"), |type_sig| {
+                format_json_string(&type_sig.to_string())
+            })
+    ));
+
+    let sym = match &node.sym {
+        None => String::from("null"),
+        Some(sym) => {
+            let sym = sym.borrow();
+            let array_dims: Vec<String> =
+                sym.array_dims.iter().map(|dim| dim.to_string()).collect();
+            format!(
+                "{{\"name\": {}, \"sig\": {}, \"returns\": {}, \"array_dims\": [{}], \"line\": {}}}",
+                format_json_string(&sym.name),
+                format_json_string(&sym.type_sig.to_string()),
+                format_json_string(&sym.get_returns().to_string()),
+                array_dims.join(", "),
+                sym.line_num
+                    .map_or(String::from("null"), |line_num| line_num.to_string())
+            )
+        }
+    };
+    fields.push(format!("\"symbol\": {}", sym));
+
+    return fields;
+}
+
+// Format a string as a JSON string literal, escaping anything that can't appear in one as it is
+pub fn format_json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+
+    for char in text.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if (char as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+
+    json.push('"');
+    return json;
+}
+
+// Format the tree below the given node as a Graphviz DOT graph, with each node labelled with its kind,
+// and its attr and type if it has them (e.g. "dot -Tsvg ast.dot -o ast.svg" draws it). Every node is named
+// after its position in the arena, and its children are kept in order from left to right
+pub fn format_ast_dot(node: NodeRef) -> String {
+    let mut dot = String::from(
+        "digraph ast {\n    ordering=out;\n    node [shape=box, fontname=\"monospace\"];\n\n",
+    );
+    for node in node.descendants() {
+        add_dot_node(node, &mut dot);
+    }
+    dot.push_str("}\n");
+    return dot;
+}
+
+// Add the given node to a DOT graph, along with the edges to its children
+fn add_dot_node(node: NodeRef, dot: &mut String) {
+    let mut label = node.node_type.clone();
+    if let Some(attr) = &node.attr {
+        label.push_str(&format!("\nattr: {}", attr));
+    }
+    if let Some(type_sig) = &node.type_sig {
+        label.push_str(&format!("\ntype: {}", type_sig));
+    }
+
+    dot.push_str(&format!(
+        "    n{} [label={}];\n",
+        node.id,
+        format_dot_string(&label)
+    ));
+    for child in node.children() {
+        dot.push_str(&format!("    n{} -> n{};\n", node.id, child.id));
+    }
+}
+
+// Format a string as a DOT string literal, where a newline starts a new line of a label
+fn format_dot_string(text: &str) -> String {
+    let mut dot = String::from("\"");
+
+    for char in text.chars() {
+        match char {
+            '"' => dot.push_str("\\\""),
+            '\\' => dot.push_str("\\\\"),
+            '\n' => dot.push_str("\\n"),
+            char => dot.push(char),
+        }
+    }
+
+    dot.push('"');
+    return dot;
+}
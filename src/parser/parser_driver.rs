@@ -1,13 +1,246 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::thread;
+
 use crate::parser::parser_data::*;
 use crate::parser::parser_grammar::*;
-use crate::scanner::scanner_data::Token;
+use crate::parser::parser_trivia::attach_trivia;
+use crate::passes::passes_timing::time_pass;
+use crate::passes::passes_trace::{log_event, LogLevel};
+use crate::scanner::scanner_data::{Token, TokenType, Trivia};
+use crate::scanner::scanner_driver::scan_file;
+use crate::{log_verbose, throw_error};
 
 // -----------------------------------------------------------------
 // PARSER
 // -----------------------------------------------------------------
 
-pub fn parser(tokens: &Vec<Token>) -> ASTNode {
-    start_(tokens, &mut 0)
+// Main parser function, returns the AST built from the tokens of a compilee file
+// (exiting with an error if they can't be parsed)
+pub fn parser(tokens: &[Token]) -> AST {
+    match parse_file(tokens) {
+        Ok(ast) => return ast,
+        Err(error) => {
+            throw_error(&error.to_string());
+            return AST::new();
+        }
+    }
+}
+
+// Parse the tokens of a compilee file into an AST, giving back an error (rather than exiting) if they can't be parsed
+pub fn parse_file(tokens: &[Token]) -> Result<AST, ParseError> {
+    let ast = parse_program(tokens)?;
+    log_event(LogLevel::Debug, || {
+        format!("parsed {} nodes", ast.nodes.len())
+    });
+    return Ok(ast);
+}
+
+// Parse the tokens of a whole program (ending with an EOF token, as scan_str gives back) into an AST. Like parse_expression,
+// this is one of the parser's stable entry points: it doesn't exit the process, so anything that needs to parse soup code
+// can call it directly, and tokens that can't be parsed give back an error saying what's wrong and on which line
+pub fn parse_program(tokens: &[Token]) -> Result<AST, ParseError> {
+    check_tokens(tokens)?;
+    let mut ast = AST::new();
+    ast.root = start_(tokens, &mut 0, &mut ast)?;
+    return Ok(ast);
+}
+
+// Parse the tokens of a whole program that was scanned losslessly (along with the trivia in front of each of them,
+// as scan_str_lossless gives back) into an AST, with the comments and blank lines around each statement and declaration
+// attached to it, so that tooling like the formatter can keep them
+pub fn parse_program_lossless(tokens: &[Token], trivia: &[Trivia]) -> Result<AST, ParseError> {
+    let mut ast = parse_program(tokens)?;
+    attach_trivia(&mut ast, tokens, trivia);
+    return Ok(ast);
+}
+
+// Parse the tokens of a single expression (ending with an EOF token, as scan_str gives back) into an AST
+// whose root is the expression, so a fragment of soup code (like a line typed into a REPL) can be parsed on its own
+pub fn parse_expression(tokens: &[Token]) -> Result<AST, ParseError> {
+    check_tokens(tokens)?;
+    let mut ast = AST::new();
+    let mut current = 0;
+    ast.root = expression_(tokens, &mut current, &mut ast)?;
+
+    // The expression has to make up the whole fragment
+    let current_token = peek_token(tokens, current);
+    if current_token.token_type != TokenType::EOF {
+        return Err(ParseError {
+            message: format!(
+                "Syntax Error on line {}: unexpected '{}' after the end of the expression",
+                current_token.line_num, current_token.lexeme
+            ),
+            line_num: current_token.line_num,
+        });
+    }
+
+    return Ok(ast);
+}
+
+// The result of scanning (and possibly parsing) a single file
+pub struct FrontEndResult {
+    pub file: String,
+    pub tokens: Vec<Token>,
+    pub program: Option<AST>,
+}
+
+// SAFETY: An AST is only kept from being Send by the symbol table entries attached to it,
+// which are shared through Rcs. The parser never attaches symbols (that's the job of the semantic checker),
+// so a freshly parsed program contains no Rcs at all, and can be safely moved to the thread that merges it
+unsafe impl Send for FrontEndResult {}
+
+// Scan (and, unless scan_only is true, parse) each of the given files on its own thread,
+// returning the results in the same order as the files were given so that the merged program is deterministic
+// (or the error found in the first of them that couldn't be scanned or parsed, naming the file if there's more than one).
+// Semantic checking and code generation happen after this, on a single thread
+pub fn scan_and_parse_files(
+    files: &Vec<String>,
+    scan_only: bool,
+    verbose: bool,
+) -> Result<Vec<FrontEndResult>, String> {
+    return thread::scope(|scope| {
+        let mut handles = vec![];
+
+        for file in files {
+            // The parser is recursive, so give each thread as much stack space as the main thread would have had
+            let handle = thread::Builder::new()
+                .name(file.clone())
+                .stack_size(8 * 1024 * 1024)
+                .spawn_scoped(scope, move || scan_and_parse_file(file, scan_only, verbose));
+
+            match handle {
+                Ok(handle) => handles.push((file, handle)),
+                Err(_) => return Err(format!("Could not start a thread to compile '{}'", file)),
+            }
+        }
+
+        // Wait for every file in order, so that if several of them have errors, the one reported is always
+        // from the file given first (rather than from whichever thread happened to finish first).
+        // Any threads still running once an error is found are waited for at the end of the scope
+        let mut results = vec![];
+        for (file, handle) in handles {
+            match handle.join() {
+                Ok(Ok(result)) => results.push(result),
+                Ok(Err(error)) => {
+                    return Err(get_file_error(file, &error.to_string(), files.len()));
+                }
+                Err(_) => return Err(format!("The thread compiling '{}' panicked", file)),
+            }
+        }
+
+        return Ok(results);
+    });
+}
+
+// Scan (and, unless scan_only is true, parse) a single file
+fn scan_and_parse_file(
+    file: &String,
+    scan_only: bool,
+    verbose: bool,
+) -> Result<FrontEndResult, ParseError> {
+    log_verbose(verbose, &format!("Scanning '{}'", file));
+    let tokens = time_pass(&format!("scanning '{}'", file), || scan_file(file))?;
+
+    let mut program = None;
+    if !scan_only {
+        log_verbose(
+            verbose,
+            &format!("Parsing {} tokens from '{}'", tokens.len(), file),
+        );
+        let ast = time_pass(&format!("parsing '{}'", file), || parse_file(&tokens))?;
+        debug_assert!(!has_symbols(&ast));
+        program = Some(ast);
+    }
+
+    return Ok(FrontEndResult {
+        file: file.clone(),
+        tokens: tokens,
+        program: program,
+    });
+}
+
+// Get the message of an error found in one of the files being compiled,
+// naming the file if there's more than one it could have been found in
+pub fn get_file_error(file: &str, message: &str, num_files: usize) -> String {
+    if num_files < 2 {
+        return String::from(message);
+    }
+
+    return format!("{}: {}", file, message);
+}
+
+// Check if any node in the given tree has a symbol table entry attached to it
+fn has_symbols(ast: &AST) -> bool {
+    return ast.nodes.iter().any(|node| node.sym.is_some());
+}
+
+// Merge the programs parsed from several files (given as (file name, program) pairs) into a single program,
+// making sure that no two files declare the same global function or variable
+pub fn merge_programs(programs: Vec<(String, AST)>) -> Result<AST, String> {
+    let mut merged = AST::new();
+    let mut merged_root = ASTNode::new("program", None, None);
+
+    // Keep track of the file that declared each global, so we can point to both files if there's a duplicate
+    let mut declared_in: Vec<(String, String)> = vec![];
+
+    for (file, program) in programs {
+        // Every node of the program is moved over as it is (its own root is left behind, unused, in place of the merged one),
+        // after every node of the files before it
+        merged.files.push((merged.nodes.len(), file.clone()));
+        let root = merged.append(program);
+
+        for decl in merged.get(root).children() {
+            let name = get_global_decl_name(decl);
+
+            if let Some((_, other_file)) = declared_in.iter().find(|(other, _)| *other == name) {
+                // A redeclaration within a single file is left for the semantic checker to report
+                if *other_file != file {
+                    return Err(format!(
+                        "{}: '{}' is declared in both '{}' and '{}'",
+                        decl.get_location(),
+                        name,
+                        other_file,
+                        file
+                    ));
+                }
+            } else {
+                declared_in.push((name, file.clone()));
+            }
+
+            merged_root.add_child(decl.id);
+        }
+    }
+
+    merged.root = merged.add_node(merged_root);
+    return Ok(merged);
+}
+
+// Get the file each function of a program was declared in, given the files (and the programs parsed from them)
+// that are about to be merged together
+pub fn get_function_files(programs: &Vec<(String, AST)>) -> HashMap<String, String> {
+    let mut function_files = HashMap::new();
+
+    for (file, program) in programs {
+        for decl in program.get_root().children() {
+            if decl.node_type != "globVarDecl" {
+                function_files.insert(get_global_decl_name(decl), file.clone());
+            }
+        }
+    }
+
+    return function_files;
+}
+
+// Get the name of a global function or variable declaration
+fn get_global_decl_name(decl: NodeRef) -> String {
+    if decl.node_type == "globVarDecl" {
+        // Global variable declarations are of the form [type, id, (assignment)]
+        return decl.child(1).get_attr().to_string();
+    }
+
+    // Function declarations (including main) are of the form [id, parameters, returns, block]
+    return decl.child(0).get_attr().to_string();
 }
 
 // -----------------------------------------------------------------
@@ -17,3 +250,114 @@ pub fn parser(tokens: &Vec<Token>) -> ASTNode {
 pub fn consume_token(current: &mut usize) {
     *current += 1;
 }
+
+// Make sure the tokens given to one of the parser's entry points end with an EOF token (as scan_str gives back),
+// which everything that looks ahead in the grammar relies on to stop at
+fn check_tokens(tokens: &[Token]) -> Result<(), ParseError> {
+    return match tokens.last() {
+        Some(token) if token.token_type == TokenType::EOF => Ok(()),
+        last => {
+            let line_num = last.map(|token| token.line_num).unwrap_or(1);
+            Err(ParseError {
+                message: format!(
+                    "Syntax Error on line {}: unexpected end of input, the tokens don't end with an EOF token",
+                    line_num
+                ),
+                line_num: line_num,
+            })
+        }
+    };
+}
+
+// Get the token at the given position, or the EOF token at the end if the position is past it,
+// so that looking ahead from the last token (like at the end of an unfinished expression) never runs off the end
+pub fn peek_token(tokens: &[Token], pos: usize) -> &Token {
+    return tokens.get(pos).unwrap_or(&tokens[tokens.len() - 1]);
+}
+
+// The most levels that expressions or statements can be nested inside each other (like brackets inside brackets,
+// or blocks inside blocks, where an if or while and its block are a level each), which keeps the parser
+// from running out of stack
+pub const MAX_NESTING_DEPTH: usize = 256;
+
+thread_local! {
+    // How many levels deep the parser currently is, in the file being parsed on this thread
+    static NESTING_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// Parse an expression or statement (described by what) nested inside whatever is currently being parsed,
+// with the given grammar function, giving back an error if it's nested too deeply
+pub fn parse_nested(
+    tokens: &[Token],
+    current: &mut usize,
+    ast: &mut AST,
+    what: &str,
+    parse: fn(&[Token], &mut usize, &mut AST) -> Result<NodeId, ParseError>,
+) -> Result<NodeId, ParseError> {
+    let depth = NESTING_DEPTH.get() + 1;
+    if depth > MAX_NESTING_DEPTH {
+        return Err(get_nesting_error(
+            peek_token(tokens, *current).line_num,
+            what,
+        ));
+    }
+
+    // The depth is put back even if there's an error, so that it doesn't count towards whatever is parsed next
+    NESTING_DEPTH.set(depth);
+    let result = parse(tokens, current, ast);
+    NESTING_DEPTH.set(depth - 1);
+    return result;
+}
+
+// Check that a chain of binary operators of the given height (see get_height), parsed at the current nesting depth,
+// isn't nested too deeply. A chain like 1 + 1 + ... + 1 is parsed in a loop, and walked in one too (see
+// get_operator_chain), so how long it is doesn't matter, but the operands of its operators are each a level deeper
+pub fn check_chain_height(height: usize, line_num: i32) -> Result<(), ParseError> {
+    if NESTING_DEPTH.get() + height > MAX_NESTING_DEPTH {
+        return Err(get_nesting_error(line_num, "expression"));
+    }
+    return Ok(());
+}
+
+fn get_nesting_error(line_num: i32, what: &str) -> ParseError {
+    return ParseError {
+        message: format!(
+            "Syntax Error on line {}: {} too deeply nested (the limit is {} levels)",
+            line_num, what, MAX_NESTING_DEPTH
+        ),
+        line_num: line_num,
+    };
+}
+
+// Get the height of the tree below (and including) the given node, where a node without any children is 1, and the
+// left hand side of a binary operator that's another one doesn't count as a level, since it's part of the same chain
+// (without recursing, since the chain could be far longer than the stack allows)
+pub fn get_height(ast: &AST, node: NodeId) -> usize {
+    let mut height = 0;
+    let mut stack = vec![(node, 1)];
+    while let Some((node, depth)) = stack.pop() {
+        height = height.max(depth);
+
+        let in_chain = is_binary_operator(&ast[node].node_type);
+        for (i, child) in ast[node].children.iter().enumerate() {
+            if in_chain && i == 0 && is_binary_operator(&ast[*child].node_type) {
+                stack.push((*child, depth));
+            } else {
+                stack.push((*child, depth + 1));
+            }
+        }
+    }
+    return height;
+}
+
+// Parse the body of an if, else or while, which is a statement nested inside it, noting the tokens it was parsed from
+pub fn parse_body(
+    tokens: &[Token],
+    current: &mut usize,
+    ast: &mut AST,
+) -> Result<NodeId, ParseError> {
+    let first = *current;
+    let body = parse_nested(tokens, current, ast, "statement", statement_)?;
+    ast.spans.insert(body, (first, *current));
+    return Ok(body);
+}
@@ -0,0 +1,261 @@
+// --------------------------------------------------------------------------------------------------------------
+// This file contains the logic involved in re-parsing a file incrementally as it's edited (like in an editor),
+// where only the global declarations an edit could have changed are scanned and parsed again
+// --------------------------------------------------------------------------------------------------------------
+
+use std::ops::Range;
+
+use crate::parser::parser_data::{ASTNode, NodeId, ParseError, AST};
+use crate::parser::parser_driver::parse_program;
+use crate::parser::parser_grammar::globaldeclarations_;
+use crate::scanner::scanner_data::{Token, TokenType};
+use crate::scanner::scanner_driver::scan_str;
+
+// An edit to a piece of soup source code, which replaces the text between two byte offsets of the source
+// (which have to be on character boundaries, like the range given to String::replace_range) with new text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub new_text: String,
+}
+
+// A file that has been scanned and parsed, which can be re-parsed incrementally after each edit to it
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub source: String,
+    pub tokens: Vec<Token>,
+    pub ast: AST,
+    // The positions (among the program's global declarations) of the declarations that were scanned and parsed again
+    // the last time the file was parsed, where every other declaration was reused as it was
+    pub reparsed: Range<usize>,
+}
+
+impl ParsedFile {
+    // Scan and parse a whole file from scratch
+    pub fn parse(source: &str) -> Result<ParsedFile, ParseError> {
+        let tokens = scan_str(source)?;
+        let ast = parse_program(&tokens)?;
+        let num_decls = ast.get_root().children.len();
+
+        return Ok(ParsedFile {
+            source: String::from(source),
+            tokens: tokens,
+            ast: ast,
+            reparsed: 0..num_decls,
+        });
+    }
+
+    // Apply an edit to the file, giving back the edited file. Only the lines between the last global declaration
+    // before the edit and the first one after it are scanned and parsed again, and the tokens and nodes of
+    // every other declaration are reused (moved down by however many lines the edit added).
+    // If the edited lines can't be scanned or parsed on their own, the whole file is parsed again instead,
+    // so that any error is the same as it would be if the file had been parsed from scratch
+    pub fn reparse(&self, edit: &TextEdit) -> Result<ParsedFile, ParseError> {
+        let mut source = self.source.clone();
+        source.replace_range(edit.start..edit.end, &edit.new_text);
+
+        let old_line_starts = get_line_starts(&self.source);
+        let new_line_starts = get_line_starts(&source);
+        let line_delta = new_line_starts.len() as i32 - old_line_starts.len() as i32;
+
+        // The first and last line of each global declaration
+        let decls: Vec<NodeId> = self.ast.get_root().children.clone();
+        let decl_lines: Vec<(i32, i32)> = decls
+            .iter()
+            .map(|decl| {
+                let (first, end) = self.ast.spans[decl];
+                return (self.tokens[first].line_num, self.tokens[end - 1].line_num);
+            })
+            .collect();
+
+        // Find the declarations that end before the line the edit starts on, and start after the line it ends on,
+        // leaving out any that share a line with a declaration that might have changed
+        let edit_first_line = get_line(&old_line_starts, edit.start);
+        let edit_last_line = get_line(&old_line_starts, edit.end);
+
+        let mut num_before = decl_lines
+            .iter()
+            .take_while(|(_, last)| *last < edit_first_line)
+            .count();
+        while num_before > 0
+            && num_before < decls.len()
+            && decl_lines[num_before - 1].1 == decl_lines[num_before].0
+        {
+            num_before -= 1;
+        }
+
+        let mut after_start = decls.len()
+            - decl_lines[num_before..]
+                .iter()
+                .rev()
+                .take_while(|(first, _)| *first > edit_last_line)
+                .count();
+        while after_start > num_before
+            && after_start < decls.len()
+            && decl_lines[after_start - 1].1 == decl_lines[after_start].0
+        {
+            after_start += 1;
+        }
+
+        // The lines in between them are the only ones that are scanned and parsed again
+        let first_line = match num_before {
+            0 => 1,
+            _ => decl_lines[num_before - 1].1 + 1,
+        };
+        let region_start = get_line_start(&new_line_starts, first_line, source.len());
+        let region_end = match decl_lines.get(after_start) {
+            Some((first, _)) => get_line_start(&new_line_starts, first + line_delta, source.len()),
+            None => source.len(),
+        };
+
+        let mut region_tokens = match scan_str(&source[region_start..region_end]) {
+            Ok(tokens) => tokens,
+            Err(_) => return ParsedFile::parse(&source),
+        };
+        for token in &mut region_tokens {
+            token.line_num += first_line - 1;
+        }
+
+        let mut region_ast = AST::new();
+        let region_decls = match region_tokens[0].token_type {
+            TokenType::EOF => vec![],
+            _ => match globaldeclarations_(&region_tokens, &mut 0, &mut region_ast) {
+                Ok(region_decls) => region_decls,
+                Err(_) => return ParsedFile::parse(&source),
+            },
+        };
+
+        // Put the tokens and declarations from before the edit, the ones that were parsed again,
+        // and the ones from after the edit back together
+        let mut tokens = vec![];
+        let mut ast = AST::new();
+        let mut root = ASTNode::new("program", None, None);
+
+        let before_end = match num_before {
+            0 => 0,
+            _ => self.ast.spans[&decls[num_before - 1]].1,
+        };
+        tokens.extend_from_slice(&self.tokens[..before_end]);
+        for decl in 0..num_before {
+            root.add_child(copy_decl(&self.ast, &decls, decl, &mut ast, 0, 0));
+        }
+
+        let region_offset = tokens.len() as isize;
+        tokens.extend_from_slice(&region_tokens[..region_tokens.len() - 1]);
+        for decl in 0..region_decls.len() {
+            root.add_child(copy_decl(
+                &region_ast,
+                &region_decls,
+                decl,
+                &mut ast,
+                0,
+                region_offset,
+            ));
+        }
+
+        let after_first = match decls.get(after_start) {
+            Some(decl) => self.ast.spans[decl].0,
+            None => self.tokens.len() - 1,
+        };
+        let token_shift = tokens.len() as isize - after_first as isize;
+        for token in &self.tokens[after_first..self.tokens.len() - 1] {
+            let mut token = token.clone();
+            token.line_num += line_delta;
+            tokens.push(token);
+        }
+        for decl in after_start..decls.len() {
+            root.add_child(copy_decl(
+                &self.ast,
+                &decls,
+                decl,
+                &mut ast,
+                line_delta,
+                token_shift,
+            ));
+        }
+
+        // The end of the file is on the line of the last character (like scan_str gives back)
+        let mut eof = self.tokens[self.tokens.len() - 1].clone();
+        eof.line_num = match source.len() {
+            0 => 1,
+            len => get_line(&new_line_starts, len - 1),
+        };
+        tokens.push(eof);
+
+        ast.root = ast.add_node(root);
+        return Ok(ParsedFile {
+            source: source,
+            tokens: tokens,
+            ast: ast,
+            reparsed: num_before..num_before + region_decls.len(),
+        });
+    }
+}
+
+// Copy one of the global declarations of an AST (given along with the rest of its declarations) into another,
+// moving its lines and spans by the given amounts, and returning the position of the copy.
+// Every node of a declaration is added to the arena while it's parsed, so each declaration's nodes
+// are all together in the arena, between the declaration before it and the declaration itself
+fn copy_decl(
+    from: &AST,
+    decls: &Vec<NodeId>,
+    decl: usize,
+    into: &mut AST,
+    line_delta: i32,
+    token_shift: isize,
+) -> NodeId {
+    let first_node = match decl {
+        0 => 0,
+        _ => decls[decl - 1] + 1,
+    };
+    let offset = into.nodes.len() as isize - first_node as isize;
+
+    for id in first_node..=decls[decl] {
+        let mut node = from[id].clone();
+        for child in &mut node.children {
+            *child = (*child as isize + offset) as usize;
+        }
+        if let Some(line_num) = &mut node.line_num {
+            *line_num += line_delta;
+        }
+
+        let copy = into.add_node(node);
+        if let Some((first, end)) = from.spans.get(&id) {
+            into.spans.insert(
+                copy,
+                (
+                    (*first as isize + token_shift) as usize,
+                    (*end as isize + token_shift) as usize,
+                ),
+            );
+        }
+    }
+
+    return (decls[decl] as isize + offset) as usize;
+}
+
+// Get the byte offset each line of some source code starts at, where lines end the same way they do
+// for the scanner (with a "\n", a "\r\n", or a "\r" on its own)
+fn get_line_starts(source: &str) -> Vec<usize> {
+    let bytes = source.as_bytes();
+    let mut line_starts = vec![0];
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if *byte == b'\n' || (*byte == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+            line_starts.push(i + 1);
+        }
+    }
+
+    return line_starts;
+}
+
+// Get the line (numbered from 1) that the given byte offset is on
+fn get_line(line_starts: &Vec<usize>, offset: usize) -> i32 {
+    return line_starts.partition_point(|start| *start <= offset) as i32;
+}
+
+// Get the byte offset the given line (numbered from 1) starts at, or the end of the source if there's no such line
+fn get_line_start(line_starts: &Vec<usize>, line: i32, source_len: usize) -> usize {
+    return *line_starts.get(line as usize - 1).unwrap_or(&source_len);
+}
@@ -0,0 +1,109 @@
+// --------------------------------------------------------------------------------------------------------------
+// This file contains the logic involved in attaching the comments and blank lines of a losslessly scanned program
+// to the statements and declarations of its AST, for tooling like the formatter
+// --------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+
+use crate::parser::parser_data::{Comment, NodeId, NodeTrivia, AST};
+use crate::scanner::scanner_data::{Token, TokenType, Trivia, TriviaPiece};
+
+// -----------------------------------------------------------------
+// TRIVIA
+// -----------------------------------------------------------------
+
+// Attach the comments and blank lines in the trivia in front of each token to the statement or declaration they belong with:
+// a comment at the end of the line a statement ends on is its trailing comment, comments on lines of their own
+// just before a statement are its leading comments, comments just before the close brace of a block
+// (or the end of the file) are the block's (or the program's) closing comments, and any other comment is
+// an inner comment of the innermost statement it's in the middle of
+pub fn attach_trivia(ast: &mut AST, tokens: &[Token], trivia: &[Trivia]) {
+    // The outermost statement starting at each token, the outermost statement ending just before each token,
+    // and the block closed by each close brace
+    let mut starts: HashMap<usize, (NodeId, usize)> = HashMap::new();
+    let mut ends: HashMap<usize, (NodeId, usize)> = HashMap::new();
+    let mut block_ends: HashMap<usize, NodeId> = HashMap::new();
+
+    for (&id, &(first, end)) in &ast.spans {
+        match starts.get(&first) {
+            Some(&(_, other_end)) if other_end >= end => {}
+            _ => {
+                starts.insert(first, (id, end));
+            }
+        }
+        match ends.get(&end) {
+            Some(&(_, other_first)) if other_first <= first => {}
+            _ => {
+                ends.insert(end, (id, first));
+            }
+        }
+        if ast[id].node_type == "block" {
+            block_ends.insert(end - 1, id);
+        }
+    }
+
+    for (i, trivia) in trivia.iter().enumerate() {
+        // The number of line endings since the last token or comment, which are a blank line apart if there are two
+        let mut newlines = 0;
+        let mut comments = vec![];
+
+        for piece in &trivia.pieces {
+            match piece {
+                TriviaPiece::Whitespace(_) => {}
+                TriviaPiece::Newline => newlines += 1,
+                TriviaPiece::Comment(text) => {
+                    let comment = Comment {
+                        text: text.clone(),
+                        blank_line_before: newlines >= 2,
+                    };
+
+                    // A comment on the same line as the end of a statement is about that statement
+                    match ends.get(&i) {
+                        Some(&(statement, _)) if newlines == 0 => {
+                            get_trivia_mut(ast, statement).trailing_comment = Some(comment);
+                        }
+                        _ => comments.push(comment),
+                    }
+                    newlines = 0;
+                }
+            }
+        }
+
+        let blank_line_before = newlines >= 2;
+        if let Some(&(statement, _)) = starts.get(&i) {
+            if !comments.is_empty() || blank_line_before {
+                let node_trivia = get_trivia_mut(ast, statement);
+                node_trivia.leading_comments = comments;
+                node_trivia.blank_line_before = blank_line_before;
+            }
+        } else if comments.is_empty() {
+            continue;
+        } else if tokens[i].token_type == TokenType::EOF {
+            let root = ast.root;
+            get_trivia_mut(ast, root).closing_comments = comments;
+        } else if let Some(&block) = block_ends.get(&i) {
+            get_trivia_mut(ast, block).closing_comments = comments;
+        } else {
+            // The comments are in the middle of a statement (like between an if's condition and its block)
+            let statement = get_innermost_statement(ast, i).unwrap_or(ast.root);
+            get_trivia_mut(ast, statement)
+                .inner_comments
+                .append(&mut comments);
+        }
+    }
+}
+
+// Get the trivia attached to a node, attaching an empty one to it first if it doesn't have any yet
+fn get_trivia_mut(ast: &mut AST, id: NodeId) -> &mut NodeTrivia {
+    return ast[id].trivia.get_or_insert_with(Box::default);
+}
+
+// Get the innermost statement (or declaration) that the given token is in the middle of, if it's in any
+fn get_innermost_statement(ast: &AST, token: usize) -> Option<NodeId> {
+    return ast
+        .spans
+        .iter()
+        .filter(|(_, &(first, end))| first < token && token < end)
+        .min_by_key(|(&id, &(first, end))| (end - first, id))
+        .map(|(&id, _)| id);
+}
@@ -1,7 +1,36 @@
 mod tests {
-    use crate::parser::parser_data::ASTNode;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use crate::parser::parser_data::{
+        format_ast, format_ast_dot, format_ast_json, format_json_string, ASTNode, Comment, NodeId,
+        NodeRef, NodeTrivia, ParseError, AST,
+    };
+    use crate::parser::parser_driver::{
+        get_function_files, merge_programs, parse_expression, parse_program,
+        parse_program_lossless, MAX_NESTING_DEPTH,
+    };
     use crate::parser::parser_grammar::*;
+    use crate::parser::parser_incremental::{ParsedFile, TextEdit};
     use crate::scanner::scanner_data::{Token, TokenType};
+    use crate::scanner::scanner_driver::{scan_str, scan_str_lossless};
+    use crate::semantic::semantic_data::Type;
+
+    // Parse the given tokens with a grammar function, into an AST of their own
+    fn parse_with(
+        parse: fn(&[Token], &mut usize, &mut AST) -> Result<NodeId, ParseError>,
+        tokens: &[Token],
+    ) -> AST {
+        let mut ast = AST::new();
+        ast.root = parse(tokens, &mut 0, &mut ast).unwrap();
+        return ast;
+    }
+
+    // Add a node to an AST along with its children (which have already been added), returning its id
+    fn add(ast: &mut AST, mut node: ASTNode, children: Vec<NodeId>) -> NodeId {
+        node.add_children(children);
+        return ast.add_node(node);
+    }
 
     #[test]
     fn test_function_header() {
@@ -9,64 +38,159 @@ mod tests {
         let tokens = vec![
             Token {
                 token_type: TokenType::FUNC,
-                lexeme: String::from("func"),
+                lexeme: Arc::from("func"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::ID,
-                lexeme: String::from("test_func"),
+                lexeme: Arc::from("test_func"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::OPENPAR,
-                lexeme: String::from("("),
+                lexeme: Arc::from("("),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::CLOSEPAR,
-                lexeme: String::from(")"),
+                lexeme: Arc::from(")"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::RETURNS,
-                lexeme: String::from("returns"),
+                lexeme: Arc::from("returns"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::VOID,
-                lexeme: String::from("void"),
+                lexeme: Arc::from("void"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::OPENBRACE,
-                lexeme: String::from("{"),
+                lexeme: Arc::from("{"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::SEMICOLON,
-                lexeme: String::from(";"),
+                lexeme: Arc::from(";"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::CLOSEBRACE,
-                lexeme: String::from("}"),
+                lexeme: Arc::from("}"),
                 line_num: 1,
             },
         ];
 
-        let mut func = ASTNode::new("funcDecl", None, Some(1));
-        let id = ASTNode::new("id", Some(String::from("test_func")), Some(1));
-        let params = ASTNode::new("parameters", None, None);
-        let mut returns = ASTNode::new("returns", None, None);
-        let void = ASTNode::new("void", Some(String::from("void")), Some(1));
-        let mut block = ASTNode::new("block", None, Some(1));
-        let void_stmt = ASTNode::new("voidStmt", None, Some(1));
+        let mut ast = AST::new();
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("test_func")), Some(1)));
+        let params = ast.add_node(ASTNode::new("parameters", None, None));
+        let void = ast.add_node(ASTNode::new("void", Some(Arc::from("void")), Some(1)));
+        let void_stmt = ast.add_node(ASTNode::new("voidStmt", None, Some(1)));
 
-        returns.add_child(void);
-        block.add_child(void_stmt);
-        func.add_children(vec![id, params, returns, block]);
+        let returns = add(&mut ast, ASTNode::new("returns", None, None), vec![void]);
+        let block = add(
+            &mut ast,
+            ASTNode::new("block", None, Some(1)),
+            vec![void_stmt],
+        );
+        let func = add(
+            &mut ast,
+            ASTNode::new("funcDecl", None, Some(1)),
+            vec![id, params, returns, block],
+        );
 
-        assert_eq!(func, functiondeclaration_(&tokens, &mut 0));
+        assert_eq!(
+            ast.get(func),
+            parse_with(functiondeclaration_, &tokens).get_root()
+        );
+    }
+
+    #[test]
+    fn test_main_returns_int() {
+        // func main() returns int { return 0; }
+        let tokens = vec![
+            Token {
+                token_type: TokenType::FUNC,
+                lexeme: Arc::from("func"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::MAIN,
+                lexeme: Arc::from("main"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENPAR,
+                lexeme: Arc::from("("),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEPAR,
+                lexeme: Arc::from(")"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::RETURNS,
+                lexeme: Arc::from("returns"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INT,
+                lexeme: Arc::from("int"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACE,
+                lexeme: Arc::from("{"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::RETURN,
+                lexeme: Arc::from("return"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INTLIT,
+                lexeme: Arc::from("0"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::SEMICOLON,
+                lexeme: Arc::from(";"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACE,
+                lexeme: Arc::from("}"),
+                line_num: 1,
+            },
+        ];
+
+        let mut ast = AST::new();
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("main")), Some(1)));
+        let params = ast.add_node(ASTNode::new("parameters", None, None));
+        let int = ast.add_node(ASTNode::new("int", Some(Arc::from("int")), Some(1)));
+        let zero = ast.add_node(ASTNode::new("number", Some(Arc::from("0")), Some(1)));
+
+        let returns = add(&mut ast, ASTNode::new("returns", None, None), vec![int]);
+        let return_stmt = add(&mut ast, ASTNode::new("return", None, Some(1)), vec![zero]);
+        let block = add(
+            &mut ast,
+            ASTNode::new("block", None, Some(1)),
+            vec![return_stmt],
+        );
+        let main = add(
+            &mut ast,
+            ASTNode::new("mainFuncDecl", None, Some(1)),
+            vec![id, params, returns, block],
+        );
+
+        assert_eq!(
+            ast.get(main),
+            parse_with(mainfunctiondeclaration_, &tokens).get_root()
+        );
     }
 
     #[test]
@@ -75,51 +199,411 @@ mod tests {
         let tokens = vec![
             Token {
                 token_type: TokenType::INTLIT,
-                lexeme: String::from("1"),
+                lexeme: Arc::from("1"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::PLUS,
-                lexeme: String::from("+"),
+                lexeme: Arc::from("+"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::INTLIT,
-                lexeme: String::from("2"),
+                lexeme: Arc::from("2"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::MULT,
-                lexeme: String::from("*"),
+                lexeme: Arc::from("*"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::INTLIT,
-                lexeme: String::from("3"),
+                lexeme: Arc::from("3"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::SEMICOLON,
-                lexeme: String::from(";"),
+                lexeme: Arc::from(";"),
                 line_num: 1,
             },
         ];
 
-        let mut plus = ASTNode::new("+", None, Some(1));
-        let mut times = ASTNode::new("*", None, Some(1));
-        let one = ASTNode::new("number", Some(String::from("1")), Some(1));
-        let two = ASTNode::new("number", Some(String::from("2")), Some(1));
-        let three = ASTNode::new("number", Some(String::from("3")), Some(1));
+        let mut ast = AST::new();
+        let one = ast.add_node(ASTNode::new("number", Some(Arc::from("1")), Some(1)));
+        let two = ast.add_node(ASTNode::new("number", Some(Arc::from("2")), Some(1)));
+        let three = ast.add_node(ASTNode::new("number", Some(Arc::from("3")), Some(1)));
 
         // * is evaluated first, and so is lower down on the tree
-        times.add_child(two);
-        times.add_child(three);
+        let times = add(&mut ast, ASTNode::new("*", None, Some(1)), vec![two, three]);
 
         // Next we evaluate +
-        plus.add_child(one);
-        plus.add_child(times);
+        let plus = add(&mut ast, ASTNode::new("+", None, Some(1)), vec![one, times]);
+
+        assert_eq!(
+            ast.get(plus),
+            parse_with(assignmentexpression_, &tokens).get_root()
+        );
+    }
+
+    #[test]
+    fn test_binary_operator_associativity() {
+        // Format an expression with every binary operation in brackets, to show the shape of its tree
+        fn bracket(node: NodeRef) -> String {
+            return match node.children.len() {
+                2 => format!(
+                    "({} {} {})",
+                    bracket(node.child(0)),
+                    node.node_type,
+                    bracket(node.child(1))
+                ),
+                _ => node.get_attr().to_string(),
+            };
+        }
+        let parse =
+            |source: &str| bracket(parse_with(expression_, &scan_str(source).unwrap()).get_root());
+
+        // Operators with the same precedence are evaluated from left to right
+        assert_eq!("((a - b) - c)", parse("a - b - c"));
+        assert_eq!("((a / b) % c)", parse("a / b % c"));
+        assert_eq!("((a && b) && c)", parse("a && b && c"));
+        assert_eq!("((a == b) != c)", parse("a == b != c"));
 
-        assert_eq!(plus, assignmentexpression_(&tokens, &mut 0));
+        // and operators with a higher precedence are evaluated first
+        assert_eq!("((a - (b * c)) + d)", parse("a - b * c + d"));
+        assert_eq!("((a < (b + c)) == (d >= e))", parse("a < b + c == d >= e"));
+        assert_eq!("((a || (b && c)) || d)", parse("a || b && c || d"));
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        // A fragment of soup code can be parsed on its own, into a tree whose root is the expression
+        let ast = parse_expression(&scan_str("x = a[1] + f(2)").unwrap()).unwrap();
+        let root = ast.get_root();
+        assert_eq!("=", root.node_type);
+        assert_eq!("+", root.child(1).node_type);
+        assert_eq!("index", root.child(1).child(0).node_type);
+        assert_eq!("funcCall", root.child(1).child(1).node_type);
+
+        // but it has to be a single, whole expression
+        assert_eq!(
+            ParseError {
+                message: String::from(
+                    "Syntax Error on line 2: unexpected '2' after the end of the expression"
+                ),
+                line_num: 2,
+            },
+            parse_expression(&scan_str("1\n2").unwrap()).unwrap_err()
+        );
+
+        // Errors are given back rather than exiting, even when the fragment ends partway through
+        for source in ["", "1 +", "(1", "f(1,", "a[", "x = "] {
+            assert!(parse_expression(&scan_str(source).unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let ast = parse_program(&scan_str("int x;\nfunc main() returns void { x = 1; }").unwrap())
+            .unwrap();
+        let root = ast.get_root();
+        assert_eq!("program", root.node_type);
+        assert_eq!("globVarDecl", root.child(0).node_type);
+        assert_eq!("mainFuncDecl", root.child(1).node_type);
+
+        assert_eq!(
+            "Syntax Error on line 1: variable declaration must end with a semicolon \";\"",
+            parse_program(&scan_str("int x").unwrap())
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(parse_program(&scan_str("func main() returns void {").unwrap()).is_err());
+
+        // Tokens that don't end with an EOF token (which everything that looks ahead relies on) are an error too
+        let mut tokens = scan_str("int x;").unwrap();
+        tokens.pop();
+        assert!(parse_program(&tokens).is_err());
+        assert!(parse_expression(&tokens).is_err());
+        assert!(parse_program(&[]).is_err());
+        assert!(parse_expression(&[]).is_err());
+    }
+
+    #[test]
+    fn test_statement_syntax_errors() {
+        let error = |source: &str| {
+            parse_program(&scan_str(source).unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            "Syntax Error on line 4: until condition must end with a semicolon",
+            error("func main() returns void {\n    repeat {\n        ;\n    } until true\n}\n")
+        );
+        assert_eq!(
+            "Syntax Error on line 2: only a loop can be labeled",
+            error("func main() returns void {\n    a: if true {\n    }\n}\n")
+        );
+        assert_eq!(
+            "Syntax Error on line 2: a conversion must be written like bool(x)",
+            error("func main() returns void {\n    printf(\"{}\", bool 1);\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_program_lossless() {
+        let source = "// header
+
+int x; // the counter
+func main() returns void {
+    // set it
+    x = 1 + // inner
+        2; // once
+
+    {
+        x = 2;
+        // closing
+    }
+}
+// the end
+";
+        let (tokens, trivia) = scan_str_lossless(source).unwrap();
+        let ast = parse_program_lossless(&tokens, &trivia).unwrap();
+        let comment = |text: &str, blank_line_before: bool| Comment {
+            text: String::from(text),
+            blank_line_before: blank_line_before,
+        };
+
+        let root = ast.get_root();
+        assert_eq!(
+            Some(Box::new(NodeTrivia {
+                leading_comments: vec![comment("// header", false)],
+                blank_line_before: true,
+                trailing_comment: Some(comment("// the counter", false)),
+                ..NodeTrivia::default()
+            })),
+            root.child(0).trivia
+        );
+        assert_eq!(None, root.child(1).trivia);
+
+        let block = root.child(1).child(3);
+        assert_eq!(
+            Some(Box::new(NodeTrivia {
+                leading_comments: vec![comment("// set it", false)],
+                trailing_comment: Some(comment("// once", false)),
+                inner_comments: vec![comment("// inner", false)],
+                ..NodeTrivia::default()
+            })),
+            block.child(0).trivia
+        );
+        assert_eq!(
+            Some(Box::new(NodeTrivia {
+                blank_line_before: true,
+                closing_comments: vec![comment("// closing", false)],
+                ..NodeTrivia::default()
+            })),
+            block.child(1).trivia
+        );
+        assert_eq!(
+            vec![comment("// the end", false)],
+            root.trivia.as_ref().unwrap().closing_comments
+        );
+
+        // Without the trivia, only the tokens each statement was parsed from are kept
+        let ast = parse_program(&tokens).unwrap();
+        assert!(ast.nodes.iter().all(|node| node.trivia.is_none()));
+        assert_eq!(Some(&(0, 3)), ast.spans.get(&ast.get_root().child(0).id));
+    }
+
+    #[test]
+    fn test_reparse() {
+        let source = "int x;
+func f() returns int {
+    return 1;
+}
+
+func main() returns void {
+    x = f();
+}
+";
+        let file = ParsedFile::parse(source).unwrap();
+        assert_eq!(0..3, file.reparsed);
+
+        // Editing the middle of a function only scans and parses that function again,
+        // and the declarations after it are moved down by the line the edit added
+        let start = source.find('1').unwrap();
+        let edit = TextEdit {
+            start: start,
+            end: start + 1,
+            new_text: String::from("2 +\n        3"),
+        };
+        let edited = file.reparse(&edit).unwrap();
+        assert_eq!(1..2, edited.reparsed);
+
+        let expected = ParsedFile::parse(&edited.source).unwrap();
+        assert_eq!(expected.tokens, edited.tokens);
+        assert_eq!(
+            format_ast(expected.ast.get_root(), 0),
+            format_ast(edited.ast.get_root(), 0)
+        );
+        assert_eq!(
+            8,
+            edited
+                .ast
+                .get_root()
+                .child(2)
+                .child(3)
+                .child(0)
+                .get_line_num()
+        );
+
+        // An edit that leaves the lines it changed unparseable on their own gives back the same error
+        // as parsing the whole file would
+        let start = source.find("}\n\n").unwrap();
+        let edit = TextEdit {
+            start: start,
+            end: start + 1,
+            new_text: String::from(""),
+        };
+        let mut edited_source = String::from(source);
+        edited_source.replace_range(start..start + 1, "");
+        assert_eq!(
+            ParsedFile::parse(&edited_source).unwrap_err(),
+            file.reparse(&edit).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_format_ast_json() {
+        let ast = parse_expression(&scan_str("-x").unwrap()).unwrap();
+        assert_eq!(
+            "{
+  \"kind\": \"u-\",
+  \"attr\": null,
+  \"line\": 1,
+  \"type\": null,
+  \"symbol\": null,
+  \"children\": [
+    {
+      \"kind\": \"id\",
+      \"attr\": \"x\",
+      \"line\": 1,
+      \"type\": null,
+      \"symbol\": null,
+      \"children\": []
+    }
+  ]
+}",
+            format_ast_json(ast.get_root(), 0)
+        );
+
+        // Anything that can't appear in a JSON string as it is gets escaped
+        assert_eq!(
+            "\"a\\\"b\\\\c\\n\\t\\u0007\"",
+            format_json_string("a\"b\\c\n\t\u{7}")
+        );
+    }
+
+    #[test]
+    fn test_format_ast_dot() {
+        // Each node is named after its id in the arena, and has an edge to each of its children
+        let ast = parse_expression(&scan_str("-x").unwrap()).unwrap();
+        assert_eq!(
+            "digraph ast {
+    ordering=out;
+    node [shape=box, fontname=\"monospace\"];
+
+    n1 [label=\"u-\"];
+    n1 -> n0;
+    n0 [label=\"id\\nattr: x\"];
+}
+",
+            format_ast_dot(ast.get_root())
+        );
+
+        // A backslash in a string literal is escaped, so it isn't mistaken for the start of a new line
+        let ast = parse_expression(&scan_str("\"hi\\n\"").unwrap()).unwrap();
+        assert_eq!(
+            "    n0 [label=\"string\\nattr: hi\\\\n\"];",
+            format_ast_dot(ast.get_root()).lines().nth(4).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        // Nesting just under the limit is fine, and doesn't count towards the nesting of anything parsed afterwards
+        let nested = format!(
+            "{}1{}",
+            "(".repeat(MAX_NESTING_DEPTH - 1),
+            ")".repeat(MAX_NESTING_DEPTH - 1)
+        );
+        for _ in 0..2 {
+            let tokens = scan_str(&nested).unwrap();
+            assert_eq!(
+                ASTNode::new("number", Some(Arc::from("1")), Some(1)),
+                *parse_with(expression_, &tokens).get_root()
+            );
+        }
+
+        let tokens = scan_str(&format!("{}x", "!".repeat(MAX_NESTING_DEPTH - 1))).unwrap();
+        let ast = parse_with(expression_, &tokens);
+        let mut node = ast.get_root();
+        for _ in 0..MAX_NESTING_DEPTH - 1 {
+            assert_eq!("!", node.node_type);
+            node = node.child(0);
+        }
+        assert_eq!("id", node.node_type);
+
+        // A chain of binary operators is parsed (and walked) in a loop, so it can be far longer than the limit,
+        // and can be nested just under it too
+        let chain = vec!["1"; MAX_NESTING_DEPTH * 100].join(" - ");
+        let tokens = scan_str(&chain).unwrap();
+        assert_eq!(
+            MAX_NESTING_DEPTH * 200 - 1,
+            parse_with(expression_, &tokens).nodes.len()
+        );
+
+        let nested_chain = format!(
+            "{}{}{}",
+            "(".repeat(MAX_NESTING_DEPTH - 3),
+            chain,
+            ")".repeat(MAX_NESTING_DEPTH - 3)
+        );
+        let tokens = scan_str(&nested_chain).unwrap();
+        assert_eq!(
+            MAX_NESTING_DEPTH * 200 - 1,
+            parse_with(expression_, &tokens).nodes.len()
+        );
+
+        // but the operands of its operators are each a level deeper than it is
+        let too_deep = [
+            format!(
+                "{}1 + 1 * 1{}",
+                "(".repeat(MAX_NESTING_DEPTH - 3),
+                ")".repeat(MAX_NESTING_DEPTH - 3)
+            ),
+            format!(
+                "{}1 + (1 + 1){}",
+                "(".repeat(MAX_NESTING_DEPTH - 3),
+                ")".repeat(MAX_NESTING_DEPTH - 3)
+            ),
+            format!(
+                "{}1{}",
+                "(1 + ".repeat(MAX_NESTING_DEPTH),
+                ")".repeat(MAX_NESTING_DEPTH)
+            ),
+        ];
+        for source in too_deep {
+            let tokens = scan_str(&source).unwrap();
+            let error = expression_(&tokens, &mut 0, &mut AST::new()).unwrap_err();
+            assert_eq!(
+                format!(
+                    "Syntax Error on line 1: expression too deeply nested (the limit is {} levels)",
+                    MAX_NESTING_DEPTH
+                ),
+                error.message
+            );
+        }
     }
 
     #[test]
@@ -128,85 +612,477 @@ mod tests {
         let mut tokens = vec![
             Token {
                 token_type: TokenType::ID,
-                lexeme: String::from("x"),
+                lexeme: Arc::from("x"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::ASSIGN,
-                lexeme: String::from("="),
+                lexeme: Arc::from("="),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::INTLIT,
-                lexeme: String::from("1"),
+                lexeme: Arc::from("1"),
                 line_num: 1,
             },
             Token {
                 token_type: TokenType::SEMICOLON,
-                lexeme: String::from(";"),
+                lexeme: Arc::from(";"),
                 line_num: 1,
             },
         ];
 
-        let mut assign = ASTNode::new("=", None, Some(1));
-        let id = ASTNode::new("id", Some(String::from("x")), Some(1));
-        let intlit = ASTNode::new("number", Some(String::from("1")), Some(1));
-
-        assign.add_child(id);
-        assign.add_child(intlit);
+        let mut ast = AST::new();
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("x")), Some(1)));
+        let intlit = ast.add_node(ASTNode::new("number", Some(Arc::from("1")), Some(1)));
+        let assign = add(&mut ast, ASTNode::new("=", None, Some(1)), vec![id, intlit]);
 
-        assert_eq!(assign, assignmentexpression_(&tokens, &mut 0));
+        assert_eq!(
+            ast.get(assign),
+            parse_with(assignmentexpression_, &tokens).get_root()
+        );
 
         // x <= 1;
         tokens[1] = Token {
             token_type: TokenType::LEQ,
-            lexeme: String::from("<="),
+            lexeme: Arc::from("<="),
             line_num: 1,
         };
-        assign.node_type = String::from("<=");
-        assign.attr = None;
+        ast[assign].node_type = String::from("<=");
+        ast[assign].attr = None;
 
-        assert_eq!(assign, assignmentexpression_(&tokens, &mut 0));
+        assert_eq!(
+            ast.get(assign),
+            parse_with(assignmentexpression_, &tokens).get_root()
+        );
     }
 
     #[test]
-    fn test_get_func_sig() {
-        let mut root = ASTNode::new("funcDecl", None, None);
-        let id = ASTNode::new("id", None, None);
-        let params = ASTNode::new("parameters", None, None);
-        let mut param = ASTNode::new("parameter", None, None);
-        let mut int = ASTNode::new("int", None, None);
-        int.type_sig = Some(String::from("int"));
+    fn test_array_element_assignment() {
+        // a[i] = 1;
+        let tokens = vec![
+            Token {
+                token_type: TokenType::ID,
+                lexeme: Arc::from("a"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::ID,
+                lexeme: Arc::from("i"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::ASSIGN,
+                lexeme: Arc::from("="),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INTLIT,
+                lexeme: Arc::from("1"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::SEMICOLON,
+                lexeme: Arc::from(";"),
+                line_num: 1,
+            },
+        ];
+
+        let mut ast = AST::new();
+        let array = ast.add_node(ASTNode::new("id", Some(Arc::from("a")), Some(1)));
+        let i = ast.add_node(ASTNode::new("id", Some(Arc::from("i")), Some(1)));
+        let intlit = ast.add_node(ASTNode::new("number", Some(Arc::from("1")), Some(1)));
+
+        let index = add(
+            &mut ast,
+            ASTNode::new("index", None, Some(1)),
+            vec![array, i],
+        );
+        let assign = add(
+            &mut ast,
+            ASTNode::new("=", None, Some(1)),
+            vec![index, intlit],
+        );
+
+        assert_eq!(
+            ast.get(assign),
+            parse_with(assignmentexpression_, &tokens).get_root()
+        );
+    }
+
+    #[test]
+    fn test_array_declaration() {
+        // int a[10];
+        let tokens = vec![
+            Token {
+                token_type: TokenType::INT,
+                lexeme: Arc::from("int"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::ID,
+                lexeme: Arc::from("a"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INTLIT,
+                lexeme: Arc::from("10"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::SEMICOLON,
+                lexeme: Arc::from(";"),
+                line_num: 1,
+            },
+        ];
+
+        let mut ast = AST::new();
+        let size = ast.add_node(ASTNode::new("number", Some(Arc::from("10")), Some(1)));
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("a")), Some(1)));
+
+        let int_array = add(
+            &mut ast,
+            ASTNode::new("int[]", Some(Arc::from("int[]")), Some(1)),
+            vec![size],
+        );
+        let var_decl = add(
+            &mut ast,
+            ASTNode::new("varDecl", None, Some(1)),
+            vec![int_array, id],
+        );
+
+        assert_eq!(
+            ast.get(var_decl),
+            parse_with(variabledeclaration_, &tokens).get_root()
+        );
+    }
+
+    #[test]
+    fn test_multi_dimensional_array() {
+        // int grid[2][3];
+        let mut tokens = vec![
+            Token {
+                token_type: TokenType::INT,
+                lexeme: Arc::from("int"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::ID,
+                lexeme: Arc::from("grid"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INTLIT,
+                lexeme: Arc::from("2"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::INTLIT,
+                lexeme: Arc::from("3"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::SEMICOLON,
+                lexeme: Arc::from(";"),
+                line_num: 1,
+            },
+        ];
+
+        let mut ast = AST::new();
+        let rows = ast.add_node(ASTNode::new("number", Some(Arc::from("2")), Some(1)));
+        let cols = ast.add_node(ASTNode::new("number", Some(Arc::from("3")), Some(1)));
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("grid")), Some(1)));
+
+        let int_array = add(
+            &mut ast,
+            ASTNode::new("int[][]", Some(Arc::from("int[][]")), Some(1)),
+            vec![rows, cols],
+        );
+        let var_decl = add(
+            &mut ast,
+            ASTNode::new("varDecl", None, Some(1)),
+            vec![int_array, id],
+        );
+
+        assert_eq!(
+            ast.get(var_decl),
+            parse_with(variabledeclaration_, &tokens).get_root()
+        );
+        assert_eq!(
+            Type::Array(Box::new(Type::Array(Box::new(Type::Int)))),
+            ast[int_array].get_type()
+        );
+
+        // grid[1][2];
+        tokens.remove(0);
+        let grid = ast.add_node(ASTNode::new("id", Some(Arc::from("grid")), Some(1)));
+        let one = ast.add_node(ASTNode::new("number", Some(Arc::from("1")), Some(1)));
+        let two = ast.add_node(ASTNode::new("number", Some(Arc::from("2")), Some(1)));
+
+        // The first index is applied first, and so is lower down on the tree
+        let inner = add(
+            &mut ast,
+            ASTNode::new("index", None, Some(1)),
+            vec![grid, one],
+        );
+        let outer = add(
+            &mut ast,
+            ASTNode::new("index", None, Some(1)),
+            vec![inner, two],
+        );
+
+        tokens[2].lexeme = Arc::from("1");
+        tokens[5].lexeme = Arc::from("2");
+        assert_eq!(
+            ast.get(outer),
+            parse_with(assignmentexpression_, &tokens).get_root()
+        );
+    }
+
+    #[test]
+    fn test_array_parameter_type() {
+        // int[] a
+        let tokens = vec![
+            Token {
+                token_type: TokenType::INT,
+                lexeme: Arc::from("int"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 1,
+            },
+            Token {
+                token_type: TokenType::ID,
+                lexeme: Arc::from("a"),
+                line_num: 1,
+            },
+        ];
 
-        root.add_child(id);
-        root.add_child(params);
+        let mut ast = AST::new();
+        let int_array = ast.add_node(ASTNode::new("int[]", Some(Arc::from("int[]")), Some(1)));
+        let id = ast.add_node(ASTNode::new("id", Some(Arc::from("a")), Some(1)));
+        let param = add(
+            &mut ast,
+            ASTNode::new("parameter", None, Some(1)),
+            vec![int_array, id],
+        );
 
-        assert_eq!(String::from("f()"), root.get_func_sig());
+        assert_eq!(
+            ast.get(param),
+            parse_with(formalparameter_, &tokens).get_root()
+        );
+        assert_eq!(Type::Array(Box::new(Type::Int)), ast[int_array].get_type());
+    }
 
-        param.add_child(int);
-        root.children[1].add_child(param);
+    #[test]
+    fn test_get_param_types() {
+        let mut ast = AST::new();
+        let id = ast.add_node(ASTNode::new("id", None, None));
+        let params = ast.add_node(ASTNode::new("parameters", None, None));
+        let root = add(
+            &mut ast,
+            ASTNode::new("funcDecl", None, None),
+            vec![id, params],
+        );
 
-        assert_eq!(String::from("f(int)"), root.get_func_sig());
+        assert_eq!(Vec::<Type>::new(), ast.get(root).get_param_types());
 
-        let mut param2 = ASTNode::new("parameter", None, None);
-        let mut bool = ASTNode::new("bool", None, None);
-        bool.type_sig = Some(String::from("bool"));
+        let mut int = ASTNode::new("int", None, None);
+        int.type_sig = Some(Type::Int);
+        let int = ast.add_node(int);
+        let param = add(&mut ast, ASTNode::new("parameter", None, None), vec![int]);
+        ast[params].add_child(param);
 
-        param2.add_child(bool);
-        root.children[1].add_child(param2);
+        assert_eq!(vec![Type::Int], ast.get(root).get_param_types());
 
-        assert_eq!(String::from("f(int, bool)"), root.get_func_sig());
+        let bool = ast.add_node(ASTNode::new("bool[]", None, None));
+        let param2 = add(&mut ast, ASTNode::new("parameter", None, None), vec![bool]);
+        ast[params].add_child(param2);
+
+        let param_types = ast.get(root).get_param_types();
+        assert_eq!(
+            vec![Type::Int, Type::Array(Box::new(Type::Bool))],
+            param_types
+        );
+
+        // A function's type is displayed as its signature, without what it returns
+        let func_type = Type::Func {
+            params: param_types,
+            ret: Box::new(Type::Int),
+        };
+        assert_eq!("f(int, bool[])", func_type.to_string());
     }
 
     #[test]
     fn test_has_nonempty_return() {
-        let mut root = ASTNode::new("funcDecl", None, None);
-        let mut return_node = ASTNode::new("return", None, None);
-        let return_val = ASTNode::new("id", None, None);
-        return_node.add_child(return_val);
-        root.add_child(return_node);
-
-        assert!(root.has_nonempty_return());
-        assert!(!ASTNode::new("funcDecl", None, None).has_nonempty_return());
+        let mut ast = AST::new();
+        let return_val = ast.add_node(ASTNode::new("id", None, None));
+        let return_node = add(
+            &mut ast,
+            ASTNode::new("return", None, None),
+            vec![return_val],
+        );
+        let root = add(
+            &mut ast,
+            ASTNode::new("funcDecl", None, None),
+            vec![return_node],
+        );
+        let empty = ast.add_node(ASTNode::new("funcDecl", None, None));
+
+        assert!(ast.get(root).has_nonempty_return());
+        assert!(!ast.get(empty).has_nonempty_return());
+    }
+
+    #[test]
+    fn test_merge_programs() {
+        // Builds the program of a file declaring a global variable x, then the program of one declaring main
+        fn first_program(ast: &mut AST) -> NodeId {
+            let int = ast.add_node(ASTNode::new("int", Some(Arc::from("int")), Some(1)));
+            let x = ast.add_node(ASTNode::new("id", Some(Arc::from("x")), Some(1)));
+            return add(
+                ast,
+                ASTNode::new("globVarDecl", None, Some(1)),
+                vec![int, x],
+            );
+        }
+        fn second_program(ast: &mut AST) -> NodeId {
+            let id = ast.add_node(ASTNode::new("id", Some(Arc::from("main")), Some(1)));
+            return add(ast, ASTNode::new("mainFuncDecl", None, Some(1)), vec![id]);
+        }
+
+        let mut first = AST::new();
+        let glob_var = first_program(&mut first);
+        first.root = add(
+            &mut first,
+            ASTNode::new("program", None, None),
+            vec![glob_var],
+        );
+
+        let mut second = AST::new();
+        let main = second_program(&mut second);
+        second.root = add(&mut second, ASTNode::new("program", None, None), vec![main]);
+
+        let programs = vec![
+            (String::from("a.soup"), first),
+            (String::from("b.soup"), second),
+        ];
+
+        // Only functions are traced back to the file they came from
+        let function_files = get_function_files(&programs);
+        assert_eq!(1, function_files.len());
+        assert_eq!("b.soup", function_files["main"]);
+
+        let merged = merge_programs(programs).unwrap();
+
+        // The global declarations of each file are kept in the order the files were given
+        let mut expected = AST::new();
+        let glob_var = first_program(&mut expected);
+        let main = second_program(&mut expected);
+        expected.root = add(
+            &mut expected,
+            ASTNode::new("program", None, None),
+            vec![glob_var, main],
+        );
+
+        assert_eq!(expected.get_root(), merged.get_root());
+
+        // Errors and warnings about a declaration say which file it came from
+        assert_eq!("a.soup: Line 1", merged.get_root().child(0).get_location());
+        assert_eq!("b.soup: Line 1", merged.get_root().child(1).get_location());
+        assert_eq!("Line 1", expected.get_root().child(1).get_location());
+
+        // Two files can't declare the same global
+        let mut duplicate = AST::new();
+        let glob_var = first_program(&mut duplicate);
+        duplicate.root = add(
+            &mut duplicate,
+            ASTNode::new("program", None, None),
+            vec![glob_var],
+        );
+        let mut first = AST::new();
+        let glob_var = first_program(&mut first);
+        first.root = add(
+            &mut first,
+            ASTNode::new("program", None, None),
+            vec![glob_var],
+        );
+        let programs = vec![
+            (String::from("a.soup"), first),
+            (String::from("b.soup"), duplicate),
+        ];
+        assert_eq!(
+            Err(String::from(
+                "b.soup: Line 1: 'x' is declared in both 'a.soup' and 'b.soup'"
+            )),
+            merge_programs(programs).map(|_| ())
+        );
+    }
+
+    // run with "cargo test --release -- --ignored bench_operator_chain --nocapture"
+    #[test]
+    #[ignore]
+    fn bench_operator_chain() {
+        let num_operands = 100000;
+
+        // 1 + 1 + ... + 1, which parses into a left-leaning tree as deep as the chain is long
+        let source = vec!["1"; num_operands].join(" + ");
+        let tokens = scan_str(&source).unwrap();
+
+        let start = Instant::now();
+        let ast = parse_with(expression_, &tokens);
+        let elapsed = start.elapsed();
+
+        assert_eq!(num_operands * 2 - 1, ast.nodes.len());
+        println!(
+            "parsing a chain of {} operands took {:?}",
+            num_operands, elapsed
+        );
     }
 }
@@ -0,0 +1,8 @@
+pub mod passes_manager;
+pub mod passes_stats;
+pub mod passes_timing;
+pub mod passes_trace;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
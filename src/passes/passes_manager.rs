@@ -0,0 +1,128 @@
+use std::convert::Infallible;
+
+use crate::parser::parser_data::{NodeId, AST};
+use crate::passes::passes_timing::time_pass;
+
+// -----------------------------------------------------------------
+// PASSES
+// -----------------------------------------------------------------
+
+// A tree of nodes (each known by its id) that passes can walk over, visiting each node before and after its children
+pub trait Traversable {
+    fn get_root(&self) -> NodeId;
+
+    fn get_children(&self, node: NodeId) -> &[NodeId];
+}
+
+impl Traversable for AST {
+    fn get_root(&self) -> NodeId {
+        return self.root;
+    }
+
+    fn get_children(&self, node: NodeId) -> &[NodeId] {
+        return &self[node].children;
+    }
+}
+
+// A single walk over a tree of nodes, where every pass run by the same pass manager shares a context
+// (for example, the scope stack shared by the semantic passes), and keeps any state of its own in itself.
+// Each node is visited with the whole tree, so that it can look at (but shouldn't change) its children.
+// A pass that can find something wrong with the tree (like the semantic passes) gives it back as an error,
+// which stops the walk, while a pass that can't never has to
+pub trait Pass<Tree, Context, Error = Infallible> {
+    // The name the pass is known by when it's timed (like "type checking")
+    fn get_name(&self) -> &'static str;
+
+    // Called on each node before any of its children are visited
+    fn pre_visit(
+        &mut self,
+        _tree: &mut Tree,
+        _node: NodeId,
+        _context: &mut Context,
+    ) -> Result<(), Error> {
+        return Ok(());
+    }
+
+    // Called on each node after all of its children have been visited
+    fn post_visit(
+        &mut self,
+        _tree: &mut Tree,
+        _node: NodeId,
+        _context: &mut Context,
+    ) -> Result<(), Error> {
+        return Ok(());
+    }
+
+    // Called once the whole tree has been walked
+    fn finish(&mut self, _context: &mut Context) -> Result<(), Error> {
+        return Ok(());
+    }
+}
+
+// -----------------------------------------------------------------
+// PASS MANAGER
+// -----------------------------------------------------------------
+
+// Runs a list of passes over a tree, one whole pass after another, in the order they were added
+pub struct PassManager<Tree, Context, Error = Infallible> {
+    pub passes: Vec<Box<dyn Pass<Tree, Context, Error>>>,
+}
+
+impl<Tree: Traversable, Context, Error> Default for PassManager<Tree, Context, Error> {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl<Tree: Traversable, Context, Error> PassManager<Tree, Context, Error> {
+    pub fn new() -> PassManager<Tree, Context, Error> {
+        return PassManager { passes: vec![] };
+    }
+
+    // Add a pass to be run after every pass added so far
+    pub fn add_pass(&mut self, pass: Box<dyn Pass<Tree, Context, Error>>) {
+        self.passes.push(pass);
+    }
+
+    // Run every pass over the whole tree, timing each one (see time_pass),
+    // stopping at the first error any of them finds
+    pub fn run(&mut self, tree: &mut Tree, context: &mut Context) -> Result<(), Error> {
+        for pass in &mut self.passes {
+            time_pass(pass.get_name(), || {
+                let root = tree.get_root();
+                walk(pass.as_mut(), tree, root, context)?;
+                return pass.finish(context);
+            })?;
+        }
+        return Ok(());
+    }
+}
+
+// Walk a single pass over the part of a tree below (and including) the given node, which can also be used on its own
+// for a one-off walk that isn't part of a pass manager (without calling the pass's finish)
+pub fn walk<Tree: Traversable, Context, Error>(
+    pass: &mut dyn Pass<Tree, Context, Error>,
+    tree: &mut Tree,
+    node: NodeId,
+    context: &mut Context,
+) -> Result<(), Error> {
+    pass.pre_visit(tree, node, context)?;
+
+    // Each node being walked is kept on a stack along with the position of the next of its children to visit,
+    // rather than recursing, since a long chain of binary operators makes a tree far deeper than the stack allows.
+    // The children are looked up one at a time, since the tree can't be borrowed while a pass is changing it
+    let mut stack = vec![(node, 0)];
+    while let Some((node, next_child)) = stack.pop() {
+        if next_child == tree.get_children(node).len() {
+            pass.post_visit(tree, node, context)?;
+            continue;
+        }
+
+        let child = tree.get_children(node)[next_child];
+        stack.push((node, next_child + 1));
+        pass.pre_visit(tree, child, context)?;
+        stack.push((child, 0));
+    }
+
+    return Ok(());
+}
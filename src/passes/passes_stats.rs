@@ -0,0 +1,105 @@
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::ir::ir_data::{Instr, IrProgram};
+use crate::parser::parser_data::{NodeRef, AST};
+
+// -----------------------------------------------------------------
+// STATISTICS
+// -----------------------------------------------------------------
+
+// How much of everything the compiler made out of a program (--stats), for seeing how big a program really is,
+// and how much an optimization level changes what's generated for it
+#[derive(PartialEq, Debug)]
+pub struct CompileStats {
+    pub tokens: usize,
+    pub ast_nodes: usize,
+    // The globals, functions, parameters and local variables declared by the program
+    pub symbols: usize,
+    pub string_literals: usize,
+    // The instructions in the (optimized) IR, not counting line markers
+    pub ir_instrs: usize,
+    pub asm_instrs: usize,
+    // The function with the largest stack frame, and its size in bytes
+    // (which WebAssembly functions don't have, since they keep everything in locals)
+    pub largest_frame: Option<(String, i32)>,
+}
+
+// Count the nodes of the given tree, including its root, that pass the given filter
+pub fn count_nodes(node: NodeRef, filter: &dyn Fn(NodeRef) -> bool) -> usize {
+    return node
+        .descendants()
+        .into_iter()
+        .filter(|node| filter(*node))
+        .count();
+}
+
+// Count the symbols a program declares: its globals and functions,
+// along with each function's parameters and local variables (which are only declared in its outermost scope)
+pub fn count_symbols(ast: &AST) -> usize {
+    let mut num_symbols = 0;
+    for decl in ast.get_root().children() {
+        num_symbols += 1;
+
+        if decl.node_type != "globVarDecl" {
+            num_symbols += decl.child(1).children().len();
+            num_symbols += decl
+                .child(3)
+                .children()
+                .filter(|statement| statement.node_type == "varDecl")
+                .count();
+        }
+    }
+    return num_symbols;
+}
+
+// Count the instructions in a program's IR, leaving out the line markers (which don't generate anything)
+pub fn count_ir_instrs(program: &IrProgram) -> usize {
+    return program
+        .functions
+        .iter()
+        .flat_map(|func| func.body.iter())
+        .filter(|instr| !matches!(instr, Instr::Line(_)))
+        .count();
+}
+
+// Count the instructions in generated assembly (or WebAssembly text), which are the indented lines that aren't
+// directives, comments, or (in WebAssembly) the start or end of a declaration like a function
+pub fn count_asm_instrs(asm: &str, target: &TargetInfo) -> usize {
+    return asm
+        .lines()
+        .filter(|line| line.starts_with(' '))
+        .map(|line| line.trim())
+        .filter(|line| {
+            return !line.is_empty()
+                && !line.starts_with('.')
+                && !line.starts_with('(')
+                && !line.starts_with(')')
+                && !line.starts_with(target.comment_prefix);
+        })
+        .count();
+}
+
+// Format the statistics of a program as a table, in the same style as the time spent in each stage (see passes_timing)
+pub fn format_stats(stats: &CompileStats) -> String {
+    let mut table = String::new();
+
+    let counts = [
+        (stats.tokens, "tokens"),
+        (stats.ast_nodes, "AST nodes"),
+        (stats.symbols, "symbols"),
+        (stats.string_literals, "string literals"),
+        (stats.ir_instrs, "IR instructions"),
+        (stats.asm_instrs, "assembly instructions"),
+    ];
+    for (count, name) in counts {
+        table.push_str(&format!("{:>10}  {}\n", count, name));
+    }
+
+    match &stats.largest_frame {
+        Some((func, size)) => table.push_str(&format!(
+            "{:>10}  bytes in the largest stack frame (function '{}')\n",
+            size, func
+        )),
+        None => table.push_str(&format!("{:>10}  bytes in the largest stack frame\n", "-")),
+    }
+    return table;
+}
@@ -0,0 +1,176 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::passes::passes_trace::log_span;
+
+// -----------------------------------------------------------------
+// COUNTING ALLOCATOR
+// -----------------------------------------------------------------
+
+// The number of bytes currently allocated, and the most that have been allocated at once since the peak was last reset
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+// The system allocator, keeping count of how much memory is allocated so that --time-passes can report it.
+// It only counts if it's made the global allocator (which the soup executable does)
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn add(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+    }
+
+    fn sub(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every allocation is passed straight on to the system allocator, only keeping count of its size on the way
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            CountingAllocator::add(layout.size());
+        }
+        return ptr;
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            CountingAllocator::add(layout.size());
+        }
+        return ptr;
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CountingAllocator::sub(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CountingAllocator::sub(layout.size());
+            CountingAllocator::add(new_size);
+        }
+        return new_ptr;
+    }
+}
+
+// -----------------------------------------------------------------
+// TIMING PASSES
+// -----------------------------------------------------------------
+
+// The time spent in a stage of the compiler, and the most memory it had allocated at once
+// (on top of whatever was already allocated when it started)
+#[derive(Clone, PartialEq, Debug)]
+pub struct PassTime {
+    pub name: String,
+    pub time: Duration,
+    pub peak_allocated: usize,
+}
+
+// Whether the stages of the compiler are being timed, set once from the command line before compiling
+static TIMING_PASSES: AtomicBool = AtomicBool::new(false);
+
+// Every stage timed so far, in the order each one first ran
+static PASS_TIMES: Mutex<Vec<PassTime>> = Mutex::new(Vec::new());
+
+pub fn set_time_passes(time_passes: bool) {
+    TIMING_PASSES.store(time_passes, Ordering::Relaxed);
+}
+
+// Run a stage of the compiler inside a span of its own (see log_span),
+// timing it (along with the memory it allocates) if we were asked to.
+// A stage that runs more than once (like an optimization, which runs on each function) adds up the time of every run,
+// and keeps the most memory any one run allocated.
+// The files are scanned and parsed at the same time, so the memory each file's stages allocate includes the others'
+pub fn time_pass<T>(name: &str, stage: impl FnOnce() -> T) -> T {
+    if !TIMING_PASSES.load(Ordering::Relaxed) {
+        return log_span(|| String::from(name), stage);
+    }
+
+    // Stages can be nested, so the peak of the stage this one is part of is put back once it's done
+    let start_allocated = ALLOCATED.load(Ordering::Relaxed);
+    let outer_peak = PEAK_ALLOCATED.swap(start_allocated, Ordering::Relaxed);
+    let start = Instant::now();
+
+    let result = log_span(|| String::from(name), stage);
+
+    let time = start.elapsed();
+    let peak_allocated = PEAK_ALLOCATED
+        .fetch_max(outer_peak, Ordering::Relaxed)
+        .saturating_sub(start_allocated);
+
+    let mut pass_times = PASS_TIMES.lock().unwrap();
+    match pass_times
+        .iter_mut()
+        .find(|pass_time| pass_time.name == name)
+    {
+        Some(pass_time) => {
+            pass_time.time += time;
+            pass_time.peak_allocated = pass_time.peak_allocated.max(peak_allocated);
+        }
+        None => pass_times.push(PassTime {
+            name: String::from(name),
+            time: time,
+            peak_allocated: peak_allocated,
+        }),
+    }
+
+    return result;
+}
+
+// Prints the time spent in every stage timed so far to stderr (if we were asked to time them) once it's dropped,
+// so that it's printed however compiling the program ends up finishing
+pub struct PassTimesReport;
+
+impl Drop for PassTimesReport {
+    fn drop(&mut self) {
+        if TIMING_PASSES.load(Ordering::Relaxed) {
+            eprint!("{}", format_pass_times(&PASS_TIMES.lock().unwrap()));
+        }
+    }
+}
+
+// Format the time spent in each stage (and the memory it allocated) as a table, with the total time at the bottom
+pub fn format_pass_times(pass_times: &[PassTime]) -> String {
+    let mut table = format!("{:>10}  {:>12}  {}\n", "Time", "Peak memory", "Stage");
+
+    for pass_time in pass_times {
+        table.push_str(&format!(
+            "{:>10}  {:>12}  {}\n",
+            format_time(pass_time.time),
+            format_size(pass_time.peak_allocated),
+            pass_time.name
+        ));
+    }
+
+    let total = pass_times.iter().map(|pass_time| pass_time.time).sum();
+    table.push_str(&format!(
+        "{:>10}  {:>12}  {}\n",
+        format_time(total),
+        "",
+        "Total"
+    ));
+    return table;
+}
+
+// Format a time in milliseconds
+fn format_time(time: Duration) -> String {
+    return format!("{:.3}ms", time.as_secs_f64() * 1000.0);
+}
+
+// Format a number of bytes in the largest unit it's at least one of
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    } else if bytes < 1024 * 1024 {
+        return format!("{:.1}KiB", bytes as f64 / 1024.0);
+    }
+    return format!("{:.1}MiB", bytes as f64 / (1024.0 * 1024.0));
+}
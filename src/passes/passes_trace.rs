@@ -0,0 +1,113 @@
+use std::cell::Cell;
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+// -----------------------------------------------------------------
+// TRACING
+// -----------------------------------------------------------------
+
+// How much of what the compiler is doing internally gets traced to stderr, set with the SOUP_LOG environment variable
+// (like "SOUP_LOG=debug"), where each level includes everything traced at the levels before it
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+// The environment variable the level of tracing is read from
+pub const LOG_ENV_VAR: &str = "SOUP_LOG";
+
+thread_local! {
+    // How many spans are open on this thread, which is how far everything traced inside them is indented
+    static SPAN_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// Get the level of tracing named by the value of SOUP_LOG (ignoring case), or None if it isn't one
+pub fn get_log_level(value: &str) -> Option<LogLevel> {
+    return match value.trim().to_lowercase().as_str() {
+        "" | "off" => Some(LogLevel::Off),
+        "error" => Some(LogLevel::Error),
+        "warn" => Some(LogLevel::Warn),
+        "info" => Some(LogLevel::Info),
+        "debug" => Some(LogLevel::Debug),
+        "trace" => Some(LogLevel::Trace),
+        _ => None,
+    };
+}
+
+// Check whether anything at the given level is being traced, which is read from SOUP_LOG the first time it's needed
+// (anything SOUP_LOG can't be understood as turns tracing off)
+pub fn log_enabled(level: LogLevel) -> bool {
+    static MAX_LEVEL: OnceLock<LogLevel> = OnceLock::new();
+    let max_level = MAX_LEVEL.get_or_init(|| {
+        return env::var(LOG_ENV_VAR)
+            .ok()
+            .and_then(|value| get_log_level(&value))
+            .unwrap_or(LogLevel::Off);
+    });
+
+    return level != LogLevel::Off && level <= *max_level;
+}
+
+// Trace something the compiler did at the given level, only building the message if it's actually traced
+pub fn log_event(level: LogLevel, msg: impl FnOnce() -> String) {
+    if log_enabled(level) {
+        eprintln!("{}", format_log_line(level, SPAN_DEPTH.get(), &msg()));
+    }
+}
+
+// Run part of the compiler (like a pass, or the code generated for a single function) inside a span, which traces
+// when it starts and finishes (and how long it took) at the debug level, indenting everything traced inside it
+pub fn log_span<T>(name: impl FnOnce() -> String, inside: impl FnOnce() -> T) -> T {
+    if !log_enabled(LogLevel::Debug) {
+        return inside();
+    }
+
+    let name = name();
+    let depth = SPAN_DEPTH.get();
+    eprintln!(
+        "{}",
+        format_log_line(LogLevel::Debug, depth, &format!("> {}", name))
+    );
+
+    SPAN_DEPTH.set(depth + 1);
+    let start = Instant::now();
+    let result = inside();
+    let time = start.elapsed();
+    SPAN_DEPTH.set(depth);
+
+    eprintln!(
+        "{}",
+        format_log_line(
+            LogLevel::Debug,
+            depth,
+            &format!("< {} ({:.3}ms)", name, time.as_secs_f64() * 1000.0)
+        )
+    );
+    return result;
+}
+
+// Format a line of tracing, indented by how many spans it's inside of
+pub fn format_log_line(level: LogLevel, depth: usize, msg: &str) -> String {
+    return format!("soup[{}]: {}{}", level, "  ".repeat(depth), msg);
+}
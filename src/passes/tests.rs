@@ -0,0 +1,265 @@
+mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use crate::code_gen::code_gen_target::TARGETS;
+    use crate::parser::parser_data::{ASTNode, NodeId, AST};
+    use crate::parser::parser_driver::parser;
+    use crate::passes::passes_manager::*;
+    use crate::passes::passes_stats::*;
+    use crate::passes::passes_timing::{format_pass_times, PassTime};
+    use crate::passes::passes_trace::{format_log_line, get_log_level, LogLevel};
+    use crate::scanner::scanner_driver::scan_str;
+
+    // Records every node it visits (and when) in the shared context, tagged with the given name
+    struct RecordingPass {
+        name: &'static str,
+    }
+
+    impl Pass<AST, Vec<String>> for RecordingPass {
+        fn get_name(&self) -> &'static str {
+            return self.name;
+        }
+
+        fn pre_visit(
+            &mut self,
+            ast: &mut AST,
+            node: NodeId,
+            visited: &mut Vec<String>,
+        ) -> Result<(), Infallible> {
+            visited.push(format!("{} pre {}", self.name, ast[node].node_type));
+            return Ok(());
+        }
+
+        fn post_visit(
+            &mut self,
+            ast: &mut AST,
+            node: NodeId,
+            visited: &mut Vec<String>,
+        ) -> Result<(), Infallible> {
+            visited.push(format!("{} post {}", self.name, ast[node].node_type));
+            return Ok(());
+        }
+
+        fn finish(&mut self, visited: &mut Vec<String>) -> Result<(), Infallible> {
+            visited.push(format!("{} finish", self.name));
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn test_pass_manager_order() {
+        let mut ast = AST::new();
+        let mut root = ASTNode::new("program", None, None);
+        root.add_child(ast.add_node(ASTNode::new("a", None, None)));
+        root.add_child(ast.add_node(ASTNode::new("b", None, None)));
+        ast.root = ast.add_node(root);
+
+        let mut passes = PassManager::new();
+        passes.add_pass(Box::new(RecordingPass { name: "first" }));
+        passes.add_pass(Box::new(RecordingPass { name: "second" }));
+
+        let mut visited = vec![];
+        let Ok(()) = passes.run(&mut ast, &mut visited);
+
+        // Each pass walks the whole tree (visiting each node before and after its children) before the next one starts
+        let expected = vec![
+            "first pre program",
+            "first pre a",
+            "first post a",
+            "first pre b",
+            "first post b",
+            "first post program",
+            "first finish",
+            "second pre program",
+            "second pre a",
+            "second post a",
+            "second pre b",
+            "second post b",
+            "second post program",
+            "second finish",
+        ];
+        assert_eq!(expected, visited);
+    }
+
+    // Records every node it visits like RecordingPass, but fails on the first node of the given type
+    struct FailingPass {
+        node_type: &'static str,
+    }
+
+    impl Pass<AST, Vec<String>, String> for FailingPass {
+        fn get_name(&self) -> &'static str {
+            return "failing";
+        }
+
+        fn pre_visit(
+            &mut self,
+            ast: &mut AST,
+            node: NodeId,
+            visited: &mut Vec<String>,
+        ) -> Result<(), String> {
+            if ast[node].node_type == self.node_type {
+                return Err(format!("found {}", self.node_type));
+            }
+            visited.push(format!("failing pre {}", ast[node].node_type));
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn test_pass_manager_error() {
+        let mut ast = AST::new();
+        let mut root = ASTNode::new("program", None, None);
+        root.add_child(ast.add_node(ASTNode::new("a", None, None)));
+        root.add_child(ast.add_node(ASTNode::new("b", None, None)));
+        root.add_child(ast.add_node(ASTNode::new("c", None, None)));
+        ast.root = ast.add_node(root);
+
+        let mut passes: PassManager<AST, Vec<String>, String> = PassManager::new();
+        passes.add_pass(Box::new(FailingPass { node_type: "b" }));
+        passes.add_pass(Box::new(FailingPass { node_type: "d" }));
+
+        // The first error stops the walk, and no pass after it is run
+        let mut visited = vec![];
+        assert_eq!(
+            Err(String::from("found b")),
+            passes.run(&mut ast, &mut visited)
+        );
+        assert_eq!(vec!["failing pre program", "failing pre a"], visited);
+    }
+
+    // Counts the nodes of each type it visits
+    struct CountingPass {
+        node_type: &'static str,
+        count: usize,
+    }
+
+    impl Pass<AST, ()> for CountingPass {
+        fn get_name(&self) -> &'static str {
+            return "counting";
+        }
+
+        fn post_visit(
+            &mut self,
+            ast: &mut AST,
+            node: NodeId,
+            _context: &mut (),
+        ) -> Result<(), Infallible> {
+            if ast[node].node_type == self.node_type {
+                self.count += 1;
+            }
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut ast = AST::new();
+        let mut inner = ASTNode::new("block", None, None);
+        inner.add_child(ast.add_node(ASTNode::new("return", None, None)));
+        let inner = ast.add_node(inner);
+        let mut root = ASTNode::new("block", None, None);
+        root.add_child(inner);
+        root.add_child(ast.add_node(ASTNode::new("return", None, None)));
+        let root = ast.add_node(root);
+
+        // A single pass can be walked over a tree without a pass manager, keeping whatever it finds in itself
+        let mut pass = CountingPass {
+            node_type: "return",
+            count: 0,
+        };
+        let Ok(()) = walk(&mut pass, &mut ast, root, &mut ());
+        assert_eq!(2, pass.count);
+
+        // including over part of a tree
+        pass.count = 0;
+        let Ok(()) = walk(&mut pass, &mut ast, inner, &mut ());
+        assert_eq!(1, pass.count);
+    }
+
+    #[test]
+    fn test_format_pass_times() {
+        let pass_times = vec![
+            PassTime {
+                name: String::from("type checking"),
+                time: Duration::from_micros(1500),
+                peak_allocated: 512,
+            },
+            PassTime {
+                name: String::from("code generation"),
+                time: Duration::from_micros(250),
+                peak_allocated: 3 * 1024 * 1024 / 2,
+            },
+        ];
+
+        let expected = "      Time   Peak memory  Stage
+   1.500ms          512B  type checking
+   0.250ms        1.5MiB  code generation
+   1.750ms                Total
+";
+        assert_eq!(expected, format_pass_times(&pass_times));
+    }
+
+    #[test]
+    fn test_count_stats() {
+        let source = "int total = 0;\nfunc add(int x, int[] xs) returns void {\nint y = x;\nprintf(\"{}\\n\", y);\n}\nfunc main() returns void {\nint z;\nadd(1, \"ab\");\n}\n";
+        let ast = parser(&scan_str(source).unwrap());
+
+        // total, add, x, xs, y, main and z
+        assert_eq!(7, count_symbols(&ast));
+        assert_eq!(
+            2,
+            count_nodes(ast.get_root(), &|node| node.node_type == "string")
+        );
+        assert_eq!(
+            1,
+            count_nodes(ast.get_root(), &|node| node.node_type == "program")
+        );
+
+        // Only instructions are counted, not labels, directives, data or comments
+        let asm = "LG0: .word 0\n_soup_main:\n        .loc 1 2\n        // line 2: int z;\n        mov     w0, 1\n        ret\n";
+        assert_eq!(2, count_asm_instrs(asm, &TARGETS[0]));
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let mut stats = CompileStats {
+            tokens: 1200,
+            ast_nodes: 640,
+            symbols: 25,
+            string_literals: 4,
+            ir_instrs: 310,
+            asm_instrs: 512,
+            largest_frame: Some((String::from("main"), 48)),
+        };
+
+        let expected = "      1200  tokens
+       640  AST nodes
+        25  symbols
+         4  string literals
+       310  IR instructions
+       512  assembly instructions
+        48  bytes in the largest stack frame (function 'main')
+";
+        assert_eq!(expected, format_stats(&stats));
+
+        stats.largest_frame = None;
+        assert!(format_stats(&stats).ends_with("\n         -  bytes in the largest stack frame\n"));
+    }
+
+    #[test]
+    fn test_log_levels() {
+        assert_eq!(Some(LogLevel::Debug), get_log_level("debug"));
+        assert_eq!(Some(LogLevel::Trace), get_log_level(" TRACE\n"));
+        assert_eq!(Some(LogLevel::Off), get_log_level(""));
+        assert_eq!(None, get_log_level("verbose"));
+
+        // Each level includes everything traced at the levels before it
+        assert!(LogLevel::Warn < LogLevel::Debug && LogLevel::Debug < LogLevel::Trace);
+
+        assert_eq!(
+            "soup[trace]:     skipping a comment on line 3",
+            format_log_line(LogLevel::Trace, 2, "skipping a comment on line 3")
+        );
+    }
+}
@@ -0,0 +1,6 @@
+pub mod repl_driver;
+pub mod repl_utils;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
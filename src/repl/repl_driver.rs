@@ -0,0 +1,415 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the main logic of "soup repl", which reads functions, statements and expressions line by line,
+// running each of them with the interpreter as soon as it's been entered, in a session that remembers the functions
+// and global variables declared so far
+// -------------------------------------------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+use crate::formatter::formatter_printer::format_declarator;
+use crate::interpreter::interpreter_data::{Frame, Stop, Value};
+use crate::interpreter::interpreter_driver::Interpreter;
+use crate::ir::ir_utils::check_strings;
+use crate::parser::parser_data::{NodeRef, ParseError, AST};
+use crate::parser::parser_driver::parse_program;
+use crate::repl::repl_utils::{format_value, get_line_num, is_incomplete};
+use crate::scanner::scanner_data::TokenType;
+use crate::scanner::scanner_driver::scan_str;
+use crate::semantic::semantic_data::Warnings;
+use crate::semantic::semantic_driver::run_semantic_passes;
+
+// The message printed by ":help"
+const HELP: &str = "\
+Enter a function declaration to declare it (or replace the function with the same name),
+one or more statements (ending with a ; or a }) to run them as if they were in main,
+or an expression to print its value. Variables declared outside of any block become global variables.
+Input carries on over more than one line until every parenthesis, bracket and brace is closed.
+
+Commands:
+    :help    Print this message
+    :quit    Quit (as does the end of the input)";
+
+// Run the REPL over stdin and stdout until the input ends (or the user quits, or a program calls exit)
+pub fn run_repl(overflow_checks: bool, warnings: Warnings) {
+    let mut input = io::stdin().lock();
+    let mut output = ReplOutput {
+        inner: io::stdout().lock(),
+        at_line_start: true,
+    };
+    let mut session = ReplSession::new(overflow_checks, warnings);
+
+    let _ = writeln!(
+        output,
+        "soup {} (enter :help for help, or :quit to quit)",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    loop {
+        let mut entry = String::new();
+
+        // Keep reading lines until every parenthesis, bracket and brace has been closed
+        loop {
+            let prompt = if entry.is_empty() { ">> " } else { ".. " };
+            let _ = write!(output, "{}", prompt);
+            let _ = output.flush();
+
+            let mut line = String::new();
+            match input.read_line(&mut line) {
+                Ok(0) | Err(_) => {
+                    let _ = writeln!(output);
+                    let _ = output.flush();
+                    return;
+                }
+                Ok(_) => output.at_line_start = true,
+            }
+
+            entry.push_str(&line);
+            if !is_incomplete(&entry) {
+                break;
+            }
+        }
+
+        let entry = entry.trim();
+        if entry.is_empty() {
+            continue;
+        } else if entry == ":quit" {
+            return;
+        } else if entry == ":help" {
+            let _ = writeln!(output, "{}", HELP);
+            continue;
+        } else if entry.starts_with(':') {
+            let _ = writeln!(
+                output,
+                "Error: Unknown command '{}', enter :help for help",
+                entry
+            );
+            continue;
+        }
+
+        if let Some(code) = session.evaluate(entry, &mut output) {
+            let _ = output.flush();
+            process::exit(code);
+        }
+
+        // Whatever was printed is finished off before the next prompt
+        if !output.at_line_start {
+            let _ = writeln!(output);
+        }
+    }
+}
+
+// The output of the REPL, which keeps track of whether it's at the start of a line
+struct ReplOutput<W: Write> {
+    inner: W,
+    at_line_start: bool,
+}
+
+impl<W: Write> Write for ReplOutput<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if written > 0 {
+            self.at_line_start = buf[written - 1] == b'\n';
+        }
+        return Ok(written);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+// -----------------------------------------------------------------------------------------
+// SESSIONS
+// -----------------------------------------------------------------------------------------
+
+// Everything the REPL remembers from one piece of input to the next
+pub struct ReplSession {
+    // The global declarations entered so far (in the order they were first entered), along with the name each declares:
+    // each function as it was entered, and each global variable as its type and name (since its value is kept separately)
+    decls: Vec<(String, String)>,
+    globals: HashMap<String, Value>,
+    overflow_checks: bool,
+    // The warnings turned on for everything entered
+    warnings: Warnings,
+}
+
+impl ReplSession {
+    pub fn new(overflow_checks: bool, warnings: Warnings) -> ReplSession {
+        return ReplSession {
+            decls: vec![],
+            globals: HashMap::new(),
+            overflow_checks: overflow_checks,
+            warnings: warnings,
+        };
+    }
+
+    // Run a piece of input, which is either a function declaration, some statements or an expression,
+    // printing everything it prints (along with any warnings and errors in it) to the given output.
+    // Gives back the exit code the input exited with, if it called exit
+    pub fn evaluate(&mut self, input: &str, output: &mut dyn Write) -> Option<i32> {
+        let first_token = scan_str(input)
+            .ok()
+            .and_then(|tokens| tokens.first().map(|token| token.token_type));
+
+        if first_token == Some(TokenType::FUNC) {
+            self.declare_function(input, output);
+            return None;
+        } else if input.ends_with(';') || input.ends_with('}') {
+            return self.run_statements(input, output);
+        }
+        return self.print_expression(input, output);
+    }
+
+    // Declare a function, replacing any function (or global variable) with the same name
+    fn declare_function(&mut self, input: &str, output: &mut dyn Write) {
+        let parsed = scan_str(input)
+            .map_err(ParseError::from)
+            .and_then(|tokens| parse_program(&tokens));
+        let name = match parsed {
+            Ok(ast) => match ast.get_root().children().collect::<Vec<NodeRef>>()[..] {
+                [decl] if decl.node_type == "funcDecl" => decl.child(0).get_attr().to_string(),
+                [decl] if decl.node_type == "mainFuncDecl" => {
+                    let _ = writeln!(output, "Error: main can't be declared in the REPL, since everything entered is run as if it were in main");
+                    return;
+                }
+                _ => {
+                    let _ = writeln!(output, "Error: Only one function can be declared at a time");
+                    return;
+                }
+            },
+            Err(error) => {
+                let _ = writeln!(output, "Error: {}", error);
+                return;
+            }
+        };
+
+        // The function is checked along with every other declaration (but not the one it's replacing)
+        let others = self
+            .decls
+            .iter()
+            .filter(|(decl_name, _)| *decl_name != name)
+            .cloned()
+            .collect();
+        let text = format!("{}\nfunc main() returns void {{;}}", input);
+        if let Some((ast, _)) = self.check_program(&text, input, &others, output) {
+            if check_part(ast.get_root().child(0), output) {
+                self.decls = self.replace_decls(vec![(name.clone(), String::from(input))]);
+                self.globals.remove(&name);
+            }
+        }
+    }
+
+    // Run some statements as if they were in main, where any variables declared outside of any block
+    // become global variables once they've all been run
+    fn run_statements(&mut self, input: &str, output: &mut dyn Write) -> Option<i32> {
+        let text = format!("func main() returns void {{{}\n}}", input);
+        let (ast, lines) = self.check_program(&text, input, &self.decls, output)?;
+        let block = ast.get_root().child(0).child(3);
+        if !check_part(block, output) {
+            return None;
+        }
+
+        // Make sure the variables can be declared globally before running anything
+        // (a global variable can't replace a function that's called somewhere, for example)
+        let new_decls: Vec<NodeRef> = block
+            .children()
+            .filter(|statement| statement.node_type == "varDecl")
+            .collect();
+        let decls = self.replace_decls(
+            new_decls
+                .iter()
+                .map(|decl| {
+                    (
+                        decl.child(1).get_attr().to_string(),
+                        format!("{};", format_declarator(*decl)),
+                    )
+                })
+                .collect(),
+        );
+        if !new_decls.is_empty() {
+            self.check_program("func main() returns void {;}", "", &decls, output)?;
+        }
+
+        let mut frame = Frame::new();
+        let mut interpreter = Interpreter::new(
+            &ast,
+            std::mem::take(&mut self.globals),
+            output,
+            self.overflow_checks,
+        );
+        let result = interpreter.execute_statement(block, &mut frame);
+        self.globals = interpreter.globals;
+
+        // Anything the statements did before an error is kept, but nothing they declared is
+        match result {
+            Ok(_) => {}
+            Err(stop) => return report_stop(stop, &lines, output),
+        }
+
+        for decl in new_decls {
+            if let Some(value) = frame.get(&decl.get_sym()) {
+                self.globals
+                    .insert(decl.child(1).get_attr().to_string(), value.clone());
+            }
+        }
+        self.decls = decls;
+        return None;
+    }
+
+    // Work out the value of an expression and print it
+    fn print_expression(&mut self, input: &str, output: &mut dyn Write) -> Option<i32> {
+        // The expression is checked as an argument to printf, which can be given a value of any type
+        let text = format!("func main() returns void {{printf(\"\", {}\n);}}", input);
+        let (ast, lines) = self.check_program(&text, input, &self.decls, output)?;
+        let expr = ast
+            .get_root()
+            .child(0)
+            .child(3)
+            .child(0)
+            .child(1)
+            .child(1)
+            .child(0);
+        if !check_part(expr, output) {
+            return None;
+        }
+
+        let mut interpreter = Interpreter::new(
+            &ast,
+            std::mem::take(&mut self.globals),
+            output,
+            self.overflow_checks,
+        );
+        let result = interpreter.evaluate(expr, &mut Frame::new());
+        self.globals = interpreter.globals;
+
+        match result {
+            Ok(value) => {
+                if let Some(text) = format_value(&value, &expr.get_type()) {
+                    let _ = writeln!(output, "{}", text);
+                }
+            }
+            Err(stop) => return report_stop(stop, &lines, output),
+        }
+        return None;
+    }
+
+    // Get the session's declarations with some of them replaced (or added to the end, if they're new)
+    fn replace_decls(&self, new_decls: Vec<(String, String)>) -> Vec<(String, String)> {
+        let mut decls = self.decls.clone();
+
+        for (name, text) in new_decls {
+            match decls.iter_mut().find(|(decl_name, _)| *decl_name == name) {
+                Some(decl) => decl.1 = text,
+                None => decls.push((name, text)),
+            }
+        }
+
+        return decls;
+    }
+
+    // Scan, parse and check a program made up of some text (which the input is at the very start of, so the line numbers
+    // of anything reported about it are the lines of the input) followed by the given declarations, printing any errors
+    // to the output, along with any warnings about the input (the declarations were already warned about when they were entered).
+    // Its strings aren't checked, since the text can wrap the input in a call to printf that isn't meant to print anything
+    // (see check_part)
+    fn check_program(
+        &self,
+        text: &str,
+        input: &str,
+        decls: &Vec<(String, String)>,
+        output: &mut dyn Write,
+    ) -> Option<(AST, ProgramLines)> {
+        let mut program = format!("{}\n", text);
+        let mut lines = ProgramLines {
+            num_input_lines: input.lines().count().max(1) as i32,
+            decl_lines: vec![],
+        };
+        for (name, decl) in decls {
+            lines
+                .decl_lines
+                .push((program.lines().count() as i32 + 1, name.clone()));
+            program.push_str(&format!("{}\n", decl));
+        }
+
+        let parsed = scan_str(&program)
+            .map_err(ParseError::from)
+            .and_then(|tokens| parse_program(&tokens));
+        let mut ast = match parsed {
+            Ok(ast) => ast,
+            Err(error) => {
+                let _ = writeln!(output, "Error: {}", error);
+                return None;
+            }
+        };
+
+        let mut warnings = self.warnings.clone();
+        let result = run_semantic_passes(&mut ast, &mut warnings);
+
+        for warning in warnings.found {
+            if get_line_num(&warning).unwrap_or(0) <= lines.num_input_lines {
+                let _ = writeln!(output, "Warning: {}", warning);
+            }
+        }
+
+        if let Err(msg) = result {
+            let _ = writeln!(output, "Error: {}", lines.locate(&msg));
+            return None;
+        }
+
+        return Some((ast, lines));
+    }
+}
+
+// Where the input and each of the session's declarations are in a program checked by the REPL
+struct ProgramLines {
+    num_input_lines: i32,
+    // The line each declaration starts on, along with its name
+    decl_lines: Vec<(i32, String)>,
+}
+
+impl ProgramLines {
+    // Work out what a message about the program (starting with the line it's about, like "Line 3: ...") is about.
+    // The line numbers of the session's declarations mean nothing to the user, so a message about one of them
+    // is reported as being in that declaration instead
+    fn locate(&self, msg: &str) -> String {
+        match get_line_num(msg) {
+            Some(line_num) if line_num > self.num_input_lines => {
+                let name = self
+                    .decl_lines
+                    .iter()
+                    .rev()
+                    .find(|(start, _)| *start <= line_num)
+                    .map(|(_, name)| name.as_str())
+                    .unwrap_or_default();
+                let msg = msg.split_once(": ").map(|(_, msg)| msg).unwrap_or(msg);
+                return format!("In '{}': {}", name, msg);
+            }
+            _ => return String::from(msg),
+        }
+    }
+}
+
+// Check the string literals and printf format strings in part of a checked program,
+// returning false (once the error has been printed) if there's an error in one of them
+fn check_part(node: NodeRef, output: &mut dyn Write) -> bool {
+    if let Err(msg) = check_strings(node) {
+        let _ = writeln!(output, "Error: {}", msg);
+        return false;
+    }
+    return true;
+}
+
+// Report why some input stopped running, giving back the exit code if it called exit
+fn report_stop(stop: Stop, lines: &ProgramLines, output: &mut dyn Write) -> Option<i32> {
+    match stop {
+        Stop::Exit(code) => return Some(code),
+        Stop::Error(msg) => {
+            // The message is printed just like a compiled program would print it, other than where it's from
+            let msg = msg.strip_prefix("Error: ").unwrap_or(&msg);
+            let _ = write!(output, "Error: {}", lines.locate(msg));
+            return None;
+        }
+    }
+}
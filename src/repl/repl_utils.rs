@@ -0,0 +1,89 @@
+// -------------------------------------------------------------------------------------------------------------
+// This file contains the helpers the REPL uses to work out when it's been given a whole piece of input,
+// and to print the values of the expressions it's given
+// -------------------------------------------------------------------------------------------------------------
+
+use crate::interpreter::interpreter_data::Value;
+use crate::semantic::semantic_data::Type;
+use crate::semantic::semantic_utils::element_type;
+
+// Return true if some input still has an open parenthesis, bracket or brace that hasn't been closed yet,
+// in which case the REPL keeps reading lines until it has (ignoring anything in strings and comments)
+pub fn is_incomplete(input: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = input.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '"' => {
+                // A string ends at the next quote that isn't escaped (or at the end of its line, which is an error)
+                while let Some(char) = chars.next() {
+                    if char == '"' || char == '\n' {
+                        break;
+                    } else if char == '\\' {
+                        chars.next();
+                    }
+                }
+            }
+            '/' if chars.as_str().starts_with('/') => {
+                // A comment goes until the end of its line
+                for char in chars.by_ref() {
+                    if char == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return depth > 0;
+}
+
+// Print a value of the given type the way it would be written in soup, like "12", "true", "\"hi\\n\"" or "[1, 2]"
+// (where an array is written out element by element, which soup itself can't do), or nothing for a void value
+pub fn format_value(value: &Value, type_sig: &Type) -> Option<String> {
+    return match value {
+        Value::Int(value) if *type_sig == Type::Bool => Some((*value != 0).to_string()),
+        Value::Int(value) => Some(value.to_string()),
+        Value::String(bytes) => Some(format!("\"{}\"", escape_string(bytes))),
+        Value::Array(array) => {
+            let element_type = element_type(type_sig);
+            let elements: Vec<String> = array
+                .get_elements()
+                .iter()
+                .filter_map(|element| format_value(element, &element_type))
+                .collect();
+            Some(format!("[{}]", elements.join(", ")))
+        }
+        Value::Void => None,
+    };
+}
+
+// Write out the bytes of a string, escaping anything that would need escaping in a string literal
+fn escape_string(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+
+    for char in String::from_utf8_lossy(bytes).chars() {
+        match char {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            char => escaped.push(char),
+        }
+    }
+
+    return escaped;
+}
+
+// Get the line a message from the semantic checker is about, which it starts with (like "Line 3: ..."), if it's about one
+pub fn get_line_num(message: &str) -> Option<i32> {
+    return message
+        .strip_prefix("Line ")
+        .and_then(|message| message.split_once(':'))
+        .and_then(|(line_num, _)| line_num.parse().ok());
+}
@@ -0,0 +1,79 @@
+mod tests {
+    use crate::repl::repl_driver::ReplSession;
+    use crate::repl::repl_utils::is_incomplete;
+    use crate::semantic::semantic_data::Warnings;
+
+    // Run each piece of input in a session, giving back everything it printed
+    fn run(session: &mut ReplSession, inputs: &[&str]) -> String {
+        let mut output = vec![];
+        for input in inputs {
+            assert_eq!(None, session.evaluate(input, &mut output));
+        }
+        return String::from_utf8(output).unwrap();
+    }
+
+    #[test]
+    fn test_is_incomplete() {
+        assert!(is_incomplete("func f() returns void {\n"));
+        assert!(is_incomplete("printf(\"{}\",\n"));
+        assert!(!is_incomplete("func f() returns void {\n    x = 1;\n}\n"));
+
+        // Brackets in strings and comments don't count
+        assert!(!is_incomplete("printf(\"{ \\\" (\");\n"));
+        assert!(!is_incomplete("x = 1; // {\n"));
+    }
+
+    #[test]
+    fn test_repl_session() {
+        let mut session = ReplSession::new(false, Warnings::default());
+
+        // Variables declared at the top level are kept, along with functions, and the values of expressions are printed
+        let output = run(
+            &mut session,
+            &[
+                "int total = 4;",
+                "int grid[2][2];",
+                "func add(int n) returns int {\n    total = total + n;\n    return total;\n}",
+                "grid[1][0] = add(3);",
+                "printf(\"{} \", total);",
+                "grid",
+                "total > 5 && add(1) == 8",
+                "\"tab\\t\"",
+            ],
+        );
+        assert_eq!("7 [[0, 0], [7, 0]]\ntrue\n\"tab\\t\"\n", output);
+
+        // Errors are printed without ending the session, and a runtime error keeps whatever happened before it
+        // (but not anything declared before it)
+        let output = run(
+            &mut session,
+            &[
+                "int x = add(1);\nint y = x / (total - 9);",
+                "y",
+                "x",
+                "total",
+            ],
+        );
+        assert_eq!(
+            "Error: Line 2: Division by zero\nError: Line 1: Unknown identifier 'y'\nError: Line 1: Unknown identifier 'x'\n9\n",
+            output
+        );
+
+        // Errors in the session's own declarations are reported as being in them
+        let output = run(
+            &mut session,
+            &[
+                "func share(int n) returns int {\n    return 100 / add(n);\n}",
+                "func add(bool b) returns bool {\n    return b;\n}",
+                "share(-9)",
+            ],
+        );
+        assert_eq!(
+            "Error: In 'share': Argument 1 passed into function 'add' must be bool, not int\nError: In 'share': Division by zero\n",
+            output
+        );
+
+        // Calling exit gives back its exit code
+        assert_eq!(Some(3), session.evaluate("exit(3);", &mut vec![]));
+    }
+}
@@ -3,4 +3,5 @@ pub mod scanner_driver;
 pub mod scanner_utils;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests;
@@ -2,21 +2,120 @@
 // This file contains data structures used in scanning the compilee file for tokens, the first step of compiling
 // -------------------------------------------------------------------------------------------------------------
 
-// Struct to hold character data along with the line of the file the character is on
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+// The number of columns between tab stops, so a character after a tab is in the column it would be shown in
+// (with a tab at column 3, the next character is in column 9)
+pub const TAB_WIDTH: i32 = 8;
+
+// Struct to hold character data along with the line of the file the character is on,
+// and the column of that line it's in (both numbered from 1)
 #[derive(Debug, PartialEq)]
 pub struct Char {
     pub char_val: char,
     pub line_num: i32,
+    pub col_num: i32,
 }
 
 // Struct to hold information about a token, like its type, its lexeme, and the line of the file it is found on
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    pub lexeme: Arc<str>,
     pub line_num: i32,
 }
 
+impl Token {
+    // Format the data contained in this token
+    pub fn display_string(&self) -> String {
+        return format!(
+            "{{{:?}, lexeme: '{}', line {}}}",
+            self.token_type, self.lexeme, self.line_num
+        );
+    }
+
+    // Get the text of the source code this token was made from, where a string literal's lexeme is what's between
+    // its quotes, and the EOF token isn't made from any text at all
+    pub fn source_text(&self) -> String {
+        return match self.token_type {
+            TokenType::STRLIT => format!("\"{}\"", self.lexeme),
+            TokenType::EOF => String::new(),
+            _ => self.lexeme.to_string(),
+        };
+    }
+}
+
+// One piece of the text between two tokens, which the compiler skips over
+#[derive(Debug, PartialEq, Clone)]
+pub enum TriviaPiece {
+    // A run of spaces and tabs
+    Whitespace(String),
+    // The end of a line
+    Newline,
+    // A comment, from its "//" up to (but not including) the end of its line
+    Comment(String),
+}
+
+// The whitespace and comments in front of a token, which are only kept when scanning losslessly (see scan_str_lossless),
+// for tooling like the formatter that needs to give back the source the tokens came from
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Trivia {
+    pub pieces: Vec<TriviaPiece>,
+}
+
+impl Trivia {
+    // Write the trivia back out exactly as it was written (except that every line ending is written as "\n")
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+
+        for piece in &self.pieces {
+            match piece {
+                TriviaPiece::Whitespace(text) | TriviaPiece::Comment(text) => source.push_str(text),
+                TriviaPiece::Newline => source.push('\n'),
+            }
+        }
+
+        return source;
+    }
+}
+
+// An error found while scanning, which stops the scan, along with the line and column it was found at
+#[derive(Debug, PartialEq)]
+pub struct ScanError {
+    pub message: String,
+    pub line_num: i32,
+    pub col_num: i32,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+// The reserved words, and the type of token each one is scanned as (rather than as an identifier)
+pub const RESERVED: &[(&str, TokenType)] = &[
+    ("if", TokenType::IF),
+    ("loop", TokenType::LOOP),
+    ("until", TokenType::UNTIL),
+    ("repeat", TokenType::REPEAT),
+    ("int", TokenType::INT),
+    ("true", TokenType::TRUE),
+    ("bool", TokenType::BOOL),
+    ("void", TokenType::VOID),
+    ("else", TokenType::ELSE),
+    ("func", TokenType::FUNC),
+    ("main", TokenType::MAIN),
+    ("false", TokenType::FALSE),
+    ("while", TokenType::WHILE),
+    ("break", TokenType::BREAK),
+    ("return", TokenType::RETURN),
+    ("continue", TokenType::CONTINUE),
+    ("returns", TokenType::RETURNS),
+];
+
 // An enumeration to define Token types for easy comparison
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TokenType {
@@ -31,7 +130,11 @@ pub enum TokenType {
     IF,
     ELSE,
     WHILE,
+    LOOP,
+    REPEAT,
+    UNTIL,
     BREAK,
+    CONTINUE,
     RETURN,
     FUNC,
     RETURNS,
@@ -60,7 +163,56 @@ pub enum TokenType {
     CLOSEPAR,
     OPENBRACE,
     CLOSEBRACE,
+    OPENBRACKET,
+    CLOSEBRACKET,
     SEMICOLON,
+    COLON,
     COMMA,
     EOF,
 }
+
+// Hands out a single shared copy of each distinct lexeme in a file, so every token (and the AST nodes and symbols
+// made from them) with the same lexeme shares one string instead of allocating its own.
+// The copies are Arcs rather than Rcs since each file is scanned and parsed on its own thread
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+    // Reused to build up lexemes that are made out of many characters, like identifiers
+    buffer: String,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        return Interner {
+            strings: HashSet::new(),
+            buffer: String::new(),
+        };
+    }
+
+    // Get the shared copy of a string, making it the first time the string is seen
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return Arc::clone(interned);
+        }
+
+        let interned: Arc<str> = Arc::from(string);
+        self.strings.insert(Arc::clone(&interned));
+        return interned;
+    }
+
+    // Get the shared copy of the string made up of a run of characters
+    pub fn intern_chars(&mut self, chars: &[Char]) -> Arc<str> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend(chars.iter().map(|char| char.char_val));
+
+        let interned = self.intern(&buffer);
+        self.buffer = buffer;
+        return interned;
+    }
+}
@@ -2,7 +2,10 @@
 // This file contains the main logic involved in scanning the compilee for tokens, the first step of compiling
 // -----------------------------------------------------------------------------------------------------------
 
-use crate::scanner::scanner_data::{Char, Token, TokenType};
+use crate::passes::passes_trace::{log_event, LogLevel};
+use crate::scanner::scanner_data::{
+    Char, Interner, ScanError, Token, TokenType, Trivia, TriviaPiece,
+};
 use crate::scanner::scanner_utils::*;
 use crate::throw_error;
 
@@ -11,80 +14,156 @@ use crate::throw_error;
 // --------------------------------------------------------------------------------------
 
 // Main scanner function, returns the vector of tokens scanned from the compilee file
+// (exiting with an error if the file can't be scanned)
 pub fn scanner(code_file: &str) -> Vec<Token> {
-    // Get a vector of characters from the file
-    let chars = get_chars(code_file);
+    match scan_file(code_file) {
+        Ok(tokens) => return tokens,
+        Err(error) => {
+            throw_error(&error.to_string());
+            return vec![];
+        }
+    }
+}
+
+// Scan a compilee file for its tokens, giving back an error (rather than exiting) if it can't be scanned
+pub fn scan_file(code_file: &str) -> Result<Vec<Token>, ScanError> {
+    let tokens = scan_str(&read_source(code_file))?;
+    log_event(LogLevel::Debug, || {
+        format!("scanned {} tokens", tokens.len())
+    });
+    return Ok(tokens);
+}
+
+// Scan a piece of soup source code for its tokens, ending with an EOF token. This is the scanner's stable entry point:
+// it doesn't read any files or exit the process, so anything that needs to lex soup code (like a syntax highlighter,
+// or a test) can call it directly, and a source that can't be scanned gives back an error saying what's wrong and on which line
+pub fn scan_str(source: &str) -> Result<Vec<Token>, ScanError> {
+    return scan_chars(&get_chars(source), None);
+}
+
+// Scan a piece of soup source code like scan_str, but losslessly, giving back the trivia (whitespace and comments)
+// in front of each token along with the tokens themselves. The trivia line up with the tokens, so trivia[i] is
+// everything between tokens[i - 1] and tokens[i], and the trivia of the EOF token is everything after the last real token
+pub fn scan_str_lossless(source: &str) -> Result<(Vec<Token>, Vec<Trivia>), ScanError> {
+    let mut trivia = Vec::new();
+    let tokens = scan_chars(&get_chars(source), Some(&mut trivia))?;
 
-    // Create a vector to add tokens to
+    // The last line is given a newline to end it if it doesn't have one, which isn't a part of the source
+    if !source.ends_with(['\n', '\r']) {
+        if let Some(eof_trivia) = trivia.last_mut() {
+            if eof_trivia.pieces.last() == Some(&TriviaPiece::Newline) {
+                eof_trivia.pieces.pop();
+            }
+        }
+    }
+
+    return Ok((tokens, trivia));
+}
+
+// Put source code that was scanned losslessly back together from its tokens and the trivia in front of each of them,
+// which gives back exactly the source that was scanned (except that every line ending is written as "\n")
+pub fn reproduce_source(tokens: &[Token], trivia: &[Trivia]) -> String {
+    let mut source = String::new();
+
+    for (token, trivia) in tokens.iter().zip(trivia) {
+        source.push_str(&trivia.to_source());
+        source.push_str(&token.source_text());
+    }
+
+    return source;
+}
+
+// Scan the characters of a piece of soup source code for its tokens, ending with an EOF token,
+// and also collect the trivia in front of each token if a vector is given to put them in
+fn scan_chars(
+    chars: &Vec<Char>,
+    mut trivia: Option<&mut Vec<Trivia>>,
+) -> Result<Vec<Token>, ScanError> {
+    // Create a vector to add tokens to, and an interner for them to share their lexemes through
     let mut tokens = Vec::new();
+    let mut interner = Interner::new();
+
+    // The position of the first character after the last token, which is where the trivia in front of the next one starts
+    let mut trivia_start = 0;
 
     // Loop through the characters
     let mut i = 0;
     while i < chars.len() {
+        let start = i;
+
         // Try to get a token, and push it to the list if you get one
-        match get_token(&chars, &mut i) {
+        match get_token(chars, &mut i, &mut interner)? {
             None => {}
-            Some(token) => tokens.push(token),
+            Some(token) => {
+                if let Some(trivia) = &mut trivia {
+                    trivia.push(get_trivia(&chars[trivia_start..start]));
+                }
+                trivia_start = i;
+
+                tokens.push(token);
+            }
         }
     }
 
-    // Once we've gone through the whole file, add an EOF token at the end
+    // Once we've gone through the whole source, add an EOF token at the end
+    // (on the last line of the source, or the first if it's empty)
+    if let Some(trivia) = &mut trivia {
+        trivia.push(get_trivia(&chars[trivia_start..]));
+    }
     tokens.push(Token {
         token_type: TokenType::EOF,
-        lexeme: String::from("EOF"),
-        line_num: chars[i - 1].line_num,
+        lexeme: interner.intern("EOF"),
+        line_num: chars.last().map_or(1, |char| char.line_num),
     });
 
     // Return vector of tokens
-    tokens
+    return Ok(tokens);
 }
 
 // --------------------------------------------------------------------------------------
 // GET TOKEN
 // --------------------------------------------------------------------------------------
 
-// Tries to get and return one token from the file, starting from the character at index i
-fn get_token(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
+// Tries to get and return one token from the source, starting from the character at index i
+// (giving back None for anything that isn't a token, like whitespace or a comment)
+fn get_token(
+    chars: &Vec<Char>,
+    i: &mut usize,
+    interner: &mut Interner,
+) -> Result<Option<Token>, ScanError> {
     match chars[*i].char_val {
         ' ' | '\t' | '\n' | '\r' => {
             // Ignore whitespace
             *i += 1;
-            return None;
+            return Ok(None);
         }
-        '(' | ')' | '{' | '}' | ';' | ',' => {
-            return get_separators(chars, i);
+        '(' | ')' | '{' | '}' | '[' | ']' | ';' | ':' | ',' => {
+            return Ok(get_separators(chars, i, interner));
         }
         '+' | '-' | '*' | '/' | '%' | '<' | '>' | '=' | '!' => {
-            return get_binary_ops(chars, i);
+            return Ok(get_binary_ops(chars, i, interner));
         }
         '&' => {
-            return get_and_or(chars, i, TokenType::AND, "&&");
+            return get_and_or(chars, i, interner, TokenType::AND, "&&").map(Some);
         }
         '|' => {
-            return get_and_or(chars, i, TokenType::OR, "||");
+            return get_and_or(chars, i, interner, TokenType::OR, "||").map(Some);
         }
         'A'..='Z' | 'a'..='z' | '_' => {
-            // Possible identifier, but we have to check for reserved words first
-            match get_reserved_words(chars, i) {
-                // If we find a reserved word, return the corresponding token
-                Some(reserved) => return Some(reserved),
-
-                // Otherwise, we have an identifier
-                None => return Some(get_identifier(chars, i)),
-            }
+            // We have an identifier or a reserved word
+            return Ok(Some(get_identifier(chars, i, interner)));
         }
         '0'..='9' => {
             // We have an integer literal
-            return Some(get_int_lits(chars, i));
+            return Ok(Some(get_int_lits(chars, i, interner)));
         }
         '"' => {
             // We have a string literal
-            return Some(get_str_lits(chars, i));
+            return get_str_lits(chars, i, interner).map(Some);
         }
-        unrecognized => {
-            // If we haven't matched any tokens, throw an error
-            throw_error(&format!("Unrecognized token '{}'", unrecognized));
-            return None;
+        _ => {
+            // If we haven't matched any tokens, this isn't valid soup
+            return Err(get_unrecognized_error(&chars[*i]));
         }
     }
 }
@@ -2,18 +2,20 @@
 // This file contains helper functions to assist in scanning the compilee for tokens, the first step of compiling
 // --------------------------------------------------------------------------------------------------------------
 
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::LazyLock;
 
-use crate::scanner::scanner_data::{Char, Token, TokenType};
-use crate::throw_error;
+use crate::passes::passes_trace::{log_event, LogLevel};
+use crate::scanner::scanner_data::{
+    Char, Interner, ScanError, Token, TokenType, Trivia, TriviaPiece, RESERVED, TAB_WIDTH,
+};
 
 // --------------------------------------------------------------------------------------
 // SCANNING - SEPARATORS
 // --------------------------------------------------------------------------------------
 
-pub fn get_separators(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
+pub fn get_separators(chars: &Vec<Char>, i: &mut usize, interner: &mut Interner) -> Option<Token> {
     // We know that we've matched a separator, so we already know we can increment to the next character
     *i += 1;
 
@@ -24,42 +26,63 @@ pub fn get_separators(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
         '(' => {
             return Some(Token {
                 token_type: TokenType::OPENPAR,
-                lexeme: String::from("("),
+                lexeme: interner.intern("("),
                 line_num: line_num,
             });
         }
         ')' => {
             return Some(Token {
                 token_type: TokenType::CLOSEPAR,
-                lexeme: String::from(")"),
+                lexeme: interner.intern(")"),
                 line_num: line_num,
             });
         }
         '{' => {
             return Some(Token {
                 token_type: TokenType::OPENBRACE,
-                lexeme: String::from("{"),
+                lexeme: interner.intern("{"),
                 line_num: line_num,
             });
         }
         '}' => {
             return Some(Token {
                 token_type: TokenType::CLOSEBRACE,
-                lexeme: String::from("}"),
+                lexeme: interner.intern("}"),
+                line_num: line_num,
+            });
+        }
+        '[' => {
+            return Some(Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: interner.intern("["),
+                line_num: line_num,
+            });
+        }
+        ']' => {
+            return Some(Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: interner.intern("]"),
                 line_num: line_num,
             });
         }
         ';' => {
             return Some(Token {
                 token_type: TokenType::SEMICOLON,
-                lexeme: String::from(";"),
+                lexeme: interner.intern(";"),
+                line_num: line_num,
+            });
+        }
+        ':' => {
+            return Some(Token {
+                token_type: TokenType::COLON,
+                lexeme: interner.intern(":"),
                 line_num: line_num,
             });
         }
         ',' => {
             return Some(Token {
                 token_type: TokenType::COMMA,
-                lexeme: String::from(","),
+                lexeme: interner.intern(","),
                 line_num: line_num,
             });
         }
@@ -74,34 +97,41 @@ pub fn get_separators(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
 
 // Given a character in the character list, knowing it matches one of the 9 single character binary operators,
 // return the correct corresponding token (or nothing in the case of a comment, which may look like a division token at first!)
-pub fn get_binary_ops(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
+pub fn get_binary_ops(chars: &Vec<Char>, i: &mut usize, interner: &mut Interner) -> Option<Token> {
     match chars[*i].char_val {
         '+' => {
-            return get_binary_op(chars, i, TokenType::PLUS, TokenType::PLUSEQ, "+");
+            return get_binary_op(chars, i, interner, TokenType::PLUS, TokenType::PLUSEQ, "+");
         }
         '-' => {
-            return get_binary_op(chars, i, TokenType::MINUS, TokenType::MINUSEQ, "-");
+            return get_binary_op(
+                chars,
+                i,
+                interner,
+                TokenType::MINUS,
+                TokenType::MINUSEQ,
+                "-",
+            );
         }
         '*' => {
-            return get_binary_op(chars, i, TokenType::MULT, TokenType::MULTEQ, "*");
+            return get_binary_op(chars, i, interner, TokenType::MULT, TokenType::MULTEQ, "*");
         }
         '/' => {
-            return get_binary_op(chars, i, TokenType::DIV, TokenType::DIVEQ, "/");
+            return get_binary_op(chars, i, interner, TokenType::DIV, TokenType::DIVEQ, "/");
         }
         '%' => {
-            return get_binary_op(chars, i, TokenType::MOD, TokenType::MODEQ, "%");
+            return get_binary_op(chars, i, interner, TokenType::MOD, TokenType::MODEQ, "%");
         }
         '<' => {
-            return get_binary_op(chars, i, TokenType::LT, TokenType::LEQ, "<");
+            return get_binary_op(chars, i, interner, TokenType::LT, TokenType::LEQ, "<");
         }
         '>' => {
-            return get_binary_op(chars, i, TokenType::GT, TokenType::GEQ, ">");
+            return get_binary_op(chars, i, interner, TokenType::GT, TokenType::GEQ, ">");
         }
         '=' => {
-            return get_binary_op(chars, i, TokenType::ASSIGN, TokenType::EQ, "=");
+            return get_binary_op(chars, i, interner, TokenType::ASSIGN, TokenType::EQ, "=");
         }
         '!' => {
-            return get_binary_op(chars, i, TokenType::NOT, TokenType::NEQ, "!");
+            return get_binary_op(chars, i, interner, TokenType::NOT, TokenType::NEQ, "!");
         }
         // Will never happen since we already matched one of the above operators
         _ => {
@@ -116,6 +146,7 @@ pub fn get_binary_ops(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
 pub fn get_binary_op(
     chars: &Vec<Char>,
     i: &mut usize,
+    interner: &mut Interner,
     op_type: TokenType,
     alt_type: TokenType,
     op_lexeme: &str,
@@ -123,26 +154,27 @@ pub fn get_binary_op(
     // Initialize a binary operator token of the requested type
     let mut token = Token {
         token_type: op_type,
-        lexeme: String::from(op_lexeme),
+        lexeme: interner.intern(op_lexeme),
         line_num: chars[*i].line_num,
     };
 
     // Check to see if token is 'op=', not just 'op' (for example, '+=' or '<=' instead of just '+' or '<')
-    if chars[*i + 1].char_val == '=' {
+    if peek_char(chars, *i + 1) == '=' {
         // Update token information
         token.token_type = alt_type;
-        token.lexeme.push('=');
+        token.lexeme = interner.intern_chars(&chars[*i..*i + 2]);
 
         // Skip the next char, since it is a part of our current token
         *i += 2;
 
         return Some(token);
-    } else if op_type == TokenType::DIV && chars[*i + 1].char_val == '/' {
-        // We have a comment, loop until we find a newline character
-        let mut comment_char = chars[*i].char_val;
-        while comment_char != '\n' {
+    } else if op_type == TokenType::DIV && peek_char(chars, *i + 1) == '/' {
+        // We have a comment, loop until we find a newline character (or the end of the file)
+        log_event(LogLevel::Trace, || {
+            format!("skipping a comment on line {}", chars[*i].line_num)
+        });
+        while *i < chars.len() && chars[*i].char_val != '\n' {
             *i += 1;
-            comment_char = chars[*i].char_val;
         }
 
         return None;
@@ -163,201 +195,88 @@ pub fn get_binary_op(
 
 // Given a character in the character list, knowing it is either '&' or '|', make sure
 // the next character is also '&' or '|' respectively and return the corresponding token,
-// or throw an error otherwise
+// or an error otherwise
 pub fn get_and_or(
     chars: &Vec<Char>,
     i: &mut usize,
+    interner: &mut Interner,
     op_type: TokenType,
     op_lexeme: &str,
-) -> Option<Token> {
+) -> Result<Token, ScanError> {
     // Check to see if the token is '&&' or '||' as it should be, depending on what the first character is
-    if (op_type == TokenType::AND && chars[*i + 1].char_val == '&')
-        || (op_type == TokenType::OR && chars[*i + 1].char_val == '|')
+    if (op_type == TokenType::AND && peek_char(chars, *i + 1) == '&')
+        || (op_type == TokenType::OR && peek_char(chars, *i + 1) == '|')
     {
         // Skip the next char, since it is a part of our current token
         *i += 2;
 
         // Return the corresponding token
-        return Some(Token {
+        return Ok(Token {
             token_type: op_type,
-            lexeme: String::from(op_lexeme),
+            lexeme: interner.intern(op_lexeme),
             line_num: chars[*i - 2].line_num,
         });
     } else {
         // Otherwise, this is an invalid token
-        throw_error(&format!("Unrecognized token '{}'", chars[*i].char_val));
-        return None;
+        let error = get_unrecognized_error(&chars[*i]);
+        return Err(ScanError {
+            message: format!("{} (did you mean '{}'?)", error.message, op_lexeme),
+            ..error
+        });
     }
 }
 
 // --------------------------------------------------------------------------------------
-// SCANNING - RESERVED WORDS
+// SCANNING - IDENTIFIERS AND RESERVED WORDS
 // --------------------------------------------------------------------------------------
 
-// Given a character in the character list, knowing it is an ID character,
-// loop through all of the possible reserved words and check if
-// the given character is the start of any of them
-pub fn get_reserved_words(chars: &Vec<Char>, i: &mut usize) -> Option<Token> {
-    let reserved_types = vec![
-        TokenType::IF,
-        TokenType::INT,
-        TokenType::TRUE,
-        TokenType::BOOL,
-        TokenType::VOID,
-        TokenType::ELSE,
-        TokenType::FUNC,
-        TokenType::MAIN,
-        TokenType::FALSE,
-        TokenType::WHILE,
-        TokenType::BREAK,
-        TokenType::RETURN,
-        TokenType::RETURNS,
-    ];
-
-    let reserved_lexemes = vec![
-        "if", "int", "true", "bool", "void", "else", "func", "main", "false", "while", "break",
-        "return", "returns",
-    ];
-
-    // Loop through the reserved words and try to match each
-    // If one matches, return the corresponding token
-    for j in 0..reserved_types.len() {
-        match get_reserved_word(chars, i, reserved_types[j], reserved_lexemes[j]) {
-            None => {}
-            Some(token) => {
-                return Some(token);
-            }
-        }
-    }
-
-    // If we've looped through all of the reserved words and haven't found a match,
-    // this token is not the start of a reserved word and we can return None
-    return None;
-}
-
-// Given a character in the character list and a particular reserved word,
-// check if the given character is the start of that reserved word
-pub fn get_reserved_word(
-    chars: &Vec<Char>,
-    i: &mut usize,
-    reserved_type: TokenType,
-    reserved: &str,
-) -> Option<Token> {
-    // Check if there are enough characters in the list to check for the reserved word
-    if has_enough_chars(chars, i, reserved.len()) {
-        // Form a list of chars so we can turn it into a string and check if it equals the given reserved word
-        let mut char_vec = Vec::new();
-
-        for j in 0..reserved.len() + 1 {
-            char_vec.push(chars[*i + j].char_val);
-        }
+// The reserved words, looked up by their lexeme
+static RESERVED_WORDS: LazyLock<HashMap<&str, TokenType>> =
+    LazyLock::new(|| RESERVED.iter().copied().collect());
 
-        if is_reserved(char_vec, reserved) {
-            // Skip the chars comprising the reserved word, since they're a part of our current token
-            *i += reserved.len();
-
-            // Return a token corresponding to the reserved word
-            return Some(Token {
-                token_type: reserved_type,
-                lexeme: String::from(reserved),
-                line_num: chars[*i - reserved.len()].line_num,
-            });
-        }
-
-        // If there are enough chars but the chars do not match the reserved word, return None
-        return None;
-    }
-
-    // If there aren't enough chars to match the reserved word, return None
-    return None;
-}
-
-// Returns true if a vector of characters equals a reserved word + a non-id character, and false otherwise
-pub fn is_reserved(actual: Vec<char>, reserved: &str) -> bool {
-    let actual_str: String = actual[0..actual.len() - 1].iter().collect();
-    let next_char = actual[actual.len() - 1];
-
-    // If the string equals the reserved word and the next character is not alphanumeric or an underscore, this is a reserved word
-    actual_str == reserved && !is_id_char(next_char)
-}
-
-// Checks to see if the vector of chars is long enough to check for a reserved word of length num_chars
-pub fn has_enough_chars(chars: &Vec<Char>, i: &usize, num_chars: usize) -> bool {
-    // For example, if we want to check if a character at index 8 of a length 10 character vector is the
-    // first letter of the reserved word "while", we would get an index out of bounds error when checking
-    // if the next 6 characters are 'w', 'h', 'i', 'l', 'e', and not an ID char because there aren't that many characters left!
-    (chars.len() as i32) - (*i as i32) - 1 >= (num_chars as i32)
-}
-
-// --------------------------------------------------------------------------------------
-// SCANNING - IDENTIFIERS
-// --------------------------------------------------------------------------------------
-
-pub fn get_identifier(chars: &Vec<Char>, i: &mut usize) -> Token {
-    let mut id_char = chars[*i].char_val;
+// Given a character in the character list, knowing it is an ID character, scan the whole identifier
+// and return a token for it, which is the corresponding reserved word's token if it's one of them
+pub fn get_identifier(chars: &Vec<Char>, i: &mut usize, interner: &mut Interner) -> Token {
+    let start = *i;
     let line_num = chars[*i].line_num;
-    let mut id_vec = Vec::new();
-
-    // Loop until we've found a non-id character
-    while is_id_char(id_char) {
-        // Add the id character to the vector
-        id_vec.push(id_char);
 
-        // Move to the next character and jump to the loop test again
+    // Loop until we've found a non-id character, which is where the identifier ends
+    while is_id_char(peek_char(chars, *i)) {
         *i += 1;
-        id_char = chars[*i].char_val;
     }
 
-    // We incremented one character too far because we had to find a non-id character to exit the loop
-    *i -= 1;
-
-    // Now that we've found the end of the identifier, turn the slice into a string
-    let id_lexeme: String = id_vec[0..id_vec.len()].iter().collect();
-
-    // Prepare to move along to the next char
-    *i += 1;
-
-    // Return an 'identifier' token, with the newly created lexeme
+    // Return a token with the lexeme made up of the characters we skipped over
+    let lexeme = interner.intern_chars(&chars[start..*i]);
     return Token {
-        token_type: TokenType::ID,
-        lexeme: id_lexeme,
+        token_type: get_reserved_word(&lexeme).unwrap_or(TokenType::ID),
+        lexeme: lexeme,
         line_num: line_num,
     };
 }
 
+// Get the type of token a reserved word is scanned as, or None if the lexeme isn't a reserved word
+pub fn get_reserved_word(lexeme: &str) -> Option<TokenType> {
+    return RESERVED_WORDS.get(lexeme).copied();
+}
+
 // --------------------------------------------------------------------------------------
 // SCANNING - INTEGER LITERALS
 // --------------------------------------------------------------------------------------
 
-pub fn get_int_lits(chars: &Vec<Char>, i: &mut usize) -> Token {
+pub fn get_int_lits(chars: &Vec<Char>, i: &mut usize, interner: &mut Interner) -> Token {
     // We have to check for multiple digit literals
-    let mut int_lit_char = chars[*i].char_val;
-    let mut int_lit_vec = Vec::new();
+    let start = *i;
 
-    // Loop until we've found a non-digit character
-    while is_digit(int_lit_char) {
-        // Add digit character to the vector
-        int_lit_vec.push(int_lit_char);
-
-        // Move to the next character and jump to the loop test again
+    // Loop until we've found a non-digit character, which is where the literal ends
+    while is_digit(peek_char(chars, *i)) {
         *i += 1;
-        int_lit_char = chars[*i].char_val;
     }
 
-    // We incremented one character too far because we had to find a non-digit character to exit the loop
-    *i -= 1;
-
-    // Now that we've found the end of the integer literal, turn the slice into a string
-    let int_lit_lexeme: String = int_lit_vec[0..int_lit_vec.len()].iter().collect();
-
-    // Prepare to move along to the next char
-    *i += 1;
-
-    // Return an 'integer literal' token, with the newly created lexeme
+    // Return an 'integer literal' token, with the lexeme made up of the digits we skipped over
     return Token {
         token_type: TokenType::INTLIT,
-        lexeme: int_lit_lexeme,
-        line_num: chars[*i].line_num,
+        lexeme: interner.intern_chars(&chars[start..*i]),
+        line_num: chars[*i - 1].line_num,
     };
 }
 
@@ -365,31 +284,103 @@ pub fn get_int_lits(chars: &Vec<Char>, i: &mut usize) -> Token {
 // SCANNING - STRING LITERALS
 // --------------------------------------------------------------------------------------
 
-pub fn get_str_lits(chars: &Vec<Char>, i: &mut usize) -> Token {
-    let mut string_vec = Vec::new();
+pub fn get_str_lits(
+    chars: &Vec<Char>,
+    i: &mut usize,
+    interner: &mut Interner,
+) -> Result<Token, ScanError> {
+    let start_line_num = chars[*i].line_num;
+    let start_col_num = chars[*i].col_num;
+
     // Skip the open quote
     *i += 1;
+    let start = *i;
 
-    // Loop until we find another quotation mark
-    let mut string_char = chars[*i].char_val;
-    while string_char != '"' {
-        string_vec.push(string_char);
-
+    // Loop until we find another quotation mark, unless we run out of characters first
+    while *i < chars.len() && chars[*i].char_val != '"' {
         *i += 1;
-        string_char = chars[*i].char_val;
+    }
+    if *i == chars.len() {
+        return Err(ScanError {
+            message: format!(
+                "Unterminated string literal starting on line {}, column {}",
+                start_line_num, start_col_num
+            ),
+            line_num: start_line_num,
+            col_num: start_col_num,
+        });
     }
 
-    // Now that we've found the end of the string literal, turn the slice into a string
-    let string_lexeme: String = string_vec.iter().collect();
+    // Now that we've found the end of the string literal, get the lexeme between the quotes
+    let string_lexeme = interner.intern_chars(&chars[start..*i]);
 
     // Prepare to move along to the next char
     *i += 1;
 
     // Return a 'string literal' token, with the newly created lexeme
-    return Token {
+    return Ok(Token {
         token_type: TokenType::STRLIT,
         lexeme: string_lexeme,
-        line_num: chars[*i].line_num,
+        line_num: chars[*i - 1].line_num,
+    });
+}
+
+// --------------------------------------------------------------------------------------
+// SCANNING - TRIVIA
+// --------------------------------------------------------------------------------------
+
+// Split the characters between two tokens (which are only ever whitespace and comments) into pieces of trivia
+pub fn get_trivia(chars: &[Char]) -> Trivia {
+    let mut pieces = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+
+        match chars[i].char_val {
+            '\n' => {
+                pieces.push(TriviaPiece::Newline);
+                i += 1;
+            }
+            '/' => {
+                // A comment goes on until the end of its line (or the end of the file)
+                while i < chars.len() && chars[i].char_val != '\n' {
+                    i += 1;
+                }
+                pieces.push(TriviaPiece::Comment(get_text(&chars[start..i])));
+            }
+            _ => {
+                while i < chars.len() && chars[i].char_val != '\n' && chars[i].char_val != '/' {
+                    i += 1;
+                }
+                pieces.push(TriviaPiece::Whitespace(get_text(&chars[start..i])));
+            }
+        }
+    }
+
+    return Trivia { pieces: pieces };
+}
+
+// Get the text made up of a run of characters
+fn get_text(chars: &[Char]) -> String {
+    return chars.iter().map(|char| char.char_val).collect();
+}
+
+// --------------------------------------------------------------------------------------
+// SCANNING - ERRORS
+// --------------------------------------------------------------------------------------
+
+// Get the error for a character that can't start (or continue) any token, saying exactly where it is
+pub fn get_unrecognized_error(unrecognized: &Char) -> ScanError {
+    return ScanError {
+        message: format!(
+            "Unrecognized character '{}' on line {}, column {}",
+            unrecognized.char_val.escape_default(),
+            unrecognized.line_num,
+            unrecognized.col_num
+        ),
+        line_num: unrecognized.line_num,
+        col_num: unrecognized.col_num,
     };
 }
 
@@ -397,62 +388,85 @@ pub fn get_str_lits(chars: &Vec<Char>, i: &mut usize) -> Token {
 // HELPERS - CHARACTER TYPE CHECKING
 // --------------------------------------------------------------------------------------
 
+// Get the character at the given position, or a newline if the position is past the end of the characters
+// (since every line, including the last, ends in one), so that looking ahead never runs off the end
+pub fn peek_char(chars: &[Char], pos: usize) -> char {
+    return chars.get(pos).map_or('\n', |char| char.char_val);
+}
+
 // Returns true if a character is in a..z, A..Z, 0..9, or is an underscore, and false otherwise
 pub fn is_id_char(id_char: char) -> bool {
-    (id_char >= 'a' && id_char <= 'z')
-        || (id_char >= 'A' && id_char <= 'Z')
+    id_char.is_ascii_lowercase()
+        || id_char.is_ascii_uppercase()
         || is_digit(id_char)
         || (id_char == '_')
 }
 
 // Returns true if a character is in 0..9
 pub fn is_digit(digit_char: char) -> bool {
-    digit_char >= '0' && digit_char <= '9'
+    digit_char.is_ascii_digit()
 }
 
 // --------------------------------------------------------------------------------------
-// HELPERS - FILE READING
+// HELPERS - SOURCE READING
 // --------------------------------------------------------------------------------------
 
-// Loops through a file and returns a vector containing each of its characters
-pub fn get_chars(file: &str) -> Vec<Char> {
+// Reads the source code in a file (which is empty if the file can't be read)
+pub fn read_source(file: &str) -> String {
+    return match fs::read(file) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => String::new(),
+    };
+}
+
+// Loops through some source code and returns a vector containing each of its characters
+pub fn get_chars(source: &str) -> Vec<Char> {
     // Initialize an empty vector to hold characters
     let mut char_vec = Vec::new();
 
-    if let Ok(lines) = read_lines(file) {
-        // Loop through the lines of the file, storing each line as a string
-        let mut line_num = 0;
-        for line in lines {
-            if let Ok(line_str) = line {
-                line_num += 1;
-                // Loop through each character in the line
-                for ch in line_str.chars() {
-                    // Add the character to the vector
-                    char_vec.push(Char {
-                        char_val: ch,
-                        line_num: line_num,
-                    });
-                }
-
-                // Make sure a newline character is included in the vector at the end of each line
-                char_vec.push(Char {
-                    char_val: '\n',
-                    line_num: line_num,
-                });
+    // Lines and columns are both numbered from 1
+    let mut line_num = 1;
+    let mut col_num = 1;
+
+    let mut source_chars = source.chars().peekable();
+    while let Some(mut ch) = source_chars.next() {
+        // A carriage return ends a line, either on its own or as the first half of a Windows line ending ("\r\n"),
+        // so that every line ending is scanned as a single '\n'
+        if ch == '\r' {
+            if source_chars.peek() == Some(&'\n') {
+                source_chars.next();
             }
+            ch = '\n';
         }
+
+        // Add the character to the vector
+        char_vec.push(Char {
+            char_val: ch,
+            line_num: line_num,
+            col_num: col_num,
+        });
+
+        // Move along to the column of the next character, which is on the next line after a newline,
+        // and at the next tab stop after a tab
+        if ch == '\n' {
+            line_num += 1;
+            col_num = 1;
+        } else if ch == '\t' {
+            col_num += TAB_WIDTH - (col_num - 1) % TAB_WIDTH;
+        } else {
+            col_num += 1;
+        }
+    }
+
+    // Make sure the last line ends in a newline character too, like every other line
+    if char_vec.last().is_some_and(|last| last.char_val != '\n') {
+        char_vec.push(Char {
+            char_val: '\n',
+            line_num: line_num,
+            col_num: col_num,
+        });
     }
 
     // Return the vector
     char_vec
 }
-
-// Returns an Iterator to the Reader of the lines of the file.
-pub fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    // Wrap the output in a Result to allow for error checking
-    Ok(io::BufReader::new(file).lines())
-}
@@ -1,6 +1,9 @@
 mod tests {
+    use std::sync::Arc;
+
     use crate::scanner::{
-        scanner_data::{Char, Token, TokenType},
+        scanner_data::{Char, Interner, ScanError, Token, TokenType, Trivia, TriviaPiece},
+        scanner_driver::{reproduce_source, scan_str, scan_str_lossless},
         scanner_utils::*,
     };
 
@@ -9,51 +12,86 @@ mod tests {
         let open_p = vec![Char {
             char_val: '(',
             line_num: 1,
+            col_num: 1,
         }];
         let open_b = vec![Char {
             char_val: '{',
             line_num: 1,
+            col_num: 1,
         }];
         let semi = vec![Char {
             char_val: ';',
             line_num: 1,
+            col_num: 1,
         }];
         let comma = vec![Char {
             char_val: ',',
             line_num: 1,
+            col_num: 1,
         }];
 
         assert_eq!(
             Some(Token {
                 token_type: TokenType::OPENPAR,
-                lexeme: String::from("("),
+                lexeme: Arc::from("("),
                 line_num: 1
             }),
-            get_separators(&open_p, &mut 0)
+            get_separators(&open_p, &mut 0, &mut Interner::new())
         );
         assert_eq!(
             Some(Token {
                 token_type: TokenType::OPENBRACE,
-                lexeme: String::from("{"),
+                lexeme: Arc::from("{"),
                 line_num: 1
             }),
-            get_separators(&open_b, &mut 0)
+            get_separators(&open_b, &mut 0, &mut Interner::new())
         );
         assert_eq!(
             Some(Token {
                 token_type: TokenType::SEMICOLON,
-                lexeme: String::from(";"),
+                lexeme: Arc::from(";"),
                 line_num: 1
             }),
-            get_separators(&semi, &mut 0)
+            get_separators(&semi, &mut 0, &mut Interner::new())
         );
         assert_eq!(
             Some(Token {
                 token_type: TokenType::COMMA,
-                lexeme: String::from(","),
+                lexeme: Arc::from(","),
+                line_num: 1
+            }),
+            get_separators(&comma, &mut 0, &mut Interner::new())
+        );
+    }
+
+    #[test]
+    fn test_get_separators_brackets() {
+        let open_b = vec![Char {
+            char_val: '[',
+            line_num: 1,
+            col_num: 1,
+        }];
+        let close_b = vec![Char {
+            char_val: ']',
+            line_num: 2,
+            col_num: 1,
+        }];
+
+        assert_eq!(
+            Some(Token {
+                token_type: TokenType::OPENBRACKET,
+                lexeme: Arc::from("["),
                 line_num: 1
             }),
-            get_separators(&comma, &mut 0)
+            get_separators(&open_b, &mut 0, &mut Interner::new())
+        );
+        assert_eq!(
+            Some(Token {
+                token_type: TokenType::CLOSEBRACKET,
+                lexeme: Arc::from("]"),
+                line_num: 2
+            }),
+            get_separators(&close_b, &mut 0, &mut Interner::new())
         );
     }
 
@@ -63,10 +101,12 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 2,
             },
         ];
 
@@ -74,14 +114,17 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '=',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 3,
             },
         ];
 
@@ -89,36 +132,56 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 3,
             },
             Char {
                 char_val: '\n',
                 line_num: 1,
+                col_num: 4,
             },
         ];
 
         let expected_div = Token {
             token_type: TokenType::DIV,
-            lexeme: String::from("/"),
+            lexeme: Arc::from("/"),
             line_num: 1,
         };
 
         let expected_diveq = Token {
             token_type: TokenType::DIVEQ,
-            lexeme: String::from("/="),
+            lexeme: Arc::from("/="),
             line_num: 1,
         };
 
-        assert_eq!(Some(expected_div), get_binary_ops(&div, &mut 0));
-        assert_eq!(Some(expected_diveq), get_binary_ops(&div_eq, &mut 0));
-        assert_eq!(None, get_binary_ops(&comment, &mut 0));
+        assert_eq!(
+            Some(expected_div),
+            get_binary_ops(&div, &mut 0, &mut Interner::new())
+        );
+        assert_eq!(
+            Some(expected_diveq),
+            get_binary_ops(&div_eq, &mut 0, &mut Interner::new())
+        );
+        assert_eq!(None, get_binary_ops(&comment, &mut 0, &mut Interner::new()));
+
+        // A comment can run right up to the end of the file (without the newline at the end of its line)
+        let mut comment_at_eof = get_chars("// ");
+        comment_at_eof.pop();
+        let mut index = 0;
+        assert_eq!(
+            None,
+            get_binary_ops(&comment_at_eof, &mut index, &mut Interner::new())
+        );
+        assert_eq!(3, index);
     }
 
     #[test]
@@ -127,10 +190,12 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 2,
             },
         ];
 
@@ -138,14 +203,17 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '=',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 3,
             },
         ];
 
@@ -153,44 +221,69 @@ mod tests {
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '/',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 3,
             },
             Char {
                 char_val: '\n',
                 line_num: 1,
+                col_num: 4,
             },
         ];
 
         let expected_div = Token {
             token_type: TokenType::DIV,
-            lexeme: String::from("/"),
+            lexeme: Arc::from("/"),
             line_num: 1,
         };
 
         let expected_diveq = Token {
             token_type: TokenType::DIVEQ,
-            lexeme: String::from("/="),
+            lexeme: Arc::from("/="),
             line_num: 1,
         };
 
         assert_eq!(
             Some(expected_div),
-            get_binary_op(&div, &mut 0, TokenType::DIV, TokenType::DIVEQ, "/")
+            get_binary_op(
+                &div,
+                &mut 0,
+                &mut Interner::new(),
+                TokenType::DIV,
+                TokenType::DIVEQ,
+                "/"
+            )
         );
         assert_eq!(
             Some(expected_diveq),
-            get_binary_op(&div_eq, &mut 0, TokenType::DIV, TokenType::DIVEQ, "/")
+            get_binary_op(
+                &div_eq,
+                &mut 0,
+                &mut Interner::new(),
+                TokenType::DIV,
+                TokenType::DIVEQ,
+                "/"
+            )
         );
         assert_eq!(
             None,
-            get_binary_op(&comment, &mut 0, TokenType::DIV, TokenType::DIVEQ, "/")
+            get_binary_op(
+                &comment,
+                &mut 0,
+                &mut Interner::new(),
+                TokenType::DIV,
+                TokenType::DIVEQ,
+                "/"
+            )
         );
     }
 
@@ -200,10 +293,12 @@ mod tests {
             Char {
                 char_val: '&',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '&',
                 line_num: 1,
+                col_num: 2,
             },
         ];
 
@@ -211,162 +306,83 @@ mod tests {
             Char {
                 char_val: '|',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '|',
                 line_num: 1,
+                col_num: 2,
             },
         ];
 
         let expected_and = Token {
             token_type: TokenType::AND,
-            lexeme: String::from("&&"),
+            lexeme: Arc::from("&&"),
             line_num: 1,
         };
 
         let expected_or = Token {
             token_type: TokenType::OR,
-            lexeme: String::from("||"),
+            lexeme: Arc::from("||"),
             line_num: 1,
         };
 
         assert_eq!(
-            Some(expected_and),
-            get_and_or(&and, &mut 0, TokenType::AND, "&&")
+            Ok(expected_and),
+            get_and_or(&and, &mut 0, &mut Interner::new(), TokenType::AND, "&&")
         );
         assert_eq!(
-            Some(expected_or),
-            get_and_or(&or, &mut 0, TokenType::OR, "||")
+            Ok(expected_or),
+            get_and_or(&or, &mut 0, &mut Interner::new(), TokenType::OR, "||")
         );
     }
 
     #[test]
     fn test_get_reserved_words() {
-        let reserved = vec![
-            Char {
-                char_val: 'i',
-                line_num: 1,
-            },
-            Char {
-                char_val: 'n',
-                line_num: 1,
-            },
-            Char {
-                char_val: 't',
-                line_num: 1,
-            },
-            Char {
-                char_val: ' ',
-                line_num: 1,
-            },
-        ];
+        let chars = get_chars("int if_ while");
+        let mut interner = Interner::new();
 
-        let not_reserved = vec![
-            Char {
-                char_val: 'n',
-                line_num: 1,
-            },
-            Char {
-                char_val: 'o',
-                line_num: 1,
-            },
-            Char {
-                char_val: 't',
-                line_num: 1,
-            },
-        ];
-
-        let expected_token = Token {
+        let expected_int = Token {
             token_type: TokenType::INT,
-            lexeme: String::from("int"),
+            lexeme: Arc::from("int"),
             line_num: 1,
         };
 
-        let mut index = 0;
-        assert_eq!(
-            Some(expected_token),
-            get_reserved_words(&reserved, &mut index)
-        );
-        assert_eq!(None, get_reserved_words(&not_reserved, &mut index));
-    }
-
-    #[test]
-    fn test_get_reserved_word() {
-        let reserved = vec![
-            Char {
-                char_val: 'i',
-                line_num: 1,
-            },
-            Char {
-                char_val: 'f',
-                line_num: 1,
-            },
-            Char {
-                char_val: ' ',
-                line_num: 1,
-            },
-        ];
-
-        let not_reserved = vec![
-            Char {
-                char_val: 'i',
-                line_num: 1,
-            },
-            Char {
-                char_val: 'f',
-                line_num: 1,
-            },
-            Char {
-                char_val: '_',
-                line_num: 1,
-            },
-        ];
-
-        let expected_token = Token {
-            token_type: TokenType::IF,
-            lexeme: String::from("if"),
+        // A reserved word is only matched when it makes up the whole identifier
+        let expected_id = Token {
+            token_type: TokenType::ID,
+            lexeme: Arc::from("if_"),
             line_num: 1,
         };
 
         let mut index = 0;
         assert_eq!(
-            Some(expected_token),
-            get_reserved_word(&reserved, &mut index, TokenType::IF, "if")
+            expected_int,
+            get_identifier(&chars, &mut index, &mut interner)
         );
+        assert_eq!(3, index);
+
+        index += 1;
         assert_eq!(
-            None,
-            get_reserved_word(&not_reserved, &mut index, TokenType::IF, "if")
+            expected_id,
+            get_identifier(&chars, &mut index, &mut interner)
         );
-    }
+        assert_eq!(7, index);
 
-    #[test]
-    fn test_is_reserved() {
-        let reserved = vec!['i', 'f', ' '];
-        let not_reserved = vec!['i', 'f', '_'];
-
-        assert!(is_reserved(reserved, "if"));
-        assert!(!is_reserved(not_reserved, "if"));
+        index += 1;
+        assert_eq!(
+            TokenType::WHILE,
+            get_identifier(&chars, &mut index, &mut interner).token_type
+        );
     }
 
     #[test]
-    fn test_has_enough_chars() {
-        let maybe_reserved = vec![
-            Char {
-                char_val: 'i',
-                line_num: 1,
-            },
-            Char {
-                char_val: 'f',
-                line_num: 1,
-            },
-            Char {
-                char_val: ' ',
-                line_num: 1,
-            },
-        ];
-
-        assert!(has_enough_chars(&maybe_reserved, &mut 0, 2));
-        assert!(!has_enough_chars(&maybe_reserved, &mut 0, 5));
+    fn test_get_reserved_word() {
+        assert_eq!(Some(TokenType::IF), get_reserved_word("if"));
+        assert_eq!(Some(TokenType::RETURNS), get_reserved_word("returns"));
+        assert_eq!(None, get_reserved_word("iff"));
+        assert_eq!(None, get_reserved_word("If"));
+        assert_eq!(None, get_reserved_word(""));
     }
 
     #[test]
@@ -375,135 +391,369 @@ mod tests {
             Char {
                 char_val: 'I',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: 'd',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: '_',
                 line_num: 1,
+                col_num: 3,
             },
             Char {
                 char_val: '1',
                 line_num: 1,
+                col_num: 4,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 5,
             },
         ];
 
         let expected_token = Token {
             token_type: TokenType::ID,
-            lexeme: String::from("Id_1"),
+            lexeme: Arc::from("Id_1"),
             line_num: 1,
         };
 
         let mut index = 0;
-        assert_eq!(expected_token, get_identifier(&identifier, &mut index));
+        assert_eq!(
+            expected_token,
+            get_identifier(&identifier, &mut index, &mut Interner::new())
+        );
         // Ensure we moved the index far enough (should now point at the final ' ' char)
         assert_eq!(4, index);
     }
 
+    #[test]
+    fn test_interned_lexemes() {
+        let chars = get_chars("count += count ;");
+        let mut interner = Interner::new();
+
+        let first = get_identifier(&chars, &mut 0, &mut interner);
+        let op = get_binary_ops(&chars, &mut 6, &mut interner).unwrap();
+        let second = get_identifier(&chars, &mut 9, &mut interner);
+        assert_eq!(Arc::from("+="), op.lexeme);
+
+        // Both identifiers share a single copy of their lexeme
+        assert_eq!(Arc::from("count"), second.lexeme);
+        assert!(Arc::ptr_eq(&first.lexeme, &second.lexeme));
+        assert!(Arc::ptr_eq(&interner.intern("count"), &first.lexeme));
+    }
+
     #[test]
     fn test_get_int_lits() {
         let int_lit = vec![
             Char {
                 char_val: '0',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: '9',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: '2',
                 line_num: 1,
+                col_num: 3,
             },
             Char {
                 char_val: '6',
                 line_num: 1,
+                col_num: 4,
             },
             Char {
                 char_val: '8',
                 line_num: 1,
+                col_num: 5,
             },
             Char {
                 char_val: ';',
                 line_num: 1,
+                col_num: 6,
             },
         ];
 
         let expected_token = Token {
             token_type: TokenType::INTLIT,
-            lexeme: String::from("09268"),
+            lexeme: Arc::from("09268"),
             line_num: 1,
         };
 
         let mut index = 0;
-        assert_eq!(expected_token, get_int_lits(&int_lit, &mut index));
+        assert_eq!(
+            expected_token,
+            get_int_lits(&int_lit, &mut index, &mut Interner::new())
+        );
         // Ensure we moved the index far enough (should now point at the final ' ' char)
         assert_eq!(5, index);
     }
 
+    #[test]
+    fn test_scan_at_end_of_chars() {
+        // Looking ahead from the last character never runs off the end, even if the characters don't end in a newline
+        // (like the ones get_chars gives back always do)
+        let get_chars_without_newline = |source: &str| -> Vec<Char> {
+            return source
+                .chars()
+                .enumerate()
+                .map(|(i, char_val)| Char {
+                    char_val: char_val,
+                    line_num: 1,
+                    col_num: i as i32 + 1,
+                })
+                .collect();
+        };
+        let mut interner = Interner::new();
+
+        let mut index = 0;
+        let token = get_int_lits(&get_chars_without_newline("12"), &mut index, &mut interner);
+        assert_eq!("12", &*token.lexeme);
+        assert_eq!(2, index);
+
+        let mut index = 0;
+        let token = get_identifier(&get_chars_without_newline("ab"), &mut index, &mut interner);
+        assert_eq!("ab", &*token.lexeme);
+        assert_eq!(2, index);
+
+        let mut index = 0;
+        let token = get_binary_ops(&get_chars_without_newline("+"), &mut index, &mut interner);
+        assert_eq!(Some(TokenType::PLUS), token.map(|token| token.token_type));
+        assert_eq!(1, index);
+
+        let mut index = 0;
+        let error = get_and_or(
+            &get_chars_without_newline("&"),
+            &mut index,
+            &mut interner,
+            TokenType::AND,
+            "&&",
+        );
+        assert!(error.is_err());
+
+        assert_eq!('\n', peek_char(&get_chars_without_newline("a"), 1));
+        assert_eq!('\n', peek_char(&[], 0));
+    }
+
     #[test]
     fn test_get_str_lits() {
         let str_lit = vec![
             Char {
                 char_val: '"',
                 line_num: 1,
+                col_num: 1,
             },
             Char {
                 char_val: 'H',
                 line_num: 1,
+                col_num: 2,
             },
             Char {
                 char_val: 'e',
                 line_num: 1,
+                col_num: 3,
             },
             Char {
                 char_val: 'l',
                 line_num: 1,
+                col_num: 4,
             },
             Char {
                 char_val: 'l',
                 line_num: 1,
+                col_num: 5,
             },
             Char {
                 char_val: 'o',
                 line_num: 1,
+                col_num: 6,
             },
             Char {
                 char_val: '!',
                 line_num: 1,
+                col_num: 7,
             },
             Char {
                 char_val: '\n',
                 line_num: 1,
+                col_num: 8,
             },
             Char {
                 char_val: '"',
                 line_num: 1,
+                col_num: 9,
             },
             Char {
                 char_val: ' ',
                 line_num: 1,
+                col_num: 10,
             },
         ];
 
         let expected_token = Token {
             token_type: TokenType::STRLIT,
-            lexeme: String::from("Hello!\n"),
+            lexeme: Arc::from("Hello!\n"),
             line_num: 1,
         };
 
         let mut index = 0;
-        assert_eq!(expected_token, get_str_lits(&str_lit, &mut index));
+        assert_eq!(
+            Ok(expected_token),
+            get_str_lits(&str_lit, &mut index, &mut Interner::new())
+        );
         // Ensure we moved the index far enough (should now point at the final ' ' char)
         assert_eq!(9, index);
     }
 
+    #[test]
+    fn test_scan_str() {
+        let tokens = scan_str("x = 1; // comment\nprintf(\"hi\");").unwrap();
+        let token_types: Vec<TokenType> = tokens.iter().map(|token| token.token_type).collect();
+        assert_eq!(
+            vec![
+                TokenType::ID,
+                TokenType::ASSIGN,
+                TokenType::INTLIT,
+                TokenType::SEMICOLON,
+                TokenType::ID,
+                TokenType::OPENPAR,
+                TokenType::STRLIT,
+                TokenType::CLOSEPAR,
+                TokenType::SEMICOLON,
+                TokenType::EOF,
+            ],
+            token_types
+        );
+        assert_eq!(Arc::from("hi"), tokens[6].lexeme);
+        assert_eq!(2, tokens[9].line_num);
+
+        // An empty source is just the end of the file
+        let tokens = scan_str("").unwrap();
+        assert_eq!(1, tokens.len());
+        assert_eq!(TokenType::EOF, tokens[0].token_type);
+
+        // Errors are given back rather than exiting, saying exactly where they are
+        assert_eq!(
+            Err(ScanError {
+                message: String::from("Unterminated string literal starting on line 2, column 8"),
+                line_num: 2,
+                col_num: 8,
+            }),
+            scan_str("x = 1;\nprintf(\"hi);\n")
+        );
+        assert_eq!(
+            "Unrecognized character '#' on line 1, column 5",
+            scan_str("x = #;").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Unrecognized character '&' on line 2, column 7 (did you mean '&&'?)",
+            scan_str("\nx = 1 & 2;").unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Unrecognized character '\\u{7}' on line 1, column 1",
+            scan_str("\u{7}").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_scan_str_lossless() {
+        let source = "int x; // one\n\n\t// two\nprintf(\"a // b\");\n";
+        let (tokens, trivia) = scan_str_lossless(source).unwrap();
+        assert_eq!(scan_str(source).unwrap(), tokens);
+        assert_eq!(tokens.len(), trivia.len());
+
+        // The trivia in front of each token is everything since the token before it
+        assert_eq!(Trivia::default(), trivia[0]);
+        assert_eq!(
+            vec![TriviaPiece::Whitespace(String::from(" "))],
+            trivia[1].pieces
+        );
+        assert_eq!(
+            vec![
+                TriviaPiece::Whitespace(String::from(" ")),
+                TriviaPiece::Comment(String::from("// one")),
+                TriviaPiece::Newline,
+                TriviaPiece::Newline,
+                TriviaPiece::Whitespace(String::from("\t")),
+                TriviaPiece::Comment(String::from("// two")),
+                TriviaPiece::Newline,
+            ],
+            trivia[3].pieces
+        );
+        assert_eq!(vec![TriviaPiece::Newline], trivia[8].pieces);
+
+        // so the source can be put back together exactly as it was
+        for source in [
+            source,
+            "",
+            "// nothing but a comment",
+            "func main() returns void {\n    x = 1;  \n}",
+        ] {
+            let (tokens, trivia) = scan_str_lossless(source).unwrap();
+            assert_eq!(source, reproduce_source(&tokens, &trivia));
+        }
+    }
+
+    #[test]
+    fn test_token_source_text() {
+        let tokens = scan_str("printf(\"a {}\", x1);").unwrap();
+        let texts: Vec<String> = tokens.iter().map(|token| token.source_text()).collect();
+
+        // A string literal gets its quotes back, and the EOF token has no text
+        assert_eq!(
+            vec!["printf", "(", "\"a {}\"", ",", "x1", ")", ";", ""],
+            texts
+        );
+    }
+
+    #[test]
+    fn test_get_chars() {
+        let positions = |source: &str| -> Vec<(char, i32, i32)> {
+            return get_chars(source)
+                .iter()
+                .map(|char| (char.char_val, char.line_num, char.col_num))
+                .collect();
+        };
+
+        // Windows and old Mac line endings are both scanned as a single newline
+        assert_eq!(
+            vec![
+                ('a', 1, 1),
+                ('\n', 1, 2),
+                ('b', 2, 1),
+                ('\n', 2, 2),
+                ('c', 3, 1),
+                ('\n', 3, 2)
+            ],
+            positions("a\r\nb\rc\r\n")
+        );
+
+        // A tab moves along to the next tab stop
+        assert_eq!(
+            vec![
+                ('\t', 1, 1),
+                ('x', 1, 9),
+                ('y', 1, 10),
+                ('\t', 1, 11),
+                ('z', 1, 17),
+                ('\n', 1, 18)
+            ],
+            positions("\txy\tz")
+        );
+
+        // The end of the file is on its last line, whether or not that line ends in a newline
+        assert_eq!(3, scan_str("x\n\ny\n").unwrap().last().unwrap().line_num);
+        assert_eq!(3, scan_str("x\r\n\r\ny").unwrap().last().unwrap().line_num);
+        assert!(get_chars("").is_empty());
+    }
+
     #[test]
     fn test_is_id_char() {
         let test_chars = vec![
@@ -1,7 +1,9 @@
 pub mod semantic_callbacks;
 pub mod semantic_data;
 pub mod semantic_driver;
+pub mod semantic_passes;
 pub mod semantic_utils;
 
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+mod tests;
@@ -1,357 +1,881 @@
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::parser::parser_data::ASTNode;
+use crate::ir::ir_constants::{evaluate_constant, EvalError};
+use crate::parser::parser_data::{is_binary_operator, NodeId, NodeRef, AST};
 use crate::semantic::semantic_data::*;
-use crate::semantic::semantic_utils::{is_binary, is_unary};
-use crate::throw_error;
+use crate::semantic::semantic_utils::{
+    element_type, get_array_dims, get_constant_value, is_array_type, is_binary, is_func_type,
+    is_unary,
+};
 
 // ----------------------------------------------------------------------------------------------------
 // PASS 1
 // ----------------------------------------------------------------------------------------------------
 
-pub fn pass1_post(node: &mut ASTNode, scope_stack: &mut ScopeStack, num_main_decls: &mut i32) {
+pub fn pass1_post(
+    ast: &mut AST,
+    id: NodeId,
+    scope_stack: &mut ScopeStack,
+    num_main_decls: &mut i32,
+) -> Result<(), String> {
+    let node = ast.get(id);
     let node_type = &node.node_type.clone();
 
     if node_type == "mainFuncDecl" {
-        // Create a symbol for the main declaration
+        // Create a symbol for the main declaration (which returns either void or an int exit code)
         let main_symbol = Symbol::new(
-            String::from("main"),
-            String::from("f()"),
-            String::from("void"),
+            node.child(0).get_attr(),
+            Type::Func {
+                params: vec![],
+                ret: Box::new(node.child(2).child(0).get_type()),
+            },
         );
 
         // Insert symbol into scope stack and AST node
-        insert_symbol(main_symbol, scope_stack, node);
+        insert_symbol(main_symbol, scope_stack, &mut ast[id]);
 
         // Keep track of the number of main declarations
         *num_main_decls += 1;
     } else if node_type == "funcDecl" {
         // Get fields from the AST
-        let func_name = &node.children[0].get_attr();
-        let func_sig = node.get_func_sig();
-        let func_returns = node.children[2].children[0].node_type.clone();
-
-        // Create a symbol for the function declaration
-        let func_symbol = Symbol::new(func_name.clone(), func_sig, func_returns);
+        let func_name = &node.child(0).get_attr();
+        let func_type = Type::Func {
+            params: node.get_param_types(),
+            ret: Box::new(node.child(2).child(0).get_type()),
+        };
+
+        // Function names can't begin with the prefix the compiler exports every function under
+        if func_name.starts_with(RESERVED_PREFIX) {
+            return Err(format!(
+                "{}: Function '{}' cannot begin with '{}', which is reserved for the compiler",
+                node.get_location(),
+                func_name,
+                RESERVED_PREFIX
+            ));
+        }
 
-        // Insert symbol into scope stack and AST node
-        insert_symbol(func_symbol, scope_stack, node);
-    } else if node_type == "globVarDecl" {
-        // If this declaration has an assignment attached to it, we have to ensure it is being assigned to a literal
-        if node.has_assignment() {
-            let value = &node.children[2];
-
-            // Get the type of the literal
-            // (can assume it's a literal because its only used at a point in the control flow where it must be a literal)
-            let value_type;
-            if value.node_type == "number" {
-                value_type = "int";
+        // An array parameter is passed as its address and its length, in two consecutive argument passing registers,
+        // so every array parameter has to fit within the 8 argument passing registers
+        let mut slot = 0;
+        for param in node.child(1).children() {
+            if is_array_type(&param.child(0).get_type()) {
+                if slot + 2 > 8 {
+                    return Err(format!(
+                        "{}: Array parameter '{}' must be passed within the first 8 argument registers, try moving it earlier in the parameter list",
+                        param.get_location(),
+                        param.child(1).get_attr()
+                    ));
+                }
+                slot += 2;
             } else {
-                value_type = "bool";
+                slot += 1;
             }
+        }
 
-            // If the value isn't a literal
-            if value.node_type != "number"
-                && value.node_type != "true"
-                && value.node_type != "false"
-            {
-                throw_error(&format!(
-                    "Line {}: Global variable '{}' can only be initialized to a literal",
-                    node.get_line_num(),
-                    node.children[1].get_attr()
-                ));
+        // Create a symbol for the function declaration
+        let func_symbol = Symbol::new(func_name.clone(), func_type);
 
-            // The value must also have the same type as the variable
-            } else if node.children[0].get_type() != value_type {
-                throw_error(&format!(
-                    "Line {}: Type mismatch for =, operands must have same type ({} != {})",
-                    node.get_line_num(),
-                    node.children[0].get_type(),
-                    value_type
-                ))
-            }
-        }
+        // Insert symbol into scope stack and AST node
+        insert_symbol(func_symbol, scope_stack, &mut ast[id]);
+    } else if node_type == "globVarDecl" {
+        // Arrays have some extra restrictions on their declarations
+        check_array_decl(node)?;
 
         // Get fields from the AST
-        let var_name = &node.children[1].get_attr();
-        let var_type = node.children[0].node_type.clone();
-        let var_returns = var_type.clone();
+        let var_name = &node.child(1).get_attr();
+        let var_type = node.child(0).get_type();
 
         // Create a symbol for the variable declaration
-        let var_symbol = Symbol::new(var_name.clone(), var_type, var_returns);
+        let mut var_symbol = Symbol::new(var_name.clone(), var_type);
+        var_symbol.array_dims = get_array_dims(node.child(0));
 
         // Insert symbol into scope stack and AST node
-        insert_symbol(var_symbol, scope_stack, node);
+        insert_symbol(var_symbol, scope_stack, &mut ast[id]);
     }
+    return Ok(());
+}
+
+// Make sure an array declaration (global or local) has a sensible size and no initializer
+fn check_array_decl(node: NodeRef) -> Result<(), String> {
+    if !is_array_type(&node.child(0).get_type()) {
+        return Ok(());
+    }
+
+    // Every dimension of the array must have a size of at least 1
+    let dims = get_array_dims(node.child(0));
+    if dims.iter().any(|dim| *dim < 1) {
+        return Err(format!(
+            "{}: Array '{}' must have a size of at least 1 in every dimension",
+            node.get_location(),
+            node.child(1).get_attr()
+        ));
+    }
+
+    // The whole array has to be addressable with an int
+    let num_elements = dims.iter().fold(1i64, |total, dim| total * (*dim as i64));
+    if num_elements * 4 > i32::MAX as i64 {
+        return Err(format!(
+            "{}: Array '{}' is too large",
+            node.get_location(),
+            node.child(1).get_attr()
+        ));
+    }
+
+    if node.has_assignment() {
+        return Err(format!(
+            "{}: Array '{}' cannot be initialized in its declaration",
+            node.get_location(),
+            node.child(1).get_attr()
+        ));
+    }
+    return Ok(());
 }
 
 // ----------------------------------------------------------------------------------------------------
 // PASS 2
 // ----------------------------------------------------------------------------------------------------
 
-pub fn pass2_pre(node: &mut ASTNode, scope_stack: &mut ScopeStack) {
+pub fn pass2_pre(
+    ast: &mut AST,
+    id: NodeId,
+    scope_stack: &mut ScopeStack,
+    warnings: &mut Warnings,
+) -> Result<(), String> {
+    let node = ast.get(id);
+
     if node.node_type == "funcDecl"
         || node.node_type == "mainFuncDecl"
         || node.node_type == "if"
         || node.node_type == "ifElse"
         || node.node_type == "while"
+        || node.node_type == "loop"
+        || node.node_type == "repeat"
     {
         // Open up a new scope
         scope_stack.open_scope();
     } else if node.node_type == "varDecl" {
         // Variables can only be defined in the global or function scopes (scope levels 2 and 3)
         if scope_stack.scope_level() > 3 {
-            throw_error(&format!("Line {}: Variables can only be defined in the outermost scope of a function or globally (i.e. not in an if statement, while loop, etc.)",
-                                      node.get_line_num()))
+            return Err(format!("{}: Variables can only be defined in the outermost scope of a function or globally (i.e. not in an if statement, while loop, etc.)",
+                                      node.get_location()));
         }
 
         // Check if a variable with this name has already been defined in this scope
-        if scope_stack.is_in_scope(&&node.children[1].get_attr()) {
+        if scope_stack.is_in_scope(&node.child(1).get_attr()) {
             // A variable with this name has been defined already in this scope
-            throw_error(&format!(
-                "Line {}: Variable illegally redefined within the same scope",
-                node.get_line_num()
+            return Err(format!(
+                "{}: Variable illegally redefined within the same scope",
+                node.get_location()
             ));
         } else {
+            // Arrays have some extra restrictions on their declarations
+            check_array_decl(node)?;
+
             // This variable hasn't been defined yet in this scope, so we can proceed to define it in our symbol table
-            let var_name = node.children[1].get_attr();
-            let var_type = node.children[0].get_type();
+            let var_name = node.child(1).get_attr();
+            warn_shadow(node, &var_name, scope_stack, warnings)?;
+            let var_type = node.child(0).get_type();
 
-            let var_symbol = Symbol::new(var_name.clone(), var_type.clone(), var_type);
+            let mut var_symbol = Symbol::new(var_name.clone(), var_type);
+            var_symbol.array_dims = get_array_dims(node.child(0));
 
             // Insert symbol into scope stack and AST node
-            insert_symbol(var_symbol, scope_stack, node);
+            insert_symbol(var_symbol, scope_stack, &mut ast[id]);
         }
     } else if node.node_type == "parameter" {
         // Parameters are essentially identical to local variables
-        let param_name = node.children[1].get_attr();
-        let param_type = node.children[0].node_type.clone();
+        let param_name = node.child(1).get_attr();
+        warn_shadow(node, &param_name, scope_stack, warnings)?;
+        let param_type = node.child(0).get_type();
 
-        let param_symbol = Symbol::new(param_name.clone(), param_type.clone(), param_type);
+        let param_symbol = Symbol::new(param_name.clone(), param_type);
 
         // Insert symbol into scope stack and AST node
-        insert_symbol(param_symbol, scope_stack, node);
+        insert_symbol(param_symbol, scope_stack, &mut ast[id]);
     } else if node.node_type == "id" {
         match scope_stack.find_symbol(&node.get_attr()) {
             // If we can't find the identifier, we haven't defined it yet
-            None => throw_error(&format!(
-                "Line {}: Unknown identifier '{}'",
-                node.get_line_num(),
-                node.get_attr()
-            )),
+            None => {
+                return Err(format!(
+                    "{}: Unknown identifier '{}'",
+                    node.get_location(),
+                    node.get_attr()
+                ))
+            }
             Some(symbol) => {
                 // This identifier exists already, so we already know what it returns and what its symbol table is
-                node.type_sig = Some(symbol.borrow().type_sig.clone());
-                node.sym = Some(Rc::clone(&symbol));
+                ast[id].type_sig = Some(symbol.borrow().type_sig.clone());
+                ast[id].sym = Some(Rc::clone(&symbol));
             }
         }
     } else if node.node_type == "number" {
-        node.type_sig = Some(String::from("int"));
+        ast[id].type_sig = Some(Type::Int);
     } else if node.node_type == "true" || node.node_type == "false" {
-        node.type_sig = Some(String::from("bool"));
+        ast[id].type_sig = Some(Type::Bool);
     }
+    return Ok(());
 }
 
-pub fn pass2_post(node: &mut ASTNode, scope_stack: &mut ScopeStack) {
+// Warn about a parameter or local variable with the same name as something declared in an outer scope
+// (like a global, a function or something in the runtime library), which it hides
+fn warn_shadow(
+    node: NodeRef,
+    name: &str,
+    scope_stack: &ScopeStack,
+    warnings: &mut Warnings,
+) -> Result<(), String> {
+    // Anything in the current scope is a redefinition rather than something being hidden
+    let outer = match scope_stack.find_symbol_level(name) {
+        Some(level) if level < scope_stack.scope_level() => scope_stack.find_symbol(name).unwrap(),
+        _ => return Ok(()),
+    };
+
+    let kind = if is_func_type(&outer.borrow().type_sig) {
+        "function"
+    } else {
+        "variable"
+    };
+    let declared = match outer.borrow().line_num {
+        None => String::from("in the runtime library"),
+        Some(line_num) => format!("declared on line {}", line_num),
+    };
+
+    return warnings.report(
+        "shadow",
+        &format!(
+            "{}: Declaration of '{}' hides the {} {}",
+            node.get_location(),
+            name,
+            kind,
+            declared
+        ),
+    );
+}
+
+pub fn pass2_post(node: NodeRef, scope_stack: &mut ScopeStack) -> Result<(), String> {
     if node.node_type == "funcDecl"
         || node.node_type == "mainFuncDecl"
         || node.node_type == "if"
         || node.node_type == "ifElse"
         || node.node_type == "while"
+        || node.node_type == "loop"
+        || node.node_type == "repeat"
     {
         // Close the topmost scope
         scope_stack.close_scope();
+    } else if node.node_type == "return" && !node.children.is_empty() {
+        // A local array lives in its function's stack frame, so returning it (or one of its rows) would leave
+        // the caller with a dangling array. Array parameters and global arrays outlive the function, so those are fine
+        let mut value = node.child(0);
+        while value.node_type == "index" {
+            value = value.child(0);
+        }
+
+        if value.node_type == "id" && value.get_sym().borrow().is_array_storage() {
+            match scope_stack.find_symbol_level(&value.get_attr()) {
+                Some(level) if level > 2 => {
+                    return Err(format!(
+                        "{}: Cannot return local array '{}', it does not outlive the function",
+                        node.get_location(),
+                        value.get_attr()
+                    ))
+                }
+                _ => {}
+            }
+        }
     }
+    return Ok(());
 }
 
 // ----------------------------------------------------------------------------------------------------
 // PASS 3
 // ----------------------------------------------------------------------------------------------------
 
-pub fn pass3_post(node: &mut ASTNode, scope_stack: &mut ScopeStack) {
+pub fn pass3_post(ast: &mut AST, id: NodeId) -> Result<(), String> {
+    let node = ast.get(id);
+
+    // The type this node turns out to have (and the symbol of the function, for a function call),
+    // which are attached to it once it's been checked
+    let mut type_sig = None;
+    let mut sym = None;
+
+    // A function can only be called, it can't be used as a value
+    // (the only other place its name can appear is its own declaration)
+    for (i, child) in node.children().enumerate() {
+        let is_callee = node.node_type == "funcCall" && i == 0;
+        let is_decl = node.node_type == "funcDecl" || node.node_type == "mainFuncDecl";
+
+        if child.node_type == "id" && is_func_type(&child.get_type()) && !is_callee && !is_decl {
+            return Err(format!(
+                "{}: Function '{}' cannot be used as a value, did you mean to call it with '{}()'?",
+                child.get_location(),
+                child.get_attr(),
+                child.get_attr()
+            ));
+        }
+
+        // A number has to fit in an int, except for the one right after a minus sign, which can be one larger
+        // (since the smallest int, "-2147483648", is written as the negation of a number that doesn't fit)
+        if child.node_type == "number" {
+            let largest = match node.node_type.as_str() {
+                "u-" => -(i32::MIN as i64),
+                _ => i32::MAX as i64,
+            };
+            if !child
+                .get_attr()
+                .parse::<i64>()
+                .is_ok_and(|value| value <= largest)
+            {
+                return Err(format!(
+                    "{}: Number '{}' is too large to fit in an int",
+                    child.get_location(),
+                    child.get_attr()
+                ));
+            }
+        }
+    }
+
     if is_binary(node) {
-        let left_type = node.children[0].get_type();
-        let right_type = node.children[1].get_type();
+        let left_type = node.child(0).get_type();
+        let right_type = node.child(1).get_type();
+
+        // Arrays can only be indexed, passed to functions and returned, they can't be operated on as a whole
+        if is_array_type(&left_type) || is_array_type(&right_type) {
+            return Err(format!(
+                "{}: Operator {} cannot be applied to an entire array",
+                node.get_location(),
+                node.node_type
+            ));
+        }
 
         // Both sides of a binary operation must have the same type
         if left_type != right_type {
-            throw_error(&format!(
-                "Line {}: Type mismatch for {}, operands must have same type ({} != {})",
-                node.get_line_num(),
+            return Err(format!(
+                "{}: Type mismatch for {}, operands must have same type ({} != {})",
+                node.get_location(),
                 node.node_type,
                 left_type,
                 right_type
-            ))
+            ));
         } else {
             // Types match, but we need to check if the types (even if they match) make sense with the operation
             if node.node_type == "&&" || node.node_type == "||" {
                 // Both operands must be bools, returns a bool
-                if left_type == "bool" && right_type == "bool" {
+                if left_type == Type::Bool && right_type == Type::Bool {
                     // Type check is successful
-                    node.type_sig = Some(String::from("bool"));
+                    type_sig = Some(Type::Bool);
                 } else {
-                    throw_error(&format!(
-                        "Line {}: Type mismatch for {}, operands must be bools",
-                        node.get_line_num(),
+                    return Err(format!(
+                        "{}: Type mismatch for {}, operands must be bools",
+                        node.get_location(),
                         node.node_type
-                    ))
+                    ));
                 }
             } else if node.node_type == "==" || node.node_type == "!=" {
                 // Operands can be either ints or bools, returns a bool
-                node.type_sig = Some(String::from("bool"));
+                type_sig = Some(Type::Bool);
             } else if node.node_type == "<"
                 || node.node_type == ">"
                 || node.node_type == "<="
                 || node.node_type == ">="
             {
                 // Both operands must be ints, returns a bool
-                if left_type == "int" && right_type == "int" {
+                if left_type == Type::Int && right_type == Type::Int {
                     // Type check is successful
-                    node.type_sig = Some(String::from("bool"));
+                    type_sig = Some(Type::Bool);
                 } else {
-                    throw_error(&format!(
-                        "Line {}: Type mismatch for {}, operands must be ints",
-                        node.get_line_num(),
+                    return Err(format!(
+                        "{}: Type mismatch for {}, operands must be ints",
+                        node.get_location(),
                         node.node_type
-                    ))
+                    ));
                 }
             } else if node.node_type == "=" {
                 // Operands can be either ints or bools, returns whatever type the operands are
-                node.type_sig = Some(left_type);
+                type_sig = Some(left_type);
             } else {
                 // One of + += - -= * *= / /= % %=
                 // Both operands must be ints, returns an int
-                if left_type == "int" && right_type == "int" {
+                if left_type == Type::Int && right_type == Type::Int {
                     // Type check is successful
-                    node.type_sig = Some(String::from("int"));
+                    type_sig = Some(Type::Int);
                 } else {
-                    throw_error(&format!(
-                        "Line {}: Type mismatch for {}, operands must be ints",
-                        node.get_line_num(),
+                    return Err(format!(
+                        "{}: Type mismatch for {}, operands must be ints",
+                        node.get_location(),
                         node.node_type
-                    ))
+                    ));
+                }
+
+                // Dividing by something that's always zero would always be a runtime error, so catch it now
+                let is_division =
+                    node.node_type.starts_with('/') || node.node_type.starts_with('%');
+                if is_division && get_constant_value(node.child(1)) == Some(0) {
+                    return Err(format!(
+                        "{}: Division by zero, the right side of {} is always 0",
+                        node.get_location(),
+                        node.node_type
+                    ));
                 }
             }
         }
-    } else if is_unary(&node) {
-        let op_type = node.children[0].get_type();
+    } else if is_unary(node) {
+        let op_type = node.child(0).get_type();
         if node.node_type == "u-" {
             // Operand must be int, returns an int
-            if op_type == "int" {
+            if op_type == Type::Int {
                 // Type check is successful
-                node.type_sig = Some(String::from("int"));
+                type_sig = Some(Type::Int);
             } else {
-                throw_error(&format!(
-                    "Line {}: Type mismatch for -, operand must be int",
-                    node.get_line_num()
-                ))
+                return Err(format!(
+                    "{}: Type mismatch for -, operand must be int",
+                    node.get_location()
+                ));
             }
         } else {
             // !
             // Operand must be bool, returns a bool
-            if op_type == "bool" {
+            if op_type == Type::Bool {
                 // Type check is successful
-                node.type_sig = Some(String::from("bool"));
+                type_sig = Some(Type::Bool);
             } else {
-                throw_error(&format!(
-                    "Line {}: Type mismatch for {}, operand must be bool",
-                    node.get_line_num(),
+                return Err(format!(
+                    "{}: Type mismatch for {}, operand must be bool",
+                    node.get_location(),
                     node.node_type
-                ))
+                ));
             }
         }
-    } else if node.node_type == "funcCall" {
-        let func_name = node.children[0].get_attr();
-
-        // Get type signature of function call
-        let func_sig = node.get_func_sig();
+    } else if node.node_type == "cast" {
+        // Ints and bools can be converted to each other (or to themselves), but nothing else can be converted
+        let op_type = node.child(0).get_type();
+        let cast_type = match &*node.get_attr() {
+            "bool" => Type::Bool,
+            _ => Type::Int,
+        };
+
+        if op_type == Type::Int || op_type == Type::Bool {
+            // Type check is successful
+            type_sig = Some(cast_type);
+        } else {
+            return Err(format!(
+                "{}: Cannot convert a value of type {} to {}, only ints and bools can be converted",
+                node.get_location(),
+                op_type,
+                cast_type
+            ));
+        }
+    } else if node.node_type == "index" {
+        let array_type = node.child(0).get_type();
+        let index_type = node.child(1).get_type();
+
+        // Only arrays and strings can be indexed
+        if !is_array_type(&array_type) && array_type != Type::String {
+            return Err(format!(
+                "{}: Cannot index into a value of type {}, only arrays and strings can be indexed",
+                node.get_location(),
+                array_type
+            ));
+        }
 
-        // Add func sig to type_sig of ASTNode
-        node.type_sig = Some(func_sig.clone());
+        // The index must be an int
+        if index_type != Type::Int {
+            return Err(format!(
+                "{}: Array index must be an int, not {}",
+                node.get_location(),
+                index_type
+            ));
+        }
 
-        // Try to find the function being called
-        match scope_stack.find_symbol(&func_name) {
-            None => throw_error(&format!(
-                "Line {}: Unknown identifier '{}'",
-                node.get_line_num(),
-                func_name
-            )),
+        if array_type == Type::String {
+            // Indexing a string gives us the value of one of its bytes
+            type_sig = Some(Type::Int);
+        } else {
+            // Indexing an array gives us one of its elements
+            type_sig = Some(element_type(&array_type));
+        }
+    } else if node.node_type == "funcCall" {
+        let func_name = node.child(0).get_attr();
+
+        // Get the types of the arguments passed into the function
+        let arg_types = node.get_param_types();
+
+        // The function being called was found when the identifiers were resolved in pass 2
+        // (in the scope the call is in, so a local variable can be found too)
+        match node.child(0).sym.clone() {
+            None => {
+                return Err(format!(
+                    "{}: Unknown identifier '{}'",
+                    node.get_location(),
+                    func_name
+                ))
+            }
             Some(symbol) => {
-                // Make sure the func sig of the found function matches our function call
-                if symbol.borrow().type_sig != func_sig {
-                    // If the function declaration is printf, the func sigs don't have to match as long as...
-                    if symbol.borrow().type_sig == "f(string, ...)" {
-                        // Our function call sig begins with a string argument
-                        if func_sig.contains("f(string") {
-                            node.type_sig = Some(symbol.borrow().returns.clone());
-                            node.sym = Some(symbol.clone());
+                // Only functions can be called
+                if !is_func_type(&symbol.borrow().type_sig) {
+                    return Err(format!(
+                        "{}: '{}' is a variable of type {}, not a function, so it cannot be called",
+                        node.get_location(),
+                        func_name,
+                        symbol.borrow().type_sig
+                    ));
+                }
+
+                // Make sure the parameters of the found function match the arguments of our function call
+                let params = match &symbol.borrow().type_sig {
+                    Type::Func { params, .. } => params.clone(),
+                    _ => vec![],
+                };
+                if params != arg_types {
+                    // If the function declaration is printf (or fwrite_line), the types don't have to match as long as...
+                    if params.last() == Some(&Type::Varargs) {
+                        // Our function call begins with a string argument (after the file being written to, for fwrite_line)
+                        let fixed = &params[..params.len() - 1];
+                        if arg_types.len() >= fixed.len() && arg_types[..fixed.len()] == *fixed {
+                            type_sig = Some(symbol.borrow().get_returns());
+                            sym = Some(symbol.clone());
+                        } else if fixed.len() == 1 {
+                            return Err(format!(
+                                "{}: First argument passed into 'printf' must be a string literal",
+                                node.get_location()
+                            ));
+                        } else {
+                            return Err(format!("{}: First two arguments passed into '{}' must be an int and a string literal",
+                                                      node.get_location(), func_name));
+                        }
+                    } else if params == vec![Type::Sequence] {
+                        // If the function declaration is len, it takes exactly one string or array,
+                        // and if it's parse_int, exactly one string or int array (holding the characters of a number)
+                        let (is_array, expected) = if &*func_name == "parse_int" {
+                            (
+                                arg_types.first() == Some(&Type::Array(Box::new(Type::Int))),
+                                "int[]",
+                            )
+                        } else {
+                            (arg_types.first().is_some_and(is_array_type), "array")
+                        };
+                        if arg_types.len() == 1 && (arg_types[0] == Type::String || is_array) {
+                            type_sig = Some(symbol.borrow().get_returns());
+                            sym = Some(symbol.clone());
                         } else {
-                            throw_error(&format!("Line {}: First argument passed into 'printf' must be a string literal",
-                                                      node.get_line_num()))
+                            return Err(format!(
+                                "{}: '{}' takes exactly one string or {} argument",
+                                node.get_location(),
+                                func_name,
+                                expected
+                            ));
                         }
+                    } else if params.len() != arg_types.len() {
+                        return Err(format!(
+                            "{}: Function '{}' takes {} argument(s), but {} were passed in",
+                            node.get_location(),
+                            func_name,
+                            params.len(),
+                            arg_types.len()
+                        ));
                     } else {
-                        throw_error(&format!("Line {}: Argument(s) for invocation of function '{}' do not match parameter(s)",
-                                                  node.get_line_num(), func_name))
+                        // Point out the first argument that doesn't have the type of its parameter
+                        let mismatch = params
+                            .iter()
+                            .zip(&arg_types)
+                            .enumerate()
+                            .find(|(_, (param, arg))| param != arg);
+
+                        if let Some((i, (param, arg))) = mismatch {
+                            return Err(format!(
+                                "{}: Argument {} passed into function '{}' must be {}, not {}",
+                                node.get_location(),
+                                i + 1,
+                                func_name,
+                                param,
+                                arg
+                            ));
+                        }
                     }
                 } else {
-                    node.type_sig = Some(symbol.borrow().returns.clone());
-                    node.sym = Some(symbol.clone());
+                    type_sig = Some(symbol.borrow().get_returns());
+                    sym = Some(symbol.clone());
                 }
             }
         }
+
+        // A file's mode is always a string literal, so it can be checked up front
+        if &*func_name == "fopen" {
+            let mode = node.child(1).child(1).child(0).get_attr();
+            if !matches!(&*mode, "r" | "w" | "a") {
+                return Err(format!(
+                    "{}: File mode must be \"r\", \"w\" or \"a\", not \"{}\"",
+                    node.get_location(),
+                    mode
+                ));
+            }
+        }
+    } else if node.node_type == "globVarDecl" && node.has_assignment() {
+        check_global_value(node)?;
+    } else if node.node_type == "varDecl" && node.has_assignment() {
+        check_assigned_type(node)?;
     } else if node.node_type == "return" {
-        if node.children.len() == 0 {
+        if node.children.is_empty() {
             // If the return statement is empty, set its type signature to "void"
-            node.type_sig = Some(String::from("void"));
+            type_sig = Some(Type::Void);
         } else {
             // Otherwise, pass the type of the expression being returned up to the return node
-            node.type_sig = Some(node.children[0].get_type());
+            type_sig = Some(node.child(0).get_type());
         }
     }
+
+    let node = &mut ast[id];
+    if type_sig.is_some() {
+        node.type_sig = type_sig;
+    }
+    if sym.is_some() {
+        node.sym = sym;
+    }
+    return Ok(());
+}
+
+// Make sure the value a local variable is initialized to has the type of the variable,
+// just like a value assigned to it later on would have to
+fn check_assigned_type(node: NodeRef) -> Result<(), String> {
+    let var_type = node.child(0).get_type();
+    let value_type = node.child(2).get_type();
+
+    if is_array_type(&value_type) {
+        return Err(format!(
+            "{}: Operator = cannot be applied to an entire array",
+            node.get_location()
+        ));
+    }
+    if var_type != value_type {
+        return Err(format!(
+            "{}: Type mismatch for =, operands must have same type ({} != {})",
+            node.get_location(),
+            var_type,
+            value_type
+        ));
+    }
+    return Ok(());
+}
+
+// Make sure the value a global variable is initialized to has the type of the variable, and can be worked out
+// at compile time (so it can't depend on other variables or function calls, or overflow)
+fn check_global_value(node: NodeRef) -> Result<(), String> {
+    let var_type = node.child(0).get_type();
+    let value_type = node.child(2).get_type();
+
+    if var_type != value_type {
+        return Err(format!(
+            "{}: Type mismatch for =, operands must have same type ({} != {})",
+            node.get_location(),
+            var_type,
+            value_type
+        ));
+    }
+
+    return match evaluate_constant(node.child(2), true) {
+        Ok(_) => Ok(()),
+        Err(EvalError::NotConstant) => Err(format!(
+            "{}: Global variable '{}' can only be initialized to a constant",
+            node.get_location(),
+            node.child(1).get_attr()
+        )),
+        Err(error) => Err(format!(
+            "{}: {} in the value of global variable '{}'",
+            node.get_location(),
+            error.get_message(),
+            node.child(1).get_attr()
+        )),
+    };
 }
 
 // ----------------------------------------------------------------------------------------------------
 // PASS 4
 // ----------------------------------------------------------------------------------------------------
 
-pub fn pass4_pre(node: &mut ASTNode, while_depth: &mut i32) {
-    if node.node_type == "while" {
-        *while_depth += 1;
+pub fn pass4_pre(
+    node: NodeRef,
+    loop_labels: &mut Vec<Option<Arc<str>>>,
+    warnings: &mut Warnings,
+) -> Result<(), String> {
+    if node.is_loop() {
+        // A loop can't have the same label as a loop it's inside of, or a break or continue couldn't tell them apart
+        if let Some(label) = &node.attr {
+            if loop_labels.contains(&node.attr) {
+                return Err(format!(
+                    "{}: there's already a loop labeled '{}' around this one",
+                    node.get_location(),
+                    label
+                ));
+            }
+        }
+        loop_labels.push(node.attr.clone());
     }
 
-    // Break statement must be within a while loop
-    if node.node_type == "break" {
-        if *while_depth == 0 {
-            throw_error(&format!(
-                "Line {}: break statement must be within a while loop",
-                node.get_line_num()
-            ))
+    // Break and continue statements must be within a loop, and within the loop they name (if they name one)
+    if node.node_type == "break" || node.node_type == "continue" {
+        if loop_labels.is_empty() {
+            return Err(format!(
+                "{}: {} statement must be within a loop",
+                node.get_location(),
+                node.node_type
+            ));
+        } else if let Some(label) = &node.attr {
+            if !loop_labels.contains(&node.attr) {
+                return Err(format!(
+                    "{}: {} statement isn't within a loop labeled '{}'",
+                    node.get_location(),
+                    node.node_type,
+                    label
+                ));
+            }
         }
     }
 
-    // An if- or while-condition must be of Boolean type
-    if node.node_type == "if" || node.node_type == "ifElse" || node.node_type == "while" {
-        // The condition is the first child of the if/if-else/while
-        if node.children[0].get_type() != "bool" {
+    // A loop only ends when something inside it gets out of it, so there has to be something that can
+    if node.node_type == "loop" && !has_loop_exit(node) {
+        return Err(format!(
+            "{}: nothing gets out of the loop (with a break, return or exit), so it never ends",
+            node.get_location()
+        ));
+    }
+
+    // An if-, while- or until-condition must be of Boolean type
+    if node.node_type == "if"
+        || node.node_type == "ifElse"
+        || node.node_type == "while"
+        || node.node_type == "repeat"
+    {
+        // The condition is the first child of the if/if-else/while, but comes after the body of a repeat
+        let cond = match node.node_type.as_str() {
+            "repeat" => node.child(1),
+            _ => node.child(0),
+        };
+
+        if cond.get_type() != Type::Bool {
             // Simply for the error statement, so that it can specify whether it was
-            // an if or while condition that caused the error
+            // an if, while or until condition that caused the error
             let node_type = match &node.node_type {
                 w if w == "while" => "while",
+                r if r == "repeat" => "until",
                 _ => "if",
             };
 
-            throw_error(&format!(
-                "Line {}: {} condition must be of boolean type",
-                node.get_line_num(),
+            return Err(format!(
+                "{}: {} condition must be of boolean type",
+                node.get_location(),
                 node_type
             ));
         }
+
+        // A condition that's always true or always false is usually a mistake
+        if let Some(value) = get_constant_value(cond) {
+            warn_constant_condition(node, value != 0, warnings)?;
+        }
+    }
+    return Ok(());
+}
+
+// Warn about an if, while or repeat whose condition is always the given value,
+// unless it's a loop that's meant to keep going until something inside it gets out of it
+fn warn_constant_condition(
+    node: NodeRef,
+    value: bool,
+    warnings: &mut Warnings,
+) -> Result<(), String> {
+    if node.node_type == "repeat" {
+        // A repeat loop ends once its condition is true, rather than once it's false
+        if value {
+            return warnings.report(
+                "constant-condition",
+                &format!(
+                    "{}: until condition is always true, so the loop only runs once",
+                    node.get_location()
+                ),
+            );
+        } else if !has_loop_exit(node) {
+            return warnings.report(
+                "constant-condition",
+                &format!(
+                    "{}: until condition is always false, and nothing gets out of the loop (with a break, return or exit), so it never ends",
+                    node.get_location()
+                ),
+            );
+        }
+    } else if node.node_type != "while" {
+        return warnings.report(
+            "constant-condition",
+            &format!("{}: if condition is always {}", node.get_location(), value),
+        );
+    } else if !value {
+        return warnings.report(
+            "constant-condition",
+            &format!(
+                "{}: while condition is always false, so the loop never runs",
+                node.get_location()
+            ),
+        );
+    } else if !has_loop_exit(node) {
+        return warnings.report(
+            "constant-condition",
+            &format!(
+                "{}: while condition is always true, and nothing gets out of the loop (with a break, return or exit), so it never ends",
+                node.get_location()
+            ),
+        );
+    }
+    return Ok(());
+}
+
+// Check whether anything in the body of a loop can get out of it, which is a return, a call to exit,
+// a break meant for it, or a break or continue meant for a loop around it
+fn has_loop_exit(loop_node: NodeRef) -> bool {
+    let body = match loop_node.node_type.as_str() {
+        "while" => loop_node.child(1),
+        _ => loop_node.child(0),
+    };
+    return jumps_out_of(body, &mut vec![loop_node.attr.clone()]);
+}
+
+// Check whether a statement can get out of the outermost of the given loops it's inside of (innermost last),
+// as the label of each one, if it has one
+fn jumps_out_of(node: NodeRef, loops: &mut Vec<Option<Arc<str>>>) -> bool {
+    if node.node_type == "break" || node.node_type == "continue" {
+        // The loop it's meant for is the innermost one, or the one it names, which can be around all of them
+        let target = match &node.attr {
+            None => Some(loops.len() - 1),
+            Some(_) => loops.iter().rposition(|label| *label == node.attr),
+        };
+        return match target {
+            None => true,
+            Some(target) => target == 0 && node.node_type == "break",
+        };
     }
+    if node.node_type == "return"
+        || (node.node_type == "funcCall" && node.get_func_name() == "exit")
+    {
+        return true;
+    }
+
+    // The operands of a binary operator have already been type checked, so they can't call exit (which returns void),
+    // and there's no need to walk down what could be a long chain of them
+    if is_binary_operator(&node.node_type) {
+        return false;
+    }
+
+    if node.is_loop() {
+        loops.push(node.attr.clone());
+    }
+    let jumps_out = node.children().any(|child| jumps_out_of(child, loops));
+    if node.is_loop() {
+        loops.pop();
+    }
+    return jumps_out;
 }
 
-pub fn pass4_post(node: &mut ASTNode, while_depth: &mut i32) {
-    if node.node_type == "while" {
-        *while_depth -= 1;
+pub fn pass4_post(node: NodeRef, loop_labels: &mut Vec<Option<Arc<str>>>) {
+    if node.is_loop() {
+        loop_labels.pop();
     }
 }
 
@@ -359,56 +883,60 @@ pub fn pass4_post(node: &mut ASTNode, while_depth: &mut i32) {
 // PASS 5
 // ----------------------------------------------------------------------------------------------------
 
-pub fn pass5_pre(node: &mut ASTNode, current_func_returns: &mut String) {
+pub fn pass5_pre(node: NodeRef, current_func_returns: &mut Option<Type>) -> Result<(), String> {
     // If we're entering into a function, make note of its return type
     if node.node_type == "funcDecl" || node.node_type == "mainFuncDecl" {
-        *current_func_returns = node.get_type();
+        *current_func_returns = Some(node.get_type());
 
-        if node.get_type() != "void" && !node.has_nonempty_return() {
+        if node.get_type() != Type::Void && !node.has_nonempty_return() {
             // If this is a non-void function, it must return a value.
             // Thus, if it does not have a non-empty return statement,
             // that is, a return statement that actually returns a value, that is an error
-            throw_error(&format!(
-                "Line {}: Non-void function '{}' must return a value",
-                node.get_line_num(),
-                node.children[0].get_attr()
+            return Err(format!(
+                "{}: Non-void function '{}' must return a value",
+                node.get_location(),
+                node.child(0).get_attr()
             ));
         }
     }
 
     if node.node_type == "return" {
-        if node.get_type() != "void" {
-            // We have a non-empty return statement
-            if current_func_returns == "void" {
+        // Return statements can only be found inside a function
+        let func_returns = current_func_returns.clone().unwrap_or(Type::Void);
+
+        if !node.children.is_empty() {
+            // We have a non-empty return statement (which could be returning a call to a void function)
+            if func_returns == Type::Void {
                 // A void function can't return a value
-                throw_error(&format!(
-                    "Line {}: Void function cannot return a value",
-                    node.get_line_num()
+                return Err(format!(
+                    "{}: Void function cannot return a value",
+                    node.get_location()
                 ));
-            } else if *current_func_returns != node.get_type() {
+            } else if func_returns != node.get_type() {
                 // If we're in a non-void function, we have to be returning a value with the same type
-                throw_error(&format!(
-                    "Line {}: Function is supposed to return {}, but returns {} instead",
-                    node.get_line_num(),
-                    current_func_returns,
+                return Err(format!(
+                    "{}: Function is supposed to return {}, but returns {} instead",
+                    node.get_location(),
+                    func_returns,
                     node.get_type()
                 ));
             }
         } else {
             // We have an empty return statement
-            if current_func_returns != "void" {
-                throw_error(&format!(
-                    "Line {}: Non-void function must return a value",
-                    node.get_line_num()
+            if func_returns != Type::Void {
+                return Err(format!(
+                    "{}: Non-void function must return a value",
+                    node.get_location()
                 ));
             }
         }
     }
+    return Ok(());
 }
 
-pub fn pass5_post(node: &mut ASTNode, current_func_returns: &mut String) {
-    // If we're leaving a function, set the return type back to "None"
+pub fn pass5_post(node: NodeRef, current_func_returns: &mut Option<Type>) {
+    // If we're leaving a function, set the return type back to None
     if node.node_type == "funcDecl" || node.node_type == "mainFuncDecl" {
-        *current_func_returns = String::from("None");
+        *current_func_returns = None;
     }
 }
@@ -1,12 +1,24 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::parser::parser_data::ASTNode;
-use crate::throw_error;
+use crate::passes::passes_trace::{log_event, LogLevel};
+
+// The prefix every function is exported under, which is reserved for the compiler
+// (so a symbol's name always says whether it came from soup)
+pub const RESERVED_PREFIX: &str = "soup_";
 
 pub struct ScopeStack {
-    pub stack: Vec<HashMap<String, Rc<RefCell<Symbol>>>>,
+    pub stack: Vec<HashMap<Arc<str>, Rc<RefCell<Symbol>>>>,
+}
+
+impl Default for ScopeStack {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl ScopeStack {
@@ -16,7 +28,7 @@ impl ScopeStack {
     }
 
     // Return a mutable reference to the top scope in the stack, or None if the scope stack is empty
-    pub fn peek(&mut self) -> Option<&mut HashMap<String, Rc<RefCell<Symbol>>>> {
+    pub fn peek(&mut self) -> Option<&mut HashMap<Arc<str>, Rc<RefCell<Symbol>>>> {
         self.stack.last_mut()
     }
 
@@ -31,11 +43,9 @@ impl ScopeStack {
     }
 
     // Insert a new symbol into the topmost scope of the scope stack
-    pub fn insert_symbol(&mut self, name: String, new_symbol: Rc<RefCell<Symbol>>) {
+    pub fn insert_symbol(&mut self, name: Arc<str>, new_symbol: Rc<RefCell<Symbol>>) {
         match self.peek() {
-            None => {
-                throw_error("Empty scope stack");
-            }
+            None => {} // Should never happen, indicates an error on my end
             Some(top) => {
                 top.insert(name, new_symbol);
             }
@@ -46,12 +56,9 @@ impl ScopeStack {
     pub fn find_symbol(&self, search_name: &str) -> Option<Rc<RefCell<Symbol>>> {
         // Iterate backwards through the scope stack (i.e. starting at the top scope and moving downwards)
         for symbol_table in self.stack.iter().rev() {
-            // Search through each entry in the symbol table for the given name
-            for (name, symbol) in symbol_table {
-                // If we find a symbol with that name, return a reference to it
-                if name == search_name {
-                    return Some(Rc::clone(symbol));
-                }
+            // If this symbol table has a symbol with the given name, return a reference to it
+            if let Some(symbol) = symbol_table.get(search_name) {
+                return Some(Rc::clone(symbol));
             }
         }
 
@@ -64,19 +71,21 @@ impl ScopeStack {
         match self.peek() {
             // If the scope stack is empty, we obviously won't be able to find the symbol
             None => false,
-            Some(symbol_table) => {
-                // Search through each entry in the symbol table for the given name
-                for (name, _symbol) in symbol_table {
-                    // If we find a symbol with that name, return true
-                    if name == search_name {
-                        return true;
-                    }
-                }
-
-                // Otherwise, we weren't able to find a symbol with the given name, so return false
-                false
+            Some(symbol_table) => symbol_table.contains_key(search_name),
+        }
+    }
+
+    // Return the level of the scope the given symbol was found in (using the same numbering as scope_level()),
+    // or None if the symbol doesn't exist anywhere in the scope stack
+    pub fn find_symbol_level(&self, search_name: &str) -> Option<usize> {
+        // Iterate backwards through the scope stack (i.e. starting at the top scope and moving downwards)
+        for (level, symbol_table) in self.stack.iter().enumerate().rev() {
+            if symbol_table.contains_key(search_name) {
+                return Some(level + 1);
             }
         }
+
+        None
     }
 
     // Return the level of the scope (the length of the list)
@@ -85,62 +94,183 @@ impl ScopeStack {
     }
 }
 
+// -----------------------------------------------------------------
+// TYPES
+// -----------------------------------------------------------------
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Type {
+    Int,
+    Bool,
+    String,
+    Void,
+    // An array of elements of the given type (which is itself an array for each extra dimension)
+    Array(Box<Type>),
+    Func { params: Vec<Type>, ret: Box<Type> },
+    // Any number of arguments of any type (only taken by the runtime library, like printf)
+    Varargs,
+    // Either a string or an array of any type (only taken by the runtime library, like len)
+    Sequence,
+    // Should never happen, indicates an error on my end
+    Unknown,
+}
+
+impl Type {
+    // Get the type named by a type node, such as "int" or "bool[][]" (one "[]" per dimension),
+    // or None if it doesn't name one
+    pub fn parse(name: &str) -> Option<Type> {
+        if let Some(element) = name.strip_suffix("[]") {
+            return match Type::parse(element)? {
+                Type::Void => None,
+                element => Some(Type::Array(Box::new(element))),
+            };
+        }
+
+        return match name {
+            "int" => Some(Type::Int),
+            "bool" => Some(Type::Bool),
+            "string" => Some(Type::String),
+            "void" => Some(Type::Void),
+            _ => None,
+        };
+    }
+}
+
+// Types are displayed the way they're written in soup, with a function being displayed as "f(int, bool)"
+// (leaving out its return type, which is what a call to it is checked against)
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Type::Int => write!(f, "int"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Void => write!(f, "void"),
+            Type::Array(element) => write!(f, "{}[]", element),
+            Type::Func { params, .. } => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "f({})", params.join(", "))
+            }
+            Type::Varargs => write!(f, "..."),
+            Type::Sequence => write!(f, "string | array"),
+            Type::Unknown => write!(f, "NO TYPE"),
+        };
+    }
+}
+
 // -----------------------------------------------------------------
 // SYMBOL
 // -----------------------------------------------------------------
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Symbol {
-    pub name: String,
-    pub type_sig: String,
-    pub returns: String,
-    pub label: Option<String>,
-    pub addr: Option<i32>,
-    pub stored_bytes: i32,
-    pub active_callee_saved: Vec<usize>,
+    pub name: Arc<str>,
+    pub type_sig: Type,
+    pub array_dims: Vec<i32>,
+    // The line the symbol was declared on, which is None for the runtime library
+    pub line_num: Option<i32>,
+    // The documentation comment of a function or global variable, if it has one ("///" comments are only kept
+    // when the program was parsed losslessly, like by "soup doc")
+    pub doc: Option<String>,
 }
 
 impl Symbol {
     // Create a new symbol
-    pub fn new(name: String, type_sig: String, returns: String) -> Self {
+    pub fn new(name: Arc<str>, type_sig: Type) -> Self {
         Symbol {
             name: name,
             type_sig: type_sig,
-            returns: returns,
-            label: None,
-            addr: None,
-            stored_bytes: 0,
-            active_callee_saved: vec![],
+            array_dims: vec![],
+            line_num: None,
+            doc: None,
         }
     }
 
-    pub fn get_label(&self) -> String {
-        return match &self.label {
-            None => String::from("LABEL"), // Should never happen, indicates an error on my end
-            Some(label) => label.clone(),
+    // Get the type of the value this symbol gives, which is what a function returns,
+    // or the type of a variable or parameter
+    pub fn get_returns(&self) -> Type {
+        return match &self.type_sig {
+            Type::Func { ret, .. } => *ret.clone(),
+            type_sig => type_sig.clone(),
         };
     }
 
-    pub fn get_addr(&self) -> i32 {
-        return match &self.addr {
-            None => -1, // Should never happen, indicates an error on my end
-            Some(addr) => *addr,
-        };
+    // Return true if this symbol is an array whose elements are stored with it
+    // (i.e. it was declared with a size, rather than being a reference to an array passed in as a parameter)
+    pub fn is_array_storage(&self) -> bool {
+        return !self.array_dims.is_empty();
     }
 
-    pub fn get_active_callees(&self) -> Vec<usize> {
-        return self.active_callee_saved.clone();
+    // Get the total number of elements stored in an array (e.g. 200 for "int grid[10][20]")
+    pub fn get_num_elements(&self) -> i32 {
+        return self.array_dims.iter().product();
     }
 }
 
-// Insert symbol into scope stack and AST node
-pub fn insert_symbol(symbol: Symbol, scope_stack: &mut ScopeStack, ast_node: &mut ASTNode) {
+// Insert symbol into scope stack and AST node (which is where the symbol was declared)
+pub fn insert_symbol(mut symbol: Symbol, scope_stack: &mut ScopeStack, ast_node: &mut ASTNode) {
+    symbol.line_num = ast_node.line_num;
+
+    // Only functions and global variables are documented
+    if ["funcDecl", "mainFuncDecl", "globVarDecl"].contains(&ast_node.node_type.as_str()) {
+        symbol.doc = ast_node
+            .trivia
+            .as_ref()
+            .and_then(|trivia| trivia.get_doc_comment());
+    }
+
+    log_event(LogLevel::Trace, || {
+        format!("declared '{}' as {}", symbol.name, symbol.type_sig)
+    });
+
     // Create a smart pointer to the symbol
     let rc_symbol = Rc::new(RefCell::new(symbol));
 
     // Add symbol to the scope stack
-    scope_stack.insert_symbol(rc_symbol.borrow().name.clone(), Rc::clone(&rc_symbol));
+    scope_stack.insert_symbol(Arc::clone(&rc_symbol.borrow().name), Rc::clone(&rc_symbol));
 
     // Add symbol table entry to the AST node
     ast_node.add_sym(Rc::clone(&rc_symbol));
 }
+
+// -----------------------------------------------------------------
+// WARNINGS
+// -----------------------------------------------------------------
+
+// The warnings turned on for a program (and whether they're treated as errors), along with the ones found in it
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Warnings {
+    // The names of the warnings that are turned on (like "shadow", for -Wshadow)
+    pub enabled: Vec<String>,
+    pub as_errors: bool,
+    // Every warning found so far
+    pub found: Vec<String>,
+}
+
+impl Warnings {
+    // Turn on the given warnings (treating them as errors if asked to), without having found any yet
+    pub fn new(enabled: &[String], as_errors: bool) -> Warnings {
+        return Warnings {
+            enabled: enabled.to_vec(),
+            as_errors: as_errors,
+            found: vec![],
+        };
+    }
+
+    // Report a warning, if the given warning is turned on (naming the flag that turns it off alongside it),
+    // by adding it to the warnings found, or by giving it back as an error if warnings are being treated as errors
+    pub fn report(&mut self, warning: &str, msg: &str) -> Result<(), String> {
+        if !self.enabled.iter().any(|enabled| enabled == warning) {
+            return Ok(());
+        }
+
+        if self.as_errors {
+            return Err(format!(
+                "{} [-W{}] (warnings are being treated as errors)",
+                msg, warning
+            ));
+        }
+
+        self.found.push(format!("{} [-W{}]", msg, warning));
+        return Ok(());
+    }
+}
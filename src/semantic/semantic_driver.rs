@@ -1,26 +1,36 @@
 use std::cell::RefCell;
+use std::mem;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::parser::parser_data::ASTNode;
-use crate::semantic::semantic_callbacks::*;
+use crate::code_gen::code_gen_target::TargetInfo;
+use crate::code_gen::code_gen_utils::{get_func_label, get_global_label};
+use crate::ir::ir_utils::check_strings;
+use crate::parser::parser_data::AST;
+use crate::passes::passes_manager::PassManager;
 use crate::semantic::semantic_data::*;
-use crate::throw_error;
+use crate::semantic::semantic_passes::*;
 
 // -----------------------------------------------------------------
 // SEMANTIC CHECKER
 // -----------------------------------------------------------------
 
-pub fn semantic_checker(ast: &mut ASTNode) {
-    // This semantic checker will perform five traversals of the AST:
+// Check a program, giving back the first error found in it, and adding any warnings about it
+// (that are turned on) to the given warnings
+pub fn semantic_checker(ast: &mut AST, warnings: &mut Warnings) -> Result<(), String> {
+    run_semantic_passes(ast, warnings)?;
+
+    // Finally, now that the type of every format argument is known, check every string in the program
+    return check_strings(ast.get_root());
+}
+
+// Run the semantic passes over a program, which checks everything but its strings
+pub fn run_semantic_passes(ast: &mut AST, warnings: &mut Warnings) -> Result<(), String> {
+    // This semantic checker will perform five traversals of the AST (see semantic_passes.rs):
     //
     // pass 1 - post-order - collects information about global declarations
-    //
     // pass 2 - pre/post-order - figures out what all the identifiers refer to
-    //                           deals with the scope stack, opens up a new scope
-    //                           in the pre pass and closes it in the post pass
-    //
     // pass 3 - post-order - full type checking
-    //
     // pass 4 - pre/post-order - ensure break statements are inside while loops
     //                           and if/while conditions are of boolean type
     // pass 5 - pre/post-order - various checks of return statements and their functions
@@ -35,109 +45,231 @@ pub fn semantic_checker(ast: &mut ASTNode) {
     scope_stack.open_scope();
 
     // Add a symbol for everything in the runtime library
-    scope_stack.insert_symbol(
-        String::from("exit"),
-        Rc::new(RefCell::new(Symbol::new(
-            String::from("exit"),
-            String::from("f(int)"),
-            String::from("void"),
-        ))),
-    );
-    scope_stack.insert_symbol(
-        String::from("printf"),
-        Rc::new(RefCell::new(Symbol::new(
-            String::from("printf"),
-            String::from("f(string, ...)"),
-            String::from("void"),
-        ))),
-    );
+    for symbol in get_runtime_symbols() {
+        scope_stack.insert_symbol(symbol.name.clone(), Rc::new(RefCell::new(symbol)));
+    }
 
     // Open a new scope for the global symbols in anticipation of the first pass
     scope_stack.open_scope();
 
-    // Begin first pass
-    let mut num_main_decls = 0;
-    pass1(ast, &mut scope_stack, &mut num_main_decls);
-
-    // Check for incorrect number of main declarations
-    if num_main_decls == 0 {
-        throw_error("Program must contain a main function declaration");
-    } else if num_main_decls > 1 {
-        throw_error("Program cannot contain more than one main function declaration")
-    }
-
-    // Begin second pass
-    pass2(ast, &mut scope_stack);
-
-    // Begin third pass
-    pass3(ast, &mut scope_stack);
+    // Run each pass over the whole AST in turn
+    let mut passes = get_semantic_passes();
+    let mut context = SemanticContext {
+        scope_stack: scope_stack,
+        warnings: mem::take(warnings),
+    };
+    let result = passes.run(ast, &mut context);
+    *warnings = context.warnings;
+    return result;
+}
 
-    // Begin fourth pass
-    pass4(ast, &mut 0);
+// Get the semantic passes, in the order they need to run in
+// (each one relies on what the passes before it worked out)
+pub fn get_semantic_passes() -> PassManager<AST, SemanticContext, String> {
+    let mut passes = PassManager::new();
+    passes.add_pass(Box::new(GlobalDeclPass { num_main_decls: 0 }));
+    passes.add_pass(Box::new(NameResolutionPass));
+    passes.add_pass(Box::new(TypeCheckPass));
+    passes.add_pass(Box::new(ControlFlowPass {
+        loop_labels: vec![],
+    }));
+    passes.add_pass(Box::new(ReturnCheckPass {
+        current_func_returns: None,
+    }));
+    return passes;
+}
 
-    // Begin fifth pass
-    pass5(ast, &mut String::from("None"));
+// Get a symbol for everything in the runtime library
+fn get_runtime_symbols() -> Vec<Symbol> {
+    return vec![
+        Symbol::new(
+            Arc::from("exit"),
+            Type::Func {
+                params: vec![Type::Int],
+                ret: Box::new(Type::Void),
+            },
+        ),
+        Symbol::new(
+            Arc::from("sleep"),
+            Type::Func {
+                params: vec![Type::Int],
+                ret: Box::new(Type::Void),
+            },
+        ),
+        Symbol::new(
+            Arc::from("printf"),
+            Type::Func {
+                params: vec![Type::String, Type::Varargs],
+                ret: Box::new(Type::Void),
+            },
+        ),
+        Symbol::new(
+            Arc::from("len"),
+            Type::Func {
+                params: vec![Type::Sequence],
+                ret: Box::new(Type::Int),
+            },
+        ),
+        Symbol::new(
+            Arc::from("parse_int"),
+            Type::Func {
+                params: vec![Type::Sequence],
+                ret: Box::new(Type::Int),
+            },
+        ),
+        Symbol::new(
+            Arc::from("to_string"),
+            Type::Func {
+                params: vec![Type::Int, Type::Array(Box::new(Type::Int))],
+                ret: Box::new(Type::Int),
+            },
+        ),
+        Symbol::new(
+            Arc::from("fopen"),
+            Type::Func {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Int),
+            },
+        ),
+        Symbol::new(
+            Arc::from("fwrite_line"),
+            Type::Func {
+                params: vec![Type::Int, Type::String, Type::Varargs],
+                ret: Box::new(Type::Void),
+            },
+        ),
+        Symbol::new(
+            Arc::from("fread_line"),
+            Type::Func {
+                params: vec![Type::Int, Type::Array(Box::new(Type::Int))],
+                ret: Box::new(Type::Int),
+            },
+        ),
+        Symbol::new(
+            Arc::from("fclose"),
+            Type::Func {
+                params: vec![Type::Int],
+                ret: Box::new(Type::Void),
+            },
+        ),
+    ];
 }
 
 // -----------------------------------------------------------------
-// AST TRAVERSALS
+// SYMBOL DUMP
 // -----------------------------------------------------------------
 
-fn pass1(node: &mut ASTNode, scope_stack: &mut ScopeStack, num_main_decls: &mut i32) {
-    // Call recursively on the current node's children
-    for child in &mut node.children {
-        pass1(child, scope_stack, num_main_decls);
-    }
+// Format the symbols of a semantically checked program, one table per scope (the runtime library, the globals,
+// then each function's parameters and local variables), along with the label each function and global
+// is given in the assembly. Parameters and local variables don't have an address yet, since they're only
+// given a register or a place in their function's stack frame during code generation
+pub fn format_symbols(ast: &AST, target: &TargetInfo) -> String {
+    let mut text = String::from("");
 
-    // Execute pass1 function
-    pass1_post(node, scope_stack, num_main_decls);
-}
+    let mut runtime = vec![];
+    for symbol in get_runtime_symbols() {
+        // printf is the C library's own (as is dprintf, which fwrite_line prints with), len is never called
+        // (since the length of a string or array is always kept alongside its address),
+        // and everything else is defined alongside the program
+        let label = match &*symbol.name {
+            "printf" => format!("{}printf", target.symbol_prefix),
+            "fwrite_line" => format!("{}dprintf", target.symbol_prefix),
+            "len" => String::from("-"),
+            _ => get_func_label(target, &symbol.name),
+        };
+        runtime.push((symbol, label));
+    }
+    text.push_str(&format_scope("runtime library", &runtime));
 
-fn pass2(node: &mut ASTNode, scope_stack: &mut ScopeStack) {
-    // Execute pass2 function before checking node children
-    pass2_pre(node, scope_stack);
+    // Globals are numbered in the order they're declared in (which is the order they're lowered in)
+    let mut globals = vec![];
+    let mut num_globals = 0;
+    for decl in ast.get_root().children() {
+        let symbol = decl.get_sym().borrow().clone();
 
-    // Call recursively on the current node's children
-    for child in &mut node.children {
-        pass2(child, scope_stack);
+        if decl.node_type == "globVarDecl" {
+            globals.push((symbol, get_global_label(num_globals)));
+            num_globals += 1;
+        } else {
+            let label = get_func_label(target, &symbol.name);
+            globals.push((symbol, label));
+        }
     }
+    text.push_str(&format_scope("global", &globals));
 
-    // Execute pass2 function after checking node children
-    pass2_post(node, scope_stack);
-}
+    for decl in ast.get_root().children() {
+        if decl.node_type == "globVarDecl" {
+            continue;
+        }
 
-fn pass3(node: &mut ASTNode, scope_stack: &mut ScopeStack) {
-    // Call recursively on the current node's children
-    for child in &mut node.children {
-        pass3(child, scope_stack);
+        // Variables can only be declared in the outermost scope of a function, alongside its parameters
+        let mut locals = vec![];
+        for param in decl.child(1).children() {
+            locals.push((param.get_sym().borrow().clone(), String::from("-")));
+        }
+        for statement in decl.child(3).children() {
+            if statement.node_type == "varDecl" {
+                locals.push((statement.get_sym().borrow().clone(), String::from("-")));
+            }
+        }
+        text.push_str(&format_scope(
+            &format!("function '{}'", decl.get_func_name()),
+            &locals,
+        ));
     }
 
-    // Execute pass3 function after checking node children
-    pass3_post(node, scope_stack);
+    return text;
 }
 
-fn pass4(node: &mut ASTNode, while_depth: &mut i32) {
-    // Execute pass4 function before checking node children
-    pass4_pre(node, while_depth);
+// Format the symbols of a single scope (each alongside its label) as a table
+fn format_scope(name: &str, symbols: &[(Symbol, String)]) -> String {
+    let mut rows = vec![vec![
+        String::from("name"),
+        String::from("type"),
+        String::from("returns"),
+        String::from("size"),
+        String::from("label"),
+    ]];
+    for (symbol, label) in symbols {
+        // The size of an array is only known if it's stored with the symbol
+        let size = if symbol.is_array_storage() {
+            let dims: Vec<String> = symbol
+                .array_dims
+                .iter()
+                .map(|dim| dim.to_string())
+                .collect();
+            dims.join(" x ")
+        } else {
+            String::from("-")
+        };
 
-    // Call recursively on the current node's children
-    for child in &mut node.children {
-        pass4(child, while_depth);
+        rows.push(vec![
+            symbol.name.to_string(),
+            symbol.type_sig.to_string(),
+            symbol.get_returns().to_string(),
+            size,
+            label.clone(),
+        ]);
     }
 
-    // Execute pass3 function after checking node children
-    pass4_post(node, while_depth);
-}
-
-fn pass5(node: &mut ASTNode, current_func_returns: &mut String) {
-    // Execute pass4 function before checking node children
-    pass5_pre(node, current_func_returns);
+    // Pad every column out to the width of its widest entry
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (i, entry) in row.iter().enumerate() {
+            widths[i] = widths[i].max(entry.len());
+        }
+    }
 
-    // Call recursively on the current node's children
-    for child in &mut node.children {
-        pass5(child, current_func_returns);
+    let mut text = format!("scope {}:\n", name);
+    for row in &rows {
+        let entries: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| format!("{:<width$}", entry, width = widths[i]))
+            .collect();
+        text.push_str(&format!("    {}\n", entries.join("  ").trim_end()));
     }
+    text.push('\n');
 
-    // Execute pass3 function after checking node children
-    pass5_post(node, current_func_returns);
+    return text;
 }
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use crate::parser::parser_data::{NodeId, AST};
+use crate::passes::passes_manager::Pass;
+use crate::semantic::semantic_callbacks::*;
+use crate::semantic::semantic_data::{ScopeStack, Type, Warnings};
+
+// Everything shared between the semantic passes
+pub struct SemanticContext {
+    pub scope_stack: ScopeStack,
+    // The warnings that are turned on, and the ones found so far
+    pub warnings: Warnings,
+}
+
+// -----------------------------------------------------------------
+// PASS 1 - post-order - collects information about global declarations
+// -----------------------------------------------------------------
+
+pub struct GlobalDeclPass {
+    pub num_main_decls: i32,
+}
+
+impl Pass<AST, SemanticContext, String> for GlobalDeclPass {
+    fn get_name(&self) -> &'static str {
+        return "global declarations";
+    }
+
+    fn post_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass1_post(
+            ast,
+            node,
+            &mut context.scope_stack,
+            &mut self.num_main_decls,
+        );
+    }
+
+    fn finish(&mut self, _context: &mut SemanticContext) -> Result<(), String> {
+        // Check for incorrect number of main declarations
+        if self.num_main_decls == 0 {
+            return Err(String::from(
+                "Program must contain a main function declaration",
+            ));
+        } else if self.num_main_decls > 1 {
+            return Err(String::from(
+                "Program cannot contain more than one main function declaration",
+            ));
+        }
+        return Ok(());
+    }
+}
+
+// -----------------------------------------------------------------
+// PASS 2 - pre/post-order - figures out what all the identifiers refer to
+// -----------------------------------------------------------------
+
+pub struct NameResolutionPass;
+
+impl Pass<AST, SemanticContext, String> for NameResolutionPass {
+    fn get_name(&self) -> &'static str {
+        return "name resolution";
+    }
+
+    // Deals with the scope stack, opening up a new scope in the pre pass and closing it in the post pass
+    fn pre_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass2_pre(ast, node, &mut context.scope_stack, &mut context.warnings);
+    }
+
+    fn post_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass2_post(ast.get(node), &mut context.scope_stack);
+    }
+}
+
+// -----------------------------------------------------------------
+// PASS 3 - post-order - full type checking
+// -----------------------------------------------------------------
+
+pub struct TypeCheckPass;
+
+impl Pass<AST, SemanticContext, String> for TypeCheckPass {
+    fn get_name(&self) -> &'static str {
+        return "type checking";
+    }
+
+    fn post_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        _context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass3_post(ast, node);
+    }
+}
+
+// -----------------------------------------------------------------
+// PASS 4 - pre/post-order - ensure break and continue statements are inside the loops they're meant for
+//                           and if/while/until conditions are of boolean type
+// -----------------------------------------------------------------
+
+pub struct ControlFlowPass {
+    // The label of every loop we're currently inside of (or None for one without a label), innermost last
+    pub loop_labels: Vec<Option<Arc<str>>>,
+}
+
+impl Pass<AST, SemanticContext, String> for ControlFlowPass {
+    fn get_name(&self) -> &'static str {
+        return "control flow checking";
+    }
+
+    fn pre_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass4_pre(ast.get(node), &mut self.loop_labels, &mut context.warnings);
+    }
+
+    fn post_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        _context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        pass4_post(ast.get(node), &mut self.loop_labels);
+        return Ok(());
+    }
+}
+
+// -----------------------------------------------------------------
+// PASS 5 - pre/post-order - various checks of return statements and their functions
+// -----------------------------------------------------------------
+
+pub struct ReturnCheckPass {
+    pub current_func_returns: Option<Type>,
+}
+
+impl Pass<AST, SemanticContext, String> for ReturnCheckPass {
+    fn get_name(&self) -> &'static str {
+        return "return checking";
+    }
+
+    fn pre_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        _context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        return pass5_pre(ast.get(node), &mut self.current_func_returns);
+    }
+
+    fn post_visit(
+        &mut self,
+        ast: &mut AST,
+        node: NodeId,
+        _context: &mut SemanticContext,
+    ) -> Result<(), String> {
+        pass5_post(ast.get(node), &mut self.current_func_returns);
+        return Ok(());
+    }
+}
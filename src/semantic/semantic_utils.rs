@@ -1,6 +1,8 @@
-use crate::parser::parser_data::ASTNode;
+use crate::ir::ir_constants::evaluate_constant;
+use crate::parser::parser_data::NodeRef;
+use crate::semantic::semantic_data::Type;
 
-pub fn is_binary(node: &ASTNode) -> bool {
+pub fn is_binary(node: NodeRef) -> bool {
     node.node_type == "+"
         || node.node_type == "+="
         || node.node_type == "-"
@@ -23,6 +25,42 @@ pub fn is_binary(node: &ASTNode) -> bool {
         || node.node_type == "||"
 }
 
-pub fn is_unary(node: &ASTNode) -> bool {
+pub fn is_unary(node: NodeRef) -> bool {
     node.node_type == "u-" || node.node_type == "!"
 }
+
+// Returns true if a type is an array (e.g. "int[]"), and false otherwise
+pub fn is_array_type(type_sig: &Type) -> bool {
+    matches!(type_sig, Type::Array(_))
+}
+
+// Returns true if a type is a function (e.g. "f(int)"), which can only be called, and false otherwise
+pub fn is_func_type(type_sig: &Type) -> bool {
+    matches!(type_sig, Type::Func { .. })
+}
+
+// Returns the type of the elements of an array type (e.g. "int" for "int[]")
+pub fn element_type(type_sig: &Type) -> Type {
+    match type_sig {
+        Type::Array(element) => *element.clone(),
+        _ => type_sig.clone(),
+    }
+}
+
+// Get the declared dimensions of an array from its type node (e.g. [10, 20] for the type node of "int grid[10][20]"),
+// which is empty if the type node isn't a sized array. A size too large to fit in an int is given as 0
+pub fn get_array_dims(type_node: NodeRef) -> Vec<i32> {
+    let mut dims = Vec::new();
+
+    for size_node in type_node.children() {
+        dims.push(size_node.get_attr().parse::<i32>().unwrap_or(0));
+    }
+
+    return dims;
+}
+
+// Get the value of an expression if it's a constant that can be worked out at compile time (with a bool being
+// 1 for true and 0 for false), or None if it isn't one, or if working it out would overflow or divide by zero
+pub fn get_constant_value(node: NodeRef) -> Option<i32> {
+    return evaluate_constant(node, true).ok();
+}
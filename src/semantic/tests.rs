@@ -1,7 +1,13 @@
 mod tests {
-    use std::{rc::Rc, cell::RefCell};
+    use std::{env, fs, rc::Rc, cell::RefCell, sync::Arc, time::Instant};
 
-    use crate::semantic::semantic_data::{ScopeStack, Symbol};
+    use crate::code_gen::code_gen_target::get_target_info;
+    use crate::compile_str;
+    use crate::parser::parser_driver::{parse_expression, parser};
+    use crate::scanner::scanner_driver::{scan_str, scanner};
+    use crate::semantic::semantic_data::{ScopeStack, Symbol, Type, Warnings};
+    use crate::semantic::semantic_driver::{format_symbols, semantic_checker};
+    use crate::semantic::semantic_utils::get_constant_value;
 
 
     #[test]
@@ -10,11 +16,10 @@ mod tests {
         scope_stack.open_scope();
 
         scope_stack.insert_symbol(
-            String::from("symbol1"),
+            Arc::from("symbol1"),
             Rc::new(RefCell::new(Symbol::new(
-                String::from("symbol1"),
-                String::from("type"),
-                String::from("returns"),
+                Arc::from("symbol1"),
+                Type::Int,
             ))),
         );
 
@@ -42,12 +47,11 @@ mod tests {
         scope_stack.open_scope();
 
         let test_symbol = Rc::new(RefCell::new(Symbol::new(
-            String::from("symbol1"),
-            String::from("type"),
-            String::from("returns"),
+            Arc::from("symbol1"),
+            Type::Int,
         )));
 
-        scope_stack.insert_symbol(String::from("symbol1"), test_symbol.clone());
+        scope_stack.insert_symbol(Arc::from("symbol1"), test_symbol.clone());
 
         assert_eq!(Some(test_symbol), scope_stack.find_symbol("symbol1"));
     }
@@ -62,24 +66,22 @@ mod tests {
         scope_stack.open_scope();
 
         let symbol1 = Rc::new(RefCell::new(Symbol::new(
-            String::from("symbol"),
-            String::from(""),
-            String::from(""),
+            Arc::from("symbol"),
+            Type::Int,
         )));
 
-        scope_stack.insert_symbol(String::from("symbol"), symbol1);
+        scope_stack.insert_symbol(Arc::from("symbol"), symbol1);
 
 
         // Open another scope, add a symbol with the same name
         scope_stack.open_scope();
 
         let symbol2 = Rc::new(RefCell::new(Symbol::new(
-            String::from("symbol"),
-            String::from("type_sig"),
-            String::from("returns"),
+            Arc::from("symbol"),
+            Type::Bool,
         )));
 
-        scope_stack.insert_symbol(String::from("symbol"), symbol2.clone());
+        scope_stack.insert_symbol(Arc::from("symbol"), symbol2.clone());
 
         assert_eq!(Some(symbol2), scope_stack.find_symbol("symbol"));
     }
@@ -98,4 +100,269 @@ mod tests {
 
         assert_eq!(None, scope_stack.find_symbol("symbol1"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_find_symbol_level() {
+        // Adding two symbols to different scopes, find_symbol_level() should return the level of each
+        let mut scope_stack = ScopeStack::new();
+        scope_stack.open_scope();
+
+        scope_stack.insert_symbol(
+            Arc::from("outer"),
+            Rc::new(RefCell::new(Symbol::new(
+                Arc::from("outer"),
+                Type::Int,
+            ))),
+        );
+
+        scope_stack.open_scope();
+
+        scope_stack.insert_symbol(
+            Arc::from("inner"),
+            Rc::new(RefCell::new(Symbol::new(
+                Arc::from("inner"),
+                Type::Int,
+            ))),
+        );
+
+        assert_eq!(Some(1), scope_stack.find_symbol_level("outer"));
+        assert_eq!(Some(2), scope_stack.find_symbol_level("inner"));
+        assert_eq!(None, scope_stack.find_symbol_level("missing"));
+    }
+
+    #[test]
+    fn test_format_symbols() {
+        let file = env::temp_dir().join(format!("soup-semantic-symbols-{}.soup", std::process::id()));
+        fs::write(&file, "int grid[2][3];\nfunc add(int a, int[] b) returns int {\nint c = a;\nreturn c;\n}\nfunc main() returns void {;}\n").unwrap();
+
+        let tokens = scanner(&file.to_string_lossy());
+        let _ = fs::remove_file(&file);
+
+        let mut ast = parser(&tokens);
+        semantic_checker(&mut ast, &mut Warnings::default()).unwrap();
+
+        let symbols = format_symbols(&ast, get_target_info("x86_64-unknown-linux-gnu").unwrap());
+        assert_eq!("scope runtime library:
+    name         type                 returns  size  label
+    exit         f(int)               void     -     soup_exit
+    sleep        f(int)               void     -     soup_sleep
+    printf       f(string, ...)       void     -     printf
+    len          f(string | array)    int      -     -
+    parse_int    f(string | array)    int      -     soup_parse_int
+    to_string    f(int, int[])        int      -     soup_to_string
+    fopen        f(string, string)    int      -     soup_fopen
+    fwrite_line  f(int, string, ...)  void     -     dprintf
+    fread_line   f(int, int[])        int      -     soup_fread_line
+    fclose       f(int)               void     -     soup_fclose
+
+scope global:
+    name  type           returns  size   label
+    grid  int[][]        int[][]  2 x 3  LG0
+    add   f(int, int[])  int      -      soup_add
+    main  f()            void     -      soup_main
+
+scope function 'add':
+    name  type   returns  size  label
+    a     int    int      -     -
+    b     int[]  int[]    -     -
+    c     int    int      -     -
+
+scope function 'main':
+    name  type  returns  size  label
+
+", symbols);
+    }
+
+    #[test]
+    fn test_get_constant_value() {
+        let value = |source: &str| {
+            let ast = parse_expression(&scan_str(source).unwrap()).unwrap();
+            get_constant_value(ast.get_root())
+        };
+
+        // 1 + 2 * 3 and !(3 < 2)
+        assert_eq!(Some(7), value("1 + 2 * 3"));
+        assert_eq!(Some(1), value("!(3 < 2)"));
+
+        // Anything that would overflow or divide by zero isn't worked out
+        assert_eq!(None, value("2147483647 + 1"));
+        assert_eq!(None, value("1 % 0"));
+
+        // x || true is always true, even though x isn't a constant, but x && true isn't
+        assert_eq!(Some(1), value("x || true"));
+        assert_eq!(None, value("x && true"));
+        assert_eq!(Some(0), value("true && false"));
+        assert_eq!(Some(1), value("x + 1 - 2 < 3 || true"));
+
+        // A chain of operators is worked out in a loop, however long it is
+        assert_eq!(Some(100000), value(&vec!["1"; 100000].join(" + ")));
+    }
+
+    // Compile the given source, expecting it to fail with an error
+    fn compile_error(source: &str) -> String {
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        return compile_str(source, target, 0).unwrap_err();
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let error = |call: &str| {
+            compile_error(&format!("func f(int a, bool b, int c) returns int {{\n    return a;\n}}\nfunc main() returns void {{\n    printf(\"{{}}\", {});\n}}\n", call))
+        };
+
+        // Passing too few or too many arguments
+        assert_eq!("Line 5: Function 'f' takes 3 argument(s), but 2 were passed in", error("f(1, true)"));
+        assert_eq!("Line 5: Function 'f' takes 3 argument(s), but 4 were passed in", error("f(1, true, 2, 3)"));
+
+        // Only the first argument that doesn't have the type of its parameter is pointed out
+        assert_eq!("Line 5: Argument 1 passed into function 'f' must be int, not bool", error("f(false, 1, 2)"));
+        assert_eq!("Line 5: Argument 2 passed into function 'f' must be bool, not int", error("f(1, 2, true)"));
+        assert_eq!("Line 5: Argument 3 passed into function 'f' must be int, not string", error("f(1, true, \"2\")"));
+    }
+
+    #[test]
+    fn test_declaration_errors() {
+        // A local variable has to be initialized to a value of its own type, just like anything assigned to it
+        assert_eq!("Line 2: Type mismatch for =, operands must have same type (int != string)",
+                   compile_error("func main() returns void {\n    int y = \"x\";\n}\n"));
+        assert_eq!("Line 2: Type mismatch for =, operands must have same type (bool != int)",
+                   compile_error("func main() returns void {\n    bool b = 5;\n}\n"));
+        assert_eq!("Line 3: Operator = cannot be applied to an entire array",
+                   compile_error("func main() returns void {\n    int arr[2];\n    int z = arr;\n}\n"));
+        assert_eq!("Line 4: Operator = cannot be applied to an entire array",
+                   compile_error("func main() returns void {\n    int arr[2];\n    int z;\n    z = arr;\n}\n"));
+    }
+
+    #[test]
+    fn test_function_use_errors() {
+        // A function can only be called, and only a function can be called
+        assert_eq!("Line 2: Function 'f' cannot be used as a value, did you mean to call it with 'f()'?",
+                   compile_error("func main() returns void {\n    int y = f;\n}\nfunc f() returns int {\n    return 1;\n}\n"));
+        assert_eq!("Line 3: 'x' is a variable of type int, not a function, so it cannot be called",
+                   compile_error("func main() returns void {\n    int x = 1;\n    x();\n}\n"));
+    }
+
+    #[test]
+    fn test_division_by_zero_errors() {
+        // Dividing (or taking the remainder) by something that's always zero, however it's written
+        assert_eq!("Line 3: Division by zero, the right side of / is always 0",
+                   compile_error("func main() returns void {\n    int x = 1;\n    x = x / (2 - 2);\n}\n"));
+        assert_eq!("Line 3: Division by zero, the right side of %= is always 0",
+                   compile_error("func main() returns void {\n    int x = 1;\n    x %= 0;\n}\n"));
+
+        // while dividing by something that isn't always zero is left to be checked when the program runs
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        assert!(compile_str("func main() returns void {\n    int x = 1;\n    x = x / (x - 1);\n}\n", target, 0).is_ok());
+    }
+
+    #[test]
+    fn test_loop_errors() {
+        // A loop that nothing breaks out of (where breaking out of a loop inside it doesn't count)
+        assert_eq!("Line 2: nothing gets out of the loop (with a break, return or exit), so it never ends",
+                   compile_error("func main() returns void {\n    loop {\n        printf(\"hi\");\n    }\n}\n"));
+        assert_eq!("Line 2: nothing gets out of the loop (with a break, return or exit), so it never ends",
+                   compile_error("func main() returns void {\n    loop {\n        while true {\n            break;\n        }\n    }\n}\n"));
+
+        assert_eq!("Line 2: until condition must be of boolean type",
+                   compile_error("func main() returns void {\n    repeat\n        ;\n    until 1;\n}\n"));
+
+        // Continuing outside of a loop, or naming a loop that isn't around the statement (or is already taken)
+        assert_eq!("Line 2: continue statement must be within a loop",
+                   compile_error("func main() returns void {\n    continue;\n}\n"));
+        assert_eq!("Line 3: break statement isn't within a loop labeled 'b'",
+                   compile_error("func main() returns void {\n    a: while true {\n        break b;\n    }\n}\n"));
+        assert_eq!("Line 3: there's already a loop labeled 'a' around this one",
+                   compile_error("func main() returns void {\n    a: while true {\n        a: loop {\n            break a;\n        }\n    }\n}\n"));
+    }
+
+    #[test]
+    fn test_number_and_conversion_errors() {
+        // The smallest int can be written as it is, even though the number after its minus sign doesn't fit in an int
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+        assert!(compile_str("int smallest = -2147483648;\nfunc main() returns void {\n    printf(\"{}\", -2147483648 / 2);\n}\n", target, 0).is_ok());
+
+        assert_eq!("Line 2: Number '2147483648' is too large to fit in an int",
+                   compile_error("func main() returns void {\n    printf(\"{}\", 2147483648);\n}\n"));
+        assert_eq!("Line 2: Number '2147483649' is too large to fit in an int",
+                   compile_error("func main() returns void {\n    printf(\"{}\", -2147483649);\n}\n"));
+
+        assert_eq!("Line 2: Cannot convert a value of type string to int, only ints and bools can be converted",
+                   compile_error("func main() returns void {\n    printf(\"{}\", int(\"1\"));\n}\n"));
+    }
+
+    #[test]
+    fn test_runtime_function_errors() {
+        assert_eq!("Line 2: 'parse_int' takes exactly one string or int[] argument",
+                   compile_error("func main() returns void {\n    printf(\"{}\", parse_int(1));\n}\n"));
+        assert_eq!("Line 3: 'parse_int' takes exactly one string or int[] argument",
+                   compile_error("func main() returns void {\n    bool b[2];\n    printf(\"{}\", parse_int(b));\n}\n"));
+        assert_eq!("Line 2: 'len' takes exactly one string or array argument",
+                   compile_error("func main() returns void {\n    printf(\"{}\", len(\"a\", 1));\n}\n"));
+        assert_eq!("Line 2: Argument 2 passed into function 'to_string' must be int[], not string",
+                   compile_error("func main() returns void {\n    printf(\"{}\", to_string(1, \"1\"));\n}\n"));
+
+        // A file has to be opened with a mode it can be opened in, and written to with a format string
+        // that has as many formatters as it's given arguments
+        assert_eq!("Line 2: File mode must be \"r\", \"w\" or \"a\", not \"rw\"",
+                   compile_error("func main() returns void {\n    int x = fopen(\"out.txt\", \"rw\");\n}\n"));
+        assert_eq!("Line 2: First two arguments passed into 'fwrite_line' must be an int and a string literal",
+                   compile_error("func main() returns void {\n    fwrite_line(\"{}\", 1);\n}\n"));
+        assert_eq!("Line 2: 2 formatter(s) given to fwrite_line, but only 1 format argument(s) passed in",
+                   compile_error("func main() returns void {\n    fwrite_line(1, \"{} {}\", 2);\n}\n"));
+    }
+
+    #[test]
+    fn test_warning_settings() {
+        let check = |warnings: &mut Warnings| {
+            let mut ast = parser(&scan_str("int x;\nfunc main() returns void {\n    int x = 1;\n}\n").unwrap());
+            return semantic_checker(&mut ast, warnings);
+        };
+
+        // Each check only reports the warnings it was asked for, into its own list
+        let mut shadow = Warnings::new(&[String::from("shadow")], false);
+        check(&mut shadow).unwrap();
+        assert_eq!(vec!["Line 3: Declaration of 'x' hides the variable declared on line 1 [-Wshadow]"], shadow.found);
+
+        let mut quiet = Warnings::default();
+        check(&mut quiet).unwrap();
+        assert!(quiet.found.is_empty());
+
+        // and only stops when that check treats warnings as errors
+        let mut errors = Warnings::new(&[String::from("shadow")], true);
+        assert_eq!(
+            "Line 3: Declaration of 'x' hides the variable declared on line 1 [-Wshadow] (warnings are being treated as errors)",
+            check(&mut errors).unwrap_err()
+        );
+    }
+
+    // Benchmark looking up every symbol of a program with thousands of them,
+    // run with "cargo test --release -- --ignored bench_find_symbol --nocapture"
+    #[test]
+    #[ignore]
+    fn bench_find_symbol() {
+        let num_symbols = 5000;
+
+        // A global scope full of functions, and a function scope full of local variables
+        let mut scope_stack = ScopeStack::new();
+        scope_stack.open_scope();
+        for i in 0..num_symbols {
+            let name: Arc<str> = Arc::from(format!("func{}", i));
+            scope_stack.insert_symbol(name.clone(), Rc::new(RefCell::new(Symbol::new(name, Type::Void))));
+        }
+        scope_stack.open_scope();
+        for i in 0..num_symbols {
+            let name: Arc<str> = Arc::from(format!("var{}", i));
+            scope_stack.insert_symbol(name.clone(), Rc::new(RefCell::new(Symbol::new(name, Type::Int))));
+        }
+
+        let start = Instant::now();
+        for i in 0..num_symbols {
+            assert!(scope_stack.find_symbol(&format!("func{}", i)).is_some());
+            assert!(scope_stack.is_in_scope(&format!("var{}", i)));
+        }
+        let elapsed = start.elapsed();
+
+        println!("{} lookups of {} symbols took {:?} ({:?} each)",
+                 num_symbols * 2, num_symbols * 2, elapsed, elapsed / (num_symbols * 2));
+    }
+}
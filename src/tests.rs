@@ -0,0 +1,178 @@
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::code_gen::code_gen_target::{get_target_info, TARGETS};
+    use crate::compile_str;
+
+    // The pieces of soup the fuzzer splices into programs, which are more likely to get past the scanner
+    // (and into the parser and semantic checker) than random characters
+    const FUZZ_PIECES: &[&str] = &[
+        "(",
+        ")",
+        "{",
+        "}",
+        "[",
+        "]",
+        ";",
+        ",",
+        "\"",
+        "\\",
+        "//",
+        "\n",
+        " ",
+        "{}",
+        "=",
+        "+=",
+        "-",
+        "/",
+        "%",
+        "!",
+        "&&",
+        "<=",
+        "==",
+        "func",
+        "main",
+        "returns",
+        "void",
+        "int",
+        "bool",
+        "if",
+        "else",
+        "while",
+        "loop",
+        "repeat",
+        "until",
+        "break",
+        "continue",
+        ":",
+        "return",
+        "true",
+        "printf",
+        "len",
+        "exit",
+        "sleep",
+        "parse_int",
+        "to_string",
+        "fopen",
+        "fwrite_line",
+        "fread_line",
+        "fclose",
+        "x",
+        "0",
+        "2147483648",
+        "int x[0]",
+        "-2147483648",
+    ];
+
+    // A small, fast pseudo-random number generator (xorshift), so that every run of the fuzzer is the same
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            return (self.0 % bound.max(1) as u64) as usize;
+        }
+    }
+
+    // Make a few random changes to a program: deleting, duplicating or inserting pieces of it
+    fn mutate(rng: &mut Rng, program: &str) -> String {
+        let mut chars: Vec<char> = program.chars().collect();
+
+        for _ in 0..1 + rng.next(4) {
+            let start = rng.next(chars.len() + 1);
+            let end = (start + rng.next(12)).min(chars.len());
+
+            match rng.next(4) {
+                0 => {
+                    chars.drain(start..end);
+                }
+                1 => {
+                    let copy: Vec<char> = chars[start..end].to_vec();
+                    let at = rng.next(chars.len() + 1);
+                    chars.splice(at..at, copy);
+                }
+                2 => {
+                    let piece = FUZZ_PIECES[rng.next(FUZZ_PIECES.len())];
+                    chars.splice(start..start, piece.chars());
+                }
+                _ => chars.insert(start, (rng.next(128) as u8) as char),
+            }
+        }
+
+        return chars.into_iter().collect();
+    }
+
+    // Compile mutations of every program in test_files, for every target and optimization level,
+    // making sure each one either compiles or gives back an error (rather than panicking or exiting)
+    fn fuzz_compile_str(iterations: usize) {
+        let mut programs = vec![];
+        for entry in fs::read_dir("test_files").unwrap().flatten() {
+            if entry
+                .path()
+                .extension()
+                .is_some_and(|extension| extension == "soup")
+            {
+                programs.push(fs::read_to_string(entry.path()).unwrap());
+            }
+        }
+        programs.sort();
+
+        let mut rng = Rng(0x5eed);
+        for i in 0..iterations {
+            let program = mutate(&mut rng, &programs[i % programs.len()]);
+            let target = &TARGETS[i % TARGETS.len()];
+            let opt_level = (i / TARGETS.len() % 4) as u32;
+
+            // A panic here is reported along with the program that caused it, so it can be reproduced
+            if std::panic::catch_unwind(|| compile_str(&program, target, opt_level)).is_err() {
+                panic!(
+                    "compile_str panicked for {} at -O{} on:\n{}",
+                    target.triple, opt_level, program
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_compile_str() {
+        let target = get_target_info("x86_64-unknown-linux-gnu").unwrap();
+
+        let asm = compile_str(
+            "func main() returns void {\n    printf(\"hi\");\n}\n",
+            target,
+            0,
+        );
+        assert!(asm.unwrap().contains("main"));
+
+        // Every kind of problem with the source is given back as an error
+        for (source, expected) in [
+            ("func main() returns void {\n    x = \"\n}\n", "Unterminated string literal starting on line 2"),
+            ("func main() returns void {\n    x = ;\n}\n", "Syntax Error on line 2"),
+            ("func main() returns void {\n    x = 1;\n}\n", "Line 2: Unknown identifier 'x'"),
+            ("func main() returns void {\n    printf(\"\\q\");\n}\n", "Line 2:"),
+            ("func main() returns void {\n    printf(\"{} {}\", 1);\n}\n", "Line 2:"),
+            // Found by the fuzzer, which used to get past the semantic checker
+            ("func f() returns void {\n    exit(0);\n}\nfunc main() returns void {\n    return f();\n}\n", "Line 5: Void function cannot return a value"),
+        ] {
+            let error = compile_str(source, target, 0).unwrap_err();
+            assert!(error.contains(expected), "{} doesn't contain {}", error, expected);
+        }
+
+        fuzz_compile_str(2000);
+    }
+
+    // Fuzz compile_str for much longer than the tests do,
+    // run with "SOUP_FUZZ_ITERATIONS=1000000 cargo test --release -- --ignored fuzz_compile_str_long"
+    #[test]
+    #[ignore]
+    fn fuzz_compile_str_long() {
+        let iterations = env::var("SOUP_FUZZ_ITERATIONS")
+            .ok()
+            .and_then(|iterations| iterations.parse().ok())
+            .unwrap_or(100000);
+        fuzz_compile_str(iterations);
+    }
+}
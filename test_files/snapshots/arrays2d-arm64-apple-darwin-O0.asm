@@ -0,0 +1,276 @@
+.data
+.balign 8
+LG0: .space 48
+.section __TEXT,__cstring,cstring_literals
+LG1: .string "Error: Line %d: Array index out of bounds\n"
+LG2: .string "grid[2][3] = %d\n"
+LG3: .string "sum of grid row 1 = %d\n"
+LG4: .string "sum of table row 2 = %d\n"
+LG5: .string "Error: Line %d: A control path reaches the end of a non-void function without returning a value\n"
+last_newline: .string "\n"
+.text
+
+        .globl  _start
+        .balign 4
+_start:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        bl      _soup_main
+end:
+        ldp     x29, x30, [sp], 16
+        adrp    x0, last_newline@PAGE
+        add     x0, x0, last_newline@PAGEOFF
+        bl      _printf
+        mov     x0, 0  // Return code 0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+        .globl  _soup_main
+_soup_main:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 96
+        str     x19, [sp, 56]
+        str     x20, [sp, 64]
+        str     x21, [sp, 72]
+        str     x22, [sp, 80]
+        str     x23, [sp, 88]
+        mov     w19, 0
+        mov     w20, 0
+Lfn_main_0:
+        cmp     w19, 3
+        b.ge    Lfn_main_2
+Lfn_main_1:
+        mov     w20, 0
+Lfn_main_3:
+        cmp     w20, 4
+        b.ge    Lfn_main_5
+Lfn_main_4:
+        add     x21, sp, 8
+        cmp     w19, 3
+        b.lo    Lfn_main_6
+Lfn_main_7:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 13
+        bl      __soup_runtime_error
+Lfn_main_6:
+        mov     w11, 16
+        smaddl  x22, w19, w11, x21
+        mov     x21, x22
+        cmp     w20, 4
+        b.lo    Lfn_main_8
+Lfn_main_9:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 13
+        bl      __soup_runtime_error
+Lfn_main_8:
+        add     x22, x21, w20, sxtw 2
+        mov     w10, 4
+        madd    w23, w19, w10, w20
+        str     w23, [x22]
+        adrp    x21, LG0@PAGE
+        add     x21, x21, LG0@PAGEOFF
+        cmp     w19, 3
+        b.lo    Lfn_main_10
+Lfn_main_11:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_10:
+        mov     w11, 16
+        smaddl  x22, w19, w11, x21
+        mov     x21, x22
+        cmp     w20, 4
+        b.lo    Lfn_main_12
+Lfn_main_13:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_12:
+        add     x22, x21, w20, sxtw 2
+        add     x21, sp, 8
+        cmp     w19, 3
+        b.lo    Lfn_main_14
+Lfn_main_15:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_14:
+        mov     w11, 16
+        smaddl  x23, w19, w11, x21
+        mov     x21, x23
+        cmp     w20, 4
+        b.lo    Lfn_main_16
+Lfn_main_17:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_16:
+        add     x23, x21, w20, sxtw 2
+        ldr     w21, [x23]
+        mov     w10, 2
+        mul     w23, w21, w10
+        str     w23, [x22]
+        add     w21, w20, 1
+        mov     w20, w21
+        b       Lfn_main_3
+Lfn_main_5:
+        add     w20, w19, 1
+        mov     w19, w20
+        b       Lfn_main_0
+Lfn_main_2:
+        add     x19, sp, 8
+        mov     w9, 2
+        cmp     w9, 3
+        b.lo    Lfn_main_18
+Lfn_main_19:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 20
+        bl      __soup_runtime_error
+Lfn_main_18:
+        mov     w10, 2
+        mov     w11, 16
+        smaddl  x20, w10, w11, x19
+        mov     x19, x20
+        mov     w9, 3
+        cmp     w9, 4
+        b.lo    Lfn_main_20
+Lfn_main_21:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 20
+        bl      __soup_runtime_error
+Lfn_main_20:
+        mov     w10, 3
+        add     x20, x19, w10, sxtw 2
+        ldr     w19, [x20]
+        str     w19, [sp, 0]
+        adrp    x0, LG2@PAGE
+        add     x0, x0, LG2@PAGEOFF
+        bl      _printf
+        add     x19, sp, 8
+        mov     w9, 1
+        cmp     w9, 3
+        b.lo    Lfn_main_22
+Lfn_main_23:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 21
+        bl      __soup_runtime_error
+Lfn_main_22:
+        mov     w10, 1
+        mov     w11, 16
+        smaddl  x20, w10, w11, x19
+        mov     x19, x20
+        mov     x0, x19
+        mov     w1, 4
+        bl      _soup_sum_row
+        mov     w20, w0
+        str     w20, [sp, 0]
+        adrp    x0, LG3@PAGE
+        add     x0, x0, LG3@PAGEOFF
+        bl      _printf
+        adrp    x19, LG0@PAGE
+        add     x19, x19, LG0@PAGEOFF
+        mov     w9, 2
+        cmp     w9, 3
+        b.lo    Lfn_main_24
+Lfn_main_25:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 22
+        bl      __soup_runtime_error
+Lfn_main_24:
+        mov     w10, 2
+        mov     w11, 16
+        smaddl  x20, w10, w11, x19
+        mov     x19, x20
+        mov     x0, x19
+        mov     w1, 4
+        bl      _soup_sum_row
+        mov     w20, w0
+        str     w20, [sp, 0]
+        adrp    x0, LG4@PAGE
+        add     x0, x0, LG4@PAGEOFF
+        bl      _printf
+Lfn_main_ret:
+        ldr     x19, [sp, 56]
+        ldr     x20, [sp, 64]
+        ldr     x21, [sp, 72]
+        ldr     x22, [sp, 80]
+        ldr     x23, [sp, 88]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_sum_row
+_soup_sum_row:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 64
+        str     x19, [sp, 16]
+        str     x20, [sp, 24]
+        str     x21, [sp, 32]
+        str     x22, [sp, 40]
+        str     x23, [sp, 48]
+        str     x24, [sp, 56]
+        mov     x19, x0
+        mov     w20, w1
+        mov     w21, 0
+        mov     w22, 0
+Lfn_sum_row_0:
+        cmp     w22, 4
+        b.ge    Lfn_sum_row_2
+Lfn_sum_row_1:
+        cmp     w22, w20
+        b.lo    Lfn_sum_row_3
+Lfn_sum_row_4:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 30
+        bl      __soup_runtime_error
+Lfn_sum_row_3:
+        add     x23, x19, w22, sxtw 2
+        ldr     w24, [x23]
+        add     w23, w21, w24
+        mov     w21, w23
+        add     w23, w22, 1
+        mov     w22, w23
+        b       Lfn_sum_row_0
+Lfn_sum_row_2:
+        mov     w0, w21
+        b       Lfn_sum_row_ret
+        adrp    x0, LG5@PAGE
+        add     x0, x0, LG5@PAGEOFF
+        mov     w1, 25
+        bl      __soup_runtime_error
+Lfn_sum_row_ret:
+        ldr     x19, [sp, 16]
+        ldr     x20, [sp, 24]
+        ldr     x21, [sp, 32]
+        ldr     x22, [sp, 40]
+        ldr     x23, [sp, 48]
+        ldr     x24, [sp, 56]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+_soup_exit:
+// Return code is passed into exit and is already in x0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+__soup_runtime_error:
+        sub     sp, sp, 16
+        str     w1, [sp]
+        bl      _printf
+        mov     x0, 1  // Return code 1
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
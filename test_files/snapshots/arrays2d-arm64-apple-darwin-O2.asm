@@ -0,0 +1,227 @@
+.data
+.balign 8
+LG0: .space 48
+.section __TEXT,__cstring,cstring_literals
+LG1: .string "Error: Line %d: Array index out of bounds\n"
+LG2: .string "grid[2][3] = %d\n"
+LG3: .string "sum of grid row 1 = %d\n"
+LG4: .string "sum of table row 2 = %d\n"
+LG5: .string "Error: Line %d: A control path reaches the end of a non-void function without returning a value\n"
+last_newline: .string "\n"
+.text
+
+        .globl  _start
+        .balign 4
+_start:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        bl      _soup_main
+end:
+        ldp     x29, x30, [sp], 16
+        adrp    x0, last_newline@PAGE
+        add     x0, x0, last_newline@PAGEOFF
+        bl      _printf
+        mov     x0, 0  // Return code 0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+        .globl  _soup_main
+_soup_main:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 144
+        str     x19, [sp, 104]
+        str     x20, [sp, 112]
+        str     x21, [sp, 120]
+        str     x22, [sp, 128]
+        str     x23, [sp, 136]
+        mov     w23, 0
+        mov     w22, 0
+Lfn_main_0:
+        cmp     w23, 3
+        b.ge    Lfn_main_2
+Lfn_main_1:
+        mov     w22, 0
+Lfn_main_3:
+        cmp     w22, 4
+        b.ge    Lfn_main_5
+Lfn_main_4:
+        add     x20, sp, 56
+        cmp     w23, 3
+        b.lo    Lfn_main_6
+Lfn_main_7:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 13
+        bl      __soup_runtime_error
+Lfn_main_6:
+        mov     w11, 16
+        smaddl  x20, w23, w11, x20
+        cmp     w22, 4
+        b.lo    Lfn_main_8
+Lfn_main_9:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 13
+        bl      __soup_runtime_error
+Lfn_main_8:
+        add     x20, x20, w22, sxtw 2
+        mov     w10, 4
+        madd    w19, w23, w10, w22
+        str     w19, [x20]
+        adrp    x20, LG0@PAGE
+        add     x20, x20, LG0@PAGEOFF
+        cmp     w23, 3
+        b.lo    Lfn_main_10
+Lfn_main_11:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_10:
+        mov     w11, 16
+        smaddl  x20, w23, w11, x20
+        cmp     w22, 4
+        b.lo    Lfn_main_12
+Lfn_main_13:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_12:
+        add     x21, x20, w22, sxtw 2
+        add     x20, sp, 56
+        cmp     w23, 3
+        b.lo    Lfn_main_14
+Lfn_main_15:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_14:
+        mov     w11, 16
+        smaddl  x20, w23, w11, x20
+        cmp     w22, 4
+        b.lo    Lfn_main_16
+Lfn_main_17:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 14
+        bl      __soup_runtime_error
+Lfn_main_16:
+        add     x19, x20, w22, sxtw 2
+        ldr     w19, [x19]
+        mov     w10, 2
+        mul     w19, w19, w10
+        str     w19, [x21]
+        add     w22, w22, 1
+        b       Lfn_main_3
+Lfn_main_5:
+        add     w23, w23, 1
+        b       Lfn_main_0
+Lfn_main_2:
+        add     x19, sp, 56
+Lfn_main_18:
+        mov     w10, 2
+        mov     w11, 16
+        smaddl  x19, w10, w11, x19
+Lfn_main_20:
+        mov     w10, 3
+        add     x19, x19, w10, sxtw 2
+        ldr     w19, [x19]
+        str     w19, [sp, 0]
+        adrp    x0, LG2@PAGE
+        add     x0, x0, LG2@PAGEOFF
+        bl      _printf
+        add     x19, sp, 56
+Lfn_main_22:
+        mov     w10, 1
+        mov     w11, 16
+        smaddl  x19, w10, w11, x19
+        mov     x0, x19
+        mov     w1, 4
+        bl      _soup_sum_row
+        mov     w19, w0
+        str     w19, [sp, 0]
+        adrp    x0, LG3@PAGE
+        add     x0, x0, LG3@PAGEOFF
+        bl      _printf
+        adrp    x19, LG0@PAGE
+        add     x19, x19, LG0@PAGEOFF
+Lfn_main_24:
+        mov     w10, 2
+        mov     w11, 16
+        smaddl  x19, w10, w11, x19
+        mov     x0, x19
+        mov     w1, 4
+        bl      _soup_sum_row
+        mov     w19, w0
+        str     w19, [sp, 0]
+        adrp    x0, LG4@PAGE
+        add     x0, x0, LG4@PAGEOFF
+        bl      _printf
+Lfn_main_ret:
+        ldr     x19, [sp, 104]
+        ldr     x20, [sp, 112]
+        ldr     x21, [sp, 120]
+        ldr     x22, [sp, 128]
+        ldr     x23, [sp, 136]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_sum_row
+_soup_sum_row:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 64
+        str     x19, [sp, 24]
+        str     x20, [sp, 32]
+        str     x21, [sp, 40]
+        str     x22, [sp, 48]
+        str     x23, [sp, 56]
+        mov     x23, x0
+        mov     w22, w1
+        mov     w21, 0
+        mov     w20, 0
+Lfn_sum_row_0:
+        cmp     w20, 4
+        b.ge    Lfn_sum_row_2
+Lfn_sum_row_1:
+        cmp     w20, w22
+        b.lo    Lfn_sum_row_3
+Lfn_sum_row_4:
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        mov     w1, 30
+        bl      __soup_runtime_error
+Lfn_sum_row_3:
+        add     x19, x23, w20, sxtw 2
+        ldr     w19, [x19]
+        add     w21, w21, w19
+        add     w20, w20, 1
+        b       Lfn_sum_row_0
+Lfn_sum_row_2:
+        mov     w0, w21
+Lfn_sum_row_ret:
+        ldr     x19, [sp, 24]
+        ldr     x20, [sp, 32]
+        ldr     x21, [sp, 40]
+        ldr     x22, [sp, 48]
+        ldr     x23, [sp, 56]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+_soup_exit:
+// Return code is passed into exit and is already in x0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+__soup_runtime_error:
+        sub     sp, sp, 16
+        str     w1, [sp]
+        bl      _printf
+        mov     x0, 1  // Return code 1
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
@@ -0,0 +1,354 @@
+.intel_syntax noprefix
+.data
+.balign 8
+LG0: .zero 48
+.section .rodata
+LG1: .string "Error: Line %d: Array index out of bounds\n"
+LG2: .string "grid[2][3] = %d\n"
+LG3: .string "sum of grid row 1 = %d\n"
+LG4: .string "sum of table row 2 = %d\n"
+LG5: .string "Error: Line %d: A control path reaches the end of a non-void function without returning a value\n"
+last_newline: .string "\n"
+.text
+
+        .weak   main
+main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 16
+        call    soup_main
+        lea     rdi, [rip + last_newline]
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 0
+        call    exit@PLT
+
+        .globl  soup_main
+        .type   soup_main, %function
+soup_main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 144
+        mov     QWORD PTR [rsp + 96], rbx
+        mov     QWORD PTR [rsp + 104], r12
+        mov     QWORD PTR [rsp + 112], r13
+        mov     QWORD PTR [rsp + 120], r14
+        mov     QWORD PTR [rsp + 128], r15
+        mov     eax, 0
+        mov     r14d, eax
+        mov     eax, 0
+        mov     r13d, eax
+.Lfn_main_0:
+        mov     eax, r14d
+        cmp     eax, 3
+        setl    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        je      .Lfn_main_2
+.Lfn_main_1:
+        mov     eax, 0
+        mov     r13d, eax
+.Lfn_main_3:
+        mov     eax, r13d
+        cmp     eax, 4
+        setl    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        je      .Lfn_main_5
+.Lfn_main_4:
+        lea     rax, [rsp + 48]
+        mov     r12, rax
+        mov     eax, r14d
+        cmp     eax, 3
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_6
+.Lfn_main_7:
+        lea     rdi, [rip + LG1]
+        mov     esi, 13
+        call    __soup_runtime_error
+.Lfn_main_6:
+        mov     rax, r12
+        mov     ecx, r14d
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     r12, rax
+        mov     eax, r13d
+        cmp     eax, 4
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_8
+.Lfn_main_9:
+        lea     rdi, [rip + LG1]
+        mov     esi, 13
+        call    __soup_runtime_error
+.Lfn_main_8:
+        mov     rax, r12
+        mov     ecx, r13d
+        movsxd  rcx, ecx
+        lea     rax, [rax + rcx * 4]
+        mov     r12, rax
+        mov     eax, r14d
+        imul    eax, eax, 4
+        mov     ebx, eax
+        mov     eax, ebx
+        mov     ecx, r13d
+        add     eax, ecx
+        mov     ebx, eax
+        mov     rax, r12
+        mov     ecx, ebx
+        mov     DWORD PTR [rax], ecx
+        lea     rax, [rip + LG0]
+        mov     r12, rax
+        mov     eax, r14d
+        cmp     eax, 3
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_10
+.Lfn_main_11:
+        lea     rdi, [rip + LG1]
+        mov     esi, 14
+        call    __soup_runtime_error
+.Lfn_main_10:
+        mov     rax, r12
+        mov     ecx, r14d
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     r12, rax
+        mov     eax, r13d
+        cmp     eax, 4
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_12
+.Lfn_main_13:
+        lea     rdi, [rip + LG1]
+        mov     esi, 14
+        call    __soup_runtime_error
+.Lfn_main_12:
+        mov     rax, r12
+        mov     ecx, r13d
+        movsxd  rcx, ecx
+        lea     rax, [rax + rcx * 4]
+        mov     r12, rax
+        lea     rax, [rsp + 48]
+        mov     r15, rax
+        mov     eax, r14d
+        cmp     eax, 3
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_14
+.Lfn_main_15:
+        lea     rdi, [rip + LG1]
+        mov     esi, 14
+        call    __soup_runtime_error
+.Lfn_main_14:
+        mov     rax, r15
+        mov     ecx, r14d
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     r15, rax
+        mov     eax, r13d
+        cmp     eax, 4
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_main_16
+.Lfn_main_17:
+        lea     rdi, [rip + LG1]
+        mov     esi, 14
+        call    __soup_runtime_error
+.Lfn_main_16:
+        mov     rax, r15
+        mov     ecx, r13d
+        movsxd  rcx, ecx
+        lea     rax, [rax + rcx * 4]
+        mov     rbx, rax
+        mov     rax, rbx
+        mov     eax, DWORD PTR [rax]
+        mov     ebx, eax
+        mov     eax, ebx
+        imul    eax, eax, 2
+        mov     ebx, eax
+        mov     rax, r12
+        mov     ecx, ebx
+        mov     DWORD PTR [rax], ecx
+        mov     eax, r13d
+        add     eax, 1
+        mov     r13d, eax
+        jmp     .Lfn_main_3
+.Lfn_main_5:
+        mov     eax, r14d
+        add     eax, 1
+        mov     r14d, eax
+        jmp     .Lfn_main_0
+.Lfn_main_2:
+        lea     rax, [rsp + 48]
+        mov     rbx, rax
+.Lfn_main_18:
+        mov     rax, rbx
+        mov     ecx, 2
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     rbx, rax
+.Lfn_main_20:
+        mov     rax, rbx
+        mov     ecx, 3
+        movsxd  rcx, ecx
+        lea     rax, [rax + rcx * 4]
+        mov     rbx, rax
+        mov     rax, rbx
+        mov     eax, DWORD PTR [rax]
+        mov     ebx, eax
+        mov     esi, ebx
+        lea     rdi, [rip + LG2]
+        xor     eax, eax
+        call    printf@PLT
+        lea     rax, [rsp + 48]
+        mov     rbx, rax
+.Lfn_main_22:
+        mov     rax, rbx
+        mov     ecx, 1
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     rbx, rax
+        mov     rdi, rbx
+        mov     esi, 4
+        call    soup_sum_row
+        mov     ebx, eax
+        mov     esi, ebx
+        lea     rdi, [rip + LG3]
+        xor     eax, eax
+        call    printf@PLT
+        lea     rax, [rip + LG0]
+        mov     rbx, rax
+.Lfn_main_24:
+        mov     rax, rbx
+        mov     ecx, 2
+        movsxd  rcx, ecx
+        imul    rcx, rcx, 16
+        add     rax, rcx
+        mov     rbx, rax
+        mov     rdi, rbx
+        mov     esi, 4
+        call    soup_sum_row
+        mov     ebx, eax
+        mov     esi, ebx
+        lea     rdi, [rip + LG4]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_main_ret:
+        mov     rbx, QWORD PTR [rsp + 96]
+        mov     r12, QWORD PTR [rsp + 104]
+        mov     r13, QWORD PTR [rsp + 112]
+        mov     r14, QWORD PTR [rsp + 120]
+        mov     r15, QWORD PTR [rsp + 128]
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+        .globl  soup_sum_row
+        .type   soup_sum_row, %function
+soup_sum_row:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 64
+        mov     QWORD PTR [rsp + 24], rbx
+        mov     QWORD PTR [rsp + 32], r12
+        mov     QWORD PTR [rsp + 40], r13
+        mov     QWORD PTR [rsp + 48], r14
+        mov     QWORD PTR [rsp + 56], r15
+        mov     r15, rdi
+        mov     r14d, esi
+        mov     eax, 0
+        mov     r13d, eax
+        mov     eax, 0
+        mov     r12d, eax
+.Lfn_sum_row_0:
+        mov     eax, r12d
+        cmp     eax, 4
+        setl    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        je      .Lfn_sum_row_2
+.Lfn_sum_row_1:
+        mov     eax, r12d
+        mov     ecx, r14d
+        cmp     eax, ecx
+        setb    al
+        movzx   eax, al
+        mov     ebx, eax
+        mov     eax, ebx
+        test    eax, eax
+        jne     .Lfn_sum_row_3
+.Lfn_sum_row_4:
+        lea     rdi, [rip + LG1]
+        mov     esi, 30
+        call    __soup_runtime_error
+.Lfn_sum_row_3:
+        mov     rax, r15
+        mov     ecx, r12d
+        movsxd  rcx, ecx
+        lea     rax, [rax + rcx * 4]
+        mov     rbx, rax
+        mov     rax, rbx
+        mov     eax, DWORD PTR [rax]
+        mov     ebx, eax
+        mov     eax, r13d
+        mov     ecx, ebx
+        add     eax, ecx
+        mov     r13d, eax
+        mov     eax, r12d
+        add     eax, 1
+        mov     r12d, eax
+        jmp     .Lfn_sum_row_0
+.Lfn_sum_row_2:
+        mov     eax, r13d
+.Lfn_sum_row_ret:
+        mov     rbx, QWORD PTR [rsp + 24]
+        mov     r12, QWORD PTR [rsp + 32]
+        mov     r13, QWORD PTR [rsp + 40]
+        mov     r14, QWORD PTR [rsp + 48]
+        mov     r15, QWORD PTR [rsp + 56]
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+soup_exit:
+        jmp     exit@PLT
+
+__soup_runtime_error:
+        push    rbp
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 1
+        call    exit@PLT
+
+        .section .note.GNU-stack,"",@progbits
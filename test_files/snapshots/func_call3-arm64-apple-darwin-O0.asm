@@ -0,0 +1,107 @@
+.data
+.section __TEXT,__cstring,cstring_literals
+LG0: .string "%d %d %d %d %d "
+LG1: .string "%d %d %d %d\n"
+last_newline: .string "\n"
+.text
+
+        .globl  _start
+        .balign 4
+_start:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        bl      _soup_main
+end:
+        ldp     x29, x30, [sp], 16
+        adrp    x0, last_newline@PAGE
+        add     x0, x0, last_newline@PAGEOFF
+        bl      _printf
+        mov     x0, 0  // Return code 0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+        .globl  _soup_main
+_soup_main:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 16
+        mov     w0, 0
+        mov     w1, 1
+        mov     w2, 2
+        mov     w3, 3
+        mov     w4, 4
+        mov     w5, 5
+        mov     w6, 6
+        mov     w7, 7
+        mov     w9, 8
+        str     w9, [sp, 0]
+        bl      _soup_test_func
+Lfn_main_ret:
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_test_func
+_soup_test_func:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 112
+        str     x19, [sp, 40]
+        str     x20, [sp, 48]
+        str     x21, [sp, 56]
+        str     x22, [sp, 64]
+        str     x23, [sp, 72]
+        str     x24, [sp, 80]
+        str     x25, [sp, 88]
+        str     x26, [sp, 96]
+        str     x27, [sp, 104]
+        mov     w19, w0
+        mov     w20, w1
+        mov     w21, w2
+        mov     w22, w3
+        mov     w23, w4
+        mov     w24, w5
+        mov     w25, w6
+        mov     w26, w7
+        ldr     w27, [x29, 16]
+        str     w19, [sp, 0]
+        str     w20, [sp, 8]
+        str     w21, [sp, 16]
+        str     w22, [sp, 24]
+        str     w23, [sp, 32]
+        adrp    x0, LG0@PAGE
+        add     x0, x0, LG0@PAGEOFF
+        bl      _printf
+        str     w24, [sp, 0]
+        str     w25, [sp, 8]
+        str     w26, [sp, 16]
+        str     w27, [sp, 24]
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        bl      _printf
+Lfn_test_func_ret:
+        ldr     x19, [sp, 40]
+        ldr     x20, [sp, 48]
+        ldr     x21, [sp, 56]
+        ldr     x22, [sp, 64]
+        ldr     x23, [sp, 72]
+        ldr     x24, [sp, 80]
+        ldr     x25, [sp, 88]
+        ldr     x26, [sp, 96]
+        ldr     x27, [sp, 104]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+_soup_exit:
+// Return code is passed into exit and is already in x0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+__soup_runtime_error:
+        sub     sp, sp, 16
+        str     w1, [sp]
+        bl      _printf
+        mov     x0, 1  // Return code 1
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
@@ -0,0 +1,106 @@
+.intel_syntax noprefix
+.data
+.section .rodata
+LG0: .string "%d %d %d %d %d "
+LG1: .string "%d %d %d %d\n"
+last_newline: .string "\n"
+.text
+
+        .weak   main
+main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 16
+        call    soup_main
+        lea     rdi, [rip + last_newline]
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 0
+        call    exit@PLT
+
+        .globl  soup_main
+        .type   soup_main, %function
+soup_main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 48
+        mov     esi, 0
+        mov     edx, 1
+        mov     ecx, 2
+        mov     r8d, 3
+        mov     r9d, 4
+        lea     rdi, [rip + LG0]
+        xor     eax, eax
+        call    printf@PLT
+        mov     esi, 5
+        mov     edx, 6
+        mov     ecx, 7
+        mov     r8d, 8
+        lea     rdi, [rip + LG1]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_main_0:
+.Lfn_main_ret:
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+        .globl  soup_test_func
+        .type   soup_test_func, %function
+soup_test_func:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 64
+        mov     QWORD PTR [rsp + 16], rbx
+        mov     QWORD PTR [rsp + 24], r12
+        mov     QWORD PTR [rsp + 32], r13
+        mov     QWORD PTR [rsp + 40], r14
+        mov     QWORD PTR [rsp + 48], r15
+        mov     r15d, edi
+        mov     r14d, esi
+        mov     r13d, edx
+        mov     r12d, ecx
+        mov     ebx, r8d
+        mov     DWORD PTR [rsp + 0], r9d
+        mov     eax, DWORD PTR [rbp + 16]
+        mov     DWORD PTR [rsp + 4], eax
+        mov     eax, DWORD PTR [rbp + 24]
+        mov     DWORD PTR [rsp + 8], eax
+        mov     eax, DWORD PTR [rbp + 32]
+        mov     DWORD PTR [rsp + 12], eax
+        mov     esi, r15d
+        mov     edx, r14d
+        mov     ecx, r13d
+        mov     r8d, r12d
+        mov     r9d, ebx
+        lea     rdi, [rip + LG0]
+        xor     eax, eax
+        call    printf@PLT
+        mov     esi, DWORD PTR [rsp + 0]
+        mov     edx, DWORD PTR [rsp + 4]
+        mov     ecx, DWORD PTR [rsp + 8]
+        mov     r8d, DWORD PTR [rsp + 12]
+        lea     rdi, [rip + LG1]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_test_func_ret:
+        mov     rbx, QWORD PTR [rsp + 16]
+        mov     r12, QWORD PTR [rsp + 24]
+        mov     r13, QWORD PTR [rsp + 32]
+        mov     r14, QWORD PTR [rsp + 40]
+        mov     r15, QWORD PTR [rsp + 48]
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+soup_exit:
+        jmp     exit@PLT
+
+__soup_runtime_error:
+        push    rbp
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 1
+        call    exit@PLT
+
+        .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,45 @@
+.data
+.section __TEXT,__cstring,cstring_literals
+LG0: .string "Hello, world!\n"
+last_newline: .string "\n"
+.text
+
+        .globl  _start
+        .balign 4
+_start:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        bl      _soup_main
+end:
+        ldp     x29, x30, [sp], 16
+        adrp    x0, last_newline@PAGE
+        add     x0, x0, last_newline@PAGEOFF
+        bl      _printf
+        mov     x0, 0  // Return code 0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+        .globl  _soup_main
+_soup_main:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        adrp    x0, LG0@PAGE
+        add     x0, x0, LG0@PAGEOFF
+        bl      _printf
+Lfn_main_ret:
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+_soup_exit:
+// Return code is passed into exit and is already in x0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+__soup_runtime_error:
+        sub     sp, sp, 16
+        str     w1, [sp]
+        bl      _printf
+        mov     x0, 1  // Return code 1
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
@@ -0,0 +1,43 @@
+.intel_syntax noprefix
+.data
+.section .rodata
+LG0: .string "Hello, world!\n"
+last_newline: .string "\n"
+.text
+
+        .weak   main
+main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 16
+        call    soup_main
+        lea     rdi, [rip + last_newline]
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 0
+        call    exit@PLT
+
+        .globl  soup_main
+        .type   soup_main, %function
+soup_main:
+        push    rbp
+        mov     rbp, rsp
+        lea     rdi, [rip + LG0]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_main_ret:
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+soup_exit:
+        jmp     exit@PLT
+
+__soup_runtime_error:
+        push    rbp
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 1
+        call    exit@PLT
+
+        .section .note.GNU-stack,"",@progbits
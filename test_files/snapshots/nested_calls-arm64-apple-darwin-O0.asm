@@ -0,0 +1,201 @@
+.data
+.section __TEXT,__cstring,cstring_literals
+LG0: .string "%d\n"
+LG1: .string "%d\n"
+LG2: .string "Error: Line %d: A control path reaches the end of a non-void function without returning a value\n"
+last_newline: .string "\n"
+.text
+
+        .globl  _start
+        .balign 4
+_start:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        bl      _soup_main
+end:
+        ldp     x29, x30, [sp], 16
+        adrp    x0, last_newline@PAGE
+        add     x0, x0, last_newline@PAGEOFF
+        bl      _printf
+        mov     x0, 0  // Return code 0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+        .globl  _soup_main
+_soup_main:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 48
+        str     x19, [sp, 8]
+        str     x20, [sp, 16]
+        str     x21, [sp, 24]
+        str     x22, [sp, 32]
+        str     x23, [sp, 40]
+        bl      _soup_return1
+        mov     w19, w0
+        mov     w0, w19
+        mov     w1, 2
+        bl      _soup_add
+        mov     w20, w0
+        bl      _soup_return1
+        mov     w19, w0
+        mov     w0, w20
+        mov     w1, w19
+        bl      _soup_add
+        mov     w21, w0
+        str     w21, [sp, 0]
+        adrp    x0, LG0@PAGE
+        add     x0, x0, LG0@PAGEOFF
+        bl      _printf
+        mov     w0, 2
+        mov     w1, 3
+        bl      _soup_add
+        mov     w19, w0
+        mov     w0, 5
+        mov     w1, 6
+        bl      _soup_add
+        mov     w20, w0
+        mov     w0, w20
+        mov     w1, 7
+        bl      _soup_add
+        mov     w21, w0
+        mov     w0, 10
+        mov     w1, 11
+        bl      _soup_add
+        mov     w20, w0
+        bl      _soup_return1
+        mov     w22, w0
+        mov     w0, 13
+        mov     w1, w22
+        bl      _soup_add
+        mov     w23, w0
+        mov     w0, 1
+        mov     w1, w19
+        mov     w2, 4
+        mov     w3, w21
+        mov     w4, 8
+        mov     w5, 9
+        mov     w6, w20
+        mov     w7, 12
+        str     w23, [sp, 0]
+        bl      _soup_sum9
+        mov     w22, w0
+        str     w22, [sp, 0]
+        adrp    x0, LG1@PAGE
+        add     x0, x0, LG1@PAGEOFF
+        bl      _printf
+Lfn_main_ret:
+        ldr     x19, [sp, 8]
+        ldr     x20, [sp, 16]
+        ldr     x21, [sp, 24]
+        ldr     x22, [sp, 32]
+        ldr     x23, [sp, 40]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_return1
+_soup_return1:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        mov     w0, 1
+        b       Lfn_return1_ret
+        adrp    x0, LG2@PAGE
+        add     x0, x0, LG2@PAGEOFF
+        mov     w1, 8
+        bl      __soup_runtime_error
+Lfn_return1_ret:
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_add
+_soup_add:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 32
+        str     x19, [sp, 0]
+        str     x20, [sp, 8]
+        str     x21, [sp, 16]
+        mov     w19, w0
+        mov     w20, w1
+        add     w21, w19, w20
+        mov     w0, w21
+        b       Lfn_add_ret
+        adrp    x0, LG2@PAGE
+        add     x0, x0, LG2@PAGEOFF
+        mov     w1, 12
+        bl      __soup_runtime_error
+Lfn_add_ret:
+        ldr     x19, [sp, 0]
+        ldr     x20, [sp, 8]
+        ldr     x21, [sp, 16]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+        .globl  _soup_sum9
+_soup_sum9:
+        stp     x29, x30, [sp, -16]!
+        mov     x29, sp
+        sub     sp, sp, 80
+        str     x19, [sp, 0]
+        str     x20, [sp, 8]
+        str     x21, [sp, 16]
+        str     x22, [sp, 24]
+        str     x23, [sp, 32]
+        str     x24, [sp, 40]
+        str     x25, [sp, 48]
+        str     x26, [sp, 56]
+        str     x27, [sp, 64]
+        str     x28, [sp, 72]
+        mov     w19, w0
+        mov     w20, w1
+        mov     w21, w2
+        mov     w22, w3
+        mov     w23, w4
+        mov     w24, w5
+        mov     w25, w6
+        mov     w26, w7
+        ldr     w27, [x29, 16]
+        add     w28, w19, w20
+        add     w19, w28, w21
+        add     w20, w19, w22
+        add     w19, w20, w23
+        add     w20, w19, w24
+        add     w19, w20, w25
+        add     w20, w19, w26
+        add     w19, w20, w27
+        mov     w0, w19
+        b       Lfn_sum9_ret
+        adrp    x0, LG2@PAGE
+        add     x0, x0, LG2@PAGEOFF
+        mov     w1, 16
+        bl      __soup_runtime_error
+Lfn_sum9_ret:
+        ldr     x19, [sp, 0]
+        ldr     x20, [sp, 8]
+        ldr     x21, [sp, 16]
+        ldr     x22, [sp, 24]
+        ldr     x23, [sp, 32]
+        ldr     x24, [sp, 40]
+        ldr     x25, [sp, 48]
+        ldr     x26, [sp, 56]
+        ldr     x27, [sp, 64]
+        ldr     x28, [sp, 72]
+        mov     sp, x29
+        ldp     x29, x30, [sp], 16
+        ret
+
+_soup_exit:
+// Return code is passed into exit and is already in x0
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
+
+__soup_runtime_error:
+        sub     sp, sp, 16
+        str     w1, [sp]
+        bl      _printf
+        mov     x0, 1  // Return code 1
+        mov     x16, 1  // Sys call code to terminate program
+        svc     0x80  // Make system call
@@ -0,0 +1,161 @@
+.intel_syntax noprefix
+.data
+.section .rodata
+LG0: .string "%d\n"
+LG1: .string "%d\n"
+LG2: .string "Error: Line %d: A control path reaches the end of a non-void function without returning a value\n"
+last_newline: .string "\n"
+.text
+
+        .weak   main
+main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 16
+        call    soup_main
+        lea     rdi, [rip + last_newline]
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 0
+        call    exit@PLT
+
+        .globl  soup_main
+        .type   soup_main, %function
+soup_main:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 256
+.Lfn_main_0:
+.Lfn_main_1:
+.Lfn_main_2:
+.Lfn_main_3:
+        mov     esi, 4
+        lea     rdi, [rip + LG0]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_main_4:
+.Lfn_main_5:
+.Lfn_main_6:
+.Lfn_main_7:
+.Lfn_main_8:
+.Lfn_main_9:
+.Lfn_main_10:
+        mov     esi, 92
+        lea     rdi, [rip + LG1]
+        xor     eax, eax
+        call    printf@PLT
+.Lfn_main_ret:
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+        .globl  soup_return1
+        .type   soup_return1, %function
+soup_return1:
+        push    rbp
+        mov     rbp, rsp
+        mov     eax, 1
+.Lfn_return1_ret:
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+        .globl  soup_add
+        .type   soup_add, %function
+soup_add:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 16
+        mov     QWORD PTR [rsp + 0], rbx
+        mov     QWORD PTR [rsp + 8], r12
+        mov     r12d, edi
+        mov     ebx, esi
+        mov     eax, r12d
+        mov     ecx, ebx
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+.Lfn_add_ret:
+        mov     rbx, QWORD PTR [rsp + 0]
+        mov     r12, QWORD PTR [rsp + 8]
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+        .globl  soup_sum9
+        .type   soup_sum9, %function
+soup_sum9:
+        push    rbp
+        mov     rbp, rsp
+        sub     rsp, 64
+        mov     QWORD PTR [rsp + 16], rbx
+        mov     QWORD PTR [rsp + 24], r12
+        mov     QWORD PTR [rsp + 32], r13
+        mov     QWORD PTR [rsp + 40], r14
+        mov     QWORD PTR [rsp + 48], r15
+        mov     r15d, edi
+        mov     r14d, esi
+        mov     r13d, edx
+        mov     r12d, ecx
+        mov     ebx, r8d
+        mov     DWORD PTR [rsp + 0], r9d
+        mov     eax, DWORD PTR [rbp + 16]
+        mov     DWORD PTR [rsp + 4], eax
+        mov     eax, DWORD PTR [rbp + 24]
+        mov     DWORD PTR [rsp + 8], eax
+        mov     eax, DWORD PTR [rbp + 32]
+        mov     DWORD PTR [rsp + 12], eax
+        mov     eax, r15d
+        mov     ecx, r14d
+        add     eax, ecx
+        mov     r14d, eax
+        mov     eax, r14d
+        mov     ecx, r13d
+        add     eax, ecx
+        mov     r13d, eax
+        mov     eax, r13d
+        mov     ecx, r12d
+        add     eax, ecx
+        mov     r12d, eax
+        mov     eax, r12d
+        mov     ecx, ebx
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+        mov     ecx, DWORD PTR [rsp + 0]
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+        mov     ecx, DWORD PTR [rsp + 4]
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+        mov     ecx, DWORD PTR [rsp + 8]
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+        mov     ecx, DWORD PTR [rsp + 12]
+        add     eax, ecx
+        mov     ebx, eax
+        mov     eax, ebx
+.Lfn_sum9_ret:
+        mov     rbx, QWORD PTR [rsp + 16]
+        mov     r12, QWORD PTR [rsp + 24]
+        mov     r13, QWORD PTR [rsp + 32]
+        mov     r14, QWORD PTR [rsp + 40]
+        mov     r15, QWORD PTR [rsp + 48]
+        mov     rsp, rbp
+        pop     rbp
+        ret
+
+soup_exit:
+        jmp     exit@PLT
+
+__soup_runtime_error:
+        push    rbp
+        xor     eax, eax
+        call    printf@PLT
+        mov     edi, 1
+        call    exit@PLT
+
+        .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,63 @@
+// Build a static library out of soup files the way a Cargo build script would (if soup can target the machine we're
+// running on), and make sure a C program linked against it can call its functions
+
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use soup::build::build_driver::SoupBuild;
+
+#[test]
+fn test_build_static_library() {
+    if env::consts::OS != "linux" || !["x86_64", "aarch64"].contains(&env::consts::ARCH) {
+        // There's nothing we can link the library into
+        return;
+    }
+
+    let dir = env::temp_dir().join(format!("soup-build-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let soup_file = dir.join("math.soup");
+    fs::write(
+        &soup_file,
+        "func add1(int x) returns int {\n    return x + 1;\n}\n\nfunc main() returns void {;}\n",
+    )
+    .unwrap();
+
+    // The library is built at -O2, like a release build
+    let lib_file = SoupBuild::new()
+        .file(&soup_file)
+        .opt_level(2)
+        .target(&format!("{}-unknown-linux-gnu", env::consts::ARCH))
+        .out_dir(&dir)
+        .try_compile("math")
+        .unwrap();
+    assert_eq!(dir.join("libmath.a"), lib_file);
+
+    // The C program defines main itself, which takes the place of the soup program's
+    let c_file = dir.join("main.c");
+    fs::write(
+        &c_file,
+        "#include <stdint.h>\n#include <stdio.h>\nint32_t soup_add1(int32_t x);\nint main(void) {\n    printf(\"%d\\n\", soup_add1(41));\n    return 0;\n}\n",
+    )
+    .unwrap();
+    let exe_file = dir.join("main");
+    let output = Command::new("cc")
+        .arg(&c_file)
+        .arg("-L")
+        .arg(&dir)
+        .args(["-lmath", "-o"])
+        .arg(&exe_file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = Command::new(&exe_file).output().unwrap();
+    assert_eq!("42\n", String::from_utf8_lossy(&output.stdout));
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,61 @@
+// Run a program compiled with --coverage through the soup executable (if soup can target the machine we're running on),
+// and make sure the report made from the counts it wrote out has the number of times each line ran
+
+use std::env;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_coverage_report() {
+    let target = match (env::consts::ARCH, env::consts::OS) {
+        ("x86_64", "linux") => "x86_64-unknown-linux-gnu",
+        ("aarch64", "linux") => "aarch64-unknown-linux-gnu",
+        ("aarch64", "macos") => "arm64-apple-darwin",
+        // There's nothing we can run a compiled program on
+        _ => return,
+    };
+
+    let coverage_file = env::temp_dir().join(format!("soup-coverage-{}.cov", std::process::id()));
+    let coverage_arg = format!("--coverage={}", coverage_file.display());
+
+    // The program exits through exit(), from inside a loop, which still has to write out its counts
+    for opt_level in ["-O0", "-O2"] {
+        let _ = fs::remove_file(&coverage_file);
+        let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+            .args(["run", "test_files/exit.soup", "--target", target, opt_level])
+            .arg(&coverage_arg)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "soup run {} failed:\n{}",
+            opt_level,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+            .args(["cov", "report"])
+            .arg(&coverage_file)
+            .output()
+            .unwrap();
+        let report = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "soup cov report failed:\n{}",
+            report
+        );
+
+        for line in [
+            "        1:    4:    int x = 0;",
+            "       11:    6:        if x == 10 {",
+            "        1:    7:            exit(0);",
+            "       10:    9:        printf(\"true!!\\n\");",
+            "        -:   11:    }",
+        ] {
+            assert!(report.contains(&format!("{}\n", line)), "{}", report);
+        }
+        assert!(report.ends_with("Lines run: 100.00% of 6\n"), "{}", report);
+    }
+
+    let _ = fs::remove_file(&coverage_file);
+}
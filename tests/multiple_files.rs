@@ -0,0 +1,97 @@
+// Compile programs made up of several files through the soup executable, where an error in any one of them
+// has to say which file it was found in (while an error in a program made up of a single file doesn't need to)
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Write a file of the program into the temporary directory, under a name no other test uses
+fn write_file(name: &str, source: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("soup-files-{}-{}", std::process::id(), name));
+    fs::write(&file, source).unwrap();
+    file
+}
+
+// Compile the given files together, expecting them to fail with the given error
+fn check_error(files: &[&PathBuf], expected: &str) {
+    let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+        .args(["build", "-o", "-"])
+        .args(files)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(Some(1), output.status.code(), "{}", stderr);
+    assert_eq!(format!("Error: {}\n", expected), stderr);
+}
+
+#[test]
+fn test_multiple_file_errors() {
+    let main = write_file(
+        "main.soup",
+        "func main() returns void {\n    printf(\"{}\\n\", double(2));\n}\n",
+    );
+    let scan_error = write_file(
+        "scan.soup",
+        "func double(int x) returns int {\n    return x $ 2;\n}\n",
+    );
+    let parse_error = write_file(
+        "parse.soup",
+        "func double(int x) returns int {\n    return x * ;\n}\n",
+    );
+    let semantic_error = write_file(
+        "semantic.soup",
+        "func double(int x) returns int {\n    return y * 2;\n}\n",
+    );
+
+    check_error(
+        &[&main, &scan_error],
+        &format!(
+            "{}: Unrecognized character '$' on line 2, column 14",
+            scan_error.display()
+        ),
+    );
+    check_error(
+        &[&main, &parse_error],
+        &format!(
+            "{}: Syntax Error on line 2: expected an identifier",
+            parse_error.display()
+        ),
+    );
+    check_error(
+        &[&main, &semantic_error],
+        &format!(
+            "{}: Line 2: Unknown identifier 'y'",
+            semantic_error.display()
+        ),
+    );
+
+    // With only one file, there's no question of which file the error is in
+    // When several files have errors, the one reported is from the first of them on the command line,
+    // however long each one takes to scan and parse
+    check_error(
+        &[&main, &parse_error, &scan_error],
+        &format!(
+            "{}: Syntax Error on line 2: expected an identifier",
+            parse_error.display()
+        ),
+    );
+    check_error(
+        &[&main, &scan_error, &parse_error],
+        &format!(
+            "{}: Unrecognized character '$' on line 2, column 14",
+            scan_error.display()
+        ),
+    );
+
+    let single = write_file(
+        "single.soup",
+        "func main() returns void {\n    printf(\"{}\\n\", y);\n}\n",
+    );
+    check_error(&[&single], "Line 2: Unknown identifier 'y'");
+
+    for file in [main, scan_error, parse_error, semantic_error, single] {
+        let _ = fs::remove_file(file);
+    }
+}
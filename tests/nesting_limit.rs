@@ -0,0 +1,91 @@
+// Compile programs through the soup executable with a chain of binary operators far longer than the nesting limit,
+// which has to be compiled and run without overflowing the stack of anything that walks its tree, and with
+// parentheses nested more deeply than the limit, which has to be reported as an error
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Write a program that prints the given expression into the temporary directory, under a name no other test uses
+fn write_file(name: &str, expr: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("soup-nesting-{}-{}", std::process::id(), name));
+    fs::write(
+        &file,
+        format!(
+            "func main() returns void {{\n    printf(\"{{}}\\n\", {});\n}}\n",
+            expr
+        ),
+    )
+    .unwrap();
+    file
+}
+
+// Run the soup executable on the program with the given arguments
+fn soup(args: &[&str], file: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_soup"))
+        .args(args)
+        .arg(file)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_long_operator_chain() {
+    let file = write_file("chain.soup", &vec!["1"; 20000].join(" + "));
+
+    let output = soup(&["build", "--emit=ir", "-o", "-"], &file);
+    assert_eq!(Some(0), output.status.code());
+
+    // The compiled program is only run on a target the machine can run natively
+    let mut runs = vec![vec!["run", "--interpret"]];
+    let target = match (env::consts::ARCH, env::consts::OS) {
+        ("x86_64", "linux") => Some("x86_64-unknown-linux-gnu"),
+        ("aarch64", "linux") => Some("aarch64-unknown-linux-gnu"),
+        ("aarch64", "macos") => Some("arm64-apple-darwin"),
+        _ => None,
+    };
+    if let Some(target) = target {
+        runs.push(vec!["run", "--target", target, "-O0"]);
+        runs.push(vec!["run", "--target", target, "-O2"]);
+    }
+
+    for args in runs {
+        let output = soup(&args, &file);
+        assert_eq!(Some(0), output.status.code(), "soup {}", args.join(" "));
+        assert_eq!(
+            "20000\n\n",
+            String::from_utf8_lossy(&output.stdout),
+            "soup {}",
+            args.join(" ")
+        );
+    }
+
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn test_deep_parentheses() {
+    let file = write_file(
+        "parentheses.soup",
+        &format!("{}1{}", "(1 + ".repeat(20000), ")".repeat(20000)),
+    );
+
+    for args in [
+        vec!["build", "--emit=ir"],
+        vec!["run", "--interpret"],
+        vec!["run", "--target", "x86_64-unknown-linux-gnu", "-O2"],
+    ] {
+        let output = soup(&args, &file);
+        assert_eq!(Some(1), output.status.code(), "soup {}", args.join(" "));
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .contains("Syntax Error on line 2: expression too deeply nested"),
+            "soup {}:\n{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let _ = fs::remove_file(&file);
+}
@@ -0,0 +1,62 @@
+// Run a program that divides the smallest int by -1 through the soup executable, which wraps around
+// unless overflow is being checked for, in which case it's a runtime error (just like adding, subtracting
+// or multiplying past the range of an int)
+
+use std::env;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn test_division_overflow() {
+    let source_file =
+        env::temp_dir().join(format!("soup-div-overflow-{}.soup", std::process::id()));
+    fs::write(
+        &source_file,
+        "func div(int a, int b) returns int {\n    return a / b;\n}\n\nfunc main() returns void {\n    printf(\"{}\\n\", div(-2147483647 - 1, -1));\n}\n",
+    )
+    .unwrap();
+
+    // The compiled program is only run on a target the machine can run natively
+    let mut runs = vec![vec!["run", "--interpret"]];
+    let target = match (env::consts::ARCH, env::consts::OS) {
+        ("x86_64", "linux") => Some("x86_64-unknown-linux-gnu"),
+        ("aarch64", "linux") => Some("aarch64-unknown-linux-gnu"),
+        ("aarch64", "macos") => Some("arm64-apple-darwin"),
+        _ => None,
+    };
+    if let Some(target) = target {
+        runs.push(vec!["run", "--target", target, "-O0"]);
+        runs.push(vec!["run", "--target", target, "-O2"]);
+    }
+
+    for args in runs {
+        let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+            .args(&args)
+            .arg(&source_file)
+            .output()
+            .unwrap();
+        assert_eq!(Some(0), output.status.code(), "soup {}", args.join(" "));
+        assert_eq!(
+            "-2147483648\n\n",
+            String::from_utf8_lossy(&output.stdout),
+            "soup {}",
+            args.join(" ")
+        );
+
+        let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+            .args(&args)
+            .arg("--overflow-checks")
+            .arg(&source_file)
+            .output()
+            .unwrap();
+        assert_eq!(Some(1), output.status.code(), "soup {}", args.join(" "));
+        assert_eq!(
+            "Error: Line 2: Integer overflow\n",
+            String::from_utf8_lossy(&output.stdout),
+            "soup {} --overflow-checks",
+            args.join(" ")
+        );
+    }
+
+    let _ = fs::remove_file(&source_file);
+}
@@ -0,0 +1,42 @@
+// Run the end-to-end test suite (every program in test_files with the output it's expected to print alongside it)
+// through the soup executable, with the interpreter and, if soup can target the machine we're running on,
+// compiled into a real executable at each optimization level
+
+use std::env;
+use std::process::Command;
+
+// Run the test suite with the given options, failing with its report if any program didn't print what was expected
+fn run_test_suite(args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_soup"))
+        .args(["test-suite", "test_files"])
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "soup test-suite {} failed:\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn test_suite_interpreted() {
+    run_test_suite(&["--interpret"]);
+}
+
+#[test]
+fn test_suite_native() {
+    let target = match (env::consts::ARCH, env::consts::OS) {
+        ("x86_64", "linux") => "x86_64-unknown-linux-gnu",
+        ("aarch64", "linux") => "aarch64-unknown-linux-gnu",
+        ("aarch64", "macos") => "arm64-apple-darwin",
+        // There's nothing we can run a compiled program on
+        _ => return,
+    };
+
+    for opt_level in ["-O0", "-O1", "-O2", "-O3"] {
+        run_test_suite(&["--target", target, opt_level]);
+    }
+}
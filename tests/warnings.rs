@@ -0,0 +1,66 @@
+// Compile a program that hides things through the soup executable, where -Wshadow has to point out every
+// parameter and local variable that hides a global variable, a function or a runtime library function,
+// while nothing is said about them unless it's asked for
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const SOURCE: &str = "int total;
+func helper(int total) returns int {
+    int helper = total;
+    int parse_int = helper;
+    return parse_int;
+}
+func main() returns void {
+    printf(\"{}\\n\", helper(1));
+}
+";
+
+// Write the program into the temporary directory, under a name no other test uses
+fn write_file(name: &str) -> PathBuf {
+    let file = env::temp_dir().join(format!("soup-warnings-{}-{}", std::process::id(), name));
+    fs::write(&file, SOURCE).unwrap();
+    file
+}
+
+// Compile the program with the given options
+fn build(file: &PathBuf, options: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_soup"))
+        .args(["build", "-o", "-"])
+        .args(options)
+        .arg(file)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_shadow_warnings() {
+    let file = write_file("shadow.soup");
+
+    // A parameter hiding a global, and locals hiding a function and a runtime library function
+    let output = build(&file, &["-Wshadow"]);
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!(
+        "Warning: Line 2: Declaration of 'total' hides the variable declared on line 1 [-Wshadow]\n\
+         Warning: Line 3: Declaration of 'helper' hides the function declared on line 2 [-Wshadow]\n\
+         Warning: Line 4: Declaration of 'parse_int' hides the function in the runtime library [-Wshadow]\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // The first of them stops the build when warnings are errors
+    let output = build(&file, &["-Wshadow", "-Werror"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "Error: Line 2: Declaration of 'total' hides the variable declared on line 1 [-Wshadow] (warnings are being treated as errors)\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // and none of them are pointed out by default
+    let output = build(&file, &[]);
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("", String::from_utf8_lossy(&output.stderr));
+
+    let _ = fs::remove_file(file);
+}